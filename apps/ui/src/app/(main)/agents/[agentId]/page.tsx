@@ -1,10 +1,22 @@
 "use client";
 
-import { use, useMemo, useCallback } from "react";
-import { useAgent, useSessions, useCreateSession, useCapabilities, useLlmModels, useExportAgent } from "@/hooks";
+import { use, useMemo, useCallback, useState } from "react";
+import {
+  useAgent,
+  useAgentRevisions,
+  useAgentRevisionDiff,
+  useSessions,
+  useCreateSession,
+  useCapabilities,
+  useLlmModels,
+  useExportAgent,
+  useRollbackAgentRevision,
+} from "@/hooks";
 import { useRouter } from "next/navigation";
 import Link from "next/link";
 import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
 import { Skeleton } from "@/components/ui/skeleton";
@@ -21,9 +33,12 @@ import {
   Box,
   Folder,
   Download,
+  History,
+  RotateCcw,
   LucideIcon,
 } from "lucide-react";
 import type { Capability, LlmModelWithProvider } from "@/lib/api/types";
+import { requiredTemplateVariables } from "@/lib/prompt-template";
 
 const iconMap: Record<string, LucideIcon> = {
   "circle-off": CircleOff,
@@ -46,6 +61,18 @@ export default function AgentDetailPage({
   const { data: llmModels } = useLlmModels();
   const createSession = useCreateSession();
   const exportAgent = useExportAgent();
+  const { data: revisions } = useAgentRevisions(agentId);
+  const rollbackRevision = useRollbackAgentRevision();
+  const [selectedRevision, setSelectedRevision] = useState<number | undefined>();
+  const { data: revisionDiff } = useAgentRevisionDiff(agentId, selectedRevision);
+  const [variables, setVariables] = useState<Record<string, string>>({});
+
+  // Variables the system prompt template needs before a session can start
+  const templateVariables = useMemo(
+    () => requiredTemplateVariables(agent?.system_prompt ?? ""),
+    [agent?.system_prompt]
+  );
+  const missingVariables = templateVariables.some((name) => !variables[name]);
 
   // Create a map of model_id -> model for quick lookups
   const modelMap = useMemo(() => {
@@ -60,7 +87,7 @@ export default function AgentDetailPage({
     try {
       const session = await createSession.mutateAsync({
         agentId,
-        request: {},
+        request: { variables },
       });
       router.push(`/agents/${agentId}/sessions/${session.id}`);
     } catch (error) {
@@ -87,6 +114,15 @@ export default function AgentDetailPage({
     }
   }, [agent, agentId, exportAgent]);
 
+  const handleRollback = async (revision: number) => {
+    try {
+      await rollbackRevision.mutateAsync({ agentId, revision });
+      setSelectedRevision(undefined);
+    } catch (error) {
+      console.error("Failed to roll back agent:", error);
+    }
+  };
+
   const getCapabilityInfo = (capabilityId: string): Capability | undefined =>
     allCapabilities?.find((c) => c.id === capabilityId);
 
@@ -135,7 +171,7 @@ export default function AgentDetailPage({
             </Badge>
           </h1>
           <p className="text-muted-foreground font-mono text-sm">
-            ID: {agent.id.slice(0, 8)}...
+            ID: {agent.id.slice(0, 8)}... · Revision {agent.revision}
           </p>
         </div>
         <div className="flex gap-2">
@@ -153,7 +189,10 @@ export default function AgentDetailPage({
               Edit
             </Button>
           </Link>
-          <Button onClick={handleNewSession} disabled={createSession.isPending}>
+          <Button
+            onClick={handleNewSession}
+            disabled={createSession.isPending || missingVariables}
+          >
             <Plus className="w-4 h-4 mr-2" />
             {createSession.isPending ? "Creating..." : "New Session"}
           </Button>
@@ -171,6 +210,28 @@ export default function AgentDetailPage({
             </CardContent>
           </Card>
 
+          {templateVariables.length > 0 && (
+            <Card>
+              <CardHeader>
+                <CardTitle>Session Variables</CardTitle>
+              </CardHeader>
+              <CardContent className="space-y-4">
+                {templateVariables.map((name) => (
+                  <div key={name} className="space-y-2">
+                    <Label htmlFor={`var-${name}`}>{name}</Label>
+                    <Input
+                      id={`var-${name}`}
+                      value={variables[name] ?? ""}
+                      onChange={(e) =>
+                        setVariables((prev) => ({ ...prev, [name]: e.target.value }))
+                      }
+                    />
+                  </div>
+                ))}
+              </CardContent>
+            </Card>
+          )}
+
           <Card>
             <CardHeader>
               <CardTitle>Sessions</CardTitle>
@@ -324,6 +385,64 @@ export default function AgentDetailPage({
               </div>
             </CardContent>
           </Card>
+
+          <Card>
+            <CardHeader>
+              <CardTitle className="flex items-center gap-2">
+                <History className="w-4 h-4" />
+                Revisions
+              </CardTitle>
+            </CardHeader>
+            <CardContent className="space-y-2">
+              {revisions?.map((revision) => (
+                <div key={revision.revision} className="rounded-md border">
+                  <div className="flex items-center justify-between p-2">
+                    <button
+                      type="button"
+                      className="text-left"
+                      onClick={() =>
+                        setSelectedRevision(
+                          selectedRevision === revision.revision
+                            ? undefined
+                            : revision.revision
+                        )
+                      }
+                    >
+                      <p className="text-sm font-medium">
+                        Revision {revision.revision}
+                      </p>
+                      <p className="text-xs text-muted-foreground">
+                        {new Date(revision.updated_at).toLocaleString()}
+                      </p>
+                    </button>
+                    {revision.revision === agent.revision ? (
+                      <Badge variant="outline">Current</Badge>
+                    ) : (
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        onClick={() => handleRollback(revision.revision)}
+                        disabled={rollbackRevision.isPending}
+                      >
+                        <RotateCcw className="w-4 h-4 mr-1" />
+                        Restore
+                      </Button>
+                    )}
+                  </div>
+                  {selectedRevision === revision.revision &&
+                    revisionDiff?.to_revision === revision.revision && (
+                      <div className="border-t p-2 text-xs text-muted-foreground">
+                        {revisionDiff.changes.length === 0
+                          ? "No configuration changes"
+                          : `Changed: ${revisionDiff.changes
+                              .map((change) => change.field)
+                              .join(", ")}`}
+                      </div>
+                    )}
+                </div>
+              ))}
+            </CardContent>
+          </Card>
         </div>
       </div>
     </div>
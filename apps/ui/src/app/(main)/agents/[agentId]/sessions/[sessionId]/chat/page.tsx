@@ -11,10 +11,11 @@ import {
   SelectTrigger,
   SelectValue,
 } from "@/components/ui/select";
-import { Send, Bot, Loader2, Brain } from "lucide-react";
+import { Send, Bot, Loader2, Brain, Pencil, RotateCcw, X } from "lucide-react";
 import type { Controls, MessageUserData, MessageAgentData } from "@/lib/api/types";
 import { ToolCallCardFromEvent } from "@/components/chat/tool-call-card-from-event";
 import { useSessionContext } from "../session-context";
+import { useEditMessage, useRegenerateResponse } from "@/hooks";
 
 export default function ChatPage() {
   const {
@@ -36,7 +37,17 @@ export default function ChatPage() {
   } = useSessionContext();
 
   const [inputValue, setInputValue] = useState("");
+  // User message being edited; submitting replaces it and reruns the conversation
+  const [editingMessageId, setEditingMessageId] = useState<string | null>(null);
   const messagesEndRef = useRef<HTMLDivElement>(null);
+  const editMessage = useEditMessage();
+  const regenerate = useRegenerateResponse();
+  const isSubmitting = sendMessage.isPending || editMessage.isPending;
+
+  // Only the latest agent reply can be regenerated
+  const lastAgentEventId = [...chatEvents]
+    .reverse()
+    .find((e) => e.type === "message.agent")?.id;
 
   const reasoningEffortConfig = llmModel?.profile?.reasoning_effort;
 
@@ -47,7 +58,7 @@ export default function ChatPage() {
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
-    if (!inputValue.trim() || sendMessage.isPending) return;
+    if (!inputValue.trim() || isSubmitting) return;
 
     // Build controls with reasoning effort if selected
     const controls: Controls | undefined = reasoningEffort
@@ -55,12 +66,23 @@ export default function ChatPage() {
       : undefined;
 
     try {
-      await sendMessage.mutateAsync({
-        agentId,
-        sessionId,
-        content: inputValue.trim(),
-        controls,
-      });
+      if (editingMessageId) {
+        await editMessage.mutateAsync({
+          agentId,
+          sessionId,
+          messageId: editingMessageId,
+          content: inputValue.trim(),
+          controls,
+        });
+        setEditingMessageId(null);
+      } else {
+        await sendMessage.mutateAsync({
+          agentId,
+          sessionId,
+          content: inputValue.trim(),
+          controls,
+        });
+      }
       setInputValue("");
       // Start polling for the response
       setIsWaitingForResponse(true);
@@ -69,6 +91,25 @@ export default function ChatPage() {
     }
   };
 
+  const handleRegenerate = async () => {
+    try {
+      await regenerate.mutateAsync({ agentId, sessionId });
+      setIsWaitingForResponse(true);
+    } catch (error) {
+      console.error("Failed to regenerate response:", error);
+    }
+  };
+
+  const startEditing = (messageId: string, text: string) => {
+    setEditingMessageId(messageId);
+    setInputValue(text);
+  };
+
+  const cancelEditing = () => {
+    setEditingMessageId(null);
+    setInputValue("");
+  };
+
   const handleKeyDown = (e: React.KeyboardEvent<HTMLTextAreaElement>) => {
     if (e.key === "Enter" && !e.shiftKey) {
       e.preventDefault();
@@ -110,9 +151,23 @@ export default function ChatPage() {
                 {textContent && (
                   <div className={`flex ${isUser ? "justify-end" : "justify-start"}`}>
                     {isUser ? (
-                      /* User message - dark box, 90% width */
-                      <div className="max-w-[90%] bg-gray-500 text-white rounded-lg p-3">
-                        <p className="text-sm whitespace-pre-wrap">{textContent}</p>
+                      /* User message - dark box, 90% width, editable */
+                      <div className="group flex items-start gap-1 max-w-[90%]">
+                        <Button
+                          type="button"
+                          variant="ghost"
+                          size="icon"
+                          className="h-7 w-7 opacity-0 group-hover:opacity-100"
+                          title="Edit and rerun"
+                          onClick={() =>
+                            startEditing((data as MessageUserData).message.id, textContent)
+                          }
+                        >
+                          <Pencil className="h-3.5 w-3.5" />
+                        </Button>
+                        <div className="bg-gray-500 text-white rounded-lg p-3">
+                          <p className="text-sm whitespace-pre-wrap">{textContent}</p>
+                        </div>
                       </div>
                     ) : (
                       /* Agent message - darker background with robot icon */
@@ -137,6 +192,22 @@ export default function ChatPage() {
                     })}
                   </div>
                 )}
+
+                {/* Regenerate the latest agent reply */}
+                {event.id === lastAgentEventId && (
+                  <div className="pl-[25px]">
+                    <Button
+                      type="button"
+                      variant="ghost"
+                      size="sm"
+                      onClick={handleRegenerate}
+                      disabled={regenerate.isPending}
+                    >
+                      <RotateCcw className="h-3.5 w-3.5 mr-1" />
+                      Regenerate
+                    </Button>
+                  </div>
+                )}
               </div>
             );
           })
@@ -146,6 +217,15 @@ export default function ChatPage() {
 
       {/* Input area */}
       <div className="border-t p-4">
+        {editingMessageId && (
+          <div className="flex items-center justify-between mb-2 text-sm text-muted-foreground">
+            <span>Editing message. Later messages will be replaced.</span>
+            <Button type="button" variant="ghost" size="sm" onClick={cancelEditing}>
+              <X className="h-3.5 w-3.5 mr-1" />
+              Cancel
+            </Button>
+          </div>
+        )}
         <form onSubmit={handleSubmit} className="flex gap-2">
           <Textarea
             value={inputValue}
@@ -153,15 +233,15 @@ export default function ChatPage() {
             onKeyDown={handleKeyDown}
             placeholder="Type a message... (Enter to send, Shift+Enter for newline)"
             className="flex-1 min-h-[60px] max-h-[200px] resize-none"
-            disabled={sendMessage.isPending}
+            disabled={isSubmitting}
           />
           <Button
             type="submit"
             size="icon"
             className="h-[60px] w-[60px]"
-            disabled={!inputValue.trim() || sendMessage.isPending}
+            disabled={!inputValue.trim() || isSubmitting}
           >
-            {sendMessage.isPending ? (
+            {isSubmitting ? (
               <Loader2 className="h-5 w-5 animate-spin" />
             ) : (
               <Send className="h-5 w-5" />
@@ -4,10 +4,13 @@ import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
 import {
   createAgent,
   deleteAgent,
+  diffAgentRevision,
   exportAgent,
   getAgent,
   importAgent,
+  listAgentRevisions,
   listAgents,
+  rollbackAgentRevision,
   updateAgent,
 } from "@/lib/api/agents";
 import type { CreateAgentRequest, UpdateAgentRequest } from "@/lib/api/types";
@@ -52,6 +55,40 @@ export function useUpdateAgent() {
     onSuccess: (_, { agentId }) => {
       queryClient.invalidateQueries({ queryKey: ["agents"] });
       queryClient.invalidateQueries({ queryKey: ["agent", agentId] });
+      queryClient.invalidateQueries({ queryKey: ["agent-revisions", agentId] });
+    },
+  });
+}
+
+export function useAgentRevisions(agentId: string | undefined) {
+  return useQuery({
+    queryKey: ["agent-revisions", agentId],
+    queryFn: () => listAgentRevisions(agentId!),
+    enabled: !!agentId,
+  });
+}
+
+export function useAgentRevisionDiff(
+  agentId: string | undefined,
+  revision: number | undefined
+) {
+  return useQuery({
+    queryKey: ["agent-revision-diff", agentId, revision],
+    queryFn: () => diffAgentRevision(agentId!, revision!),
+    enabled: !!agentId && revision !== undefined && revision > 1,
+  });
+}
+
+export function useRollbackAgentRevision() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({ agentId, revision }: { agentId: string; revision: number }) =>
+      rollbackAgentRevision(agentId, revision),
+    onSuccess: (_, { agentId }) => {
+      queryClient.invalidateQueries({ queryKey: ["agents"] });
+      queryClient.invalidateQueries({ queryKey: ["agent", agentId] });
+      queryClient.invalidateQueries({ queryKey: ["agent-revisions", agentId] });
     },
   });
 }
@@ -8,11 +8,22 @@ import {
   getSession,
   listSessions,
   updateSession,
+  forkSession,
+  listSessionBranches,
   sendUserMessage,
+  editMessage,
+  regenerateResponse,
   listEvents,
 } from "@/lib/api/sessions";
 import { getSseUrl } from "@/lib/api/events";
-import type { CreateSessionRequest, UpdateSessionRequest, Controls, Event } from "@/lib/api/types";
+import type {
+  CreateSessionRequest,
+  UpdateSessionRequest,
+  ForkSessionRequest,
+  Controls,
+  Event,
+  EventsSupersededData,
+} from "@/lib/api/types";
 
 export function useSessions(agentId: string | undefined) {
   return useQuery({
@@ -91,6 +102,39 @@ export function useDeleteSession() {
   });
 }
 
+export function useForkSession() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({
+      agentId,
+      sessionId,
+      request,
+    }: {
+      agentId: string;
+      sessionId: string;
+      request?: ForkSessionRequest;
+    }) => forkSession(agentId, sessionId, request),
+    onSuccess: (_, { agentId, sessionId }) => {
+      queryClient.invalidateQueries({ queryKey: ["sessions", agentId] });
+      queryClient.invalidateQueries({
+        queryKey: ["session-branches", agentId, sessionId],
+      });
+    },
+  });
+}
+
+export function useSessionBranches(
+  agentId: string | undefined,
+  sessionId: string | undefined
+) {
+  return useQuery({
+    queryKey: ["session-branches", agentId, sessionId],
+    queryFn: () => listSessionBranches(agentId!, sessionId!),
+    enabled: !!agentId && !!sessionId,
+  });
+}
+
 export function useSendMessage() {
   const queryClient = useQueryClient();
 
@@ -115,6 +159,57 @@ export function useSendMessage() {
   });
 }
 
+export function useEditMessage() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({
+      agentId,
+      sessionId,
+      messageId,
+      content,
+      controls,
+    }: {
+      agentId: string;
+      sessionId: string;
+      messageId: string;
+      content: string;
+      controls?: Controls;
+    }) =>
+      editMessage(agentId, sessionId, messageId, {
+        message: {
+          role: "user",
+          content: [{ type: "text", text: content }],
+        },
+        controls,
+      }),
+    onSuccess: (_, { agentId, sessionId }) => {
+      queryClient.invalidateQueries({
+        queryKey: ["events", agentId, sessionId],
+      });
+    },
+  });
+}
+
+export function useRegenerateResponse() {
+  const queryClient = useQueryClient();
+
+  return useMutation({
+    mutationFn: ({
+      agentId,
+      sessionId,
+    }: {
+      agentId: string;
+      sessionId: string;
+    }) => regenerateResponse(agentId, sessionId),
+    onSuccess: (_, { agentId, sessionId }) => {
+      queryClient.invalidateQueries({
+        queryKey: ["events", agentId, sessionId],
+      });
+    },
+  });
+}
+
 // ============================================
 // Events hook - uses SSE for real-time updates
 // ============================================
@@ -203,6 +298,7 @@ export function useEvents(
         "session.started",
         "session.activated",
         "session.idled",
+        "events.superseded",
       ];
 
       for (const eventType of eventTypes) {
@@ -217,6 +313,19 @@ export function useEvents(
 
             eventIdsRef.current.add(event.id);
             lastEventIdRef.current = event.id;
+
+            // A regenerated or edited turn replaces earlier events
+            if (event.type === "events.superseded") {
+              const { from_sequence } = event.data as EventsSupersededData;
+              setEvents((prev) => [
+                ...prev.filter(
+                  (e) => e.sequence === undefined || e.sequence < from_sequence
+                ),
+                event,
+              ]);
+              return;
+            }
+
             setEvents((prev) => [...prev, event]);
           } catch (e) {
             console.error("Failed to parse SSE event:", e);
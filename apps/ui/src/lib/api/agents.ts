@@ -3,6 +3,7 @@
 import { api } from "./client";
 import type {
   Agent,
+  AgentRevisionDiff,
   CreateAgentRequest,
   UpdateAgentRequest,
   ListResponse,
@@ -40,6 +41,46 @@ export async function deleteAgent(agentId: string): Promise<void> {
   await api.delete(`/v1/agents/${agentId}`);
 }
 
+export async function listAgentRevisions(agentId: string): Promise<Agent[]> {
+  const response = await api.get<ListResponse<Agent>>(
+    `/v1/agents/${agentId}/revisions`
+  );
+  return response.data.data;
+}
+
+export async function getAgentRevision(
+  agentId: string,
+  revision: number
+): Promise<Agent> {
+  const response = await api.get<Agent>(
+    `/v1/agents/${agentId}/revisions/${revision}`
+  );
+  return response.data;
+}
+
+export async function diffAgentRevision(
+  agentId: string,
+  revision: number,
+  from?: number
+): Promise<AgentRevisionDiff> {
+  const query = from !== undefined ? `?from=${from}` : "";
+  const response = await api.get<AgentRevisionDiff>(
+    `/v1/agents/${agentId}/revisions/${revision}/diff${query}`
+  );
+  return response.data;
+}
+
+export async function rollbackAgentRevision(
+  agentId: string,
+  revision: number
+): Promise<Agent> {
+  const response = await api.post<Agent>(
+    `/v1/agents/${agentId}/revisions/${revision}/rollback`,
+    {}
+  );
+  return response.data;
+}
+
 export async function exportAgent(agentId: string): Promise<string> {
   // Use fetch directly since we need to return text, not JSON
   const response = await fetch(`/api/v1/agents/${agentId}/export`, {
@@ -45,3 +45,28 @@ export async function sendUserMessage(
     controls,
   });
 }
+
+// Replace a user message and rerun the conversation from there
+export async function editMessage(
+  agentId: string,
+  sessionId: string,
+  messageId: string,
+  request: CreateMessageRequest
+): Promise<Message> {
+  const response = await api.post<Message>(
+    `/v1/agents/${agentId}/sessions/${sessionId}/messages/${messageId}/edit`,
+    request
+  );
+  return response.data;
+}
+
+// Rerun the turn for the latest user message
+export async function regenerateResponse(
+  agentId: string,
+  sessionId: string
+): Promise<Message> {
+  const response = await api.post<Message>(
+    `/v1/agents/${agentId}/sessions/${sessionId}/regenerate`
+  );
+  return response.data;
+}
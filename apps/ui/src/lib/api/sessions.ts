@@ -5,11 +5,18 @@ import type {
   Session,
   CreateSessionRequest,
   UpdateSessionRequest,
+  ForkSessionRequest,
   ListResponse,
 } from "./types";
 
 // Re-export message and event functions for convenience
-export { createMessage, listMessages, sendUserMessage } from "./messages";
+export {
+  createMessage,
+  listMessages,
+  sendUserMessage,
+  editMessage,
+  regenerateResponse,
+} from "./messages";
 export { listEvents } from "./events";
 
 // ============================================
@@ -62,3 +69,29 @@ export async function deleteSession(
 ): Promise<void> {
   await api.delete(`/v1/agents/${agentId}/sessions/${sessionId}`);
 }
+
+// ============================================
+// Forks
+// ============================================
+
+export async function forkSession(
+  agentId: string,
+  sessionId: string,
+  request: ForkSessionRequest = {}
+): Promise<Session> {
+  const response = await api.post<Session>(
+    `/v1/agents/${agentId}/sessions/${sessionId}/fork`,
+    request
+  );
+  return response.data;
+}
+
+export async function listSessionBranches(
+  agentId: string,
+  sessionId: string
+): Promise<Session[]> {
+  const response = await api.get<ListResponse<Session>>(
+    `/v1/agents/${agentId}/sessions/${sessionId}/branches`
+  );
+  return response.data.data;
+}
@@ -19,10 +19,25 @@ export interface Agent {
   tags: string[];
   capabilities: CapabilityId[];
   status: AgentStatus;
+  /** Configuration revision; every update creates a new one */
+  revision: number;
   created_at: string;
   updated_at: string;
 }
 
+export interface AgentFieldChange {
+  field: string;
+  from: unknown;
+  to: unknown;
+}
+
+export interface AgentRevisionDiff {
+  agent_id: string;
+  from_revision: number;
+  to_revision: number;
+  changes: AgentFieldChange[];
+}
+
 export interface CreateAgentRequest {
   name: string;
   description?: string;
@@ -62,12 +77,24 @@ export interface Session {
   created_at: string;
   started_at: string | null;
   finished_at: string | null;
+  /** Session this one was forked from */
+  parent_session_id?: string;
+  /** Last parent event copied into this fork */
+  forked_at_event_id?: string;
+  /** Pinned agent revision; absent when the session follows the latest */
+  agent_revision?: number;
+  /** System prompt template variables */
+  variables?: Record<string, string>;
 }
 
 export interface CreateSessionRequest {
   title?: string;
   tags?: string[];
   model_id?: string;
+  /** Use the agent's latest revision on every turn instead of pinning */
+  follow_latest_revision?: boolean;
+  /** Values for the agent's system prompt template variables */
+  variables?: Record<string, string>;
 }
 
 export interface UpdateSessionRequest {
@@ -76,6 +103,17 @@ export interface UpdateSessionRequest {
   model_id?: string;
 }
 
+export interface ForkSessionRequest {
+  /** Copy events up to the end of this turn */
+  turn_id?: string;
+  /** Copy events up to this message */
+  message_id?: string;
+  include_files?: boolean;
+  title?: string;
+  tags?: string[];
+  model_id?: string;
+}
+
 // ============================================
 // Message types (M2) - PRIMARY data
 // ============================================
@@ -368,6 +406,13 @@ export interface SessionIdledData {
   iterations?: number;
 }
 
+/** Data for events.superseded event (turn regenerated or user message edited) */
+export interface EventsSupersededData {
+  /** Events from this sequence up to the superseded event were replaced */
+  from_sequence: number;
+  input_message_id: string;
+}
+
 /** Union type for all event data types */
 export type EventData =
   | MessageUserData
@@ -386,6 +431,7 @@ export type EventData =
   | SessionStartedData
   | SessionActivatedData
   | SessionIdledData
+  | EventsSupersededData
   | Record<string, unknown>; // Raw/unknown event data
 
 export interface CreateEventRequest {
@@ -521,6 +567,8 @@ export interface LlmProvider {
   base_url?: string;
   api_key_set: boolean;
   status: LlmProviderStatus;
+  requests_per_minute?: number;
+  tokens_per_minute?: number;
   created_at: string;
   updated_at: string;
 }
@@ -639,6 +687,8 @@ export interface CreateLlmProviderRequest {
   provider_type: LlmProviderType;
   base_url?: string;
   api_key?: string;
+  requests_per_minute?: number;
+  tokens_per_minute?: number;
 }
 
 export interface UpdateLlmProviderRequest {
@@ -647,6 +697,8 @@ export interface UpdateLlmProviderRequest {
   base_url?: string;
   api_key?: string;
   status?: LlmProviderStatus;
+  requests_per_minute?: number;
+  tokens_per_minute?: number;
 }
 
 export interface CreateLlmModelRequest {
@@ -0,0 +1,19 @@
+// System prompt template helpers
+//
+// Mirrors the placeholder syntax rendered by the backend: `{{name}}` for
+// session variables and `{{file "/path"}}` for session files.
+
+/** Variables filled in by the runtime rather than the session creator */
+export const BUILTIN_TEMPLATE_VARIABLES = ["current_date", "session_id", "user_name"];
+
+/** Variables a session must provide for the given system prompt, in order of first use */
+export function requiredTemplateVariables(systemPrompt: string): string[] {
+  const names: string[] = [];
+  for (const match of systemPrompt.matchAll(/\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}/g)) {
+    const name = match[1];
+    if (!BUILTIN_TEMPLATE_VARIABLES.includes(name) && !names.includes(name)) {
+      names.push(name);
+    }
+  }
+  return names;
+}
@@ -14,14 +14,17 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 
 use everruns_core::error::{AgentLoopError, Result};
 use everruns_core::llm_driver_registry::{
     BoxedLlmDriver, DriverRegistry, LlmCallConfig, LlmCompletionMetadata, LlmContentPart,
     LlmDriver, LlmMessage, LlmMessageContent, LlmMessageRole, LlmResponseStream, LlmStreamEvent,
-    ProviderType,
+    ProviderType, RateLimitInfo,
 };
-use everruns_core::tool_types::{ToolCall, ToolDefinition};
+use everruns_core::tool_types::{ToolCall, ToolChoice, ToolDefinition};
 
 const DEFAULT_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
@@ -47,6 +50,7 @@ pub struct AnthropicLlmDriver {
     client: Client,
     api_key: String,
     api_url: String,
+    rate_limit: Arc<Mutex<Option<RateLimitInfo>>>,
 }
 
 impl AnthropicLlmDriver {
@@ -56,6 +60,7 @@ impl AnthropicLlmDriver {
             client: Client::new(),
             api_key: api_key.into(),
             api_url: DEFAULT_API_URL.to_string(),
+            rate_limit: Arc::default(),
         }
     }
 
@@ -72,6 +77,7 @@ impl AnthropicLlmDriver {
             client: Client::new(),
             api_key: api_key.into(),
             api_url: api_url.into(),
+            rate_limit: Arc::default(),
         }
     }
 
@@ -201,25 +207,21 @@ impl AnthropicLlmDriver {
             })
             .collect()
     }
-}
 
-#[async_trait]
-impl LlmDriver for AnthropicLlmDriver {
-    async fn chat_completion_stream(
-        &self,
-        messages: Vec<LlmMessage>,
+    /// Build the Messages API request body.
+    ///
+    /// A response format is mapped to a synthetic tool whose input schema is the
+    /// requested JSON schema, and tool_choice forces the model to call it (or any
+    /// tool, when the agent has real tools it may still need to use). Anthropic
+    /// only allows auto/none tool choice with extended thinking, so forcing is
+    /// relaxed to auto then.
+    pub(crate) fn build_request(
+        messages: &[LlmMessage],
         config: &LlmCallConfig,
-    ) -> Result<LlmResponseStream> {
-        // Note: OTel instrumentation is handled via event listeners.
-        // ReasonAtom emits llm.generation events, and OtelEventListener
-        // creates gen-ai spans from those events.
-        let (system_prompt, anthropic_messages) = Self::convert_messages(&messages);
+    ) -> AnthropicRequest {
+        let (system_prompt, anthropic_messages) = Self::convert_messages(messages);
 
-        let tools = if config.tools.is_empty() {
-            None
-        } else {
-            Some(Self::convert_tools(&config.tools))
-        };
+        let mut tools = Self::convert_tools(&config.tools);
 
         // Build thinking config from reasoning effort
         let thinking = config
@@ -227,21 +229,76 @@ impl LlmDriver for AnthropicLlmDriver {
             .as_ref()
             .and_then(|e| AnthropicThinking::from_effort(e));
 
-        let mut request = AnthropicRequest {
+        let mut choice = config.tool_choice.as_ref().map(|c| match c {
+            ToolChoice::Auto => AnthropicToolChoiceKind::Auto,
+            ToolChoice::None => AnthropicToolChoiceKind::None,
+            ToolChoice::Required => AnthropicToolChoiceKind::Any,
+            ToolChoice::Tool { name } => AnthropicToolChoiceKind::Tool { name: name.clone() },
+        });
+
+        if let Some(format) = &config.response_format {
+            choice = Some(if tools.is_empty() {
+                AnthropicToolChoiceKind::Tool {
+                    name: format.name.clone(),
+                }
+            } else {
+                AnthropicToolChoiceKind::Any
+            });
+            tools.push(AnthropicTool {
+                name: format.name.clone(),
+                description: "Respond to the user with the final answer. The input must \
+                              follow the required output schema."
+                    .to_string(),
+                input_schema: format.schema.clone(),
+            });
+        }
+
+        if thinking.is_some()
+            && matches!(
+                choice,
+                Some(AnthropicToolChoiceKind::Any | AnthropicToolChoiceKind::Tool { .. })
+            )
+        {
+            choice = Some(AnthropicToolChoiceKind::Auto);
+        }
+
+        let disable_parallel_tool_use = config.parallel_tool_calls.filter(|p| !p).map(|_| true);
+        let tool_choice =
+            if tools.is_empty() || (choice.is_none() && disable_parallel_tool_use.is_none()) {
+                None
+            } else {
+                Some(AnthropicToolChoice {
+                    kind: choice.unwrap_or(AnthropicToolChoiceKind::Auto),
+                    disable_parallel_tool_use,
+                })
+            };
+
+        AnthropicRequest {
             model: config.model.clone(),
             messages: anthropic_messages,
-            max_tokens: config.max_tokens.unwrap_or(4096),
+            // Ensure max_tokens is set (required by Anthropic)
+            max_tokens: config.max_tokens.filter(|t| *t > 0).unwrap_or(4096),
             temperature: config.temperature,
             system: system_prompt,
             stream: true,
-            tools,
+            tools: if tools.is_empty() { None } else { Some(tools) },
+            tool_choice,
             thinking,
-        };
-
-        // Ensure max_tokens is set (required by Anthropic)
-        if request.max_tokens == 0 {
-            request.max_tokens = 4096;
         }
+    }
+}
+
+#[async_trait]
+impl LlmDriver for AnthropicLlmDriver {
+    async fn chat_completion_stream(
+        &self,
+        messages: Vec<LlmMessage>,
+        config: &LlmCallConfig,
+    ) -> Result<LlmResponseStream> {
+        // Note: OTel instrumentation is handled via event listeners.
+        // ReasonAtom emits llm.generation events, and OtelEventListener
+        // creates gen-ai spans from those events.
+        let request = Self::build_request(&messages, config);
 
         let response = self
             .client
@@ -254,6 +311,9 @@ impl LlmDriver for AnthropicLlmDriver {
             .await
             .map_err(|e| AgentLoopError::llm(format!("Failed to send request: {}", e)))?;
 
+        let rate_limit = parse_rate_limit_headers(response.headers(), Utc::now());
+        *self.rate_limit.lock().unwrap() = (!rate_limit.is_empty()).then_some(rate_limit);
+
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
@@ -267,6 +327,7 @@ impl LlmDriver for AnthropicLlmDriver {
         let event_stream = byte_stream.eventsource();
 
         let model = config.model.clone();
+        let response_tool_name = config.response_format.as_ref().map(|f| f.name.clone());
         let input_tokens = Arc::new(Mutex::new(0u32));
         let output_tokens = Arc::new(Mutex::new(0u32));
         let current_tool_call = Arc::new(Mutex::new(Option::<ToolCall>::None));
@@ -274,6 +335,7 @@ impl LlmDriver for AnthropicLlmDriver {
 
         let converted_stream: LlmResponseStream = Box::pin(event_stream.then(move |result| {
             let model = model.clone();
+            let response_tool_name = response_tool_name.clone();
             let input_tokens = Arc::clone(&input_tokens);
             let output_tokens = Arc::clone(&output_tokens);
             let current_tool_call = Arc::clone(&current_tool_call);
@@ -348,6 +410,13 @@ impl LlmDriver for AnthropicLlmDriver {
                                         tc.arguments =
                                             serde_json::from_str(args_str).unwrap_or(json!({}));
                                     }
+                                    // The forced structured-output tool carries the final
+                                    // answer: surface it as text instead of a tool call
+                                    if response_tool_name.as_deref() == Some(tc.name.as_str()) {
+                                        return Ok(LlmStreamEvent::TextDelta(
+                                            tc.arguments.to_string(),
+                                        ));
+                                    }
                                     accumulated_tool_calls.lock().unwrap().push(tc);
                                 }
                                 Ok(LlmStreamEvent::TextDelta(String::new()))
@@ -406,6 +475,10 @@ impl LlmDriver for AnthropicLlmDriver {
 
         Ok(converted_stream)
     }
+
+    fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        self.rate_limit.lock().unwrap().clone()
+    }
 }
 
 impl std::fmt::Debug for AnthropicLlmDriver {
@@ -417,6 +490,43 @@ impl std::fmt::Debug for AnthropicLlmDriver {
     }
 }
 
+// ============================================================================
+// Rate Limit Headers
+// ============================================================================
+
+/// Parse `anthropic-ratelimit-*` and `retry-after` headers
+///
+/// Anthropic reports resets as RFC 3339 timestamps; they are converted to
+/// durations relative to `now`.
+pub(crate) fn parse_rate_limit_headers(
+    headers: &reqwest::header::HeaderMap,
+    now: DateTime<Utc>,
+) -> RateLimitInfo {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let number = |name: &str| header(name).and_then(|v| v.trim().parse::<u32>().ok());
+    let reset = |name: &str| {
+        header(name)
+            .and_then(|v| DateTime::parse_from_rfc3339(v.trim()).ok())
+            .map(|at| {
+                (at.with_timezone(&Utc) - now)
+                    .to_std()
+                    .unwrap_or(Duration::ZERO)
+            })
+    };
+
+    RateLimitInfo {
+        request_limit: number("anthropic-ratelimit-requests-limit"),
+        requests_remaining: number("anthropic-ratelimit-requests-remaining"),
+        requests_reset: reset("anthropic-ratelimit-requests-reset"),
+        token_limit: number("anthropic-ratelimit-tokens-limit"),
+        tokens_remaining: number("anthropic-ratelimit-tokens-remaining"),
+        tokens_reset: reset("anthropic-ratelimit-tokens-reset"),
+        retry_after: header("retry-after")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs),
+    }
+}
+
 // ============================================================================
 // Driver Registration
 // ============================================================================
@@ -449,7 +559,7 @@ pub fn register_driver(registry: &mut DriverRegistry) {
 // ============================================================================
 
 #[derive(Debug, Serialize)]
-struct AnthropicRequest {
+pub(crate) struct AnthropicRequest {
     model: String,
     messages: Vec<AnthropicMessage>,
     max_tokens: u32,
@@ -460,6 +570,8 @@ struct AnthropicRequest {
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<AnthropicTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<AnthropicToolChoice>,
     /// Extended thinking configuration (for Claude models that support it)
     #[serde(skip_serializing_if = "Option::is_none")]
     thinking: Option<AnthropicThinking>,
@@ -534,6 +646,23 @@ struct AnthropicTool {
     input_schema: Value,
 }
 
+#[derive(Debug, Serialize)]
+struct AnthropicToolChoice {
+    #[serde(flatten)]
+    kind: AnthropicToolChoiceKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disable_parallel_tool_use: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicToolChoiceKind {
+    Auto,
+    Any,
+    None,
+    Tool { name: String },
+}
+
 // Streaming response types
 
 #[derive(Debug, Deserialize)]
@@ -1,7 +1,12 @@
 // Unit tests for Anthropic driver
 
 use crate::{register_driver, AnthropicLlmDriver, DriverRegistry};
-use everruns_core::llm_driver_registry::{ProviderConfig, ProviderType};
+use everruns_core::llm_driver_registry::{
+    LlmCallConfig, LlmMessage, LlmMessageRole, ProviderConfig, ProviderType,
+};
+use everruns_core::tool_types::{BuiltinTool, ToolChoice, ToolDefinition, ToolPolicy};
+use everruns_core::{ResponseFormat, RuntimeAgentBuilder};
+use serde_json::json;
 
 #[test]
 fn test_driver_with_api_key() {
@@ -31,3 +36,121 @@ fn test_register_driver() {
     let driver = registry.create_driver(&config);
     assert!(driver.is_ok());
 }
+
+fn structured_config(tools: Vec<ToolDefinition>) -> LlmCallConfig {
+    let runtime_agent = RuntimeAgentBuilder::new()
+        .model("claude-sonnet-4-5")
+        .tools(tools)
+        .response_format(ResponseFormat::new(
+            "answer",
+            json!({"type": "object", "properties": {"value": {"type": "integer"}}}),
+        ))
+        .build();
+    LlmCallConfig::from(&runtime_agent)
+}
+
+#[test]
+fn test_response_format_forces_synthetic_tool() {
+    let messages = vec![LlmMessage::text(LlmMessageRole::User, "What is 2+2?")];
+    let request = AnthropicLlmDriver::build_request(&messages, &structured_config(vec![]));
+    let body = serde_json::to_value(&request).unwrap();
+
+    assert_eq!(
+        body["tool_choice"],
+        json!({"type": "tool", "name": "answer"})
+    );
+    assert_eq!(body["tools"][0]["name"], "answer");
+    assert_eq!(
+        body["tools"][0]["input_schema"]["properties"]["value"]["type"],
+        "integer"
+    );
+}
+
+#[test]
+fn test_response_format_with_agent_tools_uses_any_choice() {
+    let tool = ToolDefinition::Builtin(BuiltinTool {
+        name: "get_weather".to_string(),
+        description: "Get weather".to_string(),
+        parameters: json!({"type": "object"}),
+        policy: ToolPolicy::Auto,
+    });
+    let messages = vec![LlmMessage::text(LlmMessageRole::User, "Weather?")];
+    let request = AnthropicLlmDriver::build_request(&messages, &structured_config(vec![tool]));
+    let body = serde_json::to_value(&request).unwrap();
+
+    assert_eq!(body["tool_choice"], json!({"type": "any"}));
+    assert_eq!(body["tools"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_tool_choice_and_parallel_toggle() {
+    let tool = ToolDefinition::Builtin(BuiltinTool {
+        name: "get_weather".to_string(),
+        description: "Get weather".to_string(),
+        parameters: json!({"type": "object"}),
+        policy: ToolPolicy::Auto,
+    });
+    let runtime_agent = RuntimeAgentBuilder::new()
+        .model("claude-sonnet-4-5")
+        .tool(tool)
+        .tool_choice(ToolChoice::Required)
+        .parallel_tool_calls(false)
+        .build();
+    let messages = vec![LlmMessage::text(LlmMessageRole::User, "Weather?")];
+    let request =
+        AnthropicLlmDriver::build_request(&messages, &LlmCallConfig::from(&runtime_agent));
+    let body = serde_json::to_value(&request).unwrap();
+
+    assert_eq!(
+        body["tool_choice"],
+        json!({"type": "any", "disable_parallel_tool_use": true})
+    );
+}
+
+#[test]
+fn test_no_tool_choice_without_tools() {
+    let runtime_agent = RuntimeAgentBuilder::new()
+        .tool_choice(ToolChoice::Required)
+        .build();
+    let messages = vec![LlmMessage::text(LlmMessageRole::User, "Hi")];
+    let request =
+        AnthropicLlmDriver::build_request(&messages, &LlmCallConfig::from(&runtime_agent));
+    let body = serde_json::to_value(&request).unwrap();
+
+    assert!(body.get("tool_choice").is_none());
+}
+
+#[test]
+fn test_parse_rate_limit_headers() {
+    use crate::driver::parse_rate_limit_headers;
+    use std::time::Duration;
+
+    let now = chrono::DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("anthropic-ratelimit-requests-limit", "50".parse().unwrap());
+    headers.insert(
+        "anthropic-ratelimit-requests-remaining",
+        "0".parse().unwrap(),
+    );
+    headers.insert(
+        "anthropic-ratelimit-requests-reset",
+        "2025-01-01T00:00:30Z".parse().unwrap(),
+    );
+    headers.insert("anthropic-ratelimit-tokens-limit", "40000".parse().unwrap());
+    headers.insert(
+        "anthropic-ratelimit-tokens-remaining",
+        "12000".parse().unwrap(),
+    );
+    headers.insert("retry-after", "30".parse().unwrap());
+
+    let info = parse_rate_limit_headers(&headers, now);
+    assert_eq!(info.request_limit, Some(50));
+    assert_eq!(info.requests_remaining, Some(0));
+    assert_eq!(info.requests_reset, Some(Duration::from_secs(30)));
+    assert_eq!(info.token_limit, Some(40000));
+    assert_eq!(info.tokens_remaining, Some(12000));
+    assert_eq!(info.tokens_reset, None);
+    assert_eq!(info.retry_after, Some(Duration::from_secs(30)));
+}
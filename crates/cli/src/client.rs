@@ -45,7 +45,6 @@ impl Client {
         self.handle_response(response).await
     }
 
-    #[allow(dead_code)]
     pub async fn patch<T: DeserializeOwned, B: Serialize>(
         &self,
         path: &str,
@@ -56,6 +55,51 @@ impl Client {
         self.handle_response(response).await
     }
 
+    /// POST a raw body (e.g. an archive) and decode the JSON response
+    pub async fn post_bytes<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> Result<T, ClientError> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .http
+            .post(&url)
+            .query(query)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await?;
+        self.handle_response(response).await
+    }
+
+    /// GET a raw response body (e.g. an archive)
+    pub async fn get_bytes(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Vec<u8>, ClientError> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.http.get(&url).query(query).send().await?;
+        let status = response.status();
+
+        if status == StatusCode::NOT_FOUND {
+            return Err(ClientError::NotFound);
+        }
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(ClientError::Api {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
     pub async fn delete(&self, path: &str) -> Result<(), ClientError> {
         let url = format!("{}{}", self.base_url, path);
         let response = self.http.delete(&url).send().await?;
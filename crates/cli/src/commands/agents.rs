@@ -5,6 +5,7 @@ use crate::output::{print_field, print_table_header, print_table_row, OutputForm
 use anyhow::{Context, Result};
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 #[derive(Subcommand)]
@@ -38,6 +39,10 @@ pub enum AgentsCommand {
         /// Capability IDs (repeatable)
         #[arg(long, short)]
         capability: Vec<String>,
+
+        /// Capability configuration as ID=JSON, e.g. web_fetch='{"allowed_domains":["docs.rs"]}' (repeatable)
+        #[arg(long, value_parser = parse_capability_config)]
+        capability_config: Vec<(String, serde_json::Value)>,
     },
 
     /// List all agents
@@ -54,6 +59,34 @@ pub enum AgentsCommand {
         /// Agent ID
         agent_id: Uuid,
     },
+
+    /// List an agent's revisions, newest first
+    Revisions {
+        /// Agent ID
+        agent_id: Uuid,
+    },
+
+    /// Show what changed in a revision
+    Diff {
+        /// Agent ID
+        agent_id: Uuid,
+
+        /// Revision to inspect
+        revision: i32,
+
+        /// Revision to compare against (defaults to the preceding one)
+        #[arg(long)]
+        from: Option<i32>,
+    },
+
+    /// Restore an earlier revision (recorded as a new revision)
+    Rollback {
+        /// Agent ID
+        agent_id: Uuid,
+
+        /// Revision to restore
+        revision: i32,
+    },
 }
 
 /// Agent definition from YAML/JSON file
@@ -67,6 +100,8 @@ pub struct AgentFile {
     pub tags: Vec<String>,
     #[serde(default)]
     pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub capability_config: BTreeMap<String, serde_json::Value>,
 }
 
 /// Request to create an agent
@@ -82,6 +117,8 @@ struct CreateAgentRequest {
     tags: Vec<String>,
     #[serde(default)]
     capabilities: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    capability_config: BTreeMap<String, serde_json::Value>,
 }
 
 /// Agent response from API
@@ -98,16 +135,48 @@ pub struct Agent {
     pub tags: Vec<String>,
     #[serde(default)]
     pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub capability_config: BTreeMap<String, serde_json::Value>,
     pub status: String,
+    #[serde(default)]
+    pub revision: i32,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// Differences between two revisions
+#[derive(Debug, Serialize, Deserialize)]
+struct AgentRevisionDiff {
+    from_revision: i32,
+    to_revision: i32,
+    changes: Vec<AgentFieldChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AgentFieldChange {
+    field: String,
+    from: serde_json::Value,
+    to: serde_json::Value,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ListResponse<T> {
     data: Vec<T>,
 }
 
+/// Parse a `--capability-config` value of the form `ID=JSON`
+fn parse_capability_config(s: &str) -> Result<(String, serde_json::Value), String> {
+    let (id, json) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected ID=JSON, got '{}'", s))?;
+    let config: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("invalid JSON for '{}': {}", id, e))?;
+    if !config.is_object() {
+        return Err(format!("config for '{}' must be a JSON object", id));
+    }
+    Ok((id.to_string(), config))
+}
+
 /// Parse markdown file with YAML front matter.
 /// Format:
 /// ```markdown
@@ -160,6 +229,7 @@ pub async fn run(
             model,
             tag,
             capability,
+            capability_config,
         } => {
             create(
                 client,
@@ -172,12 +242,22 @@ pub async fn run(
                 model,
                 tag,
                 capability,
+                capability_config,
             )
             .await
         }
         AgentsCommand::List => list(client, output).await,
         AgentsCommand::Get { agent_id } => get(client, output, agent_id).await,
         AgentsCommand::Delete { agent_id } => delete(client, output, quiet, agent_id).await,
+        AgentsCommand::Revisions { agent_id } => revisions(client, output, agent_id).await,
+        AgentsCommand::Diff {
+            agent_id,
+            revision,
+            from,
+        } => diff(client, output, agent_id, revision, from).await,
+        AgentsCommand::Rollback { agent_id, revision } => {
+            rollback(client, output, quiet, agent_id, revision).await
+        }
     }
 }
 
@@ -193,6 +273,7 @@ async fn create(
     model: Option<Uuid>,
     tags: Vec<String>,
     capabilities: Vec<String>,
+    capability_config: Vec<(String, serde_json::Value)>,
 ) -> Result<()> {
     // Load from file if provided
     let file_config = if let Some(path) = file {
@@ -254,6 +335,9 @@ async fn create(
     } else {
         capabilities
     };
+    // CLI configs override the file's config of the same capability
+    let mut final_capability_config = file_config.capability_config;
+    final_capability_config.extend(capability_config);
 
     let request = CreateAgentRequest {
         name: final_name,
@@ -262,6 +346,7 @@ async fn create(
         default_model_id: final_model,
         tags: final_tags,
         capabilities: final_capabilities,
+        capability_config: final_capability_config,
     };
 
     let agent: Agent = client.post("/v1/agents", &request).await?;
@@ -332,12 +417,16 @@ async fn get(client: &Client, output: OutputFormat, agent_id: Uuid) -> Result<()
         print_field("ID", &agent.id.to_string());
         print_field("Name", &agent.name);
         print_field("Status", &agent.status);
+        print_field("Revision", &agent.revision.to_string());
         if let Some(desc) = &agent.description {
             print_field("Description", desc);
         }
         if !agent.capabilities.is_empty() {
             print_field("Capabilities", &agent.capabilities.join(", "));
         }
+        for (capability_id, config) in &agent.capability_config {
+            print_field(&format!("Config {}", capability_id), &config.to_string());
+        }
         if !agent.tags.is_empty() {
             print_field("Tags", &agent.tags.join(", "));
         }
@@ -366,3 +455,98 @@ async fn delete(client: &Client, output: OutputFormat, quiet: bool, agent_id: Uu
 
     Ok(())
 }
+
+async fn revisions(client: &Client, output: OutputFormat, agent_id: Uuid) -> Result<()> {
+    let response: ListResponse<Agent> = client
+        .get(&format!("/v1/agents/{}/revisions", agent_id))
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Agent not found: {}", agent_id),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        print_table_header(&[("REVISION", 8), ("NAME", 20), ("CREATED", 30)]);
+        for agent in &response.data {
+            print_table_row(&[
+                (&agent.revision.to_string(), 8),
+                (&agent.name, 20),
+                (&agent.updated_at, 30),
+            ]);
+        }
+    } else {
+        output.print_value(&response);
+    }
+
+    Ok(())
+}
+
+async fn diff(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    revision: i32,
+    from: Option<i32>,
+) -> Result<()> {
+    let mut path = format!("/v1/agents/{}/revisions/{}/diff", agent_id, revision);
+    if let Some(from) = from {
+        path.push_str(&format!("?from={}", from));
+    }
+    let diff: AgentRevisionDiff = client.get(&path).await.map_err(|e| match e {
+        ClientError::NotFound => anyhow::anyhow!("Agent or revision not found: {}", agent_id),
+        e => e.into(),
+    })?;
+
+    if output.is_text() {
+        if diff.changes.is_empty() {
+            println!(
+                "No changes between revisions {} and {}",
+                diff.from_revision, diff.to_revision
+            );
+            return Ok(());
+        }
+        println!(
+            "Changes from revision {} to {}:",
+            diff.from_revision, diff.to_revision
+        );
+        for change in &diff.changes {
+            println!("  {}:", change.field);
+            println!("    - {}", change.from);
+            println!("    + {}", change.to);
+        }
+    } else {
+        output.print_value(&diff);
+    }
+
+    Ok(())
+}
+
+async fn rollback(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    revision: i32,
+) -> Result<()> {
+    let agent: Agent = client
+        .post(
+            &format!("/v1/agents/{}/revisions/{}/rollback", agent_id, revision),
+            &serde_json::json!({}),
+        )
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Agent or revision not found: {}", agent_id),
+            e => e.into(),
+        })?;
+
+    if output.is_text() && !quiet {
+        println!(
+            "Restored revision {} of agent {} as revision {}",
+            revision, agent.id, agent.revision
+        );
+    } else if !output.is_text() {
+        output.print_value(&agent);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,490 @@
+// Session file commands: version history and archive import/export
+
+use crate::client::{Client, ClientError};
+use crate::output::{print_field, print_table_header, print_table_row, OutputFormat};
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Subcommand)]
+pub enum FilesCommand {
+    /// List versions of a file
+    Versions {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// File path
+        path: String,
+    },
+
+    /// Show a unified diff between two versions of a file
+    Diff {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// File path
+        path: String,
+
+        /// Older version (defaults to the version before --to)
+        #[arg(long)]
+        from: Option<i32>,
+
+        /// Newer version (defaults to the latest)
+        #[arg(long)]
+        to: Option<i32>,
+    },
+
+    /// Restore a file to a version, snapshot or turn
+    Restore {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// File path
+        path: String,
+
+        /// File version number
+        #[arg(long)]
+        version: Option<i32>,
+
+        /// Snapshot ID
+        #[arg(long)]
+        snapshot: Option<Uuid>,
+
+        /// Turn ID
+        #[arg(long)]
+        turn: Option<Uuid>,
+    },
+
+    /// Upload a local directory or a .tar.gz/.tgz/.zip archive into the session
+    Push {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// Local directory or archive file
+        source: PathBuf,
+
+        /// Session directory to unpack into
+        #[arg(long, default_value = "/")]
+        dest: String,
+
+        /// Replace files that already exist
+        #[arg(long)]
+        overwrite: bool,
+    },
+
+    /// Download session files into a local directory or a .tar.gz/.tgz/.zip file
+    Pull {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// Local directory to extract into, or archive file to write
+        target: PathBuf,
+
+        /// Session file or directory to download
+        #[arg(long, default_value = "/")]
+        path: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct PathRequest {
+    path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DiffRequest {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_version: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_version: Option<i32>,
+}
+
+/// Request to restore a file or the whole tree
+#[derive(Debug, Serialize)]
+pub struct RestoreRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_id: Option<Uuid>,
+}
+
+/// File version response from API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileVersion {
+    pub id: Uuid,
+    pub path: String,
+    pub version: i32,
+    pub size_bytes: i64,
+    pub is_deleted: bool,
+    pub created_at: String,
+}
+
+/// File diff response from API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub diff: String,
+    pub is_binary: bool,
+}
+
+/// Archive import response from API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub path: String,
+    pub file_count: usize,
+    pub size_bytes: i64,
+}
+
+/// Restored file response from API (content omitted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoredFile {
+    pub path: String,
+    pub size_bytes: i64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ListResponse<T> {
+    data: Vec<T>,
+}
+
+pub async fn run(
+    command: FilesCommand,
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+) -> Result<()> {
+    match command {
+        FilesCommand::Versions {
+            agent,
+            session,
+            path,
+        } => versions(client, output, agent, session, path).await,
+        FilesCommand::Diff {
+            agent,
+            session,
+            path,
+            from,
+            to,
+        } => diff(client, output, agent, session, path, from, to).await,
+        FilesCommand::Restore {
+            agent,
+            session,
+            path,
+            version,
+            snapshot,
+            turn,
+        } => {
+            let request = RestoreRequest {
+                path: Some(path),
+                version,
+                snapshot_id: snapshot,
+                turn_id: turn,
+            };
+            restore(client, output, agent, session, request).await
+        }
+        FilesCommand::Push {
+            agent,
+            session,
+            source,
+            dest,
+            overwrite,
+        } => {
+            push(
+                client, output, quiet, agent, session, source, dest, overwrite,
+            )
+            .await
+        }
+        FilesCommand::Pull {
+            agent,
+            session,
+            target,
+            path,
+        } => pull(client, quiet, agent, session, target, path).await,
+    }
+}
+
+fn fs_path(agent_id: Uuid, session_id: Uuid, action: &str) -> String {
+    format!(
+        "/v1/agents/{}/sessions/{}/fs/_/{}",
+        agent_id, session_id, action
+    )
+}
+
+async fn versions(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    session_id: Uuid,
+    path: String,
+) -> Result<()> {
+    let response: ListResponse<FileVersion> = client
+        .post(
+            &fs_path(agent_id, session_id, "versions"),
+            &PathRequest { path: path.clone() },
+        )
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("No versions found for: {}", path),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        if response.data.is_empty() {
+            println!("No versions found");
+            return Ok(());
+        }
+
+        print_table_header(&[("VERSION", 8), ("SIZE", 10), ("STATUS", 8), ("CREATED", 30)]);
+
+        for version in &response.data {
+            let status = if version.is_deleted { "deleted" } else { "-" };
+            print_table_row(&[
+                (&version.version.to_string(), 8),
+                (&version.size_bytes.to_string(), 10),
+                (status, 8),
+                (&version.created_at, 30),
+            ]);
+        }
+    } else {
+        output.print_value(&response);
+    }
+
+    Ok(())
+}
+
+async fn diff(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    session_id: Uuid,
+    path: String,
+    from_version: Option<i32>,
+    to_version: Option<i32>,
+) -> Result<()> {
+    let request = DiffRequest {
+        path: path.clone(),
+        from_version,
+        to_version,
+    };
+
+    let diff: FileDiff = client
+        .post(&fs_path(agent_id, session_id, "diff"), &request)
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Version not found for: {}", path),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        if diff.is_binary {
+            println!(
+                "Binary file {} differs between v{} and v{}",
+                diff.path, diff.from_version, diff.to_version
+            );
+        } else if diff.diff.is_empty() {
+            println!(
+                "No changes between v{} and v{}",
+                diff.from_version, diff.to_version
+            );
+        } else {
+            print!("{}", diff.diff);
+        }
+    } else {
+        output.print_value(&diff);
+    }
+
+    Ok(())
+}
+
+async fn restore(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    session_id: Uuid,
+    request: RestoreRequest,
+) -> Result<()> {
+    let targets = [
+        request.version.is_some(),
+        request.snapshot_id.is_some(),
+        request.turn_id.is_some(),
+    ];
+    if targets.iter().filter(|set| **set).count() != 1 {
+        bail!("Specify exactly one of --version, --snapshot or --turn");
+    }
+
+    let file: RestoredFile = client
+        .post(&fs_path(agent_id, session_id, "restore"), &request)
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Restore target not found"),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        println!("Restored file: {}", file.path);
+        print_field("Size", &file.size_bytes.to_string());
+        print_field("Updated", &file.updated_at);
+    } else {
+        output.print_value(&file);
+    }
+
+    Ok(())
+}
+
+/// Archive format implied by a file name, if it names an archive
+fn archive_format(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some("tar.gz")
+    } else if name.ends_with(".zip") {
+        Some("zip")
+    } else {
+        None
+    }
+}
+
+/// Pack a directory as tar.gz. Symlinks are stored as links, which the
+/// server skips.
+fn pack_directory(dir: &Path) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    builder.follow_symlinks(false);
+    builder
+        .append_dir_all(".", dir)
+        .with_context(|| format!("Failed to pack {}", dir.display()))?;
+    Ok(builder.into_inner()?.finish()?)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn push(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+    source: PathBuf,
+    dest: String,
+    overwrite: bool,
+) -> Result<()> {
+    let (data, content_type) = if source.is_dir() {
+        (pack_directory(&source)?, "application/gzip")
+    } else {
+        let content_type = match archive_format(&source) {
+            Some("zip") => "application/zip",
+            Some(_) => "application/gzip",
+            None => bail!(
+                "{} is not a directory or a .tar.gz, .tgz or .zip file",
+                source.display()
+            ),
+        };
+        let data = std::fs::read(&source)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        (data, content_type)
+    };
+
+    let query = [("path", dest), ("overwrite", overwrite.to_string())];
+    let result: ImportResult = client
+        .post_bytes(
+            &fs_path(agent_id, session_id, "import"),
+            &query,
+            content_type,
+            data,
+        )
+        .await?;
+
+    if output.is_text() {
+        if !quiet {
+            println!(
+                "Imported {} files ({} bytes) into {}",
+                result.file_count, result.size_bytes, result.path
+            );
+        }
+    } else {
+        output.print_value(&result);
+    }
+
+    Ok(())
+}
+
+async fn pull(
+    client: &Client,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+    target: PathBuf,
+    path: String,
+) -> Result<()> {
+    let format = archive_format(&target);
+    let query = [
+        ("path", path.clone()),
+        ("format", format.unwrap_or("tar.gz").to_string()),
+    ];
+    let data = client
+        .get_bytes(&fs_path(agent_id, session_id, "export"), &query)
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Path not found: {}", path),
+            e => e.into(),
+        })?;
+
+    if format.is_some() {
+        std::fs::write(&target, &data)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        if !quiet {
+            println!("Saved {} ({} bytes)", target.display(), data.len());
+        }
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(data.as_slice()));
+    let mut count = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        // unpack_in refuses paths that would escape the target directory
+        if entry.unpack_in(&target)? && entry.header().entry_type().is_file() {
+            count += 1;
+        }
+    }
+    if !quiet {
+        println!("Extracted {} files into {}", count, target.display());
+    }
+
+    Ok(())
+}
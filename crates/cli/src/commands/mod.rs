@@ -1,4 +1,7 @@
 pub mod agents;
 pub mod capabilities;
 pub mod chat;
+pub mod files;
+pub mod schedules;
 pub mod sessions;
+pub mod snapshots;
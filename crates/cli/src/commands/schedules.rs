@@ -0,0 +1,558 @@
+// Agent schedule commands
+
+use crate::client::{Client, ClientError};
+use crate::commands::sessions::parse_variable;
+use crate::output::{print_field, print_table_header, print_table_row, OutputFormat};
+use anyhow::Result;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+const MISSED_RUN_POLICIES: [&str; 3] = ["skip", "run_once", "run_all"];
+const OVERLAP_POLICIES: [&str; 2] = ["skip", "allow"];
+
+#[derive(Subcommand)]
+pub enum SchedulesCommand {
+    /// Create a schedule
+    Create {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule name
+        #[arg(long)]
+        name: String,
+
+        /// Cron expression (minute hour day-of-month month day-of-week) or @daily etc.
+        #[arg(long)]
+        cron: String,
+
+        /// IANA timezone the expression is evaluated in
+        #[arg(long, default_value = "UTC")]
+        timezone: String,
+
+        /// Message posted for each run ({{variable}} placeholders allowed)
+        #[arg(long, short)]
+        message: String,
+
+        /// Post to this existing session instead of a fresh session per run
+        #[arg(long, short)]
+        session: Option<Uuid>,
+
+        /// Variable for sessions created by the schedule as NAME=VALUE (repeatable)
+        #[arg(long = "var", value_parser = parse_variable)]
+        vars: Vec<(String, String)>,
+
+        /// What to do with occurrences missed while the scheduler was down
+        #[arg(long, default_value = "run_once", value_parser = MISSED_RUN_POLICIES)]
+        missed: String,
+
+        /// What to do when the previous run is still in progress
+        #[arg(long, default_value = "skip", value_parser = OVERLAP_POLICIES)]
+        overlap: String,
+
+        /// Create the schedule disabled
+        #[arg(long)]
+        disabled: bool,
+    },
+
+    /// List schedules for an agent
+    List {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+    },
+
+    /// Get schedule by ID
+    Get {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+    },
+
+    /// Update a schedule
+    Update {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+
+        /// New name
+        #[arg(long)]
+        name: Option<String>,
+
+        /// New cron expression
+        #[arg(long)]
+        cron: Option<String>,
+
+        /// New timezone
+        #[arg(long)]
+        timezone: Option<String>,
+
+        /// New message
+        #[arg(long, short)]
+        message: Option<String>,
+
+        /// New missed-run policy
+        #[arg(long, value_parser = MISSED_RUN_POLICIES)]
+        missed: Option<String>,
+
+        /// New overlap policy
+        #[arg(long, value_parser = OVERLAP_POLICIES)]
+        overlap: Option<String>,
+    },
+
+    /// Enable a schedule
+    Enable {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+    },
+
+    /// Disable a schedule
+    Disable {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+    },
+
+    /// Delete a schedule and its run history
+    Delete {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+    },
+
+    /// Show run history, newest first
+    Runs {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+
+        /// Maximum number of runs
+        #[arg(long, default_value = "20")]
+        limit: i64,
+    },
+
+    /// Run a schedule now
+    Trigger {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Schedule ID
+        schedule_id: Uuid,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct CreateScheduleRequest {
+    name: String,
+    cron: String,
+    timezone: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    session_variables: BTreeMap<String, String>,
+    missed_run_policy: String,
+    overlap_policy: String,
+    enabled: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct UpdateScheduleRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cron: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timezone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missed_run_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overlap_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+}
+
+/// Schedule response from API
+#[derive(Debug, Serialize, Deserialize)]
+struct Schedule {
+    id: Uuid,
+    agent_id: Uuid,
+    name: String,
+    cron: String,
+    timezone: String,
+    message: String,
+    #[serde(default)]
+    session_id: Option<Uuid>,
+    #[serde(default)]
+    session_variables: BTreeMap<String, String>,
+    missed_run_policy: String,
+    overlap_policy: String,
+    enabled: bool,
+    #[serde(default)]
+    next_run_at: Option<String>,
+    #[serde(default)]
+    last_run_at: Option<String>,
+    created_at: String,
+}
+
+/// Schedule run response from API
+#[derive(Debug, Serialize, Deserialize)]
+struct ScheduleRun {
+    id: Uuid,
+    scheduled_for: String,
+    status: String,
+    #[serde(default)]
+    session_id: Option<Uuid>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    finished_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ListResponse<T> {
+    data: Vec<T>,
+}
+
+pub async fn run(
+    command: SchedulesCommand,
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+) -> Result<()> {
+    match command {
+        SchedulesCommand::Create {
+            agent,
+            name,
+            cron,
+            timezone,
+            message,
+            session,
+            vars,
+            missed,
+            overlap,
+            disabled,
+        } => {
+            let request = CreateScheduleRequest {
+                name,
+                cron,
+                timezone,
+                message,
+                session_id: session,
+                session_variables: vars.into_iter().collect(),
+                missed_run_policy: missed,
+                overlap_policy: overlap,
+                enabled: !disabled,
+            };
+            create(client, output, quiet, agent, request).await
+        }
+        SchedulesCommand::List { agent } => list(client, output, agent).await,
+        SchedulesCommand::Get { agent, schedule_id } => {
+            get(client, output, agent, schedule_id).await
+        }
+        SchedulesCommand::Update {
+            agent,
+            schedule_id,
+            name,
+            cron,
+            timezone,
+            message,
+            missed,
+            overlap,
+        } => {
+            let request = UpdateScheduleRequest {
+                name,
+                cron,
+                timezone,
+                message,
+                missed_run_policy: missed,
+                overlap_policy: overlap,
+                enabled: None,
+            };
+            update(client, output, quiet, agent, schedule_id, request).await
+        }
+        SchedulesCommand::Enable { agent, schedule_id } => {
+            let request = UpdateScheduleRequest {
+                enabled: Some(true),
+                ..Default::default()
+            };
+            update(client, output, quiet, agent, schedule_id, request).await
+        }
+        SchedulesCommand::Disable { agent, schedule_id } => {
+            let request = UpdateScheduleRequest {
+                enabled: Some(false),
+                ..Default::default()
+            };
+            update(client, output, quiet, agent, schedule_id, request).await
+        }
+        SchedulesCommand::Delete { agent, schedule_id } => {
+            delete(client, quiet, agent, schedule_id).await
+        }
+        SchedulesCommand::Runs {
+            agent,
+            schedule_id,
+            limit,
+        } => runs(client, output, agent, schedule_id, limit).await,
+        SchedulesCommand::Trigger { agent, schedule_id } => {
+            trigger(client, output, quiet, agent, schedule_id).await
+        }
+    }
+}
+
+fn schedule_path(agent_id: Uuid, schedule_id: Uuid) -> String {
+    format!("/v1/agents/{}/schedules/{}", agent_id, schedule_id)
+}
+
+fn not_found(schedule_id: Uuid) -> impl FnOnce(ClientError) -> anyhow::Error {
+    move |e| match e {
+        ClientError::NotFound => anyhow::anyhow!("Schedule not found: {}", schedule_id),
+        e => e.into(),
+    }
+}
+
+async fn create(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    request: CreateScheduleRequest,
+) -> Result<()> {
+    let schedule: Schedule = client
+        .post(&format!("/v1/agents/{}/schedules", agent_id), &request)
+        .await?;
+
+    if output.is_text() {
+        if quiet {
+            println!("{}", schedule.id);
+        } else {
+            println!("Created schedule: {}", schedule.id);
+            print_summary(&schedule);
+        }
+    } else {
+        output.print_value(&schedule);
+    }
+
+    Ok(())
+}
+
+async fn list(client: &Client, output: OutputFormat, agent_id: Uuid) -> Result<()> {
+    let response: ListResponse<Schedule> = client
+        .get(&format!("/v1/agents/{}/schedules", agent_id))
+        .await?;
+
+    if output.is_text() {
+        if response.data.is_empty() {
+            println!("No schedules found");
+            return Ok(());
+        }
+
+        print_table_header(&[
+            ("ID", 36),
+            ("NAME", 25),
+            ("CRON", 18),
+            ("ENABLED", 8),
+            ("NEXT RUN", 25),
+        ]);
+
+        for schedule in &response.data {
+            print_table_row(&[
+                (&schedule.id.to_string(), 36),
+                (&schedule.name, 25),
+                (&schedule.cron, 18),
+                (if schedule.enabled { "yes" } else { "no" }, 8),
+                (schedule.next_run_at.as_deref().unwrap_or("-"), 25),
+            ]);
+        }
+    } else {
+        output.print_value(&response);
+    }
+
+    Ok(())
+}
+
+async fn get(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    schedule_id: Uuid,
+) -> Result<()> {
+    let schedule: Schedule = client
+        .get(&schedule_path(agent_id, schedule_id))
+        .await
+        .map_err(not_found(schedule_id))?;
+
+    if output.is_text() {
+        print_field("ID", &schedule.id.to_string());
+        print_field("Agent", &schedule.agent_id.to_string());
+        print_summary(&schedule);
+        print_field("Message", &schedule.message);
+        print_field("Missed runs", &schedule.missed_run_policy);
+        print_field("Overlap", &schedule.overlap_policy);
+        for (name, value) in &schedule.session_variables {
+            print_field(&format!("Var {}", name), value);
+        }
+        if let Some(last_run_at) = &schedule.last_run_at {
+            print_field("Last run", last_run_at);
+        }
+        print_field("Created", &schedule.created_at);
+    } else {
+        output.print_value(&schedule);
+    }
+
+    Ok(())
+}
+
+async fn update(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    schedule_id: Uuid,
+    request: UpdateScheduleRequest,
+) -> Result<()> {
+    let schedule: Schedule = client
+        .patch(&schedule_path(agent_id, schedule_id), &request)
+        .await
+        .map_err(not_found(schedule_id))?;
+
+    if output.is_text() {
+        if !quiet {
+            println!("Updated schedule: {}", schedule.id);
+            print_summary(&schedule);
+        }
+    } else {
+        output.print_value(&schedule);
+    }
+
+    Ok(())
+}
+
+async fn delete(client: &Client, quiet: bool, agent_id: Uuid, schedule_id: Uuid) -> Result<()> {
+    client
+        .delete(&schedule_path(agent_id, schedule_id))
+        .await
+        .map_err(not_found(schedule_id))?;
+
+    if !quiet {
+        println!("Deleted schedule: {}", schedule_id);
+    }
+
+    Ok(())
+}
+
+async fn runs(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    schedule_id: Uuid,
+    limit: i64,
+) -> Result<()> {
+    let response: ListResponse<ScheduleRun> = client
+        .get(&format!(
+            "{}/runs?limit={}",
+            schedule_path(agent_id, schedule_id),
+            limit
+        ))
+        .await
+        .map_err(not_found(schedule_id))?;
+
+    if output.is_text() {
+        if response.data.is_empty() {
+            println!("No runs yet");
+            return Ok(());
+        }
+
+        print_table_header(&[
+            ("SCHEDULED FOR", 25),
+            ("STATUS", 10),
+            ("SESSION", 36),
+            ("ERROR", 40),
+        ]);
+
+        for run in &response.data {
+            let session = run.session_id.map(|id| id.to_string());
+            print_table_row(&[
+                (&run.scheduled_for, 25),
+                (&run.status, 10),
+                (session.as_deref().unwrap_or("-"), 36),
+                (run.error.as_deref().unwrap_or(""), 40),
+            ]);
+        }
+    } else {
+        output.print_value(&response);
+    }
+
+    Ok(())
+}
+
+async fn trigger(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    schedule_id: Uuid,
+) -> Result<()> {
+    let run: ScheduleRun = client
+        .post(
+            &format!("{}/trigger", schedule_path(agent_id, schedule_id)),
+            &serde_json::json!({}),
+        )
+        .await
+        .map_err(not_found(schedule_id))?;
+
+    if output.is_text() {
+        if quiet {
+            println!("{}", run.id);
+        } else {
+            println!("Triggered run: {}", run.id);
+            print_field("Status", &run.status);
+        }
+    } else {
+        output.print_value(&run);
+    }
+
+    Ok(())
+}
+
+fn print_summary(schedule: &Schedule) {
+    print_field("Name", &schedule.name);
+    print_field(
+        "Cron",
+        &format!("{} ({})", schedule.cron, schedule.timezone),
+    );
+    print_field(
+        "Target",
+        &schedule
+            .session_id
+            .map(|id| format!("session {}", id))
+            .unwrap_or_else(|| "new session per run".to_string()),
+    );
+    print_field("Enabled", if schedule.enabled { "yes" } else { "no" });
+    if let Some(next_run_at) = &schedule.next_run_at {
+        print_field("Next run", next_run_at);
+    }
+}
@@ -1,10 +1,12 @@
 // Session management commands
 
 use crate::client::{Client, ClientError};
+use crate::commands::files;
 use crate::output::{print_field, print_table_header, print_table_row, OutputFormat};
 use anyhow::Result;
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 #[derive(Subcommand)]
@@ -26,6 +28,14 @@ pub enum SessionsCommand {
         /// Tags (repeatable)
         #[arg(long, short)]
         tag: Vec<String>,
+
+        /// Use the agent's latest revision on every turn instead of pinning the current one
+        #[arg(long)]
+        follow_latest: bool,
+
+        /// System prompt template variable as NAME=VALUE (repeatable)
+        #[arg(long = "var", value_parser = parse_variable)]
+        vars: Vec<(String, String)>,
     },
 
     /// List sessions for an agent
@@ -45,6 +55,83 @@ pub enum SessionsCommand {
         #[arg(long, short)]
         session: Uuid,
     },
+
+    /// Fork a session into a new one, optionally at an earlier turn or message
+    Fork {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID to fork
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// Fork after this turn
+        #[arg(long, conflicts_with = "message")]
+        turn: Option<Uuid>,
+
+        /// Fork after this message
+        #[arg(long)]
+        message: Option<Uuid>,
+
+        /// Also copy the session's files as of the fork point
+        #[arg(long)]
+        files: bool,
+
+        /// Title for the new session (defaults to the parent's)
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Model ID for the new session (defaults to the parent's)
+        #[arg(long)]
+        model: Option<Uuid>,
+    },
+
+    /// List sessions forked from a session
+    Branches {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+    },
+
+    /// Rerun the turn for the latest user message
+    Regenerate {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+    },
+
+    /// Replace a user message and rerun the conversation from there
+    Edit {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// User message ID to replace
+        #[arg(long, short)]
+        message: Uuid,
+
+        /// New message text
+        text: String,
+    },
+
+    /// Manage session files: versions, import and export
+    Files {
+        #[command(subcommand)]
+        command: files::FilesCommand,
+    },
 }
 
 /// Request to create a session
@@ -56,6 +143,44 @@ struct CreateSessionRequest {
     tags: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     model_id: Option<Uuid>,
+    follow_latest_revision: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: BTreeMap<String, String>,
+}
+
+/// Request to fork a session
+#[derive(Debug, Serialize)]
+struct ForkSessionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turn_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_id: Option<Uuid>,
+    include_files: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model_id: Option<Uuid>,
+}
+
+/// Request to replace a user message
+#[derive(Debug, Serialize)]
+struct EditMessageRequest {
+    message: InputMessage,
+}
+
+#[derive(Debug, Serialize)]
+struct InputMessage {
+    role: String,
+    content: Vec<serde_json::Value>,
+}
+
+/// Message response from API
+#[derive(Debug, Serialize, Deserialize)]
+struct Message {
+    id: Uuid,
+    sequence: i32,
+    #[serde(flatten)]
+    rest: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Session response from API
@@ -75,6 +200,14 @@ pub struct Session {
     pub started_at: Option<String>,
     #[serde(default)]
     pub finished_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_at_event_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_revision: Option<i32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,6 +215,14 @@ struct ListResponse<T> {
     data: Vec<T>,
 }
 
+/// Parse a `--var` value of the form `NAME=VALUE`
+pub(crate) fn parse_variable(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", s))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
 pub async fn run(
     command: SessionsCommand,
     client: &Client,
@@ -94,12 +235,59 @@ pub async fn run(
             title,
             model,
             tag,
-        } => create(client, output, quiet, agent, title, model, tag).await,
+            follow_latest,
+            vars,
+        } => {
+            create(
+                client,
+                output,
+                quiet,
+                agent,
+                title,
+                model,
+                tag,
+                follow_latest,
+                vars.into_iter().collect(),
+            )
+            .await
+        }
         SessionsCommand::List { agent } => list(client, output, agent).await,
         SessionsCommand::Get { agent, session } => get(client, output, agent, session).await,
+        SessionsCommand::Fork {
+            agent,
+            session,
+            turn,
+            message,
+            files,
+            title,
+            model,
+        } => {
+            let request = ForkSessionRequest {
+                turn_id: turn,
+                message_id: message,
+                include_files: files,
+                title,
+                model_id: model,
+            };
+            fork(client, output, quiet, agent, session, request).await
+        }
+        SessionsCommand::Branches { agent, session } => {
+            branches(client, output, agent, session).await
+        }
+        SessionsCommand::Regenerate { agent, session } => {
+            regenerate(client, output, quiet, agent, session).await
+        }
+        SessionsCommand::Edit {
+            agent,
+            session,
+            message,
+            text,
+        } => edit(client, output, quiet, agent, session, message, text).await,
+        SessionsCommand::Files { command } => files::run(command, client, output, quiet).await,
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn create(
     client: &Client,
     output: OutputFormat,
@@ -108,11 +296,15 @@ async fn create(
     title: Option<String>,
     model_id: Option<Uuid>,
     tags: Vec<String>,
+    follow_latest_revision: bool,
+    variables: BTreeMap<String, String>,
 ) -> Result<()> {
     let request = CreateSessionRequest {
         title,
         tags,
         model_id,
+        follow_latest_revision,
+        variables,
     };
 
     let session: Session = client
@@ -126,6 +318,9 @@ async fn create(
             println!("Created session: {}", session.id);
             print_field("Agent", &session.agent_id.to_string());
             print_field("Status", &session.status);
+            if let Some(revision) = session.agent_revision {
+                print_field("Agent revision", &revision.to_string());
+            }
         }
     } else {
         output.print_value(&session);
@@ -194,9 +389,170 @@ async fn get(
         if let Some(finished) = &session.finished_at {
             print_field("Finished", finished);
         }
+        if let Some(parent) = &session.parent_session_id {
+            print_field("Forked from", &parent.to_string());
+        }
+        for (name, value) in &session.variables {
+            print_field(&format!("Var {}", name), value);
+        }
     } else {
         output.print_value(&session);
     }
 
     Ok(())
 }
+
+async fn fork(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+    request: ForkSessionRequest,
+) -> Result<()> {
+    let session: Session = client
+        .post(
+            &format!("/v1/agents/{}/sessions/{}/fork", agent_id, session_id),
+            &request,
+        )
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => {
+                anyhow::anyhow!(
+                    "Session, turn or message not found in session {}",
+                    session_id
+                )
+            }
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        if quiet {
+            println!("{}", session.id);
+        } else {
+            println!("Forked session: {}", session.id);
+            print_field("Parent", &session_id.to_string());
+            print_field("Status", &session.status);
+        }
+    } else {
+        output.print_value(&session);
+    }
+
+    Ok(())
+}
+
+async fn branches(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    session_id: Uuid,
+) -> Result<()> {
+    let response: ListResponse<Session> = client
+        .get(&format!(
+            "/v1/agents/{}/sessions/{}/branches",
+            agent_id, session_id
+        ))
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Session not found: {}", session_id),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        if response.data.is_empty() {
+            println!("No branches found");
+            return Ok(());
+        }
+
+        print_table_header(&[("ID", 36), ("TITLE", 25), ("STATUS", 10), ("CREATED", 20)]);
+
+        for session in &response.data {
+            let title = session.title.as_deref().unwrap_or("-");
+            print_table_row(&[
+                (&session.id.to_string(), 36),
+                (title, 25),
+                (&session.status, 10),
+                (&session.created_at, 20),
+            ]);
+        }
+    } else {
+        output.print_value(&response);
+    }
+
+    Ok(())
+}
+
+async fn regenerate(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+) -> Result<()> {
+    let message: Message = client
+        .post(
+            &format!("/v1/agents/{}/sessions/{}/regenerate", agent_id, session_id),
+            &serde_json::json!({}),
+        )
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Session not found: {}", session_id),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        if quiet {
+            println!("{}", message.id);
+        } else {
+            println!("Regenerating response to message: {}", message.id);
+        }
+    } else {
+        output.print_value(&message);
+    }
+
+    Ok(())
+}
+
+async fn edit(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+    message_id: Uuid,
+    text: String,
+) -> Result<()> {
+    let request = EditMessageRequest {
+        message: InputMessage {
+            role: "user".to_string(),
+            content: vec![serde_json::json!({ "type": "text", "text": text })],
+        },
+    };
+
+    let message: Message = client
+        .post(
+            &format!(
+                "/v1/agents/{}/sessions/{}/messages/{}/edit",
+                agent_id, session_id, message_id
+            ),
+            &request,
+        )
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Message not found: {}", message_id),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        if quiet {
+            println!("{}", message.id);
+        } else {
+            println!("Replaced message {} with: {}", message_id, message.id);
+            print_field("Sequence", &message.sequence.to_string());
+        }
+    } else {
+        output.print_value(&message);
+    }
+
+    Ok(())
+}
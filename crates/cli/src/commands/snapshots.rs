@@ -0,0 +1,263 @@
+// Session filesystem snapshot commands
+
+use crate::client::{Client, ClientError};
+use crate::commands::files::RestoreRequest;
+use crate::output::{print_field, print_table_header, print_table_row, OutputFormat};
+use anyhow::{bail, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Subcommand)]
+pub enum SnapshotsCommand {
+    /// List snapshots of a session's files
+    List {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+    },
+
+    /// Snapshot the current file tree
+    Create {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// Snapshot name
+        name: String,
+    },
+
+    /// Delete a snapshot
+    Delete {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// Snapshot ID
+        snapshot_id: Uuid,
+    },
+
+    /// Restore the whole file tree to a snapshot or turn
+    Restore {
+        /// Agent ID
+        #[arg(long, short)]
+        agent: Uuid,
+
+        /// Session ID
+        #[arg(long, short)]
+        session: Uuid,
+
+        /// Snapshot ID
+        #[arg(long)]
+        snapshot: Option<Uuid>,
+
+        /// Turn ID
+        #[arg(long)]
+        turn: Option<Uuid>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct CreateSnapshotRequest {
+    name: String,
+}
+
+/// Snapshot response from API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: String,
+    #[serde(default)]
+    pub turn_id: Option<Uuid>,
+    #[serde(default)]
+    pub turn_number: Option<i32>,
+    pub file_count: i32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ListResponse<T> {
+    data: Vec<T>,
+}
+
+pub async fn run(
+    command: SnapshotsCommand,
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+) -> Result<()> {
+    match command {
+        SnapshotsCommand::List { agent, session } => list(client, output, agent, session).await,
+        SnapshotsCommand::Create {
+            agent,
+            session,
+            name,
+        } => create(client, output, quiet, agent, session, name).await,
+        SnapshotsCommand::Delete {
+            agent,
+            session,
+            snapshot_id,
+        } => delete(client, quiet, agent, session, snapshot_id).await,
+        SnapshotsCommand::Restore {
+            agent,
+            session,
+            snapshot,
+            turn,
+        } => restore(client, output, agent, session, snapshot, turn).await,
+    }
+}
+
+fn snapshots_path(agent_id: Uuid, session_id: Uuid) -> String {
+    format!("/v1/agents/{}/sessions/{}/snapshots", agent_id, session_id)
+}
+
+async fn list(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    session_id: Uuid,
+) -> Result<()> {
+    let response: ListResponse<Snapshot> =
+        client.get(&snapshots_path(agent_id, session_id)).await?;
+
+    if output.is_text() {
+        if response.data.is_empty() {
+            println!("No snapshots found");
+            return Ok(());
+        }
+
+        print_table_header(&[
+            ("ID", 36),
+            ("NAME", 25),
+            ("KIND", 10),
+            ("FILES", 6),
+            ("CREATED", 20),
+        ]);
+
+        for snapshot in &response.data {
+            print_table_row(&[
+                (&snapshot.id.to_string(), 36),
+                (&snapshot.name, 25),
+                (&snapshot.kind, 10),
+                (&snapshot.file_count.to_string(), 6),
+                (&snapshot.created_at, 20),
+            ]);
+        }
+    } else {
+        output.print_value(&response);
+    }
+
+    Ok(())
+}
+
+async fn create(
+    client: &Client,
+    output: OutputFormat,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+    name: String,
+) -> Result<()> {
+    let snapshot: Snapshot = client
+        .post(
+            &snapshots_path(agent_id, session_id),
+            &CreateSnapshotRequest { name },
+        )
+        .await?;
+
+    if output.is_text() {
+        if quiet {
+            println!("{}", snapshot.id);
+        } else {
+            println!("Created snapshot: {}", snapshot.id);
+            print_field("Name", &snapshot.name);
+            print_field("Files", &snapshot.file_count.to_string());
+        }
+    } else {
+        output.print_value(&snapshot);
+    }
+
+    Ok(())
+}
+
+async fn delete(
+    client: &Client,
+    quiet: bool,
+    agent_id: Uuid,
+    session_id: Uuid,
+    snapshot_id: Uuid,
+) -> Result<()> {
+    client
+        .delete(&format!(
+            "{}/{}",
+            snapshots_path(agent_id, session_id),
+            snapshot_id
+        ))
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Snapshot not found: {}", snapshot_id),
+            e => e.into(),
+        })?;
+
+    if !quiet {
+        println!("Deleted snapshot: {}", snapshot_id);
+    }
+
+    Ok(())
+}
+
+async fn restore(
+    client: &Client,
+    output: OutputFormat,
+    agent_id: Uuid,
+    session_id: Uuid,
+    snapshot_id: Option<Uuid>,
+    turn_id: Option<Uuid>,
+) -> Result<()> {
+    if snapshot_id.is_some() == turn_id.is_some() {
+        bail!("Specify exactly one of --snapshot or --turn");
+    }
+
+    let request = RestoreRequest {
+        path: None,
+        version: None,
+        snapshot_id,
+        turn_id,
+    };
+
+    let snapshot: Snapshot = client
+        .post(
+            &format!(
+                "/v1/agents/{}/sessions/{}/fs/_/restore",
+                agent_id, session_id
+            ),
+            &request,
+        )
+        .await
+        .map_err(|e| match e {
+            ClientError::NotFound => anyhow::anyhow!("Snapshot not found"),
+            e => e.into(),
+        })?;
+
+    if output.is_text() {
+        println!("Restored snapshot: {}", snapshot.name);
+        print_field("Files", &snapshot.file_count.to_string());
+    } else {
+        output.print_value(&snapshot);
+    }
+
+    Ok(())
+}
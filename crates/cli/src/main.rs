@@ -56,6 +56,18 @@ pub enum Commands {
         command: commands::sessions::SessionsCommand,
     },
 
+    /// Manage agent schedules (recurring runs)
+    Schedules {
+        #[command(subcommand)]
+        command: commands::schedules::SchedulesCommand,
+    },
+
+    /// Manage session filesystem snapshots
+    Snapshots {
+        #[command(subcommand)]
+        command: commands::snapshots::SnapshotsCommand,
+    },
+
     /// Send a message and stream the response
     Chat {
         /// Message text to send
@@ -95,6 +107,12 @@ async fn main() -> anyhow::Result<()> {
         Commands::Sessions { command } => {
             commands::sessions::run(command, &client, output_format, cli.quiet).await
         }
+        Commands::Schedules { command } => {
+            commands::schedules::run(command, &client, output_format, cli.quiet).await
+        }
+        Commands::Snapshots { command } => {
+            commands::snapshots::run(command, &client, output_format, cli.quiet).await
+        }
         Commands::Chat {
             message,
             session,
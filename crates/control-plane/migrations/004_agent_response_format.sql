@@ -0,0 +1,6 @@
+-- Agent structured output
+--
+-- Optional JSON-schema response format applied to an agent's final responses.
+-- Shape: {"name": "...", "schema": {...}, "strict": true}
+
+ALTER TABLE agents ADD COLUMN response_format JSONB;
@@ -0,0 +1,7 @@
+-- Agent tool-calling settings
+--
+-- parallel_tool_calls = FALSE declares the agent's tools order-dependent:
+-- providers are asked for one tool call at a time and calls run sequentially.
+-- NULL keeps the provider default.
+
+ALTER TABLE agents ADD COLUMN parallel_tool_calls BOOLEAN;
@@ -0,0 +1,8 @@
+-- MCP servers configured per agent
+--
+-- mcp_servers holds the server configs (name, transport, credential names).
+-- Credential values are stored separately as one encrypted JSON map of
+-- server name -> {credential name -> value} (envelope encryption, see specs/encryption.md).
+
+ALTER TABLE agents ADD COLUMN mcp_servers JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE agents ADD COLUMN mcp_credentials_encrypted BYTEA;
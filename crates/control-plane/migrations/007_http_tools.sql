@@ -0,0 +1,22 @@
+-- User-defined HTTP tools
+--
+-- Tools defined through /v1/tools. Each tool is attached to agents as the
+-- capability `custom:<name>` (stored in agent_capabilities like built-ins).
+
+CREATE TABLE http_tools (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    -- Tool name shown to the model; capability IDs are limited to 50 characters
+    name VARCHAR(40) NOT NULL UNIQUE,
+    description TEXT NOT NULL,
+    -- JSON schema of the tool arguments
+    parameters JSONB NOT NULL,
+    -- HttpToolEndpoint: method, url/header/body templates, auth scheme
+    endpoint JSONB NOT NULL,
+    -- Encrypted auth secret (AES-256-GCM, see specs/encryption.md)
+    secret_encrypted BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_http_tools_updated_at BEFORE UPDATE ON http_tools
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,6 @@
+-- Per-agent capability configuration
+--
+-- Config object passed to a capability when building the agent's tools and
+-- system prompt. Validated against the schema the capability publishes.
+
+ALTER TABLE agent_capabilities ADD COLUMN config JSONB NOT NULL DEFAULT '{}';
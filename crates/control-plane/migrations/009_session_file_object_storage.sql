@@ -0,0 +1,15 @@
+-- Object storage for session file content
+--
+-- Files above the inline threshold keep only metadata in Postgres; the bytes
+-- live in the configured object store under storage_key. NULL means the
+-- content (if any) is inline in the content column.
+
+ALTER TABLE session_files ADD COLUMN storage_key TEXT;
+
+ALTER TABLE session_files ADD CONSTRAINT session_files_single_content_location CHECK (
+    content IS NULL OR storage_key IS NULL
+);
+
+ALTER TABLE session_files ADD CONSTRAINT session_files_directory_no_storage_key CHECK (
+    NOT is_directory OR storage_key IS NULL
+);
@@ -0,0 +1,119 @@
+-- Session file version history and snapshots
+--
+-- Every change to a file's content in session_files is recorded in
+-- session_file_versions by trigger, so all write paths (API, tools, copy,
+-- move, delete) are versioned. Deletes and the old side of a move are
+-- recorded as tombstones. Versions share object storage keys with the live
+-- row, so objects are only released when the session itself is deleted.
+--
+-- Snapshots capture the whole tree as a list of (path -> version) entries.
+
+CREATE TABLE session_file_versions (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+
+    -- Per-path version number, starting at 1
+    version INTEGER NOT NULL,
+
+    -- Content at this version (same layout as session_files)
+    content BYTEA,
+    storage_key TEXT,
+    size_bytes BIGINT NOT NULL DEFAULT 0,
+
+    -- Tombstone: the file was deleted or moved away at this version
+    is_deleted BOOLEAN NOT NULL DEFAULT FALSE,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT session_file_versions_unique UNIQUE (session_id, path, version),
+    CONSTRAINT session_file_versions_single_content_location CHECK (
+        content IS NULL OR storage_key IS NULL
+    )
+);
+
+-- For session cleanup (object storage keys)
+CREATE INDEX idx_session_file_versions_storage_key ON session_file_versions(session_id)
+    WHERE storage_key IS NOT NULL;
+
+-- Next version number for a path
+CREATE OR REPLACE FUNCTION next_session_file_version(p_session_id UUID, p_path TEXT) RETURNS INTEGER AS $$
+    SELECT COALESCE(MAX(version), 0) + 1
+    FROM session_file_versions
+    WHERE session_id = p_session_id AND path = p_path;
+$$ LANGUAGE sql;
+
+CREATE OR REPLACE FUNCTION record_session_file_version() RETURNS TRIGGER AS $$
+BEGIN
+    -- Tombstone for deletes and for the source path of a move
+    IF TG_OP = 'DELETE' OR (TG_OP = 'UPDATE' AND NEW.path <> OLD.path) THEN
+        -- Session deletes cascade here after the session row is gone
+        IF NOT OLD.is_directory AND EXISTS (SELECT 1 FROM sessions WHERE id = OLD.session_id) THEN
+            INSERT INTO session_file_versions (session_id, path, version, is_deleted)
+            VALUES (OLD.session_id, OLD.path, next_session_file_version(OLD.session_id, OLD.path), TRUE);
+        END IF;
+    END IF;
+
+    IF TG_OP = 'DELETE' OR NEW.is_directory THEN
+        RETURN NULL;
+    END IF;
+
+    IF TG_OP = 'INSERT'
+        OR NEW.path <> OLD.path
+        OR NEW.content IS DISTINCT FROM OLD.content
+        OR NEW.storage_key IS DISTINCT FROM OLD.storage_key
+    THEN
+        INSERT INTO session_file_versions (session_id, path, version, content, storage_key, size_bytes)
+        VALUES (NEW.session_id, NEW.path, next_session_file_version(NEW.session_id, NEW.path),
+                NEW.content, NEW.storage_key, NEW.size_bytes);
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_session_file_version
+    AFTER INSERT OR UPDATE OR DELETE ON session_files
+    FOR EACH ROW EXECUTE FUNCTION record_session_file_version();
+
+-- Existing files start their history at version 1
+INSERT INTO session_file_versions (session_id, path, version, content, storage_key, size_bytes, created_at)
+SELECT session_id, path, 1, content, storage_key, size_bytes, updated_at
+FROM session_files
+WHERE NOT is_directory;
+
+-- Named snapshots of a session's file tree
+CREATE TABLE session_snapshots (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+
+    -- manual, turn_start or turn_end
+    kind VARCHAR(20) NOT NULL DEFAULT 'manual',
+
+    -- Turn boundary for automatic snapshots
+    turn_id UUID,
+    turn_number INTEGER,
+
+    file_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT session_snapshots_name_unique UNIQUE (session_id, name),
+    CONSTRAINT session_snapshots_kind_check CHECK (kind IN ('manual', 'turn_start', 'turn_end'))
+);
+
+-- Restore to a turn
+CREATE INDEX idx_session_snapshots_turn ON session_snapshots(session_id, turn_id)
+    WHERE turn_id IS NOT NULL;
+
+CREATE TABLE session_snapshot_entries (
+    snapshot_id UUID NOT NULL REFERENCES session_snapshots(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    is_directory BOOLEAN NOT NULL DEFAULT FALSE,
+    is_readonly BOOLEAN NOT NULL DEFAULT FALSE,
+
+    -- File content at snapshot time (NULL for directories)
+    version_id UUID REFERENCES session_file_versions(id) ON DELETE CASCADE,
+
+    PRIMARY KEY (snapshot_id, path)
+);
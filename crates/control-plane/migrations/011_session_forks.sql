@@ -0,0 +1,15 @@
+-- Session forks
+--
+-- A fork is a new session seeded with a copy of another session's events up
+-- to a chosen turn or message (and optionally its files). The parent link and
+-- the last copied event record where the branch was taken.
+
+ALTER TABLE sessions
+    ADD COLUMN parent_session_id UUID REFERENCES sessions(id) ON DELETE SET NULL,
+    ADD COLUMN forked_at_event_id UUID REFERENCES events(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_sessions_parent_session_id ON sessions(parent_session_id)
+WHERE parent_session_id IS NOT NULL;
+
+COMMENT ON COLUMN sessions.parent_session_id IS 'Session this one was forked from';
+COMMENT ON COLUMN sessions.forked_at_event_id IS 'Last parent event copied into this fork';
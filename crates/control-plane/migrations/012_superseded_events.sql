@@ -0,0 +1,9 @@
+-- Superseded events
+--
+-- Regenerating a reply or editing a user message reruns the conversation from
+-- that point. The events it replaces are kept for audit but marked superseded,
+-- and are skipped when listing events or rebuilding message history.
+
+ALTER TABLE events ADD COLUMN superseded_at TIMESTAMPTZ;
+
+COMMENT ON COLUMN events.superseded_at IS 'When the event was replaced by a regenerated or edited turn';
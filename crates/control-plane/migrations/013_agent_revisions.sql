@@ -0,0 +1,49 @@
+-- Agent revisions
+--
+-- Every change to an agent's configuration is recorded as an immutable
+-- revision. `agents` keeps the current configuration and its revision number;
+-- `agent_revisions` keeps a snapshot of each one, including the capability
+-- chain. Sessions pin the revision they were created with, or follow the
+-- latest one when `agent_revision` is NULL.
+
+ALTER TABLE agents ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+
+CREATE TABLE agent_revisions (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    agent_id UUID NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    system_prompt TEXT NOT NULL,
+    default_model_id UUID,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    response_format JSONB,
+    parallel_tool_calls BOOLEAN,
+    mcp_servers JSONB NOT NULL DEFAULT '[]',
+    mcp_credentials_encrypted BYTEA,
+    -- Ordered capability chain: [{"capability_id": "...", "config": {...}}]
+    capabilities JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(agent_id, revision)
+);
+
+-- Existing agents start at revision 1
+INSERT INTO agent_revisions (
+    agent_id, revision, name, description, system_prompt, default_model_id, tags,
+    response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, capabilities
+)
+SELECT
+    a.id, 1, a.name, a.description, a.system_prompt, a.default_model_id, a.tags,
+    a.response_format, a.parallel_tool_calls, a.mcp_servers, a.mcp_credentials_encrypted,
+    COALESCE(
+        (SELECT jsonb_agg(jsonb_build_object('capability_id', c.capability_id, 'config', c.config) ORDER BY c.position)
+         FROM agent_capabilities c
+         WHERE c.agent_id = a.id),
+        '[]'::jsonb
+    )
+FROM agents a;
+
+ALTER TABLE sessions ADD COLUMN agent_revision INTEGER;
+
+COMMENT ON COLUMN agents.revision IS 'Current configuration revision';
+COMMENT ON COLUMN sessions.agent_revision IS 'Pinned agent revision (NULL follows the latest revision)';
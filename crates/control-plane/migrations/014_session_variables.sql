@@ -0,0 +1,8 @@
+-- Session template variables
+--
+-- Agent system prompts may reference `{{variable}}` placeholders. Values are
+-- provided when a session is created and rendered into the prompt each turn.
+
+ALTER TABLE sessions ADD COLUMN variables JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+COMMENT ON COLUMN sessions.variables IS 'System prompt template variables (JSON object of strings)';
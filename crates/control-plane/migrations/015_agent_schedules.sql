@@ -0,0 +1,59 @@
+-- Agent schedules
+--
+-- A schedule runs an agent on a cron expression by posting a message, either
+-- to a fresh session each time or to an existing session. Every due
+-- occurrence is recorded in `schedule_runs`; a run links to the session and
+-- message whose turn workflow executes it.
+
+CREATE TABLE agent_schedules (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    agent_id UUID NOT NULL REFERENCES agents(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    cron VARCHAR(255) NOT NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    message TEXT NOT NULL,
+    session_id UUID REFERENCES sessions(id) ON DELETE CASCADE,
+    session_variables JSONB NOT NULL DEFAULT '{}'::jsonb,
+    missed_run_policy VARCHAR(16) NOT NULL DEFAULT 'run_once',
+    overlap_policy VARCHAR(16) NOT NULL DEFAULT 'skip',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT agent_schedules_missed_run_policy_check
+        CHECK (missed_run_policy IN ('skip', 'run_once', 'run_all')),
+    CONSTRAINT agent_schedules_overlap_policy_check
+        CHECK (overlap_policy IN ('skip', 'allow'))
+);
+
+CREATE INDEX idx_agent_schedules_agent_id ON agent_schedules(agent_id);
+CREATE INDEX idx_agent_schedules_due ON agent_schedules(next_run_at) WHERE enabled;
+
+CREATE TABLE schedule_runs (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    schedule_id UUID NOT NULL REFERENCES agent_schedules(id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    session_id UUID REFERENCES sessions(id) ON DELETE SET NULL,
+    message_id UUID,
+    error TEXT,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT schedule_runs_status_check
+        CHECK (status IN ('pending', 'running', 'completed', 'failed', 'skipped')),
+    UNIQUE (schedule_id, scheduled_for)
+);
+
+CREATE INDEX idx_schedule_runs_schedule_id ON schedule_runs(schedule_id, scheduled_for DESC);
+CREATE INDEX idx_schedule_runs_pending ON schedule_runs(scheduled_for) WHERE status = 'pending';
+CREATE INDEX idx_schedule_runs_message_id ON schedule_runs(message_id) WHERE status = 'running';
+
+CREATE TRIGGER update_agent_schedules_updated_at BEFORE UPDATE ON agent_schedules
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+COMMENT ON TABLE agent_schedules IS 'Cron schedules that post a message to an agent session';
+COMMENT ON COLUMN agent_schedules.session_id IS 'Target session (NULL creates a fresh session for every run)';
+COMMENT ON COLUMN agent_schedules.next_run_at IS 'Next occurrence to run (NULL when the expression has no future occurrence)';
+COMMENT ON TABLE schedule_runs IS 'One row per due occurrence of a schedule';
@@ -0,0 +1,26 @@
+-- Outbound webhooks
+--
+-- A subscription POSTs session events to a URL, either for one agent's
+-- sessions or (agent_id NULL) for all sessions. Deliveries are HMAC-signed
+-- with the subscription secret and executed as `deliver_webhook` tasks on the
+-- durable task queue, so retries, backoff and the DLQ come from the engine.
+
+CREATE TABLE webhook_subscriptions (
+    id UUID PRIMARY KEY DEFAULT uuidv7(),
+    -- NULL = global subscription (events of all agents)
+    agent_id UUID REFERENCES agents(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- Event types to deliver (e.g. 'turn.completed'); empty = all events
+    event_types TEXT[] NOT NULL DEFAULT '{}',
+    description TEXT,
+    -- Encrypted signing secret (AES-256-GCM, see specs/encryption.md)
+    secret_encrypted BYTEA NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_subscriptions_agent_id ON webhook_subscriptions(agent_id);
+
+CREATE TRIGGER update_webhook_subscriptions_updated_at BEFORE UPDATE ON webhook_subscriptions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,19 @@
+-- Named task queues
+--
+-- Every durable task belongs to a task queue and workers claim only from the
+-- queues they serve, so activities can be routed to dedicated worker pools
+-- (sandbox-enabled workers, per-tenant pools, workers holding specific tool
+-- credentials). Existing tasks and workers use the `default` queue.
+
+ALTER TABLE durable_task_queue
+    ADD COLUMN task_queue TEXT NOT NULL DEFAULT 'default';
+
+-- Dead tasks are requeued to the queue they were routed to
+ALTER TABLE durable_dead_letter_queue
+    ADD COLUMN task_queue TEXT NOT NULL DEFAULT 'default';
+
+-- Claiming filters on queue and activity type
+DROP INDEX idx_durable_task_queue_pending;
+CREATE INDEX idx_durable_task_queue_pending
+    ON durable_task_queue(task_queue, activity_type, priority DESC, visible_at)
+    WHERE status = 'pending';
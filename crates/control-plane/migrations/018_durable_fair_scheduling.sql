@@ -0,0 +1,18 @@
+-- Fair scheduling of durable tasks
+--
+-- Tasks carry an optional fairness key (e.g. 'session:<id>'). Claims take
+-- turns between keys within a priority, and the number of claimed tasks can
+-- be capped per key and per activity type (see FairnessConfig in the durable
+-- crate), so one busy session or agent cannot starve the others.
+
+ALTER TABLE durable_task_queue
+    ADD COLUMN fairness_key TEXT;
+
+-- Running task counts per key and per activity type, read on every claim
+CREATE INDEX idx_durable_task_queue_claimed_fairness_key
+    ON durable_task_queue(fairness_key)
+    WHERE status = 'claimed' AND fairness_key IS NOT NULL;
+
+CREATE INDEX idx_durable_task_queue_claimed_activity_type
+    ON durable_task_queue(activity_type)
+    WHERE status = 'claimed';
@@ -0,0 +1,24 @@
+-- Distributed rate limiting for LLM providers
+--
+-- Providers enforce requests-per-minute and tokens-per-minute limits per API
+-- key. Workers share token buckets keyed by provider ('llm_provider:<id>'),
+-- so capacity is acquired before each call instead of discovered via 429s.
+-- Limits are configured per provider and adapted from rate limit headers.
+
+ALTER TABLE llm_providers
+    ADD COLUMN requests_per_minute INT CHECK (requests_per_minute > 0),
+    ADD COLUMN tokens_per_minute INT CHECK (tokens_per_minute > 0);
+
+-- Token bucket state (see TokenBucketState in the durable crate)
+CREATE TABLE durable_rate_limiter_state (
+    key TEXT PRIMARY KEY,  -- e.g., "llm_provider:<id>"
+    -- Capacity left in each bucket; NULL while no limit is known (unlimited)
+    available_requests DOUBLE PRECISION,
+    available_tokens DOUBLE PRECISION,
+    -- Limits reported by the provider in response headers
+    learned_requests_per_minute INT,
+    learned_tokens_per_minute INT,
+    -- Set when the provider is exhausted or asked callers to back off
+    blocked_until TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,200 @@
+-- History retention and partitioning
+--
+-- Durable workflow history and the task queue grow with every workflow.
+-- The maintenance job (see services::maintenance) deletes finished workflows
+-- and tasks after their retention period, optionally archiving workflow
+-- histories first, and drops old partitions once retention has emptied them.
+--
+-- durable_workflow_events and durable_task_queue are range-partitioned by
+-- month on created_at, in partitions named <table>_pYYYY_MM (UTC months).
+-- Rows outside every monthly partition land in <table>_default; the
+-- maintenance job creates partitions ahead of time so it stays empty.
+--
+-- Decision: Partitioned tables can only enforce unique constraints that
+-- include the partition key, so primary keys become (id, created_at) and
+-- UNIQUE(workflow_id, sequence_num) becomes a plain index. Appends already
+-- serialize on the workflow instance row lock.
+-- Decision: The session events table is not partitioned. create_event relies
+-- on UNIQUE(session_id, sequence) to reject concurrent appends, which a
+-- partitioned table cannot enforce. Superseded events are pruned instead.
+
+-- ============================================
+-- Partition helper
+-- ============================================
+
+-- Create the monthly partition of `parent` containing `at_time` if missing
+-- and return its name
+CREATE FUNCTION durable_ensure_monthly_partition(parent TEXT, at_time TIMESTAMPTZ)
+RETURNS TEXT AS $$
+DECLARE
+    starts_at TIMESTAMP := date_trunc('month', at_time AT TIME ZONE 'UTC');
+    partition_name TEXT := parent || '_p' || to_char(starts_at, 'YYYY_MM');
+BEGIN
+    IF to_regclass(partition_name) IS NULL THEN
+        EXECUTE format(
+            'CREATE TABLE %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+            partition_name,
+            parent,
+            starts_at AT TIME ZONE 'UTC',
+            (starts_at + INTERVAL '1 month') AT TIME ZONE 'UTC'
+        );
+    END IF;
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
+
+-- ============================================
+-- Workflow events
+-- ============================================
+
+ALTER TABLE durable_workflow_events RENAME TO durable_workflow_events_old;
+ALTER TABLE durable_workflow_events_old
+    DROP CONSTRAINT durable_workflow_events_pkey,
+    DROP CONSTRAINT durable_workflow_events_workflow_id_sequence_num_key;
+ALTER SEQUENCE durable_workflow_events_id_seq OWNED BY NONE;
+
+CREATE TABLE durable_workflow_events (
+    id BIGINT NOT NULL DEFAULT nextval('durable_workflow_events_id_seq'),
+    workflow_id UUID NOT NULL REFERENCES durable_workflow_instances(id) ON DELETE CASCADE,
+    sequence_num INT NOT NULL,  -- Per-workflow sequence number (0-indexed)
+    event_type TEXT NOT NULL,
+    event_data JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Tracing context for this event
+    trace_id TEXT,
+    span_id TEXT,
+
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+ALTER SEQUENCE durable_workflow_events_id_seq OWNED BY durable_workflow_events.id;
+
+-- Partitions for existing rows and the next two months
+SELECT durable_ensure_monthly_partition('durable_workflow_events', starts_at AT TIME ZONE 'UTC')
+FROM generate_series(
+    date_trunc('month', COALESCE(
+        (SELECT MIN(created_at) FROM durable_workflow_events_old), NOW()
+    ) AT TIME ZONE 'UTC'),
+    (NOW() AT TIME ZONE 'UTC') + INTERVAL '2 months',
+    INTERVAL '1 month'
+) AS months(starts_at);
+
+CREATE TABLE durable_workflow_events_default PARTITION OF durable_workflow_events DEFAULT;
+
+INSERT INTO durable_workflow_events (
+    id, workflow_id, sequence_num, event_type, event_data, created_at, trace_id, span_id
+)
+SELECT id, workflow_id, sequence_num, event_type, event_data, created_at, trace_id, span_id
+FROM durable_workflow_events_old;
+
+DROP TABLE durable_workflow_events_old;
+
+CREATE INDEX idx_durable_workflow_events_workflow
+    ON durable_workflow_events(workflow_id, sequence_num);
+
+-- ============================================
+-- Task queue
+-- ============================================
+
+ALTER TABLE durable_task_queue RENAME TO durable_task_queue_old;
+ALTER TABLE durable_task_queue_old DROP CONSTRAINT durable_task_queue_pkey;
+
+CREATE TABLE durable_task_queue (
+    id UUID NOT NULL DEFAULT uuidv7(),
+    workflow_id UUID NOT NULL REFERENCES durable_workflow_instances(id) ON DELETE CASCADE,
+    activity_id TEXT NOT NULL,
+    activity_type TEXT NOT NULL,
+    input JSONB NOT NULL,
+    options JSONB NOT NULL,
+    task_queue TEXT NOT NULL DEFAULT 'default',
+    fairness_key TEXT,
+
+    -- Scheduling
+    status TEXT NOT NULL DEFAULT 'pending',  -- pending, claimed, completed, failed, dead, cancelled
+    priority INT NOT NULL DEFAULT 0,
+    scheduled_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    visible_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),  -- For delayed retry
+
+    -- Claiming
+    claimed_by TEXT,  -- Worker ID
+    claimed_at TIMESTAMPTZ,
+    heartbeat_at TIMESTAMPTZ,
+
+    -- Execution tracking
+    attempt INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL,
+    last_error TEXT,
+
+    -- Timeouts (stored as intervals)
+    schedule_to_start_timeout_ms BIGINT NOT NULL,
+    start_to_close_timeout_ms BIGINT NOT NULL,
+    heartbeat_timeout_ms BIGINT,
+
+    -- Tracing
+    trace_id TEXT,
+    span_id TEXT,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+-- Partitions for existing rows and the next two months
+SELECT durable_ensure_monthly_partition('durable_task_queue', starts_at AT TIME ZONE 'UTC')
+FROM generate_series(
+    date_trunc('month', COALESCE(
+        (SELECT MIN(created_at) FROM durable_task_queue_old), NOW()
+    ) AT TIME ZONE 'UTC'),
+    (NOW() AT TIME ZONE 'UTC') + INTERVAL '2 months',
+    INTERVAL '1 month'
+) AS months(starts_at);
+
+CREATE TABLE durable_task_queue_default PARTITION OF durable_task_queue DEFAULT;
+
+INSERT INTO durable_task_queue (
+    id, workflow_id, activity_id, activity_type, input, options, task_queue, fairness_key,
+    status, priority, scheduled_at, visible_at, claimed_by, claimed_at, heartbeat_at,
+    attempt, max_attempts, last_error,
+    schedule_to_start_timeout_ms, start_to_close_timeout_ms, heartbeat_timeout_ms,
+    trace_id, span_id, created_at
+)
+SELECT
+    id, workflow_id, activity_id, activity_type, input, options, task_queue, fairness_key,
+    status, priority, scheduled_at, visible_at, claimed_by, claimed_at, heartbeat_at,
+    attempt, max_attempts, last_error,
+    schedule_to_start_timeout_ms, start_to_close_timeout_ms, heartbeat_timeout_ms,
+    trace_id, span_id, created_at
+FROM durable_task_queue_old;
+
+DROP TABLE durable_task_queue_old;
+
+CREATE INDEX idx_durable_task_queue_pending
+    ON durable_task_queue(task_queue, activity_type, priority DESC, visible_at)
+    WHERE status = 'pending';
+
+CREATE INDEX idx_durable_task_queue_claimed
+    ON durable_task_queue(claimed_by, heartbeat_at)
+    WHERE status = 'claimed';
+
+CREATE INDEX idx_durable_task_queue_workflow ON durable_task_queue(workflow_id);
+
+CREATE INDEX idx_durable_task_queue_claimed_fairness_key
+    ON durable_task_queue(fairness_key)
+    WHERE status = 'claimed' AND fairness_key IS NOT NULL;
+
+CREATE INDEX idx_durable_task_queue_claimed_activity_type
+    ON durable_task_queue(activity_type)
+    WHERE status = 'claimed';
+
+-- ============================================
+-- Retention lookups
+-- ============================================
+
+CREATE INDEX idx_durable_workflow_instances_completed
+    ON durable_workflow_instances(completed_at)
+    WHERE status IN ('completed', 'failed', 'cancelled');
+
+CREATE INDEX idx_events_superseded
+    ON events(superseded_at)
+    WHERE superseded_at IS NOT NULL;
@@ -0,0 +1,16 @@
+-- Continue-as-new run chains
+--
+-- A workflow that continues as new keeps its id: its finished run (history,
+-- old input) moves to a new completed instance row and the original row
+-- restarts as the next run. run_number counts runs in the chain from 1,
+-- previous_run_id links a run to the run before it, and continued_as marks
+-- a closed run with the workflow id that continued it.
+
+ALTER TABLE durable_workflow_instances
+    ADD COLUMN run_number INT NOT NULL DEFAULT 1,
+    ADD COLUMN previous_run_id UUID REFERENCES durable_workflow_instances(id) ON DELETE SET NULL,
+    ADD COLUMN continued_as UUID;
+
+CREATE INDEX idx_durable_workflow_instances_continued_as
+    ON durable_workflow_instances(continued_as)
+    WHERE continued_as IS NOT NULL;
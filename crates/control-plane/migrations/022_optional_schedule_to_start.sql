@@ -0,0 +1,14 @@
+-- Optional schedule-to-start timeouts
+--
+-- Pending tasks are only failed for waiting too long when their activity sets
+-- a schedule-to-start timeout. Tasks held back by fairness caps, backpressure
+-- or a worker outage otherwise stay pending. No activity sets one yet, so the
+-- old 60s default is cleared from queued tasks (the sweeper reads the options).
+
+ALTER TABLE durable_task_queue
+    ALTER COLUMN schedule_to_start_timeout_ms DROP NOT NULL;
+
+UPDATE durable_task_queue
+SET schedule_to_start_timeout_ms = NULL,
+    options = jsonb_set(options, '{schedule_to_start_timeout}', 'null')
+WHERE status IN ('pending', 'claimed');
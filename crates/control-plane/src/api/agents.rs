@@ -1,24 +1,27 @@
 // Agent CRUD HTTP routes (M2)
 
-use crate::storage::Database;
+use crate::storage::{Database, EncryptionService};
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{header, StatusCode},
     response::Response,
     routing::{get, post},
     Json, Router,
 };
 use chrono::Utc;
-use everruns_core::{Agent, AgentStatus, CapabilityId};
+use everruns_core::capabilities::{self, CapabilityConfigs, CapabilityRegistry};
+use everruns_core::mcp::{McpServerConfig, McpTransportConfig};
+use everruns_core::{Agent, AgentStatus, CapabilityId, ResponseFormat};
 
 use super::common::{ErrorResponse, ListResponse};
 use super::validation::{
     validate_create_agent_input, validate_import_file_size, validate_update_agent_input,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 /// Request to create a new agent
@@ -48,6 +51,22 @@ pub struct CreateAgentRequest {
     #[serde(default)]
     #[schema(example = json!(["current_time", "web_fetch"]), value_type = Vec<String>)]
     pub capabilities: Vec<CapabilityId>,
+    /// Per-capability configuration, keyed by capability ID.
+    /// Each config must match the `config_schema` the capability publishes.
+    #[serde(default)]
+    #[schema(example = json!({"web_fetch": {"allowed_domains": ["docs.rs"]}}), value_type = Object)]
+    pub capability_config: CapabilityConfigs,
+    /// JSON-schema constrained format for the agent's final responses.
+    /// Can be overridden per message via `controls.response_format`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Whether the model may request several tool calls at once.
+    /// Set to false when the agent's tools are order-dependent; calls then run sequentially.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    /// MCP servers whose tools are exposed when the `mcp` capability is enabled.
+    #[serde(default)]
+    pub mcp_servers: Vec<McpServerInput>,
 }
 
 /// Request to update an agent. Only provided fields will be updated.
@@ -76,15 +95,105 @@ pub struct UpdateAgentRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schema(example = json!(["current_time", "web_fetch"]), value_type = Option<Vec<String>>)]
     pub capabilities: Option<Vec<CapabilityId>>,
+    /// Per-capability configuration. Replaces existing configuration.
+    /// When omitted, capabilities that stay enabled keep their configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub capability_config: Option<CapabilityConfigs>,
+    /// JSON-schema constrained format for the agent's final responses.
+    /// Set to `null` to remove it.
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[schema(value_type = Option<ResponseFormat>, nullable)]
+    pub response_format: Option<Option<ResponseFormat>>,
+    /// Whether the model may request several tool calls at once.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    /// MCP servers for the `mcp` capability. Replaces existing servers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<Vec<McpServerInput>>,
     /// The status of the agent. Set to "archived" to soft-delete.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<AgentStatus>,
 }
 
+/// MCP server definition in create/update requests
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct McpServerInput {
+    /// Server name (lowercase letters, digits, `_`, `-`). Tools are exposed as `<name>__<tool>`.
+    #[schema(example = "github")]
+    pub name: String,
+    /// How to reach the server.
+    pub transport: McpTransportConfig,
+    /// Credentials: environment variables for stdio servers, HTTP headers for HTTP servers.
+    /// Write-only: stored encrypted and never returned. Omit on update to keep the stored values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = json!({"GITHUB_TOKEN": "ghp_..."}))]
+    pub credentials: Option<HashMap<String, String>>,
+}
+
+impl From<&McpServerConfig> for McpServerInput {
+    fn from(config: &McpServerConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            transport: config.transport.clone(),
+            credentials: None,
+        }
+    }
+}
+
+/// Query parameters for diffing revisions
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct RevisionDiffQuery {
+    /// Revision to compare against (defaults to the preceding revision)
+    pub from: Option<i32>,
+}
+
+/// Differences between two revisions of an agent
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AgentRevisionDiff {
+    /// ID of the agent.
+    pub agent_id: Uuid,
+    /// Revision compared against.
+    pub from_revision: i32,
+    /// Revision being inspected.
+    pub to_revision: i32,
+    /// Configuration fields that differ, in a stable order.
+    pub changes: Vec<AgentFieldChange>,
+}
+
+/// A configuration field that differs between two revisions
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AgentFieldChange {
+    /// Field name, as in the Agent schema (e.g. `system_prompt`).
+    #[schema(example = "system_prompt")]
+    pub field: String,
+    /// Value in the older revision (null when unset).
+    pub from: serde_json::Value,
+    /// Value in the newer revision (null when unset).
+    pub to: serde_json::Value,
+}
+
+/// Agent fields captured by a revision, in diff order
+const REVISION_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "system_prompt",
+    "default_model_id",
+    "tags",
+    "capabilities",
+    "capability_config",
+    "response_format",
+    "parallel_tool_calls",
+    "mcp_servers",
+];
+
 /// Agent file format for import (matches CLI format)
 /// Parsed from YAML front matter in Markdown files.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct AgentFile {
+    /// Revision the file was exported from (informational; ignored on import)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<i32>,
     pub name: Option<String>,
     pub description: Option<String>,
     pub system_prompt: Option<String>,
@@ -93,6 +202,14 @@ struct AgentFile {
     pub tags: Vec<String>,
     #[serde(default)]
     pub capabilities: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub capability_config: CapabilityConfigs,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(default)]
+    pub mcp_servers: Vec<McpServerInput>,
 }
 
 use crate::services::AgentService;
@@ -104,9 +221,9 @@ pub struct AppState {
 }
 
 impl AppState {
-    pub fn new(db: Arc<Database>) -> Self {
+    pub fn new(db: Arc<Database>, encryption: Option<Arc<EncryptionService>>) -> Self {
         Self {
-            service: Arc::new(AgentService::new(db)),
+            service: Arc::new(AgentService::new(db, encryption)),
         }
     }
 }
@@ -121,6 +238,19 @@ pub fn routes(state: AppState) -> Router {
             get(get_agent).patch(update_agent).delete(delete_agent),
         )
         .route("/v1/agents/:agent_id/export", get(export_agent))
+        .route("/v1/agents/:agent_id/revisions", get(list_agent_revisions))
+        .route(
+            "/v1/agents/:agent_id/revisions/:revision",
+            get(get_agent_revision),
+        )
+        .route(
+            "/v1/agents/:agent_id/revisions/:revision/diff",
+            get(diff_agent_revision),
+        )
+        .route(
+            "/v1/agents/:agent_id/revisions/:revision/rollback",
+            post(rollback_agent_revision),
+        )
         .with_state(state)
 }
 
@@ -147,11 +277,15 @@ pub async fn create_agent(
         &req.system_prompt,
         req.capabilities.len(),
     )?;
+    validate_capability_config(&req.capabilities, &req.capability_config)?;
+    validate_response_format(req.response_format.as_ref())?;
+    validate_mcp_servers(&req.mcp_servers)?;
 
-    let agent = state.service.create(req).await.map_err(|e| {
-        tracing::error!("Failed to create agent: {}", e);
-        ErrorResponse::new("Internal server error").into_response(StatusCode::INTERNAL_SERVER_ERROR)
-    })?;
+    let agent = state
+        .service
+        .create(req)
+        .await
+        .map_err(|e| service_error("create agent", e))?;
 
     Ok((StatusCode::CREATED, Json(agent)))
 }
@@ -236,16 +370,34 @@ pub async fn update_agent(
         req.system_prompt.as_deref(),
         req.capabilities.as_ref().map(|c| c.len()),
     )?;
+    validate_response_format(req.response_format.as_ref().and_then(Option::as_ref))?;
+    if let Some(servers) = &req.mcp_servers {
+        validate_mcp_servers(servers)?;
+    }
+    if let Some(config) = &req.capability_config {
+        // Configs must belong to the capabilities the agent will have after the update
+        let capabilities = match &req.capabilities {
+            Some(capabilities) => capabilities.clone(),
+            None => {
+                state
+                    .service
+                    .get(agent_id)
+                    .await
+                    .map_err(|e| service_error("get agent", e))?
+                    .ok_or_else(|| {
+                        ErrorResponse::new("Not found").into_response(StatusCode::NOT_FOUND)
+                    })?
+                    .capabilities
+            }
+        };
+        validate_capability_config(&capabilities, config)?;
+    }
 
     let agent = state
         .service
         .update(agent_id, req)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to update agent: {}", e);
-            ErrorResponse::new("Internal server error")
-                .into_response(StatusCode::INTERNAL_SERVER_ERROR)
-        })?
+        .map_err(|e| service_error("update agent", e))?
         .ok_or_else(|| ErrorResponse::new("Not found").into_response(StatusCode::NOT_FOUND))?;
 
     Ok(Json(agent))
@@ -281,6 +433,146 @@ pub async fn delete_agent(
     }
 }
 
+/// GET /v1/agents/{agent_id}/revisions - List agent revisions, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/revisions",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID")
+    ),
+    responses(
+        (status = 200, description = "Agent configuration at each revision", body = ListResponse<Agent>),
+        (status = 404, description = "Agent not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "agents"
+)]
+pub async fn list_agent_revisions(
+    State(state): State<AppState>,
+    Path(agent_id): Path<Uuid>,
+) -> Result<Json<ListResponse<Agent>>, StatusCode> {
+    let revisions = state
+        .service
+        .list_revisions(agent_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list agent revisions: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(ListResponse::new(revisions)))
+}
+
+/// GET /v1/agents/{agent_id}/revisions/{revision} - Get agent at a revision
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/revisions/{revision}",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("revision" = i32, Path, description = "Revision number")
+    ),
+    responses(
+        (status = 200, description = "Agent configuration at the revision", body = Agent),
+        (status = 404, description = "Agent or revision not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "agents"
+)]
+pub async fn get_agent_revision(
+    State(state): State<AppState>,
+    Path((agent_id, revision)): Path<(Uuid, i32)>,
+) -> Result<Json<Agent>, StatusCode> {
+    let agent = state
+        .service
+        .get_revision(agent_id, revision)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get agent revision: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(agent))
+}
+
+/// GET /v1/agents/{agent_id}/revisions/{revision}/diff - Compare a revision with another
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/revisions/{revision}/diff",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("revision" = i32, Path, description = "Revision number"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Changed configuration fields", body = AgentRevisionDiff),
+        (status = 404, description = "Agent or revision not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "agents"
+)]
+pub async fn diff_agent_revision(
+    State(state): State<AppState>,
+    Path((agent_id, revision)): Path<(Uuid, i32)>,
+    Query(query): Query<RevisionDiffQuery>,
+) -> Result<Json<AgentRevisionDiff>, StatusCode> {
+    let from_revision = query.from.unwrap_or(revision - 1);
+    let load = |revision| {
+        let service = state.service.clone();
+        async move {
+            service
+                .get_revision(agent_id, revision)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get agent revision: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or(StatusCode::NOT_FOUND)
+        }
+    };
+    let from = load(from_revision).await?;
+    let to = load(revision).await?;
+
+    Ok(Json(AgentRevisionDiff {
+        agent_id,
+        from_revision,
+        to_revision: revision,
+        changes: diff_agents(&from, &to),
+    }))
+}
+
+/// POST /v1/agents/{agent_id}/revisions/{revision}/rollback - Restore a revision
+///
+/// The restored configuration is recorded as a new revision; history is never rewritten.
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/revisions/{revision}/rollback",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("revision" = i32, Path, description = "Revision to restore")
+    ),
+    responses(
+        (status = 200, description = "Agent restored as a new revision", body = Agent),
+        (status = 404, description = "Agent or revision not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "agents"
+)]
+pub async fn rollback_agent_revision(
+    State(state): State<AppState>,
+    Path((agent_id, revision)): Path<(Uuid, i32)>,
+) -> Result<Json<Agent>, (StatusCode, Json<ErrorResponse>)> {
+    let agent = state
+        .service
+        .rollback(agent_id, revision)
+        .await
+        .map_err(|e| service_error("roll back agent", e))?
+        .ok_or_else(|| ErrorResponse::new("Not found").into_response(StatusCode::NOT_FOUND))?;
+
+    Ok(Json(agent))
+}
+
 /// GET /v1/agents/{agent_id}/export - Export agent in Markdown format with YAML front matter
 #[utoipa::path(
     get,
@@ -373,6 +665,14 @@ pub async fn import_agent(
         &system_prompt,
         agent_file.capabilities.len(),
     )?;
+    let capabilities: Vec<CapabilityId> = agent_file
+        .capabilities
+        .into_iter()
+        .map(CapabilityId::from)
+        .collect();
+    validate_capability_config(&capabilities, &agent_file.capability_config)?;
+    validate_response_format(agent_file.response_format.as_ref())?;
+    validate_mcp_servers(&agent_file.mcp_servers)?;
 
     let request = CreateAgentRequest {
         name,
@@ -380,30 +680,145 @@ pub async fn import_agent(
         system_prompt,
         default_model_id: agent_file.default_model_id,
         tags: agent_file.tags,
-        capabilities: agent_file
-            .capabilities
-            .into_iter()
-            .map(CapabilityId::from)
-            .collect(),
+        capabilities,
+        capability_config: agent_file.capability_config,
+        response_format: agent_file.response_format,
+        parallel_tool_calls: agent_file.parallel_tool_calls,
+        mcp_servers: agent_file.mcp_servers,
     };
 
-    let agent = state.service.create(request).await.map_err(|e| {
-        tracing::error!("Failed to import agent: {}", e);
-        ErrorResponse::new("Internal server error").into_response(StatusCode::INTERNAL_SERVER_ERROR)
-    })?;
+    let agent = state
+        .service
+        .create(request)
+        .await
+        .map_err(|e| service_error("import agent", e))?;
 
     Ok((StatusCode::CREATED, Json(agent)))
 }
 
+/// Deserialize a field where `null` differs from absent: absent is `None`,
+/// `null` is `Some(None)`
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Validate a response format definition, surfacing the reason to the client
+fn validate_response_format(
+    format: Option<&ResponseFormat>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if let Some(format) = format {
+        format
+            .validate_definition()
+            .map_err(|e| ErrorResponse::new(e).into_response(StatusCode::BAD_REQUEST))?;
+    }
+    Ok(())
+}
+
+/// Validate per-capability configs: each must belong to an enabled capability
+/// and match the schema the capability publishes
+fn validate_capability_config(
+    capabilities: &[CapabilityId],
+    config: &CapabilityConfigs,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if config.is_empty() {
+        return Ok(());
+    }
+
+    let registry = CapabilityRegistry::with_builtins();
+    for (capability_id, capability_config) in config {
+        if !capabilities.iter().any(|c| c.as_str() == capability_id) {
+            return Err(ErrorResponse::new(format!(
+                "Configuration provided for capability '{}' which is not enabled",
+                capability_id
+            ))
+            .into_response(StatusCode::BAD_REQUEST));
+        }
+        let result = match registry.get(capability_id) {
+            Some(capability) => {
+                capabilities::validate_capability_config(capability.as_ref(), capability_config)
+            }
+            None => Err(format!(
+                "Capability '{}' does not accept configuration",
+                capability_id
+            )),
+        };
+        result.map_err(|e| ErrorResponse::new(e).into_response(StatusCode::BAD_REQUEST))?;
+    }
+    Ok(())
+}
+
+/// Validate MCP server definitions (unique names, valid transports)
+fn validate_mcp_servers(
+    servers: &[McpServerInput],
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let mut names = HashSet::new();
+    for server in servers {
+        let config = McpServerConfig {
+            name: server.name.clone(),
+            transport: server.transport.clone(),
+            credential_names: vec![],
+        };
+        config
+            .validate()
+            .map_err(|e| ErrorResponse::new(e).into_response(StatusCode::BAD_REQUEST))?;
+        if !names.insert(server.name.as_str()) {
+            return Err(
+                ErrorResponse::new(format!("Duplicate MCP server name '{}'", server.name))
+                    .into_response(StatusCode::BAD_REQUEST),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Map a service error to a response; missing encryption is a client-visible 400
+fn service_error(action: &str, e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    let error_msg = e.to_string();
+    if error_msg.contains("Encryption not configured") {
+        return ErrorResponse::new(error_msg).into_response(StatusCode::BAD_REQUEST);
+    }
+    tracing::error!("Failed to {}: {}", action, e);
+    ErrorResponse::new("Internal server error").into_response(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Compare the configuration fields captured by revisions
+fn diff_agents(from: &Agent, to: &Agent) -> Vec<AgentFieldChange> {
+    let (Ok(from), Ok(to)) = (serde_json::to_value(from), serde_json::to_value(to)) else {
+        return vec![];
+    };
+    REVISION_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let before = from.get(field).cloned().unwrap_or_default();
+            let after = to.get(field).cloned().unwrap_or_default();
+            (before != after).then(|| AgentFieldChange {
+                field: field.to_string(),
+                from: before,
+                to: after,
+            })
+        })
+        .collect()
+}
+
 /// Convert agent to Markdown format with YAML front matter
 fn agent_to_markdown(agent: &Agent) -> String {
     let mut front_matter = AgentFile {
+        revision: Some(agent.revision),
         name: Some(agent.name.clone()),
         description: agent.description.clone(),
         system_prompt: None, // System prompt goes in body
         default_model_id: agent.default_model_id,
         tags: agent.tags.clone(),
         capabilities: agent.capabilities.iter().map(|c| c.to_string()).collect(),
+        capability_config: agent.capability_config.clone(),
+        response_format: agent.response_format.clone(),
+        parallel_tool_calls: agent.parallel_tool_calls,
+        // Credentials are never exported
+        mcp_servers: agent.mcp_servers.iter().map(McpServerInput::from).collect(),
     };
 
     // Don't include empty arrays in front matter
@@ -418,6 +833,10 @@ fn agent_to_markdown(agent: &Agent) -> String {
     let mut yaml_lines = vec![];
     yaml_lines.push(format!("name: \"{}\"", agent.name.replace('"', "\\\"")));
 
+    if let Some(revision) = front_matter.revision {
+        yaml_lines.push(format!("revision: {}", revision));
+    }
+
     if let Some(ref desc) = front_matter.description {
         yaml_lines.push(format!("description: \"{}\"", desc.replace('"', "\\\"")));
     }
@@ -440,6 +859,29 @@ fn agent_to_markdown(agent: &Agent) -> String {
         }
     }
 
+    if !front_matter.capability_config.is_empty() {
+        if let Ok(json) = serde_json::to_string(&front_matter.capability_config) {
+            yaml_lines.push(format!("capability_config: {}", json));
+        }
+    }
+
+    if let Some(parallel) = front_matter.parallel_tool_calls {
+        yaml_lines.push(format!("parallel_tool_calls: {}", parallel));
+    }
+
+    // JSON is valid YAML flow syntax, so the schema round-trips as a single line
+    if let Some(ref format) = front_matter.response_format {
+        if let Ok(json) = serde_json::to_string(format) {
+            yaml_lines.push(format!("response_format: {}", json));
+        }
+    }
+
+    if !front_matter.mcp_servers.is_empty() {
+        if let Ok(json) = serde_json::to_string(&front_matter.mcp_servers) {
+            yaml_lines.push(format!("mcp_servers: {}", json));
+        }
+    }
+
     format!(
         "---\n{}\n---\n{}",
         yaml_lines.join("\n"),
@@ -476,12 +918,17 @@ fn parse_agent_content(content: &str) -> Result<AgentFile, String> {
 
     // Fall back to treating entire content as system prompt
     Ok(AgentFile {
+        revision: None,
         name: None, // Will be auto-generated
         description: None,
         system_prompt: Some(content.to_string()),
         default_model_id: None,
         tags: vec![],
         capabilities: vec![],
+        capability_config: CapabilityConfigs::new(),
+        response_format: None,
+        parallel_tool_calls: None,
+        mcp_servers: vec![],
     })
 }
 
@@ -2,7 +2,8 @@
 //
 // Design Decision: Capabilities are defined in everruns-core via the Capability trait.
 // This module provides HTTP endpoints that expose capability information from the
-// CapabilityRegistry in everruns-core.
+// CapabilityRegistry in everruns-core, plus user-defined HTTP tools (`custom:<name>`,
+// managed through /v1/tools).
 //
 // Agent capabilities are managed through the agents API (POST/PATCH /v1/agents).
 
@@ -45,14 +46,18 @@ pub fn routes(state: AppState) -> Router {
     path = "/v1/capabilities",
     responses(
         (status = 200, description = "List of available capabilities", body = ListResponse<CapabilityInfo>),
+        (status = 500, description = "Internal server error"),
     ),
     tag = "capabilities"
 )]
 pub async fn list_capabilities(
     State(state): State<AppState>,
-) -> Json<ListResponse<CapabilityInfo>> {
-    let capabilities = state.service.list_all();
-    Json(ListResponse::new(capabilities))
+) -> Result<Json<ListResponse<CapabilityInfo>>, StatusCode> {
+    let capabilities = state.service.list_all().await.map_err(|e| {
+        tracing::error!("Failed to list capabilities: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(ListResponse::new(capabilities)))
 }
 
 /// GET /v1/capabilities/{capability_id} - Get a specific capability
@@ -65,6 +70,7 @@ pub async fn list_capabilities(
     responses(
         (status = 200, description = "Capability found", body = CapabilityInfo),
         (status = 404, description = "Capability not found"),
+        (status = 500, description = "Internal server error"),
     ),
     tag = "capabilities"
 )]
@@ -74,7 +80,15 @@ pub async fn get_capability(
 ) -> Result<Json<CapabilityInfo>, StatusCode> {
     let cap_id = CapabilityId::new(&capability_id);
 
-    let capability = state.service.get(&cap_id).ok_or(StatusCode::NOT_FOUND)?;
+    let capability = state
+        .service
+        .get(&cap_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get capability: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
     Ok(Json(capability))
 }
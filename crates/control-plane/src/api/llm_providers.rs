@@ -47,6 +47,15 @@ pub struct CreateLlmProviderRequest {
     /// Will be encrypted at rest if encryption is configured.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    /// Requests per minute allowed across all workers.
+    /// Limits reported by the provider in response headers also apply.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 500, minimum = 1)]
+    pub requests_per_minute: Option<u32>,
+    /// Tokens (prompt + completion) per minute allowed across all workers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 30000, minimum = 1)]
+    pub tokens_per_minute: Option<u32>,
 }
 
 /// Request to update an LLM provider. Only provided fields will be updated.
@@ -70,6 +79,14 @@ pub struct UpdateLlmProviderRequest {
     /// The status of the provider. Set to "inactive" to disable.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<LlmProviderStatus>,
+    /// Requests per minute allowed across all workers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 500, minimum = 1)]
+    pub requests_per_minute: Option<u32>,
+    /// Tokens (prompt + completion) per minute allowed across all workers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = 30000, minimum = 1)]
+    pub tokens_per_minute: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -95,7 +112,9 @@ pub async fn create_provider(
 ) -> Result<(StatusCode, Json<LlmProvider>), (StatusCode, Json<ErrorResponse>)> {
     let provider = state.service.create(req).await.map_err(|e| {
         let error_msg = e.to_string();
-        if error_msg.contains("Encryption not configured") {
+        if error_msg.contains("Encryption not configured")
+            || error_msg.contains("Rate limits must be")
+        {
             (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse { error: error_msg }),
@@ -206,7 +225,9 @@ pub async fn update_provider(
         .await
         .map_err(|e| {
             let error_msg = e.to_string();
-            if error_msg.contains("Encryption not configured") {
+            if error_msg.contains("Encryption not configured")
+                || error_msg.contains("Rate limits must be")
+            {
                 (
                     StatusCode::BAD_REQUEST,
                     Json(ErrorResponse { error: error_msg }),
@@ -163,6 +163,14 @@ pub fn routes(state: AppState) -> Router {
             "/v1/agents/:agent_id/sessions/:session_id/messages",
             post(create_message).get(list_messages),
         )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/messages/:message_id/edit",
+            post(edit_message),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/regenerate",
+            post(regenerate),
+        )
         .with_state(state)
 }
 
@@ -181,6 +189,7 @@ pub fn routes(state: AppState) -> Router {
     request_body = CreateMessageRequest,
     responses(
         (status = 201, description = "Message created successfully", body = Message),
+        (status = 400, description = "Invalid response format in controls"),
         (status = 500, description = "Internal server error")
     ),
     tag = "messages"
@@ -189,14 +198,28 @@ pub async fn create_message(
     State(state): State<AppState>,
     Path((agent_id, session_id)): Path<(Uuid, Uuid)>,
     Json(req): Json<CreateMessageRequest>,
-) -> Result<(StatusCode, Json<Message>), StatusCode> {
+) -> Result<(StatusCode, Json<Message>), (StatusCode, String)> {
+    if let Some(format) = req
+        .controls
+        .as_ref()
+        .and_then(|c| c.response_format.as_ref())
+    {
+        if let Err(e) = format.validate_definition() {
+            tracing::warn!("Rejected message with invalid response_format: {}", e);
+            return Err((StatusCode::BAD_REQUEST, e));
+        }
+    }
+
     let message = state
         .message_service
         .create(agent_id, session_id, req)
         .await
         .map_err(|e| {
             tracing::error!("Failed to create message: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
         })?;
 
     Ok((StatusCode::CREATED, Json(message)))
@@ -240,6 +263,98 @@ pub async fn list_messages(
     Ok(Json(ListResponse::new(messages)))
 }
 
+/// POST /v1/agents/{agent_id}/sessions/{session_id}/messages/{message_id}/edit - Edit a user message and rerun
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/messages/{message_id}/edit",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID"),
+        ("message_id" = Uuid, Path, description = "User message to replace")
+    ),
+    request_body = CreateMessageRequest,
+    responses(
+        (status = 201, description = "Replacement message created and turn started", body = Message),
+        (status = 400, description = "Invalid response format in controls"),
+        (status = 404, description = "Session or user message not found"),
+        (status = 409, description = "A turn is running"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "messages"
+)]
+pub async fn edit_message(
+    State(state): State<AppState>,
+    Path((agent_id, session_id, message_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(req): Json<CreateMessageRequest>,
+) -> Result<(StatusCode, Json<Message>), (StatusCode, String)> {
+    if let Some(format) = req
+        .controls
+        .as_ref()
+        .and_then(|c| c.response_format.as_ref())
+    {
+        if let Err(e) = format.validate_definition() {
+            tracing::warn!("Rejected message with invalid response_format: {}", e);
+            return Err((StatusCode::BAD_REQUEST, e));
+        }
+    }
+
+    let message = state
+        .message_service
+        .edit(agent_id, session_id, message_id, req)
+        .await
+        .map_err(|e| history_error("edit message", e))?;
+
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+/// POST /v1/agents/{agent_id}/sessions/{session_id}/regenerate - Regenerate the last response
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/regenerate",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 200, description = "Turn restarted for the returned user message", body = Message),
+        (status = 400, description = "Session has no user message"),
+        (status = 404, description = "Session not found"),
+        (status = 409, description = "A turn is running"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "messages"
+)]
+pub async fn regenerate(
+    State(state): State<AppState>,
+    Path((agent_id, session_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Message>, (StatusCode, String)> {
+    let message = state
+        .message_service
+        .regenerate(agent_id, session_id)
+        .await
+        .map_err(|e| history_error("regenerate", e))?;
+
+    Ok(Json(message))
+}
+
+// Map regenerate/edit errors to client errors where the caller is at fault
+fn history_error(action: &str, e: anyhow::Error) -> (StatusCode, String) {
+    let msg = e.to_string();
+    if msg.contains("not found") {
+        (StatusCode::NOT_FOUND, msg)
+    } else if msg.contains("turn is running") {
+        (StatusCode::CONFLICT, msg)
+    } else if msg.contains("Cannot") {
+        (StatusCode::BAD_REQUEST, msg)
+    } else {
+        tracing::error!("Failed to {}: {}", action, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    }
+}
+
 // ============================================
 // Tests
 // ============================================
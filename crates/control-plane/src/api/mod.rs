@@ -10,10 +10,13 @@ pub mod events;
 pub mod llm_models;
 pub mod llm_providers;
 pub mod messages;
+pub mod schedules;
 pub mod session_files;
 pub mod sessions;
+pub mod tools;
 pub mod users;
 pub mod validation;
+pub mod webhooks;
 
 // Re-export common types
 pub use common::{ErrorResponse, ListResponse};
@@ -0,0 +1,549 @@
+// Agent schedule HTTP routes
+//
+// A schedule runs an agent on a cron expression by posting its message to a
+// fresh session (default) or to an existing session. Runs are dispatched by
+// the background scheduler (services::scheduler) and recorded as run history.
+
+use crate::storage::Database;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::common::{ErrorResponse, ListResponse};
+use crate::services::ScheduleService;
+
+/// What to do with occurrences that were due while the scheduler was not running
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Drop missed occurrences and wait for the next one
+    Skip,
+    /// Run once for all missed occurrences (the latest one)
+    #[default]
+    RunOnce,
+    /// Run every missed occurrence (at most 100)
+    RunAll,
+}
+
+/// What to do when an occurrence is due while the previous run is still in progress
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Record the occurrence as skipped
+    #[default]
+    Skip,
+    /// Start the run anyway
+    Allow,
+}
+
+/// Status of a schedule run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleRunStatus {
+    /// Recorded, waiting to be dispatched
+    Pending,
+    /// Message posted, turn in progress
+    Running,
+    /// Turn completed
+    Completed,
+    /// Dispatch or turn failed
+    Failed,
+    /// Not run because of the overlap policy
+    Skipped,
+}
+
+impl MissedRunPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MissedRunPolicy::Skip => "skip",
+            MissedRunPolicy::RunOnce => "run_once",
+            MissedRunPolicy::RunAll => "run_all",
+        }
+    }
+}
+
+impl From<&str> for MissedRunPolicy {
+    fn from(s: &str) -> Self {
+        match s {
+            "skip" => MissedRunPolicy::Skip,
+            "run_all" => MissedRunPolicy::RunAll,
+            _ => MissedRunPolicy::RunOnce,
+        }
+    }
+}
+
+impl OverlapPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlapPolicy::Skip => "skip",
+            OverlapPolicy::Allow => "allow",
+        }
+    }
+}
+
+impl From<&str> for OverlapPolicy {
+    fn from(s: &str) -> Self {
+        match s {
+            "allow" => OverlapPolicy::Allow,
+            _ => OverlapPolicy::Skip,
+        }
+    }
+}
+
+impl ScheduleRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduleRunStatus::Pending => "pending",
+            ScheduleRunStatus::Running => "running",
+            ScheduleRunStatus::Completed => "completed",
+            ScheduleRunStatus::Failed => "failed",
+            ScheduleRunStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl From<&str> for ScheduleRunStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "running" => ScheduleRunStatus::Running,
+            "completed" => ScheduleRunStatus::Completed,
+            "failed" => ScheduleRunStatus::Failed,
+            "skipped" => ScheduleRunStatus::Skipped,
+            _ => ScheduleRunStatus::Pending,
+        }
+    }
+}
+
+/// Agent schedule
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Schedule {
+    pub id: Uuid,
+    pub agent_id: Uuid,
+    pub name: String,
+    /// Cron expression (minute hour day-of-month month day-of-week) or macro
+    /// such as `@daily`
+    #[schema(example = "0 9 * * MON-FRI")]
+    pub cron: String,
+    /// IANA timezone the cron expression is evaluated in
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: String,
+    /// Message posted for each run. Supports `{{variable}}` placeholders
+    /// (session variables, built-ins, `schedule_name` and `scheduled_for`).
+    pub message: String,
+    /// Session that receives the messages. When absent, every run creates a
+    /// fresh session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<Uuid>,
+    /// Variables for sessions created by the schedule
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub session_variables: BTreeMap<String, String>,
+    pub missed_run_policy: MissedRunPolicy,
+    pub overlap_policy: OverlapPolicy,
+    pub enabled: bool,
+    /// Next occurrence (absent when disabled or the expression has no future occurrence)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One due occurrence of a schedule
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScheduleRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    /// Occurrence this run is for (the trigger time for manual runs)
+    pub scheduled_for: DateTime<Utc>,
+    pub status: ScheduleRunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<Uuid>,
+    /// User message that started the run's turn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Request to create a schedule
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateScheduleRequest {
+    pub name: String,
+    /// Cron expression (minute hour day-of-month month day-of-week) or macro
+    /// such as `@daily`
+    #[schema(example = "0 9 * * MON-FRI")]
+    pub cron: String,
+    /// IANA timezone (default: UTC)
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Message posted for each run
+    pub message: String,
+    /// Existing session of the agent to post to. Omit to create a fresh
+    /// session for every run.
+    #[serde(default)]
+    pub session_id: Option<Uuid>,
+    /// Variables for sessions created by the schedule
+    #[serde(default)]
+    pub session_variables: BTreeMap<String, String>,
+    #[serde(default)]
+    pub missed_run_policy: MissedRunPolicy,
+    #[serde(default)]
+    pub overlap_policy: OverlapPolicy,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Request to update a schedule. Only provided fields will be updated.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateScheduleRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub cron: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub session_variables: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub missed_run_policy: Option<MissedRunPolicy>,
+    #[serde(default)]
+    pub overlap_policy: Option<OverlapPolicy>,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Query parameters for listing schedule runs
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListScheduleRunsQuery {
+    /// Maximum number of runs to return, newest first (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+/// App state for schedule routes
+#[derive(Clone)]
+pub struct AppState {
+    pub service: Arc<ScheduleService>,
+}
+
+impl AppState {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            service: Arc::new(ScheduleService::new(db)),
+        }
+    }
+}
+
+/// Create schedule routes (nested under agents)
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/v1/agents/:agent_id/schedules",
+            post(create_schedule).get(list_schedules),
+        )
+        .route(
+            "/v1/agents/:agent_id/schedules/:schedule_id",
+            get(get_schedule)
+                .patch(update_schedule)
+                .delete(delete_schedule),
+        )
+        .route(
+            "/v1/agents/:agent_id/schedules/:schedule_id/runs",
+            get(list_schedule_runs),
+        )
+        .route(
+            "/v1/agents/:agent_id/schedules/:schedule_id/trigger",
+            post(trigger_schedule),
+        )
+        .with_state(state)
+}
+
+/// Create a schedule
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/schedules",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID")
+    ),
+    request_body = CreateScheduleRequest,
+    responses(
+        (status = 201, description = "Schedule created", body = Schedule),
+        (status = 400, description = "Invalid cron expression, timezone or target session", body = ErrorResponse),
+        (status = 404, description = "Agent not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    Path(agent_id): Path<Uuid>,
+    Json(req): Json<CreateScheduleRequest>,
+) -> Result<(StatusCode, Json<Schedule>), (StatusCode, Json<ErrorResponse>)> {
+    let schedule = state
+        .service
+        .create(agent_id, req)
+        .await
+        .map_err(|e| service_error("create schedule", e))?;
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+/// List an agent's schedules
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/schedules",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID")
+    ),
+    responses(
+        (status = 200, description = "List of schedules", body = ListResponse<Schedule>),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn list_schedules(
+    State(state): State<AppState>,
+    Path(agent_id): Path<Uuid>,
+) -> Result<Json<ListResponse<Schedule>>, (StatusCode, Json<ErrorResponse>)> {
+    let schedules = state
+        .service
+        .list(agent_id)
+        .await
+        .map_err(|e| service_error("list schedules", e))?;
+
+    Ok(Json(ListResponse::new(schedules)))
+}
+
+/// Get a schedule
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/schedules/{schedule_id}",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("schedule_id" = Uuid, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 200, description = "Schedule found", body = Schedule),
+        (status = 404, description = "Schedule not found", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn get_schedule(
+    State(state): State<AppState>,
+    Path((agent_id, schedule_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Schedule>, (StatusCode, Json<ErrorResponse>)> {
+    let schedule = state
+        .service
+        .get(agent_id, schedule_id)
+        .await
+        .map_err(|e| service_error("get schedule", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(schedule))
+}
+
+/// Update a schedule
+///
+/// Changing the cron expression or timezone, or enabling the schedule,
+/// recomputes the next occurrence from now.
+#[utoipa::path(
+    patch,
+    path = "/v1/agents/{agent_id}/schedules/{schedule_id}",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("schedule_id" = Uuid, Path, description = "Schedule ID")
+    ),
+    request_body = UpdateScheduleRequest,
+    responses(
+        (status = 200, description = "Schedule updated", body = Schedule),
+        (status = 400, description = "Invalid cron expression or timezone", body = ErrorResponse),
+        (status = 404, description = "Schedule not found", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn update_schedule(
+    State(state): State<AppState>,
+    Path((agent_id, schedule_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateScheduleRequest>,
+) -> Result<Json<Schedule>, (StatusCode, Json<ErrorResponse>)> {
+    let schedule = state
+        .service
+        .update(agent_id, schedule_id, req)
+        .await
+        .map_err(|e| service_error("update schedule", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(schedule))
+}
+
+/// Delete a schedule and its run history
+#[utoipa::path(
+    delete,
+    path = "/v1/agents/{agent_id}/schedules/{schedule_id}",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("schedule_id" = Uuid, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 204, description = "Schedule deleted"),
+        (status = 404, description = "Schedule not found", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn delete_schedule(
+    State(state): State<AppState>,
+    Path((agent_id, schedule_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let deleted = state
+        .service
+        .delete(agent_id, schedule_id)
+        .await
+        .map_err(|e| service_error("delete schedule", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
+
+/// List a schedule's runs, newest first
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/schedules/{schedule_id}/runs",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("schedule_id" = Uuid, Path, description = "Schedule ID"),
+        ListScheduleRunsQuery
+    ),
+    responses(
+        (status = 200, description = "Run history", body = ListResponse<ScheduleRun>),
+        (status = 404, description = "Schedule not found", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn list_schedule_runs(
+    State(state): State<AppState>,
+    Path((agent_id, schedule_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<ListScheduleRunsQuery>,
+) -> Result<Json<ListResponse<ScheduleRun>>, (StatusCode, Json<ErrorResponse>)> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    let runs = state
+        .service
+        .list_runs(agent_id, schedule_id, limit)
+        .await
+        .map_err(|e| service_error("list schedule runs", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(ListResponse::new(runs)))
+}
+
+/// Run a schedule now
+///
+/// Records a run for the current time, dispatched by the scheduler like any
+/// other run. Works for disabled schedules too.
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/schedules/{schedule_id}/trigger",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("schedule_id" = Uuid, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 202, description = "Run recorded", body = ScheduleRun),
+        (status = 404, description = "Schedule not found", body = ErrorResponse)
+    ),
+    tag = "schedules"
+)]
+pub async fn trigger_schedule(
+    State(state): State<AppState>,
+    Path((agent_id, schedule_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<ScheduleRun>), (StatusCode, Json<ErrorResponse>)> {
+    let run = state
+        .service
+        .trigger(agent_id, schedule_id)
+        .await
+        .map_err(|e| service_error("trigger schedule", e))?
+        .ok_or_else(not_found)?;
+
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+fn not_found() -> (StatusCode, Json<ErrorResponse>) {
+    ErrorResponse::new("Schedule not found").into_response(StatusCode::NOT_FOUND)
+}
+
+/// Map a service error to a response; validation failures are a client-visible 400
+fn service_error(action: &str, e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    let error_msg = e.to_string();
+    if error_msg.contains("Agent not found") {
+        return ErrorResponse::new(error_msg).into_response(StatusCode::NOT_FOUND);
+    }
+    if error_msg.contains("Invalid cron expression")
+        || error_msg.contains("Invalid timezone")
+        || error_msg.contains("Schedule name")
+        || error_msg.contains("Schedule message")
+        || error_msg.contains("Target session")
+    {
+        return ErrorResponse::new(error_msg).into_response(StatusCode::BAD_REQUEST);
+    }
+    tracing::error!("Failed to {}: {}", action, e);
+    ErrorResponse::new("Internal server error").into_response(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_create_request_defaults() {
+        let req: CreateScheduleRequest = serde_json::from_value(json!({
+            "name": "Daily report",
+            "cron": "0 9 * * *",
+            "message": "Write the daily report"
+        }))
+        .unwrap();
+
+        assert_eq!(req.timezone, "UTC");
+        assert_eq!(req.missed_run_policy, MissedRunPolicy::RunOnce);
+        assert_eq!(req.overlap_policy, OverlapPolicy::Skip);
+        assert!(req.enabled);
+        assert!(req.session_id.is_none());
+    }
+
+    #[test]
+    fn test_policies_use_snake_case() {
+        let req: UpdateScheduleRequest = serde_json::from_value(json!({
+            "missed_run_policy": "run_all",
+            "overlap_policy": "allow"
+        }))
+        .unwrap();
+
+        assert_eq!(req.missed_run_policy, Some(MissedRunPolicy::RunAll));
+        assert_eq!(req.overlap_policy, Some(OverlapPolicy::Allow));
+    }
+}
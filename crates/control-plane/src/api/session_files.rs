@@ -2,25 +2,45 @@
 //
 // RESTful API design:
 // - GET    /fs/*path  - Read file content or list directory
-// - POST   /fs/*path  - Create file or directory
-// - PUT    /fs/*path  - Update file content
+//                       (raw bytes with ?raw=true, Accept: application/octet-stream
+//                       or a Range header)
+// - POST   /fs/*path  - Create file or directory (JSON or raw octet-stream body)
+// - PUT    /fs/*path  - Update file content (JSON or raw octet-stream body)
 // - DELETE /fs/*path  - Delete file or directory
 // - POST   /fs/_/move - Move/rename file
 // - POST   /fs/_/copy - Copy file
 // - POST   /fs/_/grep - Search files
+// - POST   /fs/_/glob - Find files by path pattern
 // - POST   /fs/_/stat - Get file metadata
+// - POST   /fs/_/versions - List a file's versions
+// - POST   /fs/_/diff     - Diff two versions of a file
+// - POST   /fs/_/restore  - Restore a file or the whole tree to a version,
+//                           snapshot or turn
+// - POST   /fs/_/import   - Unpack a tar.gz or zip archive into a directory
+// - GET    /fs/_/export   - Download a file or subtree as tar.gz or zip
+// - GET    /snapshots     - List snapshots of the file tree
+// - POST   /snapshots     - Take a named snapshot
+// - GET    /snapshots/:id - Get a snapshot
+// - DELETE /snapshots/:id - Delete a snapshot
 //
 // Note: Paths starting with "_" are reserved for actions and cannot be
 // used for file creation or updates.
 
-use crate::storage::Database;
+use crate::services::ArchiveFormat;
+use crate::storage::{ByteStream, Database, FileContentStore};
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{FromRequest, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
-use everruns_core::{FileInfo, FileStat, GrepResult, SessionFile};
+use everruns_core::{
+    FileDiff, FileInfo, FileStat, FileVersion, GrepResult, RestoreTarget, SessionFile,
+    SessionSnapshot,
+};
+use futures::{StreamExt, TryStreamExt};
 
 use super::common::ListResponse;
 use serde::{Deserialize, Serialize};
@@ -29,10 +49,13 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::services::session_file::{
-    CopyFileInput, CreateDirectoryInput, CreateFileInput, GrepInput, MoveFileInput,
-    SessionFileService, UpdateFileInput,
+    CopyFileInput, CreateDirectoryInput, CreateFileInput, FileDownload, GrepInput, MoveFileInput,
+    SessionFileLimits, SessionFileService, UpdateFileInput,
 };
 
+/// Content type for raw file uploads and downloads
+const OCTET_STREAM: &str = "application/octet-stream";
+
 /// Request to create a file
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct CreateFileRequest {
@@ -92,6 +115,13 @@ pub struct GrepRequest {
     pub path_pattern: Option<String>,
 }
 
+/// Request to find files by path pattern
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct GlobRequest {
+    /// Glob pattern matched against absolute paths (`*`, `?`, `**`, `{a,b}`)
+    pub pattern: String,
+}
+
 /// Request to get file stat
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct StatRequest {
@@ -99,12 +129,121 @@ pub struct StatRequest {
     pub path: String,
 }
 
+/// Request to list a file's versions
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ListVersionsRequest {
+    /// Path to the file
+    pub path: String,
+}
+
+/// Request to diff two versions of a file
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct DiffRequest {
+    /// Path to the file
+    pub path: String,
+    /// Older version (defaults to the version before `to_version`)
+    #[serde(default)]
+    pub from_version: Option<i32>,
+    /// Newer version (defaults to the latest)
+    #[serde(default)]
+    pub to_version: Option<i32>,
+}
+
+/// Request to restore a file or the whole tree.
+///
+/// With `path`, restores that file from exactly one of `version`,
+/// `snapshot_id` or `turn_id`. Without `path`, restores the whole tree from
+/// `snapshot_id` or `turn_id`. A turn resolves to the snapshot taken when it
+/// ended, or when it started if it never finished.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RestoreRequest {
+    /// File to restore (omit to restore the whole tree)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// File version number
+    #[serde(default)]
+    pub version: Option<i32>,
+    /// Snapshot to restore from
+    #[serde(default)]
+    pub snapshot_id: Option<Uuid>,
+    /// Turn to restore to
+    #[serde(default)]
+    pub turn_id: Option<Uuid>,
+}
+
+impl RestoreRequest {
+    fn target(&self) -> Result<RestoreTarget, String> {
+        match (self.version, self.snapshot_id, self.turn_id) {
+            (Some(version), None, None) => Ok(RestoreTarget::Version(version)),
+            (None, Some(snapshot_id), None) => Ok(RestoreTarget::Snapshot(snapshot_id)),
+            (None, None, Some(turn_id)) => Ok(RestoreTarget::Turn(turn_id)),
+            _ => Err("Specify exactly one of version, snapshot_id or turn_id".to_string()),
+        }
+    }
+}
+
+/// Restore result: the file for single-file restores, otherwise the snapshot
+/// that was applied
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum RestoreResponse {
+    File(SessionFile),
+    Snapshot(SessionSnapshot),
+}
+
+/// Request to take a snapshot
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateSnapshotRequest {
+    /// Unique snapshot name (the `turn-` prefix is reserved)
+    pub name: String,
+}
+
+/// Query parameters for archive imports
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ImportQuery {
+    /// Directory to unpack into (default "/")
+    #[serde(default = "root_path")]
+    pub path: String,
+    /// Replace existing files instead of failing
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Query parameters for archive exports
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ExportQuery {
+    /// File or directory to export (default "/")
+    #[serde(default = "root_path")]
+    pub path: String,
+    /// Archive format: "tar.gz" (default) or "zip"
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+fn root_path() -> String {
+    "/".to_string()
+}
+
+/// Result of an archive import
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ImportResponse {
+    /// Directory the archive was unpacked into
+    pub path: String,
+    /// Number of files written
+    pub file_count: usize,
+    /// Total size of the written files
+    pub size_bytes: i64,
+}
+
 /// Query parameters for GET requests
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct GetQuery {
     /// For directories: whether to list recursively
     #[serde(default)]
     pub recursive: bool,
+    /// For files: return raw bytes instead of the JSON file object
+    #[serde(default)]
+    pub raw: bool,
 }
 
 /// Query parameters for DELETE requests
@@ -136,9 +275,13 @@ pub struct AppState {
 }
 
 impl AppState {
-    pub fn new(db: Arc<Database>) -> Self {
+    pub fn new(
+        db: Arc<Database>,
+        content: Arc<FileContentStore>,
+        limits: SessionFileLimits,
+    ) -> Self {
         Self {
-            file_service: Arc::new(SessionFileService::new(db)),
+            file_service: Arc::new(SessionFileService::new(db, content).with_limits(limits)),
         }
     }
 }
@@ -159,10 +302,43 @@ pub fn routes(state: AppState) -> Router {
             "/v1/agents/:agent_id/sessions/:session_id/fs/_/grep",
             post(grep_files),
         )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fs/_/glob",
+            post(glob_files),
+        )
         .route(
             "/v1/agents/:agent_id/sessions/:session_id/fs/_/stat",
             post(stat_file),
         )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fs/_/versions",
+            post(list_versions),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fs/_/diff",
+            post(diff_file),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fs/_/restore",
+            post(restore),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fs/_/import",
+            post(import_archive),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fs/_/export",
+            get(export_archive),
+        )
+        // Snapshots
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/snapshots",
+            get(list_snapshots).post(create_snapshot),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/snapshots/:snapshot_id",
+            get(get_snapshot).delete(delete_snapshot),
+        )
         // File operations with path
         .route(
             "/v1/agents/:agent_id/sessions/:session_id/fs",
@@ -194,6 +370,86 @@ fn is_reserved_path(path: &str) -> bool {
     path.starts_with('_') || path.split('/').any(|segment| segment.starts_with('_'))
 }
 
+// Map version and snapshot errors to client errors where the caller is at fault
+fn history_error(action: &str, e: anyhow::Error) -> (StatusCode, String) {
+    let msg = e.to_string();
+    if msg.contains("not found") || msg.contains("No versions") || msg.contains("No snapshot") {
+        (StatusCode::NOT_FOUND, msg)
+    } else if msg.contains("already exists") {
+        (StatusCode::CONFLICT, msg)
+    } else if msg.contains("Invalid")
+        || msg.contains("Cannot")
+        || msg.contains("No earlier version")
+    {
+        (StatusCode::BAD_REQUEST, msg)
+    } else {
+        tracing::error!("Failed to {}: {}", action, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal server error".to_string(),
+        )
+    }
+}
+
+// Raw uploads send file bytes directly instead of a JSON body
+fn is_raw_upload(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(OCTET_STREAM))
+}
+
+// Raw downloads are requested explicitly or implied by a Range header
+fn wants_raw_download(headers: &HeaderMap, query: &GetQuery) -> bool {
+    query.raw
+        || headers.contains_key(header::RANGE)
+        || headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains(OCTET_STREAM))
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+fn request_body_stream(request: Request) -> ByteStream {
+    request
+        .into_body()
+        .into_data_stream()
+        .map_err(std::io::Error::other)
+        .boxed()
+}
+
+// Build a streaming response for raw file content
+fn raw_file_response(download: FileDownload) -> Response {
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, OCTET_STREAM)
+        .header(header::ACCEPT_RANGES, "bytes");
+    let builder = match download.range {
+        Some(range) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!(
+                    "bytes {}-{}/{}",
+                    range.start, range.end, download.size_bytes
+                ),
+            )
+            .header(header::CONTENT_LENGTH, range.length()),
+        None => builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, download.size_bytes),
+    };
+
+    builder
+        .body(Body::from_stream(download.body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
 /// GET /fs - Get root directory listing
 #[utoipa::path(
     get,
@@ -213,8 +469,9 @@ pub async fn get_root(
     State(state): State<AppState>,
     Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
     Query(query): Query<GetQuery>,
-) -> Result<Json<GetResponse>, StatusCode> {
-    get_path_impl(state, session_id, "/", query).await
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    get_path_impl(state, session_id, "/", query, &headers).await
 }
 
 /// GET /fs/*path - Get file content or directory listing
@@ -225,11 +482,15 @@ pub async fn get_root(
         ("agent_id" = Uuid, Path, description = "Agent ID"),
         ("session_id" = Uuid, Path, description = "Session ID"),
         ("path" = String, Path, description = "File or directory path"),
-        ("recursive" = Option<bool>, Query, description = "List recursively")
+        ("recursive" = Option<bool>, Query, description = "List recursively"),
+        ("raw" = Option<bool>, Query, description = "Return raw file bytes instead of JSON"),
+        ("Range" = Option<String>, Header, description = "Byte range of the file to return (implies raw)")
     ),
     responses(
         (status = 200, description = "File content or directory listing"),
+        (status = 206, description = "Partial raw file content"),
         (status = 404, description = "Not found"),
+        (status = 416, description = "Range not satisfiable"),
         (status = 500, description = "Internal server error")
     ),
     tag = "filesystem"
@@ -238,9 +499,10 @@ pub async fn get_path(
     State(state): State<AppState>,
     Path((_agent_id, session_id, path)): Path<(Uuid, Uuid, String)>,
     Query(query): Query<GetQuery>,
-) -> Result<Json<GetResponse>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
     let normalized = normalize_path(&path);
-    get_path_impl(state, session_id, &normalized, query).await
+    get_path_impl(state, session_id, &normalized, query, &headers).await
 }
 
 async fn get_path_impl(
@@ -248,7 +510,8 @@ async fn get_path_impl(
     session_id: Uuid,
     path: &str,
     query: GetQuery,
-) -> Result<Json<GetResponse>, StatusCode> {
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
     // Check if path is a directory or file
     let stat = state
         .file_service
@@ -271,7 +534,23 @@ async fn get_path_impl(
                 tracing::error!("Failed to list: {}", e);
                 StatusCode::INTERNAL_SERVER_ERROR
             })?;
-            Ok(Json(GetResponse::Listing(ListResponse::new(files))))
+            Ok(Json(GetResponse::Listing(ListResponse::new(files))).into_response())
+        }
+        Some(s) if wants_raw_download(headers, &query) => {
+            let range = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+            match state.file_service.open_file(session_id, path, range).await {
+                Ok(Some(download)) => Ok(raw_file_response(download)),
+                Ok(None) => Err(StatusCode::NOT_FOUND),
+                Err(e) if e.to_string().contains("Range not satisfiable") => Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, format!("bytes */{}", s.size_bytes))],
+                )
+                    .into_response()),
+                Err(e) => {
+                    tracing::error!("Failed to open file: {}", e);
+                    Err(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            }
         }
         Some(_) => {
             // Read file
@@ -284,12 +563,12 @@ async fn get_path_impl(
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?
                 .ok_or(StatusCode::NOT_FOUND)?;
-            Ok(Json(GetResponse::File(file)))
+            Ok(Json(GetResponse::File(file)).into_response())
         }
         None => {
             // For root path, return empty listing
             if path == "/" {
-                Ok(Json(GetResponse::Listing(ListResponse::new(vec![]))))
+                Ok(Json(GetResponse::Listing(ListResponse::new(vec![]))).into_response())
             } else {
                 Err(StatusCode::NOT_FOUND)
             }
@@ -314,9 +593,12 @@ pub async fn create_root() -> (StatusCode, String) {
         ("session_id" = Uuid, Path, description = "Session ID"),
         ("path" = String, Path, description = "File or directory path")
     ),
-    request_body = CreateFileRequest,
+    request_body(content(
+        (CreateFileRequest = "application/json"),
+        ("application/octet-stream")
+    )),
     responses(
-        (status = 201, description = "Created successfully", body = SessionFile),
+        (status = 201, description = "Created successfully (FileInfo for raw uploads)", body = SessionFile),
         (status = 400, description = "Invalid request"),
         (status = 409, description = "Already exists"),
         (status = 500, description = "Internal server error")
@@ -326,8 +608,8 @@ pub async fn create_root() -> (StatusCode, String) {
 pub async fn create_path(
     State(state): State<AppState>,
     Path((_agent_id, session_id, path)): Path<(Uuid, Uuid, String)>,
-    Json(req): Json<CreateFileRequest>,
-) -> Result<(StatusCode, Json<SessionFile>), (StatusCode, String)> {
+    request: Request,
+) -> Result<Response, (StatusCode, String)> {
     let normalized = normalize_path(&path);
 
     // Paths starting with _ are reserved for actions
@@ -338,6 +620,40 @@ pub async fn create_path(
         ));
     }
 
+    if is_raw_upload(request.headers()) {
+        let length = content_length(request.headers());
+        let info = state
+            .file_service
+            .create_file_stream(
+                session_id,
+                &normalized,
+                request_body_stream(request),
+                length,
+            )
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to upload file: {}", e);
+                let msg = e.to_string();
+                if msg.contains("already exists") {
+                    (StatusCode::CONFLICT, msg)
+                } else if msg.contains("Invalid") || msg.contains("mismatch") {
+                    (StatusCode::BAD_REQUEST, msg)
+                } else if msg.contains("quota") {
+                    (StatusCode::PAYLOAD_TOO_LARGE, msg)
+                } else {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                }
+            })?;
+        return Ok((StatusCode::CREATED, Json(info)).into_response());
+    }
+
+    let Json(req) = Json::<CreateFileRequest>::from_request(request, &())
+        .await
+        .map_err(|e| (e.status(), e.body_text()))?;
+
     if req.is_directory.unwrap_or(false) {
         // Create directory
         let dir = state
@@ -374,7 +690,8 @@ pub async fn create_path(
                 created_at: dir.created_at,
                 updated_at: dir.updated_at,
             }),
-        ))
+        )
+            .into_response())
     } else {
         // Create file
         let file = state
@@ -396,6 +713,8 @@ pub async fn create_path(
                     (StatusCode::CONFLICT, msg)
                 } else if msg.contains("Invalid") || msg.contains("cannot") {
                     (StatusCode::BAD_REQUEST, msg)
+                } else if msg.contains("quota") {
+                    (StatusCode::PAYLOAD_TOO_LARGE, msg)
                 } else {
                     (
                         StatusCode::INTERNAL_SERVER_ERROR,
@@ -403,7 +722,7 @@ pub async fn create_path(
                     )
                 }
             })?;
-        Ok((StatusCode::CREATED, Json(file)))
+        Ok((StatusCode::CREATED, Json(file)).into_response())
     }
 }
 
@@ -416,9 +735,12 @@ pub async fn create_path(
         ("session_id" = Uuid, Path, description = "Session ID"),
         ("path" = String, Path, description = "File path")
     ),
-    request_body = UpdateFileRequest,
+    request_body(content(
+        (UpdateFileRequest = "application/json"),
+        ("application/octet-stream")
+    )),
     responses(
-        (status = 200, description = "Updated successfully", body = SessionFile),
+        (status = 200, description = "Updated successfully (FileInfo for raw uploads)", body = SessionFile),
         (status = 400, description = "Cannot modify readonly file or directory"),
         (status = 404, description = "Not found"),
         (status = 500, description = "Internal server error")
@@ -428,8 +750,8 @@ pub async fn create_path(
 pub async fn update_path(
     State(state): State<AppState>,
     Path((_agent_id, session_id, path)): Path<(Uuid, Uuid, String)>,
-    Json(req): Json<UpdateFileRequest>,
-) -> Result<Json<SessionFile>, (StatusCode, String)> {
+    request: Request,
+) -> Result<Response, (StatusCode, String)> {
     let normalized = normalize_path(&path);
 
     // Paths starting with _ are reserved for actions
@@ -440,6 +762,40 @@ pub async fn update_path(
         ));
     }
 
+    if is_raw_upload(request.headers()) {
+        let length = content_length(request.headers());
+        let info = state
+            .file_service
+            .update_file_stream(
+                session_id,
+                &normalized,
+                request_body_stream(request),
+                length,
+            )
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to upload file: {}", e);
+                let msg = e.to_string();
+                if msg.contains("readonly") || msg.contains("directory") || msg.contains("mismatch")
+                {
+                    (StatusCode::BAD_REQUEST, msg)
+                } else if msg.contains("quota") {
+                    (StatusCode::PAYLOAD_TOO_LARGE, msg)
+                } else {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )
+                }
+            })?
+            .ok_or((StatusCode::NOT_FOUND, "File not found".to_string()))?;
+        return Ok(Json(info).into_response());
+    }
+
+    let Json(req) = Json::<UpdateFileRequest>::from_request(request, &())
+        .await
+        .map_err(|e| (e.status(), e.body_text()))?;
+
     let input = UpdateFileInput {
         content: req.content,
         encoding: req.encoding,
@@ -455,6 +811,8 @@ pub async fn update_path(
             let msg = e.to_string();
             if msg.contains("readonly") || msg.contains("directory") {
                 (StatusCode::BAD_REQUEST, msg)
+            } else if msg.contains("quota") {
+                (StatusCode::PAYLOAD_TOO_LARGE, msg)
             } else {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -464,7 +822,7 @@ pub async fn update_path(
         })?
         .ok_or((StatusCode::NOT_FOUND, "File not found".to_string()))?;
 
-    Ok(Json(file))
+    Ok(Json(file).into_response())
 }
 
 /// DELETE /fs - Delete root (not allowed)
@@ -613,6 +971,8 @@ pub async fn copy_file(
                 (StatusCode::CONFLICT, msg)
             } else if msg.contains("Cannot copy") || msg.contains("Invalid") {
                 (StatusCode::BAD_REQUEST, msg)
+            } else if msg.contains("quota") {
+                (StatusCode::PAYLOAD_TOO_LARGE, msg)
             } else {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -671,6 +1031,47 @@ pub async fn grep_files(
     Ok(Json(ListResponse::new(results)))
 }
 
+/// POST /fs/_/glob - Find files by path pattern
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fs/_/glob",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = GlobRequest,
+    responses(
+        (status = 200, description = "Matching files and directories", body = ListResponse<FileInfo>),
+        (status = 400, description = "Invalid glob pattern"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn glob_files(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<GlobRequest>,
+) -> Result<Json<ListResponse<FileInfo>>, (StatusCode, String)> {
+    let files = state
+        .file_service
+        .glob(session_id, &req.pattern)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("Invalid") {
+                (StatusCode::BAD_REQUEST, msg)
+            } else {
+                tracing::error!("Failed to glob files: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+        })?;
+
+    Ok(Json(ListResponse::new(files)))
+}
+
 /// POST /fs/_/stat - Get file or directory stat
 #[utoipa::path(
     post,
@@ -709,3 +1110,379 @@ pub async fn stat_file(
 
     Ok(Json(stat))
 }
+
+/// POST /fs/_/versions - List a file's versions
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fs/_/versions",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = ListVersionsRequest,
+    responses(
+        (status = 200, description = "Versions, newest first", body = ListResponse<FileVersion>),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn list_versions(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<ListVersionsRequest>,
+) -> Result<Json<ListResponse<FileVersion>>, (StatusCode, String)> {
+    let versions = state
+        .file_service
+        .list_versions(session_id, &req.path)
+        .await
+        .map_err(|e| history_error("list file versions", e))?;
+
+    Ok(Json(ListResponse::new(versions)))
+}
+
+/// POST /fs/_/diff - Diff two versions of a file
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fs/_/diff",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = DiffRequest,
+    responses(
+        (status = 200, description = "Unified diff", body = FileDiff),
+        (status = 400, description = "No earlier version to diff against"),
+        (status = 404, description = "Version not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn diff_file(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<DiffRequest>,
+) -> Result<Json<FileDiff>, (StatusCode, String)> {
+    let diff = state
+        .file_service
+        .diff(session_id, &req.path, req.from_version, req.to_version)
+        .await
+        .map_err(|e| history_error("diff file", e))?;
+
+    Ok(Json(diff))
+}
+
+/// POST /fs/_/restore - Restore a file or the whole tree
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fs/_/restore",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = RestoreRequest,
+    responses(
+        (status = 200, description = "Restored file, or the snapshot applied to the tree", body = RestoreResponse),
+        (status = 400, description = "Invalid restore target"),
+        (status = 404, description = "Version, snapshot or turn not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn restore(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<RestoreResponse>, (StatusCode, String)> {
+    let target = req.target().map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+
+    let response = match &req.path {
+        Some(path) => state
+            .file_service
+            .restore_file(session_id, &normalize_path(path), target)
+            .await
+            .map(RestoreResponse::File),
+        None => state
+            .file_service
+            .restore_snapshot(session_id, target)
+            .await
+            .map(RestoreResponse::Snapshot),
+    }
+    .map_err(|e| history_error("restore", e))?;
+
+    Ok(Json(response))
+}
+
+/// POST /fs/_/import - Unpack an archive into the session filesystem
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fs/_/import",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID"),
+        ("path" = Option<String>, Query, description = "Directory to unpack into (default /)"),
+        ("overwrite" = Option<bool>, Query, description = "Replace existing files")
+    ),
+    request_body(content(
+        ("application/gzip"),
+        ("application/zip")
+    ), description = "tar.gz or zip archive (format is detected from the content)"),
+    responses(
+        (status = 201, description = "Archive imported", body = ImportResponse),
+        (status = 400, description = "Invalid archive or path"),
+        (status = 409, description = "A file already exists and overwrite is false"),
+        (status = 413, description = "Archive too large or session file quota exceeded"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn import_archive(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<ImportQuery>,
+    request: Request,
+) -> Result<(StatusCode, Json<ImportResponse>), (StatusCode, String)> {
+    let dest = normalize_path(&query.path);
+    if is_reserved_path(&dest) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Paths starting with '_' are reserved for system actions".to_string(),
+        ));
+    }
+
+    let limit = state.file_service.limits().max_import_bytes;
+    let too_large = || {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Archive exceeds the import size limit of {} bytes", limit),
+        )
+    };
+    if content_length(request.headers()).is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+    let data = axum::body::to_bytes(request.into_body(), limit as usize)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("length limit") {
+                too_large()
+            } else {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read archive: {}", e),
+                )
+            }
+        })?;
+
+    let files = state
+        .file_service
+        .import_archive(session_id, &dest, data, query.overwrite)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("already exists") {
+                (StatusCode::CONFLICT, msg)
+            } else if msg.contains("quota") || msg.contains("limit") {
+                (StatusCode::PAYLOAD_TOO_LARGE, msg)
+            } else if msg.contains("Invalid")
+                || msg.contains("Cannot")
+                || msg.contains("A file exists")
+            {
+                (StatusCode::BAD_REQUEST, msg)
+            } else {
+                tracing::error!("Failed to import archive: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+        })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ImportResponse {
+            path: dest,
+            file_count: files.len(),
+            size_bytes: files.iter().map(|f| f.size_bytes).sum(),
+        }),
+    ))
+}
+
+/// GET /fs/_/export - Download a file or directory subtree as an archive
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fs/_/export",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID"),
+        ("path" = Option<String>, Query, description = "File or directory to export (default /)"),
+        ("format" = Option<String>, Query, description = "Archive format: tar.gz (default) or zip")
+    ),
+    responses(
+        (status = 200, description = "Archive stream (application/gzip or application/zip)"),
+        (status = 400, description = "Invalid format"),
+        (status = 404, description = "Path not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn export_archive(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let format = match query.format.as_deref() {
+        Some(format) => format
+            .parse::<ArchiveFormat>()
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?,
+        None => ArchiveFormat::default(),
+    };
+    let path = normalize_path(&query.path);
+
+    let body = state
+        .file_service
+        .export_archive(session_id, &path, format)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to export archive: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Internal server error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, format!("Path not found: {}", path)))?;
+
+    let name = if path == "/" {
+        format!("session-{}", session_id)
+    } else {
+        FileInfo::name_from_path(&path).replace('"', "_")
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.{}\"", name, format.extension()),
+        )
+        .body(Body::from_stream(body))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// GET /snapshots - List snapshots of the session file tree
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/snapshots",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 200, description = "Snapshots, newest first", body = ListResponse<SessionSnapshot>),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn list_snapshots(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ListResponse<SessionSnapshot>>, (StatusCode, String)> {
+    let snapshots = state
+        .file_service
+        .list_snapshots(session_id)
+        .await
+        .map_err(|e| history_error("list snapshots", e))?;
+
+    Ok(Json(ListResponse::new(snapshots)))
+}
+
+/// POST /snapshots - Take a named snapshot of the session file tree
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/snapshots",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = CreateSnapshotRequest,
+    responses(
+        (status = 201, description = "Snapshot created", body = SessionSnapshot),
+        (status = 400, description = "Invalid snapshot name"),
+        (status = 409, description = "Snapshot name already used"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn create_snapshot(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<CreateSnapshotRequest>,
+) -> Result<(StatusCode, Json<SessionSnapshot>), (StatusCode, String)> {
+    let snapshot = state
+        .file_service
+        .create_snapshot(session_id, &req.name)
+        .await
+        .map_err(|e| history_error("create snapshot", e))?;
+
+    Ok((StatusCode::CREATED, Json(snapshot)))
+}
+
+/// GET /snapshots/:snapshot_id - Get a snapshot
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/snapshots/{snapshot_id}",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID"),
+        ("snapshot_id" = Uuid, Path, description = "Snapshot ID")
+    ),
+    responses(
+        (status = 200, description = "Snapshot", body = SessionSnapshot),
+        (status = 404, description = "Snapshot not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn get_snapshot(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id, snapshot_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Json<SessionSnapshot>, (StatusCode, String)> {
+    let snapshot = state
+        .file_service
+        .get_snapshot(session_id, snapshot_id)
+        .await
+        .map_err(|e| history_error("get snapshot", e))?
+        .ok_or((StatusCode::NOT_FOUND, "Snapshot not found".to_string()))?;
+
+    Ok(Json(snapshot))
+}
+
+/// DELETE /snapshots/:snapshot_id - Delete a snapshot (file versions are kept)
+#[utoipa::path(
+    delete,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/snapshots/{snapshot_id}",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID"),
+        ("snapshot_id" = Uuid, Path, description = "Snapshot ID")
+    ),
+    responses(
+        (status = 204, description = "Snapshot deleted"),
+        (status = 404, description = "Snapshot not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "filesystem"
+)]
+pub async fn delete_snapshot(
+    State(state): State<AppState>,
+    Path((_agent_id, session_id, snapshot_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let deleted = state
+        .file_service
+        .delete_snapshot(session_id, snapshot_id)
+        .await
+        .map_err(|e| history_error("delete snapshot", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, "Snapshot not found".to_string()))
+    }
+}
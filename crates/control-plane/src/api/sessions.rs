@@ -1,6 +1,7 @@
 // Session CRUD HTTP routes
 
-use crate::storage::Database;
+use crate::auth::middleware::{AuthState, FromRef, OptionalAuthUser};
+use crate::storage::{Database, FileContentStore};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -11,6 +12,7 @@ use everruns_core::Session;
 
 use super::common::ListResponse;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -30,6 +32,16 @@ pub struct CreateSessionRequest {
     /// Overrides the agent's default model if specified.
     #[serde(default)]
     pub model_id: Option<Uuid>,
+    /// Use the agent's latest revision on every turn instead of pinning the
+    /// revision current at creation.
+    #[serde(default)]
+    pub follow_latest_revision: bool,
+    /// Values for the variables in the agent's system prompt template.
+    /// Every variable the template references, other than the built-ins
+    /// (`current_date`, `session_id`, `user_name`), must be provided.
+    #[serde(default)]
+    #[schema(example = json!({"customer": "Acme Corp"}))]
+    pub variables: BTreeMap<String, String>,
 }
 
 /// Request to update a session. Only provided fields will be updated.
@@ -45,22 +57,55 @@ pub struct UpdateSessionRequest {
     pub tags: Option<Vec<String>>,
 }
 
+/// Request to fork a session. The fork point is a turn or a message; if
+/// neither is given the whole history is copied.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct ForkSessionRequest {
+    /// Copy events up to and including the end of this turn.
+    #[serde(default)]
+    pub turn_id: Option<Uuid>,
+    /// Copy events up to and including this message.
+    #[serde(default)]
+    pub message_id: Option<Uuid>,
+    /// Also copy the session's files as of the fork point.
+    #[serde(default)]
+    pub include_files: bool,
+    /// Title for the new session. Defaults to the parent's title.
+    #[serde(default)]
+    #[schema(example = "Retry with a different model")]
+    pub title: Option<String>,
+    /// Tags for the new session. Defaults to the parent's tags.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// LLM model for the new session. Defaults to the parent's model.
+    #[serde(default)]
+    pub model_id: Option<Uuid>,
+}
+
 use crate::services::SessionService;
 
 /// App state for sessions routes
 #[derive(Clone)]
 pub struct AppState {
     pub session_service: Arc<SessionService>,
+    pub auth: AuthState,
 }
 
 impl AppState {
-    pub fn new(db: Arc<Database>) -> Self {
+    pub fn new(db: Arc<Database>, file_content: Arc<FileContentStore>, auth: AuthState) -> Self {
         Self {
-            session_service: Arc::new(SessionService::new(db)),
+            session_service: Arc::new(SessionService::new(db).with_file_content(file_content)),
+            auth,
         }
     }
 }
 
+impl FromRef<AppState> for AuthState {
+    fn from_ref(input: &AppState) -> Self {
+        input.auth.clone()
+    }
+}
+
 /// Create session routes (nested under agents)
 pub fn routes(state: AppState) -> Router {
     Router::new()
@@ -75,6 +120,14 @@ pub fn routes(state: AppState) -> Router {
                 .patch(update_session)
                 .delete(delete_session),
         )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/fork",
+            post(fork_session),
+        )
+        .route(
+            "/v1/agents/:agent_id/sessions/:session_id/branches",
+            get(list_session_branches),
+        )
         .with_state(state)
 }
 
@@ -88,22 +141,32 @@ pub fn routes(state: AppState) -> Router {
     request_body = CreateSessionRequest,
     responses(
         (status = 201, description = "Session created successfully", body = Session),
+        (status = 400, description = "Missing required template variables"),
         (status = 500, description = "Internal server error")
     ),
     tag = "sessions"
 )]
 pub async fn create_session(
     State(state): State<AppState>,
+    OptionalAuthUser(user): OptionalAuthUser,
     Path(agent_id): Path<Uuid>,
     Json(req): Json<CreateSessionRequest>,
-) -> Result<(StatusCode, Json<Session>), StatusCode> {
+) -> Result<(StatusCode, Json<Session>), (StatusCode, String)> {
     let session = state
         .session_service
-        .create(agent_id, req)
+        .create(agent_id, req, user.map(|u| u.name))
         .await
         .map_err(|e| {
-            tracing::error!("Failed to create session: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            let msg = e.to_string();
+            if msg.contains("Missing required template variables") {
+                (StatusCode::BAD_REQUEST, msg)
+            } else {
+                tracing::error!("Failed to create session: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
         })?;
 
     Ok((StatusCode::CREATED, Json(session)))
@@ -235,6 +298,95 @@ pub async fn delete_session(
     }
 }
 
+/// POST /v1/agents/{agent_id}/sessions/{session_id}/fork - Fork a session
+#[utoipa::path(
+    post,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/fork",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    request_body = ForkSessionRequest,
+    responses(
+        (status = 201, description = "Session forked successfully", body = Session),
+        (status = 400, description = "Both turn_id and message_id given"),
+        (status = 404, description = "Session, turn or message not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "sessions"
+)]
+pub async fn fork_session(
+    State(state): State<AppState>,
+    Path((agent_id, session_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<ForkSessionRequest>,
+) -> Result<(StatusCode, Json<Session>), (StatusCode, String)> {
+    let session = state
+        .session_service
+        .fork(agent_id, session_id, req)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") {
+                (StatusCode::NOT_FOUND, msg)
+            } else if msg.contains("Cannot") {
+                (StatusCode::BAD_REQUEST, msg)
+            } else {
+                tracing::error!("Failed to fork session: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+/// GET /v1/agents/{agent_id}/sessions/{session_id}/branches - List forks of a session
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{agent_id}/sessions/{session_id}/branches",
+    params(
+        ("agent_id" = Uuid, Path, description = "Agent ID"),
+        ("session_id" = Uuid, Path, description = "Session ID")
+    ),
+    responses(
+        (status = 200, description = "Sessions forked from this session", body = ListResponse<Session>),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "sessions"
+)]
+pub async fn list_session_branches(
+    State(state): State<AppState>,
+    Path((agent_id, session_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ListResponse<Session>>, StatusCode> {
+    let session = state
+        .session_service
+        .get(session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get session: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if session.agent_id != agent_id {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let branches = state
+        .session_service
+        .branches(session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list session branches: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ListResponse::new(branches)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +431,28 @@ mod tests {
         assert_eq!(req.model_id, Some(model_uuid));
     }
 
+    #[test]
+    fn test_fork_session_request_minimal() {
+        let req: ForkSessionRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(req.turn_id, None);
+        assert_eq!(req.message_id, None);
+        assert!(!req.include_files);
+        assert_eq!(req.tags, None);
+    }
+
+    #[test]
+    fn test_fork_session_request_at_turn() {
+        let turn_id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440002").unwrap();
+        let json = format!(
+            r#"{{"turn_id": "{}", "include_files": true, "title": "Retry"}}"#,
+            turn_id
+        );
+        let req: ForkSessionRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(req.turn_id, Some(turn_id));
+        assert!(req.include_files);
+        assert_eq!(req.title, Some("Retry".to_string()));
+    }
+
     #[test]
     fn test_update_session_request_minimal() {
         let json = r#"{}"#;
@@ -0,0 +1,344 @@
+// User-defined HTTP tool API endpoints
+//
+// Tools are attached to agents by adding their `capability_id` (`custom:<name>`)
+// to the agent's capabilities, like any built-in capability.
+
+use crate::storage::{Database, EncryptionService};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use everruns_core::capabilities::CapabilityRegistry;
+use everruns_core::http_tool::{CustomTool, HttpToolDefinition, HttpToolEndpoint};
+use everruns_core::ToolRegistry;
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::common::{ErrorResponse, ListResponse};
+use crate::services::HttpToolService;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub service: Arc<HttpToolService>,
+}
+
+impl AppState {
+    pub fn new(db: Arc<Database>, encryption: Option<Arc<EncryptionService>>) -> Self {
+        Self {
+            service: Arc::new(HttpToolService::new(db, encryption)),
+        }
+    }
+}
+
+/// Request to create a user-defined HTTP tool
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateHttpToolRequest {
+    /// Tool name shown to the model. Letters, digits, '_' and '-' (max 40).
+    /// Cannot be changed after creation.
+    #[schema(example = "create_ticket")]
+    pub name: String,
+    /// What the tool does and when the model should use it.
+    pub description: String,
+    /// JSON schema of the tool arguments (`"type": "object"`).
+    #[schema(value_type = Object)]
+    pub parameters: serde_json::Value,
+    /// HTTP endpoint called when the tool is executed.
+    pub endpoint: HttpToolEndpoint,
+    /// Secret used by `endpoint.auth`. Write-only, encrypted at rest.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// Request to update a user-defined HTTP tool. Only provided fields will be updated.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateHttpToolRequest {
+    pub description: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub parameters: Option<serde_json::Value>,
+    pub endpoint: Option<HttpToolEndpoint>,
+    /// New secret for `endpoint.auth`. Write-only, encrypted at rest.
+    pub secret: Option<String>,
+}
+
+/// Create a user-defined HTTP tool
+#[utoipa::path(
+    post,
+    path = "/v1/tools",
+    request_body = CreateHttpToolRequest,
+    responses(
+        (status = 201, description = "Tool created", body = CustomTool),
+        (status = 400, description = "Invalid tool definition", body = ErrorResponse),
+        (status = 409, description = "A tool with this name already exists", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "tools"
+)]
+pub async fn create_tool(
+    State(state): State<AppState>,
+    Json(req): Json<CreateHttpToolRequest>,
+) -> Result<(StatusCode, Json<CustomTool>), (StatusCode, Json<ErrorResponse>)> {
+    validate_definition(&HttpToolDefinition {
+        name: req.name.clone(),
+        description: req.description.clone(),
+        parameters: req.parameters.clone(),
+        endpoint: req.endpoint.clone(),
+    })?;
+
+    let name = req.name.clone();
+    let tool = state.service.create(req).await.map_err(|e| {
+        if is_unique_violation(&e) {
+            return ErrorResponse::new(format!("A tool named '{}' already exists", name))
+                .into_response(StatusCode::CONFLICT);
+        }
+        service_error("create tool", e)
+    })?;
+
+    Ok((StatusCode::CREATED, Json(tool)))
+}
+
+/// List user-defined HTTP tools
+#[utoipa::path(
+    get,
+    path = "/v1/tools",
+    responses(
+        (status = 200, description = "List of tools", body = ListResponse<CustomTool>),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "tools"
+)]
+pub async fn list_tools(
+    State(state): State<AppState>,
+) -> Result<Json<ListResponse<CustomTool>>, (StatusCode, Json<ErrorResponse>)> {
+    let tools = state
+        .service
+        .list()
+        .await
+        .map_err(|e| service_error("list tools", e))?;
+
+    Ok(Json(ListResponse::new(tools)))
+}
+
+/// Get a user-defined HTTP tool
+#[utoipa::path(
+    get,
+    path = "/v1/tools/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Tool ID")
+    ),
+    responses(
+        (status = 200, description = "Tool found", body = CustomTool),
+        (status = 404, description = "Tool not found", body = ErrorResponse)
+    ),
+    tag = "tools"
+)]
+pub async fn get_tool(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CustomTool>, (StatusCode, Json<ErrorResponse>)> {
+    let tool = state
+        .service
+        .get(id)
+        .await
+        .map_err(|e| service_error("get tool", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(tool))
+}
+
+/// Update a user-defined HTTP tool
+#[utoipa::path(
+    patch,
+    path = "/v1/tools/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Tool ID")
+    ),
+    request_body = UpdateHttpToolRequest,
+    responses(
+        (status = 200, description = "Tool updated", body = CustomTool),
+        (status = 400, description = "Invalid tool definition", body = ErrorResponse),
+        (status = 404, description = "Tool not found", body = ErrorResponse)
+    ),
+    tag = "tools"
+)]
+pub async fn update_tool(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateHttpToolRequest>,
+) -> Result<Json<CustomTool>, (StatusCode, Json<ErrorResponse>)> {
+    // Validate the definition as it will be after the update
+    let mut definition = state
+        .service
+        .get_definition(id)
+        .await
+        .map_err(|e| service_error("get tool", e))?
+        .ok_or_else(not_found)?;
+    if let Some(description) = &req.description {
+        definition.description = description.clone();
+    }
+    if let Some(parameters) = &req.parameters {
+        definition.parameters = parameters.clone();
+    }
+    if let Some(endpoint) = &req.endpoint {
+        definition.endpoint = endpoint.clone();
+    }
+    validate_definition(&definition)?;
+
+    let tool = state
+        .service
+        .update(id, req)
+        .await
+        .map_err(|e| service_error("update tool", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(tool))
+}
+
+/// Delete a user-defined HTTP tool and detach it from all agents
+#[utoipa::path(
+    delete,
+    path = "/v1/tools/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Tool ID")
+    ),
+    responses(
+        (status = 204, description = "Tool deleted"),
+        (status = 404, description = "Tool not found", body = ErrorResponse)
+    ),
+    tag = "tools"
+)]
+pub async fn delete_tool(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let deleted = state
+        .service
+        .delete(id)
+        .await
+        .map_err(|e| service_error("delete tool", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/v1/tools", post(create_tool).get(list_tools))
+        .route(
+            "/v1/tools/:id",
+            get(get_tool).patch(update_tool).delete(delete_tool),
+        )
+        .with_state(state)
+}
+
+/// Validate a definition; names of tools registered on workers are reserved
+fn validate_definition(
+    definition: &HttpToolDefinition,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    definition
+        .validate()
+        .map_err(|e| ErrorResponse::new(e).into_response(StatusCode::BAD_REQUEST))?;
+
+    let registry = CapabilityRegistry::with_builtins();
+    let reserved = registry
+        .list()
+        .iter()
+        .flat_map(|capability| capability.tools())
+        .any(|tool| tool.name() == definition.name)
+        || ToolRegistry::with_defaults().has(&definition.name);
+    if reserved {
+        return Err(ErrorResponse::new(format!(
+            "Tool name '{}' is reserved by a built-in tool",
+            definition.name
+        ))
+        .into_response(StatusCode::BAD_REQUEST));
+    }
+
+    Ok(())
+}
+
+fn is_unique_violation(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<sqlx::Error>()
+        .and_then(|e| e.as_database_error())
+        .is_some_and(|e| e.is_unique_violation())
+}
+
+fn not_found() -> (StatusCode, Json<ErrorResponse>) {
+    ErrorResponse::new("Tool not found").into_response(StatusCode::NOT_FOUND)
+}
+
+/// Map a service error to a response; missing encryption is a client-visible 400
+fn service_error(action: &str, e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    let error_msg = e.to_string();
+    if error_msg.contains("Encryption not configured") {
+        return ErrorResponse::new(error_msg).into_response(StatusCode::BAD_REQUEST);
+    }
+    tracing::error!("Failed to {}: {}", action, e);
+    ErrorResponse::new("Internal server error").into_response(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use everruns_core::http_tool::HttpToolMethod;
+    use serde_json::json;
+
+    fn definition(name: &str) -> HttpToolDefinition {
+        HttpToolDefinition {
+            name: name.to_string(),
+            description: "Test tool".to_string(),
+            parameters: json!({"type": "object"}),
+            endpoint: HttpToolEndpoint {
+                method: HttpToolMethod::Get,
+                url: "https://example.com/{{id}}".to_string(),
+                headers: Default::default(),
+                body: None,
+                auth: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        assert!(validate_definition(&definition("lookup_order")).is_ok());
+        assert_eq!(
+            validate_definition(&definition("bad name")).unwrap_err().0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_builtin_tool_names_are_reserved() {
+        for name in ["web_fetch", "echo"] {
+            let (status, Json(error)) = validate_definition(&definition(name)).unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert!(error.error.contains("reserved"));
+        }
+    }
+
+    #[test]
+    fn test_create_request_deserialization() {
+        let req: CreateHttpToolRequest = serde_json::from_value(json!({
+            "name": "create_ticket",
+            "description": "Create a ticket",
+            "parameters": {"type": "object"},
+            "endpoint": {
+                "method": "POST",
+                "url": "https://example.com/tickets",
+                "body": "{\"title\": {{title}}}",
+                "auth": {"type": "header", "name": "X-Api-Key"}
+            },
+            "secret": "k"
+        }))
+        .unwrap();
+
+        assert_eq!(req.endpoint.method, HttpToolMethod::Post);
+        assert_eq!(req.secret.as_deref(), Some("k"));
+    }
+}
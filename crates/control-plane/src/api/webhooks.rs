@@ -0,0 +1,335 @@
+// Webhook subscription HTTP routes
+//
+// A subscription POSTs session events to a URL, for one agent's sessions or
+// for all sessions. Deliveries are HMAC-signed and retried through the durable
+// task queue (services::webhook_delivery).
+
+use crate::storage::{Database, EncryptionService};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::common::{ErrorResponse, ListResponse};
+use crate::services::WebhookService;
+
+/// Webhook subscription
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: Uuid,
+    /// Agent whose session events are delivered. Absent for global
+    /// subscriptions, which receive the events of all agents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<Uuid>,
+    /// Endpoint the events are POSTed to
+    #[schema(example = "https://example.com/hooks/everruns")]
+    pub url: String,
+    /// Event types delivered; empty means all events
+    #[schema(example = json!(["turn.completed", "turn.failed"]))]
+    pub event_types: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub enabled: bool,
+    /// Signing secret. Only returned when the subscription is created or the
+    /// secret is rotated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Request to create a webhook subscription
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    /// Agent whose session events are delivered. Omit for a global subscription.
+    #[serde(default)]
+    pub agent_id: Option<Uuid>,
+    /// Endpoint the events are POSTed to (http or https)
+    pub url: String,
+    /// Event types to deliver (e.g. `turn.completed`). Empty or omitted means
+    /// all events.
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Signing secret (at least 16 characters). Generated when omitted.
+    /// Write-only, encrypted at rest.
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Request to update a webhook subscription. Only provided fields will be updated.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateWebhookRequest {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub event_types: Option<Vec<String>>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// New signing secret. Write-only, encrypted at rest.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Replace the signing secret with a generated one, returned in the response
+    #[serde(default)]
+    pub rotate_secret: bool,
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// Query parameters for listing webhook subscriptions
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListWebhooksQuery {
+    /// Only list the subscriptions of this agent
+    pub agent_id: Option<Uuid>,
+}
+
+/// App state for webhook routes
+#[derive(Clone)]
+pub struct AppState {
+    pub service: Arc<WebhookService>,
+}
+
+impl AppState {
+    pub fn new(
+        db: Arc<Database>,
+        encryption: Option<Arc<EncryptionService>>,
+        allow_private_networks: bool,
+    ) -> Self {
+        Self {
+            service: Arc::new(
+                WebhookService::new(db, encryption).allow_private_networks(allow_private_networks),
+            ),
+        }
+    }
+}
+
+/// Create webhook routes
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/v1/webhooks", post(create_webhook).get(list_webhooks))
+        .route(
+            "/v1/webhooks/:id",
+            get(get_webhook)
+                .patch(update_webhook)
+                .delete(delete_webhook),
+        )
+        .with_state(state)
+}
+
+/// Create a webhook subscription
+///
+/// The response contains the signing secret; it is not returned again.
+#[utoipa::path(
+    post,
+    path = "/v1/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook created", body = Webhook),
+        (status = 400, description = "Invalid URL, event types or secret", body = ErrorResponse),
+        (status = 404, description = "Agent not found", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "webhooks"
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<Webhook>), (StatusCode, Json<ErrorResponse>)> {
+    let webhook = state
+        .service
+        .create(req)
+        .await
+        .map_err(|e| service_error("create webhook", e))?;
+
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// List webhook subscriptions
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks",
+    params(ListWebhooksQuery),
+    responses(
+        (status = 200, description = "List of webhooks", body = ListResponse<Webhook>),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "webhooks"
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Query(query): Query<ListWebhooksQuery>,
+) -> Result<Json<ListResponse<Webhook>>, (StatusCode, Json<ErrorResponse>)> {
+    let webhooks = state
+        .service
+        .list(query.agent_id)
+        .await
+        .map_err(|e| service_error("list webhooks", e))?;
+
+    Ok(Json(ListResponse::new(webhooks)))
+}
+
+/// Get a webhook subscription
+#[utoipa::path(
+    get,
+    path = "/v1/webhooks/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook found", body = Webhook),
+        (status = 404, description = "Webhook not found", body = ErrorResponse)
+    ),
+    tag = "webhooks"
+)]
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Webhook>, (StatusCode, Json<ErrorResponse>)> {
+    let webhook = state
+        .service
+        .get(id)
+        .await
+        .map_err(|e| service_error("get webhook", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(webhook))
+}
+
+/// Update a webhook subscription
+#[utoipa::path(
+    patch,
+    path = "/v1/webhooks/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID")
+    ),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = Webhook),
+        (status = 400, description = "Invalid URL, event types or secret", body = ErrorResponse),
+        (status = 404, description = "Webhook not found", body = ErrorResponse)
+    ),
+    tag = "webhooks"
+)]
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateWebhookRequest>,
+) -> Result<Json<Webhook>, (StatusCode, Json<ErrorResponse>)> {
+    let webhook = state
+        .service
+        .update(id, req)
+        .await
+        .map_err(|e| service_error("update webhook", e))?
+        .ok_or_else(not_found)?;
+
+    Ok(Json(webhook))
+}
+
+/// Delete a webhook subscription
+///
+/// Deliveries already queued are dropped when they are attempted.
+#[utoipa::path(
+    delete,
+    path = "/v1/webhooks/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 404, description = "Webhook not found", body = ErrorResponse)
+    ),
+    tag = "webhooks"
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let deleted = state
+        .service
+        .delete(id)
+        .await
+        .map_err(|e| service_error("delete webhook", e))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found())
+    }
+}
+
+fn not_found() -> (StatusCode, Json<ErrorResponse>) {
+    ErrorResponse::new("Webhook not found").into_response(StatusCode::NOT_FOUND)
+}
+
+/// Map a service error to a response; validation failures are a client-visible 400
+fn service_error(action: &str, e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    let error_msg = e.to_string();
+    if error_msg.contains("Agent not found") {
+        return ErrorResponse::new(error_msg).into_response(StatusCode::NOT_FOUND);
+    }
+    if error_msg.contains("Invalid webhook URL")
+        || error_msg.contains("Invalid event type")
+        || error_msg.contains("Webhook secret")
+        || error_msg.contains("Encryption not configured")
+    {
+        return ErrorResponse::new(error_msg).into_response(StatusCode::BAD_REQUEST);
+    }
+    tracing::error!("Failed to {}: {}", action, e);
+    ErrorResponse::new("Internal server error").into_response(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_create_request_defaults() {
+        let req: CreateWebhookRequest = serde_json::from_value(json!({
+            "url": "https://example.com/hooks"
+        }))
+        .unwrap();
+
+        assert!(req.agent_id.is_none());
+        assert!(req.event_types.is_empty());
+        assert!(req.secret.is_none());
+        assert!(req.enabled);
+    }
+
+    #[test]
+    fn test_secret_only_serialized_when_set() {
+        let now = Utc::now();
+        let mut webhook = Webhook {
+            id: Uuid::now_v7(),
+            agent_id: None,
+            url: "https://example.com/hooks".to_string(),
+            event_types: vec!["turn.completed".to_string()],
+            description: None,
+            enabled: true,
+            secret: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let value = serde_json::to_value(&webhook).unwrap();
+        assert!(value.get("secret").is_none());
+        assert!(value.get("agent_id").is_none());
+
+        webhook.secret = Some("whsec_abc".to_string());
+        let value = serde_json::to_value(&webhook).unwrap();
+        assert_eq!(value["secret"], "whsec_abc");
+    }
+}
@@ -0,0 +1,195 @@
+// CLI tool for history retention and partition maintenance.
+// Run with: cargo run --bin maintenance -- --help
+
+use anyhow::{Context, Result};
+use everruns_control_plane::services::maintenance::{
+    parse_max_versions, parse_retention_days, MaintenanceService, RetentionPolicy,
+    DEFAULT_BATCH_SIZE,
+};
+use everruns_control_plane::storage::{Database, FileContentStore};
+use sqlx::PgPool;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug)]
+struct Args {
+    dry_run: bool,
+    batch_size: usize,
+    policy: RetentionPolicy,
+}
+
+impl Args {
+    fn parse() -> Result<Self> {
+        let args: Vec<String> = env::args().collect();
+        let mut dry_run = false;
+        let mut batch_size = DEFAULT_BATCH_SIZE;
+        let mut policy = RetentionPolicy::from_env()?;
+        let mut i = 1;
+
+        while i < args.len() {
+            match args[i].as_str() {
+                "--dry-run" | "-n" => dry_run = true,
+                "--batch-size" | "-b" => {
+                    i += 1;
+                    batch_size = args
+                        .get(i)
+                        .context("--batch-size requires a value")?
+                        .parse()
+                        .context("Invalid batch size")?;
+                }
+                "--workflow-retention-days" => {
+                    i += 1;
+                    let value = args
+                        .get(i)
+                        .context("--workflow-retention-days requires a value")?;
+                    policy.workflow_retention =
+                        Some(parse_retention_days(value).context("Invalid workflow retention")?);
+                }
+                "--task-retention-days" => {
+                    i += 1;
+                    let value = args
+                        .get(i)
+                        .context("--task-retention-days requires a value")?;
+                    policy.task_retention =
+                        Some(parse_retention_days(value).context("Invalid task retention")?);
+                }
+                "--superseded-event-retention-days" => {
+                    i += 1;
+                    let value = args
+                        .get(i)
+                        .context("--superseded-event-retention-days requires a value")?;
+                    policy.superseded_event_retention = Some(
+                        parse_retention_days(value)
+                            .context("Invalid superseded event retention")?,
+                    );
+                }
+                "--file-history-retention-days" => {
+                    i += 1;
+                    let value = args
+                        .get(i)
+                        .context("--file-history-retention-days requires a value")?;
+                    policy.file_history_retention = Some(
+                        parse_retention_days(value).context("Invalid file history retention")?,
+                    );
+                }
+                "--max-file-versions" => {
+                    i += 1;
+                    let value = args
+                        .get(i)
+                        .context("--max-file-versions requires a value")?;
+                    policy.max_file_versions =
+                        Some(parse_max_versions(value).context("Invalid max file versions")?);
+                }
+                "--archive-dir" | "-a" => {
+                    i += 1;
+                    policy.archive_dir = Some(PathBuf::from(
+                        args.get(i).context("--archive-dir requires a value")?,
+                    ));
+                }
+                "--help" | "-h" => {
+                    print_help();
+                    std::process::exit(0);
+                }
+                arg => {
+                    eprintln!("Unknown argument: {}", arg);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            i += 1;
+        }
+
+        Ok(Self {
+            dry_run,
+            batch_size,
+            policy,
+        })
+    }
+}
+
+fn print_help() {
+    eprintln!(
+        r#"
+maintenance - Apply history retention and maintain table partitions
+
+Creates upcoming partitions of the durable history tables, deletes (and
+optionally archives) finished workflows, tasks and superseded session events
+past their retention, prunes session file history, and drops emptied
+partitions of past months.
+Nothing is deleted unless a retention period is configured.
+
+USAGE:
+    maintenance [OPTIONS]
+
+OPTIONS:
+    -n, --dry-run                               Report what would change without making changes
+    -b, --batch-size <N>                        Delete N rows per statement (default: 500)
+        --workflow-retention-days <DAYS>        Delete terminal workflows finished DAYS ago
+        --task-retention-days <DAYS>            Delete finished tasks created DAYS ago
+        --superseded-event-retention-days <DAYS>
+                                                Delete session events superseded DAYS ago
+        --file-history-retention-days <DAYS>    Delete file versions and turn snapshots older than DAYS
+        --max-file-versions <N>                 Keep the newest N versions of each file
+    -a, --archive-dir <DIR>                     Archive workflow histories to DIR before deleting
+    -h, --help                                  Show this help message
+
+ENVIRONMENT:
+    DATABASE_URL                        PostgreSQL connection string (required)
+    WORKFLOW_RETENTION_DAYS             Default for --workflow-retention-days
+    TASK_RETENTION_DAYS                 Default for --task-retention-days
+    SUPERSEDED_EVENT_RETENTION_DAYS     Default for --superseded-event-retention-days
+    FILE_HISTORY_RETENTION_DAYS         Default for --file-history-retention-days
+    FILE_HISTORY_MAX_VERSIONS           Default for --max-file-versions
+    SESSION_FILES_STORAGE (and related) Object storage releasing pruned file content
+    WORKFLOW_ARCHIVE_DIR                Default for --archive-dir
+
+EXAMPLES:
+    # Report what a 30-day workflow retention would delete
+    maintenance --dry-run --workflow-retention-days 30
+
+    # Archive and delete workflows finished more than 30 days ago
+    maintenance --workflow-retention-days 30 --archive-dir /archive
+"#
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Initialize logging
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("maintenance=info".parse().unwrap()),
+        )
+        .init();
+
+    // Load environment before reading retention defaults
+    if let Ok(path) = dotenvy::dotenv() {
+        tracing::info!("Loaded .env from {:?}", path);
+    }
+
+    let args = Args::parse()?;
+
+    // Connect to database
+    let database_url = env::var("DATABASE_URL").context("DATABASE_URL not set")?;
+    let pool = PgPool::connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    tracing::info!("Connected to database");
+
+    let file_content =
+        FileContentStore::from_env().context("Failed to configure session file storage")?;
+    let service = MaintenanceService::new(Arc::new(Database::new(pool)), args.policy)
+        .with_file_content(Arc::new(file_content))
+        .with_batch_size(args.batch_size);
+    let report = service.run_once(args.dry_run).await?;
+
+    if args.dry_run {
+        println!("DRY RUN: nothing was changed");
+    }
+    println!("{}", report);
+
+    Ok(())
+}
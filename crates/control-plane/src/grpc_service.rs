@@ -6,39 +6,56 @@
 // Decision: No direct database access - all operations go through services layer
 
 use everruns_control_plane::services::{
+    rate_limit_key,
     session_file::{CreateDirectoryInput, CreateFileInput, GrepInput, UpdateFileInput},
-    AgentService, EventService, LlmResolverService, SessionFileService, SessionService,
+    AgentService, EventService, HttpToolService, LlmResolverService, SessionFileLimits,
+    SessionFileService, SessionService, TaskRouter,
 };
-use everruns_control_plane::storage::{Database, EncryptionService};
+use everruns_control_plane::storage::{Database, EncryptionService, FileContentStore};
 use everruns_durable::{
-    ActivityOptions, PostgresWorkflowEventStore, StoreError, TaskDefinition, TaskFailureOutcome,
-    WorkflowError, WorkflowEventStore, WorkflowStatus,
+    query_history, ActivityOptions, ExecutorError, FairnessConfig, PostgresWorkflowEventStore,
+    RateLimitDecision, RateLimitObservation, StoreError, TaskDefinition, TaskFailureOutcome,
+    WorkflowError, WorkflowEventStore, WorkflowExecutor, WorkflowSignal, WorkflowStatus,
+    DEFAULT_TASK_QUEUE, UNKNOWN_QUERY,
 };
 use everruns_internal_protocol::proto::{
-    self, AddMessageRequest, AddMessageResponse, ClaimDurableTasksRequest,
-    ClaimDurableTasksResponse, CommitExecRequest, CommitExecResponse, CompleteDurableTaskRequest,
-    CompleteDurableTaskResponse, CountActiveDurableWorkflowsRequest,
-    CountActiveDurableWorkflowsResponse, CreateDurableWorkflowRequest,
-    CreateDurableWorkflowResponse, DurableWorkflowStatus, EmitEventRequest, EmitEventResponse,
-    EmitEventStreamResponse, EnqueueDurableTaskRequest, EnqueueDurableTaskResponse,
-    FailDurableTaskRequest, FailDurableTaskResponse, GetAgentRequest, GetAgentResponse,
-    GetDefaultModelRequest, GetDefaultModelResponse, GetDurableWorkflowStatusRequest,
-    GetDurableWorkflowStatusResponse, GetModelWithProviderRequest, GetModelWithProviderResponse,
-    GetSessionRequest, GetSessionResponse, GetTurnContextRequest, GetTurnContextResponse,
-    HeartbeatDurableTaskRequest, HeartbeatDurableTaskResponse, LoadMessagesRequest,
-    LoadMessagesResponse, SessionCreateDirectoryRequest, SessionCreateDirectoryResponse,
-    SessionDeleteFileRequest, SessionDeleteFileResponse, SessionGrepFilesRequest,
-    SessionGrepFilesResponse, SessionListDirectoryRequest, SessionListDirectoryResponse,
-    SessionReadFileRequest, SessionReadFileResponse, SessionStatFileRequest,
-    SessionStatFileResponse, SessionWriteFileRequest, SessionWriteFileResponse,
-    SetSessionStatusRequest, SetSessionStatusResponse, UpdateDurableWorkflowStatusRequest,
-    UpdateDurableWorkflowStatusResponse,
+    self, AcquireLlmRateLimitRequest, AcquireLlmRateLimitResponse, AddMessageRequest,
+    AddMessageResponse, ClaimDurableTasksRequest, ClaimDurableTasksResponse, CommitExecRequest,
+    CommitExecResponse, CompleteDurableTaskRequest, CompleteDurableTaskResponse,
+    CountActiveDurableWorkflowsRequest, CountActiveDurableWorkflowsResponse,
+    CreateDurableWorkflowRequest, CreateDurableWorkflowResponse, DurableWorkflowStatus,
+    EmitEventRequest, EmitEventResponse, EmitEventStreamResponse, EnqueueDurableTaskRequest,
+    EnqueueDurableTaskResponse, FailDurableTaskRequest, FailDurableTaskResponse,
+    GetAgentHttpToolsRequest, GetAgentHttpToolsResponse, GetAgentMcpServersRequest,
+    GetAgentMcpServersResponse, GetAgentRequest, GetAgentResponse, GetDefaultModelRequest,
+    GetDefaultModelResponse, GetDurableWorkflowStatusRequest, GetDurableWorkflowStatusResponse,
+    GetModelWithProviderRequest, GetModelWithProviderResponse, GetSessionRequest,
+    GetSessionResponse, GetTurnContextRequest, GetTurnContextResponse, HeartbeatDurableTaskRequest,
+    HeartbeatDurableTaskResponse, LoadMessagesRequest, LoadMessagesResponse,
+    QueryDurableWorkflowRequest, QueryDurableWorkflowResponse, ReportLlmRateLimitRequest,
+    ReportLlmRateLimitResponse, SessionApplyFileChangesRequest, SessionApplyFileChangesResponse,
+    SessionCreateDirectoryRequest, SessionCreateDirectoryResponse, SessionCreateSnapshotRequest,
+    SessionCreateSnapshotResponse, SessionDeleteFileRequest, SessionDeleteFileResponse,
+    SessionDiffFileRequest, SessionDiffFileResponse, SessionGlobFilesRequest,
+    SessionGlobFilesResponse, SessionGrepFilesRequest, SessionGrepFilesResponse,
+    SessionListDirectoryRequest, SessionListDirectoryResponse, SessionListFileVersionsRequest,
+    SessionListFileVersionsResponse, SessionListSnapshotsRequest, SessionListSnapshotsResponse,
+    SessionReadFileRequest, SessionReadFileResponse, SessionRestoreFileRequest,
+    SessionRestoreFileResponse, SessionRestoreSnapshotRequest, SessionRestoreSnapshotResponse,
+    SessionStatFileRequest, SessionStatFileResponse, SessionWriteFileRequest,
+    SessionWriteFileResponse, SetSessionStatusRequest, SetSessionStatusResponse,
+    SignalWithStartDurableWorkflowRequest, SignalWithStartDurableWorkflowResponse,
+    UpdateDurableWorkflowStatusRequest, UpdateDurableWorkflowStatusResponse,
 };
 use everruns_internal_protocol::{
-    proto_event_request_to_schema, schema_agent_to_proto, schema_event_to_proto, WorkerService,
+    http_tool_connection_to_proto, mcp_connection_to_proto, proto_event_request_to_schema,
+    proto_file_change_to_schema, proto_restore_target_to_schema, schema_agent_to_proto,
+    schema_event_to_proto, schema_file_info_to_proto, schema_file_version_to_proto,
+    schema_session_file_to_proto, schema_session_snapshot_to_proto, WorkerService,
     WorkerServiceServer,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use tonic::{Request, Response, Status, Streaming};
 
 /// gRPC service implementation for worker communication
@@ -48,10 +65,14 @@ use tonic::{Request, Response, Status, Streaming};
 pub struct WorkerServiceImpl {
     event_service: EventService,
     agent_service: AgentService,
+    http_tool_service: HttpToolService,
     session_service: SessionService,
     session_file_service: SessionFileService,
     llm_resolver_service: LlmResolverService,
     durable_store: Option<Arc<PostgresWorkflowEventStore>>,
+    task_router: TaskRouter,
+    /// Drives the workflow types the control-plane runs, e.g. session workflows
+    workflow_executor: WorkflowExecutor<PostgresWorkflowEventStore>,
 }
 
 impl WorkerServiceImpl {
@@ -59,25 +80,48 @@ impl WorkerServiceImpl {
         event_service: EventService,
         db: Arc<Database>,
         encryption: Option<Arc<EncryptionService>>,
+        file_content: Arc<FileContentStore>,
+        file_limits: SessionFileLimits,
     ) -> Self {
-        let agent_service = AgentService::new(db.clone());
+        let agent_service = AgentService::new(db.clone(), encryption.clone());
+        let http_tool_service = HttpToolService::new(db.clone(), encryption.clone());
         let session_service = SessionService::new(db.clone());
-        let session_file_service = SessionFileService::new(db.clone());
+        let session_file_service =
+            SessionFileService::new(db.clone(), file_content).with_limits(file_limits);
         let llm_resolver_service = LlmResolverService::new(db.clone(), encryption);
+        let task_router = TaskRouter::new(db.clone(), Vec::new());
 
         // Create durable store using the same pool
         let durable_store = Some(Arc::new(PostgresWorkflowEventStore::new(db.pool().clone())));
+        let workflow_executor = everruns_worker::session_workflow_executor(db.pool().clone());
 
         Self {
             event_service,
             agent_service,
+            http_tool_service,
             session_service,
             session_file_service,
             llm_resolver_service,
             durable_store,
+            task_router,
+            workflow_executor,
         }
     }
 
+    /// Route enqueued durable tasks to task queues with the given router
+    pub fn with_task_router(mut self, task_router: TaskRouter) -> Self {
+        self.task_router = task_router;
+        self
+    }
+
+    /// Apply concurrency limits to worker task claims
+    pub fn with_fairness(mut self, fairness: FairnessConfig) -> Self {
+        self.durable_store = self
+            .durable_store
+            .map(|store| Arc::new((*store).clone().with_fairness(fairness)));
+        self
+    }
+
     /// Get durable store or return unavailable error
     #[allow(clippy::result_large_err)] // tonic::Status is the standard gRPC error type
     fn durable_store(&self) -> Result<&Arc<PostgresWorkflowEventStore>, Status> {
@@ -86,6 +130,53 @@ impl WorkerServiceImpl {
             .ok_or_else(|| Status::unavailable("Durable execution not enabled"))
     }
 
+    /// Build a task definition, routing it to a task queue and fairness key
+    ///
+    /// An explicit task queue and fairness key win over the router.
+    async fn task_definition(
+        &self,
+        task_def: proto::DurableTaskDefinition,
+    ) -> Result<TaskDefinition, Status> {
+        let workflow_id = parse_uuid(task_def.workflow_id.as_ref())?;
+
+        let input = task_def
+            .input
+            .map(|s| everruns_internal_protocol::proto_struct_to_json(&s))
+            .unwrap_or_else(|| serde_json::json!({}));
+
+        let proto_options = task_def.options.unwrap_or_default();
+        let route_error = |e: anyhow::Error| {
+            tracing::error!("Failed to route task: {}", e);
+            Status::internal("Failed to route task")
+        };
+        let task_queue = match proto_options.task_queue {
+            Some(queue) if !queue.is_empty() => queue,
+            _ => self
+                .task_router
+                .route(&task_def.activity_type, &input)
+                .await
+                .map_err(route_error)?,
+        };
+        let fairness_key = match proto_options.fairness_key {
+            Some(key) if !key.is_empty() => Some(key),
+            _ => self
+                .task_router
+                .fairness_key(&input)
+                .await
+                .map_err(route_error)?,
+        };
+        let mut options = ActivityOptions::default().with_task_queue(task_queue);
+        options.fairness_key = fairness_key;
+
+        Ok(TaskDefinition {
+            workflow_id,
+            activity_id: task_def.activity_id,
+            activity_type: task_def.activity_type,
+            input,
+            options,
+        })
+    }
+
     /// Create a tonic server for this service
     pub fn into_server(self) -> WorkerServiceServer<Self> {
         WorkerServiceServer::new(self)
@@ -100,6 +191,9 @@ impl WorkerServiceImpl {
             provider_type: resolved.provider_type,
             api_key: resolved.api_key,
             base_url: resolved.base_url,
+            provider_id: Some(everruns_internal_protocol::uuid_to_proto_uuid(
+                resolved.provider_id,
+            )),
         }
     }
 }
@@ -114,6 +208,39 @@ fn parse_uuid(proto_uuid: Option<&proto::Uuid>) -> Result<uuid::Uuid, Status> {
         .map_err(|e| Status::invalid_argument(format!("Invalid UUID: {}", e)))
 }
 
+// Map file history and multi-file change errors to a status, passing user
+// errors through
+fn history_status(action: &str, e: anyhow::Error) -> Status {
+    let msg = e.to_string();
+    if msg.contains("not found") || msg.contains("No versions") || msg.contains("No snapshot") {
+        Status::not_found(msg)
+    } else if msg.contains("already exists") {
+        Status::already_exists(msg)
+    } else if msg.contains("quota") {
+        Status::resource_exhausted(msg)
+    } else if msg.contains("Invalid")
+        || msg.contains("Cannot")
+        || msg.contains("No earlier version")
+        || msg.contains("Duplicate path")
+        || msg.contains("A file exists")
+    {
+        Status::invalid_argument(msg)
+    } else {
+        tracing::error!("Failed to {}: {}", action, e);
+        Status::internal(format!("Failed to {}", action))
+    }
+}
+
+// Convert a proto restore target, which must set exactly one field
+#[allow(clippy::result_large_err)] // tonic::Status is the standard gRPC error type
+fn parse_restore_target(
+    target: Option<proto::RestoreTarget>,
+) -> Result<everruns_core::RestoreTarget, Status> {
+    let target = target.ok_or_else(|| Status::invalid_argument("Missing restore target"))?;
+    proto_restore_target_to_schema(target)
+        .map_err(|e| Status::invalid_argument(format!("Invalid restore target: {}", e)))
+}
+
 /// Extract a Message from an Event's data field
 ///
 /// Events returned from EventService already have data parsed into EventData.
@@ -166,11 +293,16 @@ impl WorkerService for WorkerServiceImpl {
             })?
             .ok_or_else(|| Status::not_found("Session not found"))?;
 
-        // Get agent with capabilities via AgentService
-        let agent = self
-            .agent_service
-            .get(session.agent_id)
-            .await
+        // Get agent with capabilities via AgentService, at the session's pinned revision
+        let agent = match session.agent_revision {
+            Some(revision) => {
+                self.agent_service
+                    .get_revision(session.agent_id, revision)
+                    .await
+            }
+            None => self.agent_service.get(session.agent_id).await,
+        };
+        let agent = agent
             .map_err(|e| {
                 tracing::error!("Failed to get agent: {}", e);
                 Status::internal("Failed to get agent")
@@ -190,6 +322,8 @@ impl WorkerService for WorkerServiceImpl {
             created_at: Some(datetime_to_proto_timestamp(session.created_at)),
             updated_at: Some(datetime_to_proto_timestamp(session.created_at)),
             default_model_id: session.model_id.map(uuid_to_proto_uuid),
+            agent_revision: session.agent_revision,
+            variables: session.variables.clone().into_iter().collect(),
         };
 
         // Load messages from events using EventService
@@ -331,17 +465,62 @@ impl WorkerService for WorkerServiceImpl {
         let agent_id = parse_uuid(req.agent_id.as_ref())?;
 
         // Get agent with capabilities via AgentService
-        let agent = self
-            .agent_service
-            .get(agent_id)
-            .await
-            .map_err(|e| Status::internal(format!("Failed to get agent: {}", e)))?;
+        let agent = match req.revision {
+            Some(revision) => self.agent_service.get_revision(agent_id, revision).await,
+            None => self.agent_service.get(agent_id).await,
+        }
+        .map_err(|e| Status::internal(format!("Failed to get agent: {}", e)))?;
 
         let proto_agent = agent.map(|a| schema_agent_to_proto(&a));
 
         Ok(Response::new(GetAgentResponse { agent: proto_agent }))
     }
 
+    async fn get_agent_mcp_servers(
+        &self,
+        request: Request<GetAgentMcpServersRequest>,
+    ) -> Result<Response<GetAgentMcpServersResponse>, Status> {
+        let req = request.into_inner();
+        let agent_id = parse_uuid(req.agent_id.as_ref())?;
+
+        // Credentials are decrypted here; workers never see the encrypted store
+        let connections = self
+            .agent_service
+            .get_mcp_connections(agent_id, req.revision)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to get MCP servers: {}", e)))?;
+
+        let servers = connections
+            .iter()
+            .map(mcp_connection_to_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Status::internal(format!("Failed to convert MCP server: {}", e)))?;
+
+        Ok(Response::new(GetAgentMcpServersResponse { servers }))
+    }
+
+    async fn get_agent_http_tools(
+        &self,
+        request: Request<GetAgentHttpToolsRequest>,
+    ) -> Result<Response<GetAgentHttpToolsResponse>, Status> {
+        let req = request.into_inner();
+        let agent_id = parse_uuid(req.agent_id.as_ref())?;
+
+        let connections = self
+            .http_tool_service
+            .get_agent_connections(agent_id, req.revision)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to get HTTP tools: {}", e)))?;
+
+        let tools = connections
+            .iter()
+            .map(http_tool_connection_to_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Status::internal(format!("Failed to convert HTTP tool: {}", e)))?;
+
+        Ok(Response::new(GetAgentHttpToolsResponse { tools }))
+    }
+
     async fn get_session(
         &self,
         request: Request<GetSessionRequest>,
@@ -365,6 +544,8 @@ impl WorkerService for WorkerServiceImpl {
             created_at: Some(datetime_to_proto_timestamp(s.created_at)),
             updated_at: Some(datetime_to_proto_timestamp(s.created_at)),
             default_model_id: s.model_id.map(uuid_to_proto_uuid),
+            agent_revision: s.agent_revision,
+            variables: s.variables.clone().into_iter().collect(),
         });
 
         Ok(Response::new(GetSessionResponse {
@@ -408,6 +589,8 @@ impl WorkerService for WorkerServiceImpl {
             created_at: Some(datetime_to_proto_timestamp(session.created_at)),
             updated_at: Some(datetime_to_proto_timestamp(session.created_at)),
             default_model_id: session.model_id.map(uuid_to_proto_uuid),
+            agent_revision: session.agent_revision,
+            variables: session.variables.clone().into_iter().collect(),
         };
 
         Ok(Response::new(SetSessionStatusResponse {
@@ -692,6 +875,80 @@ impl WorkerService for WorkerServiceImpl {
         }))
     }
 
+    async fn acquire_llm_rate_limit(
+        &self,
+        request: Request<AcquireLlmRateLimitRequest>,
+    ) -> Result<Response<AcquireLlmRateLimitResponse>, Status> {
+        let req = request.into_inner();
+        let provider_id = parse_uuid(req.provider_id.as_ref())?;
+        let store = self.durable_store()?;
+
+        let config = self
+            .llm_resolver_service
+            .provider_rate_limits(provider_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load provider rate limits: {}", e);
+                Status::internal("Failed to load provider rate limits")
+            })?;
+
+        let decision = store
+            .acquire_rate_limit(&rate_limit_key(provider_id), &config, req.estimated_tokens)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to acquire rate limit: {}", e);
+                Status::internal("Failed to acquire rate limit")
+            })?;
+
+        Ok(Response::new(match decision {
+            RateLimitDecision::Acquired => AcquireLlmRateLimitResponse {
+                acquired: true,
+                retry_after_ms: 0,
+            },
+            RateLimitDecision::Wait(wait) => AcquireLlmRateLimitResponse {
+                acquired: false,
+                retry_after_ms: wait.as_millis() as u64,
+            },
+        }))
+    }
+
+    async fn report_llm_rate_limit(
+        &self,
+        request: Request<ReportLlmRateLimitRequest>,
+    ) -> Result<Response<ReportLlmRateLimitResponse>, Status> {
+        let req = request.into_inner();
+        let provider_id = parse_uuid(req.provider_id.as_ref())?;
+        let store = self.durable_store()?;
+
+        let config = self
+            .llm_resolver_service
+            .provider_rate_limits(provider_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load provider rate limits: {}", e);
+                Status::internal("Failed to load provider rate limits")
+            })?;
+
+        let observation = RateLimitObservation {
+            request_limit: req.request_limit,
+            requests_remaining: req.requests_remaining,
+            requests_reset: req.requests_reset_ms.map(Duration::from_millis),
+            token_limit: req.token_limit,
+            tokens_remaining: req.tokens_remaining,
+            tokens_reset: req.tokens_reset_ms.map(Duration::from_millis),
+            retry_after: req.retry_after_ms.map(Duration::from_millis),
+        };
+        store
+            .observe_rate_limit(&rate_limit_key(provider_id), &config, &observation)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to report rate limit: {}", e);
+                Status::internal("Failed to report rate limit")
+            })?;
+
+        Ok(Response::new(ReportLlmRateLimitResponse {}))
+    }
+
     // ========================================================================
     // Session file operations (via SessionFileService)
     // ========================================================================
@@ -762,6 +1019,9 @@ impl WorkerService for WorkerServiceImpl {
                 .update_file(session_id, &req.path, update)
                 .await
                 .map_err(|e| {
+                    if e.to_string().contains("quota") {
+                        return Status::resource_exhausted(e.to_string());
+                    }
                     tracing::error!("Failed to update file: {}", e);
                     Status::internal("Failed to update file")
                 })?
@@ -778,6 +1038,9 @@ impl WorkerService for WorkerServiceImpl {
                 .create_file(session_id, create)
                 .await
                 .map_err(|e| {
+                    if e.to_string().contains("quota") {
+                        return Status::resource_exhausted(e.to_string());
+                    }
                     tracing::error!("Failed to create file: {}", e);
                     Status::internal("Failed to create file")
                 })?
@@ -979,6 +1242,164 @@ impl WorkerService for WorkerServiceImpl {
         }))
     }
 
+    async fn session_glob_files(
+        &self,
+        request: Request<SessionGlobFilesRequest>,
+    ) -> Result<Response<SessionGlobFilesResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+
+        let files = self
+            .session_file_service
+            .glob(session_id, &req.pattern)
+            .await
+            .map_err(|e| history_status("glob files", e))?;
+
+        Ok(Response::new(SessionGlobFilesResponse {
+            files: files.iter().map(schema_file_info_to_proto).collect(),
+        }))
+    }
+
+    async fn session_apply_file_changes(
+        &self,
+        request: Request<SessionApplyFileChangesRequest>,
+    ) -> Result<Response<SessionApplyFileChangesResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+        let changes = req
+            .changes
+            .into_iter()
+            .map(proto_file_change_to_schema)
+            .collect();
+
+        let files = self
+            .session_file_service
+            .apply_changes(session_id, changes)
+            .await
+            .map_err(|e| history_status("apply file changes", e))?;
+
+        Ok(Response::new(SessionApplyFileChangesResponse {
+            files: files.iter().map(schema_file_info_to_proto).collect(),
+        }))
+    }
+
+    async fn session_list_file_versions(
+        &self,
+        request: Request<SessionListFileVersionsRequest>,
+    ) -> Result<Response<SessionListFileVersionsResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+
+        let versions = self
+            .session_file_service
+            .list_versions(session_id, &req.path)
+            .await
+            .map_err(|e| history_status("list file versions", e))?;
+
+        Ok(Response::new(SessionListFileVersionsResponse {
+            versions: versions.iter().map(schema_file_version_to_proto).collect(),
+        }))
+    }
+
+    async fn session_diff_file(
+        &self,
+        request: Request<SessionDiffFileRequest>,
+    ) -> Result<Response<SessionDiffFileResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+
+        let diff = self
+            .session_file_service
+            .diff(session_id, &req.path, req.from_version, req.to_version)
+            .await
+            .map_err(|e| history_status("diff file", e))?;
+
+        Ok(Response::new(SessionDiffFileResponse {
+            path: diff.path,
+            from_version: diff.from_version,
+            to_version: diff.to_version,
+            diff: diff.diff,
+            is_binary: diff.is_binary,
+        }))
+    }
+
+    async fn session_restore_file(
+        &self,
+        request: Request<SessionRestoreFileRequest>,
+    ) -> Result<Response<SessionRestoreFileResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+        let target = parse_restore_target(req.target)?;
+
+        let file = self
+            .session_file_service
+            .restore_file(session_id, &req.path, target)
+            .await
+            .map_err(|e| history_status("restore file", e))?;
+
+        Ok(Response::new(SessionRestoreFileResponse {
+            file: Some(schema_session_file_to_proto(&file)),
+        }))
+    }
+
+    async fn session_list_snapshots(
+        &self,
+        request: Request<SessionListSnapshotsRequest>,
+    ) -> Result<Response<SessionListSnapshotsResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+
+        let snapshots = self
+            .session_file_service
+            .list_snapshots(session_id)
+            .await
+            .map_err(|e| history_status("list snapshots", e))?;
+
+        Ok(Response::new(SessionListSnapshotsResponse {
+            snapshots: snapshots
+                .iter()
+                .map(schema_session_snapshot_to_proto)
+                .collect(),
+        }))
+    }
+
+    async fn session_create_snapshot(
+        &self,
+        request: Request<SessionCreateSnapshotRequest>,
+    ) -> Result<Response<SessionCreateSnapshotResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+
+        let snapshot = self
+            .session_file_service
+            .create_snapshot(session_id, &req.name)
+            .await
+            .map_err(|e| history_status("create snapshot", e))?;
+
+        Ok(Response::new(SessionCreateSnapshotResponse {
+            snapshot: Some(schema_session_snapshot_to_proto(&snapshot)),
+        }))
+    }
+
+    async fn session_restore_snapshot(
+        &self,
+        request: Request<SessionRestoreSnapshotRequest>,
+    ) -> Result<Response<SessionRestoreSnapshotResponse>, Status> {
+        let req = request.into_inner();
+        let session_id = parse_uuid(req.session_id.as_ref())?;
+        let target = parse_restore_target(req.target)?;
+
+        let snapshot = self
+            .session_file_service
+            .restore_snapshot(session_id, target)
+            .await
+            .map_err(|e| history_status("restore snapshot", e))?;
+
+        Ok(Response::new(SessionRestoreSnapshotResponse {
+            snapshot: Some(schema_session_snapshot_to_proto(&snapshot)),
+        }))
+    }
+
     // ========================================================================
     // Durable execution operations
     // ========================================================================
@@ -1085,24 +1506,7 @@ impl WorkerService for WorkerServiceImpl {
         let task_def = req
             .task
             .ok_or_else(|| Status::invalid_argument("Missing task definition"))?;
-        let workflow_id = parse_uuid(task_def.workflow_id.as_ref())?;
-
-        let input = task_def
-            .input
-            .map(|s| everruns_internal_protocol::proto_struct_to_json(&s))
-            .unwrap_or_else(|| serde_json::json!({}));
-
-        // For now, use default activity options
-        // TODO: Map proto options to ActivityOptions when needed
-        let options = ActivityOptions::default();
-
-        let task = TaskDefinition {
-            workflow_id,
-            activity_id: task_def.activity_id,
-            activity_type: task_def.activity_type,
-            input,
-            options,
-        };
+        let task = self.task_definition(task_def).await?;
 
         let task_id = store.enqueue_task(task).await.map_err(|e| {
             tracing::error!("Failed to enqueue task: {}", e);
@@ -1124,8 +1528,19 @@ impl WorkerService for WorkerServiceImpl {
         let req = request.into_inner();
         let store = self.durable_store()?;
 
+        let task_queues = if req.task_queues.is_empty() {
+            vec![DEFAULT_TASK_QUEUE.to_string()]
+        } else {
+            req.task_queues
+        };
+
         let tasks = store
-            .claim_task(&req.worker_id, &req.activity_types, req.max_tasks as usize)
+            .claim_task_from_queues(
+                &req.worker_id,
+                &task_queues,
+                &req.activity_types,
+                req.max_tasks as usize,
+            )
             .await
             .map_err(|e| {
                 tracing::error!("Failed to claim tasks: {}", e);
@@ -1162,14 +1577,20 @@ impl WorkerService for WorkerServiceImpl {
             .map(|s| everruns_internal_protocol::proto_struct_to_json(&s))
             .unwrap_or_else(|| serde_json::json!({}));
 
-        store.complete_task(task_id, output).await.map_err(|e| {
-            tracing::error!("Failed to complete task: {}", e);
-            Status::internal("Failed to complete task")
-        })?;
+        let next = match req.next {
+            Some(next) => Some(self.task_definition(next).await?),
+            None => None,
+        };
 
-        Ok(Response::new(CompleteDurableTaskResponse {
-            completed: true,
-        }))
+        let completed = store
+            .complete_and_enqueue(task_id, output, next)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to complete task: {}", e);
+                Status::internal("Failed to complete task")
+            })?;
+
+        Ok(Response::new(CompleteDurableTaskResponse { completed }))
     }
 
     async fn fail_durable_task(
@@ -1233,6 +1654,128 @@ impl WorkerService for WorkerServiceImpl {
 
         Ok(Response::new(CountActiveDurableWorkflowsResponse { count }))
     }
+
+    async fn signal_with_start_durable_workflow(
+        &self,
+        request: Request<SignalWithStartDurableWorkflowRequest>,
+    ) -> Result<Response<SignalWithStartDurableWorkflowResponse>, Status> {
+        let req = request.into_inner();
+        let store = self.durable_store()?;
+        let workflow_id = parse_uuid(req.workflow_id.as_ref())?;
+
+        if req.signal_type.is_empty() {
+            return Err(Status::invalid_argument("signal_type is required"));
+        }
+
+        let input = req
+            .input
+            .map(|s| everruns_internal_protocol::proto_struct_to_json(&s))
+            .unwrap_or_else(|| serde_json::json!({}));
+        let payload = req
+            .payload
+            .map(|v| everruns_internal_protocol::proto_value_to_json(&v))
+            .unwrap_or_else(|| serde_json::json!({}));
+        let signal = WorkflowSignal::new(req.signal_type, payload);
+
+        // Workflow types the control-plane runs are processed right away
+        if self
+            .workflow_executor
+            .registry()
+            .contains(&req.workflow_type)
+        {
+            let (created, _) = self
+                .workflow_executor
+                .signal_with_start_json(workflow_id, &req.workflow_type, input, signal, None)
+                .await
+                .map_err(|e| {
+                    if matches!(e, ExecutorError::WorkflowCompleted(_)) {
+                        return Status::failed_precondition("Workflow already closed");
+                    }
+                    if let ExecutorError::Store(e @ StoreError::WorkflowTypeMismatch { .. }) = &e {
+                        return Status::failed_precondition(e.to_string());
+                    }
+                    tracing::error!(%workflow_id, "Failed to signal with start: {}", e);
+                    Status::internal("Failed to signal workflow")
+                })?;
+
+            return Ok(Response::new(SignalWithStartDurableWorkflowResponse {
+                created,
+            }));
+        }
+
+        let created = store
+            .signal_with_start(workflow_id, &req.workflow_type, input, signal, None)
+            .await
+            .map_err(|e| {
+                if matches!(e, StoreError::WorkflowClosed(_)) {
+                    return Status::failed_precondition("Workflow already closed");
+                }
+                if matches!(e, StoreError::WorkflowTypeMismatch { .. }) {
+                    return Status::failed_precondition(e.to_string());
+                }
+                tracing::error!("Failed to signal with start: {}", e);
+                Status::internal("Failed to signal workflow")
+            })?;
+
+        Ok(Response::new(SignalWithStartDurableWorkflowResponse {
+            created,
+        }))
+    }
+
+    async fn query_durable_workflow(
+        &self,
+        request: Request<QueryDurableWorkflowRequest>,
+    ) -> Result<Response<QueryDurableWorkflowResponse>, Status> {
+        let req = request.into_inner();
+        let store = self.durable_store()?;
+        let workflow_id = parse_uuid(req.workflow_id.as_ref())?;
+
+        let info = store.get_workflow_info(workflow_id).await.map_err(|e| {
+            if matches!(e, StoreError::WorkflowNotFound(_)) {
+                return Status::not_found("Workflow not found");
+            }
+            tracing::error!("Failed to get workflow info: {}", e);
+            Status::internal("Failed to query workflow")
+        })?;
+        let registry = self.workflow_executor.registry();
+        if !registry.contains(&info.workflow_type) {
+            return Err(Status::failed_precondition(format!(
+                "Workflow type {} does not support queries",
+                info.workflow_type
+            )));
+        }
+
+        let events = store.load_events(workflow_id).await.map_err(|e| {
+            tracing::error!("Failed to load workflow events: {}", e);
+            Status::internal("Failed to query workflow")
+        })?;
+        let args = req
+            .args
+            .map(|v| everruns_internal_protocol::proto_value_to_json(&v))
+            .unwrap_or(serde_json::Value::Null);
+
+        let result = query_history(
+            registry,
+            &info.workflow_type,
+            &events,
+            &req.query_type,
+            args,
+        )
+        .map_err(|e| match e {
+            ExecutorError::QueryFailed(error) if error.code.as_deref() == Some(UNKNOWN_QUERY) => {
+                Status::invalid_argument(error.message)
+            }
+            ExecutorError::QueryFailed(error) => Status::failed_precondition(error.message),
+            e => {
+                tracing::error!(%workflow_id, "Failed to replay workflow for query: {}", e);
+                Status::internal("Failed to query workflow")
+            }
+        })?;
+
+        Ok(Response::new(QueryDurableWorkflowResponse {
+            result: Some(everruns_internal_protocol::json_to_proto_value(&result)),
+        }))
+    }
 }
 
 // Helper functions for status conversion
@@ -9,7 +9,7 @@ use everruns_control_plane::api;
 use everruns_control_plane::auth;
 use everruns_control_plane::openapi::ApiDoc;
 use everruns_control_plane::services;
-use everruns_control_plane::storage::{Database, EncryptionService};
+use everruns_control_plane::storage::{Database, EncryptionService, FileContentStore};
 
 use anyhow::{Context, Result};
 use axum::http::{header, HeaderValue, Method};
@@ -103,6 +103,23 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Session file content placement (inline in Postgres or object storage)
+    let file_content =
+        Arc::new(FileContentStore::from_env().context("Failed to configure session file storage")?);
+    tracing::info!(
+        backend = file_content.backend_name(),
+        inline_threshold = file_content.inline_threshold(),
+        "Session file storage configured"
+    );
+    let file_limits = services::SessionFileLimits::from_env()
+        .context("Failed to configure session file limits")?;
+    tracing::info!(
+        max_total_bytes = ?file_limits.max_total_bytes,
+        max_files = ?file_limits.max_files,
+        max_import_bytes = file_limits.max_import_bytes,
+        "Session file limits configured"
+    );
+
     // Load authentication configuration
     let auth_config = auth::AuthConfig::from_env();
     tracing::info!(
@@ -116,18 +133,43 @@ async fn main() -> Result<()> {
     let auth_state = auth::AuthState::new(auth_config.clone(), db.clone());
 
     // Create module-specific states
-    let agents_state = api::agents::AppState::new(db.clone());
-    let sessions_state = api::sessions::AppState::new(db.clone());
+    let agents_state = api::agents::AppState::new(db.clone(), encryption.clone());
+    let sessions_state =
+        api::sessions::AppState::new(db.clone(), file_content.clone(), auth_state.clone());
     let messages_state = api::messages::AppState::new(db.clone(), runner.clone());
+    let schedules_state = api::schedules::AppState::new(db.clone());
+    let webhooks_private_networks = std::env::var("WEBHOOKS_ALLOW_PRIVATE_NETWORKS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+    let webhooks_state =
+        api::webhooks::AppState::new(db.clone(), encryption.clone(), webhooks_private_networks);
 
     // Create event listeners for observability
     // OtelEventListener generates gen-ai semantic convention spans from events
     let otel_listener: Arc<dyn EventListener> = Arc::new(OtelEventListener::new());
+    let mut listeners = vec![otel_listener];
+
+    // TurnSnapshotListener snapshots the session filesystem at turn boundaries
+    let turn_snapshots = std::env::var("SESSION_FILES_TURN_SNAPSHOTS")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "false" | "0" | "off"))
+        .unwrap_or(true);
+    if turn_snapshots {
+        listeners.push(Arc::new(services::TurnSnapshotListener::new(
+            services::SessionFileService::new(db.clone(), file_content.clone()),
+        )));
+    }
+    tracing::info!(enabled = turn_snapshots, "Session file turn snapshots");
+
+    // ScheduleRunListener finishes schedule runs when their turn ends
+    listeners.push(Arc::new(services::ScheduleRunListener::new(db.clone())));
+
+    // WebhookListener enqueues durable deliveries for matching webhook subscriptions
+    listeners.push(Arc::new(services::WebhookListener::new(db.clone())));
 
     // Create EventService with listeners - shared between HTTP API and gRPC service
     let event_service = Arc::new(services::EventService::with_listeners(
         db.clone(),
-        vec![otel_listener],
+        listeners,
     ));
 
     let events_state = api::events::AppState {
@@ -138,7 +180,9 @@ async fn main() -> Result<()> {
     let llm_models_state = api::llm_models::AppState::new(db.clone());
     let capability_service = Arc::new(services::CapabilityService::new(db.clone()));
     let capabilities_state = api::capabilities::AppState::new(capability_service);
-    let session_files_state = api::session_files::AppState::new(db.clone());
+    let session_files_state =
+        api::session_files::AppState::new(db.clone(), file_content.clone(), file_limits.clone());
+    let tools_state = api::tools::AppState::new(db.clone(), encryption.clone());
     let users_state = api::users::UsersState {
         db: db.clone(),
         auth: auth_state.clone(),
@@ -177,11 +221,14 @@ async fn main() -> Result<()> {
         .merge(api::agents::routes(agents_state))
         .merge(api::sessions::routes(sessions_state))
         .merge(api::messages::routes(messages_state))
+        .merge(api::schedules::routes(schedules_state))
         .merge(api::events::routes(events_state))
         .merge(api::llm_models::routes(llm_models_state))
         .merge(api::llm_providers::routes(llm_providers_state))
         .merge(api::capabilities::routes(capabilities_state))
         .merge(api::session_files::routes(session_files_state))
+        .merge(api::tools::routes(tools_state))
+        .merge(api::webhooks::routes(webhooks_state))
         .merge(api::users::routes(users_state))
         .merge(auth::routes(auth_state));
 
@@ -228,14 +275,28 @@ async fn main() -> Result<()> {
     let grpc_addr = std::env::var("GRPC_ADDR").unwrap_or_else(|_| "0.0.0.0:9001".to_string());
     let grpc_db = db.clone();
     let grpc_encryption = encryption.clone();
+    let grpc_file_content = file_content.clone();
+    let grpc_file_limits = file_limits.clone();
     let grpc_event_service = event_service.clone();
+    let task_router = services::TaskRouter::from_env(db.clone())?;
+    let fairness = services::task_routing::fairness_config_from_env()?;
+    if !task_router.rules().is_empty() {
+        tracing::info!(
+            rules = task_router.rules().len(),
+            "Durable task routing rules loaded"
+        );
+    }
     tokio::spawn(async move {
         // Use the shared EventService with listeners (OTel, etc.)
         let grpc_service = grpc_service::WorkerServiceImpl::new(
             (*grpc_event_service).clone(),
             grpc_db,
             grpc_encryption,
-        );
+            grpc_file_content,
+            grpc_file_limits,
+        )
+        .with_task_router(task_router)
+        .with_fairness(fairness);
         let addr = grpc_addr.parse().expect("Invalid GRPC_ADDR");
         tracing::info!("gRPC server listening on {}", addr);
         if let Err(e) = tonic::transport::Server::builder()
@@ -286,6 +347,164 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Start background task for activity timeout enforcement
+    let timeout_sweep_enabled = std::env::var("DURABLE_TIMEOUT_SWEEP_ENABLED")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "false" | "0" | "off"))
+        .unwrap_or(true);
+    if timeout_sweep_enabled {
+        use everruns_durable::TimeoutManager;
+        use std::time::Duration;
+
+        let sweep_interval = std::env::var("DURABLE_TIMEOUT_SWEEP_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(10));
+        let sweep_batch_size = 100; // Tasks failed per sweep; the rest wait for the next tick
+
+        // Workflow types the control-plane runs are processed after an
+        // exhausted activity; other workflows are marked failed
+        let manager = TimeoutManager::new(everruns_worker::session_workflow_executor(
+            db.pool().clone(),
+        ));
+        // Turns whose activity the sweeper failed for good still end
+        let timed_out_turns = services::TimedOutTurns::new(db.clone(), event_service.clone());
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(sweep_interval);
+
+            tracing::info!(
+                sweep_interval_secs = sweep_interval.as_secs(),
+                "Started activity timeout sweeper background task"
+            );
+
+            loop {
+                interval.tick().await;
+                match manager.sweep(sweep_batch_size).await {
+                    Ok(timed_out) => {
+                        for task in timed_out {
+                            tracing::warn!(
+                                task_id = %task.task_id,
+                                workflow_id = %task.workflow_id,
+                                timeout_type = ?task.timeout_type,
+                                exceeded_by_ms = task.exceeded_by.as_millis() as u64,
+                                outcome = ?task.outcome,
+                                "Activity timed out"
+                            );
+                            if let Err(e) = timed_out_turns.handle(&task).await {
+                                tracing::error!(
+                                    workflow_id = %task.workflow_id,
+                                    error = %e,
+                                    "Failed to end timed out turn"
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to sweep activity timeouts: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Start background scheduler for agent schedules
+    let scheduler_enabled = std::env::var("SCHEDULER_ENABLED")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "false" | "0" | "off"))
+        .unwrap_or(true);
+    if scheduler_enabled {
+        let poll_interval = std::env::var("SCHEDULER_POLL_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(std::time::Duration::from_secs(15));
+        let scheduler = services::Scheduler::new(db.clone(), runner.clone());
+        tracing::info!(
+            poll_interval_secs = poll_interval.as_secs(),
+            "Started agent scheduler background task"
+        );
+        tokio::spawn(scheduler.run(poll_interval));
+    }
+
+    // Start background delivery of webhook tasks from the durable task queue
+    let webhook_delivery_enabled = std::env::var("WEBHOOK_DELIVERY_ENABLED")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "false" | "0" | "off"))
+        .unwrap_or(true);
+    if webhook_delivery_enabled {
+        let poll_interval = std::env::var("WEBHOOK_DELIVERY_POLL_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(std::time::Duration::from_secs(2));
+        let worker = services::WebhookDeliveryWorker::new(
+            db.clone(),
+            encryption.clone(),
+            webhooks_private_networks,
+        )
+        .context("Failed to create webhook delivery worker")?;
+        tracing::info!(
+            poll_interval_secs = poll_interval.as_secs(),
+            "Started webhook delivery background task"
+        );
+        tokio::spawn(worker.run(poll_interval));
+    }
+
+    // Start background history retention and partition maintenance
+    let maintenance_enabled = std::env::var("MAINTENANCE_ENABLED")
+        .map(|v| !matches!(v.to_lowercase().as_str(), "false" | "0" | "off"))
+        .unwrap_or(true);
+    if maintenance_enabled {
+        let interval = std::env::var("MAINTENANCE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(std::time::Duration::from_secs(3600));
+        let policy = services::RetentionPolicy::from_env()?;
+        tracing::info!(
+            interval_secs = interval.as_secs(),
+            ?policy,
+            "Started history maintenance background task"
+        );
+        tokio::spawn(
+            services::MaintenanceService::new(db.clone(), policy)
+                .with_file_content(file_content.clone())
+                .run(interval),
+        );
+    }
+
+    // Start background firing of session workflow timers (scheduled wakeups)
+    if everruns_worker::session_workflows_enabled() {
+        let timer_interval = std::env::var("SESSION_TIMER_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(std::time::Duration::from_secs(5));
+        let timer_batch_size = 100; // Timers fired per tick; the rest wait for the next tick
+        let executor = everruns_worker::session_workflow_executor(db.pool().clone());
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(timer_interval);
+
+            tracing::info!(
+                interval_secs = timer_interval.as_secs(),
+                "Started session workflow timer background task"
+            );
+
+            loop {
+                interval.tick().await;
+                match executor.fire_due_timers(timer_batch_size).await {
+                    Ok(fired) if fired > 0 => {
+                        tracing::debug!(fired, "Fired session workflow timers");
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!("Failed to fire session workflow timers: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
     // Start HTTP server
     let addr = "0.0.0.0:9000";
     let listener = tokio::net::TcpListener::bind(addr)
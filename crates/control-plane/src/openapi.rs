@@ -6,18 +6,21 @@
 
 use crate::api;
 use crate::api::ListResponse;
+use everruns_core::http_tool::{CustomTool, HttpToolAuth, HttpToolEndpoint, HttpToolMethod};
 use everruns_core::llm_models::LlmProvider;
 use everruns_core::{
     events::{
-        ActCompletedData, ActStartedData, InputReceivedData, LlmGenerationData,
-        LlmGenerationMetadata, LlmGenerationOutput, MessageAgentData, MessageUserData,
-        ModelMetadata, ReasonCompletedData, ReasonStartedData, SessionStartedData, TokenUsage,
-        ToolCallCompletedData, ToolCallStartedData, ToolCallSummary, TurnCompletedData,
+        ActCompletedData, ActStartedData, EventsSupersededData, InputReceivedData,
+        LlmGenerationData, LlmGenerationMetadata, LlmGenerationOutput, MessageAgentData,
+        MessageUserData, ModelMetadata, ReasonCompletedData, ReasonStartedData, SessionStartedData,
+        TokenUsage, ToolCallCompletedData, ToolCallStartedData, ToolCallSummary, TurnCompletedData,
         TurnFailedData, TurnStartedData,
     },
-    Agent, AgentStatus, CapabilityInfo, Event, EventContext, EventData, FileInfo, FileStat,
-    GrepMatch, GrepResult, LlmModel, LlmModelStatus, LlmModelWithProvider, LlmProviderStatus,
-    LlmProviderType, Session, SessionFile, SessionStatus, ToolCall,
+    mcp::{McpServerConfig, McpTransportConfig},
+    Agent, AgentStatus, CapabilityInfo, Event, EventContext, EventData, FileDiff, FileInfo,
+    FileStat, FileVersion, GrepMatch, GrepResult, LlmModel, LlmModelStatus, LlmModelWithProvider,
+    LlmProviderStatus, LlmProviderType, ResponseFormat, Session, SessionFile, SessionSnapshot,
+    SessionStatus, SnapshotKind, ToolCall,
 };
 use utoipa::OpenApi;
 
@@ -35,13 +38,21 @@ use utoipa::OpenApi;
         api::agents::delete_agent,
         api::agents::export_agent,
         api::agents::import_agent,
+        api::agents::list_agent_revisions,
+        api::agents::get_agent_revision,
+        api::agents::diff_agent_revision,
+        api::agents::rollback_agent_revision,
         api::sessions::create_session,
         api::sessions::list_sessions,
         api::sessions::get_session,
         api::sessions::update_session,
         api::sessions::delete_session,
+        api::sessions::fork_session,
+        api::sessions::list_session_branches,
         api::messages::create_message,
         api::messages::list_messages,
+        api::messages::edit_message,
+        api::messages::regenerate,
         api::events::stream_sse,
         api::events::list_events,
         api::llm_providers::create_provider,
@@ -57,6 +68,23 @@ use utoipa::OpenApi;
         api::llm_models::delete_model,
         api::capabilities::list_capabilities,
         api::capabilities::get_capability,
+        api::tools::create_tool,
+        api::tools::list_tools,
+        api::tools::get_tool,
+        api::tools::update_tool,
+        api::tools::delete_tool,
+        api::schedules::create_schedule,
+        api::schedules::list_schedules,
+        api::schedules::get_schedule,
+        api::schedules::update_schedule,
+        api::schedules::delete_schedule,
+        api::schedules::list_schedule_runs,
+        api::schedules::trigger_schedule,
+        api::webhooks::create_webhook,
+        api::webhooks::list_webhooks,
+        api::webhooks::get_webhook,
+        api::webhooks::update_webhook,
+        api::webhooks::delete_webhook,
         api::users::list_users,
         api::session_files::get_root,
         api::session_files::get_path,
@@ -66,11 +94,21 @@ use utoipa::OpenApi;
         api::session_files::move_file,
         api::session_files::copy_file,
         api::session_files::grep_files,
+        api::session_files::glob_files,
         api::session_files::stat_file,
+        api::session_files::list_versions,
+        api::session_files::diff_file,
+        api::session_files::restore,
+        api::session_files::import_archive,
+        api::session_files::export_archive,
+        api::session_files::list_snapshots,
+        api::session_files::create_snapshot,
+        api::session_files::get_snapshot,
+        api::session_files::delete_snapshot,
     ),
     components(
         schemas(
-            Agent, AgentStatus,
+            Agent, AgentStatus, ResponseFormat,
             Session, SessionStatus, Event, EventContext, EventData,
             // Event data types
             MessageUserData, MessageAgentData, ModelMetadata, TokenUsage,
@@ -79,10 +117,13 @@ use utoipa::OpenApi;
             ActStartedData, ActCompletedData, ToolCallSummary,
             ToolCallStartedData, ToolCallCompletedData,
             LlmGenerationData, LlmGenerationOutput, LlmGenerationMetadata,
-            SessionStartedData,
+            SessionStartedData, EventsSupersededData,
             // Agent/Session types
             api::agents::CreateAgentRequest, api::agents::UpdateAgentRequest,
+            api::agents::McpServerInput, McpServerConfig, McpTransportConfig,
+            api::agents::AgentRevisionDiff, api::agents::AgentFieldChange,
             api::sessions::CreateSessionRequest, api::sessions::UpdateSessionRequest,
+            api::sessions::ForkSessionRequest,
             api::messages::Message, api::messages::MessageRole, api::messages::ContentPart, api::messages::InputContentPart,
             api::messages::CreateMessageRequest, api::messages::InputMessage,
             api::messages::Controls, api::messages::ReasoningConfig,
@@ -98,16 +139,36 @@ use utoipa::OpenApi;
             api::llm_models::UpdateLlmModelRequest,
             CapabilityInfo,
             ListResponse<CapabilityInfo>,
+            api::tools::CreateHttpToolRequest, api::tools::UpdateHttpToolRequest,
+            CustomTool, HttpToolEndpoint, HttpToolMethod, HttpToolAuth,
+            ListResponse<CustomTool>,
+            api::schedules::Schedule, api::schedules::ScheduleRun,
+            api::schedules::ScheduleRunStatus, api::schedules::MissedRunPolicy,
+            api::schedules::OverlapPolicy,
+            api::schedules::CreateScheduleRequest, api::schedules::UpdateScheduleRequest,
+            ListResponse<api::schedules::Schedule>,
+            ListResponse<api::schedules::ScheduleRun>,
+            api::webhooks::Webhook,
+            api::webhooks::CreateWebhookRequest, api::webhooks::UpdateWebhookRequest,
+            ListResponse<api::webhooks::Webhook>,
             api::users::User,
             api::users::ListUsersQuery,
             ListResponse<api::users::User>,
             SessionFile, FileInfo, FileStat, GrepMatch, GrepResult,
             api::session_files::CreateFileRequest, api::session_files::UpdateFileRequest,
             api::session_files::MoveFileRequest, api::session_files::CopyFileRequest,
-            api::session_files::GrepRequest, api::session_files::DeleteResponse,
+            api::session_files::GrepRequest, api::session_files::GlobRequest, api::session_files::DeleteResponse,
             api::session_files::GetQuery, api::session_files::DeleteQuery, api::session_files::GetResponse,
             ListResponse<FileInfo>,
             ListResponse<GrepResult>,
+            FileVersion, FileDiff, SessionSnapshot, SnapshotKind,
+            api::session_files::ListVersionsRequest, api::session_files::DiffRequest,
+            api::session_files::RestoreRequest, api::session_files::RestoreResponse,
+            api::session_files::CreateSnapshotRequest,
+            api::session_files::ImportQuery, api::session_files::ExportQuery,
+            api::session_files::ImportResponse,
+            ListResponse<FileVersion>,
+            ListResponse<SessionSnapshot>,
             // Tool types
             ToolCall,
         )
@@ -120,6 +181,9 @@ use utoipa::OpenApi;
         (name = "llm-providers", description = "LLM Provider management endpoints"),
         (name = "llm-models", description = "LLM Model management endpoints"),
         (name = "capabilities", description = "Capability management endpoints"),
+        (name = "tools", description = "User-defined HTTP tool endpoints"),
+        (name = "schedules", description = "Scheduled agent run endpoints"),
+        (name = "webhooks", description = "Outbound webhook subscription endpoints"),
         (name = "users", description = "User management endpoints"),
         (name = "filesystem", description = "Session virtual filesystem endpoints")
     ),
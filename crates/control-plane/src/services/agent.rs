@@ -5,61 +5,75 @@
 // by event listeners rather than direct spans.
 
 use crate::storage::{
-    models::{CreateAgentRow, UpdateAgent},
-    AgentRow, Database,
+    models::{AgentCapabilityRow, AgentRevisionRow, CreateAgentRow, UpdateAgent},
+    AgentRow, Database, EncryptionService,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use everruns_core::capabilities::CapabilityConfigs;
+use everruns_core::mcp::{McpServerConfig, McpServerConnection};
 use everruns_core::{Agent, AgentStatus, CapabilityId};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::api::agents::{CreateAgentRequest, UpdateAgentRequest};
+use crate::api::agents::{CreateAgentRequest, McpServerInput, UpdateAgentRequest};
+
+/// Decrypted MCP credentials: server name -> credential name -> value
+type McpCredentials = HashMap<String, HashMap<String, String>>;
 
 pub struct AgentService {
     db: Arc<Database>,
+    encryption: Option<Arc<EncryptionService>>,
 }
 
 impl AgentService {
-    pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<Database>, encryption: Option<Arc<EncryptionService>>) -> Self {
+        Self { db, encryption }
     }
 
     pub async fn create(&self, req: CreateAgentRequest) -> Result<Agent> {
         // Note: OTel instrumentation is handled via event listeners.
         // Agent creation events would be handled by listeners rather than direct spans.
+        let (mcp_servers, mcp_credentials_encrypted) =
+            self.prepare_mcp_servers(req.mcp_servers, &HashMap::new())?;
         let input = CreateAgentRow {
             name: req.name,
             description: req.description,
             system_prompt: req.system_prompt,
             default_model_id: req.default_model_id,
             tags: req.tags,
+            response_format: req.response_format.map(serde_json::to_value).transpose()?,
+            parallel_tool_calls: req.parallel_tool_calls,
+            mcp_servers,
+            mcp_credentials_encrypted,
         };
         let row = self.db.create_agent(input).await?;
         let agent_id = row.id;
 
         // Set capabilities if provided
-        let capabilities = if !req.capabilities.is_empty() {
-            let cap_tuples: Vec<(String, i32)> = req
-                .capabilities
-                .iter()
-                .enumerate()
-                .map(|(idx, cap)| (cap.to_string(), idx as i32))
-                .collect();
-            self.db.set_agent_capabilities(agent_id, cap_tuples).await?;
-            req.capabilities
+        let (capabilities, capability_config) = if !req.capabilities.is_empty() {
+            let config = self
+                .set_capabilities(agent_id, &req.capabilities, &req.capability_config)
+                .await?;
+            (req.capabilities, config)
         } else {
-            vec![]
+            (vec![], CapabilityConfigs::new())
         };
+        self.db.record_agent_revision(agent_id, false).await?;
 
-        Ok(Self::row_to_agent(row, capabilities))
+        Ok(Self::row_to_agent(row, capabilities, capability_config))
     }
 
     pub async fn get(&self, id: Uuid) -> Result<Option<Agent>> {
         let row = self.db.get_agent(id).await?;
         match row {
             Some(row) => {
-                let capabilities = self.get_capabilities(id).await?;
-                Ok(Some(Self::row_to_agent(row, capabilities)))
+                let (capabilities, capability_config) = self.get_capabilities(id).await?;
+                Ok(Some(Self::row_to_agent(
+                    row,
+                    capabilities,
+                    capability_config,
+                )))
             }
             None => Ok(None),
         }
@@ -71,58 +85,270 @@ impl AgentService {
         // Fetch capabilities for each agent
         let mut agents = Vec::with_capacity(rows.len());
         for row in rows {
-            let capabilities = self.get_capabilities(row.id).await?;
-            agents.push(Self::row_to_agent(row, capabilities));
+            let (capabilities, capability_config) = self.get_capabilities(row.id).await?;
+            agents.push(Self::row_to_agent(row, capabilities, capability_config));
         }
 
         Ok(agents)
     }
 
     pub async fn update(&self, id: Uuid, req: UpdateAgentRequest) -> Result<Option<Agent>> {
+        // Any change other than the lifecycle status produces a new revision
+        let changes_config = req.name.is_some()
+            || req.description.is_some()
+            || req.system_prompt.is_some()
+            || req.default_model_id.is_some()
+            || req.tags.is_some()
+            || req.capabilities.is_some()
+            || req.capability_config.is_some()
+            || req.response_format.is_some()
+            || req.parallel_tool_calls.is_some()
+            || req.mcp_servers.is_some();
+
+        // Servers are replaced as a whole; servers sent without credentials keep their stored ones
+        let (mcp_servers, mcp_credentials_encrypted) = match req.mcp_servers {
+            Some(servers) => {
+                let Some(existing) = self.db.get_agent(id).await? else {
+                    return Ok(None);
+                };
+                let existing_credentials =
+                    self.decrypt_mcp_credentials(existing.mcp_credentials_encrypted.as_deref())?;
+                let (servers, credentials) =
+                    self.prepare_mcp_servers(servers, &existing_credentials)?;
+                (Some(servers), credentials)
+            }
+            None => (None, None),
+        };
+
         let input = UpdateAgent {
             name: req.name,
             description: req.description,
             system_prompt: req.system_prompt,
             default_model_id: req.default_model_id,
             tags: req.tags,
+            response_format: req
+                .response_format
+                .map(|format| format.map(serde_json::to_value).transpose())
+                .transpose()?,
+            parallel_tool_calls: req.parallel_tool_calls,
+            mcp_servers,
+            mcp_credentials_encrypted,
             status: req.status.map(|s| s.to_string()),
         };
-        let row = self.db.update_agent(id, input).await?;
 
-        match row {
-            Some(row) => {
-                // Update capabilities if provided
-                let capabilities = if let Some(caps) = req.capabilities {
-                    let cap_tuples: Vec<(String, i32)> = caps
-                        .iter()
-                        .enumerate()
-                        .map(|(idx, cap)| (cap.to_string(), idx as i32))
-                        .collect();
-                    self.db.set_agent_capabilities(id, cap_tuples).await?;
-                    caps
-                } else {
-                    self.get_capabilities(id).await?
+        // Replace capabilities if provided; configs of capabilities that
+        // remain enabled are kept unless a new config is provided
+        let capabilities = match (req.capabilities, req.capability_config) {
+            (None, None) => None,
+            (capabilities, config) => {
+                let (existing, existing_config) = self.get_capabilities(id).await?;
+                let capabilities = capabilities.unwrap_or(existing);
+                let config = config.unwrap_or(existing_config);
+                Some(Self::capability_tuples(&capabilities, &config))
+            }
+        };
+
+        let Some(row) = self
+            .db
+            .update_agent(id, input, capabilities, changes_config)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let (capabilities, capability_config) = self.get_capabilities(id).await?;
+
+        Ok(Some(Self::row_to_agent(
+            row,
+            capabilities,
+            capability_config,
+        )))
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        self.db.delete_agent(id).await
+    }
+
+    /// Revisions of an agent, newest first (None if the agent doesn't exist)
+    pub async fn list_revisions(&self, agent_id: Uuid) -> Result<Option<Vec<Agent>>> {
+        let Some(agent) = self.db.get_agent(agent_id).await? else {
+            return Ok(None);
+        };
+        let rows = self.db.list_agent_revisions(agent_id).await?;
+        Ok(Some(
+            rows.into_iter()
+                .map(|row| Self::revision_to_agent(row, &agent))
+                .collect(),
+        ))
+    }
+
+    /// The agent as it was at a revision
+    pub async fn get_revision(&self, agent_id: Uuid, revision: i32) -> Result<Option<Agent>> {
+        let Some(agent) = self.db.get_agent(agent_id).await? else {
+            return Ok(None);
+        };
+        let row = self.db.get_agent_revision(agent_id, revision).await?;
+        Ok(row.map(|row| Self::revision_to_agent(row, &agent)))
+    }
+
+    /// Restore the configuration of an earlier revision as a new revision
+    pub async fn rollback(&self, agent_id: Uuid, revision: i32) -> Result<Option<Agent>> {
+        if self
+            .db
+            .restore_agent_revision(agent_id, revision)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        self.get(agent_id).await
+    }
+
+    /// Get the agent's MCP servers with decrypted credentials (for workers).
+    /// `revision` selects a pinned revision; None uses the current configuration.
+    ///
+    /// Returns nothing unless the agent has the `mcp` capability enabled.
+    pub async fn get_mcp_connections(
+        &self,
+        agent_id: Uuid,
+        revision: Option<i32>,
+    ) -> Result<Vec<McpServerConnection>> {
+        let (capabilities, mcp_servers, credentials_encrypted) = match revision {
+            Some(revision) => {
+                let Some(row) = self.db.get_agent_revision(agent_id, revision).await? else {
+                    return Ok(vec![]);
                 };
+                let capabilities = row
+                    .capability_list()
+                    .into_iter()
+                    .map(|c| CapabilityId::new(&c.capability_id))
+                    .collect();
+                (capabilities, row.mcp_servers, row.mcp_credentials_encrypted)
+            }
+            None => {
+                let Some(row) = self.db.get_agent(agent_id).await? else {
+                    return Ok(vec![]);
+                };
+                let (capabilities, _) = self.get_capabilities(agent_id).await?;
+                (capabilities, row.mcp_servers, row.mcp_credentials_encrypted)
+            }
+        };
+        if !capabilities.iter().any(|c| c.as_str() == CapabilityId::MCP) {
+            return Ok(vec![]);
+        }
+
+        let mut credentials = self.decrypt_mcp_credentials(credentials_encrypted.as_deref())?;
+        let servers: Vec<McpServerConfig> = serde_json::from_value(mcp_servers)?;
+        Ok(servers
+            .into_iter()
+            .map(|config| {
+                let server_credentials = credentials.remove(&config.name).unwrap_or_default();
+                McpServerConnection::new(config, server_credentials)
+            })
+            .collect())
+    }
+
+    /// Split MCP server input into stored configs and encrypted credentials
+    fn prepare_mcp_servers(
+        &self,
+        servers: Vec<McpServerInput>,
+        existing_credentials: &McpCredentials,
+    ) -> Result<(serde_json::Value, Option<Vec<u8>>)> {
+        let mut configs = Vec::with_capacity(servers.len());
+        let mut credentials = McpCredentials::new();
 
-                Ok(Some(Self::row_to_agent(row, capabilities)))
+        for server in servers {
+            let server_credentials = match server.credentials {
+                Some(provided) => provided,
+                None => existing_credentials
+                    .get(&server.name)
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+            let mut credential_names: Vec<String> = server_credentials.keys().cloned().collect();
+            credential_names.sort();
+
+            if !server_credentials.is_empty() {
+                credentials.insert(server.name.clone(), server_credentials);
             }
-            None => Ok(None),
+            configs.push(McpServerConfig {
+                name: server.name,
+                transport: server.transport,
+                credential_names,
+            });
         }
+
+        let credentials_encrypted = if credentials.is_empty() {
+            None
+        } else {
+            let encryption = self.encryption.as_ref().ok_or_else(|| {
+                anyhow!("Encryption not configured. Cannot store MCP server credentials.")
+            })?;
+            Some(encryption.encrypt_string(&serde_json::to_string(&credentials)?)?)
+        };
+
+        Ok((serde_json::to_value(configs)?, credentials_encrypted))
     }
 
-    pub async fn delete(&self, id: Uuid) -> Result<bool> {
-        self.db.delete_agent(id).await
+    fn decrypt_mcp_credentials(&self, encrypted: Option<&[u8]>) -> Result<McpCredentials> {
+        let Some(encrypted) = encrypted else {
+            return Ok(McpCredentials::new());
+        };
+        let encryption = self.encryption.as_ref().ok_or_else(|| {
+            anyhow!("Encryption not configured. Cannot decrypt MCP server credentials.")
+        })?;
+        Ok(serde_json::from_str(
+            &encryption.decrypt_to_string(encrypted)?,
+        )?)
     }
 
-    async fn get_capabilities(&self, agent_id: Uuid) -> Result<Vec<CapabilityId>> {
+    async fn get_capabilities(
+        &self,
+        agent_id: Uuid,
+    ) -> Result<(Vec<CapabilityId>, CapabilityConfigs)> {
         let rows = self.db.get_agent_capabilities(agent_id).await?;
-        Ok(rows
+        let config = AgentCapabilityRow::configs(&rows);
+        let capabilities = rows
             .into_iter()
             .map(|row| CapabilityId::new(&row.capability_id))
-            .collect())
+            .collect();
+        Ok((capabilities, config))
     }
 
-    fn row_to_agent(row: AgentRow, capabilities: Vec<CapabilityId>) -> Agent {
+    /// Replace the agent's capabilities; returns the configs that were stored
+    async fn set_capabilities(
+        &self,
+        agent_id: Uuid,
+        capabilities: &[CapabilityId],
+        config: &CapabilityConfigs,
+    ) -> Result<CapabilityConfigs> {
+        let cap_tuples = Self::capability_tuples(capabilities, config);
+        let rows = self.db.set_agent_capabilities(agent_id, cap_tuples).await?;
+        Ok(AgentCapabilityRow::configs(&rows))
+    }
+
+    /// (capability_id, position, config) rows for the given capabilities
+    fn capability_tuples(
+        capabilities: &[CapabilityId],
+        config: &CapabilityConfigs,
+    ) -> Vec<(String, i32, serde_json::Value)> {
+        capabilities
+            .iter()
+            .enumerate()
+            .map(|(idx, cap)| {
+                let config = config
+                    .get(cap.as_str())
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!({}));
+                (cap.to_string(), idx as i32, config)
+            })
+            .collect()
+    }
+
+    fn row_to_agent(
+        row: AgentRow,
+        capabilities: Vec<CapabilityId>,
+        capability_config: CapabilityConfigs,
+    ) -> Agent {
         Agent {
             id: row.id,
             name: row.name,
@@ -131,9 +357,47 @@ impl AgentService {
             default_model_id: row.default_model_id,
             tags: row.tags,
             capabilities,
+            capability_config,
+            response_format: row
+                .response_format
+                .and_then(|v| serde_json::from_value(v).ok()),
+            parallel_tool_calls: row.parallel_tool_calls,
+            mcp_servers: serde_json::from_value(row.mcp_servers).unwrap_or_default(),
             status: AgentStatus::from(row.status.as_str()),
+            revision: row.revision,
             created_at: row.created_at,
             updated_at: row.updated_at,
         }
     }
+
+    /// Build the agent as captured by a revision; status and creation time
+    /// come from the current agent
+    fn revision_to_agent(row: AgentRevisionRow, agent: &AgentRow) -> Agent {
+        let capability_config = row.configs();
+        let capabilities = row
+            .capability_list()
+            .into_iter()
+            .map(|c| CapabilityId::new(&c.capability_id))
+            .collect();
+
+        Agent {
+            id: row.agent_id,
+            name: row.name,
+            description: row.description,
+            system_prompt: row.system_prompt,
+            default_model_id: row.default_model_id,
+            tags: row.tags,
+            capabilities,
+            capability_config,
+            response_format: row
+                .response_format
+                .and_then(|v| serde_json::from_value(v).ok()),
+            parallel_tool_calls: row.parallel_tool_calls,
+            mcp_servers: serde_json::from_value(row.mcp_servers).unwrap_or_default(),
+            status: AgentStatus::from(agent.status.as_str()),
+            revision: row.revision,
+            created_at: agent.created_at,
+            updated_at: row.created_at,
+        }
+    }
 }
@@ -1,17 +1,20 @@
 // Capability service - business logic for capabilities
 //
 // Uses CapabilityRegistry from everruns-core as the single source of truth
-// for capability definitions.
+// for built-in capability definitions. User-defined HTTP tools are listed
+// alongside them as `custom:<name>` capabilities.
 //
 // Note: Agent-specific capability management is handled by AgentService.
 
+use crate::services::HttpToolService;
 use crate::storage::Database;
-use everruns_core::capabilities::CapabilityRegistry;
+use anyhow::Result;
+use everruns_core::capabilities::{CapabilityRegistry, HttpToolCapability};
+use everruns_core::http_tool::{tool_name_from_capability_id, HttpToolConnection};
 use everruns_core::{CapabilityId, CapabilityInfo};
 use std::sync::Arc;
 
 pub struct CapabilityService {
-    #[allow(dead_code)]
     db: Arc<Database>,
     registry: CapabilityRegistry,
 }
@@ -25,18 +28,45 @@ impl CapabilityService {
     }
 
     /// List all available capabilities (public info only)
-    pub fn list_all(&self) -> Vec<CapabilityInfo> {
-        self.registry
+    pub async fn list_all(&self) -> Result<Vec<CapabilityInfo>> {
+        let mut capabilities: Vec<CapabilityInfo> = self
+            .registry
             .list()
             .into_iter()
             .map(|cap| CapabilityInfo::from_core(cap.as_ref()))
-            .collect()
+            .collect();
+
+        for row in self.db.list_http_tools().await? {
+            capabilities.push(Self::custom_capability_info(
+                HttpToolService::row_to_definition(&row)?,
+            ));
+        }
+
+        Ok(capabilities)
     }
 
     /// Get a specific capability by ID
-    pub fn get(&self, id: &CapabilityId) -> Option<CapabilityInfo> {
-        self.registry
+    pub async fn get(&self, id: &CapabilityId) -> Result<Option<CapabilityInfo>> {
+        if let Some(name) = tool_name_from_capability_id(id.as_str()) {
+            let rows = self.db.get_http_tools_by_name(&[name.to_string()]).await?;
+            return rows
+                .first()
+                .map(|row| {
+                    HttpToolService::row_to_definition(row).map(Self::custom_capability_info)
+                })
+                .transpose();
+        }
+
+        Ok(self
+            .registry
             .get(id.as_str())
-            .map(|cap| CapabilityInfo::from_core(cap.as_ref()))
+            .map(|cap| CapabilityInfo::from_core(cap.as_ref())))
+    }
+
+    fn custom_capability_info(
+        definition: everruns_core::http_tool::HttpToolDefinition,
+    ) -> CapabilityInfo {
+        let capability = HttpToolCapability::new(HttpToolConnection::new(definition, None));
+        CapabilityInfo::from_core(&capability)
     }
 }
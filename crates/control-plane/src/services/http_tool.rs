@@ -0,0 +1,172 @@
+// HTTP tool service - user-defined tools backed by HTTP endpoints
+//
+// Tools are attached to agents through the capability `custom:<name>`.
+// Auth secrets are encrypted at rest and only decrypted for workers (gRPC).
+
+use crate::storage::{
+    models::{CreateHttpToolRow, HttpToolRow, UpdateHttpTool},
+    Database, EncryptionService,
+};
+use anyhow::{anyhow, Result};
+use everruns_core::http_tool::{
+    capability_id_for, tool_name_from_capability_id, CustomTool, HttpToolConnection,
+    HttpToolDefinition,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::api::tools::{CreateHttpToolRequest, UpdateHttpToolRequest};
+
+pub struct HttpToolService {
+    db: Arc<Database>,
+    encryption: Option<Arc<EncryptionService>>,
+}
+
+impl HttpToolService {
+    pub fn new(db: Arc<Database>, encryption: Option<Arc<EncryptionService>>) -> Self {
+        Self { db, encryption }
+    }
+
+    pub async fn create(&self, req: CreateHttpToolRequest) -> Result<CustomTool> {
+        let input = CreateHttpToolRow {
+            name: req.name,
+            description: req.description,
+            parameters: req.parameters,
+            endpoint: serde_json::to_value(&req.endpoint)?,
+            secret_encrypted: self.encrypt_secret(req.secret.as_deref())?,
+        };
+
+        let row = self.db.create_http_tool(input).await?;
+        Self::row_to_tool(&row)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Option<CustomTool>> {
+        let row = self.db.get_http_tool(id).await?;
+        row.as_ref().map(Self::row_to_tool).transpose()
+    }
+
+    pub async fn list(&self) -> Result<Vec<CustomTool>> {
+        let rows = self.db.list_http_tools().await?;
+        rows.iter().map(Self::row_to_tool).collect()
+    }
+
+    pub async fn update(&self, id: Uuid, req: UpdateHttpToolRequest) -> Result<Option<CustomTool>> {
+        let endpoint = req
+            .endpoint
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?;
+
+        let input = UpdateHttpTool {
+            description: req.description,
+            parameters: req.parameters,
+            endpoint,
+            secret_encrypted: self.encrypt_secret(req.secret.as_deref())?,
+        };
+
+        let row = self.db.update_http_tool(id, input).await?;
+        row.as_ref().map(Self::row_to_tool).transpose()
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        self.db.delete_http_tool(id).await
+    }
+
+    /// Get the definition of a tool (used to validate partial updates)
+    pub async fn get_definition(&self, id: Uuid) -> Result<Option<HttpToolDefinition>> {
+        let row = self.db.get_http_tool(id).await?;
+        row.as_ref().map(Self::row_to_definition).transpose()
+    }
+
+    /// Get the tools attached to an agent (at a pinned revision, if given) with
+    /// decrypted secrets (for workers)
+    pub async fn get_agent_connections(
+        &self,
+        agent_id: Uuid,
+        revision: Option<i32>,
+    ) -> Result<Vec<HttpToolConnection>> {
+        let capability_ids: Vec<String> = match revision {
+            Some(revision) => self
+                .db
+                .get_agent_revision(agent_id, revision)
+                .await?
+                .map(|row| {
+                    row.capability_list()
+                        .into_iter()
+                        .map(|c| c.capability_id)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            None => self
+                .db
+                .get_agent_capabilities(agent_id)
+                .await?
+                .into_iter()
+                .map(|row| row.capability_id)
+                .collect(),
+        };
+        let names: Vec<String> = capability_ids
+            .iter()
+            .filter_map(|id| tool_name_from_capability_id(id))
+            .map(str::to_string)
+            .collect();
+        if names.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let rows = self.db.get_http_tools_by_name(&names).await?;
+        rows.iter()
+            .map(|row| {
+                Ok(HttpToolConnection::new(
+                    Self::row_to_definition(row)?,
+                    self.decrypt_secret(row)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn encrypt_secret(&self, secret: Option<&str>) -> Result<Option<Vec<u8>>> {
+        let Some(secret) = secret else {
+            return Ok(None);
+        };
+        let encryption = self
+            .encryption
+            .as_ref()
+            .ok_or_else(|| anyhow!("Encryption not configured. Cannot store tool secret."))?;
+        Ok(Some(encryption.encrypt_string(secret)?))
+    }
+
+    fn decrypt_secret(&self, row: &HttpToolRow) -> Result<Option<String>> {
+        let Some(encrypted) = &row.secret_encrypted else {
+            return Ok(None);
+        };
+        let encryption = self
+            .encryption
+            .as_ref()
+            .ok_or_else(|| anyhow!("Encryption not configured. Cannot decrypt tool secret."))?;
+        Ok(Some(encryption.decrypt_to_string(encrypted)?))
+    }
+
+    pub(crate) fn row_to_definition(row: &HttpToolRow) -> Result<HttpToolDefinition> {
+        Ok(HttpToolDefinition {
+            name: row.name.clone(),
+            description: row.description.clone(),
+            parameters: row.parameters.clone(),
+            endpoint: serde_json::from_value(row.endpoint.clone())?,
+        })
+    }
+
+    fn row_to_tool(row: &HttpToolRow) -> Result<CustomTool> {
+        Ok(CustomTool {
+            id: row.id,
+            name: row.name.clone(),
+            description: row.description.clone(),
+            parameters: row.parameters.clone(),
+            endpoint: serde_json::from_value(row.endpoint.clone())?,
+            capability_id: capability_id_for(&row.name),
+            secret_set: row.secret_encrypted.is_some(),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
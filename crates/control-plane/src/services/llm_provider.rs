@@ -23,6 +23,9 @@ impl LlmProviderService {
     }
 
     pub async fn create(&self, req: CreateLlmProviderRequest) -> Result<LlmProvider> {
+        let requests_per_minute = rate_limit_column(req.requests_per_minute)?;
+        let tokens_per_minute = rate_limit_column(req.tokens_per_minute)?;
+
         // Encrypt API key if provided
         let api_key_encrypted = if let Some(api_key) = &req.api_key {
             let encryption = self
@@ -40,6 +43,8 @@ impl LlmProviderService {
             base_url: req.base_url,
             api_key_encrypted,
             settings: None,
+            requests_per_minute,
+            tokens_per_minute,
         };
 
         let row = self.db.create_llm_provider(input).await?;
@@ -61,6 +66,9 @@ impl LlmProviderService {
         id: Uuid,
         req: UpdateLlmProviderRequest,
     ) -> Result<Option<LlmProvider>> {
+        let requests_per_minute = rate_limit_column(req.requests_per_minute)?;
+        let tokens_per_minute = rate_limit_column(req.tokens_per_minute)?;
+
         // Encrypt API key if provided
         let api_key_encrypted = if let Some(api_key) = &req.api_key {
             let encryption = self
@@ -82,6 +90,8 @@ impl LlmProviderService {
                 LlmProviderStatus::Disabled => "disabled".to_string(),
             }),
             settings: None,
+            requests_per_minute,
+            tokens_per_minute,
         };
 
         let row = self.db.update_llm_provider(id, input).await?;
@@ -109,12 +119,23 @@ impl LlmProviderService {
                 "active" => LlmProviderStatus::Active,
                 _ => LlmProviderStatus::Disabled,
             },
+            requests_per_minute: row.requests_per_minute.map(|v| v as u32),
+            tokens_per_minute: row.tokens_per_minute.map(|v| v as u32),
             created_at: row.created_at,
             updated_at: row.updated_at,
         }
     }
 }
 
+/// Convert a per-minute rate limit to its column value
+fn rate_limit_column(limit: Option<u32>) -> Result<Option<i32>> {
+    match limit {
+        Some(0) => Err(anyhow!("Rate limits must be greater than zero")),
+        Some(limit) => Ok(Some(i32::try_from(limit).unwrap_or(i32::MAX))),
+        None => Ok(None),
+    }
+}
+
 /// Check if a default API key is available from environment variable.
 ///
 /// Environment variables (for development convenience):
@@ -5,6 +5,7 @@
 
 use crate::storage::{Database, EncryptionService};
 use anyhow::{anyhow, Result};
+use everruns_durable::RateLimitConfig;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -22,6 +23,8 @@ pub struct ResolvedModel {
     pub api_key: Option<String>,
     /// Provider base URL override (if set)
     pub base_url: Option<String>,
+    /// Provider ID (keys the provider's shared rate limiter)
+    pub provider_id: Uuid,
 }
 
 pub struct LlmResolverService {
@@ -67,6 +70,7 @@ impl LlmResolverService {
             provider_type: provider_with_key.provider_type,
             api_key: provider_with_key.api_key,
             base_url: provider_with_key.base_url,
+            provider_id: provider_row.id,
         }))
     }
 
@@ -103,11 +107,28 @@ impl LlmResolverService {
             provider_type: provider_with_key.provider_type,
             api_key: provider_with_key.api_key,
             base_url: provider_with_key.base_url,
+            provider_id: provider_row.id,
         }))
     }
 
+    /// Configured rate limits of a provider (none if the provider is unknown)
+    pub async fn provider_rate_limits(&self, provider_id: Uuid) -> Result<RateLimitConfig> {
+        let provider_row = self.db.get_llm_provider(provider_id).await?;
+        Ok(provider_row
+            .map(|row| RateLimitConfig {
+                requests_per_minute: row.requests_per_minute.map(|v| v as u32),
+                tokens_per_minute: row.tokens_per_minute.map(|v| v as u32),
+            })
+            .unwrap_or_default())
+    }
+
     /// Check if encryption service is available
     pub fn has_encryption(&self) -> bool {
         self.encryption.is_some()
     }
 }
+
+/// Durable rate limiter key shared by all calls to a provider
+pub fn rate_limit_key(provider_id: Uuid) -> String {
+    format!("llm_provider:{}", provider_id)
+}
@@ -0,0 +1,543 @@
+// History retention and partition maintenance
+//
+// Each run:
+// 1. Creates the monthly partitions of the durable history tables for the
+//    current month and the next PARTITION_MONTHS_AHEAD months
+// 2. Archives (when WORKFLOW_ARCHIVE_DIR is set) and deletes terminal
+//    workflows past WORKFLOW_RETENTION_DAYS, with their events and tasks
+// 3. Deletes finished tasks past TASK_RETENTION_DAYS
+// 4. Deletes session events superseded longer than SUPERSEDED_EVENT_RETENTION_DAYS
+// 5. Prunes session file history: turn snapshots and file versions older than
+//    FILE_HISTORY_RETENTION_DAYS, and versions beyond FILE_HISTORY_MAX_VERSIONS
+//    per path, releasing objects no longer referenced
+// 6. Drops empty partitions of past months
+//
+// Decision: Nothing is deleted unless a retention period is configured.
+// Decision: Archives are gzipped JSONL files, one workflow (with its full
+// event history) per line and one file per batch. A file is written and
+// synced before its workflows are deleted, so history is never lost to a
+// failed archive.
+// Decision: A file's latest version and versions captured by a manual snapshot
+// are never pruned, so current content and named restore points survive.
+// Decision: Only empty partitions are dropped. Retention deletes the rows;
+// dropping the emptied partition returns the space without a vacuum.
+
+use crate::storage::{Database, FileContentStore};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use everruns_durable::{
+    ExpiredWorkflow, MonthlyPartition, PostgresWorkflowEventStore, WorkflowEventStore,
+    PARTITIONED_TABLES,
+};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::json;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Months after the current one that get partitions ahead of time
+const PARTITION_MONTHS_AHEAD: usize = 2;
+
+/// Default number of rows deleted (and workflows archived) per statement
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// How long finished history is kept. `None` keeps it forever.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Terminal workflows, by completion time
+    pub workflow_retention: Option<Duration>,
+    /// Finished tasks, by creation time
+    pub task_retention: Option<Duration>,
+    /// Superseded session events, by when they were superseded
+    pub superseded_event_retention: Option<Duration>,
+    /// Session file versions and turn snapshots, by creation time
+    pub file_history_retention: Option<Duration>,
+    /// Versions kept per file path (the newest ones)
+    pub max_file_versions: Option<u32>,
+    /// Directory receiving archives of deleted workflows
+    pub archive_dir: Option<PathBuf>,
+}
+
+impl RetentionPolicy {
+    /// Read the policy from WORKFLOW_RETENTION_DAYS, TASK_RETENTION_DAYS,
+    /// SUPERSEDED_EVENT_RETENTION_DAYS, FILE_HISTORY_RETENTION_DAYS,
+    /// FILE_HISTORY_MAX_VERSIONS and WORKFLOW_ARCHIVE_DIR
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            workflow_retention: retention_days_from_env("WORKFLOW_RETENTION_DAYS")?,
+            task_retention: retention_days_from_env("TASK_RETENTION_DAYS")?,
+            superseded_event_retention: retention_days_from_env("SUPERSEDED_EVENT_RETENTION_DAYS")?,
+            file_history_retention: retention_days_from_env("FILE_HISTORY_RETENTION_DAYS")?,
+            max_file_versions: match std::env::var("FILE_HISTORY_MAX_VERSIONS") {
+                Ok(value) if !value.trim().is_empty() => {
+                    Some(parse_max_versions(&value).context("Invalid FILE_HISTORY_MAX_VERSIONS")?)
+                }
+                _ => None,
+            },
+            archive_dir: std::env::var("WORKFLOW_ARCHIVE_DIR")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from),
+        })
+    }
+}
+
+fn retention_days_from_env(name: &str) -> Result<Option<Duration>> {
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => parse_retention_days(&value)
+            .map(Some)
+            .with_context(|| format!("Invalid {}", name)),
+        _ => Ok(None),
+    }
+}
+
+/// Parse a retention period given in whole days
+pub fn parse_retention_days(value: &str) -> Result<Duration> {
+    let days: u64 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("expected a number of days, got '{}'", value.trim()))?;
+    if days == 0 {
+        bail!("retention must be at least one day");
+    }
+    Ok(Duration::from_secs(days * 24 * 60 * 60))
+}
+
+/// Parse the number of versions kept per file path
+pub fn parse_max_versions(value: &str) -> Result<u32> {
+    let versions: u32 = value
+        .trim()
+        .parse()
+        .with_context(|| format!("expected a number of versions, got '{}'", value.trim()))?;
+    if versions == 0 {
+        bail!("at least one version must be kept");
+    }
+    Ok(versions)
+}
+
+/// What a maintenance run changed, or would change in a dry run
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceReport {
+    pub dry_run: bool,
+    pub partitions_created: Vec<String>,
+    pub workflows_deleted: u64,
+    pub archive_files: Vec<PathBuf>,
+    pub tasks_deleted: u64,
+    pub superseded_events_deleted: u64,
+    pub snapshots_deleted: u64,
+    pub file_versions_deleted: u64,
+    pub partitions_dropped: Vec<String>,
+}
+
+impl fmt::Display for MaintenanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "would be " } else { "" };
+        let list = |names: &[String]| {
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
+        };
+        writeln!(
+            f,
+            "Partitions {}created: {}",
+            verb,
+            list(&self.partitions_created)
+        )?;
+        writeln!(f, "Workflows {}deleted: {}", verb, self.workflows_deleted)?;
+        for path in &self.archive_files {
+            writeln!(f, "  archived to {}", path.display())?;
+        }
+        writeln!(f, "Tasks {}deleted: {}", verb, self.tasks_deleted)?;
+        writeln!(
+            f,
+            "Superseded events {}deleted: {}",
+            verb, self.superseded_events_deleted
+        )?;
+        writeln!(
+            f,
+            "Turn snapshots {}deleted: {}",
+            verb, self.snapshots_deleted
+        )?;
+        writeln!(
+            f,
+            "File versions {}deleted: {}",
+            verb, self.file_versions_deleted
+        )?;
+        write!(
+            f,
+            "Partitions {}dropped: {}",
+            verb,
+            list(&self.partitions_dropped)
+        )
+    }
+}
+
+/// Applies the retention policy and maintains partitions
+pub struct MaintenanceService {
+    db: Arc<Database>,
+    store: PostgresWorkflowEventStore,
+    content: Arc<FileContentStore>,
+    policy: RetentionPolicy,
+    batch_size: usize,
+}
+
+impl MaintenanceService {
+    pub fn new(db: Arc<Database>, policy: RetentionPolicy) -> Self {
+        Self {
+            store: PostgresWorkflowEventStore::new(db.pool().clone()),
+            db,
+            content: Arc::new(FileContentStore::inline_only()),
+            policy,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Object storage of session files, for releasing pruned versions
+    pub fn with_file_content(mut self, content: Arc<FileContentStore>) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Rows deleted (and workflows archived) per statement
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Run maintenance forever
+    pub async fn run(self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match self.run_once(false).await {
+                Ok(report) => tracing::info!(
+                    partitions_created = report.partitions_created.len(),
+                    workflows_deleted = report.workflows_deleted,
+                    archive_files = report.archive_files.len(),
+                    tasks_deleted = report.tasks_deleted,
+                    superseded_events_deleted = report.superseded_events_deleted,
+                    snapshots_deleted = report.snapshots_deleted,
+                    file_versions_deleted = report.file_versions_deleted,
+                    partitions_dropped = report.partitions_dropped.len(),
+                    "Maintenance run finished"
+                ),
+                Err(e) => tracing::error!("Maintenance run failed: {:#}", e),
+            }
+        }
+    }
+
+    /// Run maintenance once. A dry run only reports what would change.
+    pub async fn run_once(&self, dry_run: bool) -> Result<MaintenanceReport> {
+        let now = Utc::now();
+        let mut report = MaintenanceReport {
+            dry_run,
+            ..Default::default()
+        };
+
+        self.create_partitions(now, &mut report).await?;
+
+        if let Some(retention) = self.policy.workflow_retention {
+            let cutoff = cutoff(now, retention)?;
+            if dry_run {
+                report.workflows_deleted = self.store.count_expired_workflows(cutoff).await?;
+            } else {
+                self.delete_workflows(cutoff, &mut report).await?;
+            }
+        }
+
+        if let Some(retention) = self.policy.task_retention {
+            let cutoff = cutoff(now, retention)?;
+            if dry_run {
+                report.tasks_deleted = self.store.count_expired_tasks(cutoff).await?;
+            } else {
+                loop {
+                    let deleted = self
+                        .store
+                        .delete_expired_tasks(cutoff, self.batch_size)
+                        .await?;
+                    report.tasks_deleted += deleted;
+                    if deleted < self.batch_size as u64 {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(retention) = self.policy.superseded_event_retention {
+            let cutoff = cutoff(now, retention)?;
+            if dry_run {
+                report.superseded_events_deleted =
+                    self.db.count_superseded_events(cutoff).await? as u64;
+            } else {
+                loop {
+                    let deleted = self
+                        .db
+                        .delete_superseded_events(cutoff, self.batch_size as i64)
+                        .await?;
+                    report.superseded_events_deleted += deleted;
+                    if deleted < self.batch_size as u64 {
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.prune_file_history(now, &mut report).await?;
+
+        self.drop_empty_partitions(now, &mut report).await?;
+
+        Ok(report)
+    }
+
+    /// Delete expired turn snapshots, then the file versions nothing keeps
+    async fn prune_file_history(
+        &self,
+        now: DateTime<Utc>,
+        report: &mut MaintenanceReport,
+    ) -> Result<()> {
+        let cutoff = match self.policy.file_history_retention {
+            Some(retention) => Some(cutoff(now, retention)?),
+            None => None,
+        };
+        let max_versions = self.policy.max_file_versions.map(i64::from);
+        if cutoff.is_none() && max_versions.is_none() {
+            return Ok(());
+        }
+
+        if report.dry_run {
+            if let Some(cutoff) = cutoff {
+                report.snapshots_deleted =
+                    self.db.count_expired_turn_snapshots(cutoff).await? as u64;
+            }
+            report.file_versions_deleted = self
+                .db
+                .count_expired_file_versions(cutoff, max_versions)
+                .await? as u64;
+            return Ok(());
+        }
+
+        if let Some(cutoff) = cutoff {
+            loop {
+                let deleted = self
+                    .db
+                    .delete_expired_turn_snapshots(cutoff, self.batch_size as i64)
+                    .await?;
+                report.snapshots_deleted += deleted;
+                if deleted < self.batch_size as u64 {
+                    break;
+                }
+            }
+        }
+
+        loop {
+            let (deleted, unreferenced) = self
+                .db
+                .delete_expired_file_versions(cutoff, max_versions, self.batch_size as i64)
+                .await?;
+            report.file_versions_deleted += deleted;
+            self.content.release(unreferenced).await;
+            if deleted < self.batch_size as u64 {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn create_partitions(
+        &self,
+        now: DateTime<Utc>,
+        report: &mut MaintenanceReport,
+    ) -> Result<()> {
+        for table in PARTITIONED_TABLES {
+            let existing = self.store.list_partitions(table).await?;
+            let mut partition = MonthlyPartition::containing(table, now);
+            for _ in 0..=PARTITION_MONTHS_AHEAD {
+                if !existing.contains(&partition) {
+                    if !report.dry_run {
+                        self.store.create_partition(&partition).await?;
+                    }
+                    report.partitions_created.push(partition.name.clone());
+                }
+                partition = partition.next();
+            }
+        }
+        Ok(())
+    }
+
+    async fn drop_empty_partitions(
+        &self,
+        now: DateTime<Utc>,
+        report: &mut MaintenanceReport,
+    ) -> Result<()> {
+        for table in PARTITIONED_TABLES {
+            let current = MonthlyPartition::containing(table, now);
+            for partition in self.store.list_partitions(table).await? {
+                if partition.ends_at > current.starts_at
+                    || !self.store.is_partition_empty(&partition).await?
+                {
+                    continue;
+                }
+                if !report.dry_run {
+                    self.store.drop_partition(&partition).await?;
+                }
+                report.partitions_dropped.push(partition.name);
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_workflows(
+        &self,
+        cutoff: DateTime<Utc>,
+        report: &mut MaintenanceReport,
+    ) -> Result<()> {
+        loop {
+            let expired = self
+                .store
+                .list_expired_workflows(cutoff, self.batch_size)
+                .await?;
+            if expired.is_empty() {
+                return Ok(());
+            }
+
+            if let Some(dir) = &self.policy.archive_dir {
+                let path = self.archive(dir, &expired).await?;
+                report.archive_files.push(path);
+            }
+
+            let ids: Vec<_> = expired.iter().map(|w| w.id).collect();
+            let deleted = self.store.delete_workflows(&ids).await?;
+            report.workflows_deleted += deleted;
+            if deleted == 0 || expired.len() < self.batch_size {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Write the histories of `workflows` to a new archive file in `dir`
+    async fn archive(&self, dir: &Path, workflows: &[ExpiredWorkflow]) -> Result<PathBuf> {
+        let mut records = Vec::with_capacity(workflows.len());
+        for workflow in workflows {
+            let info = self.store.get_workflow_info(workflow.id).await?;
+            let events = self.store.load_events(workflow.id).await?;
+            records.push(json!({
+                "id": workflow.id,
+                "workflow_type": workflow.workflow_type,
+                "status": workflow.status,
+                "input": info.input,
+                "result": info.result,
+                "error": info.error,
+                "created_at": workflow.created_at,
+                "completed_at": workflow.completed_at,
+                "events": events
+                    .into_iter()
+                    .map(|(sequence, event)| json!({ "sequence": sequence, "event": event }))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+        let data = encode_archive(&records)?;
+
+        // Name by the first workflow so batches of one run never collide
+        let path = dir.join(format!(
+            "workflows-{}-{}.jsonl.gz",
+            Utc::now().format("%Y%m%dT%H%M%SZ"),
+            workflows[0].id
+        ));
+        let target = path.clone();
+        tokio::task::spawn_blocking(move || write_synced(&target, &data))
+            .await
+            .context("Archive writer panicked")?
+            .with_context(|| format!("Failed to write archive {}", path.display()))?;
+
+        tracing::info!(
+            path = %path.display(),
+            workflows = workflows.len(),
+            "Archived workflow histories"
+        );
+        Ok(path)
+    }
+}
+
+fn cutoff(now: DateTime<Utc>, retention: Duration) -> Result<DateTime<Utc>> {
+    Ok(now - chrono::Duration::from_std(retention).context("Retention period too long")?)
+}
+
+/// Gzip-compressed JSONL, one record per line
+pub fn encode_archive(records: &[serde_json::Value]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for record in records {
+        serde_json::to_writer(&mut encoder, record)?;
+        encoder.write_all(b"\n")?;
+    }
+    Ok(encoder.finish()?)
+}
+
+/// Write a file and sync it to disk; the file appears under its final name
+/// only once complete
+fn write_synced(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let partial = path.with_extension("gz.partial");
+    let mut file = std::fs::File::create(&partial)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    std::fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn test_parse_retention_days() {
+        assert_eq!(
+            parse_retention_days("30").unwrap(),
+            Duration::from_secs(30 * 86_400)
+        );
+        assert_eq!(
+            parse_retention_days(" 1 ").unwrap(),
+            Duration::from_secs(86_400)
+        );
+        assert!(parse_retention_days("0").is_err());
+        assert!(parse_retention_days("7d").is_err());
+    }
+
+    #[test]
+    fn test_parse_max_versions() {
+        assert_eq!(parse_max_versions(" 20 ").unwrap(), 20);
+        assert!(parse_max_versions("0").is_err());
+        assert!(parse_max_versions("-1").is_err());
+    }
+
+    #[test]
+    fn test_encode_archive_is_gzipped_jsonl() {
+        let records = vec![json!({"id": 1, "events": []}), json!({"id": 2})];
+        let data = encode_archive(&records).unwrap();
+
+        let lines: Vec<serde_json::Value> = BufReader::new(GzDecoder::new(data.as_slice()))
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        assert_eq!(lines, records);
+    }
+
+    #[test]
+    fn test_dry_run_report() {
+        let report = MaintenanceReport {
+            dry_run: true,
+            partitions_created: vec!["durable_task_queue_p2026_12".to_string()],
+            workflows_deleted: 3,
+            ..Default::default()
+        };
+        let text = report.to_string();
+        assert!(text.contains("Partitions would be created: durable_task_queue_p2026_12"));
+        assert!(text.contains("Workflows would be deleted: 3"));
+        assert!(text.contains("Partitions would be dropped: none"));
+    }
+}
@@ -4,14 +4,15 @@
 // - Creating user message events
 // - Listing messages by querying message events
 // - Workflow triggering for user messages
+// - Regenerating and editing turns (superseding the events they replace)
 
 use super::EventService;
 use crate::api::messages::{ContentPart, CreateMessageRequest, Message, MessageRole};
 use crate::storage::Database;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::Utc;
-use everruns_core::events::{EventContext, EventRequest, MessageUserData};
-use everruns_core::Event;
+use everruns_core::events::{EventContext, EventRequest, EventsSupersededData, MessageUserData};
+use everruns_core::{Event, SessionStatus};
 use everruns_worker::AgentRunner;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -42,6 +43,123 @@ impl MessageService {
         agent_id: Uuid,
         session_id: Uuid,
         req: CreateMessageRequest,
+    ) -> Result<Message> {
+        self.create_with_id(agent_id, session_id, Uuid::now_v7(), req)
+            .await
+    }
+
+    /// Create a user message with a caller-chosen id
+    ///
+    /// Lets callers record the id before the message exists, e.g. the
+    /// scheduler links a run to its message ahead of posting it.
+    pub async fn create_with_id(
+        &self,
+        agent_id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+        req: CreateMessageRequest,
+    ) -> Result<Message> {
+        let message = self
+            .append_user_message(session_id, message_id, req)
+            .await?;
+        self.start_turn(agent_id, session_id, message.id);
+        Ok(message)
+    }
+
+    /// Rerun the turn for the latest user message
+    ///
+    /// Everything after that message (the agent's reply, tool calls, turn
+    /// events) is marked superseded, then a new turn is started for the same
+    /// message. Returns the user message being rerun.
+    pub async fn regenerate(&self, agent_id: Uuid, session_id: Uuid) -> Result<Message> {
+        self.ensure_idle(session_id).await?;
+
+        let event = self
+            .db
+            .find_user_message_event(session_id, None)
+            .await?
+            .ok_or_else(|| anyhow!("Cannot regenerate: session has no user message"))?;
+        let message =
+            Self::event_to_message(session_id, &event.data, &event.event_type, event.sequence)
+                .map_err(|e| anyhow!(e))?;
+
+        self.supersede(session_id, event.sequence + 1, message.id)
+            .await?;
+        self.start_turn(agent_id, session_id, message.id);
+
+        Ok(message)
+    }
+
+    /// Replace a user message and rerun the conversation from there
+    ///
+    /// The original message and everything after it are marked superseded,
+    /// the new content is appended as a fresh user message, and a turn is
+    /// started for it. Returns the new message.
+    pub async fn edit(
+        &self,
+        agent_id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+        req: CreateMessageRequest,
+    ) -> Result<Message> {
+        self.ensure_idle(session_id).await?;
+
+        let event = self
+            .db
+            .find_user_message_event(session_id, Some(message_id))
+            .await?
+            .ok_or_else(|| anyhow!("Message not found: {}", message_id))?;
+
+        let new_message_id = Uuid::now_v7();
+        self.supersede(session_id, event.sequence, new_message_id)
+            .await?;
+        let message = self
+            .append_user_message(session_id, new_message_id, req)
+            .await?;
+        self.start_turn(agent_id, session_id, message.id);
+
+        Ok(message)
+    }
+
+    // History can only be rewritten between turns
+    async fn ensure_idle(&self, session_id: Uuid) -> Result<()> {
+        let session = self
+            .db
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| anyhow!("Session not found: {}", session_id))?;
+        if session.status == SessionStatus::Active.to_string() {
+            return Err(anyhow!("Cannot rewrite history while a turn is running"));
+        }
+        Ok(())
+    }
+
+    // Mark events superseded and tell stream listeners which ones to drop
+    async fn supersede(
+        &self,
+        session_id: Uuid,
+        from_sequence: i32,
+        input_message_id: Uuid,
+    ) -> Result<()> {
+        self.db.supersede_events(session_id, from_sequence).await?;
+        self.event_service
+            .emit(EventRequest::new(
+                session_id,
+                EventContext::empty(),
+                EventsSupersededData {
+                    from_sequence,
+                    input_message_id,
+                },
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn append_user_message(
+        &self,
+        session_id: Uuid,
+        message_id: Uuid,
+        req: CreateMessageRequest,
     ) -> Result<Message> {
         // Convert InputContentPart array to ContentPart array
         let content: Vec<ContentPart> = req
@@ -51,8 +169,6 @@ impl MessageService {
             .map(ContentPart::from)
             .collect();
 
-        // Generate a new message ID
-        let message_id = Uuid::now_v7();
         let now = Utc::now();
 
         // Build the core message
@@ -76,7 +192,7 @@ impl MessageService {
             .await?;
 
         // Construct API Message
-        let message = Message {
+        Ok(Message {
             id: message_id,
             session_id,
             sequence: stored_event.sequence.unwrap_or(0),
@@ -85,10 +201,12 @@ impl MessageService {
             controls: req.controls,
             metadata: req.metadata,
             created_at: now,
-        };
+        })
+    }
 
-        // Start workflow for user message in background (don't block the response)
-        // The message is already persisted, so we can return immediately
+    // Start workflow for user message in background (don't block the response)
+    // The message is already persisted, so we can return immediately
+    fn start_turn(&self, agent_id: Uuid, session_id: Uuid, message_id: Uuid) {
         let runner = self.runner.clone();
         tokio::spawn(async move {
             if let Err(e) = runner.start_run(session_id, agent_id, message_id).await {
@@ -106,8 +224,6 @@ impl MessageService {
                 );
             }
         });
-
-        Ok(message)
     }
 
     pub async fn list(&self, session_id: Uuid) -> Result<Vec<Message>> {
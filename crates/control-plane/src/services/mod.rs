@@ -4,19 +4,39 @@
 pub mod agent;
 pub mod capability;
 pub mod event;
+pub mod http_tool;
 pub mod llm_model;
 pub mod llm_provider;
 pub mod llm_resolver;
+pub mod maintenance;
 pub mod message;
+pub mod schedule;
+pub mod scheduler;
 pub mod session;
+pub mod session_archive;
 pub mod session_file;
+pub mod task_routing;
+pub mod timed_out_turns;
+pub mod turn_snapshot;
+pub mod webhook;
+pub mod webhook_delivery;
 
 pub use agent::AgentService;
 pub use capability::CapabilityService;
 pub use event::EventService;
+pub use http_tool::HttpToolService;
 pub use llm_model::LlmModelService;
 pub use llm_provider::LlmProviderService;
-pub use llm_resolver::{LlmResolverService, ResolvedModel};
+pub use llm_resolver::{rate_limit_key, LlmResolverService, ResolvedModel};
+pub use maintenance::{MaintenanceReport, MaintenanceService, RetentionPolicy};
 pub use message::MessageService;
+pub use schedule::ScheduleService;
+pub use scheduler::{ScheduleRunListener, Scheduler};
 pub use session::SessionService;
-pub use session_file::SessionFileService;
+pub use session_archive::ArchiveFormat;
+pub use session_file::{SessionFileLimits, SessionFileService};
+pub use task_routing::{FairnessKey, TaskRouteRule, TaskRouter};
+pub use timed_out_turns::TimedOutTurns;
+pub use turn_snapshot::TurnSnapshotListener;
+pub use webhook::WebhookService;
+pub use webhook_delivery::{WebhookDeliveryWorker, WebhookListener};
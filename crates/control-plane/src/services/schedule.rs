@@ -0,0 +1,443 @@
+// Agent schedule service
+//
+// Schedules use standard 5-field cron expressions (minute hour day-of-month
+// month day-of-week) or macros such as `@daily`, evaluated in an IANA
+// timezone. The `cron` crate expects a leading seconds field and numbers days
+// of the week from 1 (Sunday), so expressions are translated before parsing.
+//
+// Decision: The schedule stores `next_run_at`; the scheduler plans runs for
+// schedules whose next occurrence is due and advances it. Occurrences more
+// than MISSED_RUN_GRACE in the past were missed (the control plane was down)
+// and are handled per the schedule's missed-run policy.
+
+use crate::storage::{
+    models::{CreateScheduleRow, SchedulePlan, ScheduleRow, ScheduleRunRow, UpdateSchedule},
+    Database,
+};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::api::schedules::{
+    CreateScheduleRequest, MissedRunPolicy, OverlapPolicy, Schedule, ScheduleRun,
+    ScheduleRunStatus, UpdateScheduleRequest,
+};
+
+/// How late an occurrence may be planned and still count as on time
+const MISSED_RUN_GRACE: Duration = Duration::minutes(2);
+
+/// Maximum number of missed occurrences run by the `run_all` policy
+const MAX_MISSED_RUNS: usize = 100;
+
+/// A parsed cron expression bound to a timezone
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    schedule: cron::Schedule,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str, timezone: &str) -> Result<Self> {
+        let timezone =
+            Tz::from_str(timezone).map_err(|_| anyhow!("Invalid timezone: {}", timezone))?;
+        let schedule = cron::Schedule::from_str(&translate_expression(expression)?)
+            .map_err(|e| anyhow!("Invalid cron expression '{}': {}", expression, e))?;
+        Ok(Self { schedule, timezone })
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .next()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Decide which occurrences to run for a schedule whose next occurrence
+    /// (`next_run_at`) is due, and the occurrence to wait for after `now`
+    pub fn plan(
+        &self,
+        next_run_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+        policy: MissedRunPolicy,
+    ) -> SchedulePlan {
+        let missed_before = now - MISSED_RUN_GRACE;
+        let mut missed = VecDeque::new();
+        let mut on_time = Vec::new();
+
+        let mut occurrence = Some(next_run_at);
+        while let Some(at) = occurrence.filter(|at| *at <= now) {
+            if at < missed_before {
+                if missed.len() == MAX_MISSED_RUNS {
+                    missed.pop_front();
+                }
+                missed.push_back(at);
+            } else {
+                on_time.push(at);
+            }
+            occurrence = self.next_after(at);
+        }
+
+        let runs = match policy {
+            MissedRunPolicy::Skip => on_time,
+            MissedRunPolicy::RunOnce if on_time.is_empty() => {
+                missed.pop_back().into_iter().collect()
+            }
+            MissedRunPolicy::RunOnce => on_time,
+            MissedRunPolicy::RunAll => missed.into_iter().chain(on_time).collect(),
+        };
+
+        SchedulePlan {
+            runs,
+            next_run_at: occurrence,
+        }
+    }
+}
+
+/// Translate a 5-field cron expression to the `cron` crate's format
+fn translate_expression(expression: &str) -> Result<String> {
+    let expression = expression.trim();
+    if expression.starts_with('@') {
+        return Ok(expression.to_string());
+    }
+
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+        return Err(anyhow!(
+            "Invalid cron expression '{}': expected 5 fields (minute hour day-of-month month day-of-week)",
+            expression
+        ));
+    };
+    let day_of_week = translate_day_of_week(day_of_week)
+        .ok_or_else(|| anyhow!("Invalid cron expression '{}': bad day of week", expression))?;
+
+    Ok(format!(
+        "0 {} {} {} {} {}",
+        minute, hour, day_of_month, month, day_of_week
+    ))
+}
+
+/// Replace day-of-week numbers (0-7, both 0 and 7 are Sunday) with names
+fn translate_day_of_week(field: &str) -> Option<String> {
+    const DAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+    let day = |s: &str| match s.parse::<usize>() {
+        Ok(n) => DAYS.get(n).map(|d| d.to_string()),
+        Err(_) => Some(s.to_string()),
+    };
+
+    let mut items = Vec::new();
+    for item in field.split(',') {
+        let (base, step) = match item.split_once('/') {
+            Some((base, step)) => (base, Some(step)),
+            None => (item, None),
+        };
+        let base = match base.split_once('-') {
+            // Sunday as the end of a range, e.g. 5-7
+            Some((start, "7")) if step.is_none() => {
+                items.push("SUN".to_string());
+                format!("{}-SAT", day(start)?)
+            }
+            Some((start, end)) => format!("{}-{}", day(start)?, day(end)?),
+            None if base == "*" => base.to_string(),
+            None => day(base)?,
+        };
+        items.push(match step {
+            Some(step) => format!("{}/{}", base, step),
+            None => base,
+        });
+    }
+    Some(items.join(","))
+}
+
+pub struct ScheduleService {
+    db: Arc<Database>,
+}
+
+impl ScheduleService {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    pub async fn create(&self, agent_id: Uuid, req: CreateScheduleRequest) -> Result<Schedule> {
+        self.db
+            .get_agent(agent_id)
+            .await?
+            .ok_or_else(|| anyhow!("Agent not found: {}", agent_id))?;
+        validate_text(&req.name, &req.message)?;
+        let cron = CronSchedule::parse(&req.cron, &req.timezone)?;
+
+        if let Some(session_id) = req.session_id {
+            let session = self.db.get_session(session_id).await?;
+            if session.is_none_or(|s| s.agent_id != agent_id) {
+                return Err(anyhow!(
+                    "Target session {} not found for this agent",
+                    session_id
+                ));
+            }
+        }
+
+        let next_run_at = if req.enabled {
+            cron.next_after(Utc::now())
+        } else {
+            None
+        };
+
+        let input = CreateScheduleRow {
+            agent_id,
+            name: req.name,
+            cron: req.cron.trim().to_string(),
+            timezone: req.timezone,
+            message: req.message,
+            session_id: req.session_id,
+            session_variables: serde_json::to_value(&req.session_variables)?,
+            missed_run_policy: req.missed_run_policy.as_str().to_string(),
+            overlap_policy: req.overlap_policy.as_str().to_string(),
+            enabled: req.enabled,
+            next_run_at,
+        };
+
+        let row = self.db.create_schedule(input).await?;
+        Ok(Self::row_to_schedule(row))
+    }
+
+    pub async fn get(&self, agent_id: Uuid, id: Uuid) -> Result<Option<Schedule>> {
+        Ok(self.get_row(agent_id, id).await?.map(Self::row_to_schedule))
+    }
+
+    pub async fn list(&self, agent_id: Uuid) -> Result<Vec<Schedule>> {
+        let rows = self.db.list_schedules(agent_id).await?;
+        Ok(rows.into_iter().map(Self::row_to_schedule).collect())
+    }
+
+    pub async fn update(
+        &self,
+        agent_id: Uuid,
+        id: Uuid,
+        req: UpdateScheduleRequest,
+    ) -> Result<Option<Schedule>> {
+        let Some(current) = self.get_row(agent_id, id).await? else {
+            return Ok(None);
+        };
+        validate_text(
+            req.name.as_deref().unwrap_or(&current.name),
+            req.message.as_deref().unwrap_or(&current.message),
+        )?;
+
+        let expression = req.cron.as_deref().unwrap_or(&current.cron);
+        let timezone = req.timezone.as_deref().unwrap_or(&current.timezone);
+        let cron = CronSchedule::parse(expression, timezone)?;
+
+        // Recompute the next occurrence when the timing changes or the
+        // schedule is re-enabled, so occurrences while it was off are not missed runs
+        let enabled = req.enabled.unwrap_or(current.enabled);
+        let timing_changed = req.cron.is_some() || req.timezone.is_some();
+        let next_run_at = if !enabled {
+            Some(None)
+        } else if timing_changed || !current.enabled {
+            Some(cron.next_after(Utc::now()))
+        } else {
+            None
+        };
+
+        let input = UpdateSchedule {
+            name: req.name,
+            cron: req.cron.map(|c| c.trim().to_string()),
+            timezone: req.timezone,
+            message: req.message,
+            session_variables: req
+                .session_variables
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()?,
+            missed_run_policy: req.missed_run_policy.map(|p| p.as_str().to_string()),
+            overlap_policy: req.overlap_policy.map(|p| p.as_str().to_string()),
+            enabled: req.enabled,
+            next_run_at,
+        };
+
+        let row = self.db.update_schedule(agent_id, id, input).await?;
+        Ok(row.map(Self::row_to_schedule))
+    }
+
+    pub async fn delete(&self, agent_id: Uuid, id: Uuid) -> Result<bool> {
+        self.db.delete_schedule(agent_id, id).await
+    }
+
+    /// List runs, newest first. Returns None if the schedule does not exist.
+    pub async fn list_runs(
+        &self,
+        agent_id: Uuid,
+        id: Uuid,
+        limit: i64,
+    ) -> Result<Option<Vec<ScheduleRun>>> {
+        if self.get_row(agent_id, id).await?.is_none() {
+            return Ok(None);
+        }
+        let rows = self.db.list_schedule_runs(id, limit).await?;
+        Ok(Some(rows.into_iter().map(Self::row_to_run).collect()))
+    }
+
+    /// Record a run for now; the scheduler dispatches it on its next tick
+    pub async fn trigger(&self, agent_id: Uuid, id: Uuid) -> Result<Option<ScheduleRun>> {
+        if self.get_row(agent_id, id).await?.is_none() {
+            return Ok(None);
+        }
+        let row = self.db.create_schedule_run(id, Utc::now()).await?;
+        Ok(Some(Self::row_to_run(row)))
+    }
+
+    async fn get_row(&self, agent_id: Uuid, id: Uuid) -> Result<Option<ScheduleRow>> {
+        let row = self.db.get_schedule(id).await?;
+        Ok(row.filter(|row| row.agent_id == agent_id))
+    }
+
+    pub(crate) fn row_to_schedule(row: ScheduleRow) -> Schedule {
+        Schedule {
+            id: row.id,
+            agent_id: row.agent_id,
+            name: row.name,
+            cron: row.cron,
+            timezone: row.timezone,
+            message: row.message,
+            session_id: row.session_id,
+            session_variables: session_variables(&row.session_variables),
+            missed_run_policy: MissedRunPolicy::from(row.missed_run_policy.as_str()),
+            overlap_policy: OverlapPolicy::from(row.overlap_policy.as_str()),
+            enabled: row.enabled,
+            next_run_at: row.next_run_at,
+            last_run_at: row.last_run_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+
+    fn row_to_run(row: ScheduleRunRow) -> ScheduleRun {
+        ScheduleRun {
+            id: row.id,
+            schedule_id: row.schedule_id,
+            scheduled_for: row.scheduled_for,
+            status: ScheduleRunStatus::from(row.status.as_str()),
+            session_id: row.session_id,
+            message_id: row.message_id,
+            error: row.error,
+            started_at: row.started_at,
+            finished_at: row.finished_at,
+        }
+    }
+}
+
+pub(crate) fn session_variables(value: &serde_json::Value) -> BTreeMap<String, String> {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+fn validate_text(name: &str, message: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("Schedule name cannot be empty"));
+    }
+    if message.trim().is_empty() {
+        return Err(anyhow!("Schedule message cannot be empty"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_translate_expression() {
+        assert_eq!(
+            translate_expression("*/15 9-17 * * 1-5").unwrap(),
+            "0 */15 9-17 * * MON-FRI"
+        );
+        assert_eq!(
+            translate_expression("0 0 * * 0,6").unwrap(),
+            "0 0 0 * * SUN,SAT"
+        );
+        assert_eq!(
+            translate_expression("0 0 * * 5-7").unwrap(),
+            "0 0 0 * * SUN,FRI-SAT"
+        );
+        assert_eq!(translate_expression(" @daily ").unwrap(), "@daily");
+        assert!(translate_expression("0 0 * *").is_err());
+        assert!(translate_expression("0 0 0 * * *").is_err());
+        assert!(translate_expression("0 0 * * 8").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_input() {
+        let err = CronSchedule::parse("0 9 * * *", "Mars/Olympus").unwrap_err();
+        assert!(err.to_string().contains("Invalid timezone"));
+        let err = CronSchedule::parse("61 9 * * *", "UTC").unwrap_err();
+        assert!(err.to_string().contains("Invalid cron expression"));
+    }
+
+    #[test]
+    fn test_next_after_uses_timezone() {
+        // 09:00 in Berlin is 07:00 UTC in summer
+        let cron = CronSchedule::parse("0 9 * * MON-FRI", "Europe/Berlin").unwrap();
+        // Saturday
+        let next = cron.next_after(utc(2026, 6, 13, 12, 0)).unwrap();
+        assert_eq!(next, utc(2026, 6, 15, 7, 0));
+    }
+
+    #[test]
+    fn test_sunday_numbers() {
+        let cron = CronSchedule::parse("0 12 * * 0", "UTC").unwrap();
+        let next = cron.next_after(utc(2026, 6, 15, 0, 0)).unwrap();
+        assert_eq!(next, utc(2026, 6, 21, 12, 0));
+        let cron = CronSchedule::parse("0 12 * * 7", "UTC").unwrap();
+        assert_eq!(cron.next_after(utc(2026, 6, 15, 0, 0)).unwrap(), next);
+    }
+
+    #[test]
+    fn test_plan_on_time() {
+        let cron = CronSchedule::parse("0 * * * *", "UTC").unwrap();
+        let plan = cron.plan(
+            utc(2026, 1, 1, 10, 0),
+            utc(2026, 1, 1, 10, 0) + Duration::seconds(20),
+            MissedRunPolicy::Skip,
+        );
+        assert_eq!(plan.runs, vec![utc(2026, 1, 1, 10, 0)]);
+        assert_eq!(plan.next_run_at, Some(utc(2026, 1, 1, 11, 0)));
+    }
+
+    #[test]
+    fn test_plan_missed_runs() {
+        let cron = CronSchedule::parse("0 * * * *", "UTC").unwrap();
+        let next_run_at = utc(2026, 1, 1, 6, 0);
+        let now = utc(2026, 1, 1, 10, 30);
+
+        let plan = cron.plan(next_run_at, now, MissedRunPolicy::Skip);
+        assert!(plan.runs.is_empty());
+        assert_eq!(plan.next_run_at, Some(utc(2026, 1, 1, 11, 0)));
+
+        let plan = cron.plan(next_run_at, now, MissedRunPolicy::RunOnce);
+        assert_eq!(plan.runs, vec![utc(2026, 1, 1, 10, 0)]);
+
+        let plan = cron.plan(next_run_at, now, MissedRunPolicy::RunAll);
+        assert_eq!(plan.runs.len(), 5);
+        assert_eq!(plan.runs[0], next_run_at);
+    }
+
+    #[test]
+    fn test_plan_run_all_is_capped() {
+        let cron = CronSchedule::parse("* * * * *", "UTC").unwrap();
+        let now = utc(2026, 1, 2, 0, 0);
+        let plan = cron.plan(utc(2026, 1, 1, 0, 0), now, MissedRunPolicy::RunAll);
+
+        // The latest missed occurrences plus the on-time ones
+        assert_eq!(plan.runs.len(), MAX_MISSED_RUNS + 3);
+        assert_eq!(*plan.runs.last().unwrap(), now);
+        assert_eq!(plan.next_run_at, Some(now + Duration::minutes(1)));
+    }
+}
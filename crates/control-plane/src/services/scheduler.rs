@@ -0,0 +1,281 @@
+// Background scheduler for agent schedules
+//
+// Each tick plans runs for schedules whose next occurrence is due, then
+// claims pending runs (planned or manually triggered) and dispatches them:
+// the schedule's message is posted to the target session, which starts the
+// durable turn workflow like any user message. ScheduleRunListener finishes
+// the run when that turn completes or fails.
+//
+// Decision: Planning and claiming use `FOR UPDATE SKIP LOCKED`, so several
+// control-plane replicas can run the scheduler without double-dispatching.
+// Decision: A run records its session and message id before the message is
+// posted, so a finished turn can always be matched to its run. Runs left in
+// `running` without their message (e.g. the replica died mid-dispatch) are
+// failed by a sweep once DISPATCH_TIMEOUT has passed.
+// Decision: Overlap is checked at dispatch time. With the `skip` policy a run
+// is skipped while a previous run's turn is in progress or the target session
+// is busy with another turn.
+
+use super::schedule::{session_variables, CronSchedule};
+use super::{MessageService, SessionService};
+use crate::api::messages::{CreateMessageRequest, InputMessage, MessageRole};
+use crate::api::schedules::{MissedRunPolicy, OverlapPolicy};
+use crate::api::sessions::CreateSessionRequest;
+use crate::storage::models::{SchedulePlan, ScheduleRow, ScheduleRunRow};
+use crate::storage::Database;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use everruns_core::events::{TURN_COMPLETED, TURN_FAILED};
+use everruns_core::prompt_template::{PromptTemplate, TemplateContext};
+use everruns_core::{Event, EventData, EventListener, InputContentPart, SessionStatus};
+use everruns_worker::AgentRunner;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Maximum number of schedules planned / runs dispatched per tick
+const BATCH_SIZE: i64 = 50;
+
+/// How long a run may stay `running` without its message before it is failed
+const DISPATCH_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+pub struct Scheduler {
+    db: Arc<Database>,
+    sessions: SessionService,
+    messages: MessageService,
+}
+
+impl Scheduler {
+    pub fn new(db: Arc<Database>, runner: Arc<dyn AgentRunner>) -> Self {
+        Self {
+            sessions: SessionService::new(db.clone()),
+            messages: MessageService::new(db.clone(), runner),
+            db,
+        }
+    }
+
+    /// Run the scheduler loop forever
+    pub async fn run(self, poll_interval: Duration) {
+        let mut interval = tokio::time::interval(poll_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.tick().await {
+                tracing::error!("Scheduler tick failed: {}", e);
+            }
+        }
+    }
+
+    /// Plan due schedules, dispatch pending runs and fail interrupted dispatches
+    pub async fn tick(&self) -> Result<()> {
+        let now = Utc::now();
+        let stale = self
+            .db
+            .fail_stale_schedule_runs(now - DISPATCH_TIMEOUT, BATCH_SIZE)
+            .await?;
+        if stale > 0 {
+            tracing::warn!(count = stale, "Failed interrupted schedule runs");
+        }
+
+        let planned = self
+            .db
+            .plan_due_schedules(now, BATCH_SIZE, |schedule| plan_schedule(schedule, now))
+            .await?;
+        if planned > 0 {
+            tracing::debug!(count = planned, "Planned schedule runs");
+        }
+
+        for run in self.db.claim_pending_schedule_runs(BATCH_SIZE).await? {
+            if let Err(e) = self.dispatch(&run).await {
+                tracing::warn!(
+                    schedule_id = %run.schedule_id,
+                    run_id = %run.id,
+                    error = %e,
+                    "Schedule run failed"
+                );
+                self.db
+                    .finish_schedule_run(run.id, "failed", Some(&e.to_string()))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn dispatch(&self, run: &ScheduleRunRow) -> Result<()> {
+        let schedule = self
+            .db
+            .get_schedule(run.schedule_id)
+            .await?
+            .ok_or_else(|| anyhow!("Schedule not found: {}", run.schedule_id))?;
+
+        if OverlapPolicy::from(schedule.overlap_policy.as_str()) == OverlapPolicy::Skip {
+            if let Some(reason) = self.overlap(&schedule, run).await? {
+                tracing::info!(
+                    schedule_id = %schedule.id,
+                    run_id = %run.id,
+                    reason,
+                    "Skipping schedule run"
+                );
+                return self
+                    .db
+                    .finish_schedule_run(run.id, "skipped", Some(reason))
+                    .await;
+            }
+        }
+
+        let session = match schedule.session_id {
+            Some(session_id) => self
+                .sessions
+                .get(session_id)
+                .await?
+                .ok_or_else(|| anyhow!("Target session not found: {}", session_id))?,
+            None => {
+                let req = CreateSessionRequest {
+                    title: Some(format!(
+                        "{} ({})",
+                        schedule.name,
+                        run.scheduled_for.format("%Y-%m-%d %H:%M UTC")
+                    )),
+                    tags: vec!["scheduled".to_string()],
+                    model_id: None,
+                    follow_latest_revision: false,
+                    variables: session_variables(&schedule.session_variables),
+                };
+                self.sessions.create(schedule.agent_id, req, None).await?
+            }
+        };
+
+        let context = TemplateContext::for_session(&session)
+            .variable("schedule_name", schedule.name.clone())
+            .variable("scheduled_for", run.scheduled_for.to_rfc3339());
+        let text = PromptTemplate::parse(&schedule.message).render(&context);
+
+        let metadata = HashMap::from([
+            ("schedule_id".to_string(), schedule.id.to_string().into()),
+            ("schedule_run_id".to_string(), run.id.to_string().into()),
+        ]);
+        let req = CreateMessageRequest {
+            message: InputMessage {
+                role: MessageRole::User,
+                content: vec![InputContentPart::text(text)],
+            },
+            controls: None,
+            metadata: Some(metadata),
+            tags: None,
+        };
+        let message_id = Uuid::now_v7();
+        self.db
+            .set_schedule_run_dispatched(run.id, session.id, message_id)
+            .await?;
+        self.messages
+            .create_with_id(schedule.agent_id, session.id, message_id, req)
+            .await?;
+        tracing::info!(
+            schedule_id = %schedule.id,
+            run_id = %run.id,
+            session_id = %session.id,
+            "Dispatched schedule run"
+        );
+        Ok(())
+    }
+
+    /// Why the run would overlap another turn, if it would
+    async fn overlap(
+        &self,
+        schedule: &ScheduleRow,
+        run: &ScheduleRunRow,
+    ) -> Result<Option<&'static str>> {
+        if self
+            .db
+            .schedule_run_in_progress(schedule.id, run.id)
+            .await?
+        {
+            return Ok(Some("Previous run still in progress"));
+        }
+        if let Some(session_id) = schedule.session_id {
+            let busy = self
+                .db
+                .get_session(session_id)
+                .await?
+                .is_some_and(|s| s.status == SessionStatus::Active.to_string());
+            if busy {
+                return Ok(Some("Target session is running another turn"));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Plan a due schedule; an unparseable schedule is parked (no next run)
+fn plan_schedule(schedule: &ScheduleRow, now: DateTime<Utc>) -> SchedulePlan {
+    let Some(next_run_at) = schedule.next_run_at else {
+        return SchedulePlan::default();
+    };
+    match CronSchedule::parse(&schedule.cron, &schedule.timezone) {
+        Ok(cron) => cron.plan(
+            next_run_at,
+            now,
+            MissedRunPolicy::from(schedule.missed_run_policy.as_str()),
+        ),
+        Err(e) => {
+            tracing::error!(schedule_id = %schedule.id, error = %e, "Cannot plan schedule");
+            SchedulePlan::default()
+        }
+    }
+}
+
+/// Finishes schedule runs when the turn they started completes or fails
+pub struct ScheduleRunListener {
+    db: Arc<Database>,
+}
+
+impl ScheduleRunListener {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl EventListener for ScheduleRunListener {
+    async fn on_event(&self, event: &Event) {
+        let Some(message_id) = event.context.input_message_id else {
+            return;
+        };
+        let (status, error) = match &event.data {
+            EventData::TurnCompleted(_) => ("completed", None),
+            EventData::TurnFailed(data) => ("failed", Some(data.error.as_str())),
+            _ => return,
+        };
+
+        match self
+            .db
+            .finish_schedule_run_for_message(message_id, status, error)
+            .await
+        {
+            Ok(Some(run)) => tracing::info!(
+                schedule_id = %run.schedule_id,
+                run_id = %run.id,
+                status,
+                "Schedule run finished"
+            ),
+            // Not a scheduled turn
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                session_id = %event.session_id,
+                input_message_id = %message_id,
+                error = %e,
+                "Failed to finish schedule run"
+            ),
+        }
+    }
+
+    fn event_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec![TURN_COMPLETED, TURN_FAILED])
+    }
+
+    fn name(&self) -> &'static str {
+        "ScheduleRunListener"
+    }
+}
@@ -1,34 +1,78 @@
 // Session service for business logic (M2)
 
 use crate::storage::{
-    models::{CreateSessionRow, UpdateSession},
-    Database,
+    models::{CreateSessionRow, ForkFilesRow, ForkSessionRow, UpdateSession},
+    Database, FileContentStore,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use everruns_core::prompt_template::PromptTemplate;
 use everruns_core::{Session, SessionStatus};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::api::sessions::{CreateSessionRequest, UpdateSessionRequest};
+use crate::api::sessions::{CreateSessionRequest, ForkSessionRequest, UpdateSessionRequest};
 
 pub struct SessionService {
     db: Arc<Database>,
+    file_content: Option<Arc<FileContentStore>>,
 }
 
 impl SessionService {
     pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+        Self {
+            db,
+            file_content: None,
+        }
     }
 
-    pub async fn create(&self, agent_id: Uuid, req: CreateSessionRequest) -> Result<Session> {
-        // If model_id not provided, use the agent's default_model_id
-        let model_id = match req.model_id {
-            Some(id) => Some(id),
-            None => {
-                // Look up the agent to get its default_model_id
-                let agent = self.db.get_agent(agent_id).await?;
-                agent.and_then(|a| a.default_model_id)
+    /// Clean up object-stored session files when sessions are deleted
+    pub fn with_file_content(mut self, file_content: Arc<FileContentStore>) -> Self {
+        self.file_content = Some(file_content);
+        self
+    }
+
+    /// Create a session, checking that every variable referenced by the
+    /// agent's system prompt template is provided. `user_name` is taken from
+    /// the creating user unless it is set in the request variables.
+    pub async fn create(
+        &self,
+        agent_id: Uuid,
+        req: CreateSessionRequest,
+        user_name: Option<String>,
+    ) -> Result<Session> {
+        let agent = self.db.get_agent(agent_id).await?;
+
+        let mut variables = req.variables;
+        if let Some(agent) = &agent {
+            let template = PromptTemplate::parse(&agent.system_prompt);
+            let missing: Vec<&str> = template
+                .required_variables()
+                .into_iter()
+                .filter(|name| !variables.contains_key(*name))
+                .collect();
+            if !missing.is_empty() {
+                return Err(anyhow!(
+                    "Missing required template variables: {}",
+                    missing.join(", ")
+                ));
             }
+        }
+        if let Some(user_name) = user_name {
+            variables
+                .entry("user_name".to_string())
+                .or_insert(user_name);
+        }
+
+        // If model_id not provided, use the agent's default_model_id
+        let model_id = req
+            .model_id
+            .or_else(|| agent.as_ref().and_then(|a| a.default_model_id));
+
+        // Pin the agent's current revision unless the session follows the latest one
+        let agent_revision = if req.follow_latest_revision {
+            None
+        } else {
+            agent.as_ref().map(|a| a.revision)
         };
 
         let input = CreateSessionRow {
@@ -36,6 +80,8 @@ impl SessionService {
             title: req.title,
             tags: req.tags,
             model_id,
+            agent_revision,
+            variables: serde_json::to_value(variables)?,
         };
         let row = self.db.create_session(input).await?;
         Ok(Self::row_to_session(row))
@@ -72,7 +118,147 @@ impl SessionService {
     }
 
     pub async fn delete(&self, id: Uuid) -> Result<bool> {
-        self.db.delete_session(id).await
+        let Some(file_content) = &self.file_content else {
+            return self.db.delete_session(id).await;
+        };
+
+        // File rows go away with the session (FK cascade); their objects don't
+        let storage_keys = self.db.list_session_file_storage_keys(id).await?;
+        let deleted = self.db.delete_session(id).await?;
+        if deleted {
+            file_content.release(storage_keys).await;
+        }
+        Ok(deleted)
+    }
+
+    /// Create a new session from the history of an existing one
+    ///
+    /// Copies events up to the requested turn or message (all events if
+    /// neither is given). With `include_files`, the file tree is taken from
+    /// the snapshot of the turn at the fork point, or the current tree if
+    /// that turn has none. Returns None if the session is not in the agent.
+    pub async fn fork(
+        &self,
+        agent_id: Uuid,
+        session_id: Uuid,
+        req: ForkSessionRequest,
+    ) -> Result<Option<Session>> {
+        if req.turn_id.is_some() && req.message_id.is_some() {
+            return Err(anyhow!("Cannot fork at both a turn and a message"));
+        }
+
+        let Some(parent) = self.db.get_session(session_id).await? else {
+            return Ok(None);
+        };
+        if parent.agent_id != agent_id {
+            return Ok(None);
+        }
+
+        let fork_event = self
+            .db
+            .find_session_fork_event(session_id, req.turn_id, req.message_id)
+            .await?;
+        if fork_event.is_none() {
+            if let Some(turn_id) = req.turn_id {
+                return Err(anyhow!("Turn not found: {}", turn_id));
+            }
+            if let Some(message_id) = req.message_id {
+                return Err(anyhow!("Message not found: {}", message_id));
+            }
+        }
+
+        let id = Uuid::now_v7();
+        let files = if req.include_files {
+            let turn_id = match (req.turn_id, &fork_event) {
+                (Some(turn_id), _) => Some(turn_id),
+                (None, Some(event)) => {
+                    self.db
+                        .find_session_turn_at(session_id, event.sequence)
+                        .await?
+                }
+                (None, None) => None,
+            };
+            let snapshot_id = match turn_id {
+                Some(turn_id) => self
+                    .db
+                    .get_session_turn_snapshot(session_id, turn_id)
+                    .await?
+                    .map(|s| s.id),
+                None => None,
+            };
+            Some(ForkFilesRow {
+                snapshot_id,
+                storage_keys: self.duplicate_objects(session_id, snapshot_id, id).await?,
+            })
+        } else {
+            None
+        };
+        let copied_keys: Vec<String> = files
+            .iter()
+            .flat_map(|f| f.storage_keys.iter().map(|(_, new)| new.clone()))
+            .collect();
+
+        let input = ForkSessionRow {
+            id,
+            parent_session_id: session_id,
+            agent_id,
+            title: req.title.or(parent.title),
+            tags: req.tags.unwrap_or(parent.tags),
+            model_id: req.model_id.or(parent.model_id),
+            agent_revision: parent.agent_revision,
+            variables: parent.variables,
+            forked_at_event: fork_event.map(|e| (e.id, e.sequence)),
+            files,
+        };
+        match self.db.fork_session(input).await {
+            Ok(row) => Ok(Some(Self::row_to_session(row))),
+            Err(e) => {
+                if let Some(file_content) = &self.file_content {
+                    file_content.release(copied_keys).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Sessions forked from a session, newest first
+    pub async fn branches(&self, session_id: Uuid) -> Result<Vec<Session>> {
+        let rows = self.db.list_session_branches(session_id).await?;
+        Ok(rows.into_iter().map(Self::row_to_session).collect())
+    }
+
+    // Copy object-stored file content for a fork so the sessions don't share
+    // objects; returns (parent key, copy) pairs
+    async fn duplicate_objects(
+        &self,
+        session_id: Uuid,
+        snapshot_id: Option<Uuid>,
+        fork_id: Uuid,
+    ) -> Result<Vec<(String, String)>> {
+        let keys = self
+            .db
+            .list_session_fork_storage_keys(session_id, snapshot_id)
+            .await?;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let Some(file_content) = &self.file_content else {
+            return Err(anyhow!("Object storage is not configured"));
+        };
+
+        let mut copies = Vec::with_capacity(keys.len());
+        for key in keys {
+            match file_content.duplicate(fork_id, &key).await {
+                Ok(copy) => copies.push((key, copy)),
+                Err(e) => {
+                    file_content
+                        .release(copies.into_iter().map(|(_, copy)| copy))
+                        .await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(copies)
     }
 
     fn row_to_session(row: crate::storage::SessionRow) -> Session {
@@ -86,6 +272,10 @@ impl SessionService {
             created_at: row.created_at,
             started_at: row.started_at,
             finished_at: row.finished_at,
+            parent_session_id: row.parent_session_id,
+            forked_at_event_id: row.forked_at_event_id,
+            agent_revision: row.agent_revision,
+            variables: serde_json::from_value(row.variables).unwrap_or_default(),
         }
     }
 }
@@ -0,0 +1,381 @@
+// Session filesystem archives (tar.gz and zip)
+//
+// Decision: tar.gz is the default export format because it can be written
+// incrementally - each file is appended and its compressed bytes handed to the
+// response, so export memory is bounded by the largest file. zip needs a
+// seekable writer, so zip exports are assembled in memory and sent at the end.
+// Decision: Imports are unpacked and validated in full before anything is
+// written. Only regular files are imported; symlinks, hard links and device
+// entries are skipped, and `..` paths are rejected.
+
+use anyhow::{anyhow, bail, Result};
+use bytes::Bytes;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Archive format for session filesystem import and export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    #[default]
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from the leading bytes of an archive
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::TarGz => "application/gzip",
+            Self::Zip => "application/zip",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "tar.gz" | "tgz" | "targz" => Ok(Self::TarGz),
+            "zip" => Ok(Self::Zip),
+            other => bail!("Invalid archive format '{}': expected tar.gz or zip", other),
+        }
+    }
+}
+
+/// A regular file read from an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Relative path with `/` separators, e.g. `src/main.rs`
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+/// Unpack the regular files of an archive.
+///
+/// Fails when the archive holds more than `max_entries` files or more than
+/// `max_bytes` of unpacked content. A path repeated in the archive keeps its
+/// last entry.
+pub fn unpack(data: &[u8], max_entries: usize, max_bytes: u64) -> Result<Vec<ArchiveEntry>> {
+    let format = ArchiveFormat::detect(data)
+        .ok_or_else(|| anyhow!("Invalid archive: expected tar.gz or zip"))?;
+    let mut unpacker = Unpacker {
+        entries: Vec::new(),
+        index: HashMap::new(),
+        remaining: max_bytes,
+        max_entries,
+    };
+
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut archive = tar::Archive::new(GzDecoder::new(data));
+            for entry in archive
+                .entries()
+                .map_err(|e| anyhow!("Invalid archive: {}", e))?
+            {
+                let entry = entry.map_err(|e| anyhow!("Invalid archive: {}", e))?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let path = entry
+                    .path()
+                    .map_err(|e| anyhow!("Invalid archive entry path: {}", e))?;
+                let path = relative_path(&path)?;
+                unpacker.push(path, entry)?;
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(data))
+                .map_err(|e| anyhow!("Invalid archive: {}", e))?;
+            for i in 0..archive.len() {
+                let file = archive
+                    .by_index(i)
+                    .map_err(|e| anyhow!("Invalid archive: {}", e))?;
+                if !file.is_file() || file.is_symlink() {
+                    continue;
+                }
+                let path = file
+                    .enclosed_name()
+                    .ok_or_else(|| anyhow!("Invalid archive entry path: {}", file.name()))?;
+                let path = relative_path(&path)?;
+                unpacker.push(path, file)?;
+            }
+        }
+    }
+
+    Ok(unpacker.entries)
+}
+
+struct Unpacker {
+    entries: Vec<ArchiveEntry>,
+    index: HashMap<String, usize>,
+    remaining: u64,
+    max_entries: usize,
+}
+
+impl Unpacker {
+    fn push(&mut self, path: String, reader: impl Read) -> Result<()> {
+        // Read one byte past the budget to detect oversized content without
+        // trusting the sizes recorded in the archive
+        let mut data = Vec::new();
+        reader
+            .take(self.remaining + 1)
+            .read_to_end(&mut data)
+            .map_err(|e| anyhow!("Invalid archive: {}", e))?;
+        if data.len() as u64 > self.remaining {
+            bail!("Archive exceeds the import size limit");
+        }
+        self.remaining -= data.len() as u64;
+
+        match self.index.get(&path) {
+            Some(&i) => self.entries[i].data = data,
+            None => {
+                if self.entries.len() >= self.max_entries {
+                    bail!("Archive exceeds the limit of {} files", self.max_entries);
+                }
+                self.index.insert(path.clone(), self.entries.len());
+                self.entries.push(ArchiveEntry { path, data });
+            }
+        }
+        Ok(())
+    }
+}
+
+// Convert an archive path to a relative `/`-separated path, dropping leading
+// `/` and `./` and rejecting `..`
+fn relative_path(path: &Path) -> Result<String> {
+    let mut segments = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(segment) => segments.push(
+                segment
+                    .to_str()
+                    .ok_or_else(|| anyhow!("Invalid archive entry path: {}", path.display()))?,
+            ),
+            Component::CurDir | Component::RootDir => {}
+            Component::ParentDir | Component::Prefix(_) => {
+                bail!("Invalid archive entry path: {}", path.display())
+            }
+        }
+    }
+    if segments.is_empty() {
+        bail!("Invalid archive entry path: {}", path.display());
+    }
+    Ok(segments.join("/"))
+}
+
+/// Incrementally writes an archive, returning compressed bytes as they
+/// become available
+pub struct ArchiveWriter {
+    inner: WriterInner,
+    output: SharedBuffer,
+}
+
+enum WriterInner {
+    TarGz(tar::Builder<GzEncoder<SharedBuffer>>),
+    Zip(Box<zip::ZipWriter<Cursor<Vec<u8>>>>),
+}
+
+impl ArchiveWriter {
+    pub fn new(format: ArchiveFormat) -> Self {
+        let output = SharedBuffer::default();
+        let inner = match format {
+            ArchiveFormat::TarGz => WriterInner::TarGz(tar::Builder::new(GzEncoder::new(
+                output.clone(),
+                Compression::default(),
+            ))),
+            ArchiveFormat::Zip => {
+                WriterInner::Zip(Box::new(zip::ZipWriter::new(Cursor::new(Vec::new()))))
+            }
+        };
+        Self { inner, output }
+    }
+
+    /// Add a directory entry; `mtime` is seconds since the Unix epoch
+    pub fn add_directory(&mut self, path: &str, mtime: i64) -> Result<Bytes> {
+        match &mut self.inner {
+            WriterInner::TarGz(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+                header.set_mtime(mtime.max(0) as u64);
+                builder.append_data(&mut header, format!("{}/", path), std::io::empty())?;
+            }
+            WriterInner::Zip(writer) => {
+                writer.add_directory(path, zip_options())?;
+            }
+        }
+        Ok(self.output.take())
+    }
+
+    /// Add a file entry; `mtime` is seconds since the Unix epoch
+    pub fn add_file(&mut self, path: &str, data: &[u8], mtime: i64) -> Result<Bytes> {
+        match &mut self.inner {
+            WriterInner::TarGz(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(0o644);
+                header.set_size(data.len() as u64);
+                header.set_mtime(mtime.max(0) as u64);
+                builder.append_data(&mut header, path, data)?;
+            }
+            WriterInner::Zip(writer) => {
+                writer.start_file(path, zip_options())?;
+                writer.write_all(data)?;
+            }
+        }
+        Ok(self.output.take())
+    }
+
+    /// Finish the archive and return the remaining bytes
+    pub fn finish(self) -> Result<Bytes> {
+        match self.inner {
+            WriterInner::TarGz(builder) => {
+                builder.into_inner()?.finish()?;
+                Ok(self.output.take())
+            }
+            WriterInner::Zip(writer) => Ok(Bytes::from(writer.finish()?.into_inner())),
+        }
+    }
+}
+
+fn zip_options() -> zip::write::SimpleFileOptions {
+    zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true)
+}
+
+// Write sink whose contents are drained after each entry
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Bytes {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        Bytes::from(std::mem::take(&mut *buffer))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(format: ArchiveFormat, files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ArchiveWriter::new(format);
+        let mut out = Vec::new();
+        out.extend_from_slice(&writer.add_directory("src", 0).unwrap());
+        for (path, data) in files {
+            out.extend_from_slice(&writer.add_file(path, data, 1_700_000_000).unwrap());
+        }
+        out.extend_from_slice(&writer.finish().unwrap());
+        out
+    }
+
+    #[test]
+    fn test_roundtrip_both_formats() {
+        for format in [ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let data = build(
+                format,
+                &[
+                    ("README.md", b"# hello\n"),
+                    ("src/main.rs", b"fn main() {}\n"),
+                ],
+            );
+            assert_eq!(ArchiveFormat::detect(&data), Some(format));
+
+            let entries = unpack(&data, 10, 1024).unwrap();
+            assert_eq!(
+                entries,
+                vec![
+                    ArchiveEntry {
+                        path: "README.md".to_string(),
+                        data: b"# hello\n".to_vec()
+                    },
+                    ArchiveEntry {
+                        path: "src/main.rs".to_string(),
+                        data: b"fn main() {}\n".to_vec()
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_unpack_enforces_limits() {
+        let data = build(
+            ArchiveFormat::TarGz,
+            &[("a.txt", b"aaaa"), ("b.txt", b"bbbb")],
+        );
+        assert!(unpack(&data, 2, 8).is_ok());
+        assert!(unpack(&data, 1, 8)
+            .unwrap_err()
+            .to_string()
+            .contains("limit of 1 files"));
+        assert!(unpack(&data, 2, 7)
+            .unwrap_err()
+            .to_string()
+            .contains("size limit"));
+    }
+
+    #[test]
+    fn test_unpack_rejects_parent_paths() {
+        // tar::Builder refuses `..`, so write the header name directly
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.as_gnu_mut().unwrap().name[..9].copy_from_slice(b"../escape");
+        header.set_size(1);
+        header.set_cksum();
+        builder.append(&header, &b"x"[..]).unwrap();
+        let data = builder.into_inner().unwrap().finish().unwrap();
+
+        assert!(unpack(&data, 10, 1024)
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid archive entry path"));
+    }
+
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!(
+            "tgz".parse::<ArchiveFormat>().unwrap(),
+            ArchiveFormat::TarGz
+        );
+        assert_eq!("ZIP".parse::<ArchiveFormat>().unwrap(), ArchiveFormat::Zip);
+        assert!("rar".parse::<ArchiveFormat>().is_err());
+        assert_eq!(ArchiveFormat::detect(b"plain text"), None);
+    }
+}
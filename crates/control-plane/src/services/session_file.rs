@@ -1,12 +1,33 @@
 // Session Files service for virtual filesystem operations
-
+//
+// Every content change is versioned by a database trigger, so replaced or
+// deleted object-stored content is never released here; objects are removed
+// when maintenance prunes the versions referencing them or when the session
+// itself is deleted.
+//
+// Decision: Quotas are checked against current usage before each write rather
+// than enforced by the database. Usage includes the version history, so
+// replacing or deleting a file frees nothing until its old versions are pruned. Concurrent writers can overshoot a quota by
+// at most one write each, which is acceptable for an abuse guard.
+
+use super::session_archive::{self, ArchiveFormat, ArchiveWriter};
 use crate::storage::{
-    models::{CreateSessionFileRow, SessionFileInfoRow, SessionFileRow, UpdateSessionFile},
-    Database,
+    models::{
+        CreateSessionFileRow, CreateSessionSnapshotRow, SessionFileChangeRow, SessionFileInfoRow,
+        SessionFileRow, SessionFileVersionInfoRow, SessionFileVersionRow, SessionSnapshotRow,
+        StoredContent, UpdateSessionFile,
+    },
+    ByteRange, ByteStream, Database, FileContentStore,
+};
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use everruns_core::{
+    FileChange, FileDiff, FileInfo, FileStat, FileVersion, GrepMatch, GrepResult, RestoreTarget,
+    SessionFile, SessionSnapshot, SnapshotKind,
 };
-use anyhow::{anyhow, Result};
-use everruns_core::{FileInfo, FileStat, GrepMatch, GrepResult, SessionFile};
+use futures::stream;
 use regex::Regex;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -48,93 +69,260 @@ pub struct GrepInput {
     pub path_pattern: Option<String>,
 }
 
-pub struct SessionFileService {
-    db: Arc<Database>,
+/// Raw file content for streaming downloads
+pub struct FileDownload {
+    /// Total size of the file
+    pub size_bytes: u64,
+    /// Range being returned, if the request asked for one
+    pub range: Option<ByteRange>,
+    pub body: ByteStream,
 }
 
-impl SessionFileService {
-    pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
-    }
+/// Name prefix of automatic turn snapshots, reserved from manual use
+pub const TURN_SNAPSHOT_PREFIX: &str = "turn-";
 
-    /// Normalize a path: ensure it starts with /, no trailing slash, no double slashes
-    fn normalize_path(path: &str) -> String {
-        let mut normalized = path.trim().to_string();
+/// Default cap on an uploaded archive and on its unpacked content
+pub const DEFAULT_MAX_IMPORT_BYTES: u64 = 100 * 1024 * 1024;
 
-        // Ensure starts with /
-        if !normalized.starts_with('/') {
-            normalized = format!("/{}", normalized);
-        }
+/// Most files a single archive import may contain
+const MAX_IMPORT_FILES: usize = 10_000;
 
-        // Remove trailing slash (except for root)
-        if normalized.len() > 1 && normalized.ends_with('/') {
-            normalized.pop();
-        }
+/// Per-session file limits
+#[derive(Debug, Clone)]
+pub struct SessionFileLimits {
+    /// Total content bytes a session may hold (None = unlimited)
+    pub max_total_bytes: Option<u64>,
+    /// Files (not directories) a session may hold (None = unlimited)
+    pub max_files: Option<u64>,
+    /// Largest archive accepted by import, compressed or unpacked
+    pub max_import_bytes: u64,
+}
 
-        // Remove double slashes
-        while normalized.contains("//") {
-            normalized = normalized.replace("//", "/");
+impl Default for SessionFileLimits {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: None,
+            max_files: None,
+            max_import_bytes: DEFAULT_MAX_IMPORT_BYTES,
         }
+    }
+}
+
+impl SessionFileLimits {
+    /// Configure from environment:
+    /// - SESSION_FILES_MAX_TOTAL_BYTES: total content bytes per session (default unlimited)
+    /// - SESSION_FILES_MAX_FILES: files per session (default unlimited)
+    /// - SESSION_FILES_MAX_IMPORT_BYTES: largest archive import (default 104857600)
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| -> Result<Option<u64>> {
+            match std::env::var(name) {
+                Ok(value) if !value.trim().is_empty() => {
+                    Ok(Some(value.trim().parse().with_context(|| {
+                        format!("{} must be a non-negative integer", name)
+                    })?))
+                }
+                _ => Ok(None),
+            }
+        };
 
-        normalized
+        Ok(Self {
+            max_total_bytes: var("SESSION_FILES_MAX_TOTAL_BYTES")?,
+            max_files: var("SESSION_FILES_MAX_FILES")?,
+            max_import_bytes: var("SESSION_FILES_MAX_IMPORT_BYTES")?
+                .unwrap_or(DEFAULT_MAX_IMPORT_BYTES),
+        })
     }
+}
 
-    /// Validate that a path is valid
-    fn validate_path(path: &str) -> Result<()> {
-        if path.is_empty() {
-            return Err(anyhow!("Path cannot be empty"));
-        }
+pub struct SessionFileService {
+    db: Arc<Database>,
+    content: Arc<FileContentStore>,
+    limits: SessionFileLimits,
+}
 
-        if !path.starts_with('/') {
-            return Err(anyhow!("Path must start with /"));
+impl SessionFileService {
+    pub fn new(db: Arc<Database>, content: Arc<FileContentStore>) -> Self {
+        Self {
+            db,
+            content,
+            limits: SessionFileLimits::default(),
         }
+    }
 
-        // Check for invalid characters
-        if path.contains('\0') {
-            return Err(anyhow!("Path cannot contain null characters"));
+    /// Enforce per-session quotas and import limits
+    pub fn with_limits(mut self, limits: SessionFileLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> &SessionFileLimits {
+        &self.limits
+    }
+
+    /// Fail if adding `files` files and `bytes` bytes would exceed the
+    /// session's quota. Changes that shrink usage always pass.
+    ///
+    /// `bytes` is the new content written: replaced content stays in the
+    /// version history and keeps counting.
+    async fn check_quota(&self, session_id: Uuid, files: i64, bytes: i64) -> Result<()> {
+        let SessionFileLimits {
+            max_total_bytes,
+            max_files,
+            ..
+        } = self.limits;
+        if max_total_bytes.is_none() && max_files.is_none() {
+            return Ok(());
         }
 
-        // Check for .. path traversal
-        if path.split('/').any(|segment| segment == "..") {
-            return Err(anyhow!("Path cannot contain '..' segments"));
+        let (used_files, used_bytes) = self.db.session_file_usage(session_id).await?;
+        if let Some(max) = max_files {
+            if files > 0 && used_files + files > max as i64 {
+                return Err(anyhow!(
+                    "Session file quota exceeded: {} files would exceed the limit of {}",
+                    used_files + files,
+                    max
+                ));
+            }
         }
+        if let Some(max) = max_total_bytes {
+            if bytes > 0 && used_bytes + bytes > max as i64 {
+                return Err(anyhow!(
+                    "Session file quota exceeded: {} bytes would exceed the limit of {}",
+                    used_bytes + bytes,
+                    max
+                ));
+            }
+        }
+        Ok(())
+    }
 
+    /// Check the quota for content that is already stored, releasing it if
+    /// the check fails
+    async fn check_stored_quota(
+        &self,
+        session_id: Uuid,
+        files: i64,
+        bytes: i64,
+        stored: &StoredContent,
+    ) -> Result<()> {
+        if let Err(e) = self.check_quota(session_id, files, bytes).await {
+            self.content
+                .release(stored.storage_key().map(str::to_string))
+                .await;
+            return Err(e);
+        }
         Ok(())
     }
 
+    /// Normalize a path (shared with the agent file tools)
+    fn normalize_path(path: &str) -> String {
+        FileInfo::normalize_path(path)
+    }
+
+    /// Validate that a path is valid (shared with the agent file tools)
+    fn validate_path(path: &str) -> Result<()> {
+        FileInfo::validate_path(path).map_err(|e| anyhow!(e))
+    }
+
     /// Create a new file
     pub async fn create_file(&self, session_id: Uuid, req: CreateFileInput) -> Result<SessionFile> {
         let path = Self::normalize_path(&req.path);
         Self::validate_path(&path)?;
 
         // Decode content if provided
-        let content = if let Some(ref content_str) = req.content {
+        let data = if let Some(ref content_str) = req.content {
             let encoding = req.encoding.as_deref().unwrap_or("text");
-            Some(SessionFile::decode_content(content_str, encoding)?)
+            Some(Bytes::from(SessionFile::decode_content(
+                content_str,
+                encoding,
+            )?))
         } else {
             None
         };
 
+        self.prepare_new_file(session_id, &path).await?;
+        let size = data.as_ref().map_or(0, |d| d.len() as i64);
+        self.check_quota(session_id, 1, size).await?;
+
+        let stored = match data.clone() {
+            Some(bytes) => Some(self.content.store(session_id, bytes).await?),
+            None => None,
+        };
+        let row = self
+            .insert_file(session_id, path, stored, req.is_readonly.unwrap_or(false))
+            .await?;
+        Ok(Self::row_to_session_file(Self::with_content(row, data)))
+    }
+
+    /// Create a file from a streamed body (raw uploads)
+    pub async fn create_file_stream(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        body: ByteStream,
+        content_length: Option<u64>,
+    ) -> Result<FileInfo> {
+        let path = Self::normalize_path(path);
+        Self::validate_path(&path)?;
+
+        self.prepare_new_file(session_id, &path).await?;
+
+        // The length may be unknown up front, so the quota is checked once the
+        // content is stored
+        let stored = self
+            .content
+            .store_stream(session_id, body, content_length)
+            .await?;
+        self.check_stored_quota(session_id, 1, stored.size_bytes(), &stored)
+            .await?;
+        let row = self
+            .insert_file(session_id, path, Some(stored), false)
+            .await?;
+        Ok(Self::row_to_file_info(row))
+    }
+
+    /// Create parent directories and check the path is free
+    async fn prepare_new_file(&self, session_id: Uuid, path: &str) -> Result<()> {
         // Ensure parent directory exists (create recursively if needed)
-        if let Some(parent) = FileInfo::parent_path(&path) {
+        if let Some(parent) = FileInfo::parent_path(path) {
             self.ensure_directory_exists(session_id, &parent).await?;
         }
 
         // Check if file already exists
-        if self.db.session_file_exists(session_id, &path).await? {
+        if self.db.session_file_exists(session_id, path).await? {
             return Err(anyhow!("File already exists at path: {}", path));
         }
 
+        Ok(())
+    }
+
+    /// Insert a file row, releasing its object if the insert fails
+    async fn insert_file(
+        &self,
+        session_id: Uuid,
+        path: String,
+        content: Option<StoredContent>,
+        is_readonly: bool,
+    ) -> Result<SessionFileRow> {
+        let storage_key = content
+            .as_ref()
+            .and_then(|c| c.storage_key())
+            .map(str::to_string);
         let input = CreateSessionFileRow {
             session_id,
-            path: path.clone(),
+            path,
             content,
             is_directory: false,
-            is_readonly: req.is_readonly.unwrap_or(false),
+            is_readonly,
         };
 
-        let row = self.db.create_session_file(input).await?;
-        Ok(Self::row_to_session_file(row))
+        match self.db.create_session_file(input).await {
+            Ok(row) => Ok(row),
+            Err(e) => {
+                self.content.release(storage_key).await;
+                Err(e)
+            }
+        }
     }
 
     /// Create a directory (and parent directories if needed)
@@ -209,7 +397,36 @@ impl SessionFileService {
     pub async fn read_file(&self, session_id: Uuid, path: &str) -> Result<Option<SessionFile>> {
         let path = Self::normalize_path(path);
         let row = self.db.get_session_file(session_id, &path).await?;
-        Ok(row.map(Self::row_to_session_file))
+        self.hydrated(row).await
+    }
+
+    /// Open a file's raw content for streaming, honoring an HTTP `Range` header
+    pub async fn open_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        range: Option<&str>,
+    ) -> Result<Option<FileDownload>> {
+        let path = Self::normalize_path(path);
+        let Some(row) = self.db.get_session_file(session_id, &path).await? else {
+            return Ok(None);
+        };
+        if row.is_directory {
+            return Err(anyhow!("Cannot read directory as file: {}", path));
+        }
+
+        let size_bytes = row.size_bytes as u64;
+        let range = match range {
+            Some(header) => ByteRange::parse(header, size_bytes)?,
+            None => None,
+        };
+        let body = self.content.open(&row, range).await?;
+
+        Ok(Some(FileDownload {
+            size_bytes,
+            range,
+            body,
+        }))
     }
 
     /// Get file stat (metadata)
@@ -273,6 +490,143 @@ impl SessionFileService {
             .collect())
     }
 
+    /// List files and directories whose absolute path matches a glob pattern
+    pub async fn glob(&self, session_id: Uuid, pattern: &str) -> Result<Vec<FileInfo>> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err(anyhow!("Invalid glob pattern: pattern cannot be empty"));
+        }
+        let pattern = if pattern.starts_with('/') {
+            pattern.to_string()
+        } else {
+            format!("/{}", pattern)
+        };
+
+        let rows = self.db.list_all_session_files(session_id).await?;
+        Ok(rows
+            .into_iter()
+            .filter(|row| FileInfo::glob_matches(&pattern, &row.path))
+            .map(Self::row_to_file_info_from_info)
+            .collect())
+    }
+
+    /// Apply writes and deletes across several files in one transaction.
+    ///
+    /// Writes create missing files (and parent directories) or replace the
+    /// content of existing ones. Returns the written files.
+    pub async fn apply_changes(
+        &self,
+        session_id: Uuid,
+        changes: Vec<FileChange>,
+    ) -> Result<Vec<FileInfo>> {
+        let mut seen = std::collections::HashSet::new();
+        let mut validated = Vec::with_capacity(changes.len());
+        for change in changes {
+            let change = match change {
+                FileChange::Write {
+                    path,
+                    content,
+                    encoding,
+                } => FileChange::Write {
+                    path: Self::normalize_path(&path),
+                    content,
+                    encoding,
+                },
+                FileChange::Delete { path } => FileChange::Delete {
+                    path: Self::normalize_path(&path),
+                },
+            };
+            Self::validate_path(change.path())?;
+            if change.path() == "/" {
+                return Err(anyhow!("Cannot modify root directory"));
+            }
+            if !seen.insert(change.path().to_string()) {
+                return Err(anyhow!("Duplicate path in changes: {}", change.path()));
+            }
+            validated.push(change);
+        }
+
+        // Check every target up front so no content is stored for a doomed batch
+        let mut existing_sizes = HashMap::new();
+        for change in &validated {
+            let existing = self.db.get_session_file(session_id, change.path()).await?;
+            if let Some(ref f) = existing {
+                existing_sizes.insert(f.path.clone(), f.size_bytes);
+            }
+            match (change, existing) {
+                (_, Some(f)) if f.is_directory => {
+                    return Err(anyhow!("Cannot modify directory: {}", f.path))
+                }
+                (FileChange::Write { .. }, Some(f)) if f.is_readonly => {
+                    return Err(anyhow!("Cannot modify readonly file: {}", f.path))
+                }
+                (FileChange::Delete { path }, None) => {
+                    return Err(anyhow!("File not found: {}", path))
+                }
+                _ => {}
+            }
+        }
+
+        let mut decoded = Vec::with_capacity(validated.len());
+        for change in validated {
+            decoded.push(match change {
+                FileChange::Write {
+                    path,
+                    content,
+                    encoding,
+                } => (
+                    path,
+                    Some(SessionFile::decode_content(&content, &encoding)?),
+                ),
+                FileChange::Delete { path } => (path, None),
+            });
+        }
+
+        let mut added_files = 0;
+        let mut added_bytes = 0;
+        for (path, data) in &decoded {
+            let existing = existing_sizes.get(path);
+            match data {
+                Some(data) => {
+                    added_files += i64::from(existing.is_none());
+                    added_bytes += data.len() as i64;
+                }
+                None => added_files -= 1,
+            }
+        }
+        self.check_quota(session_id, added_files, added_bytes)
+            .await?;
+
+        let mut rows = Vec::with_capacity(decoded.len());
+        let mut new_keys = Vec::new();
+        for (path, data) in decoded {
+            let Some(data) = data else {
+                rows.push(SessionFileChangeRow::Delete { path });
+                continue;
+            };
+            let stored = match self.content.store(session_id, Bytes::from(data)).await {
+                Ok(stored) => stored,
+                Err(e) => {
+                    self.content.release(new_keys).await;
+                    return Err(e);
+                }
+            };
+            new_keys.extend(stored.storage_key().map(str::to_string));
+            rows.push(SessionFileChangeRow::Write {
+                path,
+                content: stored,
+            });
+        }
+
+        match self.db.apply_session_file_changes(session_id, rows).await {
+            Ok(written) => Ok(written.into_iter().map(Self::row_to_file_info).collect()),
+            Err(e) => {
+                self.content.release(new_keys).await;
+                Err(e)
+            }
+        }
+    }
+
     /// Update a file
     pub async fn update_file(
         &self,
@@ -283,33 +637,101 @@ impl SessionFileService {
         let path = Self::normalize_path(path);
 
         // Check if file exists and is not readonly
-        if let Some(existing) = self.db.get_session_file(session_id, &path).await? {
-            if existing.is_directory {
-                return Err(anyhow!("Cannot update directory: {}", path));
-            }
-            if existing.is_readonly && req.content.is_some() {
-                return Err(anyhow!("Cannot modify readonly file: {}", path));
-            }
+        let Some(existing) = self.db.get_session_file(session_id, &path).await? else {
+            return Ok(None);
+        };
+        if existing.is_directory {
+            return Err(anyhow!("Cannot update directory: {}", path));
+        }
+        if existing.is_readonly && req.content.is_some() {
+            return Err(anyhow!("Cannot modify readonly file: {}", path));
         }
 
         // Decode content if provided
-        let content = if let Some(ref content_str) = req.content {
+        let data = if let Some(ref content_str) = req.content {
             let encoding = req.encoding.as_deref().unwrap_or("text");
-            Some(SessionFile::decode_content(content_str, encoding)?)
+            Some(Bytes::from(SessionFile::decode_content(
+                content_str,
+                encoding,
+            )?))
         } else {
             None
         };
+        if let Some(ref bytes) = data {
+            self.check_quota(session_id, 0, bytes.len() as i64).await?;
+        }
 
-        let input = UpdateSessionFile {
-            content,
-            is_readonly: req.is_readonly,
+        let stored = match data.clone() {
+            Some(bytes) => Some(self.content.store(session_id, bytes).await?),
+            None => None,
+        };
+        let row = self
+            .apply_update(session_id, &path, stored, req.is_readonly)
+            .await?;
+        Ok(row.map(|r| Self::row_to_session_file(Self::with_content(r, data))))
+    }
+
+    /// Replace a file's content from a streamed body (raw uploads)
+    pub async fn update_file_stream(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        body: ByteStream,
+        content_length: Option<u64>,
+    ) -> Result<Option<FileInfo>> {
+        let path = Self::normalize_path(path);
+
+        let Some(existing) = self.db.get_session_file(session_id, &path).await? else {
+            return Ok(None);
         };
+        if existing.is_directory {
+            return Err(anyhow!("Cannot update directory: {}", path));
+        }
+        if existing.is_readonly {
+            return Err(anyhow!("Cannot modify readonly file: {}", path));
+        }
 
+        let stored = self
+            .content
+            .store_stream(session_id, body, content_length)
+            .await?;
+        self.check_stored_quota(session_id, 0, stored.size_bytes(), &stored)
+            .await?;
         let row = self
-            .db
-            .update_session_file(session_id, &path, input)
+            .apply_update(session_id, &path, Some(stored), None)
             .await?;
-        Ok(row.map(Self::row_to_session_file))
+        Ok(row.map(Self::row_to_file_info))
+    }
+
+    /// Update a file row, releasing the new object if the update doesn't land.
+    /// The replaced content stays referenced by the file's version history.
+    async fn apply_update(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        content: Option<StoredContent>,
+        is_readonly: Option<bool>,
+    ) -> Result<Option<SessionFileRow>> {
+        let new_key = content
+            .as_ref()
+            .and_then(|c| c.storage_key())
+            .map(str::to_string);
+        let input = UpdateSessionFile {
+            content,
+            is_readonly,
+        };
+
+        match self.db.update_session_file(session_id, path, input).await {
+            Ok(Some(row)) => Ok(Some(row)),
+            Ok(None) => {
+                self.content.release(new_key).await;
+                Ok(None)
+            }
+            Err(e) => {
+                self.content.release(new_key).await;
+                Err(e)
+            }
+        }
     }
 
     /// Delete a file or directory
@@ -346,15 +768,14 @@ impl SessionFileService {
             }
         }
 
-        if recursive {
-            let deleted = self
-                .db
+        let deleted = if recursive {
+            self.db
                 .delete_session_file_recursive(session_id, &path)
-                .await?;
-            Ok(deleted > 0)
+                .await?
         } else {
-            self.db.delete_session_file(session_id, &path).await
-        }
+            self.db.delete_session_file(session_id, &path).await?
+        };
+        Ok(deleted > 0)
     }
 
     /// Move/rename a file or directory
@@ -388,7 +809,7 @@ impl SessionFileService {
             .db
             .move_session_file(session_id, &src_path, &dst_path)
             .await?;
-        Ok(row.map(Self::row_to_session_file))
+        self.hydrated(row).await
     }
 
     /// Copy a file
@@ -403,14 +824,13 @@ impl SessionFileService {
         Self::validate_path(&dst_path)?;
 
         // Check source exists and is not a directory
-        let source = self.db.get_session_file(session_id, &src_path).await?;
-        match source {
+        let source = match self.db.get_session_file(session_id, &src_path).await? {
             None => return Err(anyhow!("Source not found: {}", src_path)),
-            Some(ref s) if s.is_directory => {
+            Some(s) if s.is_directory => {
                 return Err(anyhow!("Cannot copy directories: {}", src_path))
             }
-            _ => {}
-        }
+            Some(s) => s,
+        };
 
         // Check destination doesn't exist
         if self.db.session_file_exists(session_id, &dst_path).await? {
@@ -422,11 +842,30 @@ impl SessionFileService {
             self.ensure_directory_exists(session_id, &parent).await?;
         }
 
-        let row = self
+        self.check_quota(session_id, 1, source.size_bytes).await?;
+
+        // Object-stored content gets its own copy so the files stay independent
+        let storage_key = match &source.storage_key {
+            Some(key) => Some(self.content.duplicate(session_id, key).await?),
+            None => None,
+        };
+
+        let row = match self
             .db
-            .copy_session_file(session_id, &src_path, &dst_path)
-            .await?;
-        Ok(row.map(Self::row_to_session_file))
+            .copy_session_file(session_id, &src_path, &dst_path, storage_key.as_deref())
+            .await
+        {
+            Ok(Some(row)) => row,
+            Ok(None) => {
+                self.content.release(storage_key).await;
+                return Ok(None);
+            }
+            Err(e) => {
+                self.content.release(storage_key).await;
+                return Err(e);
+            }
+        };
+        self.hydrated(Some(row)).await
     }
 
     /// Search files using grep-like pattern matching
@@ -450,6 +889,7 @@ impl SessionFileService {
                 .get_session_file(session_id, &file_info.path)
                 .await?;
             if let Some(f) = file {
+                let f = self.content.hydrate(f).await?;
                 if let Some(content) = f.content {
                     // Try to decode as text
                     if let Ok(text) = String::from_utf8(content) {
@@ -478,6 +918,442 @@ impl SessionFileService {
         Ok(results)
     }
 
+    /// Stream an archive of a file or directory subtree. Entry paths are
+    /// relative to the exported directory (a single file is archived under
+    /// its name). Returns None if the path doesn't exist.
+    pub async fn export_archive(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        format: ArchiveFormat,
+    ) -> Result<Option<ByteStream>> {
+        let root = Self::normalize_path(path);
+
+        // Entries are named relative to `base`: the exported directory, or
+        // the parent of an exported file
+        let (base, entries) = if root == "/" {
+            (root, self.db.list_all_session_files(session_id).await?)
+        } else {
+            let Some(file) = self.db.get_session_file(session_id, &root).await? else {
+                return Ok(None);
+            };
+            if file.is_directory {
+                let prefix = format!("{}/", root);
+                let entries = self
+                    .db
+                    .list_all_session_files(session_id)
+                    .await?
+                    .into_iter()
+                    .filter(|row| row.path.starts_with(&prefix))
+                    .collect();
+                (root, entries)
+            } else {
+                let base = FileInfo::parent_path(&root).unwrap_or_else(|| "/".to_string());
+                let entry = SessionFileInfoRow {
+                    id: file.id,
+                    session_id: file.session_id,
+                    path: file.path,
+                    is_directory: false,
+                    is_readonly: file.is_readonly,
+                    size_bytes: file.size_bytes,
+                    created_at: file.created_at,
+                    updated_at: file.updated_at,
+                };
+                (base, vec![entry])
+            }
+        };
+        let strip = if base == "/" { 1 } else { base.len() + 1 };
+
+        let state = ExportState {
+            db: self.db.clone(),
+            content: self.content.clone(),
+            session_id,
+            strip,
+            entries: entries.into_iter(),
+            writer: Some(ArchiveWriter::new(format)),
+        };
+        let body = stream::unfold(state, |mut state| async move {
+            match state.next_chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), state)),
+                Ok(None) => None,
+                Err(e) => {
+                    // Stop after the first error; the client sees a truncated archive
+                    state.writer = None;
+                    Some((Err(std::io::Error::other(e.to_string())), state))
+                }
+            }
+        });
+        Ok(Some(Box::pin(body)))
+    }
+
+    /// Unpack a tar.gz or zip archive under `dest`, creating parent
+    /// directories as needed. All files are written in one transaction; an
+    /// existing file is only replaced when `overwrite` is set.
+    pub async fn import_archive(
+        &self,
+        session_id: Uuid,
+        dest: &str,
+        data: Bytes,
+        overwrite: bool,
+    ) -> Result<Vec<FileInfo>> {
+        let dest = Self::normalize_path(dest);
+        Self::validate_path(&dest)?;
+
+        let max_bytes = self.limits.max_import_bytes;
+        if data.len() as u64 > max_bytes {
+            return Err(anyhow!(
+                "Archive exceeds the import size limit of {} bytes",
+                max_bytes
+            ));
+        }
+        let max_entries = match self.limits.max_files {
+            Some(max) => (max as usize).min(MAX_IMPORT_FILES),
+            None => MAX_IMPORT_FILES,
+        };
+        let entries = tokio::task::spawn_blocking(move || {
+            session_archive::unpack(&data, max_entries, max_bytes)
+        })
+        .await??;
+
+        let existing: HashMap<String, SessionFileInfoRow> = self
+            .db
+            .list_all_session_files(session_id)
+            .await?
+            .into_iter()
+            .map(|row| (row.path.clone(), row))
+            .collect();
+        if existing.get(&dest).is_some_and(|row| !row.is_directory) {
+            return Err(anyhow!("A file exists at path: {}", dest));
+        }
+
+        let mut files = Vec::with_capacity(entries.len());
+        let mut added_files = 0;
+        let mut added_bytes = 0;
+        for entry in entries {
+            let path = Self::normalize_path(&format!("{}/{}", dest, entry.path));
+            Self::validate_path(&path)?;
+            match existing.get(&path) {
+                Some(row) if row.is_directory => {
+                    return Err(anyhow!("Cannot modify directory: {}", path))
+                }
+                Some(row) if row.is_readonly => {
+                    return Err(anyhow!("Cannot modify readonly file: {}", path))
+                }
+                Some(_) if !overwrite => {
+                    return Err(anyhow!("File already exists at path: {}", path))
+                }
+                Some(_) => {}
+                None => added_files += 1,
+            }
+            added_bytes += entry.data.len() as i64;
+            files.push((path, entry.data));
+        }
+        self.check_quota(session_id, added_files, added_bytes)
+            .await?;
+
+        let mut rows = Vec::with_capacity(files.len());
+        let mut new_keys = Vec::new();
+        for (path, data) in files {
+            let stored = match self.content.store(session_id, Bytes::from(data)).await {
+                Ok(stored) => stored,
+                Err(e) => {
+                    self.content.release(new_keys).await;
+                    return Err(e);
+                }
+            };
+            new_keys.extend(stored.storage_key().map(str::to_string));
+            rows.push(SessionFileChangeRow::Write {
+                path,
+                content: stored,
+            });
+        }
+
+        match self.db.apply_session_file_changes(session_id, rows).await {
+            Ok(written) => Ok(written.into_iter().map(Self::row_to_file_info).collect()),
+            Err(e) => {
+                self.content.release(new_keys).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// List a file's versions, newest first
+    pub async fn list_versions(&self, session_id: Uuid, path: &str) -> Result<Vec<FileVersion>> {
+        let path = Self::normalize_path(path);
+        let rows = self
+            .db
+            .list_session_file_versions(session_id, &path)
+            .await?;
+        Ok(rows.into_iter().map(Self::row_to_file_version).collect())
+    }
+
+    /// Diff two versions of a file. `to` defaults to the latest version and
+    /// `from` to the version before `to`.
+    pub async fn diff(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        from: Option<i32>,
+        to: Option<i32>,
+    ) -> Result<FileDiff> {
+        let path = Self::normalize_path(path);
+
+        let to_row = self.get_version(session_id, &path, to).await?;
+        let from_version = from.unwrap_or(to_row.version - 1);
+        if from_version < 1 {
+            return Err(anyhow!(
+                "No earlier version to diff against: {} is at version {}",
+                path,
+                to_row.version
+            ));
+        }
+        let from_row = self
+            .get_version(session_id, &path, Some(from_version))
+            .await?;
+
+        let old = self.version_content(&from_row).await?;
+        let new = self.version_content(&to_row).await?;
+        Ok(FileDiff::between(
+            &path,
+            (from_row.version, &old),
+            (to_row.version, &new),
+        ))
+    }
+
+    /// Restore a single file to a version, snapshot or turn. The restored
+    /// content is written as a new version.
+    pub async fn restore_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        target: RestoreTarget,
+    ) -> Result<SessionFile> {
+        let path = Self::normalize_path(path);
+
+        let version = match target {
+            RestoreTarget::Version(version) => {
+                self.get_version(session_id, &path, Some(version)).await?
+            }
+            RestoreTarget::Snapshot(_) | RestoreTarget::Turn(_) => {
+                let snapshot = self.resolve_snapshot(session_id, target).await?;
+                self.db
+                    .get_session_snapshot_file(snapshot.id, &path)
+                    .await?
+                    .ok_or_else(|| {
+                        anyhow!("File not found in snapshot '{}': {}", snapshot.name, path)
+                    })?
+            }
+        };
+        let Some(content) = version.stored_content() else {
+            return Err(anyhow!(
+                "Cannot restore version {} of {}: the file was deleted at that version",
+                version.version,
+                path
+            ));
+        };
+
+        // The version's object is shared rather than copied, so nothing is
+        // released if the write fails
+        let row = match self.db.get_session_file(session_id, &path).await? {
+            Some(existing) if existing.is_directory => {
+                return Err(anyhow!("Cannot restore over directory: {}", path));
+            }
+            Some(existing) if existing.is_readonly => {
+                return Err(anyhow!("Cannot modify readonly file: {}", path));
+            }
+            Some(_) => self
+                .db
+                .update_session_file(
+                    session_id,
+                    &path,
+                    UpdateSessionFile {
+                        content: Some(content),
+                        is_readonly: None,
+                    },
+                )
+                .await?
+                .ok_or_else(|| anyhow!("File not found: {}", path))?,
+            None => {
+                self.prepare_new_file(session_id, &path).await?;
+                self.db
+                    .create_session_file(CreateSessionFileRow {
+                        session_id,
+                        path: path.clone(),
+                        content: Some(content),
+                        is_directory: false,
+                        is_readonly: false,
+                    })
+                    .await?
+            }
+        };
+
+        Ok(Self::row_to_session_file(self.content.hydrate(row).await?))
+    }
+
+    /// List snapshots, newest first
+    pub async fn list_snapshots(&self, session_id: Uuid) -> Result<Vec<SessionSnapshot>> {
+        let rows = self.db.list_session_snapshots(session_id).await?;
+        Ok(rows.into_iter().map(Self::row_to_snapshot).collect())
+    }
+
+    /// Get a snapshot by ID
+    pub async fn get_snapshot(
+        &self,
+        session_id: Uuid,
+        snapshot_id: Uuid,
+    ) -> Result<Option<SessionSnapshot>> {
+        let row = self
+            .db
+            .get_session_snapshot(session_id, snapshot_id)
+            .await?;
+        Ok(row.map(Self::row_to_snapshot))
+    }
+
+    /// Take a named snapshot of the current file tree
+    pub async fn create_snapshot(&self, session_id: Uuid, name: &str) -> Result<SessionSnapshot> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > 255 {
+            return Err(anyhow!(
+                "Invalid snapshot name: must be 1 to 255 characters"
+            ));
+        }
+        if name.starts_with(TURN_SNAPSHOT_PREFIX) {
+            return Err(anyhow!(
+                "Invalid snapshot name: '{}' is reserved for turn snapshots",
+                TURN_SNAPSHOT_PREFIX
+            ));
+        }
+
+        let input = CreateSessionSnapshotRow {
+            session_id,
+            name: name.to_string(),
+            kind: SnapshotKind::Manual.as_str().to_string(),
+            turn_id: None,
+            turn_number: None,
+        };
+        let row = self
+            .db
+            .create_session_snapshot(input)
+            .await?
+            .ok_or_else(|| anyhow!("Snapshot already exists: {}", name))?;
+        Ok(Self::row_to_snapshot(row))
+    }
+
+    /// Take the automatic snapshot for a turn boundary, named
+    /// `turn-<n>-start` or `turn-<n>-end`. Returns None if it already exists
+    /// (e.g. a retried turn re-emitting its events).
+    pub async fn snapshot_turn(
+        &self,
+        session_id: Uuid,
+        turn_id: Uuid,
+        kind: SnapshotKind,
+    ) -> Result<Option<SessionSnapshot>> {
+        let turn_number = self
+            .db
+            .session_snapshot_turn_number(session_id, turn_id)
+            .await?;
+        let boundary = match kind {
+            SnapshotKind::TurnStart => "start",
+            _ => "end",
+        };
+
+        let input = CreateSessionSnapshotRow {
+            session_id,
+            name: format!("{}{}-{}", TURN_SNAPSHOT_PREFIX, turn_number, boundary),
+            kind: kind.as_str().to_string(),
+            turn_id: Some(turn_id),
+            turn_number: Some(turn_number),
+        };
+        let row = self.db.create_session_snapshot(input).await?;
+        Ok(row.map(Self::row_to_snapshot))
+    }
+
+    /// Delete a snapshot. File versions it referenced are kept.
+    pub async fn delete_snapshot(&self, session_id: Uuid, snapshot_id: Uuid) -> Result<bool> {
+        self.db
+            .delete_session_snapshot(session_id, snapshot_id)
+            .await
+    }
+
+    /// Restore the whole file tree to a snapshot or turn, returning the
+    /// snapshot that was applied
+    pub async fn restore_snapshot(
+        &self,
+        session_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<SessionSnapshot> {
+        let snapshot = self.resolve_snapshot(session_id, target).await?;
+        self.db
+            .restore_session_snapshot(session_id, snapshot.id)
+            .await?;
+        Ok(Self::row_to_snapshot(snapshot))
+    }
+
+    /// Find the snapshot a restore target refers to
+    async fn resolve_snapshot(
+        &self,
+        session_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<SessionSnapshotRow> {
+        match target {
+            RestoreTarget::Snapshot(snapshot_id) => self
+                .db
+                .get_session_snapshot(session_id, snapshot_id)
+                .await?
+                .ok_or_else(|| anyhow!("Snapshot not found: {}", snapshot_id)),
+            RestoreTarget::Turn(turn_id) => self
+                .db
+                .get_session_turn_snapshot(session_id, turn_id)
+                .await?
+                .ok_or_else(|| anyhow!("No snapshot found for turn: {}", turn_id)),
+            RestoreTarget::Version(_) => Err(anyhow!(
+                "Invalid restore target: versions apply to single files only"
+            )),
+        }
+    }
+
+    /// Get a version (latest when None), failing if it doesn't exist
+    async fn get_version(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        version: Option<i32>,
+    ) -> Result<SessionFileVersionRow> {
+        self.db
+            .get_session_file_version(session_id, path, version)
+            .await?
+            .ok_or_else(|| match version {
+                Some(v) => anyhow!("Version {} not found for: {}", v, path),
+                None => anyhow!("No versions found for: {}", path),
+            })
+    }
+
+    /// Content of a version (empty for deletions)
+    async fn version_content(&self, row: &SessionFileVersionRow) -> Result<Vec<u8>> {
+        if row.is_deleted {
+            return Ok(Vec::new());
+        }
+        self.content.version_bytes(row).await
+    }
+
+    /// Load object-stored content and convert to the API model
+    async fn hydrated(&self, row: Option<SessionFileRow>) -> Result<Option<SessionFile>> {
+        match row {
+            Some(row) => Ok(Some(Self::row_to_session_file(
+                self.content.hydrate(row).await?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Attach content we already hold in memory to a freshly written row
+    fn with_content(mut row: SessionFileRow, data: Option<Bytes>) -> SessionFileRow {
+        if row.content.is_none() && row.storage_key.is_some() {
+            row.content = data.map(|bytes| bytes.to_vec());
+        }
+        row
+    }
+
     fn row_to_session_file(row: SessionFileRow) -> SessionFile {
         let (content, encoding) = if let Some(bytes) = row.content {
             let (c, e) = SessionFile::encode_content(&bytes);
@@ -528,4 +1404,89 @@ impl SessionFileService {
             updated_at: row.updated_at,
         }
     }
+
+    fn row_to_file_version(row: SessionFileVersionInfoRow) -> FileVersion {
+        FileVersion {
+            id: row.id,
+            session_id: row.session_id,
+            path: row.path,
+            version: row.version,
+            size_bytes: row.size_bytes,
+            is_deleted: row.is_deleted,
+            created_at: row.created_at,
+        }
+    }
+
+    fn row_to_snapshot(row: SessionSnapshotRow) -> SessionSnapshot {
+        SessionSnapshot {
+            id: row.id,
+            session_id: row.session_id,
+            name: row.name,
+            kind: SnapshotKind::from(row.kind.as_str()),
+            turn_id: row.turn_id,
+            turn_number: row.turn_number,
+            file_count: row.file_count,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Export progress: remaining entries and the archive being written
+struct ExportState {
+    db: Arc<Database>,
+    content: Arc<FileContentStore>,
+    session_id: Uuid,
+    /// Bytes to strip from each path to make it archive-relative
+    strip: usize,
+    entries: std::vec::IntoIter<SessionFileInfoRow>,
+    writer: Option<ArchiveWriter>,
+}
+
+impl ExportState {
+    /// Write entries until the archive has output, returning None once the
+    /// archive is finished
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>> {
+        loop {
+            let Some(mut writer) = self.writer.take() else {
+                return Ok(None);
+            };
+            let Some(entry) = self.entries.next() else {
+                let chunk = tokio::task::spawn_blocking(move || writer.finish()).await??;
+                return Ok(Some(chunk));
+            };
+
+            let name = entry.path[self.strip.min(entry.path.len())..].to_string();
+            let mtime = entry.updated_at.timestamp();
+            let data = if entry.is_directory {
+                None
+            } else {
+                let Some(row) = self
+                    .db
+                    .get_session_file(self.session_id, &entry.path)
+                    .await?
+                else {
+                    // Deleted since the listing was taken
+                    self.writer = Some(writer);
+                    continue;
+                };
+                Some(self.content.hydrate(row).await?.content.unwrap_or_default())
+            };
+
+            // Compression is CPU-bound, keep it off the async workers
+            let (writer, chunk) = tokio::task::spawn_blocking(move || {
+                let chunk = match data {
+                    Some(data) => writer.add_file(&name, &data, mtime),
+                    None => writer.add_directory(&name, mtime),
+                };
+                (writer, chunk)
+            })
+            .await?;
+            self.writer = Some(writer);
+
+            let chunk = chunk?;
+            if !chunk.is_empty() {
+                return Ok(Some(chunk));
+            }
+        }
+    }
 }
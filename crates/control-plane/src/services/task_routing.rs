@@ -0,0 +1,402 @@
+// Durable task queue routing
+//
+// Decides which task queue an agent's activities (process_input, reason, act)
+// are enqueued on; workers only claim from the queues they serve
+// (WORKER_TASK_QUEUES). Rules are read from DURABLE_TASK_ROUTES, a JSON array
+// evaluated in order. A rule matches when all of its conditions hold, the
+// first match wins, and tasks matching no rule go to the default queue:
+//
+//   [
+//     {"capability": "sandbox", "activity_types": ["act"], "task_queue": "sandbox"},
+//     {"agent_tag": "tenant:acme", "task_queue": "acme"}
+//   ]
+//
+// Decision: Routing happens in the control plane when the task is enqueued,
+// so workers need no routing configuration and rules change without
+// redeploying workers. A queue set explicitly in the task options wins.
+// Decision: The agent is identified by the `agent_id` of the task input.
+// Tasks without one only match rules that have no agent conditions.
+//
+// The router also assigns each task a fairness key (DURABLE_FAIRNESS_KEY), so
+// claims take turns between sessions, agents or tenants and can be capped per
+// key (DURABLE_MAX_CONCURRENT_PER_KEY, DURABLE_MAX_CONCURRENT_PER_ACTIVITY;
+// see FairnessConfig in the durable crate).
+
+use crate::storage::Database;
+use anyhow::{anyhow, Context, Result};
+use everruns_durable::{FairnessConfig, DEFAULT_TASK_QUEUE};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A routing rule; omitted conditions match any task
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskRouteRule {
+    /// Queue the matching tasks are enqueued on
+    pub task_queue: String,
+    /// Activity types the rule applies to; empty means all
+    #[serde(default)]
+    pub activity_types: Vec<String>,
+    /// Only tasks of this agent
+    #[serde(default)]
+    pub agent_id: Option<Uuid>,
+    /// Only tasks of agents with this tag (e.g. per-tenant pools)
+    #[serde(default)]
+    pub agent_tag: Option<String>,
+    /// Only tasks of agents with this capability enabled
+    #[serde(default)]
+    pub capability: Option<String>,
+}
+
+impl TaskRouteRule {
+    /// Whether the rule needs the agent's tags or capabilities
+    fn needs_agent(&self) -> bool {
+        self.agent_tag.is_some() || self.capability.is_some()
+    }
+
+    fn matches(&self, activity_type: &str, agent: Option<&AgentFacts>) -> bool {
+        if !self.activity_types.is_empty()
+            && !self.activity_types.iter().any(|t| t == activity_type)
+        {
+            return false;
+        }
+        if self.agent_id.is_none() && !self.needs_agent() {
+            return true;
+        }
+        let Some(agent) = agent else {
+            return false;
+        };
+        self.agent_id.is_none_or(|id| id == agent.id)
+            && self
+                .agent_tag
+                .as_ref()
+                .is_none_or(|tag| agent.tags.contains(tag))
+            && self
+                .capability
+                .as_ref()
+                .is_none_or(|cap| agent.capabilities.contains(cap))
+    }
+}
+
+/// What the rules know about the agent of a task
+#[derive(Debug, Default)]
+struct AgentFacts {
+    id: Uuid,
+    tags: Vec<String>,
+    capabilities: Vec<String>,
+}
+
+/// Parse and validate routing rules (the DURABLE_TASK_ROUTES format)
+pub fn parse_task_routes(json: &str) -> Result<Vec<TaskRouteRule>> {
+    let rules: Vec<TaskRouteRule> =
+        serde_json::from_str(json).context("Invalid task routing rules")?;
+    if let Some(i) = rules.iter().position(|r| r.task_queue.trim().is_empty()) {
+        return Err(anyhow!("Task routing rule {} has an empty task_queue", i));
+    }
+    Ok(rules)
+}
+
+/// What tasks are scheduled fairly by
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FairnessKey {
+    /// No fairness key: plain priority order
+    None,
+    /// `session:<id>`
+    #[default]
+    Session,
+    /// `agent:<id>`
+    Agent,
+    /// The first agent tag with the given prefix (e.g. `tenant:` for tags like
+    /// `tenant:acme`); tasks of agents without such a tag have no key
+    AgentTag(String),
+}
+
+impl std::str::FromStr for FairnessKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "none" => Ok(Self::None),
+            "session" => Ok(Self::Session),
+            "agent" => Ok(Self::Agent),
+            other => match other.strip_prefix("agent_tag:") {
+                Some(prefix) if !prefix.is_empty() => Ok(Self::AgentTag(prefix.to_string())),
+                _ => Err(anyhow!(
+                    "Invalid fairness key '{}' (expected none, session, agent or agent_tag:<prefix>)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// Parse per-activity-type limits (`reason=20,act=50`)
+pub fn parse_activity_limits(s: &str) -> Result<Vec<(String, u32)>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (activity_type, max) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid activity limit '{}'", entry))?;
+            let max = max
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid activity limit '{}'", entry))?;
+            Ok((activity_type.trim().to_string(), max))
+        })
+        .collect()
+}
+
+/// Concurrency limits for task claims from DURABLE_MAX_CONCURRENT_PER_KEY and
+/// DURABLE_MAX_CONCURRENT_PER_ACTIVITY (no limits when unset)
+pub fn fairness_config_from_env() -> Result<FairnessConfig> {
+    let mut config = FairnessConfig::new();
+    if let Ok(max) = std::env::var("DURABLE_MAX_CONCURRENT_PER_KEY") {
+        let max = max
+            .trim()
+            .parse()
+            .context("Invalid DURABLE_MAX_CONCURRENT_PER_KEY")?;
+        config = config.with_max_concurrent_per_key(max);
+    }
+    if let Ok(limits) = std::env::var("DURABLE_MAX_CONCURRENT_PER_ACTIVITY") {
+        for (activity_type, max) in
+            parse_activity_limits(&limits).context("Invalid DURABLE_MAX_CONCURRENT_PER_ACTIVITY")?
+        {
+            config = config.with_max_concurrent_for_activity(activity_type, max);
+        }
+    }
+    Ok(config)
+}
+
+/// Resolves the task queue and fairness key of durable tasks
+#[derive(Clone)]
+pub struct TaskRouter {
+    db: Arc<Database>,
+    rules: Arc<Vec<TaskRouteRule>>,
+    fairness_key: FairnessKey,
+}
+
+impl TaskRouter {
+    pub fn new(db: Arc<Database>, rules: Vec<TaskRouteRule>) -> Self {
+        Self {
+            db,
+            rules: Arc::new(rules),
+            fairness_key: FairnessKey::default(),
+        }
+    }
+
+    /// Create a router from DURABLE_TASK_ROUTES (no rules when unset) and
+    /// DURABLE_FAIRNESS_KEY (`session` when unset)
+    pub fn from_env(db: Arc<Database>) -> Result<Self> {
+        let rules = match std::env::var("DURABLE_TASK_ROUTES") {
+            Ok(json) if !json.trim().is_empty() => {
+                parse_task_routes(&json).context("Invalid DURABLE_TASK_ROUTES")?
+            }
+            _ => Vec::new(),
+        };
+        let fairness_key = match std::env::var("DURABLE_FAIRNESS_KEY") {
+            Ok(key) => key.parse().context("Invalid DURABLE_FAIRNESS_KEY")?,
+            Err(_) => FairnessKey::default(),
+        };
+        Ok(Self::new(db, rules).with_fairness_key(fairness_key))
+    }
+
+    /// Schedule tasks fairly by the given key
+    pub fn with_fairness_key(mut self, fairness_key: FairnessKey) -> Self {
+        self.fairness_key = fairness_key;
+        self
+    }
+
+    pub fn rules(&self) -> &[TaskRouteRule] {
+        &self.rules
+    }
+
+    /// Queue for a task of the given activity type and input
+    pub async fn route(&self, activity_type: &str, input: &serde_json::Value) -> Result<String> {
+        let candidates: Vec<&TaskRouteRule> = self
+            .rules
+            .iter()
+            .filter(|r| {
+                r.activity_types.is_empty() || r.activity_types.iter().any(|t| t == activity_type)
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Ok(DEFAULT_TASK_QUEUE.to_string());
+        }
+
+        let agent_id = input
+            .get("agent_id")
+            .and_then(|v| v.as_str())
+            .and_then(|s| Uuid::parse_str(s).ok());
+        let agent = match agent_id {
+            Some(id) => Some(self.agent_facts(id, &candidates).await?),
+            None => None,
+        };
+
+        Ok(select_queue(&candidates, activity_type, agent.as_ref()))
+    }
+
+    /// Fairness key for a task with the given input
+    pub async fn fairness_key(&self, input: &serde_json::Value) -> Result<Option<String>> {
+        let uuid_at = |pointer: &str| {
+            input
+                .pointer(pointer)
+                .and_then(|v| v.as_str())
+                .and_then(|s| Uuid::parse_str(s).ok())
+        };
+
+        Ok(match &self.fairness_key {
+            FairnessKey::None => None,
+            // Turn inputs carry the session at the top level, act inputs in
+            // their atom context
+            FairnessKey::Session => uuid_at("/session_id")
+                .or_else(|| uuid_at("/context/session_id"))
+                .map(|id| format!("session:{}", id)),
+            FairnessKey::Agent => uuid_at("/agent_id").map(|id| format!("agent:{}", id)),
+            FairnessKey::AgentTag(prefix) => match uuid_at("/agent_id") {
+                Some(id) => self
+                    .db
+                    .get_agent(id)
+                    .await?
+                    .and_then(|agent| agent.tags.into_iter().find(|t| t.starts_with(prefix))),
+                None => None,
+            },
+        })
+    }
+
+    /// Load only what the candidate rules look at
+    async fn agent_facts(&self, id: Uuid, rules: &[&TaskRouteRule]) -> Result<AgentFacts> {
+        let mut facts = AgentFacts {
+            id,
+            ..Default::default()
+        };
+        if rules.iter().any(|r| r.agent_tag.is_some()) {
+            if let Some(agent) = self.db.get_agent(id).await? {
+                facts.tags = agent.tags;
+            }
+        }
+        if rules.iter().any(|r| r.capability.is_some()) {
+            facts.capabilities = self
+                .db
+                .get_agent_capabilities(id)
+                .await?
+                .into_iter()
+                .map(|c| c.capability_id)
+                .collect();
+        }
+        Ok(facts)
+    }
+}
+
+fn select_queue(
+    rules: &[&TaskRouteRule],
+    activity_type: &str,
+    agent: Option<&AgentFacts>,
+) -> String {
+    rules
+        .iter()
+        .find(|r| r.matches(activity_type, agent))
+        .map(|r| r.task_queue.clone())
+        .unwrap_or_else(|| DEFAULT_TASK_QUEUE.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> Vec<TaskRouteRule> {
+        parse_task_routes(
+            r#"[
+                {"capability": "sandbox", "activity_types": ["act"], "task_queue": "sandbox"},
+                {"agent_tag": "tenant:acme", "task_queue": "acme"},
+                {"activity_types": ["reason"], "task_queue": "llm"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn agent(tags: &[&str], capabilities: &[&str]) -> AgentFacts {
+        AgentFacts {
+            id: Uuid::now_v7(),
+            tags: tags.iter().map(|s| s.to_string()).collect(),
+            capabilities: capabilities.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = rules();
+        let rules: Vec<&TaskRouteRule> = rules.iter().collect();
+        let acme_sandbox = agent(&["tenant:acme"], &["sandbox"]);
+
+        assert_eq!(select_queue(&rules, "act", Some(&acme_sandbox)), "sandbox");
+        assert_eq!(select_queue(&rules, "reason", Some(&acme_sandbox)), "acme");
+        assert_eq!(
+            select_queue(&rules, "reason", Some(&agent(&[], &[]))),
+            "llm"
+        );
+        assert_eq!(
+            select_queue(&rules, "act", Some(&agent(&[], &[]))),
+            DEFAULT_TASK_QUEUE
+        );
+    }
+
+    #[test]
+    fn test_agent_rules_need_an_agent() {
+        let rules = rules();
+        let rules: Vec<&TaskRouteRule> = rules.iter().collect();
+
+        assert_eq!(select_queue(&rules, "act", None), DEFAULT_TASK_QUEUE);
+        assert_eq!(select_queue(&rules, "reason", None), "llm");
+    }
+
+    #[test]
+    fn test_agent_id_rule() {
+        let facts = agent(&[], &[]);
+        let rule = TaskRouteRule {
+            task_queue: "dedicated".to_string(),
+            activity_types: vec![],
+            agent_id: Some(facts.id),
+            agent_tag: None,
+            capability: None,
+        };
+
+        assert!(rule.matches("act", Some(&facts)));
+        assert!(!rule.matches("act", Some(&agent(&[], &[]))));
+        assert!(!rule.matches("act", None));
+    }
+
+    #[test]
+    fn test_parse_fairness_key() {
+        assert_eq!(
+            "session".parse::<FairnessKey>().unwrap(),
+            FairnessKey::Session
+        );
+        assert_eq!("none".parse::<FairnessKey>().unwrap(), FairnessKey::None);
+        assert_eq!(
+            "agent_tag:tenant:".parse::<FairnessKey>().unwrap(),
+            FairnessKey::AgentTag("tenant:".to_string())
+        );
+        assert!("agent_tag:".parse::<FairnessKey>().is_err());
+        assert!("tenant".parse::<FairnessKey>().is_err());
+    }
+
+    #[test]
+    fn test_parse_activity_limits() {
+        assert_eq!(
+            parse_activity_limits("reason=20, act = 50,").unwrap(),
+            vec![("reason".to_string(), 20), ("act".to_string(), 50)]
+        );
+        assert!(parse_activity_limits("reason").is_err());
+        assert!(parse_activity_limits("reason=many").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_rules() {
+        assert!(parse_task_routes(r#"[{"task_queue": " "}]"#).is_err());
+        assert!(parse_task_routes(r#"[{"queue": "sandbox"}]"#).is_err());
+        assert!(parse_task_routes("[]").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+// Ends turns whose activity timed out for good
+//
+// When an activity exhausts its attempts through timeouts, the timeout sweeper
+// marks a legacy turn workflow (`turn_workflow`, one per message) failed, and
+// a session workflow moves on to its next queued turn. The worker that would
+// end the turn never runs again, so without this the session stays active and
+// clients never see the turn end.
+//
+// Decision: The turn ends like a failed reason step: `turn.failed`, session
+// status `idle`, then `session.idled`.
+// Decision: A session whose workflow already started its next turn stays
+// active; only `turn.failed` is emitted.
+
+use super::{EventService, SessionService};
+use crate::storage::Database;
+use anyhow::Result;
+use everruns_core::events::{EventContext, EventRequest, SessionIdledData, TurnFailedData};
+use everruns_durable::{
+    PostgresWorkflowEventStore, TaskFailureOutcome, TimedOutTask, WorkflowEventStore,
+    WorkflowExecutor,
+};
+use everruns_worker::session_workflow::session_queries;
+use everruns_worker::{
+    session_workflow_executor, turn_message_id, DurableTurnInput, SessionState,
+    SessionWorkflowInput, SESSION_WORKFLOW_TYPE, TURN_WORKFLOW_TYPE,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub struct TimedOutTurns {
+    store: PostgresWorkflowEventStore,
+    executor: WorkflowExecutor<PostgresWorkflowEventStore>,
+    sessions: SessionService,
+    events: Arc<EventService>,
+}
+
+impl TimedOutTurns {
+    pub fn new(db: Arc<Database>, events: Arc<EventService>) -> Self {
+        Self {
+            store: PostgresWorkflowEventStore::new(db.pool().clone()),
+            executor: session_workflow_executor(db.pool().clone()),
+            sessions: SessionService::new(db),
+            events,
+        }
+    }
+
+    /// End the turn of a timed out task that will not be retried
+    pub async fn handle(&self, task: &TimedOutTask) -> Result<()> {
+        if matches!(task.outcome, TaskFailureOutcome::WillRetry { .. }) {
+            return Ok(());
+        }
+        let info = self.store.get_workflow_info(task.workflow_id).await?;
+        match info.workflow_type.as_str() {
+            TURN_WORKFLOW_TYPE => {
+                let input: DurableTurnInput = serde_json::from_value(info.input)?;
+                self.end_turn(input.session_id, input.input_message_id, true)
+                    .await
+            }
+            SESSION_WORKFLOW_TYPE => {
+                let Some(input_message_id) = turn_message_id(&task.activity_id) else {
+                    return Ok(());
+                };
+                let input: SessionWorkflowInput = serde_json::from_value(info.input)?;
+                let state = self
+                    .executor
+                    .query_workflow(
+                        task.workflow_id,
+                        session_queries::STATE,
+                        serde_json::Value::Null,
+                    )
+                    .await?;
+                let state: SessionState = serde_json::from_value(state)?;
+                self.end_turn(
+                    input.session_id,
+                    input_message_id,
+                    state.current_turn.is_none(),
+                )
+                .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn end_turn(&self, session_id: Uuid, input_message_id: Uuid, idle: bool) -> Result<()> {
+        let turn_id = Uuid::now_v7();
+        let context = EventContext::turn(turn_id, input_message_id);
+
+        self.events
+            .emit(EventRequest::new(
+                session_id,
+                context.clone(),
+                TurnFailedData {
+                    turn_id,
+                    error: "An error occurred while processing your request.".to_string(),
+                    error_code: Some("activity_timed_out".to_string()),
+                },
+            ))
+            .await?;
+        if !idle {
+            return Ok(());
+        }
+        self.sessions
+            .update_status(session_id, "idle".to_string())
+            .await?;
+        self.events
+            .emit(EventRequest::new(
+                session_id,
+                context,
+                SessionIdledData {
+                    turn_id,
+                    iterations: None,
+                },
+            ))
+            .await?;
+        Ok(())
+    }
+}
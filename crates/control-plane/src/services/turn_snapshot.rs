@@ -0,0 +1,68 @@
+// Automatic session filesystem snapshots at turn boundaries
+//
+// Listens for turn lifecycle events and snapshots the session file tree as
+// `turn-<n>-start` when a turn begins and `turn-<n>-end` when it completes
+// or fails, so any turn can be restored later.
+//
+// Decision: Snapshot inline rather than in a spawned task. The start snapshot
+// must capture the tree before the turn's first tool write, and a snapshot is
+// a single transaction that only copies version references.
+// Decision: Failures are logged, never propagated - a missing snapshot must
+// not fail the turn that emitted the event.
+
+use super::SessionFileService;
+use async_trait::async_trait;
+use everruns_core::events::{TURN_COMPLETED, TURN_FAILED, TURN_STARTED};
+use everruns_core::{Event, EventData, EventListener, SnapshotKind};
+
+pub struct TurnSnapshotListener {
+    files: SessionFileService,
+}
+
+impl TurnSnapshotListener {
+    pub fn new(files: SessionFileService) -> Self {
+        Self { files }
+    }
+}
+
+#[async_trait]
+impl EventListener for TurnSnapshotListener {
+    async fn on_event(&self, event: &Event) {
+        let (turn_id, kind) = match &event.data {
+            EventData::TurnStarted(data) => (data.turn_id, SnapshotKind::TurnStart),
+            EventData::TurnCompleted(data) => (data.turn_id, SnapshotKind::TurnEnd),
+            EventData::TurnFailed(data) => (data.turn_id, SnapshotKind::TurnEnd),
+            _ => return,
+        };
+
+        match self
+            .files
+            .snapshot_turn(event.session_id, turn_id, kind)
+            .await
+        {
+            Ok(Some(snapshot)) => tracing::debug!(
+                session_id = %event.session_id,
+                turn_id = %turn_id,
+                snapshot = %snapshot.name,
+                files = snapshot.file_count,
+                "Took turn snapshot"
+            ),
+            // Already taken, e.g. by a retried turn re-emitting its events
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                session_id = %event.session_id,
+                turn_id = %turn_id,
+                error = %e,
+                "Failed to take turn snapshot"
+            ),
+        }
+    }
+
+    fn event_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec![TURN_STARTED, TURN_COMPLETED, TURN_FAILED])
+    }
+
+    fn name(&self) -> &'static str {
+        "TurnSnapshotListener"
+    }
+}
@@ -0,0 +1,318 @@
+// Webhook subscription service
+//
+// Subscriptions are per agent or global (no agent) and filtered by event type.
+// Each delivery body is the event JSON, signed with the subscription secret:
+//
+//   X-Everruns-Signature: t=<unix seconds>,v1=<hex HMAC-SHA256("<t>.<body>")>
+//
+// Decision: The secret is generated server-side unless provided, returned
+// only on create/rotate (like API keys) and encrypted at rest, since signing
+// needs the plaintext.
+// Decision: The timestamp is part of the signed payload so receivers can
+// reject replays of old deliveries.
+// Decision: URLs on private network addresses (loopback, cloud metadata, ...)
+// are refused unless the operator allows them
+// (`WEBHOOKS_ALLOW_PRIVATE_NETWORKS`); deliveries re-check every resolved
+// address, so DNS names cannot reach them either.
+
+use crate::api::webhooks::{CreateWebhookRequest, UpdateWebhookRequest, Webhook};
+use crate::storage::{
+    models::{CreateWebhookRow, UpdateWebhook, WebhookRow},
+    Database, EncryptionService,
+};
+use anyhow::{anyhow, bail, Result};
+use everruns_core::network_guard::literal_private_host;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Header carrying the delivery signature
+pub const SIGNATURE_HEADER: &str = "X-Everruns-Signature";
+/// Header carrying the event type of a delivery
+pub const EVENT_HEADER: &str = "X-Everruns-Event";
+/// Header carrying the delivery ID (stable across retries)
+pub const DELIVERY_HEADER: &str = "X-Everruns-Delivery";
+
+/// Prefix of generated signing secrets
+const SECRET_PREFIX: &str = "whsec_";
+const SECRET_LENGTH: usize = 32; // 32 random bytes = 64 hex chars
+const MIN_SECRET_LENGTH: usize = 16;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct WebhookService {
+    db: Arc<Database>,
+    encryption: Option<Arc<EncryptionService>>,
+    allow_private_networks: bool,
+}
+
+impl WebhookService {
+    pub fn new(db: Arc<Database>, encryption: Option<Arc<EncryptionService>>) -> Self {
+        Self {
+            db,
+            encryption,
+            allow_private_networks: false,
+        }
+    }
+
+    /// Accept URLs on private network addresses (refused by default)
+    pub fn allow_private_networks(mut self, allow: bool) -> Self {
+        self.allow_private_networks = allow;
+        self
+    }
+
+    pub async fn create(&self, req: CreateWebhookRequest) -> Result<Webhook> {
+        validate_url(&req.url, self.allow_private_networks)?;
+        validate_event_types(&req.event_types)?;
+        if let Some(agent_id) = req.agent_id {
+            self.db
+                .get_agent(agent_id)
+                .await?
+                .ok_or_else(|| anyhow!("Agent not found: {}", agent_id))?;
+        }
+        let secret = match req.secret {
+            Some(secret) => validate_secret(secret)?,
+            None => generate_secret(),
+        };
+
+        let input = CreateWebhookRow {
+            agent_id: req.agent_id,
+            url: req.url,
+            event_types: req.event_types,
+            description: req.description,
+            secret_encrypted: self.encrypt_secret(&secret)?,
+            enabled: req.enabled,
+        };
+
+        let row = self.db.create_webhook(input).await?;
+        Ok(Self::row_to_webhook(&row, Some(secret)))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Option<Webhook>> {
+        let row = self.db.get_webhook(id).await?;
+        Ok(row.as_ref().map(|row| Self::row_to_webhook(row, None)))
+    }
+
+    pub async fn list(&self, agent_id: Option<Uuid>) -> Result<Vec<Webhook>> {
+        let rows = self.db.list_webhooks(agent_id).await?;
+        Ok(rows
+            .iter()
+            .map(|row| Self::row_to_webhook(row, None))
+            .collect())
+    }
+
+    pub async fn update(&self, id: Uuid, req: UpdateWebhookRequest) -> Result<Option<Webhook>> {
+        if let Some(url) = &req.url {
+            validate_url(url, self.allow_private_networks)?;
+        }
+        if let Some(event_types) = &req.event_types {
+            validate_event_types(event_types)?;
+        }
+        // A rotated secret is returned once; a provided one is already known
+        let (secret, returned) = match (req.secret, req.rotate_secret) {
+            (_, true) => {
+                let secret = generate_secret();
+                (Some(secret.clone()), Some(secret))
+            }
+            (Some(secret), false) => (Some(validate_secret(secret)?), None),
+            (None, false) => (None, None),
+        };
+
+        let input = UpdateWebhook {
+            url: req.url,
+            event_types: req.event_types,
+            description: req.description,
+            secret_encrypted: secret
+                .as_deref()
+                .map(|s| self.encrypt_secret(s))
+                .transpose()?,
+            enabled: req.enabled,
+        };
+
+        let row = self.db.update_webhook(id, input).await?;
+        Ok(row.as_ref().map(|row| Self::row_to_webhook(row, returned)))
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<bool> {
+        self.db.delete_webhook(id).await
+    }
+
+    /// Decrypt a subscription's signing secret (for deliveries)
+    pub fn decrypt_secret(&self, row: &WebhookRow) -> Result<String> {
+        let encryption = self
+            .encryption
+            .as_ref()
+            .ok_or_else(|| anyhow!("Encryption not configured. Cannot decrypt webhook secret."))?;
+        encryption.decrypt_to_string(&row.secret_encrypted)
+    }
+
+    fn encrypt_secret(&self, secret: &str) -> Result<Vec<u8>> {
+        let encryption = self
+            .encryption
+            .as_ref()
+            .ok_or_else(|| anyhow!("Encryption not configured. Cannot store webhook secret."))?;
+        encryption.encrypt_string(secret)
+    }
+
+    fn row_to_webhook(row: &WebhookRow, secret: Option<String>) -> Webhook {
+        Webhook {
+            id: row.id,
+            agent_id: row.agent_id,
+            url: row.url.clone(),
+            event_types: row.event_types.clone(),
+            description: row.description.clone(),
+            enabled: row.enabled,
+            secret,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Signature header value for a delivery body sent at `timestamp` (unix seconds)
+pub fn sign_payload(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "t={},v1={}",
+        timestamp,
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+fn generate_secret() -> String {
+    let mut rng = rand::thread_rng();
+    let random_bytes: Vec<u8> = (0..SECRET_LENGTH).map(|_| rng.gen()).collect();
+    format!("{}{}", SECRET_PREFIX, hex::encode(random_bytes))
+}
+
+fn validate_secret(secret: String) -> Result<String> {
+    if secret.chars().count() < MIN_SECRET_LENGTH {
+        bail!(
+            "Webhook secret must be at least {} characters",
+            MIN_SECRET_LENGTH
+        );
+    }
+    Ok(secret)
+}
+
+fn validate_url(url: &str, allow_private_networks: bool) -> Result<()> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|e| anyhow!("Invalid webhook URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!(
+            "Invalid webhook URL '{}': scheme must be http or https",
+            url
+        );
+    }
+    if !allow_private_networks {
+        // Other names are checked when deliveries resolve them
+        let host = parsed
+            .host_str()
+            .unwrap_or_default()
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        let loopback_name = host == "localhost" || host.ends_with(".localhost");
+        if loopback_name || literal_private_host(&parsed).is_some() {
+            bail!(
+                "Invalid webhook URL '{}': private network addresses are not allowed",
+                url
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Event types are dot-separated lowercase words, e.g. `turn.completed`
+fn validate_event_types(event_types: &[String]) -> Result<()> {
+    for event_type in event_types {
+        let valid = event_type.split('.').count() >= 2
+            && event_type.split('.').all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            });
+        if !valid {
+            bail!("Invalid event type '{}'", event_type);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload() {
+        let signature = sign_payload("whsec_test", 1700000000, b"{\"type\":\"turn.completed\"}");
+        let (timestamp, digest) = signature.split_once(",v1=").unwrap();
+        assert_eq!(timestamp, "t=1700000000");
+        assert_eq!(digest.len(), 64);
+
+        // Receivers recompute the HMAC over "<t>.<body>"
+        let mut mac = HmacSha256::new_from_slice(b"whsec_test").unwrap();
+        mac.update(b"1700000000.{\"type\":\"turn.completed\"}");
+        assert_eq!(digest, hex::encode(mac.finalize().into_bytes()));
+
+        assert_ne!(signature, sign_payload("other_secret", 1700000000, b"{}"));
+        assert_ne!(
+            signature,
+            sign_payload("whsec_test", 1700000001, b"{\"type\":\"turn.completed\"}")
+        );
+    }
+
+    #[test]
+    fn test_generate_secret() {
+        let secret = generate_secret();
+        assert!(secret.starts_with(SECRET_PREFIX));
+        assert_eq!(secret.len(), SECRET_PREFIX.len() + SECRET_LENGTH * 2);
+        assert_ne!(secret, generate_secret());
+    }
+
+    #[test]
+    fn test_validate_secret() {
+        assert!(validate_secret("short".to_string()).is_err());
+        assert!(validate_secret("a-long-enough-secret".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://example.com/hooks", false).is_ok());
+        assert!(validate_url("ftp://example.com", false).is_err());
+        assert!(validate_url("not a url", false).is_err());
+
+        for url in [
+            "http://localhost:8080/hooks",
+            "http://api.localhost/hooks",
+            "http://127.0.0.1/hooks",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hooks",
+            "http://10.0.0.5/hooks",
+        ] {
+            assert!(validate_url(url, false).is_err(), "{}", url);
+            assert!(validate_url(url, true).is_ok(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_validate_event_types() {
+        let types = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(validate_event_types(&types(&[])).is_ok());
+        assert!(validate_event_types(&types(&[
+            "turn.completed",
+            "turn.failed",
+            "tool.approval_requested"
+        ]))
+        .is_ok());
+        assert!(validate_event_types(&types(&["turn"])).is_err());
+        assert!(validate_event_types(&types(&["Turn.Completed"])).is_err());
+        assert!(validate_event_types(&types(&["turn..completed"])).is_err());
+    }
+}
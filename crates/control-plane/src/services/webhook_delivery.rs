@@ -0,0 +1,333 @@
+// Durable webhook delivery
+//
+// WebhookListener turns every matching event into a `webhook_delivery`
+// workflow with one `deliver_webhook` task on the durable task queue.
+// WebhookDeliveryWorker (spawned by the control plane) claims those tasks,
+// POSTs the signed event and reports the outcome back to the queue.
+//
+// Decision: Retries and backoff come from the task's RetryPolicy
+// (`fail_task` requeues with a delay). When attempts are exhausted the task is
+// moved to the durable dead letter queue and the workflow marked failed, so
+// failed deliveries can be inspected and requeued like any other task.
+// Decision: Deliveries run in the control plane, not in agent workers: they
+// need the decrypted subscription secret, which never leaves the control plane.
+// Decision: Deliveries of a deleted or disabled subscription complete without
+// a request.
+// Decision: Redirects are not followed (a 3xx response is a failed delivery)
+// and, unless private networks are allowed, every resolved address is checked
+// with the shared network guard.
+
+use super::webhook::{
+    sign_payload, WebhookService, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+};
+use crate::storage::{Database, EncryptionService};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use everruns_core::network_guard::{guard_client, literal_private_host, private_target_refused};
+use everruns_core::{Event, EventListener};
+use everruns_durable::{
+    ActivityOptions, ClaimedTask, PostgresWorkflowEventStore, RetryPolicy, TaskDefinition,
+    TaskFailureOutcome, WorkflowError, WorkflowEventStore, WorkflowStatus,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Workflow type of a webhook delivery
+pub const WEBHOOK_DELIVERY_WORKFLOW: &str = "webhook_delivery";
+/// Activity type of the delivery task
+pub const DELIVER_WEBHOOK_ACTIVITY: &str = "deliver_webhook";
+
+/// Maximum number of deliveries claimed per poll
+const BATCH_SIZE: usize = 20;
+/// Timeout of one delivery request (well below the stale task threshold)
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Retry policy of deliveries: 8 attempts over roughly an hour
+fn delivery_options() -> ActivityOptions {
+    ActivityOptions::default()
+        .with_retry(
+            RetryPolicy::exponential()
+                .with_max_attempts(8)
+                .with_initial_interval(Duration::from_secs(30))
+                .with_max_interval(Duration::from_secs(30 * 60)),
+        )
+        .with_start_to_close_timeout(REQUEST_TIMEOUT)
+}
+
+/// Enqueues a durable delivery for every subscription matching an event
+pub struct WebhookListener {
+    db: Arc<Database>,
+    store: PostgresWorkflowEventStore,
+}
+
+impl WebhookListener {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            store: PostgresWorkflowEventStore::new(db.pool().clone()),
+            db,
+        }
+    }
+
+    async fn enqueue(&self, webhook_id: Uuid, event: &Event) -> Result<Uuid> {
+        let delivery_id = Uuid::now_v7();
+        self.store
+            .create_workflow(
+                delivery_id,
+                WEBHOOK_DELIVERY_WORKFLOW,
+                json!({ "webhook_id": webhook_id, "event_id": event.id }),
+                None,
+            )
+            .await?;
+        self.store
+            .enqueue_task(TaskDefinition {
+                workflow_id: delivery_id,
+                activity_id: "deliver".to_string(),
+                activity_type: DELIVER_WEBHOOK_ACTIVITY.to_string(),
+                input: json!({ "webhook_id": webhook_id, "event": event }),
+                options: delivery_options(),
+            })
+            .await?;
+        Ok(delivery_id)
+    }
+}
+
+#[async_trait]
+impl EventListener for WebhookListener {
+    async fn on_event(&self, event: &Event) {
+        let webhooks = match self
+            .db
+            .list_webhooks_for_event(event.session_id, &event.event_type)
+            .await
+        {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::warn!(
+                    session_id = %event.session_id,
+                    event_type = %event.event_type,
+                    error = %e,
+                    "Failed to match webhooks"
+                );
+                return;
+            }
+        };
+
+        for webhook in webhooks {
+            match self.enqueue(webhook.id, event).await {
+                Ok(delivery_id) => tracing::debug!(
+                    webhook_id = %webhook.id,
+                    %delivery_id,
+                    event_type = %event.event_type,
+                    "Enqueued webhook delivery"
+                ),
+                Err(e) => tracing::warn!(
+                    webhook_id = %webhook.id,
+                    event_id = %event.id,
+                    error = %e,
+                    "Failed to enqueue webhook delivery"
+                ),
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "WebhookListener"
+    }
+}
+
+/// Executes `deliver_webhook` tasks from the durable task queue
+pub struct WebhookDeliveryWorker {
+    db: Arc<Database>,
+    webhooks: WebhookService,
+    store: PostgresWorkflowEventStore,
+    client: reqwest::Client,
+    allow_private_networks: bool,
+    worker_id: String,
+}
+
+impl WebhookDeliveryWorker {
+    pub fn new(
+        db: Arc<Database>,
+        encryption: Option<Arc<EncryptionService>>,
+        allow_private_networks: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            webhooks: WebhookService::new(db.clone(), encryption)
+                .allow_private_networks(allow_private_networks),
+            store: PostgresWorkflowEventStore::new(db.pool().clone()),
+            db,
+            client: delivery_client(allow_private_networks)?,
+            allow_private_networks,
+            worker_id: format!("control-plane-webhooks-{}", Uuid::now_v7()),
+        })
+    }
+
+    /// Run the delivery loop forever
+    pub async fn run(self, poll_interval: Duration) {
+        let mut interval = tokio::time::interval(poll_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.tick().await {
+                tracing::error!("Webhook delivery poll failed: {}", e);
+            }
+        }
+    }
+
+    /// Claim due deliveries and execute them concurrently
+    pub async fn tick(&self) -> Result<()> {
+        let tasks = self
+            .store
+            .claim_task(
+                &self.worker_id,
+                &[DELIVER_WEBHOOK_ACTIVITY.to_string()],
+                BATCH_SIZE,
+            )
+            .await?;
+
+        futures::future::join_all(tasks.into_iter().map(|task| async move {
+            if let Err(e) = self.execute(&task).await {
+                tracing::error!(task_id = %task.id, error = %e, "Failed to record webhook delivery");
+            }
+        }))
+        .await;
+
+        Ok(())
+    }
+
+    /// Deliver one task and report the outcome to the queue
+    async fn execute(&self, task: &ClaimedTask) -> Result<()> {
+        match self.deliver(task).await {
+            Ok(output) => {
+                self.store.complete_task(task.id, output.clone()).await?;
+                self.store
+                    .update_workflow_status(
+                        task.workflow_id,
+                        WorkflowStatus::Completed,
+                        Some(output),
+                        None,
+                    )
+                    .await?;
+            }
+            Err(e) => {
+                let error = e.to_string();
+                match self.store.fail_task(task.id, &error).await? {
+                    TaskFailureOutcome::WillRetry {
+                        next_attempt,
+                        delay,
+                    } => tracing::info!(
+                        delivery_id = %task.workflow_id,
+                        attempt = task.attempt,
+                        next_attempt,
+                        delay_secs = delay.as_secs(),
+                        error = %error,
+                        "Webhook delivery failed, will retry"
+                    ),
+                    TaskFailureOutcome::MovedToDlq | TaskFailureOutcome::ExhaustedRetries => {
+                        tracing::warn!(
+                            delivery_id = %task.workflow_id,
+                            attempts = task.attempt,
+                            error = %error,
+                            "Webhook delivery failed, moved to dead letter queue"
+                        );
+                        self.store.move_to_dlq(task.id, vec![error.clone()]).await?;
+                        self.store
+                            .update_workflow_status(
+                                task.workflow_id,
+                                WorkflowStatus::Failed,
+                                None,
+                                Some(WorkflowError::new(error)),
+                            )
+                            .await?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// POST the signed event; any non-2xx response is an error
+    async fn deliver(&self, task: &ClaimedTask) -> Result<serde_json::Value> {
+        let webhook_id: Uuid = serde_json::from_value(task.input["webhook_id"].clone())?;
+        let event = &task.input["event"];
+
+        let webhook = match self.db.get_webhook(webhook_id).await? {
+            Some(webhook) if webhook.enabled => webhook,
+            Some(_) => return Ok(json!({ "skipped": "Webhook disabled" })),
+            None => return Ok(json!({ "skipped": "Webhook deleted" })),
+        };
+        let secret = self.webhooks.decrypt_secret(&webhook)?;
+        // Subscriptions created before the URL check are refused here
+        if !self.allow_private_networks {
+            let url = reqwest::Url::parse(&webhook.url)?;
+            if let Some(ip) = literal_private_host(&url) {
+                return Err(anyhow!("Private network address {} is not allowed", ip));
+            }
+        }
+
+        let body = serde_json::to_vec(event)?;
+        let signature = sign_payload(&secret, Utc::now().timestamp(), &body);
+        let response = self
+            .client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(EVENT_HEADER, event["type"].as_str().unwrap_or_default())
+            .header(DELIVERY_HEADER, task.workflow_id.to_string())
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| {
+                if private_target_refused(&e) {
+                    anyhow!("URL resolves to a private network address")
+                } else {
+                    anyhow!("Request failed: {}", e)
+                }
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Endpoint responded with HTTP {}", status.as_u16()));
+        }
+        Ok(json!({ "status_code": status.as_u16() }))
+    }
+}
+
+/// HTTP client for deliveries
+fn delivery_client(allow_private_networks: bool) -> Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("everruns-webhooks/", env!("CARGO_PKG_VERSION")));
+    let builder = if allow_private_networks {
+        builder
+    } else {
+        guard_client(builder, 0)
+    };
+    // Set after the guard, which would otherwise follow redirects
+    Ok(builder
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_delivery_client_refuses_loopback() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://localhost:{}/hooks",
+            listener.local_addr().unwrap().port()
+        );
+
+        let err = delivery_client(false)
+            .unwrap()
+            .post(&url)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(private_target_refused(&err));
+    }
+}
@@ -12,6 +12,7 @@ use everruns_core::{
 };
 use uuid::Uuid;
 
+use super::models::AgentCapabilityRow;
 use super::repositories::Database;
 
 // ============================================================================
@@ -51,6 +52,7 @@ impl AgentStore for DbAgentStore {
                     .await
                     .map_err(|e| AgentLoopError::store(e.to_string()))?;
 
+                let capability_config = AgentCapabilityRow::configs(&capability_rows);
                 let capabilities: Vec<CapabilityId> = capability_rows
                     .into_iter()
                     .map(|c| CapabilityId::new(c.capability_id))
@@ -64,7 +66,17 @@ impl AgentStore for DbAgentStore {
                     default_model_id: row.default_model_id,
                     tags: row.tags,
                     capabilities,
+                    capability_config,
+                    response_format: row
+                        .response_format
+                        .map(serde_json::from_value)
+                        .transpose()
+                        .map_err(|e| AgentLoopError::store(e.to_string()))?,
+                    parallel_tool_calls: row.parallel_tool_calls,
+                    mcp_servers: serde_json::from_value(row.mcp_servers)
+                        .map_err(|e| AgentLoopError::store(e.to_string()))?,
                     status: AgentStatus::from(row.status.as_str()),
+                    revision: row.revision,
                     created_at: row.created_at,
                     updated_at: row.updated_at,
                 }))
@@ -72,6 +84,57 @@ impl AgentStore for DbAgentStore {
             None => Ok(None),
         }
     }
+
+    async fn get_agent_revision(&self, agent_id: Uuid, revision: i32) -> Result<Option<Agent>> {
+        let Some(agent_row) = self
+            .db
+            .get_agent(agent_id)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        let revision_row = self
+            .db
+            .get_agent_revision(agent_id, revision)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+
+        match revision_row {
+            Some(row) => {
+                let capability_config = row.configs();
+                let capabilities: Vec<CapabilityId> = row
+                    .capability_list()
+                    .into_iter()
+                    .map(|c| CapabilityId::new(c.capability_id))
+                    .collect();
+
+                Ok(Some(Agent {
+                    id: row.agent_id,
+                    name: row.name,
+                    description: row.description,
+                    system_prompt: row.system_prompt,
+                    default_model_id: row.default_model_id,
+                    tags: row.tags,
+                    capabilities,
+                    capability_config,
+                    response_format: row
+                        .response_format
+                        .map(serde_json::from_value)
+                        .transpose()
+                        .map_err(|e| AgentLoopError::store(e.to_string()))?,
+                    parallel_tool_calls: row.parallel_tool_calls,
+                    mcp_servers: serde_json::from_value(row.mcp_servers)
+                        .map_err(|e| AgentLoopError::store(e.to_string()))?,
+                    status: AgentStatus::from(agent_row.status.as_str()),
+                    revision: row.revision,
+                    created_at: agent_row.created_at,
+                    updated_at: row.created_at,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
 }
 
 // ============================================================================
@@ -330,6 +330,30 @@ pub const ENCRYPTED_COLUMNS: &[EncryptedColumn] = &[
         column: "api_key_encrypted",
         id_column: "id",
     },
+    // Agent MCP server credentials (JSON map) are encrypted at rest
+    EncryptedColumn {
+        table: "agents",
+        column: "mcp_credentials_encrypted",
+        id_column: "id",
+    },
+    // Agent revision snapshots carry a copy of the MCP credentials
+    EncryptedColumn {
+        table: "agent_revisions",
+        column: "mcp_credentials_encrypted",
+        id_column: "id",
+    },
+    // User-defined HTTP tool auth secrets are encrypted at rest
+    EncryptedColumn {
+        table: "http_tools",
+        column: "secret_encrypted",
+        id_column: "id",
+    },
+    // Webhook signing secrets are encrypted at rest
+    EncryptedColumn {
+        table: "webhook_subscriptions",
+        column: "secret_encrypted",
+        id_column: "id",
+    },
 ];
 
 #[cfg(test)]
@@ -546,7 +570,8 @@ mod tests {
     }
 
     /// Simple parser to extract encrypted columns from SQL.
-    /// Looks for column definitions ending with `_encrypted` in CREATE TABLE statements.
+    /// Looks for column definitions ending with `_encrypted` in CREATE TABLE
+    /// and single-line ALTER TABLE ... ADD COLUMN statements.
     fn parse_encrypted_columns(sql: &str, found: &mut HashSet<(String, String)>) {
         let mut current_table: Option<String> = None;
         let mut in_create_table = false;
@@ -556,6 +581,17 @@ mod tests {
             let line_lower = line.to_lowercase();
             let trimmed = line_lower.trim();
 
+            // Detect ALTER TABLE <table> ADD COLUMN <column> ...
+            if trimmed.starts_with("alter table") {
+                let parts: Vec<&str> = trimmed.split_whitespace().collect();
+                if let [_, _, table, "add", "column", column, ..] = parts.as_slice() {
+                    if column.ends_with("_encrypted") {
+                        found.insert((table.to_string(), column.to_string()));
+                    }
+                }
+                continue;
+            }
+
             // Detect CREATE TABLE
             if trimmed.starts_with("create table") {
                 in_create_table = true;
@@ -76,6 +76,7 @@ impl LlmProviderStore for DbLlmProviderStore {
             provider_type,
             api_key: provider_with_key.api_key,
             base_url: provider_with_key.base_url,
+            provider_id: Some(provider_row.id),
         }))
     }
 
@@ -118,6 +119,7 @@ impl LlmProviderStore for DbLlmProviderStore {
             provider_type,
             api_key: provider_with_key.api_key,
             base_url: provider_with_key.base_url,
+            provider_id: Some(provider_row.id),
         }))
     }
 }
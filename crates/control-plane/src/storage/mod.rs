@@ -6,12 +6,14 @@
 // - DbMessageStore: implements MessageStore for message persistence
 // - DbSessionFileStore: implements SessionFileStore for session filesystem
 // - DbLlmProviderStore: implements LlmProviderStore for LLM provider retrieval
+// - FileContentStore: places session file content inline or in object storage
 
 pub mod agent_store;
 pub mod encryption;
 pub mod llm_provider_store;
 pub mod message_store;
 pub mod models;
+pub mod object_store;
 pub mod password;
 pub mod repositories;
 pub mod session_file_store;
@@ -28,6 +30,9 @@ pub use encryption::{
 pub use llm_provider_store::{create_db_llm_provider_store, DbLlmProviderStore};
 pub use message_store::{create_db_message_store, DbMessageStore};
 pub use models::*;
+pub use object_store::{
+    ByteRange, ByteStream, FileContentStore, LocalObjectStore, ObjectStore, S3Config, S3ObjectStore,
+};
 pub use repositories::*;
 pub use session_file_store::{create_db_session_file_store, DbSessionFileStore};
 pub use session_store::{create_db_session_store, DbSessionStore};
@@ -1,7 +1,9 @@
 // Database models (internal, may differ from public DTOs)
 
 use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use sqlx::FromRow;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 // ============================================
@@ -120,7 +122,13 @@ pub struct AgentRow {
     pub system_prompt: String,
     pub default_model_id: Option<Uuid>,
     pub tags: Vec<String>,
+    pub response_format: Option<serde_json::Value>,
+    pub parallel_tool_calls: Option<bool>,
+    pub mcp_servers: serde_json::Value,
+    pub mcp_credentials_encrypted: Option<Vec<u8>>,
     pub status: String,
+    /// Current configuration revision
+    pub revision: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -132,6 +140,10 @@ pub struct CreateAgentRow {
     pub system_prompt: String,
     pub default_model_id: Option<Uuid>,
     pub tags: Vec<String>,
+    pub response_format: Option<serde_json::Value>,
+    pub parallel_tool_calls: Option<bool>,
+    pub mcp_servers: serde_json::Value,
+    pub mcp_credentials_encrypted: Option<Vec<u8>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -141,9 +153,59 @@ pub struct UpdateAgent {
     pub system_prompt: Option<String>,
     pub default_model_id: Option<Uuid>,
     pub tags: Option<Vec<String>>,
+    /// `Some(None)` clears the response format
+    pub response_format: Option<Option<serde_json::Value>>,
+    pub parallel_tool_calls: Option<bool>,
+    /// When set, credentials are replaced together with the servers
+    pub mcp_servers: Option<serde_json::Value>,
+    pub mcp_credentials_encrypted: Option<Vec<u8>>,
     pub status: Option<String>,
 }
 
+/// Immutable snapshot of an agent's configuration
+#[derive(Debug, Clone, FromRow)]
+pub struct AgentRevisionRow {
+    pub id: Uuid,
+    pub agent_id: Uuid,
+    pub revision: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub system_prompt: String,
+    pub default_model_id: Option<Uuid>,
+    pub tags: Vec<String>,
+    pub response_format: Option<serde_json::Value>,
+    pub parallel_tool_calls: Option<bool>,
+    pub mcp_servers: serde_json::Value,
+    pub mcp_credentials_encrypted: Option<Vec<u8>>,
+    /// Ordered capability chain: `[{"capability_id": ..., "config": ...}]`
+    pub capabilities: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One entry of a revision's capability chain
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgentRevisionCapability {
+    pub capability_id: String,
+    #[serde(default)]
+    pub config: serde_json::Value,
+}
+
+impl AgentRevisionRow {
+    /// The revision's capability chain, in order
+    pub fn capability_list(&self) -> Vec<AgentRevisionCapability> {
+        serde_json::from_value(self.capabilities.clone()).unwrap_or_default()
+    }
+
+    /// Collect the non-empty capability configs of the revision
+    pub fn configs(&self) -> BTreeMap<String, serde_json::Value> {
+        self.capability_list()
+            .into_iter()
+            .filter(|c| c.config.as_object().is_none_or(|o| !o.is_empty()))
+            .map(|c| (c.capability_id, c.config))
+            .collect()
+    }
+}
+
 // ============================================
 // Session models (instance of agentic loop)
 // ============================================
@@ -159,6 +221,10 @@ pub struct SessionRow {
     pub created_at: DateTime<Utc>,
     pub started_at: Option<DateTime<Utc>>,
     pub finished_at: Option<DateTime<Utc>>,
+    pub parent_session_id: Option<Uuid>,
+    pub forked_at_event_id: Option<Uuid>,
+    pub agent_revision: Option<i32>,
+    pub variables: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -167,6 +233,37 @@ pub struct CreateSessionRow {
     pub title: Option<String>,
     pub tags: Vec<String>,
     pub model_id: Option<Uuid>,
+    /// Pinned agent revision (None follows the latest revision)
+    pub agent_revision: Option<i32>,
+    /// System prompt template variables (JSON object of strings)
+    pub variables: serde_json::Value,
+}
+
+/// Input for forking a session
+#[derive(Debug, Clone)]
+pub struct ForkSessionRow {
+    /// ID of the new session, chosen up front so copied objects can be keyed under it
+    pub id: Uuid,
+    pub parent_session_id: Uuid,
+    pub agent_id: Uuid,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub model_id: Option<Uuid>,
+    pub agent_revision: Option<i32>,
+    pub variables: serde_json::Value,
+    /// Last parent event to copy, with its sequence (None copies no events)
+    pub forked_at_event: Option<(Uuid, i32)>,
+    /// Files to copy (None copies no files)
+    pub files: Option<ForkFilesRow>,
+}
+
+/// Files copied into a fork
+#[derive(Debug, Clone, Default)]
+pub struct ForkFilesRow {
+    /// Copy the tree as of this snapshot instead of the current tree
+    pub snapshot_id: Option<Uuid>,
+    /// Object storage keys to rewrite: (parent key, the fork's copy)
+    pub storage_keys: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -225,6 +322,8 @@ pub struct LlmProviderRow {
     pub api_key_set: bool,
     pub status: String,
     pub settings: sqlx::types::JsonValue,
+    pub requests_per_minute: Option<i32>,
+    pub tokens_per_minute: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -277,6 +376,8 @@ pub struct CreateLlmProviderRow {
     pub base_url: Option<String>,
     pub api_key_encrypted: Option<Vec<u8>>,
     pub settings: Option<serde_json::Value>,
+    pub requests_per_minute: Option<i32>,
+    pub tokens_per_minute: Option<i32>,
 }
 
 #[derive(Debug, Clone)]
@@ -287,6 +388,8 @@ pub struct UpdateLlmProvider {
     pub api_key_encrypted: Option<Vec<u8>>,
     pub status: Option<String>,
     pub settings: Option<serde_json::Value>,
+    pub requests_per_minute: Option<i32>,
+    pub tokens_per_minute: Option<i32>,
 }
 
 #[derive(Debug, Clone)]
@@ -317,14 +420,171 @@ pub struct AgentCapabilityRow {
     pub agent_id: Uuid,
     pub capability_id: String,
     pub position: i32,
+    /// Per-agent capability config (`{}` when not configured)
+    pub config: serde_json::Value,
     pub created_at: DateTime<Utc>,
 }
 
+impl AgentCapabilityRow {
+    /// Collect the non-empty configs of an agent's capability rows
+    pub fn configs(rows: &[AgentCapabilityRow]) -> BTreeMap<String, serde_json::Value> {
+        rows.iter()
+            .filter(|row| row.config.as_object().is_none_or(|o| !o.is_empty()))
+            .map(|row| (row.capability_id.clone(), row.config.clone()))
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CreateAgentCapabilityRow {
     pub agent_id: Uuid,
     pub capability_id: String,
     pub position: i32,
+    pub config: serde_json::Value,
+}
+
+// ============================================
+// HTTP Tool models (user-defined tools)
+// ============================================
+
+#[derive(Debug, Clone, FromRow)]
+pub struct HttpToolRow {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+    pub endpoint: serde_json::Value,
+    pub secret_encrypted: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateHttpToolRow {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+    pub endpoint: serde_json::Value,
+    pub secret_encrypted: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateHttpTool {
+    pub description: Option<String>,
+    pub parameters: Option<serde_json::Value>,
+    pub endpoint: Option<serde_json::Value>,
+    pub secret_encrypted: Option<Vec<u8>>,
+}
+
+// ============================================
+// Agent schedule models
+// ============================================
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ScheduleRow {
+    pub id: Uuid,
+    pub agent_id: Uuid,
+    pub name: String,
+    pub cron: String,
+    pub timezone: String,
+    pub message: String,
+    pub session_id: Option<Uuid>,
+    pub session_variables: serde_json::Value,
+    pub missed_run_policy: String,
+    pub overlap_policy: String,
+    pub enabled: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateScheduleRow {
+    pub agent_id: Uuid,
+    pub name: String,
+    pub cron: String,
+    pub timezone: String,
+    pub message: String,
+    pub session_id: Option<Uuid>,
+    pub session_variables: serde_json::Value,
+    pub missed_run_policy: String,
+    pub overlap_policy: String,
+    pub enabled: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+/// Partial schedule update. `next_run_at` is recomputed by the service
+/// whenever the timing or the enabled flag changes.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSchedule {
+    pub name: Option<String>,
+    pub cron: Option<String>,
+    pub timezone: Option<String>,
+    pub message: Option<String>,
+    pub session_variables: Option<serde_json::Value>,
+    pub missed_run_policy: Option<String>,
+    pub overlap_policy: Option<String>,
+    pub enabled: Option<bool>,
+    pub next_run_at: Option<Option<DateTime<Utc>>>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ScheduleRunRow {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+    pub status: String,
+    pub session_id: Option<Uuid>,
+    pub message_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Runs to record for a due schedule and where its next occurrence falls
+#[derive(Debug, Clone, Default)]
+pub struct SchedulePlan {
+    /// Occurrences to run, oldest first
+    pub runs: Vec<DateTime<Utc>>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+// ============================================
+// Webhook subscription models
+// ============================================
+
+#[derive(Debug, Clone, FromRow)]
+pub struct WebhookRow {
+    pub id: Uuid,
+    pub agent_id: Option<Uuid>,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+    pub secret_encrypted: Vec<u8>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateWebhookRow {
+    pub agent_id: Option<Uuid>,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+    pub secret_encrypted: Vec<u8>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub description: Option<String>,
+    pub secret_encrypted: Option<Vec<u8>>,
+    pub enabled: Option<bool>,
 }
 
 // ============================================
@@ -337,7 +597,10 @@ pub struct SessionFileRow {
     pub id: Uuid,
     pub session_id: Uuid,
     pub path: String,
+    /// Inline content (None for directories and object-stored files)
     pub content: Option<Vec<u8>>,
+    /// Object storage key when content lives outside Postgres
+    pub storage_key: Option<String>,
     pub is_directory: bool,
     pub is_readonly: bool,
     pub size_bytes: i64,
@@ -345,12 +608,45 @@ pub struct SessionFileRow {
     pub updated_at: DateTime<Utc>,
 }
 
+/// File content as persisted: inline bytes or a reference into object storage
+#[derive(Debug, Clone)]
+pub enum StoredContent {
+    Inline(Vec<u8>),
+    External {
+        storage_key: String,
+        size_bytes: i64,
+    },
+}
+
+impl StoredContent {
+    pub fn size_bytes(&self) -> i64 {
+        match self {
+            Self::Inline(bytes) => bytes.len() as i64,
+            Self::External { size_bytes, .. } => *size_bytes,
+        }
+    }
+
+    pub fn inline(&self) -> Option<&[u8]> {
+        match self {
+            Self::Inline(bytes) => Some(bytes),
+            Self::External { .. } => None,
+        }
+    }
+
+    pub fn storage_key(&self) -> Option<&str> {
+        match self {
+            Self::Inline(_) => None,
+            Self::External { storage_key, .. } => Some(storage_key),
+        }
+    }
+}
+
 /// Input for creating a session file
 #[derive(Debug, Clone)]
 pub struct CreateSessionFileRow {
     pub session_id: Uuid,
     pub path: String,
-    pub content: Option<Vec<u8>>,
+    pub content: Option<StoredContent>,
     pub is_directory: bool,
     pub is_readonly: bool,
 }
@@ -358,10 +654,22 @@ pub struct CreateSessionFileRow {
 /// Input for updating a session file
 #[derive(Debug, Clone, Default)]
 pub struct UpdateSessionFile {
-    pub content: Option<Vec<u8>>,
+    pub content: Option<StoredContent>,
     pub is_readonly: Option<bool>,
 }
 
+/// A single change in an atomic multi-file update
+#[derive(Debug, Clone)]
+pub enum SessionFileChangeRow {
+    /// Create the file or replace its content
+    Write {
+        path: String,
+        content: StoredContent,
+    },
+    /// Delete the file
+    Delete { path: String },
+}
+
 /// Lightweight file info for listing (without content)
 #[derive(Debug, Clone, FromRow)]
 pub struct SessionFileInfoRow {
@@ -374,3 +682,71 @@ pub struct SessionFileInfoRow {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// Session file version row (recorded by trigger on every content change)
+#[derive(Debug, Clone, FromRow)]
+pub struct SessionFileVersionRow {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub path: String,
+    pub version: i32,
+    /// Inline content (None for tombstones and object-stored versions)
+    pub content: Option<Vec<u8>>,
+    /// Object storage key, shared with the live row that wrote it
+    pub storage_key: Option<String>,
+    pub size_bytes: i64,
+    pub is_deleted: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SessionFileVersionRow {
+    /// Content in the form accepted by file writes (None for tombstones)
+    pub fn stored_content(&self) -> Option<StoredContent> {
+        if self.is_deleted {
+            return None;
+        }
+        Some(match &self.storage_key {
+            Some(key) => StoredContent::External {
+                storage_key: key.clone(),
+                size_bytes: self.size_bytes,
+            },
+            None => StoredContent::Inline(self.content.clone().unwrap_or_default()),
+        })
+    }
+}
+
+/// Lightweight version info for listing (without content)
+#[derive(Debug, Clone, FromRow)]
+pub struct SessionFileVersionInfoRow {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub path: String,
+    pub version: i32,
+    pub size_bytes: i64,
+    pub is_deleted: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Session snapshot row
+#[derive(Debug, Clone, FromRow)]
+pub struct SessionSnapshotRow {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub name: String,
+    /// manual, turn_start or turn_end
+    pub kind: String,
+    pub turn_id: Option<Uuid>,
+    pub turn_number: Option<i32>,
+    pub file_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Input for creating a session snapshot
+#[derive(Debug, Clone)]
+pub struct CreateSessionSnapshotRow {
+    pub session_id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub turn_id: Option<Uuid>,
+    pub turn_number: Option<i32>,
+}
@@ -1,7 +1,8 @@
 // Repository layer for database operations
 // M2 Revised: Agent/Session/Messages/Events model
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -12,6 +13,22 @@ pub struct Database {
     pool: PgPool,
 }
 
+/// File versions past retention: not the latest version of their path, not
+/// captured by a snapshot, and created before `$1` or beyond the newest `$2`
+/// versions of their path (either bound may be NULL)
+const EXPIRED_FILE_VERSIONS_SQL: &str = r#"
+    SELECT id FROM (
+        SELECT v.id, v.created_at,
+               ROW_NUMBER() OVER (PARTITION BY v.session_id, v.path ORDER BY v.version DESC) AS rank
+        FROM session_file_versions v
+    ) ranked
+    WHERE rank > 1
+      AND (created_at < $1 OR rank > $2)
+      AND NOT EXISTS (
+          SELECT 1 FROM session_snapshot_entries e WHERE e.version_id = ranked.id
+      )
+"#;
+
 impl Database {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
@@ -319,9 +336,9 @@ impl Database {
     pub async fn create_agent(&self, input: CreateAgentRow) -> Result<AgentRow> {
         let row = sqlx::query_as::<_, AgentRow>(
             r#"
-            INSERT INTO agents (name, description, system_prompt, default_model_id, tags, status)
-            VALUES ($1, $2, $3, $4, $5, 'active')
-            RETURNING id, name, description, system_prompt, default_model_id, tags, status, created_at, updated_at
+            INSERT INTO agents (name, description, system_prompt, default_model_id, tags, response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, status)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 'active')
+            RETURNING id, name, description, system_prompt, default_model_id, tags, response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, status, revision, created_at, updated_at
             "#,
         )
         .bind(&input.name)
@@ -329,6 +346,10 @@ impl Database {
         .bind(&input.system_prompt)
         .bind(input.default_model_id)
         .bind(&input.tags)
+        .bind(&input.response_format)
+        .bind(input.parallel_tool_calls)
+        .bind(&input.mcp_servers)
+        .bind(&input.mcp_credentials_encrypted)
         .fetch_one(&self.pool)
         .await?;
 
@@ -338,7 +359,7 @@ impl Database {
     pub async fn get_agent(&self, id: Uuid) -> Result<Option<AgentRow>> {
         let row = sqlx::query_as::<_, AgentRow>(
             r#"
-            SELECT id, name, description, system_prompt, default_model_id, tags, status, created_at, updated_at
+            SELECT id, name, description, system_prompt, default_model_id, tags, response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, status, revision, created_at, updated_at
             FROM agents
             WHERE id = $1
             "#,
@@ -353,7 +374,7 @@ impl Database {
     pub async fn list_agents(&self) -> Result<Vec<AgentRow>> {
         let rows = sqlx::query_as::<_, AgentRow>(
             r#"
-            SELECT id, name, description, system_prompt, default_model_id, tags, status, created_at, updated_at
+            SELECT id, name, description, system_prompt, default_model_id, tags, response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, status, revision, created_at, updated_at
             FROM agents
             WHERE status = 'active'
             ORDER BY created_at DESC
@@ -365,7 +386,23 @@ impl Database {
         Ok(rows)
     }
 
-    pub async fn update_agent(&self, id: Uuid, input: UpdateAgent) -> Result<Option<AgentRow>> {
+    /// Update the agent and, when given, replace its capabilities; with
+    /// `record_revision` the result is snapshotted as a new revision. All
+    /// writes happen in one transaction.
+    pub async fn update_agent(
+        &self,
+        id: Uuid,
+        input: UpdateAgent,
+        capabilities: Option<Vec<(String, i32, serde_json::Value)>>,
+        record_revision: bool,
+    ) -> Result<Option<AgentRow>> {
+        let (set_response_format, response_format) = match input.response_format {
+            Some(response_format) => (true, response_format),
+            None => (false, None),
+        };
+
+        let mut tx = self.pool.begin().await?;
+
         let row = sqlx::query_as::<_, AgentRow>(
             r#"
             UPDATE agents
@@ -376,9 +413,13 @@ impl Database {
                 default_model_id = COALESCE($5, default_model_id),
                 tags = COALESCE($6, tags),
                 status = COALESCE($7, status),
+                response_format = CASE WHEN $12 THEN $8 ELSE response_format END,
+                parallel_tool_calls = COALESCE($9, parallel_tool_calls),
+                mcp_servers = COALESCE($10, mcp_servers),
+                mcp_credentials_encrypted = CASE WHEN $10 IS NULL THEN mcp_credentials_encrypted ELSE $11 END,
                 updated_at = NOW()
             WHERE id = $1
-            RETURNING id, name, description, system_prompt, default_model_id, tags, status, created_at, updated_at
+            RETURNING id, name, description, system_prompt, default_model_id, tags, response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, status, revision, created_at, updated_at
             "#,
         )
         .bind(id)
@@ -388,10 +429,28 @@ impl Database {
         .bind(input.default_model_id)
         .bind(&input.tags)
         .bind(&input.status)
-        .fetch_optional(&self.pool)
+        .bind(&response_format)
+        .bind(input.parallel_tool_calls)
+        .bind(&input.mcp_servers)
+        .bind(&input.mcp_credentials_encrypted)
+        .bind(set_response_format)
+        .fetch_optional(&mut *tx)
         .await?;
+        let Some(mut row) = row else {
+            return Ok(None);
+        };
 
-        Ok(row)
+        if let Some(capabilities) = &capabilities {
+            replace_agent_capabilities(&mut tx, id, capabilities).await?;
+        }
+        if record_revision {
+            if let Some(revision) = insert_agent_revision(&mut tx, id, true).await? {
+                row.revision = revision.revision;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(Some(row))
     }
 
     pub async fn delete_agent(&self, id: Uuid) -> Result<bool> {
@@ -410,6 +469,128 @@ impl Database {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Snapshot the agent's current configuration as a revision.
+    ///
+    /// With `bump` the agent moves to the next revision number first; without it
+    /// the current number is recorded (used right after the agent is created).
+    pub async fn record_agent_revision(
+        &self,
+        agent_id: Uuid,
+        bump: bool,
+    ) -> Result<Option<AgentRevisionRow>> {
+        let mut conn = self.pool.acquire().await?;
+        insert_agent_revision(&mut conn, agent_id, bump).await
+    }
+
+    /// Revisions of an agent, newest first
+    pub async fn list_agent_revisions(&self, agent_id: Uuid) -> Result<Vec<AgentRevisionRow>> {
+        let rows = sqlx::query_as::<_, AgentRevisionRow>(
+            r#"
+            SELECT id, agent_id, revision, name, description, system_prompt, default_model_id, tags,
+                response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted,
+                capabilities, created_at
+            FROM agent_revisions
+            WHERE agent_id = $1
+            ORDER BY revision DESC
+            "#,
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn get_agent_revision(
+        &self,
+        agent_id: Uuid,
+        revision: i32,
+    ) -> Result<Option<AgentRevisionRow>> {
+        let row = sqlx::query_as::<_, AgentRevisionRow>(
+            r#"
+            SELECT id, agent_id, revision, name, description, system_prompt, default_model_id, tags,
+                response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted,
+                capabilities, created_at
+            FROM agent_revisions
+            WHERE agent_id = $1 AND revision = $2
+            "#,
+        )
+        .bind(agent_id)
+        .bind(revision)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Restore the agent's configuration and capabilities from a revision,
+    /// recording the result as a new revision
+    pub async fn restore_agent_revision(
+        &self,
+        agent_id: Uuid,
+        revision: i32,
+    ) -> Result<Option<AgentRow>> {
+        let Some(snapshot) = self.get_agent_revision(agent_id, revision).await? else {
+            return Ok(None);
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE agents
+            SET
+                name = $2,
+                description = $3,
+                system_prompt = $4,
+                default_model_id = $5,
+                tags = $6,
+                response_format = $7,
+                parallel_tool_calls = $8,
+                mcp_servers = $9,
+                mcp_credentials_encrypted = $10,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(agent_id)
+        .bind(&snapshot.name)
+        .bind(&snapshot.description)
+        .bind(&snapshot.system_prompt)
+        .bind(snapshot.default_model_id)
+        .bind(&snapshot.tags)
+        .bind(&snapshot.response_format)
+        .bind(snapshot.parallel_tool_calls)
+        .bind(&snapshot.mcp_servers)
+        .bind(&snapshot.mcp_credentials_encrypted)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM agent_capabilities WHERE agent_id = $1")
+            .bind(agent_id)
+            .execute(&mut *tx)
+            .await?;
+        for (position, capability) in snapshot.capability_list().into_iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO agent_capabilities (agent_id, capability_id, position, config)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(agent_id)
+            .bind(&capability.capability_id)
+            .bind(position as i32)
+            .bind(&capability.config)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        insert_agent_revision(&mut tx, agent_id, true).await?;
+        tx.commit().await?;
+
+        self.get_agent(agent_id).await
+    }
+
     // ============================================
     // Sessions (instance of agentic loop)
     // ============================================
@@ -417,15 +598,18 @@ impl Database {
     pub async fn create_session(&self, input: CreateSessionRow) -> Result<SessionRow> {
         let row = sqlx::query_as::<_, SessionRow>(
             r#"
-            INSERT INTO sessions (agent_id, title, tags, model_id, status)
-            VALUES ($1, $2, $3, $4, 'started')
-            RETURNING id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at
+            INSERT INTO sessions (agent_id, title, tags, model_id, agent_revision, variables, status)
+            VALUES ($1, $2, $3, $4, $5, $6, 'started')
+            RETURNING id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at,
+                parent_session_id, forked_at_event_id, agent_revision, variables
             "#,
         )
         .bind(input.agent_id)
         .bind(&input.title)
         .bind(&input.tags)
         .bind(input.model_id)
+        .bind(input.agent_revision)
+        .bind(&input.variables)
         .fetch_one(&self.pool)
         .await?;
 
@@ -435,7 +619,8 @@ impl Database {
     pub async fn get_session(&self, id: Uuid) -> Result<Option<SessionRow>> {
         let row = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at
+            SELECT id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at,
+                parent_session_id, forked_at_event_id, agent_revision, variables
             FROM sessions
             WHERE id = $1
             "#,
@@ -450,7 +635,8 @@ impl Database {
     pub async fn list_sessions(&self, agent_id: Uuid) -> Result<Vec<SessionRow>> {
         let rows = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at
+            SELECT id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at,
+                parent_session_id, forked_at_event_id, agent_revision, variables
             FROM sessions
             WHERE agent_id = $1
             ORDER BY created_at DESC
@@ -479,7 +665,8 @@ impl Database {
                 started_at = COALESCE($6, started_at),
                 finished_at = COALESCE($7, finished_at)
             WHERE id = $1
-            RETURNING id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at
+            RETURNING id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at,
+                parent_session_id, forked_at_event_id, agent_revision, variables
             "#,
         )
         .bind(id)
@@ -504,6 +691,216 @@ impl Database {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Create a session from a copy of another session's history
+    ///
+    /// Events up to and including `forked_at_event` keep their sequence
+    /// numbers and timestamps but get new IDs. Files come from the parent's
+    /// current tree or a snapshot; object-stored content must already be
+    /// duplicated, and `storage_keys` maps each parent key to its copy.
+    pub async fn fork_session(&self, input: ForkSessionRow) -> Result<SessionRow> {
+        let mut tx = self.pool.begin().await?;
+
+        let (forked_at_event_id, last_sequence) = input.forked_at_event.unzip();
+        let status = if forked_at_event_id.is_some() {
+            "idle"
+        } else {
+            "started"
+        };
+
+        let row = sqlx::query_as::<_, SessionRow>(
+            r#"
+            INSERT INTO sessions (id, agent_id, title, tags, model_id, status, parent_session_id, forked_at_event_id, agent_revision, variables)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at,
+                parent_session_id, forked_at_event_id, agent_revision, variables
+            "#,
+        )
+        .bind(input.id)
+        .bind(input.agent_id)
+        .bind(&input.title)
+        .bind(&input.tags)
+        .bind(input.model_id)
+        .bind(status)
+        .bind(input.parent_session_id)
+        .bind(forked_at_event_id)
+        .bind(input.agent_revision)
+        .bind(&input.variables)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(last_sequence) = last_sequence {
+            sqlx::query(
+                r#"
+                INSERT INTO events (session_id, sequence, event_type, ts, context, data, metadata, tags, created_at, superseded_at)
+                SELECT $1, sequence, event_type, ts, context, data, metadata, tags, created_at, superseded_at
+                FROM events
+                WHERE session_id = $2 AND sequence <= $3
+                ORDER BY sequence
+                "#,
+            )
+            .bind(input.id)
+            .bind(input.parent_session_id)
+            .bind(last_sequence)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if let Some(files) = input.files {
+            let (old_keys, new_keys): (Vec<String>, Vec<String>) =
+                files.storage_keys.into_iter().unzip();
+
+            let query = match files.snapshot_id {
+                Some(_) => {
+                    r#"
+                    INSERT INTO session_files (session_id, path, content, storage_key, is_directory, is_readonly, size_bytes)
+                    SELECT $1, e.path, v.content, k.new_key, e.is_directory, e.is_readonly, COALESCE(v.size_bytes, 0)
+                    FROM session_snapshot_entries e
+                    LEFT JOIN session_file_versions v ON v.id = e.version_id
+                    LEFT JOIN UNNEST($3::text[], $4::text[]) AS k(old_key, new_key) ON k.old_key = v.storage_key
+                    WHERE e.snapshot_id = $2
+                    ORDER BY e.path
+                    "#
+                }
+                None => {
+                    r#"
+                    INSERT INTO session_files (session_id, path, content, storage_key, is_directory, is_readonly, size_bytes)
+                    SELECT $1, f.path, f.content, k.new_key, f.is_directory, f.is_readonly, f.size_bytes
+                    FROM session_files f
+                    LEFT JOIN UNNEST($3::text[], $4::text[]) AS k(old_key, new_key) ON k.old_key = f.storage_key
+                    WHERE f.session_id = $2
+                    ORDER BY f.path
+                    "#
+                }
+            };
+
+            sqlx::query(query)
+                .bind(input.id)
+                .bind(files.snapshot_id.unwrap_or(input.parent_session_id))
+                .bind(&old_keys)
+                .bind(&new_keys)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(row)
+    }
+
+    /// Find the last event of a session to fork at
+    ///
+    /// With a turn ID this is the turn's last event, with a message ID the
+    /// message event itself, and otherwise the session's latest event.
+    pub async fn find_session_fork_event(
+        &self,
+        session_id: Uuid,
+        turn_id: Option<Uuid>,
+        message_id: Option<Uuid>,
+    ) -> Result<Option<EventRow>> {
+        let row = sqlx::query_as::<_, EventRow>(
+            r#"
+            SELECT id, session_id, sequence, event_type, ts, context, data, metadata, tags, created_at
+            FROM events
+            WHERE session_id = $1
+                AND superseded_at IS NULL
+                AND ($2::uuid IS NULL
+                    OR context->>'turn_id' = $2::text
+                    OR data->>'turn_id' = $2::text)
+                AND ($3::uuid IS NULL
+                    OR (event_type LIKE 'message.%' AND data->'message'->>'id' = $3::text))
+            ORDER BY sequence DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(turn_id)
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Turn of the latest turn-scoped event at or before a sequence number
+    pub async fn find_session_turn_at(
+        &self,
+        session_id: Uuid,
+        sequence: i32,
+    ) -> Result<Option<Uuid>> {
+        let turn_id: Option<(Uuid,)> = sqlx::query_as(
+            r#"
+            SELECT COALESCE(context->>'turn_id', data->>'turn_id')::uuid
+            FROM events
+            WHERE session_id = $1
+                AND sequence <= $2
+                AND superseded_at IS NULL
+                AND (context ? 'turn_id' OR data ? 'turn_id')
+            ORDER BY sequence DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(sequence)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(turn_id.map(|(id,)| id))
+    }
+
+    /// Object storage keys a fork would copy from the current tree or a snapshot
+    pub async fn list_session_fork_storage_keys(
+        &self,
+        session_id: Uuid,
+        snapshot_id: Option<Uuid>,
+    ) -> Result<Vec<String>> {
+        let keys: Vec<(String,)> = match snapshot_id {
+            Some(snapshot_id) => {
+                sqlx::query_as(
+                    r#"
+                    SELECT DISTINCT v.storage_key
+                    FROM session_snapshot_entries e
+                    JOIN session_file_versions v ON v.id = e.version_id
+                    WHERE e.snapshot_id = $1 AND v.storage_key IS NOT NULL
+                    "#,
+                )
+                .bind(snapshot_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as(
+                    r#"
+                    SELECT DISTINCT storage_key FROM session_files
+                    WHERE session_id = $1 AND storage_key IS NOT NULL
+                    "#,
+                )
+                .bind(session_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(keys.into_iter().map(|(key,)| key).collect())
+    }
+
+    /// Sessions forked directly from a session, newest first
+    pub async fn list_session_branches(&self, parent_session_id: Uuid) -> Result<Vec<SessionRow>> {
+        let rows = sqlx::query_as::<_, SessionRow>(
+            r#"
+            SELECT id, agent_id, title, tags, model_id, status, created_at, started_at, finished_at,
+                parent_session_id, forked_at_event_id, agent_revision, variables
+            FROM sessions
+            WHERE parent_session_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(parent_session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     // ============================================
     // Events (source of truth for messages)
     // ============================================
@@ -534,6 +931,7 @@ impl Database {
         Ok(row)
     }
 
+    /// List a session's events, skipping superseded ones
     pub async fn list_events(
         &self,
         session_id: Uuid,
@@ -547,7 +945,7 @@ impl Database {
                     r#"
                     SELECT id, session_id, sequence, event_type, ts, context, data, metadata, tags, created_at
                     FROM events
-                    WHERE session_id = $1 AND id > $2
+                    WHERE session_id = $1 AND id > $2 AND superseded_at IS NULL
                     ORDER BY id ASC
                     "#,
                 )
@@ -561,7 +959,7 @@ impl Database {
                     r#"
                     SELECT id, session_id, sequence, event_type, ts, context, data, metadata, tags, created_at
                     FROM events
-                    WHERE session_id = $1 AND sequence > $2
+                    WHERE session_id = $1 AND sequence > $2 AND superseded_at IS NULL
                     ORDER BY sequence ASC
                     "#,
                 )
@@ -575,7 +973,7 @@ impl Database {
                     r#"
                     SELECT id, session_id, sequence, event_type, ts, context, data, metadata, tags, created_at
                     FROM events
-                    WHERE session_id = $1
+                    WHERE session_id = $1 AND superseded_at IS NULL
                     ORDER BY sequence ASC
                     "#,
                 )
@@ -594,6 +992,7 @@ impl Database {
     /// Ordered by sequence for conversation reconstruction.
     /// Note: Tool calls are embedded in message.agent events via ContentPart::ToolCall.
     /// Note: Tool results come from tool.call_completed events (not message.tool_result).
+    /// Note: Superseded events are skipped, so regenerated turns replace the old ones.
     pub async fn list_message_events(&self, session_id: Uuid) -> Result<Vec<EventRow>> {
         let rows = sqlx::query_as::<_, EventRow>(
             r#"
@@ -601,6 +1000,7 @@ impl Database {
             FROM events
             WHERE session_id = $1
               AND event_type IN ('message.user', 'message.agent', 'tool.call_completed')
+              AND superseded_at IS NULL
             ORDER BY sequence ASC
             "#,
         )
@@ -611,6 +1011,93 @@ impl Database {
         Ok(rows)
     }
 
+    /// Find a user message event that has not been superseded
+    ///
+    /// Without a message ID this is the session's latest user message.
+    pub async fn find_user_message_event(
+        &self,
+        session_id: Uuid,
+        message_id: Option<Uuid>,
+    ) -> Result<Option<EventRow>> {
+        let row = sqlx::query_as::<_, EventRow>(
+            r#"
+            SELECT id, session_id, sequence, event_type, ts, context, data, metadata, tags, created_at
+            FROM events
+            WHERE session_id = $1
+              AND event_type = 'message.user'
+              AND superseded_at IS NULL
+              AND ($2::uuid IS NULL OR data->'message'->>'id' = $2::text)
+            ORDER BY sequence DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Mark a session's events from a sequence number onwards as superseded
+    ///
+    /// The events are kept but no longer listed or used to rebuild history.
+    pub async fn supersede_events(&self, session_id: Uuid, from_sequence: i32) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE events
+            SET superseded_at = NOW()
+            WHERE session_id = $1 AND sequence >= $2 AND superseded_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(from_sequence)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Count events superseded before a cutoff
+    pub async fn count_superseded_events(&self, superseded_before: DateTime<Utc>) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM events
+            WHERE superseded_at < $1
+            "#,
+        )
+        .bind(superseded_before)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Delete up to `limit` events superseded before a cutoff
+    pub async fn delete_superseded_events(
+        &self,
+        superseded_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM events
+            WHERE id IN (
+                SELECT id FROM events
+                WHERE superseded_at < $1
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(superseded_before)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     // ============================================
     // LLM Providers
     // ============================================
@@ -621,9 +1108,11 @@ impl Database {
 
         let row = sqlx::query_as::<_, LlmProviderRow>(
             r#"
-            INSERT INTO llm_providers (name, provider_type, base_url, api_key_encrypted, api_key_set, settings)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings, created_at, updated_at
+            INSERT INTO llm_providers (name, provider_type, base_url, api_key_encrypted, api_key_set, settings,
+                                       requests_per_minute, tokens_per_minute)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings,
+                      requests_per_minute, tokens_per_minute, created_at, updated_at
             "#,
         )
         .bind(&input.name)
@@ -632,6 +1121,8 @@ impl Database {
         .bind(&input.api_key_encrypted)
         .bind(api_key_set)
         .bind(&settings)
+        .bind(input.requests_per_minute)
+        .bind(input.tokens_per_minute)
         .fetch_one(&self.pool)
         .await?;
 
@@ -641,7 +1132,8 @@ impl Database {
     pub async fn get_llm_provider(&self, id: Uuid) -> Result<Option<LlmProviderRow>> {
         let row = sqlx::query_as::<_, LlmProviderRow>(
             r#"
-            SELECT id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings, created_at, updated_at
+            SELECT id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings,
+                   requests_per_minute, tokens_per_minute, created_at, updated_at
             FROM llm_providers
             WHERE id = $1
             "#,
@@ -656,7 +1148,8 @@ impl Database {
     pub async fn list_llm_providers(&self) -> Result<Vec<LlmProviderRow>> {
         let rows = sqlx::query_as::<_, LlmProviderRow>(
             r#"
-            SELECT id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings, created_at, updated_at
+            SELECT id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings,
+                   requests_per_minute, tokens_per_minute, created_at, updated_at
             FROM llm_providers
             ORDER BY created_at DESC
             "#,
@@ -686,9 +1179,12 @@ impl Database {
                 api_key_set = COALESCE($6, api_key_set),
                 status = COALESCE($7, status),
                 settings = COALESCE($8, settings),
+                requests_per_minute = COALESCE($9, requests_per_minute),
+                tokens_per_minute = COALESCE($10, tokens_per_minute),
                 updated_at = NOW()
             WHERE id = $1
-            RETURNING id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings, created_at, updated_at
+            RETURNING id, name, provider_type, base_url, api_key_encrypted, api_key_set, status, settings,
+                      requests_per_minute, tokens_per_minute, created_at, updated_at
             "#,
         )
         .bind(id)
@@ -699,6 +1195,8 @@ impl Database {
         .bind(api_key_set)
         .bind(&input.status)
         .bind(&input.settings)
+        .bind(input.requests_per_minute)
+        .bind(input.tokens_per_minute)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -947,7 +1445,7 @@ impl Database {
     pub async fn get_agent_capabilities(&self, agent_id: Uuid) -> Result<Vec<AgentCapabilityRow>> {
         let rows = sqlx::query_as::<_, AgentCapabilityRow>(
             r#"
-            SELECT id, agent_id, capability_id, position, created_at
+            SELECT id, agent_id, capability_id, position, config, created_at
             FROM agent_capabilities
             WHERE agent_id = $1
             ORDER BY position ASC
@@ -961,37 +1459,14 @@ impl Database {
     }
 
     /// Set capabilities for an agent (replaces existing capabilities)
-    /// capabilities: list of (capability_id, position) tuples
+    /// capabilities: list of (capability_id, position, config) tuples
     pub async fn set_agent_capabilities(
         &self,
         agent_id: Uuid,
-        capabilities: Vec<(String, i32)>,
+        capabilities: Vec<(String, i32, serde_json::Value)>,
     ) -> Result<Vec<AgentCapabilityRow>> {
-        // Start a transaction
         let mut tx = self.pool.begin().await?;
-
-        // Delete existing capabilities for this agent
-        sqlx::query("DELETE FROM agent_capabilities WHERE agent_id = $1")
-            .bind(agent_id)
-            .execute(&mut *tx)
-            .await?;
-
-        // Insert new capabilities
-        for (capability_id, position) in &capabilities {
-            sqlx::query(
-                r#"
-                INSERT INTO agent_capabilities (agent_id, capability_id, position)
-                VALUES ($1, $2, $3)
-                "#,
-            )
-            .bind(agent_id)
-            .bind(capability_id)
-            .bind(position)
-            .execute(&mut *tx)
-            .await?;
-        }
-
-        // Commit transaction
+        replace_agent_capabilities(&mut tx, agent_id, &capabilities).await?;
         tx.commit().await?;
 
         // Return the new capabilities
@@ -1005,14 +1480,15 @@ impl Database {
     ) -> Result<AgentCapabilityRow> {
         let row = sqlx::query_as::<_, AgentCapabilityRow>(
             r#"
-            INSERT INTO agent_capabilities (agent_id, capability_id, position)
-            VALUES ($1, $2, $3)
-            RETURNING id, agent_id, capability_id, position, created_at
+            INSERT INTO agent_capabilities (agent_id, capability_id, position, config)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, agent_id, capability_id, position, config, created_at
             "#,
         )
         .bind(input.agent_id)
         .bind(&input.capability_id)
         .bind(input.position)
+        .bind(&input.config)
         .fetch_one(&self.pool)
         .await?;
 
@@ -1037,27 +1513,652 @@ impl Database {
     }
 
     // ============================================
-    // Session Files (virtual filesystem)
+    // HTTP Tools (user-defined tools)
     // ============================================
 
-    /// Create a new file or directory in the session virtual filesystem
-    pub async fn create_session_file(&self, input: CreateSessionFileRow) -> Result<SessionFileRow> {
-        let size_bytes = input.content.as_ref().map(|c| c.len() as i64).unwrap_or(0);
-
-        let row = sqlx::query_as::<_, SessionFileRow>(
+    pub async fn create_http_tool(&self, input: CreateHttpToolRow) -> Result<HttpToolRow> {
+        let row = sqlx::query_as::<_, HttpToolRow>(
             r#"
-            INSERT INTO session_files (session_id, path, content, is_directory, is_readonly, size_bytes)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            INSERT INTO http_tools (name, description, parameters, endpoint, secret_encrypted)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, description, parameters, endpoint, secret_encrypted, created_at, updated_at
             "#,
         )
-        .bind(input.session_id)
-        .bind(&input.path)
-        .bind(&input.content)
-        .bind(input.is_directory)
-        .bind(input.is_readonly)
-        .bind(size_bytes)
-        .fetch_one(&self.pool)
+        .bind(&input.name)
+        .bind(&input.description)
+        .bind(&input.parameters)
+        .bind(&input.endpoint)
+        .bind(&input.secret_encrypted)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn get_http_tool(&self, id: Uuid) -> Result<Option<HttpToolRow>> {
+        let row = sqlx::query_as::<_, HttpToolRow>(
+            r#"
+            SELECT id, name, description, parameters, endpoint, secret_encrypted, created_at, updated_at
+            FROM http_tools
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn list_http_tools(&self) -> Result<Vec<HttpToolRow>> {
+        let rows = sqlx::query_as::<_, HttpToolRow>(
+            r#"
+            SELECT id, name, description, parameters, endpoint, secret_encrypted, created_at, updated_at
+            FROM http_tools
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Get tools by name (used to resolve `custom:<name>` capabilities)
+    pub async fn get_http_tools_by_name(&self, names: &[String]) -> Result<Vec<HttpToolRow>> {
+        let rows = sqlx::query_as::<_, HttpToolRow>(
+            r#"
+            SELECT id, name, description, parameters, endpoint, secret_encrypted, created_at, updated_at
+            FROM http_tools
+            WHERE name = ANY($1)
+            "#,
+        )
+        .bind(names)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn update_http_tool(
+        &self,
+        id: Uuid,
+        input: UpdateHttpTool,
+    ) -> Result<Option<HttpToolRow>> {
+        let row = sqlx::query_as::<_, HttpToolRow>(
+            r#"
+            UPDATE http_tools
+            SET
+                description = COALESCE($2, description),
+                parameters = COALESCE($3, parameters),
+                endpoint = COALESCE($4, endpoint),
+                secret_encrypted = COALESCE($5, secret_encrypted),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, name, description, parameters, endpoint, secret_encrypted, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(&input.description)
+        .bind(&input.parameters)
+        .bind(&input.endpoint)
+        .bind(&input.secret_encrypted)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Delete a tool and detach it from all agents
+    pub async fn delete_http_tool(&self, id: Uuid) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let name: Option<String> =
+            sqlx::query_scalar("DELETE FROM http_tools WHERE id = $1 RETURNING name")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(name) = name else {
+            return Ok(false);
+        };
+
+        sqlx::query("DELETE FROM agent_capabilities WHERE capability_id = $1")
+            .bind(everruns_core::http_tool::capability_id_for(&name))
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // ============================================
+    // Agent schedules
+    // ============================================
+
+    pub async fn create_schedule(&self, input: CreateScheduleRow) -> Result<ScheduleRow> {
+        let row = sqlx::query_as::<_, ScheduleRow>(
+            r#"
+            INSERT INTO agent_schedules (
+                agent_id, name, cron, timezone, message, session_id, session_variables,
+                missed_run_policy, overlap_policy, enabled, next_run_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, agent_id, name, cron, timezone, message, session_id, session_variables, missed_run_policy, overlap_policy, enabled, next_run_at, last_run_at, created_at, updated_at
+            "#,
+        )
+        .bind(input.agent_id)
+        .bind(&input.name)
+        .bind(&input.cron)
+        .bind(&input.timezone)
+        .bind(&input.message)
+        .bind(input.session_id)
+        .bind(&input.session_variables)
+        .bind(&input.missed_run_policy)
+        .bind(&input.overlap_policy)
+        .bind(input.enabled)
+        .bind(input.next_run_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn get_schedule(&self, id: Uuid) -> Result<Option<ScheduleRow>> {
+        let row = sqlx::query_as::<_, ScheduleRow>(
+            r#"
+            SELECT id, agent_id, name, cron, timezone, message, session_id, session_variables, missed_run_policy, overlap_policy, enabled, next_run_at, last_run_at, created_at, updated_at
+            FROM agent_schedules
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn list_schedules(&self, agent_id: Uuid) -> Result<Vec<ScheduleRow>> {
+        let rows = sqlx::query_as::<_, ScheduleRow>(
+            r#"
+            SELECT id, agent_id, name, cron, timezone, message, session_id, session_variables, missed_run_policy, overlap_policy, enabled, next_run_at, last_run_at, created_at, updated_at
+            FROM agent_schedules
+            WHERE agent_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn update_schedule(
+        &self,
+        agent_id: Uuid,
+        id: Uuid,
+        input: UpdateSchedule,
+    ) -> Result<Option<ScheduleRow>> {
+        let (set_next_run_at, next_run_at) = match input.next_run_at {
+            Some(next_run_at) => (true, next_run_at),
+            None => (false, None),
+        };
+
+        let row = sqlx::query_as::<_, ScheduleRow>(
+            r#"
+            UPDATE agent_schedules
+            SET
+                name = COALESCE($3, name),
+                cron = COALESCE($4, cron),
+                timezone = COALESCE($5, timezone),
+                message = COALESCE($6, message),
+                session_variables = COALESCE($7, session_variables),
+                missed_run_policy = COALESCE($8, missed_run_policy),
+                overlap_policy = COALESCE($9, overlap_policy),
+                enabled = COALESCE($10, enabled),
+                next_run_at = CASE WHEN $11 THEN $12 ELSE next_run_at END
+            WHERE id = $1 AND agent_id = $2
+            RETURNING id, agent_id, name, cron, timezone, message, session_id, session_variables, missed_run_policy, overlap_policy, enabled, next_run_at, last_run_at, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(agent_id)
+        .bind(&input.name)
+        .bind(&input.cron)
+        .bind(&input.timezone)
+        .bind(&input.message)
+        .bind(&input.session_variables)
+        .bind(&input.missed_run_policy)
+        .bind(&input.overlap_policy)
+        .bind(input.enabled)
+        .bind(set_next_run_at)
+        .bind(next_run_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn delete_schedule(&self, agent_id: Uuid, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM agent_schedules WHERE id = $1 AND agent_id = $2")
+            .bind(id)
+            .bind(agent_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record runs for schedules whose next occurrence is due
+    ///
+    /// Due schedules are locked with `SKIP LOCKED`, so control-plane replicas
+    /// never plan the same occurrence twice. `plan` decides which occurrences
+    /// to run (per the missed-run policy) and the next one to wait for.
+    /// Returns the number of runs recorded.
+    pub async fn plan_due_schedules<F>(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+        plan: F,
+    ) -> Result<usize>
+    where
+        F: Fn(&ScheduleRow) -> SchedulePlan,
+    {
+        let mut tx = self.pool.begin().await?;
+
+        let due = sqlx::query_as::<_, ScheduleRow>(
+            r#"
+            SELECT id, agent_id, name, cron, timezone, message, session_id, session_variables, missed_run_policy, overlap_policy, enabled, next_run_at, last_run_at, created_at, updated_at
+            FROM agent_schedules
+            WHERE enabled AND next_run_at <= $1
+            ORDER BY next_run_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut recorded = 0;
+        for schedule in due {
+            let plan = plan(&schedule);
+
+            for scheduled_for in &plan.runs {
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO schedule_runs (schedule_id, scheduled_for)
+                    VALUES ($1, $2)
+                    ON CONFLICT (schedule_id, scheduled_for) DO NOTHING
+                    "#,
+                )
+                .bind(schedule.id)
+                .bind(scheduled_for)
+                .execute(&mut *tx)
+                .await?;
+                recorded += result.rows_affected() as usize;
+            }
+
+            sqlx::query(
+                r#"
+                UPDATE agent_schedules
+                SET next_run_at = $2, last_run_at = COALESCE($3, last_run_at)
+                WHERE id = $1
+                "#,
+            )
+            .bind(schedule.id)
+            .bind(plan.next_run_at)
+            .bind(plan.runs.last())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(recorded)
+    }
+
+    /// Record a run outside the schedule (manual trigger)
+    pub async fn create_schedule_run(
+        &self,
+        schedule_id: Uuid,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<ScheduleRunRow> {
+        let row = sqlx::query_as::<_, ScheduleRunRow>(
+            r#"
+            INSERT INTO schedule_runs (schedule_id, scheduled_for)
+            VALUES ($1, $2)
+            RETURNING id, schedule_id, scheduled_for, status, session_id, message_id, error, started_at, finished_at, created_at
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(scheduled_for)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn list_schedule_runs(
+        &self,
+        schedule_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<ScheduleRunRow>> {
+        let rows = sqlx::query_as::<_, ScheduleRunRow>(
+            r#"
+            SELECT id, schedule_id, scheduled_for, status, session_id, message_id, error, started_at, finished_at, created_at
+            FROM schedule_runs
+            WHERE schedule_id = $1
+            ORDER BY scheduled_for DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Claim pending runs for dispatch, oldest first
+    pub async fn claim_pending_schedule_runs(&self, limit: i64) -> Result<Vec<ScheduleRunRow>> {
+        let mut rows = sqlx::query_as::<_, ScheduleRunRow>(
+            r#"
+            UPDATE schedule_runs
+            SET status = 'running', started_at = NOW()
+            WHERE id IN (
+                SELECT id FROM schedule_runs
+                WHERE status = 'pending'
+                ORDER BY scheduled_for
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, schedule_id, scheduled_for, status, session_id, message_id, error, started_at, finished_at, created_at
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // RETURNING does not preserve the subquery order
+        rows.sort_by_key(|r| r.scheduled_for);
+        Ok(rows)
+    }
+
+    /// Whether another run of the schedule has started a turn that is still
+    /// in progress. A run counts while its session is active, or for a few
+    /// minutes after dispatch until the worker picks the turn up. Runs whose
+    /// session went idle without a terminal turn event (e.g. a cancelled
+    /// turn) stop counting after that, so they cannot block the schedule.
+    pub async fn schedule_run_in_progress(&self, schedule_id: Uuid, except: Uuid) -> Result<bool> {
+        let in_progress: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM schedule_runs r
+                JOIN sessions s ON s.id = r.session_id
+                WHERE r.schedule_id = $1
+                  AND r.id <> $2
+                  AND r.status = 'running'
+                  AND r.message_id IS NOT NULL
+                  AND (s.status = 'active' OR r.started_at > NOW() - INTERVAL '10 minutes')
+            )
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(except)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(in_progress)
+    }
+
+    /// Attach the session and message a run dispatched
+    pub async fn set_schedule_run_dispatched(
+        &self,
+        id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<()> {
+        sqlx::query("UPDATE schedule_runs SET session_id = $2, message_id = $3 WHERE id = $1")
+            .bind(id)
+            .bind(session_id)
+            .bind(message_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fail runs that were claimed before `started_before` but whose message
+    /// was never posted, so they no longer count as in progress
+    pub async fn fail_stale_schedule_runs(
+        &self,
+        started_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE schedule_runs
+            SET status = 'failed', error = 'Dispatch interrupted before the message was posted',
+                finished_at = NOW()
+            WHERE id IN (
+                SELECT r.id FROM schedule_runs r
+                WHERE r.status = 'running'
+                  AND r.started_at < $1
+                  AND (r.message_id IS NULL OR NOT EXISTS (
+                      SELECT 1 FROM events e
+                      WHERE e.session_id = r.session_id
+                        AND e.event_type = 'message.user'
+                        AND e.data->'message'->>'id' = r.message_id::text
+                  ))
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            "#,
+        )
+        .bind(started_before)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Move a run to a terminal status
+    pub async fn finish_schedule_run(
+        &self,
+        id: Uuid,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE schedule_runs
+            SET status = $2, error = $3, finished_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Finish the running run whose turn was started by `message_id`
+    pub async fn finish_schedule_run_for_message(
+        &self,
+        message_id: Uuid,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<Option<ScheduleRunRow>> {
+        let row = sqlx::query_as::<_, ScheduleRunRow>(
+            r#"
+            UPDATE schedule_runs
+            SET status = $2, error = $3, finished_at = NOW()
+            WHERE message_id = $1 AND status = 'running'
+            RETURNING id, schedule_id, scheduled_for, status, session_id, message_id, error, started_at, finished_at, created_at
+            "#,
+        )
+        .bind(message_id)
+        .bind(status)
+        .bind(error)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    // ============================================
+    // Webhook subscriptions
+    // ============================================
+
+    pub async fn create_webhook(&self, input: CreateWebhookRow) -> Result<WebhookRow> {
+        let row = sqlx::query_as::<_, WebhookRow>(
+            r#"
+            INSERT INTO webhook_subscriptions (agent_id, url, event_types, description, secret_encrypted, enabled)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, agent_id, url, event_types, description, secret_encrypted, enabled, created_at, updated_at
+            "#,
+        )
+        .bind(input.agent_id)
+        .bind(&input.url)
+        .bind(&input.event_types)
+        .bind(&input.description)
+        .bind(&input.secret_encrypted)
+        .bind(input.enabled)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn get_webhook(&self, id: Uuid) -> Result<Option<WebhookRow>> {
+        let row = sqlx::query_as::<_, WebhookRow>(
+            r#"
+            SELECT id, agent_id, url, event_types, description, secret_encrypted, enabled, created_at, updated_at
+            FROM webhook_subscriptions
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// List subscriptions, optionally only those of one agent
+    pub async fn list_webhooks(&self, agent_id: Option<Uuid>) -> Result<Vec<WebhookRow>> {
+        let rows = sqlx::query_as::<_, WebhookRow>(
+            r#"
+            SELECT id, agent_id, url, event_types, description, secret_encrypted, enabled, created_at, updated_at
+            FROM webhook_subscriptions
+            WHERE $1::uuid IS NULL OR agent_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Enabled subscriptions matching an event of a session: global ones and
+    /// those of the session's agent, subscribed to the event type (or to all)
+    pub async fn list_webhooks_for_event(
+        &self,
+        session_id: Uuid,
+        event_type: &str,
+    ) -> Result<Vec<WebhookRow>> {
+        let rows = sqlx::query_as::<_, WebhookRow>(
+            r#"
+            SELECT w.id, w.agent_id, w.url, w.event_types, w.description, w.secret_encrypted, w.enabled, w.created_at, w.updated_at
+            FROM webhook_subscriptions w
+            WHERE w.enabled
+              AND (w.agent_id IS NULL OR w.agent_id = (SELECT agent_id FROM sessions WHERE id = $1))
+              AND (cardinality(w.event_types) = 0 OR $2 = ANY(w.event_types))
+            "#,
+        )
+        .bind(session_id)
+        .bind(event_type)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn update_webhook(
+        &self,
+        id: Uuid,
+        input: UpdateWebhook,
+    ) -> Result<Option<WebhookRow>> {
+        let row = sqlx::query_as::<_, WebhookRow>(
+            r#"
+            UPDATE webhook_subscriptions
+            SET
+                url = COALESCE($2, url),
+                event_types = COALESCE($3, event_types),
+                description = COALESCE($4, description),
+                secret_encrypted = COALESCE($5, secret_encrypted),
+                enabled = COALESCE($6, enabled),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, agent_id, url, event_types, description, secret_encrypted, enabled, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .bind(&input.url)
+        .bind(&input.event_types)
+        .bind(&input.description)
+        .bind(&input.secret_encrypted)
+        .bind(input.enabled)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn delete_webhook(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ============================================
+    // Session Files (virtual filesystem)
+    // ============================================
+
+    /// Create a new file or directory in the session virtual filesystem
+    pub async fn create_session_file(&self, input: CreateSessionFileRow) -> Result<SessionFileRow> {
+        let size_bytes = input.content.as_ref().map(|c| c.size_bytes()).unwrap_or(0);
+        let inline = input.content.as_ref().and_then(|c| c.inline());
+        let storage_key = input.content.as_ref().and_then(|c| c.storage_key());
+
+        let row = sqlx::query_as::<_, SessionFileRow>(
+            r#"
+            INSERT INTO session_files (session_id, path, content, storage_key, is_directory, is_readonly, size_bytes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
+            "#,
+        )
+        .bind(input.session_id)
+        .bind(&input.path)
+        .bind(inline)
+        .bind(storage_key)
+        .bind(input.is_directory)
+        .bind(input.is_readonly)
+        .bind(size_bytes)
+        .fetch_one(&self.pool)
         .await?;
 
         Ok(row)
@@ -1071,7 +2172,7 @@ impl Database {
     ) -> Result<Option<SessionFileRow>> {
         let row = sqlx::query_as::<_, SessionFileRow>(
             r#"
-            SELECT id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            SELECT id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
             FROM session_files
             WHERE session_id = $1 AND path = $2
             "#,
@@ -1088,7 +2189,7 @@ impl Database {
     pub async fn get_session_file_by_id(&self, id: Uuid) -> Result<Option<SessionFileRow>> {
         let row = sqlx::query_as::<_, SessionFileRow>(
             r#"
-            SELECT id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            SELECT id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
             FROM session_files
             WHERE id = $1
             "#,
@@ -1157,39 +2258,146 @@ impl Database {
         input: UpdateSessionFile,
     ) -> Result<Option<SessionFileRow>> {
         // Calculate new size if content is being updated
-        let size_bytes = input.content.as_ref().map(|c| c.len() as i64);
+        let size_bytes = input.content.as_ref().map(|c| c.size_bytes());
+        let inline = input.content.as_ref().and_then(|c| c.inline());
+        let storage_key = input.content.as_ref().and_then(|c| c.storage_key());
 
+        // New content replaces both locations, so content and storage_key are
+        // set together whenever $6 is true
         let row = sqlx::query_as::<_, SessionFileRow>(
             r#"
             UPDATE session_files
             SET
-                content = COALESCE($3, content),
+                content = CASE WHEN $6 THEN $3 ELSE content END,
+                storage_key = CASE WHEN $6 THEN $7 ELSE storage_key END,
                 is_readonly = COALESCE($4, is_readonly),
                 size_bytes = COALESCE($5, size_bytes)
             WHERE session_id = $1 AND path = $2 AND is_directory = FALSE
-            RETURNING id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            RETURNING id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
             "#,
         )
         .bind(session_id)
         .bind(path)
-        .bind(&input.content)
+        .bind(inline)
         .bind(input.is_readonly)
         .bind(size_bytes)
+        .bind(input.content.is_some())
+        .bind(storage_key)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row)
+        Ok(row)
+    }
+
+    /// Apply writes and deletes to several files in one transaction
+    ///
+    /// Deletes run first so a batch can rename a file. Writes create missing
+    /// parent directories and either insert the file or replace its content.
+    /// Any failed change rolls back the whole batch. Returns the written rows.
+    pub async fn apply_session_file_changes(
+        &self,
+        session_id: Uuid,
+        changes: Vec<SessionFileChangeRow>,
+    ) -> Result<Vec<SessionFileRow>> {
+        let mut tx = self.pool.begin().await?;
+
+        let (deletes, writes): (Vec<_>, Vec<_>) = changes
+            .into_iter()
+            .partition(|c| matches!(c, SessionFileChangeRow::Delete { .. }));
+
+        for change in deletes {
+            let SessionFileChangeRow::Delete { path } = change else {
+                continue;
+            };
+            let result = sqlx::query(
+                "DELETE FROM session_files WHERE session_id = $1 AND path = $2 AND is_directory = FALSE",
+            )
+            .bind(session_id)
+            .bind(&path)
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(anyhow!("File not found: {}", path));
+            }
+        }
+
+        let mut rows = Vec::with_capacity(writes.len());
+        for change in writes {
+            let SessionFileChangeRow::Write { path, content } = change else {
+                continue;
+            };
+
+            // Each "/" after the leading one ends an ancestor directory path
+            let ancestors = path.match_indices('/').skip(1).map(|(i, _)| &path[..i]);
+            for dir in ancestors {
+                let is_directory: bool = sqlx::query_scalar(
+                    r#"
+                    WITH created AS (
+                        INSERT INTO session_files (session_id, path, is_directory)
+                        VALUES ($1, $2, TRUE)
+                        ON CONFLICT (session_id, path) DO NOTHING
+                        RETURNING is_directory
+                    )
+                    SELECT is_directory FROM created
+                    UNION ALL
+                    SELECT is_directory FROM session_files WHERE session_id = $1 AND path = $2
+                    LIMIT 1
+                    "#,
+                )
+                .bind(session_id)
+                .bind(dir)
+                .fetch_one(&mut *tx)
+                .await?;
+                if !is_directory {
+                    return Err(anyhow!("A file exists at path: {}", dir));
+                }
+            }
+
+            let row = sqlx::query_as::<_, SessionFileRow>(
+                r#"
+                INSERT INTO session_files (session_id, path, content, storage_key, is_directory, is_readonly, size_bytes)
+                VALUES ($1, $2, $3, $4, FALSE, FALSE, $5)
+                ON CONFLICT (session_id, path) DO UPDATE
+                SET content = EXCLUDED.content,
+                    storage_key = EXCLUDED.storage_key,
+                    size_bytes = EXCLUDED.size_bytes
+                WHERE session_files.is_directory = FALSE AND session_files.is_readonly = FALSE
+                RETURNING id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
+                "#,
+            )
+            .bind(session_id)
+            .bind(&path)
+            .bind(content.inline())
+            .bind(content.storage_key())
+            .bind(content.size_bytes())
+            .fetch_optional(&mut *tx)
+            .await?;
+            match row {
+                Some(row) => rows.push(row),
+                None => {
+                    return Err(anyhow!(
+                        "Cannot modify directory or readonly file: {}",
+                        path
+                    ))
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(rows)
     }
 
     /// Delete a file or directory (directories must be empty)
-    pub async fn delete_session_file(&self, session_id: Uuid, path: &str) -> Result<bool> {
+    ///
+    /// Object-stored content stays in place for the file's version history.
+    pub async fn delete_session_file(&self, session_id: Uuid, path: &str) -> Result<u64> {
         let result = sqlx::query("DELETE FROM session_files WHERE session_id = $1 AND path = $2")
             .bind(session_id)
             .bind(path)
             .execute(&self.pool)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(result.rows_affected())
     }
 
     /// Delete a directory and all its contents recursively
@@ -1224,7 +2432,7 @@ impl Database {
         // First, check if source exists and is a directory
         let source = sqlx::query_as::<_, SessionFileRow>(
             r#"
-            SELECT id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            SELECT id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
             FROM session_files
             WHERE session_id = $1 AND path = $2
             "#,
@@ -1264,7 +2472,7 @@ impl Database {
             UPDATE session_files
             SET path = $3
             WHERE session_id = $1 AND path = $2
-            RETURNING id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            RETURNING id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
             "#,
         )
         .bind(session_id)
@@ -1279,24 +2487,29 @@ impl Database {
     }
 
     /// Copy a file (directories not supported yet)
+    ///
+    /// Object-stored content must be duplicated by the caller first and passed
+    /// as `storage_key` so the copies don't share an object.
     pub async fn copy_session_file(
         &self,
         session_id: Uuid,
         src_path: &str,
         dst_path: &str,
+        storage_key: Option<&str>,
     ) -> Result<Option<SessionFileRow>> {
         let row = sqlx::query_as::<_, SessionFileRow>(
             r#"
-            INSERT INTO session_files (session_id, path, content, is_directory, is_readonly, size_bytes)
-            SELECT session_id, $3, content, is_directory, is_readonly, size_bytes
+            INSERT INTO session_files (session_id, path, content, storage_key, is_directory, is_readonly, size_bytes)
+            SELECT session_id, $3, content, CASE WHEN storage_key IS NULL THEN NULL ELSE $4 END, is_directory, is_readonly, size_bytes
             FROM session_files
             WHERE session_id = $1 AND path = $2 AND is_directory = FALSE
-            RETURNING id, session_id, path, content, is_directory, is_readonly, size_bytes, created_at, updated_at
+            RETURNING id, session_id, path, content, storage_key, is_directory, is_readonly, size_bytes, created_at, updated_at
             "#,
         )
         .bind(session_id)
         .bind(src_path)
         .bind(dst_path)
+        .bind(storage_key)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -1304,6 +2517,9 @@ impl Database {
     }
 
     /// Search file contents using regex pattern (grep-like)
+    ///
+    /// Object-stored files can't be matched in SQL, so they are always
+    /// returned as candidates for the caller to search.
     pub async fn grep_session_files(
         &self,
         session_id: Uuid,
@@ -1319,7 +2535,7 @@ impl Database {
                 WHERE session_id = $1
                     AND is_directory = FALSE
                     AND path ~ $2
-                    AND convert_from(content, 'UTF8') ~ $3
+                    AND (storage_key IS NOT NULL OR convert_from(content, 'UTF8') ~ $3)
                 ORDER BY path ASC
                 "#,
             )
@@ -1335,7 +2551,7 @@ impl Database {
                 FROM session_files
                 WHERE session_id = $1
                     AND is_directory = FALSE
-                    AND convert_from(content, 'UTF8') ~ $2
+                    AND (storage_key IS NOT NULL OR convert_from(content, 'UTF8') ~ $2)
                 ORDER BY path ASC
                 "#,
             )
@@ -1348,6 +2564,157 @@ impl Database {
         Ok(rows)
     }
 
+    /// Object storage keys referenced by a session's files and their versions
+    pub async fn list_session_file_storage_keys(&self, session_id: Uuid) -> Result<Vec<String>> {
+        let keys: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT storage_key FROM session_files
+            WHERE session_id = $1 AND storage_key IS NOT NULL
+            UNION
+            SELECT storage_key FROM session_file_versions
+            WHERE session_id = $1 AND storage_key IS NOT NULL
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys.into_iter().map(|(key,)| key).collect())
+    }
+
+    /// Count automatic (turn) snapshots created before a cutoff
+    pub async fn count_expired_turn_snapshots(&self, created_before: DateTime<Utc>) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM session_snapshots
+            WHERE kind <> 'manual' AND created_at < $1
+            "#,
+        )
+        .bind(created_before)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Delete up to `limit` automatic (turn) snapshots created before a cutoff
+    pub async fn delete_expired_turn_snapshots(
+        &self,
+        created_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM session_snapshots
+            WHERE id IN (
+                SELECT id FROM session_snapshots
+                WHERE kind <> 'manual' AND created_at < $1
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(created_before)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Count file versions past retention (see `delete_expired_file_versions`)
+    pub async fn count_expired_file_versions(
+        &self,
+        created_before: Option<DateTime<Utc>>,
+        max_versions: Option<i64>,
+    ) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM ({}) expired",
+            EXPIRED_FILE_VERSIONS_SQL
+        ))
+        .bind(created_before)
+        .bind(max_versions)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Delete up to `limit` file versions that are older than `created_before`
+    /// or beyond the newest `max_versions` of their path. A path's latest
+    /// version and versions captured by a snapshot are kept.
+    ///
+    /// Returns the number of deleted versions and the object storage keys that
+    /// nothing references anymore.
+    pub async fn delete_expired_file_versions(
+        &self,
+        created_before: Option<DateTime<Utc>>,
+        max_versions: Option<i64>,
+        limit: i64,
+    ) -> Result<(u64, Vec<String>)> {
+        let deleted: Vec<(Uuid, Option<String>)> = sqlx::query_as(&format!(
+            r#"
+            DELETE FROM session_file_versions
+            WHERE id IN ({} LIMIT $3)
+            RETURNING session_id, storage_key
+            "#,
+            EXPIRED_FILE_VERSIONS_SQL
+        ))
+        .bind(created_before)
+        .bind(max_versions)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let (session_ids, keys): (Vec<Uuid>, Vec<String>) = deleted
+            .iter()
+            .filter_map(|(session_id, key)| Some((*session_id, key.clone()?)))
+            .unzip();
+        let unreferenced: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT k.storage_key
+            FROM UNNEST($1::uuid[], $2::text[]) AS k(session_id, storage_key)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM session_files f
+                WHERE f.session_id = k.session_id AND f.storage_key = k.storage_key
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM session_file_versions v
+                WHERE v.session_id = k.session_id AND v.storage_key = k.storage_key
+            )
+            "#,
+        )
+        .bind(&session_ids)
+        .bind(&keys)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((deleted.len() as u64, unreferenced))
+    }
+
+    /// Number of files (excluding directories) and content bytes held by a
+    /// session, including its version history. Content shared by several
+    /// versions (same object) is counted once.
+    pub async fn session_file_usage(&self, session_id: Uuid) -> Result<(i64, i64)> {
+        let usage: (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM session_files
+                 WHERE session_id = $1 AND NOT is_directory),
+                (SELECT COALESCE(SUM(size_bytes), 0)::BIGINT FROM (
+                    SELECT DISTINCT ON (COALESCE(storage_key, id::text)) size_bytes
+                    FROM session_file_versions
+                    WHERE session_id = $1 AND NOT is_deleted
+                 ) content)
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(usage)
+    }
+
     /// Check if a path exists
     pub async fn session_file_exists(&self, session_id: Uuid, path: &str) -> Result<bool> {
         let result: Option<(bool,)> =
@@ -1382,12 +2749,381 @@ impl Database {
 
         Ok(result.is_some())
     }
+
+    // ============================================
+    // Session file versions and snapshots
+    // ============================================
+
+    /// List a file's versions, newest first (no content)
+    pub async fn list_session_file_versions(
+        &self,
+        session_id: Uuid,
+        path: &str,
+    ) -> Result<Vec<SessionFileVersionInfoRow>> {
+        let rows = sqlx::query_as::<_, SessionFileVersionInfoRow>(
+            r#"
+            SELECT id, session_id, path, version, size_bytes, is_deleted, created_at
+            FROM session_file_versions
+            WHERE session_id = $1 AND path = $2
+            ORDER BY version DESC
+            "#,
+        )
+        .bind(session_id)
+        .bind(path)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Get a specific version of a file, or the latest when `version` is None
+    pub async fn get_session_file_version(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        version: Option<i32>,
+    ) -> Result<Option<SessionFileVersionRow>> {
+        let row = sqlx::query_as::<_, SessionFileVersionRow>(
+            r#"
+            SELECT id, session_id, path, version, content, storage_key, size_bytes, is_deleted, created_at
+            FROM session_file_versions
+            WHERE session_id = $1 AND path = $2 AND ($3::INTEGER IS NULL OR version = $3)
+            ORDER BY version DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(path)
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Turn number for a turn's snapshots: reuse the number already assigned
+    /// to `turn_id`, otherwise the next one in the session
+    pub async fn session_snapshot_turn_number(
+        &self,
+        session_id: Uuid,
+        turn_id: Uuid,
+    ) -> Result<i32> {
+        let (turn_number,): (i32,) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(
+                (SELECT turn_number FROM session_snapshots
+                 WHERE session_id = $1 AND turn_id = $2 AND turn_number IS NOT NULL
+                 LIMIT 1),
+                (SELECT COALESCE(MAX(turn_number), 0) + 1 FROM session_snapshots
+                 WHERE session_id = $1)
+            )
+            "#,
+        )
+        .bind(session_id)
+        .bind(turn_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(turn_number)
+    }
+
+    /// Snapshot the current file tree. Returns None if a snapshot with the
+    /// same name already exists.
+    pub async fn create_session_snapshot(
+        &self,
+        input: CreateSessionSnapshotRow,
+    ) -> Result<Option<SessionSnapshotRow>> {
+        let mut tx = self.pool.begin().await?;
+
+        let snapshot = sqlx::query_as::<_, SessionSnapshotRow>(
+            r#"
+            INSERT INTO session_snapshots (session_id, name, kind, turn_id, turn_number)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (session_id, name) DO NOTHING
+            RETURNING id, session_id, name, kind, turn_id, turn_number, file_count, created_at
+            "#,
+        )
+        .bind(input.session_id)
+        .bind(&input.name)
+        .bind(&input.kind)
+        .bind(input.turn_id)
+        .bind(input.turn_number)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(mut snapshot) = snapshot else {
+            return Ok(None);
+        };
+
+        // Each file points at its latest version, which holds the current content
+        let entries = sqlx::query(
+            r#"
+            INSERT INTO session_snapshot_entries (snapshot_id, path, is_directory, is_readonly, version_id)
+            SELECT $2, f.path, f.is_directory, f.is_readonly, v.id
+            FROM session_files f
+            LEFT JOIN LATERAL (
+                SELECT id FROM session_file_versions
+                WHERE session_id = f.session_id AND path = f.path
+                ORDER BY version DESC
+                LIMIT 1
+            ) v ON NOT f.is_directory
+            WHERE f.session_id = $1
+            "#,
+        )
+        .bind(input.session_id)
+        .bind(snapshot.id)
+        .execute(&mut *tx)
+        .await?;
+
+        snapshot.file_count = entries.rows_affected() as i32;
+        sqlx::query("UPDATE session_snapshots SET file_count = $2 WHERE id = $1")
+            .bind(snapshot.id)
+            .bind(snapshot.file_count)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(snapshot))
+    }
+
+    /// List a session's snapshots, newest first
+    pub async fn list_session_snapshots(
+        &self,
+        session_id: Uuid,
+    ) -> Result<Vec<SessionSnapshotRow>> {
+        let rows = sqlx::query_as::<_, SessionSnapshotRow>(
+            r#"
+            SELECT id, session_id, name, kind, turn_id, turn_number, file_count, created_at
+            FROM session_snapshots
+            WHERE session_id = $1
+            ORDER BY created_at DESC, id DESC
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Get a snapshot by ID
+    pub async fn get_session_snapshot(
+        &self,
+        session_id: Uuid,
+        snapshot_id: Uuid,
+    ) -> Result<Option<SessionSnapshotRow>> {
+        let row = sqlx::query_as::<_, SessionSnapshotRow>(
+            r#"
+            SELECT id, session_id, name, kind, turn_id, turn_number, file_count, created_at
+            FROM session_snapshots
+            WHERE session_id = $1 AND id = $2
+            "#,
+        )
+        .bind(session_id)
+        .bind(snapshot_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Get the snapshot taken at the end of a turn, falling back to its start
+    pub async fn get_session_turn_snapshot(
+        &self,
+        session_id: Uuid,
+        turn_id: Uuid,
+    ) -> Result<Option<SessionSnapshotRow>> {
+        let row = sqlx::query_as::<_, SessionSnapshotRow>(
+            r#"
+            SELECT id, session_id, name, kind, turn_id, turn_number, file_count, created_at
+            FROM session_snapshots
+            WHERE session_id = $1 AND turn_id = $2
+            ORDER BY (kind = 'turn_end') DESC, created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .bind(turn_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Delete a snapshot (file versions are kept)
+    pub async fn delete_session_snapshot(
+        &self,
+        session_id: Uuid,
+        snapshot_id: Uuid,
+    ) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM session_snapshots WHERE session_id = $1 AND id = $2")
+            .bind(session_id)
+            .bind(snapshot_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the version of a file captured in a snapshot
+    ///
+    /// Returns None if the path was not a file when the snapshot was taken.
+    pub async fn get_session_snapshot_file(
+        &self,
+        snapshot_id: Uuid,
+        path: &str,
+    ) -> Result<Option<SessionFileVersionRow>> {
+        let row = sqlx::query_as::<_, SessionFileVersionRow>(
+            r#"
+            SELECT v.id, v.session_id, v.path, v.version, v.content, v.storage_key, v.size_bytes, v.is_deleted, v.created_at
+            FROM session_snapshot_entries e
+            JOIN session_file_versions v ON v.id = e.version_id
+            WHERE e.snapshot_id = $1 AND e.path = $2 AND NOT e.is_directory
+            "#,
+        )
+        .bind(snapshot_id)
+        .bind(path)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Replace the session's file tree with a snapshot's contents
+    ///
+    /// Only paths that differ are touched, so unchanged files don't get new
+    /// versions. Removed files are recorded as deleted versions by trigger.
+    pub async fn restore_session_snapshot(
+        &self,
+        session_id: Uuid,
+        snapshot_id: Uuid,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        // Remove paths missing from the snapshot or whose type changed
+        sqlx::query(
+            r#"
+            DELETE FROM session_files f
+            WHERE f.session_id = $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM session_snapshot_entries e
+                    WHERE e.snapshot_id = $2 AND e.path = f.path AND e.is_directory = f.is_directory
+                )
+            "#,
+        )
+        .bind(session_id)
+        .bind(snapshot_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Recreate directories and files; existing rows only change when the
+        // content or readonly flag differs
+        sqlx::query(
+            r#"
+            INSERT INTO session_files (session_id, path, content, storage_key, is_directory, is_readonly, size_bytes)
+            SELECT $1, e.path, v.content, v.storage_key, e.is_directory, e.is_readonly, COALESCE(v.size_bytes, 0)
+            FROM session_snapshot_entries e
+            LEFT JOIN session_file_versions v ON v.id = e.version_id
+            WHERE e.snapshot_id = $2
+            ORDER BY e.path
+            ON CONFLICT (session_id, path) DO UPDATE
+            SET content = EXCLUDED.content,
+                storage_key = EXCLUDED.storage_key,
+                is_readonly = EXCLUDED.is_readonly,
+                size_bytes = EXCLUDED.size_bytes
+            WHERE session_files.content IS DISTINCT FROM EXCLUDED.content
+                OR session_files.storage_key IS DISTINCT FROM EXCLUDED.storage_key
+                OR session_files.is_readonly <> EXCLUDED.is_readonly
+            "#,
+        )
+        .bind(session_id)
+        .bind(snapshot_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
 
 // ============================================================================
 // Helper functions
 // ============================================================================
 
+/// Snapshot an agent (row and capability chain) into `agent_revisions`,
+/// optionally bumping its revision number first. The `UPDATE` locks the agent
+/// row, so concurrent snapshots get distinct numbers.
+/// Replace all capabilities of an agent with the given (capability_id, position, config) tuples
+async fn replace_agent_capabilities(
+    conn: &mut sqlx::PgConnection,
+    agent_id: Uuid,
+    capabilities: &[(String, i32, serde_json::Value)],
+) -> Result<()> {
+    sqlx::query("DELETE FROM agent_capabilities WHERE agent_id = $1")
+        .bind(agent_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (capability_id, position, config) in capabilities {
+        sqlx::query(
+            r#"
+            INSERT INTO agent_capabilities (agent_id, capability_id, position, config)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(agent_id)
+        .bind(capability_id)
+        .bind(position)
+        .bind(config)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn insert_agent_revision(
+    conn: &mut sqlx::PgConnection,
+    agent_id: Uuid,
+    bump: bool,
+) -> Result<Option<AgentRevisionRow>> {
+    let row = sqlx::query_as::<_, AgentRevisionRow>(
+        r#"
+        WITH agent AS (
+            UPDATE agents
+            SET revision = CASE WHEN $2 THEN revision + 1 ELSE revision END
+            WHERE id = $1
+            RETURNING *
+        )
+        INSERT INTO agent_revisions (
+            agent_id, revision, name, description, system_prompt, default_model_id, tags,
+            response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted, capabilities
+        )
+        SELECT
+            a.id, a.revision, a.name, a.description, a.system_prompt, a.default_model_id, a.tags,
+            a.response_format, a.parallel_tool_calls, a.mcp_servers, a.mcp_credentials_encrypted,
+            COALESCE(
+                (SELECT jsonb_agg(jsonb_build_object('capability_id', c.capability_id, 'config', c.config) ORDER BY c.position)
+                 FROM agent_capabilities c
+                 WHERE c.agent_id = a.id),
+                '[]'::jsonb
+            )
+        FROM agent a
+        RETURNING id, agent_id, revision, name, description, system_prompt, default_model_id, tags,
+            response_format, parallel_tool_calls, mcp_servers, mcp_credentials_encrypted,
+            capabilities, created_at
+        "#,
+    )
+    .bind(agent_id)
+    .bind(bump)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row)
+}
+
 /// Get default API key from environment variable based on provider type.
 ///
 /// Environment variables (for development convenience):
@@ -5,12 +5,18 @@
 
 use async_trait::async_trait;
 use everruns_core::{
-    traits::SessionFileStore, AgentLoopError, FileInfo, FileStat, GrepMatch, Result, SessionFile,
+    traits::SessionFileStore, AgentLoopError, FileChange, FileDiff, FileInfo, FileStat,
+    FileVersion, GrepMatch, RestoreTarget, Result, SessionFile, SessionSnapshot, SnapshotKind,
 };
 use regex::Regex;
+use std::sync::Arc;
 use uuid::Uuid;
 
-use super::models::{CreateSessionFileRow, UpdateSessionFile};
+use super::models::{
+    CreateSessionFileRow, CreateSessionSnapshotRow, SessionFileChangeRow, SessionFileRow,
+    SessionFileVersionRow, SessionSnapshotRow, StoredContent, UpdateSessionFile,
+};
+use super::object_store::FileContentStore;
 use super::repositories::Database;
 
 // ============================================================================
@@ -24,33 +30,34 @@ use super::repositories::Database;
 #[derive(Clone)]
 pub struct DbSessionFileStore {
     db: Database,
+    content: Arc<FileContentStore>,
 }
 
 impl DbSessionFileStore {
+    /// Store all content inline in the database
     pub fn new(db: Database) -> Self {
-        Self { db }
-    }
-
-    /// Normalize a path: ensure it starts with /, no trailing slash, no double slashes
-    fn normalize_path(path: &str) -> String {
-        let mut normalized = path.trim().to_string();
-
-        // Ensure starts with /
-        if !normalized.starts_with('/') {
-            normalized = format!("/{}", normalized);
+        Self {
+            db,
+            content: Arc::new(FileContentStore::inline_only()),
         }
+    }
 
-        // Remove trailing slash (except for root)
-        if normalized.len() > 1 && normalized.ends_with('/') {
-            normalized.pop();
-        }
+    /// Place large content in object storage according to `content`
+    pub fn with_content_store(mut self, content: Arc<FileContentStore>) -> Self {
+        self.content = content;
+        self
+    }
 
-        // Remove double slashes
-        while normalized.contains("//") {
-            normalized = normalized.replace("//", "/");
-        }
+    /// Normalize a path (shared with SessionFileService and the file tools)
+    fn normalize_path(path: &str) -> String {
+        FileInfo::normalize_path(path)
+    }
 
-        normalized
+    /// Normalize and validate a path that is about to be written
+    fn writable_path(path: &str) -> Result<String> {
+        let path = Self::normalize_path(path);
+        FileInfo::validate_path(&path).map_err(AgentLoopError::store)?;
+        Ok(path)
     }
 
     /// Ensure a directory exists, creating it and parents if needed
@@ -88,6 +95,108 @@ impl DbSessionFileStore {
         self.db.create_session_file(input).await?;
         Ok(())
     }
+
+    /// Update the file if it exists, otherwise create it
+    async fn write_row(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        exists: bool,
+        content: StoredContent,
+    ) -> Result<SessionFileRow> {
+        if exists {
+            self.db
+                .update_session_file(
+                    session_id,
+                    path,
+                    UpdateSessionFile {
+                        content: Some(content),
+                        is_readonly: None,
+                    },
+                )
+                .await
+                .map_err(|e| AgentLoopError::store(e.to_string()))?
+                .ok_or_else(|| AgentLoopError::store("File not found after update"))
+        } else {
+            let input = CreateSessionFileRow {
+                session_id,
+                path: path.to_string(),
+                content: Some(content),
+                is_directory: false,
+                is_readonly: false,
+            };
+
+            self.db
+                .create_session_file(input)
+                .await
+                .map_err(|e| AgentLoopError::store(e.to_string()))
+        }
+    }
+
+    /// Get a version (latest when None), failing if it doesn't exist
+    async fn get_version(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        version: Option<i32>,
+    ) -> Result<SessionFileVersionRow> {
+        self.db
+            .get_session_file_version(session_id, path, version)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?
+            .ok_or_else(|| match version {
+                Some(v) => AgentLoopError::store(format!("Version {} not found for: {}", v, path)),
+                None => AgentLoopError::store(format!("No versions found for: {}", path)),
+            })
+    }
+
+    /// Content of a version (empty for deletions)
+    async fn version_content(&self, row: &SessionFileVersionRow) -> Result<Vec<u8>> {
+        if row.is_deleted {
+            return Ok(Vec::new());
+        }
+        self.content
+            .version_bytes(row)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))
+    }
+
+    /// Find the snapshot a restore target refers to
+    async fn resolve_snapshot(
+        &self,
+        session_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<SessionSnapshotRow> {
+        let snapshot = match target {
+            RestoreTarget::Snapshot(snapshot_id) => {
+                self.db.get_session_snapshot(session_id, snapshot_id).await
+            }
+            RestoreTarget::Turn(turn_id) => {
+                self.db.get_session_turn_snapshot(session_id, turn_id).await
+            }
+            RestoreTarget::Version(_) => {
+                return Err(AgentLoopError::store(
+                    "Invalid restore target: versions apply to single files only",
+                ))
+            }
+        }
+        .map_err(|e| AgentLoopError::store(e.to_string()))?;
+
+        snapshot.ok_or_else(|| AgentLoopError::store("Snapshot not found"))
+    }
+
+    fn row_to_snapshot(row: SessionSnapshotRow) -> SessionSnapshot {
+        SessionSnapshot {
+            id: row.id,
+            session_id: row.session_id,
+            name: row.name,
+            kind: SnapshotKind::from(row.kind.as_str()),
+            turn_id: row.turn_id,
+            turn_number: row.turn_number,
+            file_count: row.file_count,
+            created_at: row.created_at,
+        }
+    }
 }
 
 #[async_trait]
@@ -99,6 +208,15 @@ impl SessionFileStore for DbSessionFileStore {
             .get_session_file(session_id, &path)
             .await
             .map_err(|e| AgentLoopError::store(e.to_string()))?;
+        let row = match row {
+            Some(r) => Some(
+                self.content
+                    .hydrate(r)
+                    .await
+                    .map_err(|e| AgentLoopError::store(e.to_string()))?,
+            ),
+            None => None,
+        };
 
         Ok(row.map(|r| {
             let (content, encoding) = if let Some(bytes) = r.content {
@@ -131,7 +249,7 @@ impl SessionFileStore for DbSessionFileStore {
         content: &str,
         encoding: &str,
     ) -> Result<SessionFile> {
-        let path = Self::normalize_path(path);
+        let path = Self::writable_path(path)?;
 
         // Decode content
         let bytes = SessionFile::decode_content(content, encoding)
@@ -149,8 +267,7 @@ impl SessionFileStore for DbSessionFileStore {
             .await
             .map_err(|e| AgentLoopError::store(e.to_string()))?;
 
-        let row = if let Some(existing) = existing {
-            // Update existing file
+        if let Some(ref existing) = existing {
             if existing.is_directory {
                 return Err(AgentLoopError::store(format!(
                     "Cannot write to directory: {}",
@@ -163,34 +280,28 @@ impl SessionFileStore for DbSessionFileStore {
                     path
                 )));
             }
+        }
 
-            self.db
-                .update_session_file(
-                    session_id,
-                    &path,
-                    UpdateSessionFile {
-                        content: Some(bytes),
-                        is_readonly: None,
-                    },
-                )
-                .await
-                .map_err(|e| AgentLoopError::store(e.to_string()))?
-                .ok_or_else(|| AgentLoopError::store("File not found after update"))?
-        } else {
-            // Create new file
-            let input = CreateSessionFileRow {
-                session_id,
-                path: path.clone(),
-                content: Some(bytes),
-                is_directory: false,
-                is_readonly: false,
-            };
-
-            self.db
-                .create_session_file(input)
-                .await
-                .map_err(|e| AgentLoopError::store(e.to_string()))?
+        let stored = self
+            .content
+            .store(session_id, bytes::Bytes::from(bytes.clone()))
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+        let new_key = stored.storage_key().map(str::to_string);
+
+        let result = self
+            .write_row(session_id, &path, existing.is_some(), stored)
+            .await;
+        let mut row = match result {
+            Ok(row) => row,
+            Err(e) => {
+                self.content.release(new_key).await;
+                return Err(e);
+            }
         };
+        if row.content.is_none() {
+            row.content = Some(bytes);
+        }
 
         // Convert row to SessionFile
         let (content, encoding) = if let Some(bytes) = row.content {
@@ -255,19 +366,15 @@ impl SessionFileStore for DbSessionFileStore {
             }
         }
 
-        if recursive {
-            let deleted = self
-                .db
+        let deleted = if recursive {
+            self.db
                 .delete_session_file_recursive(session_id, &path)
                 .await
-                .map_err(|e| AgentLoopError::store(e.to_string()))?;
-            Ok(deleted > 0)
         } else {
-            self.db
-                .delete_session_file(session_id, &path)
-                .await
-                .map_err(|e| AgentLoopError::store(e.to_string()))
+            self.db.delete_session_file(session_id, &path).await
         }
+        .map_err(|e| AgentLoopError::store(e.to_string()))?;
+        Ok(deleted > 0)
     }
 
     async fn list_directory(&self, session_id: Uuid, path: &str) -> Result<Vec<FileInfo>> {
@@ -381,6 +488,11 @@ impl SessionFileStore for DbSessionFileStore {
                 .map_err(|e| AgentLoopError::store(e.to_string()))?;
 
             if let Some(f) = file {
+                let f = self
+                    .content
+                    .hydrate(f)
+                    .await
+                    .map_err(|e| AgentLoopError::store(e.to_string()))?;
                 if let Some(content) = f.content {
                     // Try to decode as text
                     if let Ok(text) = String::from_utf8(content) {
@@ -402,7 +514,7 @@ impl SessionFileStore for DbSessionFileStore {
     }
 
     async fn create_directory(&self, session_id: Uuid, path: &str) -> Result<FileInfo> {
-        let path = Self::normalize_path(path);
+        let path = Self::writable_path(path)?;
 
         // Check if already exists
         if let Some(existing) = self
@@ -462,6 +574,284 @@ impl SessionFileStore for DbSessionFileStore {
             updated_at: row.updated_at,
         })
     }
+
+    async fn glob_files(&self, session_id: Uuid, pattern: &str) -> Result<Vec<FileInfo>> {
+        let pattern = Self::normalize_path(pattern);
+        let rows = self
+            .db
+            .list_all_session_files(session_id)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .filter(|r| FileInfo::glob_matches(&pattern, &r.path))
+            .map(|r| FileInfo {
+                id: r.id,
+                session_id: r.session_id,
+                path: r.path.clone(),
+                name: FileInfo::name_from_path(&r.path),
+                is_directory: r.is_directory,
+                is_readonly: r.is_readonly,
+                size_bytes: r.size_bytes,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+            .collect())
+    }
+
+    async fn apply_changes(
+        &self,
+        session_id: Uuid,
+        changes: Vec<FileChange>,
+    ) -> Result<Vec<FileInfo>> {
+        // Validate paths and decode content before storing anything
+        let mut seen = std::collections::HashSet::new();
+        let mut decoded = Vec::with_capacity(changes.len());
+        for change in changes {
+            let path = Self::writable_path(change.path())?;
+            if path == "/" {
+                return Err(AgentLoopError::store("Cannot modify root directory"));
+            }
+            if !seen.insert(path.clone()) {
+                return Err(AgentLoopError::store(format!(
+                    "Duplicate path in changes: {}",
+                    path
+                )));
+            }
+            let data = match change {
+                FileChange::Write {
+                    content, encoding, ..
+                } => Some(
+                    SessionFile::decode_content(&content, &encoding).map_err(|e| {
+                        AgentLoopError::store(format!("Invalid content encoding: {}", e))
+                    })?,
+                ),
+                FileChange::Delete { .. } => None,
+            };
+            decoded.push((path, data));
+        }
+
+        let mut rows = Vec::with_capacity(decoded.len());
+        let mut new_keys = Vec::new();
+        for (path, data) in decoded {
+            let Some(data) = data else {
+                rows.push(SessionFileChangeRow::Delete { path });
+                continue;
+            };
+            let stored = match self
+                .content
+                .store(session_id, bytes::Bytes::from(data))
+                .await
+            {
+                Ok(stored) => stored,
+                Err(e) => {
+                    self.content.release(new_keys).await;
+                    return Err(AgentLoopError::store(e.to_string()));
+                }
+            };
+            new_keys.extend(stored.storage_key().map(str::to_string));
+            rows.push(SessionFileChangeRow::Write {
+                path,
+                content: stored,
+            });
+        }
+
+        let written = match self.db.apply_session_file_changes(session_id, rows).await {
+            Ok(written) => written,
+            Err(e) => {
+                self.content.release(new_keys).await;
+                return Err(AgentLoopError::store(e.to_string()));
+            }
+        };
+
+        Ok(written
+            .into_iter()
+            .map(|r| FileInfo {
+                id: r.id,
+                session_id: r.session_id,
+                path: r.path.clone(),
+                name: FileInfo::name_from_path(&r.path),
+                is_directory: r.is_directory,
+                is_readonly: r.is_readonly,
+                size_bytes: r.size_bytes,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+            .collect())
+    }
+
+    async fn list_file_versions(&self, session_id: Uuid, path: &str) -> Result<Vec<FileVersion>> {
+        let path = Self::normalize_path(path);
+        let rows = self
+            .db
+            .list_session_file_versions(session_id, &path)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| FileVersion {
+                id: r.id,
+                session_id: r.session_id,
+                path: r.path,
+                version: r.version,
+                size_bytes: r.size_bytes,
+                is_deleted: r.is_deleted,
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    async fn diff_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        from: Option<i32>,
+        to: Option<i32>,
+    ) -> Result<FileDiff> {
+        let path = Self::normalize_path(path);
+
+        let to_row = self.get_version(session_id, &path, to).await?;
+        let from_version = from.unwrap_or(to_row.version - 1);
+        if from_version < 1 {
+            return Err(AgentLoopError::store(format!(
+                "No earlier version to diff against: {} is at version {}",
+                path, to_row.version
+            )));
+        }
+        let from_row = self
+            .get_version(session_id, &path, Some(from_version))
+            .await?;
+
+        let old = self.version_content(&from_row).await?;
+        let new = self.version_content(&to_row).await?;
+        Ok(FileDiff::between(
+            &path,
+            (from_row.version, &old),
+            (to_row.version, &new),
+        ))
+    }
+
+    async fn restore_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        target: RestoreTarget,
+    ) -> Result<SessionFile> {
+        let path = Self::normalize_path(path);
+
+        let version = match target {
+            RestoreTarget::Version(version) => {
+                self.get_version(session_id, &path, Some(version)).await?
+            }
+            RestoreTarget::Snapshot(_) | RestoreTarget::Turn(_) => {
+                let snapshot = self.resolve_snapshot(session_id, target).await?;
+                self.db
+                    .get_session_snapshot_file(snapshot.id, &path)
+                    .await
+                    .map_err(|e| AgentLoopError::store(e.to_string()))?
+                    .ok_or_else(|| {
+                        AgentLoopError::store(format!(
+                            "File not found in snapshot '{}': {}",
+                            snapshot.name, path
+                        ))
+                    })?
+            }
+        };
+        let Some(content) = version.stored_content() else {
+            return Err(AgentLoopError::store(format!(
+                "Cannot restore version {} of {}: the file was deleted at that version",
+                version.version, path
+            )));
+        };
+
+        if let Some(parent) = FileInfo::parent_path(&path) {
+            self.ensure_directory_exists(session_id, &parent).await?;
+        }
+        let existing = self
+            .db
+            .get_session_file(session_id, &path)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+        if let Some(ref existing) = existing {
+            if existing.is_directory {
+                return Err(AgentLoopError::store(format!(
+                    "Cannot restore over directory: {}",
+                    path
+                )));
+            }
+            if existing.is_readonly {
+                return Err(AgentLoopError::store(format!(
+                    "Cannot modify readonly file: {}",
+                    path
+                )));
+            }
+        }
+
+        // The version's object is shared rather than copied, so nothing is
+        // released if the write fails
+        let row = self
+            .write_row(session_id, &path, existing.is_some(), content)
+            .await?;
+        self.read_file(session_id, &row.path)
+            .await?
+            .ok_or_else(|| AgentLoopError::store("File not found after restore"))
+    }
+
+    async fn list_snapshots(&self, session_id: Uuid) -> Result<Vec<SessionSnapshot>> {
+        let rows = self
+            .db
+            .list_session_snapshots(session_id)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+
+        Ok(rows.into_iter().map(Self::row_to_snapshot).collect())
+    }
+
+    async fn create_snapshot(&self, session_id: Uuid, name: &str) -> Result<SessionSnapshot> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > 255 {
+            return Err(AgentLoopError::store(
+                "Invalid snapshot name: must be 1 to 255 characters",
+            ));
+        }
+        if name.starts_with("turn-") {
+            return Err(AgentLoopError::store(
+                "Invalid snapshot name: 'turn-' is reserved for turn snapshots",
+            ));
+        }
+
+        let input = CreateSessionSnapshotRow {
+            session_id,
+            name: name.to_string(),
+            kind: SnapshotKind::Manual.as_str().to_string(),
+            turn_id: None,
+            turn_number: None,
+        };
+        let row = self
+            .db
+            .create_session_snapshot(input)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?
+            .ok_or_else(|| AgentLoopError::store(format!("Snapshot already exists: {}", name)))?;
+
+        Ok(Self::row_to_snapshot(row))
+    }
+
+    async fn restore_snapshot(
+        &self,
+        session_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<SessionSnapshot> {
+        let snapshot = self.resolve_snapshot(session_id, target).await?;
+        self.db
+            .restore_session_snapshot(session_id, snapshot.id)
+            .await
+            .map_err(|e| AgentLoopError::store(e.to_string()))?;
+
+        Ok(Self::row_to_snapshot(snapshot))
+    }
 }
 
 // ============================================================================
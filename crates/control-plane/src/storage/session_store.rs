@@ -52,6 +52,10 @@ impl SessionStore for DbSessionStore {
                 created_at: row.created_at,
                 started_at: row.started_at,
                 finished_at: row.finished_at,
+                parent_session_id: row.parent_session_id,
+                forked_at_event_id: row.forked_at_event_id,
+                agent_revision: row.agent_revision,
+                variables: serde_json::from_value(row.variables).unwrap_or_default(),
             })),
             None => Ok(None),
         }
@@ -118,7 +118,12 @@ async fn main() -> anyhow::Result<()> {
         default_model_id: None,
         tags: vec![],
         capabilities: vec![],
+        response_format: None,
+        parallel_tool_calls: None,
+        capability_config: Default::default(),
+        mcp_servers: vec![],
         status: AgentStatus::Active,
+        revision: 1,
         created_at: now,
         updated_at: now,
     };
@@ -135,6 +140,10 @@ async fn main() -> anyhow::Result<()> {
         created_at: now,
         started_at: None,
         finished_at: None,
+        parent_session_id: None,
+        forked_at_event_id: None,
+        agent_revision: None,
+        variables: Default::default(),
     };
     session_store.add_session(session).await;
 
@@ -265,6 +274,7 @@ async fn main() -> anyhow::Result<()> {
                 agent_id,
                 tool_calls: reason_result.tool_calls.clone(),
                 tool_definitions: reason_result.tool_definitions.clone(),
+                sequential: reason_result.sequential_tool_calls,
             })
             .await?;
 
@@ -5,9 +5,13 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 use crate::capability_types::CapabilityId;
+use crate::mcp::McpServerConfig;
+use crate::structured_output::ResponseFormat;
 
 #[cfg(feature = "openapi")]
 use utoipa::ToSchema;
@@ -70,10 +74,35 @@ pub struct Agent {
     #[serde(default)]
     #[cfg_attr(feature = "openapi", schema(value_type = Vec<String>))]
     pub capabilities: Vec<CapabilityId>,
+    /// Per-capability configuration, keyed by capability ID.
+    /// Validated against the schema each capability publishes.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub capability_config: BTreeMap<String, Value>,
+    /// JSON-schema constrained format for the agent's final responses.
+    /// Can be overridden per message via controls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Whether the agent's tools may be called in parallel.
+    /// `false` declares the tools order-dependent: the model is asked for one
+    /// call at a time and calls are executed sequentially.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    /// MCP servers whose tools are available through the `mcp` capability.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_servers: Vec<McpServerConfig>,
     /// Current lifecycle status of the agent.
     pub status: AgentStatus,
+    /// Configuration revision. Every configuration change creates a new,
+    /// immutable revision; sessions pin the revision they were created with.
+    #[serde(default = "default_revision")]
+    pub revision: i32,
     /// Timestamp when the agent was created.
     pub created_at: DateTime<Utc>,
     /// Timestamp when the agent was last updated.
     pub updated_at: DateTime<Utc>,
 }
+
+fn default_revision() -> i32 {
+    1
+}
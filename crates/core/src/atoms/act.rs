@@ -2,7 +2,8 @@
 //!
 //! This atom handles:
 //! 1. Emitting act.started event
-//! 2. Executing multiple tool calls in parallel (with tool.call_started/completed events)
+//! 2. Executing multiple tool calls in parallel (with tool.call_started/completed events),
+//!    or one at a time in request order when the agent disables parallel tool calls
 //! 3. Handling errors, timeouts, and cancellations as "normal" results
 //! 4. Emitting act.completed event
 //! 5. Returning all tool results (success, error, timeout, or cancelled)
@@ -52,6 +53,9 @@ pub struct ActInput {
     pub tool_calls: Vec<ToolCall>,
     /// Available tool definitions for resolution
     pub tool_definitions: Vec<ToolDefinition>,
+    /// Execute tool calls one at a time, in order (tools are order-dependent)
+    #[serde(default)]
+    pub sequential: bool,
 }
 
 /// Result of a single tool call execution
@@ -152,6 +156,7 @@ where
             context,
             tool_calls,
             tool_definitions,
+            sequential,
             .. // agent_id not needed here, just passed through workflow
         } = input;
 
@@ -169,7 +174,8 @@ where
             turn_id = %context.turn_id,
             exec_id = %context.exec_id,
             tool_count = %tool_calls.len(),
-            "ActAtom: executing tools in parallel"
+            sequential = %sequential,
+            "ActAtom: executing tools"
         );
 
         // Create event context from atom context
@@ -203,16 +209,27 @@ where
             })
             .collect();
 
-        // Execute all tool calls in parallel
-        let futures: Vec<_> = tool_calls
-            .iter()
-            .map(|tool_call| {
+        // Execute tool calls in parallel, or in order when they depend on each other
+        let results = if sequential {
+            let mut results = Vec::with_capacity(tool_calls.len());
+            for tool_call in &tool_calls {
                 let tool_def = tool_map.get(tool_call.name.as_str()).cloned();
-                self.execute_single_tool(&context, tool_call.clone(), tool_def)
-            })
-            .collect();
-
-        let results = join_all(futures).await;
+                results.push(
+                    self.execute_single_tool(&context, tool_call.clone(), tool_def)
+                        .await,
+                );
+            }
+            results
+        } else {
+            let futures: Vec<_> = tool_calls
+                .iter()
+                .map(|tool_call| {
+                    let tool_def = tool_map.get(tool_call.name.as_str()).cloned();
+                    self.execute_single_tool(&context, tool_call.clone(), tool_def)
+                })
+                .collect();
+            join_all(futures).await
+        };
 
         // Count successes and errors
         let success_count = results.iter().filter(|r| r.success).count() as u32;
@@ -486,6 +503,7 @@ mod tests {
             agent_id: Uuid::now_v7(),
             tool_calls: vec![],
             tool_definitions: vec![],
+            sequential: false,
         };
 
         let result = atom.execute(input).await.unwrap();
@@ -512,6 +530,7 @@ mod tests {
                 arguments: json!({}),
             }],
             tool_definitions: vec![],
+            sequential: false,
         };
 
         let result = atom.execute(input).await.unwrap();
@@ -527,4 +546,67 @@ mod tests {
             .unwrap()
             .contains("not found"));
     }
+
+    /// Executor that records when each tool finishes; the first call is slow
+    struct RecordingExecutor {
+        finished: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ToolExecutor for RecordingExecutor {
+        async fn execute(
+            &self,
+            tool_call: &ToolCall,
+            _tool_def: &ToolDefinition,
+        ) -> Result<ToolResult> {
+            if tool_call.id == "call_1" {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            self.finished.lock().unwrap().push(tool_call.id.clone());
+            Ok(ToolResult {
+                tool_call_id: tool_call.id.clone(),
+                result: Some(json!({"ok": true})),
+                error: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_act_atom_sequential_preserves_order() {
+        use crate::tool_types::{BuiltinTool, ToolPolicy};
+
+        let executor = RecordingExecutor {
+            finished: std::sync::Mutex::new(vec![]),
+        };
+        let atom = ActAtom::new(executor, NoopEventEmitter);
+
+        let tool_calls = ["call_1", "call_2"]
+            .iter()
+            .map(|id| ToolCall {
+                id: id.to_string(),
+                name: "step".to_string(),
+                arguments: json!({}),
+            })
+            .collect();
+        let input = ActInput {
+            context: AtomContext::new(Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7()),
+            agent_id: Uuid::now_v7(),
+            tool_calls,
+            tool_definitions: vec![ToolDefinition::Builtin(BuiltinTool {
+                name: "step".to_string(),
+                description: "Order-dependent step".to_string(),
+                parameters: json!({"type": "object"}),
+                policy: ToolPolicy::Auto,
+            })],
+            sequential: true,
+        };
+
+        let result = atom.execute(input).await.unwrap();
+
+        assert_eq!(result.success_count, 2);
+        assert_eq!(
+            *atom.tool_executor.finished.lock().unwrap(),
+            vec!["call_1".to_string(), "call_2".to_string()]
+        );
+    }
 }
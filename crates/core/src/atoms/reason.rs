@@ -5,9 +5,10 @@
 //! 2. Context preparation (loading message history, adding system message)
 //! 3. Fixing invalid context (e.g., missing tool_results for dangling tool calls)
 //! 4. LLM call with streaming support
-//! 5. Storing the assistant response
-//! 6. Emitting reason.completed event
-//! 7. Returning the result with tool calls (if any)
+//! 5. Validating structured output against the response format (with repair retries)
+//! 6. Storing the assistant response
+//! 7. Emitting reason.completed event
+//! 8. Returning the result with tool calls (if any)
 //!
 //! NOTES from Python spec:
 //! - Context preparation includes loading message history, adding system message, editing context if needed
@@ -19,6 +20,7 @@
 use async_trait::async_trait;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Instant;
 use uuid::Uuid;
 
@@ -30,14 +32,18 @@ use crate::events::{
     ReasonStartedData, ToolDefinitionSummary,
 };
 use crate::llm_driver_registry::{
-    DriverRegistry, LlmCallConfigBuilder, LlmMessage, LlmMessageContent, LlmMessageRole,
-    LlmStreamEvent, ProviderConfig, ProviderType,
+    BoxedLlmDriver, DriverRegistry, LlmCallConfig, LlmCallConfigBuilder, LlmMessage,
+    LlmMessageContent, LlmMessageRole, LlmStreamEvent, ProviderConfig, ProviderType,
 };
 use crate::message::{Message, MessageRole};
-use crate::runtime_agent::RuntimeAgentBuilder;
-use crate::tool_types::{ToolCall, ToolDefinition};
+use crate::prompt_template::{PromptTemplate, TemplateContext};
+use crate::runtime_agent::{RuntimeAgent, RuntimeAgentBuilder};
+use crate::session::Session;
+use crate::structured_output::MAX_STRUCTURED_OUTPUT_REPAIR_ATTEMPTS;
+use crate::tool_types::{ToolCall, ToolChoice, ToolDefinition};
 use crate::traits::{
-    AgentStore, EventEmitter, LlmProviderStore, MessageStore, ModelWithProvider, SessionStore,
+    AgentStore, EventEmitter, LlmProviderStore, LlmRateLimiter, MessageStore, ModelWithProvider,
+    SessionFileStore, SessionStore,
 };
 
 // ============================================================================
@@ -110,6 +116,9 @@ pub struct ReasonResult {
     /// Maximum iterations configured for the agent
     #[serde(default = "default_max_iterations")]
     pub max_iterations: usize,
+    /// Whether the requested tool calls must run one at a time, in order
+    #[serde(default)]
+    pub sequential_tool_calls: bool,
     /// Error message if the call failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
@@ -119,6 +128,24 @@ fn default_max_iterations() -> usize {
     100
 }
 
+/// Rough token estimate for rate limiting: ~4 characters per token for the
+/// prompt, plus the completion budget
+fn estimate_tokens(messages: &[LlmMessage], config: &LlmCallConfig) -> u32 {
+    let prompt_chars: usize = messages
+        .iter()
+        .map(|m| {
+            m.content.to_text().len()
+                + m.tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|c| c.arguments.to_string().len())
+                    .sum::<usize>()
+        })
+        .sum();
+    let prompt_tokens = u32::try_from(prompt_chars.div_ceil(4)).unwrap_or(u32::MAX);
+    prompt_tokens.saturating_add(config.max_tokens.unwrap_or(0))
+}
+
 // ============================================================================
 // ReasonAtom
 // ============================================================================
@@ -151,6 +178,10 @@ where
     capability_registry: CapabilityRegistry,
     driver_registry: DriverRegistry,
     event_emitter: E,
+    /// Optional file store for `{{file "..."}}` system prompt placeholders
+    file_store: Option<Arc<dyn SessionFileStore>>,
+    /// Optional rate limiter shared with other workers calling the same provider
+    rate_limiter: Option<Arc<dyn LlmRateLimiter>>,
 }
 
 impl<A, S, M, P, E> ReasonAtom<A, S, M, P, E>
@@ -179,8 +210,22 @@ where
             capability_registry,
             driver_registry,
             event_emitter,
+            file_store: None,
+            rate_limiter: None,
         }
     }
+
+    /// Use a file store to resolve `{{file "..."}}` placeholders in the system prompt
+    pub fn with_file_store(mut self, file_store: Arc<dyn SessionFileStore>) -> Self {
+        self.file_store = Some(file_store);
+        self
+    }
+
+    /// Acquire provider capacity from a shared rate limiter before each LLM call
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<dyn LlmRateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
 }
 
 #[async_trait]
@@ -335,6 +380,7 @@ where
                     has_tool_calls: false,
                     tool_definitions: vec![],
                     max_iterations: default_max_iterations(),
+                    sequential_tool_calls: false,
                     error: Some(error_msg),
                 }
             }
@@ -352,6 +398,36 @@ where
     P: LlmProviderStore + Send + Sync,
     E: EventEmitter + Send + Sync,
 {
+    /// Build the template context for a session: its variables, the built-ins
+    /// and the text content of session files referenced by the system prompt.
+    /// Files that are missing, binary or unreadable are left unresolved.
+    async fn template_context(&self, session: &Session, system_prompt: &str) -> TemplateContext {
+        let mut context = TemplateContext::for_session(session);
+        let Some(store) = &self.file_store else {
+            return context;
+        };
+
+        for path in PromptTemplate::parse(system_prompt).files() {
+            match store.read_file(session.id, path).await {
+                Ok(Some(file)) if !file.is_directory && file.encoding == "text" => {
+                    if let Some(content) = file.content {
+                        context = context.file(path, content);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(
+                        session_id = %session.id,
+                        path = %path,
+                        error = %e,
+                        "ReasonAtom: failed to read file for system prompt"
+                    );
+                }
+            }
+        }
+        context
+    }
+
     /// Execute the actual LLM call
     async fn execute_llm_call(
         &self,
@@ -359,20 +435,24 @@ where
         agent_id: Uuid,
         context: &AtomContext,
     ) -> Result<ReasonResult> {
-        // 1. Retrieve agent
-        let agent = self
-            .agent_store
-            .get_agent(agent_id)
-            .await?
-            .ok_or_else(|| AgentLoopError::agent_not_found(agent_id))?;
-
-        // 2. Retrieve session
+        // 1. Retrieve session
         let session = self
             .session_store
             .get_session(session_id)
             .await?
             .ok_or_else(|| AgentLoopError::session_not_found(session_id))?;
 
+        // 2. Retrieve agent at the session's pinned revision (latest if unpinned)
+        let agent = match session.agent_revision {
+            Some(revision) => {
+                self.agent_store
+                    .get_agent_revision(agent_id, revision)
+                    .await?
+            }
+            None => self.agent_store.get_agent(agent_id).await?,
+        }
+        .ok_or_else(|| AgentLoopError::agent_not_found(agent_id))?;
+
         // 3. Load messages (needed for controls.model_id extraction)
         let messages = self.message_store.load(session_id).await?;
 
@@ -380,22 +460,25 @@ where
             return Err(AgentLoopError::NoMessages);
         }
 
-        // 4. Extract model_id from the last user message's controls (highest priority)
-        let controls_model_id = messages
+        // 4. Extract controls from the last user message (highest priority)
+        let controls = messages
             .iter()
             .rev()
             .find(|m| m.role == MessageRole::User)
-            .and_then(|m| m.controls.as_ref())
-            .and_then(|c| c.model_id);
+            .and_then(|m| m.controls.clone())
+            .unwrap_or_default();
+        let controls_model_id = controls.model_id;
 
         // 5. Resolve model using chain: controls.model_id > session.model_id > agent.default_model_id
         let model_with_provider = self
             .resolve_model(controls_model_id, session.model_id, agent.default_model_id)
             .await?;
 
-        // 6. Build runtime agent from agent with capabilities applied
+        // 6. Build runtime agent from agent with capabilities applied,
+        //    rendering the system prompt template for this session
+        let template_context = self.template_context(&session, &agent.system_prompt).await;
         let runtime_agent = RuntimeAgentBuilder::new()
-            .with_agent(&agent, &self.capability_registry)
+            .with_agent_context(&agent, &self.capability_registry, &template_context)
             .model(&model_with_provider.model)
             .build();
 
@@ -403,13 +486,7 @@ where
         let llm_driver = self.create_llm_driver(&model_with_provider)?;
 
         // 8. Extract reasoning effort from the last user message's controls
-        let reasoning_effort = messages
-            .iter()
-            .rev()
-            .find(|m| m.role == MessageRole::User)
-            .and_then(|m| m.controls.as_ref())
-            .and_then(|c| c.reasoning.as_ref())
-            .and_then(|r| r.effort.clone());
+        let reasoning_effort = controls.reasoning.as_ref().and_then(|r| r.effort.clone());
 
         // 9. Patch dangling tool calls (add cancelled results for tool calls without responses)
         let patched_messages = patch_dangling_tool_calls(&messages);
@@ -432,12 +509,149 @@ where
             llm_messages.push(msg.into());
         }
 
-        // 11. Build LLM call config with reasoning effort
+        // 11. Build LLM call config with reasoning effort, response format and
+        // tool settings (message controls override the agent's settings)
         let mut llm_config_builder = LlmCallConfigBuilder::from(&runtime_agent);
         if let Some(effort) = reasoning_effort.clone() {
             llm_config_builder = llm_config_builder.reasoning_effort(effort);
         }
+        if let Some(format) = controls.response_format.clone() {
+            llm_config_builder = llm_config_builder.response_format(format);
+        }
+        if let Some(tool_choice) = controls.tool_choice.clone() {
+            llm_config_builder =
+                llm_config_builder.tool_choice(step_tool_choice(tool_choice, &messages));
+        }
+        if let Some(parallel) = controls.parallel_tool_calls {
+            llm_config_builder = llm_config_builder.parallel_tool_calls(parallel);
+        }
         let llm_config = llm_config_builder.build();
+        let sequential_tool_calls = llm_config.parallel_tool_calls == Some(false);
+
+        // 12. Call the LLM; when a response format is set, validate the final
+        // answer and ask the model to repair it until it conforms
+        let mut repair_attempts = 0;
+        let (text, tool_calls, structured_output) = loop {
+            let (text, tool_calls) = self
+                .generate(
+                    &llm_driver,
+                    llm_messages.clone(),
+                    &llm_config,
+                    &runtime_agent,
+                    &model_with_provider,
+                    &patched_messages,
+                    context,
+                )
+                .await?;
+
+            // Intermediate tool-calling steps are not subject to the schema
+            let format = match &llm_config.response_format {
+                Some(format) if tool_calls.is_empty() => format,
+                _ => break (text, tool_calls, None),
+            };
+
+            match format.parse_output(&text) {
+                Ok(value) => break (text, tool_calls, Some(value)),
+                Err(errors) if repair_attempts < MAX_STRUCTURED_OUTPUT_REPAIR_ATTEMPTS => {
+                    repair_attempts += 1;
+                    tracing::warn!(
+                        session_id = %session_id,
+                        turn_id = %context.turn_id,
+                        attempt = repair_attempts,
+                        errors = ?errors,
+                        "ReasonAtom: response did not match schema, requesting repair"
+                    );
+                    llm_messages.push(LlmMessage::text(LlmMessageRole::Assistant, text));
+                    llm_messages.push(LlmMessage::text(
+                        LlmMessageRole::User,
+                        format.repair_prompt(&errors),
+                    ));
+                }
+                Err(errors) => {
+                    return Err(AgentLoopError::llm(format!(
+                        "Response did not match schema \"{}\" after {} repair attempts: {}",
+                        format.name,
+                        repair_attempts,
+                        errors.join("; ")
+                    )));
+                }
+            }
+        };
+
+        // 13. Build metadata with model and reasoning effort info
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(
+            "model".to_string(),
+            serde_json::Value::String(runtime_agent.model.clone()),
+        );
+        if let Some(ref effort) = reasoning_effort {
+            metadata.insert(
+                "reasoning_effort".to_string(),
+                serde_json::Value::String(effort.clone()),
+            );
+        }
+
+        // 14. Store and emit message.agent event with metadata
+        let has_tool_calls = !tool_calls.is_empty();
+        let mut assistant_message = if has_tool_calls {
+            Message::assistant_with_tools(&text, tool_calls.clone())
+        } else {
+            Message::assistant(&text)
+        };
+        assistant_message.metadata = Some(metadata);
+
+        // Store message (no-op in production via DbMessageStore, but needed for InMemoryMessageStore in tests)
+        self.message_store
+            .store(session_id, assistant_message.clone())
+            .await?;
+
+        // Emit message.agent event (this stores the message as an event with proper turn context)
+        let message_event_context = EventContext::from_atom_context(context);
+        let mut message_data = MessageAgentData::new(assistant_message);
+        if let Some(value) = structured_output {
+            message_data = message_data.with_structured_output(value);
+        }
+        self.event_emitter
+            .emit(EventRequest::new(
+                session_id,
+                message_event_context,
+                message_data,
+            ))
+            .await?;
+
+        tracing::info!(
+            session_id = %session_id,
+            turn_id = %context.turn_id,
+            has_tool_calls = %has_tool_calls,
+            tool_count = %tool_calls.len(),
+            "ReasonAtom: LLM call completed"
+        );
+
+        Ok(ReasonResult {
+            success: true,
+            text,
+            tool_calls,
+            has_tool_calls,
+            tool_definitions: runtime_agent.tools.clone(),
+            max_iterations: runtime_agent.max_iterations,
+            sequential_tool_calls,
+            error: None,
+        })
+    }
+
+    /// Stream one LLM completion and emit the llm.generation event for it
+    #[allow(clippy::too_many_arguments)]
+    async fn generate(
+        &self,
+        llm_driver: &BoxedLlmDriver,
+        llm_messages: Vec<LlmMessage>,
+        llm_config: &LlmCallConfig,
+        runtime_agent: &RuntimeAgent,
+        model_with_provider: &ModelWithProvider,
+        patched_messages: &[Message],
+        context: &AtomContext,
+    ) -> Result<(String, Vec<ToolCall>)> {
+        let session_id = context.session_id;
 
         tracing::debug!(
             session_id = %session_id,
@@ -447,14 +661,39 @@ where
             "ReasonAtom: calling LLM"
         );
 
+        // Wait for shared provider capacity; providers without an ID are not limited
+        let rate_limiter = self
+            .rate_limiter
+            .as_ref()
+            .zip(model_with_provider.provider_id);
+        if let Some((rate_limiter, provider_id)) = rate_limiter {
+            rate_limiter
+                .acquire(provider_id, estimate_tokens(&llm_messages, llm_config))
+                .await?;
+        }
+
         // Track LLM call timing
         let llm_start = Instant::now();
 
-        let mut stream = llm_driver
-            .chat_completion_stream(llm_messages, &llm_config)
-            .await?;
+        let stream = llm_driver
+            .chat_completion_stream(llm_messages, llm_config)
+            .await;
 
-        // 12. Process stream
+        // Report rate limit headers (including from 429s) so all workers adapt
+        if let Some((rate_limiter, provider_id)) = rate_limiter {
+            if let Some(rate_limit) = llm_driver.last_rate_limit() {
+                if let Err(e) = rate_limiter.observe(provider_id, &rate_limit).await {
+                    tracing::warn!(
+                        session_id = %session_id,
+                        error = %e,
+                        "ReasonAtom: failed to report rate limit"
+                    );
+                }
+            }
+        }
+        let mut stream = stream?;
+
+        // Process stream
         let mut text = String::new();
         let mut tool_calls = Vec::new();
 
@@ -481,7 +720,7 @@ where
                             session_id,
                             event_context,
                             LlmGenerationData::failure(
-                                patched_messages.clone(),
+                                patched_messages.to_vec(),
                                 tools_summary,
                                 runtime_agent.model.clone(),
                                 Some(model_with_provider.provider_type.to_string()),
@@ -497,7 +736,7 @@ where
 
         let llm_duration_ms = llm_start.elapsed().as_millis() as u64;
 
-        // 13. Emit llm.generation event
+        // Emit llm.generation event
         let event_context = EventContext::from_atom_context(context);
         let tools_summary: Vec<ToolDefinitionSummary> =
             runtime_agent.tools.iter().map(|t| t.into()).collect();
@@ -507,7 +746,7 @@ where
                 session_id,
                 event_context,
                 LlmGenerationData::success(
-                    patched_messages.clone(),
+                    patched_messages.to_vec(),
                     tools_summary,
                     Some(text.clone()).filter(|s| !s.is_empty()),
                     tool_calls.clone(),
@@ -526,60 +765,7 @@ where
             );
         }
 
-        // 14. Build metadata with model and reasoning effort info
-        let mut metadata = std::collections::HashMap::new();
-        metadata.insert(
-            "model".to_string(),
-            serde_json::Value::String(runtime_agent.model.clone()),
-        );
-        if let Some(ref effort) = reasoning_effort {
-            metadata.insert(
-                "reasoning_effort".to_string(),
-                serde_json::Value::String(effort.clone()),
-            );
-        }
-
-        // 14. Store and emit message.agent event with metadata
-        let has_tool_calls = !tool_calls.is_empty();
-        let mut assistant_message = if has_tool_calls {
-            Message::assistant_with_tools(&text, tool_calls.clone())
-        } else {
-            Message::assistant(&text)
-        };
-        assistant_message.metadata = Some(metadata);
-
-        // Store message (no-op in production via DbMessageStore, but needed for InMemoryMessageStore in tests)
-        self.message_store
-            .store(session_id, assistant_message.clone())
-            .await?;
-
-        // Emit message.agent event (this stores the message as an event with proper turn context)
-        let message_event_context = EventContext::from_atom_context(context);
-        self.event_emitter
-            .emit(EventRequest::new(
-                session_id,
-                message_event_context,
-                MessageAgentData::new(assistant_message),
-            ))
-            .await?;
-
-        tracing::info!(
-            session_id = %session_id,
-            turn_id = %context.turn_id,
-            has_tool_calls = %has_tool_calls,
-            tool_count = %tool_calls.len(),
-            "ReasonAtom: LLM call completed"
-        );
-
-        Ok(ReasonResult {
-            success: true,
-            text,
-            tool_calls,
-            has_tool_calls,
-            tool_definitions: runtime_agent.tools.clone(),
-            max_iterations: runtime_agent.max_iterations,
-            error: None,
-        })
+        Ok((text, tool_calls))
     }
 
     /// Resolve model using priority chain
@@ -634,10 +820,7 @@ where
     }
 
     /// Create LLM driver using the driver registry
-    fn create_llm_driver(
-        &self,
-        model: &ModelWithProvider,
-    ) -> Result<crate::llm_driver_registry::BoxedLlmDriver> {
+    fn create_llm_driver(&self, model: &ModelWithProvider) -> Result<BoxedLlmDriver> {
         let provider_type = match model.provider_type {
             crate::llm_models::LlmProviderType::Openai => ProviderType::OpenAI,
             crate::llm_models::LlmProviderType::Anthropic => ProviderType::Anthropic,
@@ -661,6 +844,23 @@ where
 // Tests
 // ============================================================================
 
+/// Tool choice for this reason step of the turn
+///
+/// A forced choice (`required` or a named tool) applies to the first step
+/// only; later steps use `auto` so the model can answer with the tool
+/// results instead of calling tools until the iteration limit.
+fn step_tool_choice(choice: ToolChoice, messages: &[Message]) -> ToolChoice {
+    let first_step = !messages
+        .iter()
+        .rev()
+        .take_while(|m| m.role != MessageRole::User)
+        .any(|m| m.role == MessageRole::Assistant);
+    match choice {
+        ToolChoice::Required | ToolChoice::Tool { .. } if !first_step => ToolChoice::Auto,
+        choice => choice,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -684,6 +884,20 @@ mod tests {
         assert_eq!(result.max_iterations, 100);
     }
 
+    #[test]
+    fn test_estimate_tokens() {
+        let messages = vec![
+            LlmMessage::text(LlmMessageRole::System, "a".repeat(40)),
+            LlmMessage::text(LlmMessageRole::User, "b".repeat(2)),
+        ];
+        let mut config = LlmCallConfig::from(&RuntimeAgentBuilder::new().build());
+        config.max_tokens = None;
+        assert_eq!(estimate_tokens(&messages, &config), 11);
+
+        config.max_tokens = Some(100);
+        assert_eq!(estimate_tokens(&messages, &config), 111);
+    }
+
     #[test]
     fn test_patch_dangling_tool_calls_no_tool_calls() {
         let messages = vec![Message::user("Hello"), Message::assistant("Hi there!")];
@@ -729,4 +943,41 @@ mod tests {
         assert_eq!(patched[2].role, MessageRole::ToolResult);
         assert_eq!(patched[2].tool_call_id(), Some("call_456"));
     }
+
+    #[test]
+    fn test_step_tool_choice_forces_first_step_only() {
+        let tool_call = ToolCall {
+            id: "call_789".to_string(),
+            name: "get_weather".to_string(),
+            arguments: serde_json::json!({"city": "Oslo"}),
+        };
+        let mut messages = vec![Message::user("What's the weather?")];
+
+        assert_eq!(
+            step_tool_choice(ToolChoice::Required, &messages),
+            ToolChoice::Required
+        );
+
+        messages.push(Message::assistant_with_tools("Checking", vec![tool_call]));
+        messages.push(Message::tool_result(
+            "call_789",
+            Some(serde_json::json!({"temp": 4})),
+            None,
+        ));
+        assert_eq!(
+            step_tool_choice(ToolChoice::Required, &messages),
+            ToolChoice::Auto
+        );
+        assert_eq!(
+            step_tool_choice(ToolChoice::None, &messages),
+            ToolChoice::None
+        );
+
+        // A new user message starts a new turn
+        messages.push(Message::user("And tomorrow?"));
+        let named = ToolChoice::Tool {
+            name: "get_weather".to_string(),
+        };
+        assert_eq!(step_tool_choice(named.clone(), &messages), named);
+    }
 }
@@ -14,6 +14,9 @@
 //! - `warehouse_create_invoice`: Generate an invoice
 //! - `warehouse_process_return`: Process a product return
 //! - `warehouse_inventory_report`: Generate inventory report
+//!
+//! Configuration: `{"inventory": [...]}` seeds the initial inventory of new
+//! sessions instead of the built-in sample products.
 
 use super::{Capability, CapabilityId, CapabilityStatus};
 use crate::tools::{Tool, ToolExecutionResult};
@@ -76,8 +79,41 @@ Data structure:
     }
 
     fn tools(&self) -> Vec<Box<dyn Tool>> {
+        self.configured_tools(&Value::Null)
+    }
+
+    fn config_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "inventory": {
+                    "type": "array",
+                    "description": "Initial inventory of new sessions",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "sku": {"type": "string", "minLength": 1},
+                            "name": {"type": "string"},
+                            "quantity": {"type": "integer", "minimum": 0},
+                            "location": {"type": "string"},
+                            "reorder_point": {"type": "integer", "minimum": 0}
+                        },
+                        "required": ["sku", "name", "quantity"],
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn configured_tools(&self, config: &Value) -> Vec<Box<dyn Tool>> {
+        let inventory = config
+            .get("inventory")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+
         vec![
-            Box::new(WarehouseGetInventoryTool),
+            Box::new(WarehouseGetInventoryTool { inventory }),
             Box::new(WarehouseUpdateInventoryTool),
             Box::new(WarehouseCreateShipmentTool),
             Box::new(WarehouseListShipmentsTool),
@@ -97,10 +133,39 @@ struct InventoryItem {
     sku: String,
     name: String,
     quantity: i32,
+    #[serde(default)]
     location: String,
+    #[serde(default)]
     reorder_point: i32,
 }
 
+/// Sample products used when no inventory is configured
+fn sample_inventory() -> Vec<InventoryItem> {
+    vec![
+        InventoryItem {
+            sku: "WH-001".to_string(),
+            name: "Industrial Widget".to_string(),
+            quantity: 150,
+            location: "A1".to_string(),
+            reorder_point: 50,
+        },
+        InventoryItem {
+            sku: "WH-002".to_string(),
+            name: "Premium Gadget".to_string(),
+            quantity: 30,
+            location: "B2".to_string(),
+            reorder_point: 40,
+        },
+        InventoryItem {
+            sku: "WH-003".to_string(),
+            name: "Standard Component".to_string(),
+            quantity: 200,
+            location: "C3".to_string(),
+            reorder_point: 75,
+        },
+    ]
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Shipment {
     id: String,
@@ -130,7 +195,18 @@ struct Order {
 // Tool: warehouse_get_inventory
 // ============================================================================
 
-pub struct WarehouseGetInventoryTool;
+/// Reads the session inventory, creating it from the seed on first use
+#[derive(Default)]
+pub struct WarehouseGetInventoryTool {
+    /// Configured initial inventory (sample products when unset)
+    inventory: Option<Vec<InventoryItem>>,
+}
+
+impl WarehouseGetInventoryTool {
+    fn initial_inventory(&self) -> Vec<InventoryItem> {
+        self.inventory.clone().unwrap_or_else(sample_inventory)
+    }
+}
 
 #[async_trait]
 impl Tool for WarehouseGetInventoryTool {
@@ -187,57 +263,10 @@ impl Tool for WarehouseGetInventoryTool {
             .await
         {
             Ok(Some(file)) => serde_json::from_str(file.content.as_deref().unwrap_or(""))
-                .unwrap_or_else(|_| {
-                    // Initialize with sample data
-                    vec![
-                        InventoryItem {
-                            sku: "WH-001".to_string(),
-                            name: "Industrial Widget".to_string(),
-                            quantity: 150,
-                            location: "A1".to_string(),
-                            reorder_point: 50,
-                        },
-                        InventoryItem {
-                            sku: "WH-002".to_string(),
-                            name: "Premium Gadget".to_string(),
-                            quantity: 30,
-                            location: "B2".to_string(),
-                            reorder_point: 40,
-                        },
-                        InventoryItem {
-                            sku: "WH-003".to_string(),
-                            name: "Standard Component".to_string(),
-                            quantity: 200,
-                            location: "C3".to_string(),
-                            reorder_point: 75,
-                        },
-                    ]
-                }),
+                .unwrap_or_else(|_| self.initial_inventory()),
             _ => {
                 // Create initial inventory
-                let initial = vec![
-                    InventoryItem {
-                        sku: "WH-001".to_string(),
-                        name: "Industrial Widget".to_string(),
-                        quantity: 150,
-                        location: "A1".to_string(),
-                        reorder_point: 50,
-                    },
-                    InventoryItem {
-                        sku: "WH-002".to_string(),
-                        name: "Premium Gadget".to_string(),
-                        quantity: 30,
-                        location: "B2".to_string(),
-                        reorder_point: 40,
-                    },
-                    InventoryItem {
-                        sku: "WH-003".to_string(),
-                        name: "Standard Component".to_string(),
-                        quantity: 200,
-                        location: "C3".to_string(),
-                        reorder_point: 75,
-                    },
-                ];
+                let initial = self.initial_inventory();
 
                 // Save initial data
                 let content = serde_json::to_string_pretty(&initial).unwrap();
@@ -4,22 +4,68 @@
 //! Each session has its own isolated filesystem stored in the database.
 //!
 //! Tools provided:
-//! - `read_file`: Read file content
+//! - `read_file`: Read file content, optionally a range of lines
 //! - `write_file`: Create or update a file
+//! - `edit_file`: Replace exact strings in one or more files
+//! - `apply_patch`: Apply a unified diff to one or more files
 //! - `list_directory`: List files in a directory
 //! - `grep_files`: Search files by regex pattern
+//! - `glob_files`: Find files by path pattern
 //! - `delete_file`: Delete a file or directory
 //! - `stat_file`: Get file metadata
+//! - `list_file_versions`: List a file's version history
+//! - `diff_file`: Diff two versions of a file
+//! - `restore_file`: Restore a file to an earlier version
+//! - `list_snapshots`: List snapshots of the file tree
+//! - `create_snapshot`: Snapshot the current file tree
+//! - `restore_snapshot`: Restore the whole file tree to a snapshot
+//!
+//! Multi-edit `edit_file` calls and multi-file patches are applied through
+//! `SessionFileStore::apply_changes`, so either every file changes or none.
+//!
+//! Configuration: `{"read_only": true}` only provides the reading tools
+//! (`read_file`, `list_directory`, `grep_files`, `glob_files`, `stat_file`,
+//! `list_file_versions`, `diff_file`, `list_snapshots`).
 
 use super::{Capability, CapabilityId, CapabilityStatus};
+use crate::error::AgentLoopError;
+use crate::file_edit::{line_window, parse_patch, replace_exact};
+use crate::session_file::{FileChange, FileInfo, RestoreTarget};
 use crate::tools::{Tool, ToolExecutionResult};
-use crate::traits::ToolContext;
+use crate::traits::{SessionFileStore, ToolContext};
 use async_trait::async_trait;
 use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
 
 /// Session File System capability - provides file operations for session storage
 pub struct FileSystemCapability;
 
+/// System prompt addition when the agent may only read files
+const READ_ONLY_SYSTEM_PROMPT: &str = r#"You have read-only access to the session file system. Each session has its own isolated filesystem stored in the database. You cannot create, modify or delete files.
+
+Available tools:
+- `read_file`: Read the content of a file by path, optionally a range of lines
+- `list_directory`: List files and directories at a given path
+- `grep_files`: Search file contents using regex patterns
+- `glob_files`: Find files by path pattern (e.g., `**/*.md`)
+- `stat_file`: Get metadata about a file (size, dates, etc.)
+- `list_file_versions`: List the version history of a file
+- `diff_file`: Show what changed between two versions of a file
+- `list_snapshots`: List snapshots of the file tree
+
+Best practices:
+- Use `list_directory` first to explore the filesystem structure
+- Use `grep_files` to search across multiple files efficiently
+- The root directory is `/` - all paths should be absolute"#;
+
+fn is_read_only(config: &Value) -> bool {
+    config
+        .get("read_only")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
 impl Capability for FileSystemCapability {
     fn id(&self) -> &str {
         CapabilityId::FILE_SYSTEM
@@ -50,19 +96,31 @@ impl Capability for FileSystemCapability {
             r#"You have access to file system tools for working with the session file system. Each session has its own isolated filesystem stored in the database.
 
 Available tools:
-- `read_file`: Read the content of a file by path
-- `write_file`: Create a new file or update existing file content
+- `read_file`: Read the content of a file by path, optionally a range of lines
+- `write_file`: Create a new file or replace a file's content
+- `edit_file`: Replace exact text in one or more files
+- `apply_patch`: Apply a unified diff to one or more files
 - `list_directory`: List files and directories at a given path
 - `grep_files`: Search file contents using regex patterns
+- `glob_files`: Find files by path pattern (e.g., `**/*.md`)
 - `delete_file`: Delete a file or directory
 - `stat_file`: Get metadata about a file (size, dates, etc.)
+- `list_file_versions`: List the version history of a file
+- `diff_file`: Show what changed between two versions of a file
+- `restore_file`: Restore a file to an earlier version
+- `list_snapshots`: List snapshots of the file tree (taken at every turn start and end)
+- `create_snapshot`: Save a named snapshot of the current file tree
+- `restore_snapshot`: Restore the whole file tree to a snapshot
 
 Best practices:
 - Use `list_directory` first to explore the filesystem structure
 - Use `stat_file` to check if a file exists before reading/writing
 - Use `grep_files` to search across multiple files efficiently
 - The root directory is `/` - all paths should be absolute
-- Directories are created automatically when writing files"#,
+- Directories are created automatically when writing files
+- Prefer `edit_file` or `apply_patch` over `write_file` to change part of an existing file
+- Use `offset` and `limit` with `read_file` to page through large files
+- Every write is versioned, so use `diff_file` and `restore_file` to review or undo changes"#,
         )
     }
 
@@ -70,10 +128,56 @@ Best practices:
         vec![
             Box::new(ReadFileTool),
             Box::new(WriteFileTool),
+            Box::new(EditFileTool),
+            Box::new(ApplyPatchTool),
             Box::new(ListDirectoryTool),
             Box::new(GrepFilesTool),
+            Box::new(GlobFilesTool),
             Box::new(DeleteFileTool),
             Box::new(StatFileTool),
+            Box::new(ListFileVersionsTool),
+            Box::new(DiffFileTool),
+            Box::new(RestoreFileTool),
+            Box::new(ListSnapshotsTool),
+            Box::new(CreateSnapshotTool),
+            Box::new(RestoreSnapshotTool),
+        ]
+    }
+
+    fn config_schema(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "read_only": {
+                    "type": "boolean",
+                    "description": "Only provide tools that read files"
+                }
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn configured_system_prompt_addition(&self, config: &Value) -> Option<String> {
+        if is_read_only(config) {
+            Some(READ_ONLY_SYSTEM_PROMPT.to_string())
+        } else {
+            self.system_prompt_addition().map(str::to_string)
+        }
+    }
+
+    fn configured_tools(&self, config: &Value) -> Vec<Box<dyn Tool>> {
+        if !is_read_only(config) {
+            return self.tools();
+        }
+        vec![
+            Box::new(ReadFileTool),
+            Box::new(ListDirectoryTool),
+            Box::new(GrepFilesTool),
+            Box::new(GlobFilesTool),
+            Box::new(StatFileTool),
+            Box::new(ListFileVersionsTool),
+            Box::new(DiffFileTool),
+            Box::new(ListSnapshotsTool),
         ]
     }
 }
@@ -92,7 +196,7 @@ impl Tool for ReadFileTool {
     }
 
     fn description(&self) -> &str {
-        "Read the content of a file. Returns the file content as text or base64-encoded binary."
+        "Read the content of a file. Returns the file content as text or base64-encoded binary. Use offset and limit to read part of a large text file."
     }
 
     fn parameters_schema(&self) -> Value {
@@ -102,6 +206,16 @@ impl Tool for ReadFileTool {
                 "path": {
                     "type": "string",
                     "description": "Absolute path to the file (e.g., '/docs/readme.txt')"
+                },
+                "offset": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Line number to start reading from (1-based, text files only)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Maximum number of lines to read (text files only)"
                 }
             },
             "required": ["path"],
@@ -125,6 +239,15 @@ impl Tool for ReadFileTool {
             None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
         };
 
+        let offset = arguments
+            .get("offset")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize);
+        let limit = arguments
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize);
+
         let file_store = match &context.file_store {
             Some(store) => store,
             None => {
@@ -139,6 +262,24 @@ impl Tool for ReadFileTool {
                         "Path '{}' is a directory, not a file. Use list_directory instead.",
                         path
                     ))
+                } else if offset.is_some() || limit.is_some() {
+                    if file.encoding != "text" {
+                        return ToolExecutionResult::tool_error(format!(
+                            "offset and limit only apply to text files; '{}' is binary",
+                            path
+                        ));
+                    }
+                    let window = line_window(file.content.as_deref().unwrap_or(""), offset, limit);
+                    ToolExecutionResult::success(json!({
+                        "path": file.path,
+                        "content": window.content,
+                        "encoding": file.encoding,
+                        "size_bytes": file.size_bytes,
+                        "start_line": window.start_line,
+                        "end_line": window.end_line,
+                        "total_lines": window.total_lines,
+                        "has_more": window.has_more()
+                    }))
                 } else {
                     ToolExecutionResult::success(json!({
                         "path": file.path,
@@ -258,6 +399,353 @@ impl Tool for WriteFileTool {
     }
 }
 
+// ============================================================================
+// EditFileTool
+// ============================================================================
+
+/// Tool to replace exact strings in one or more files
+pub struct EditFileTool;
+
+#[async_trait]
+impl Tool for EditFileTool {
+    fn name(&self) -> &str {
+        "edit_file"
+    }
+
+    fn description(&self) -> &str {
+        "Replace an exact string in a text file without rewriting the whole file. old_string must match exactly once unless replace_all is set. Pass `edits` to change several places or files at once; either all edits are applied or none."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        let edit_properties = json!({
+            "path": {
+                "type": "string",
+                "description": "Absolute path to the file"
+            },
+            "old_string": {
+                "type": "string",
+                "description": "Exact text to replace, including enough surrounding context to be unique"
+            },
+            "new_string": {
+                "type": "string",
+                "description": "Replacement text"
+            },
+            "replace_all": {
+                "type": "boolean",
+                "default": false,
+                "description": "Replace every occurrence instead of requiring a unique match"
+            }
+        });
+
+        let mut properties = edit_properties.clone();
+        properties["edits"] = json!({
+            "type": "array",
+            "description": "Several edits applied in order, atomically. Use instead of the single-edit parameters.",
+            "items": {
+                "type": "object",
+                "properties": edit_properties,
+                "required": ["path", "old_string", "new_string"],
+                "additionalProperties": false
+            }
+        });
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "edit_file requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let edits: Vec<&Value> = match arguments.get("edits").and_then(|v| v.as_array()) {
+            Some(edits) if !edits.is_empty() => edits.iter().collect(),
+            Some(_) => return ToolExecutionResult::tool_error("edits must not be empty"),
+            None => vec![&arguments],
+        };
+
+        // Validate every edit before touching the store
+        let mut parsed = Vec::with_capacity(edits.len());
+        for (index, edit) in edits.iter().enumerate() {
+            let field = |name: &str| edit.get(name).and_then(|v| v.as_str());
+            let (Some(path), Some(old_string), Some(new_string)) =
+                (field("path"), field("old_string"), field("new_string"))
+            else {
+                return ToolExecutionResult::tool_error(format!(
+                    "Edit {}: path, old_string and new_string are required",
+                    index + 1
+                ));
+            };
+            let replace_all = edit
+                .get("replace_all")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let path = match checked_path(path) {
+                Ok(path) => path,
+                Err(e) => return e,
+            };
+            parsed.push((path, old_string, new_string, replace_all));
+        }
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        // Apply every edit in memory first, in order, grouped by file
+        let mut files: Vec<(String, String)> = Vec::new();
+        let mut replacements = 0;
+        for (index, (path, old_string, new_string, replace_all)) in parsed.into_iter().enumerate() {
+            let position = match files.iter().position(|(p, _)| *p == path) {
+                Some(position) => position,
+                None => match read_text(file_store, context.session_id, &path).await {
+                    Ok(content) => {
+                        files.push((path.clone(), content));
+                        files.len() - 1
+                    }
+                    Err(e) => return e,
+                },
+            };
+
+            let content = &mut files[position].1;
+            match replace_exact(&path, content, old_string, new_string, replace_all) {
+                Ok((updated, count)) => {
+                    *content = updated;
+                    replacements += count;
+                }
+                Err(e) => {
+                    return ToolExecutionResult::tool_error(if edits.len() > 1 {
+                        format!("Edit {}: {}. No files were changed.", index + 1, e)
+                    } else {
+                        e.to_string()
+                    })
+                }
+            }
+        }
+
+        let changes = files
+            .into_iter()
+            .map(|(path, content)| FileChange::Write {
+                path,
+                content,
+                encoding: "text".to_string(),
+            })
+            .collect();
+        match file_store.apply_changes(context.session_id, changes).await {
+            Ok(written) => ToolExecutionResult::success(json!({
+                "files": written
+                    .iter()
+                    .map(|f| json!({"path": f.path, "size_bytes": f.size_bytes}))
+                    .collect::<Vec<_>>(),
+                "replacements": replacements
+            })),
+            Err(e) => change_error(e),
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// ApplyPatchTool
+// ============================================================================
+
+/// Tool to apply a unified diff to one or more files
+pub struct ApplyPatchTool;
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        "apply_patch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff (as produced by `diff -u` or `git diff`) to one or more files. Supports creating (--- /dev/null), deleting (+++ /dev/null) and renaming files. Either the whole patch applies or no files are changed."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff with '--- path' and '+++ path' headers followed by @@ hunks"
+                }
+            },
+            "required": ["patch"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "apply_patch requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let patch = match arguments.get("patch").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: patch"),
+        };
+
+        let patches = match parse_patch(patch) {
+            Ok(patches) => patches,
+            Err(e) => return ToolExecutionResult::tool_error(e.to_string()),
+        };
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        let mut changes = Vec::new();
+        let mut deleted = Vec::new();
+        for file_patch in &patches {
+            for path in [&file_patch.old_path, &file_patch.new_path]
+                .into_iter()
+                .flatten()
+            {
+                if let Err(e) = checked_path(path) {
+                    return e;
+                }
+            }
+
+            let original = match &file_patch.old_path {
+                Some(old_path) => match read_text(file_store, context.session_id, old_path).await {
+                    Ok(content) => content,
+                    Err(e) => return e,
+                },
+                None => String::new(),
+            };
+
+            if file_patch.is_delete() {
+                let path = file_patch.path().to_string();
+                deleted.push(path.clone());
+                changes.push(FileChange::Delete { path });
+                continue;
+            }
+
+            // New paths (created or renamed-to) must not already exist
+            if file_patch.is_create() || file_patch.is_rename() {
+                let path = file_patch.path();
+                match file_store.stat_file(context.session_id, path).await {
+                    Ok(None) => {}
+                    Ok(Some(_)) => {
+                        return ToolExecutionResult::tool_error(format!(
+                            "Cannot create {}: file already exists. No files were changed.",
+                            path
+                        ))
+                    }
+                    Err(e) => return ToolExecutionResult::internal_error(e),
+                }
+            }
+
+            let content = match file_patch.apply(&original) {
+                Ok(content) => content,
+                Err(e) => {
+                    return ToolExecutionResult::tool_error(format!(
+                        "{}. No files were changed.",
+                        e
+                    ))
+                }
+            };
+            if let (true, Some(old_path)) = (file_patch.is_rename(), &file_patch.old_path) {
+                changes.push(FileChange::Delete {
+                    path: old_path.clone(),
+                });
+                deleted.push(old_path.clone());
+            }
+            changes.push(FileChange::Write {
+                path: file_patch.path().to_string(),
+                content,
+                encoding: "text".to_string(),
+            });
+        }
+
+        match file_store.apply_changes(context.session_id, changes).await {
+            Ok(written) => ToolExecutionResult::success(json!({
+                "files": written
+                    .iter()
+                    .map(|f| json!({"path": f.path, "size_bytes": f.size_bytes}))
+                    .collect::<Vec<_>>(),
+                "deleted": deleted
+            })),
+            Err(e) => change_error(e),
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+/// Normalize and validate a path from tool arguments
+fn checked_path(path: &str) -> Result<String, ToolExecutionResult> {
+    let path = FileInfo::normalize_path(path);
+    FileInfo::validate_path(&path)
+        .map_err(|e| ToolExecutionResult::tool_error(format!("{}: {}", e, path)))?;
+    Ok(path)
+}
+
+/// Read a text file's content for editing
+async fn read_text(
+    file_store: &Arc<dyn SessionFileStore>,
+    session_id: Uuid,
+    path: &str,
+) -> Result<String, ToolExecutionResult> {
+    match file_store.read_file(session_id, path).await {
+        Ok(Some(file)) if file.is_directory => Err(ToolExecutionResult::tool_error(format!(
+            "Path '{}' is a directory, not a file",
+            path
+        ))),
+        Ok(Some(file)) if file.encoding != "text" => Err(ToolExecutionResult::tool_error(format!(
+            "Cannot edit binary file: {}",
+            path
+        ))),
+        Ok(Some(file)) => Ok(file.content.unwrap_or_default()),
+        Ok(None) => Err(ToolExecutionResult::tool_error(format!(
+            "File not found: {}",
+            path
+        ))),
+        Err(e) => Err(ToolExecutionResult::internal_error(e)),
+    }
+}
+
+/// Map an apply_changes error to a tool result
+fn change_error(e: AgentLoopError) -> ToolExecutionResult {
+    let msg = e.to_string();
+    if msg.contains("readonly")
+        || msg.contains("directory")
+        || msg.contains("Duplicate path")
+        || msg.contains("not found")
+        || msg.contains("A file exists")
+    {
+        ToolExecutionResult::tool_error(format!("{}. No files were changed.", msg))
+    } else {
+        ToolExecutionResult::internal_error(e)
+    }
+}
+
 // ============================================================================
 // ListDirectoryTool
 // ============================================================================
@@ -448,44 +936,44 @@ impl Tool for GrepFilesTool {
 }
 
 // ============================================================================
-// DeleteFileTool
+// GlobFilesTool
 // ============================================================================
 
-/// Tool to delete a file or directory
-pub struct DeleteFileTool;
+/// Tool to find files by path pattern
+pub struct GlobFilesTool;
 
 #[async_trait]
-impl Tool for DeleteFileTool {
+impl Tool for GlobFilesTool {
     fn name(&self) -> &str {
-        "delete_file"
+        "glob_files"
     }
 
     fn description(&self) -> &str {
-        "Delete a file or directory. Use recursive=true to delete non-empty directories."
+        "Find files and directories by path pattern. `*` and `?` match within a path segment, `**` matches any number of directories and `{a,b}` matches either alternative (e.g., '**/*.{md,txt}')."
     }
 
     fn parameters_schema(&self) -> Value {
         json!({
             "type": "object",
             "properties": {
-                "path": {
+                "pattern": {
                     "type": "string",
-                    "description": "Path to the file or directory to delete"
+                    "description": "Glob pattern. Relative patterns are matched under `path`"
                 },
-                "recursive": {
-                    "type": "boolean",
-                    "default": false,
-                    "description": "If true, delete directories and all contents recursively"
+                "path": {
+                    "type": "string",
+                    "default": "/",
+                    "description": "Directory to search under (default: root '/')"
                 }
             },
-            "required": ["path"],
+            "required": ["pattern"],
             "additionalProperties": false
         })
     }
 
     async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
         ToolExecutionResult::tool_error(
-            "delete_file requires context. This tool must be executed with session context.",
+            "glob_files requires context. This tool must be executed with session context.",
         )
     }
 
@@ -494,15 +982,25 @@ impl Tool for DeleteFileTool {
         arguments: Value,
         context: &ToolContext,
     ) -> ToolExecutionResult {
-        let path = match arguments.get("path").and_then(|v| v.as_str()) {
+        let pattern = match arguments.get("pattern").and_then(|v| v.as_str()) {
             Some(p) => p,
-            None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
+            None => return ToolExecutionResult::tool_error("Missing required parameter: pattern"),
         };
 
-        let recursive = arguments
-            .get("recursive")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+        let base = match checked_path(
+            arguments
+                .get("path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("/"),
+        ) {
+            Ok(base) => base,
+            Err(e) => return e,
+        };
+        let pattern = if pattern.starts_with('/') {
+            pattern.to_string()
+        } else {
+            format!("{}/{}", base.trim_end_matches('/'), pattern)
+        };
 
         let file_store = match &context.file_store {
             Some(store) => store,
@@ -511,28 +1009,26 @@ impl Tool for DeleteFileTool {
             }
         };
 
-        match file_store
-            .delete_file(context.session_id, path, recursive)
-            .await
-        {
-            Ok(deleted) => {
-                if deleted {
-                    ToolExecutionResult::success(json!({
-                        "path": path,
-                        "deleted": true
-                    }))
-                } else {
-                    ToolExecutionResult::tool_error(format!("File not found: {}", path))
-                }
-            }
-            Err(e) => {
-                let msg = e.to_string();
-                if msg.contains("not empty") || msg.contains("recursive") {
-                    ToolExecutionResult::tool_error(msg)
-                } else {
-                    ToolExecutionResult::internal_error(e)
-                }
+        match file_store.glob_files(context.session_id, &pattern).await {
+            Ok(files) => {
+                let entries: Vec<Value> = files
+                    .iter()
+                    .map(|f| {
+                        json!({
+                            "path": f.path,
+                            "is_directory": f.is_directory,
+                            "size_bytes": f.size_bytes
+                        })
+                    })
+                    .collect();
+
+                ToolExecutionResult::success(json!({
+                    "pattern": pattern,
+                    "entries": entries,
+                    "count": entries.len()
+                }))
             }
+            Err(e) => ToolExecutionResult::internal_error(e),
         }
     }
 
@@ -542,20 +1038,379 @@ impl Tool for DeleteFileTool {
 }
 
 // ============================================================================
-// StatFileTool
+// DeleteFileTool
 // ============================================================================
 
-/// Tool to get file metadata
-pub struct StatFileTool;
+/// Tool to delete a file or directory
+pub struct DeleteFileTool;
 
 #[async_trait]
-impl Tool for StatFileTool {
+impl Tool for DeleteFileTool {
+    fn name(&self) -> &str {
+        "delete_file"
+    }
+
+    fn description(&self) -> &str {
+        "Delete a file or directory. Use recursive=true to delete non-empty directories."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file or directory to delete"
+                },
+                "recursive": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "If true, delete directories and all contents recursively"
+                }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "delete_file requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let path = match arguments.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
+        };
+
+        let recursive = arguments
+            .get("recursive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        match file_store
+            .delete_file(context.session_id, path, recursive)
+            .await
+        {
+            Ok(deleted) => {
+                if deleted {
+                    ToolExecutionResult::success(json!({
+                        "path": path,
+                        "deleted": true
+                    }))
+                } else {
+                    ToolExecutionResult::tool_error(format!("File not found: {}", path))
+                }
+            }
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("not empty") || msg.contains("recursive") {
+                    ToolExecutionResult::tool_error(msg)
+                } else {
+                    ToolExecutionResult::internal_error(e)
+                }
+            }
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// StatFileTool
+// ============================================================================
+
+/// Tool to get file metadata
+pub struct StatFileTool;
+
+#[async_trait]
+impl Tool for StatFileTool {
+    fn name(&self) -> &str {
+        "stat_file"
+    }
+
+    fn description(&self) -> &str {
+        "Get metadata about a file or directory (exists, size, type, dates)."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file or directory"
+                }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "stat_file requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let path = match arguments.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
+        };
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        match file_store.stat_file(context.session_id, path).await {
+            Ok(Some(stat)) => ToolExecutionResult::success(json!({
+                "path": stat.path,
+                "name": stat.name,
+                "exists": true,
+                "is_directory": stat.is_directory,
+                "is_readonly": stat.is_readonly,
+                "size_bytes": stat.size_bytes,
+                "created_at": stat.created_at.to_rfc3339(),
+                "updated_at": stat.updated_at.to_rfc3339()
+            })),
+            Ok(None) => ToolExecutionResult::success(json!({
+                "path": path,
+                "exists": false
+            })),
+            Err(e) => ToolExecutionResult::internal_error(e),
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// ListFileVersionsTool
+// ============================================================================
+
+/// Tool to list a file's version history
+pub struct ListFileVersionsTool;
+
+#[async_trait]
+impl Tool for ListFileVersionsTool {
+    fn name(&self) -> &str {
+        "list_file_versions"
+    }
+
+    fn description(&self) -> &str {
+        "List the version history of a file, newest first. Every write creates a new version; deletes are recorded as deleted versions."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file"
+                }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "list_file_versions requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let path = match arguments.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
+        };
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        match file_store
+            .list_file_versions(context.session_id, path)
+            .await
+        {
+            Ok(versions) if versions.is_empty() => {
+                ToolExecutionResult::tool_error(format!("No versions found for: {}", path))
+            }
+            Ok(versions) => {
+                let entries: Vec<Value> = versions
+                    .iter()
+                    .map(|v| {
+                        json!({
+                            "version": v.version,
+                            "size_bytes": v.size_bytes,
+                            "is_deleted": v.is_deleted,
+                            "created_at": v.created_at.to_rfc3339()
+                        })
+                    })
+                    .collect();
+
+                ToolExecutionResult::success(json!({
+                    "path": path,
+                    "versions": entries,
+                    "count": entries.len()
+                }))
+            }
+            Err(e) => ToolExecutionResult::internal_error(e),
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// DiffFileTool
+// ============================================================================
+
+/// Tool to diff two versions of a file
+pub struct DiffFileTool;
+
+#[async_trait]
+impl Tool for DiffFileTool {
+    fn name(&self) -> &str {
+        "diff_file"
+    }
+
+    fn description(&self) -> &str {
+        "Show a unified diff between two versions of a file. Defaults to the latest version compared with the one before it."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path to the file"
+                },
+                "from_version": {
+                    "type": "integer",
+                    "description": "Older version number (default: the version before to_version)"
+                },
+                "to_version": {
+                    "type": "integer",
+                    "description": "Newer version number (default: latest)"
+                }
+            },
+            "required": ["path"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "diff_file requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let path = match arguments.get("path").and_then(|v| v.as_str()) {
+            Some(p) => p,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
+        };
+
+        let from = arguments
+            .get("from_version")
+            .and_then(|v| v.as_i64())
+            .map(|v| v as i32);
+        let to = arguments
+            .get("to_version")
+            .and_then(|v| v.as_i64())
+            .map(|v| v as i32);
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        match file_store
+            .diff_file(context.session_id, path, from, to)
+            .await
+        {
+            Ok(diff) => ToolExecutionResult::success(json!({
+                "path": diff.path,
+                "from_version": diff.from_version,
+                "to_version": diff.to_version,
+                "diff": diff.diff,
+                "is_binary": diff.is_binary
+            })),
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("not found") || msg.contains("No earlier version") {
+                    ToolExecutionResult::tool_error(msg)
+                } else {
+                    ToolExecutionResult::internal_error(e)
+                }
+            }
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// RestoreFileTool
+// ============================================================================
+
+/// Tool to restore a file to an earlier version
+pub struct RestoreFileTool;
+
+#[async_trait]
+impl Tool for RestoreFileTool {
     fn name(&self) -> &str {
-        "stat_file"
+        "restore_file"
     }
 
     fn description(&self) -> &str {
-        "Get metadata about a file or directory (exists, size, type, dates)."
+        "Restore a file to an earlier version. The restored content is written as a new version, so the restore itself can be undone."
     }
 
     fn parameters_schema(&self) -> Value {
@@ -564,17 +1419,21 @@ impl Tool for StatFileTool {
             "properties": {
                 "path": {
                     "type": "string",
-                    "description": "Path to the file or directory"
+                    "description": "Path to the file"
+                },
+                "version": {
+                    "type": "integer",
+                    "description": "Version number to restore (see list_file_versions)"
                 }
             },
-            "required": ["path"],
+            "required": ["path", "version"],
             "additionalProperties": false
         })
     }
 
     async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
         ToolExecutionResult::tool_error(
-            "stat_file requires context. This tool must be executed with session context.",
+            "restore_file requires context. This tool must be executed with session context.",
         )
     }
 
@@ -588,6 +1447,11 @@ impl Tool for StatFileTool {
             None => return ToolExecutionResult::tool_error("Missing required parameter: path"),
         };
 
+        let version = match arguments.get("version").and_then(|v| v.as_i64()) {
+            Some(v) => v as i32,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: version"),
+        };
+
         let file_store = match &context.file_store {
             Some(store) => store,
             None => {
@@ -595,21 +1459,95 @@ impl Tool for StatFileTool {
             }
         };
 
-        match file_store.stat_file(context.session_id, path).await {
-            Ok(Some(stat)) => ToolExecutionResult::success(json!({
-                "path": stat.path,
-                "name": stat.name,
-                "exists": true,
-                "is_directory": stat.is_directory,
-                "is_readonly": stat.is_readonly,
-                "size_bytes": stat.size_bytes,
-                "created_at": stat.created_at.to_rfc3339(),
-                "updated_at": stat.updated_at.to_rfc3339()
-            })),
-            Ok(None) => ToolExecutionResult::success(json!({
-                "path": path,
-                "exists": false
+        match file_store
+            .restore_file(context.session_id, path, RestoreTarget::Version(version))
+            .await
+        {
+            Ok(file) => ToolExecutionResult::success(json!({
+                "path": file.path,
+                "restored_version": version,
+                "size_bytes": file.size_bytes
             })),
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("not found") || msg.contains("deleted") || msg.contains("readonly")
+                {
+                    ToolExecutionResult::tool_error(msg)
+                } else {
+                    ToolExecutionResult::internal_error(e)
+                }
+            }
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// ListSnapshotsTool
+// ============================================================================
+
+/// Tool to list snapshots of the session file tree
+pub struct ListSnapshotsTool;
+
+#[async_trait]
+impl Tool for ListSnapshotsTool {
+    fn name(&self) -> &str {
+        "list_snapshots"
+    }
+
+    fn description(&self) -> &str {
+        "List snapshots of the whole file tree, newest first. Snapshots are taken automatically at the start and end of every turn, and can also be created by name."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {},
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "list_snapshots requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        _arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        match file_store.list_snapshots(context.session_id).await {
+            Ok(snapshots) => {
+                let entries: Vec<Value> = snapshots
+                    .iter()
+                    .map(|s| {
+                        json!({
+                            "name": s.name,
+                            "kind": s.kind,
+                            "turn_number": s.turn_number,
+                            "file_count": s.file_count,
+                            "created_at": s.created_at.to_rfc3339()
+                        })
+                    })
+                    .collect();
+
+                ToolExecutionResult::success(json!({
+                    "snapshots": entries,
+                    "count": entries.len()
+                }))
+            }
             Err(e) => ToolExecutionResult::internal_error(e),
         }
     }
@@ -619,6 +1557,169 @@ impl Tool for StatFileTool {
     }
 }
 
+// ============================================================================
+// CreateSnapshotTool
+// ============================================================================
+
+/// Tool to snapshot the current session file tree
+pub struct CreateSnapshotTool;
+
+#[async_trait]
+impl Tool for CreateSnapshotTool {
+    fn name(&self) -> &str {
+        "create_snapshot"
+    }
+
+    fn description(&self) -> &str {
+        "Save a named snapshot of the whole file tree so it can be restored later with restore_snapshot."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Unique snapshot name (e.g., 'before-refactor')"
+                }
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "create_snapshot requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let name = match arguments.get("name").and_then(|v| v.as_str()) {
+            Some(n) => n,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: name"),
+        };
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        match file_store.create_snapshot(context.session_id, name).await {
+            Ok(snapshot) => ToolExecutionResult::success(json!({
+                "name": snapshot.name,
+                "file_count": snapshot.file_count,
+                "created": true
+            })),
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("already exists") || msg.contains("Invalid") {
+                    ToolExecutionResult::tool_error(msg)
+                } else {
+                    ToolExecutionResult::internal_error(e)
+                }
+            }
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
+// ============================================================================
+// RestoreSnapshotTool
+// ============================================================================
+
+/// Tool to restore the whole session file tree to a snapshot
+pub struct RestoreSnapshotTool;
+
+#[async_trait]
+impl Tool for RestoreSnapshotTool {
+    fn name(&self) -> &str {
+        "restore_snapshot"
+    }
+
+    fn description(&self) -> &str {
+        "Restore the whole file tree to a snapshot. Files created after the snapshot are deleted; every change is versioned and can be undone."
+    }
+
+    fn parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Name of the snapshot to restore (see list_snapshots)"
+                }
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> ToolExecutionResult {
+        ToolExecutionResult::tool_error(
+            "restore_snapshot requires context. This tool must be executed with session context.",
+        )
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ToolContext,
+    ) -> ToolExecutionResult {
+        let name = match arguments.get("name").and_then(|v| v.as_str()) {
+            Some(n) => n,
+            None => return ToolExecutionResult::tool_error("Missing required parameter: name"),
+        };
+
+        let file_store = match &context.file_store {
+            Some(store) => store,
+            None => {
+                return ToolExecutionResult::tool_error("File system not available in this context")
+            }
+        };
+
+        let snapshot = match file_store.list_snapshots(context.session_id).await {
+            Ok(snapshots) => snapshots.into_iter().find(|s| s.name == name),
+            Err(e) => return ToolExecutionResult::internal_error(e),
+        };
+        let Some(snapshot) = snapshot else {
+            return ToolExecutionResult::tool_error(format!("Snapshot not found: {}", name));
+        };
+
+        match file_store
+            .restore_snapshot(context.session_id, RestoreTarget::Snapshot(snapshot.id))
+            .await
+        {
+            Ok(snapshot) => ToolExecutionResult::success(json!({
+                "name": snapshot.name,
+                "file_count": snapshot.file_count,
+                "restored": true
+            })),
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("not found") {
+                    ToolExecutionResult::tool_error(msg)
+                } else {
+                    ToolExecutionResult::internal_error(e)
+                }
+            }
+        }
+    }
+
+    fn requires_context(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -638,15 +1739,24 @@ mod tests {
         let cap = FileSystemCapability;
         let tools = cap.tools();
 
-        assert_eq!(tools.len(), 6);
+        assert_eq!(tools.len(), 15);
 
         let tool_names: Vec<&str> = tools.iter().map(|t| t.name()).collect();
         assert!(tool_names.contains(&"read_file"));
         assert!(tool_names.contains(&"write_file"));
+        assert!(tool_names.contains(&"edit_file"));
+        assert!(tool_names.contains(&"apply_patch"));
         assert!(tool_names.contains(&"list_directory"));
         assert!(tool_names.contains(&"grep_files"));
+        assert!(tool_names.contains(&"glob_files"));
         assert!(tool_names.contains(&"delete_file"));
         assert!(tool_names.contains(&"stat_file"));
+        assert!(tool_names.contains(&"list_file_versions"));
+        assert!(tool_names.contains(&"diff_file"));
+        assert!(tool_names.contains(&"restore_file"));
+        assert!(tool_names.contains(&"list_snapshots"));
+        assert!(tool_names.contains(&"create_snapshot"));
+        assert!(tool_names.contains(&"restore_snapshot"));
     }
 
     #[test]
@@ -658,14 +1768,53 @@ mod tests {
         assert!(prompt.contains("list_directory"));
     }
 
+    #[test]
+    fn test_read_only_config() {
+        let cap = FileSystemCapability;
+        let config = json!({"read_only": true});
+
+        let tools = cap.configured_tools(&config);
+        let tool_names: Vec<&str> = tools.iter().map(|t| t.name()).collect();
+        assert_eq!(
+            tool_names,
+            vec![
+                "read_file",
+                "list_directory",
+                "grep_files",
+                "glob_files",
+                "stat_file",
+                "list_file_versions",
+                "diff_file",
+                "list_snapshots"
+            ]
+        );
+
+        let prompt = cap.configured_system_prompt_addition(&config).unwrap();
+        assert!(prompt.contains("read-only"));
+        assert!(!prompt.contains("write_file"));
+        assert!(!prompt.contains("restore_file"));
+        assert!(!prompt.contains("edit_file"));
+
+        assert_eq!(cap.configured_tools(&json!({})).len(), 15);
+    }
+
     #[test]
     fn test_tools_require_context() {
         assert!(ReadFileTool.requires_context());
         assert!(WriteFileTool.requires_context());
+        assert!(EditFileTool.requires_context());
+        assert!(ApplyPatchTool.requires_context());
         assert!(ListDirectoryTool.requires_context());
         assert!(GrepFilesTool.requires_context());
+        assert!(GlobFilesTool.requires_context());
         assert!(DeleteFileTool.requires_context());
         assert!(StatFileTool.requires_context());
+        assert!(ListFileVersionsTool.requires_context());
+        assert!(DiffFileTool.requires_context());
+        assert!(RestoreFileTool.requires_context());
+        assert!(ListSnapshotsTool.requires_context());
+        assert!(CreateSnapshotTool.requires_context());
+        assert!(RestoreSnapshotTool.requires_context());
     }
 
     #[tokio::test]
@@ -723,4 +1872,55 @@ mod tests {
             panic!("Expected tool error for missing file store");
         }
     }
+
+    #[tokio::test]
+    async fn test_restore_file_missing_version() {
+        let tool = RestoreFileTool;
+        let context = ToolContext::new(uuid::Uuid::nil());
+
+        let result = tool
+            .execute_with_context(json!({"path": "/test.txt"}), &context)
+            .await;
+
+        if let ToolExecutionResult::ToolError(msg) = result {
+            assert!(msg.contains("Missing required parameter: version"));
+        } else {
+            panic!("Expected tool error for missing version");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_edit_file_missing_fields() {
+        let tool = EditFileTool;
+        let context = ToolContext::new(uuid::Uuid::nil());
+
+        let result = tool
+            .execute_with_context(
+                json!({"edits": [{"path": "/a.txt", "old_string": "x"}]}),
+                &context,
+            )
+            .await;
+
+        if let ToolExecutionResult::ToolError(msg) = result {
+            assert!(msg.contains("Edit 1: path, old_string and new_string are required"));
+        } else {
+            panic!("Expected tool error for incomplete edit");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_invalid_patch() {
+        let tool = ApplyPatchTool;
+        let context = ToolContext::new(uuid::Uuid::nil());
+
+        let result = tool
+            .execute_with_context(json!({"patch": "not a diff"}), &context)
+            .await;
+
+        if let ToolExecutionResult::ToolError(msg) = result {
+            assert!(msg.contains("Invalid patch"));
+        } else {
+            panic!("Expected tool error for invalid patch");
+        }
+    }
 }
@@ -0,0 +1,422 @@
+//! HTTP Tool Capability - user-defined tools backed by HTTP endpoints
+//!
+//! Each tool defined through the API is exposed as its own capability
+//! (`custom:<name>`) containing a single tool that calls the endpoint.
+//!
+//! Design decisions:
+//! - Capabilities are built per agent by the worker from the definitions the
+//!   control-plane returns (with the auth secret decrypted)
+//! - 2xx responses are returned to the model as `{"status", "body"}`
+//! - 4xx responses and timeouts are tool errors so the model can correct its call
+//! - 5xx responses and connection failures are internal errors (details are logged,
+//!   not shown to the model)
+//! - Response bodies are truncated to keep the context small
+//! - Private, loopback and link-local targets are refused unless the worker's
+//!   operator allows them: every resolved address and redirect is checked, so
+//!   argument-controlled hosts and DNS names cannot reach internal services
+
+use super::{Capability, CapabilityStatus};
+use crate::http_tool::{
+    encode_url_value, render_template, value_to_text, HttpToolAuth, HttpToolConnection,
+    HttpToolMethod,
+};
+use crate::network_guard::{guard_client, literal_private_host, private_target_refused};
+use crate::tools::{Tool, ToolExecutionResult};
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Timeout for the whole request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum response body size returned to the model
+const MAX_BODY_CHARS: usize = 32 * 1024;
+
+/// Maximum number of redirects followed
+const MAX_REDIRECTS: usize = 10;
+
+/// Tool error returned when the guard refuses a request
+const PRIVATE_TARGET_MESSAGE: &str =
+    "The request targets a private network address, which is not allowed";
+
+/// Capability exposing one user-defined HTTP tool
+pub struct HttpToolCapability {
+    id: String,
+    tool: HttpTool,
+}
+
+impl HttpToolCapability {
+    pub fn new(connection: HttpToolConnection) -> Self {
+        Self {
+            id: connection.definition.capability_id(),
+            tool: HttpTool::new(connection),
+        }
+    }
+
+    /// Allow calls to private, loopback and link-local addresses
+    pub fn allow_private_networks(mut self, allow: bool) -> Self {
+        self.tool = self.tool.allow_private_networks(allow);
+        self
+    }
+}
+
+impl Capability for HttpToolCapability {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.tool.connection.definition.name
+    }
+
+    fn description(&self) -> &str {
+        &self.tool.connection.definition.description
+    }
+
+    fn status(&self) -> CapabilityStatus {
+        CapabilityStatus::Available
+    }
+
+    fn icon(&self) -> Option<&str> {
+        Some("webhook")
+    }
+
+    fn category(&self) -> Option<&str> {
+        Some("Custom")
+    }
+
+    fn tools(&self) -> Vec<Box<dyn Tool>> {
+        vec![Box::new(self.tool.clone())]
+    }
+}
+
+// ============================================================================
+// Tool: call the endpoint
+// ============================================================================
+
+/// Tool that calls the HTTP endpoint of a user-defined tool
+#[derive(Clone)]
+pub struct HttpTool {
+    connection: HttpToolConnection,
+    client: reqwest::Client,
+    allow_private_networks: bool,
+}
+
+impl HttpTool {
+    /// Create a tool that refuses private network targets
+    pub fn new(connection: HttpToolConnection) -> Self {
+        Self {
+            connection,
+            client: build_client(false),
+            allow_private_networks: false,
+        }
+    }
+
+    /// Allow calls to private, loopback and link-local addresses
+    pub fn allow_private_networks(mut self, allow: bool) -> Self {
+        self.client = build_client(allow);
+        self.allow_private_networks = allow;
+        self
+    }
+
+    fn build_request(&self, arguments: &Value) -> reqwest::RequestBuilder {
+        let endpoint = &self.connection.definition.endpoint;
+        let url = render_template(&endpoint.url, arguments, encode_url_value);
+
+        let method = match endpoint.method {
+            HttpToolMethod::Get => reqwest::Method::GET,
+            HttpToolMethod::Post => reqwest::Method::POST,
+            HttpToolMethod::Put => reqwest::Method::PUT,
+            HttpToolMethod::Patch => reqwest::Method::PATCH,
+            HttpToolMethod::Delete => reqwest::Method::DELETE,
+        };
+        let mut request = self.client.request(method, url).timeout(REQUEST_TIMEOUT);
+
+        for (name, value) in &endpoint.headers {
+            request = request.header(name, render_template(value, arguments, value_to_text));
+        }
+
+        if let Some(template) = &endpoint.body {
+            request = request
+                .header(CONTENT_TYPE, "application/json")
+                .body(render_template(template, arguments, |v| v.to_string()));
+        } else if endpoint.method.has_body() {
+            request = request.json(arguments);
+        }
+
+        match (&endpoint.auth, &self.connection.secret) {
+            (Some(HttpToolAuth::Bearer), Some(secret)) => request.bearer_auth(secret),
+            (Some(HttpToolAuth::Header { name }), Some(secret)) => request.header(name, secret),
+            (Some(HttpToolAuth::Basic { username }), Some(secret)) => {
+                request.basic_auth(username, Some(secret))
+            }
+            _ => request,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for HttpTool {
+    fn name(&self) -> &str {
+        &self.connection.definition.name
+    }
+
+    fn description(&self) -> &str {
+        &self.connection.definition.description
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.connection.definition.parameters.clone()
+    }
+
+    async fn execute(&self, arguments: Value) -> ToolExecutionResult {
+        let request = match self.build_request(&arguments).build() {
+            Ok(request) => request,
+            Err(e) => return ToolExecutionResult::tool_error(format!("Invalid request: {}", e)),
+        };
+        // IP literals are not resolved, so the resolver cannot check them
+        if !self.allow_private_networks && literal_private_host(request.url()).is_some() {
+            return ToolExecutionResult::tool_error(PRIVATE_TARGET_MESSAGE);
+        }
+
+        let response = match self.client.execute(request).await {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => {
+                return ToolExecutionResult::tool_error("The request timed out");
+            }
+            Err(e) if private_target_refused(&e) => {
+                return ToolExecutionResult::tool_error(PRIVATE_TARGET_MESSAGE);
+            }
+            Err(e) => return ToolExecutionResult::internal_error(e),
+        };
+
+        let status = response.status();
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(e) => return ToolExecutionResult::internal_error(e),
+        };
+        let body = parse_body(text);
+
+        if status.is_success() {
+            ToolExecutionResult::success(json!({ "status": status.as_u16(), "body": body }))
+        } else if status.is_client_error() {
+            ToolExecutionResult::tool_error(format!("HTTP {}: {}", status, value_to_text(&body)))
+        } else {
+            ToolExecutionResult::internal_error_msg(format!(
+                "HTTP tool '{}' endpoint returned {}: {}",
+                self.connection.definition.name,
+                status,
+                value_to_text(&body)
+            ))
+        }
+    }
+}
+
+// ============================================================================
+// Private network guard
+// ============================================================================
+
+/// HTTP client that checks every target address unless private networks are allowed
+fn build_client(allow_private_networks: bool) -> reqwest::Client {
+    let builder = reqwest::Client::builder();
+    let builder = if allow_private_networks {
+        builder
+    } else {
+        guard_client(builder, MAX_REDIRECTS)
+    };
+    builder
+        .build()
+        .expect("HTTP tool client configuration is valid")
+}
+
+/// JSON bodies are returned as values, anything else as (truncated) text
+fn parse_body(text: String) -> Value {
+    if text.is_empty() {
+        return Value::Null;
+    }
+    if text.len() <= MAX_BODY_CHARS {
+        if let Ok(value) = serde_json::from_str(&text) {
+            return value;
+        }
+        return Value::String(text);
+    }
+
+    let mut end = MAX_BODY_CHARS;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Value::String(format!("{}... [truncated]", &text[..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_tool::{HttpToolDefinition, HttpToolEndpoint};
+    use std::collections::HashMap;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn connection(
+        method: HttpToolMethod,
+        url: String,
+        body: Option<&str>,
+        auth: Option<HttpToolAuth>,
+    ) -> HttpToolConnection {
+        HttpToolConnection::new(
+            HttpToolDefinition {
+                name: "tickets".to_string(),
+                description: "Manage tickets".to_string(),
+                parameters: json!({"type": "object"}),
+                endpoint: HttpToolEndpoint {
+                    method,
+                    url,
+                    headers: HashMap::from([("X-Project".to_string(), "{{project}}".to_string())]),
+                    body: body.map(str::to_string),
+                    auth,
+                },
+            },
+            Some("s3cret".to_string()),
+        )
+    }
+
+    /// Tool allowed to reach the local mock server
+    fn local_tool(connection: HttpToolConnection) -> HttpTool {
+        HttpTool::new(connection).allow_private_networks(true)
+    }
+
+    #[test]
+    fn test_capability_metadata() {
+        let capability = HttpToolCapability::new(connection(
+            HttpToolMethod::Get,
+            "https://example.com".to_string(),
+            None,
+            None,
+        ));
+
+        assert_eq!(capability.id(), "custom:tickets");
+        assert_eq!(capability.tools().len(), 1);
+        assert_eq!(capability.tools()[0].name(), "tickets");
+    }
+
+    #[tokio::test]
+    async fn test_post_with_body_template_and_bearer_auth() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/projects/ops/tickets"))
+            .and(header("authorization", "Bearer s3cret"))
+            .and(header("x-project", "ops"))
+            .and(body_json(json!({"title": "Disk full", "priority": 2})))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 42})))
+            .mount(&server)
+            .await;
+
+        let tool = local_tool(connection(
+            HttpToolMethod::Post,
+            format!("{}/projects/{{{{project}}}}/tickets", server.uri()),
+            Some(r#"{"title": {{title}}, "priority": {{priority}}}"#),
+            Some(HttpToolAuth::Bearer),
+        ));
+        let result = tool
+            .execute(json!({"project": "ops", "title": "Disk full", "priority": 2}))
+            .await;
+
+        assert!(
+            matches!(result, ToolExecutionResult::Success(ref v) if v == &json!({"status": 201, "body": {"id": 42}}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_with_query_and_header_auth() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("q", "a b"))
+            .and(header("x-api-key", "s3cret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("plain text"))
+            .mount(&server)
+            .await;
+
+        let tool = local_tool(connection(
+            HttpToolMethod::Get,
+            format!("{}/search?q={{{{q}}}}", server.uri()),
+            None,
+            Some(HttpToolAuth::Header {
+                name: "X-Api-Key".to_string(),
+            }),
+        ));
+        let result = tool.execute(json!({"q": "a b"})).await;
+
+        assert!(matches!(result, ToolExecutionResult::Success(ref v) if v["body"] == "plain text"));
+    }
+
+    #[tokio::test]
+    async fn test_status_mapping() {
+        let server = MockServer::start().await;
+        Mock::given(path("/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("no such ticket"))
+            .mount(&server)
+            .await;
+        Mock::given(path("/broken"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("stack trace"))
+            .mount(&server)
+            .await;
+
+        let missing = local_tool(connection(
+            HttpToolMethod::Delete,
+            format!("{}/missing", server.uri()),
+            None,
+            None,
+        ));
+        let broken = local_tool(connection(
+            HttpToolMethod::Delete,
+            format!("{}/broken", server.uri()),
+            None,
+            None,
+        ));
+
+        assert!(matches!(
+            missing.execute(json!({})).await,
+            ToolExecutionResult::ToolError(ref m) if m == "HTTP 404 Not Found: no such ticket"
+        ));
+        assert!(matches!(
+            broken.execute(json!({})).await,
+            ToolExecutionResult::InternalError(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_private_targets_refused_by_default() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        // Host from the arguments, as a literal IP and through DNS
+        let tool = HttpTool::new(connection(
+            HttpToolMethod::Get,
+            "http://{{host}}:{{port}}/internal".to_string(),
+            None,
+            None,
+        ));
+        let port = server.address().port();
+        for host in ["127.0.0.1", "localhost"] {
+            assert!(matches!(
+                tool.execute(json!({"host": host, "port": port})).await,
+                ToolExecutionResult::ToolError(ref m) if m.contains("private network")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_body_truncates() {
+        let long = "x".repeat(MAX_BODY_CHARS + 10);
+        let Value::String(text) = parse_body(long) else {
+            panic!("expected text");
+        };
+        assert!(text.ends_with("... [truncated]"));
+        assert_eq!(parse_body(String::new()), Value::Null);
+    }
+}
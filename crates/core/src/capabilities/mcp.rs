@@ -0,0 +1,295 @@
+//! MCP Capability - exposes tools from the agent's MCP servers
+//!
+//! Unlike the other capabilities, the tool list is not compiled in: it is
+//! discovered at runtime from the MCP servers configured on the agent.
+//!
+//! Design decisions:
+//! - The registry holds an empty `McpCapability` so the capability can be listed
+//!   and enabled; workers replace it with a discovered one per agent
+//! - Tool names are prefixed with the server name (`<server>__<tool>`) so tools
+//!   from different servers cannot collide
+//! - A server that fails to connect (or that the worker does not allow) is
+//!   skipped (logged) instead of failing the turn
+//! - Connections come from the worker's `McpClientPool`, so the steps of a turn
+//!   reuse them instead of reconnecting
+//! - Tool calls proxy through `ToolRegistry` like any other tool
+
+use super::{Capability, CapabilityId, CapabilityStatus};
+use crate::mcp::{McpClient, McpClientPool, McpError, McpServerConnection, McpToolInfo};
+use crate::tools::{Tool, ToolExecutionResult};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// MCP capability - tools discovered from the agent's MCP servers
+#[derive(Default)]
+pub struct McpCapability {
+    tools: Vec<McpTool>,
+}
+
+impl McpCapability {
+    /// Create a capability without servers (used for listing)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connect to the given servers through the pool and collect their tools
+    ///
+    /// `scope` identifies the turn; clients are reused within it.
+    pub async fn discover(
+        servers: &[McpServerConnection],
+        pool: &McpClientPool,
+        scope: Uuid,
+    ) -> Self {
+        let mut tools = Vec::new();
+
+        for server in servers {
+            match Self::discover_server(server, pool, scope).await {
+                Ok(server_tools) => tools.extend(server_tools),
+                Err(e) => {
+                    tracing::warn!(
+                        server = %server.config.name,
+                        error = %e,
+                        "MCP: failed to discover server tools, skipping server"
+                    );
+                }
+            }
+        }
+
+        Self { tools }
+    }
+
+    async fn discover_server(
+        server: &McpServerConnection,
+        pool: &McpClientPool,
+        scope: Uuid,
+    ) -> Result<Vec<McpTool>, McpError> {
+        let client = pool.client(scope, server).await?;
+        let remote_tools = client.list_tools().await?;
+
+        tracing::debug!(
+            server = %server.config.name,
+            tool_count = remote_tools.len(),
+            "MCP: discovered server tools"
+        );
+
+        Ok(remote_tools
+            .into_iter()
+            .map(|info| McpTool {
+                name: server.config.exposed_tool_name(&info.name),
+                info,
+                client: client.clone(),
+            })
+            .collect())
+    }
+}
+
+impl Capability for McpCapability {
+    fn id(&self) -> &str {
+        CapabilityId::MCP
+    }
+
+    fn name(&self) -> &str {
+        "MCP Servers"
+    }
+
+    fn description(&self) -> &str {
+        "Use tools from the Model Context Protocol servers configured on the agent."
+    }
+
+    fn status(&self) -> CapabilityStatus {
+        CapabilityStatus::Available
+    }
+
+    fn icon(&self) -> Option<&str> {
+        Some("plug")
+    }
+
+    fn category(&self) -> Option<&str> {
+        Some("Integrations")
+    }
+
+    fn tools(&self) -> Vec<Box<dyn Tool>> {
+        self.tools
+            .iter()
+            .map(|t| Box::new(t.clone()) as Box<dyn Tool>)
+            .collect()
+    }
+}
+
+// ============================================================================
+// Tool: proxy to a remote MCP tool
+// ============================================================================
+
+/// Tool that forwards calls to a tool on an MCP server
+#[derive(Clone)]
+pub struct McpTool {
+    /// Exposed (prefixed) name
+    name: String,
+    info: McpToolInfo,
+    client: Arc<McpClient>,
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        self.info.description.as_deref().unwrap_or("")
+    }
+
+    fn parameters_schema(&self) -> Value {
+        self.info.input_schema.clone()
+    }
+
+    async fn execute(&self, arguments: Value) -> ToolExecutionResult {
+        match self.client.call_tool(&self.info.name, arguments).await {
+            Ok(result) if result.is_error => {
+                let message = match result.into_value() {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                ToolExecutionResult::tool_error(message)
+            }
+            Ok(result) => ToolExecutionResult::success(result.into_value()),
+            // JSON-RPC errors come from the server and describe the call (e.g. bad arguments)
+            Err(McpError::Server { message, .. }) => ToolExecutionResult::tool_error(message),
+            Err(e) => ToolExecutionResult::internal_error(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::{McpServerConfig, McpTransportConfig};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn connection(url: &str) -> McpServerConnection {
+        McpServerConnection::new(
+            McpServerConfig {
+                name: "docs".to_string(),
+                transport: McpTransportConfig::Http {
+                    url: url.to_string(),
+                    headers: HashMap::new(),
+                },
+                credential_names: vec!["Authorization".to_string()],
+            },
+            HashMap::from([("Authorization".to_string(), "Bearer secret".to_string())]),
+        )
+    }
+
+    async fn mock_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({"method": "initialize"})))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(
+                    json!({"jsonrpc": "2.0", "id": 1, "result": {"capabilities": {}}}),
+                ),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "notifications/initialized"}),
+            ))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "tools/list"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "result": {"tools": [{"name": "search", "inputSchema": {"type": "object"}}]}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "tools/call"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "result": {"content": [{"type": "text", "text": "no results"}], "isError": true}
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn test_empty_capability() {
+        let capability = McpCapability::new();
+        assert_eq!(capability.id(), CapabilityId::MCP);
+        assert!(capability.tools().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_discover_prefixes_tools() {
+        let server = mock_server().await;
+        let capability = McpCapability::discover(
+            &[connection(&server.uri())],
+            &McpClientPool::default().allow_private_networks(true),
+            Uuid::now_v7(),
+        )
+        .await;
+
+        let definitions = capability.tool_definitions();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(capability.tools()[0].name(), "docs__search");
+    }
+
+    #[tokio::test]
+    async fn test_tool_error_is_returned_to_model() {
+        let server = mock_server().await;
+        let capability = McpCapability::discover(
+            &[connection(&server.uri())],
+            &McpClientPool::default().allow_private_networks(true),
+            Uuid::now_v7(),
+        )
+        .await;
+
+        let tool = &capability.tools()[0];
+        let result = tool.execute(json!({"q": "rust"})).await;
+
+        assert!(matches!(result, ToolExecutionResult::ToolError(ref m) if m == "no results"));
+    }
+
+    #[tokio::test]
+    async fn test_discover_skips_unreachable_server() {
+        let capability = McpCapability::discover(
+            &[connection("http://127.0.0.1:1/mcp")],
+            &McpClientPool::default(),
+            Uuid::now_v7(),
+        )
+        .await;
+        assert!(capability.tools().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_discover_skips_stdio_server_not_allowed() {
+        let server = McpServerConnection::new(
+            McpServerConfig {
+                name: "shell".to_string(),
+                transport: McpTransportConfig::Stdio {
+                    command: "sh".to_string(),
+                    args: vec![],
+                    env: HashMap::new(),
+                },
+                credential_names: vec![],
+            },
+            HashMap::new(),
+        );
+        let capability =
+            McpCapability::discover(&[server], &McpClientPool::default(), Uuid::now_v7()).await;
+        assert!(capability.tools().is_empty());
+    }
+}
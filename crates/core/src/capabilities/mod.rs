@@ -8,6 +8,8 @@
 //!
 //! Design decisions:
 //! - Capabilities are defined via the Capability trait for flexibility
+//! - Capabilities may publish a JSON schema for per-agent configuration; the
+//!   config is passed into tool construction and system prompt additions
 //! - CapabilityRegistry holds all available capability implementations
 //! - apply_capabilities() merges capability contributions into RuntimeAgent
 //! - The agent-loop remains execution-focused; capabilities are applied before execution
@@ -15,9 +17,11 @@
 //! Each capability is in its own file with collocated tools.
 
 use crate::runtime_agent::RuntimeAgent;
+use crate::structured_output;
 use crate::tool_types::ToolDefinition;
 use crate::tools::{Tool, ToolRegistry};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 // Re-export capability types from capability_types module
@@ -33,6 +37,8 @@ mod fake_crm;
 mod fake_financial;
 mod fake_warehouse;
 mod file_system;
+mod http_tool;
+mod mcp;
 mod noop;
 mod research;
 mod sandbox;
@@ -69,6 +75,8 @@ pub use file_system::{
     DeleteFileTool, FileSystemCapability, GrepFilesTool, ListDirectoryTool, ReadFileTool,
     StatFileTool, WriteFileTool,
 };
+pub use http_tool::{HttpTool, HttpToolCapability};
+pub use mcp::{McpCapability, McpTool};
 pub use noop::NoopCapability;
 pub use research::ResearchCapability;
 pub use sandbox::SandboxCapability;
@@ -87,6 +95,9 @@ pub use web_fetch::{WebFetchCapability, WebFetchTool};
 /// - System prompt additions (prepended to agent's system prompt)
 /// - Tools (added to agent's available tools)
 ///
+/// Configurable capabilities publish a `config_schema()` and override the
+/// `configured_*` methods to build their contributions from the per-agent config.
+///
 /// # Example
 ///
 /// ```ignore
@@ -152,6 +163,57 @@ pub trait Capability: Send + Sync {
     fn tool_definitions(&self) -> Vec<ToolDefinition> {
         self.tools().iter().map(|t| t.to_definition()).collect()
     }
+
+    /// Returns the JSON schema of the per-agent configuration (optional).
+    /// Capabilities without a schema don't accept configuration.
+    fn config_schema(&self) -> Option<Value> {
+        None
+    }
+
+    /// Returns the system prompt addition for a per-agent configuration.
+    /// By default, ignores the config and uses system_prompt_addition()
+    fn configured_system_prompt_addition(&self, _config: &Value) -> Option<String> {
+        self.system_prompt_addition().map(str::to_string)
+    }
+
+    /// Returns the tools built for a per-agent configuration.
+    /// By default, ignores the config and uses tools()
+    fn configured_tools(&self, _config: &Value) -> Vec<Box<dyn Tool>> {
+        self.tools()
+    }
+}
+
+/// Per-agent capability configuration: capability ID -> config object
+pub type CapabilityConfigs = BTreeMap<String, Value>;
+
+/// Validate a per-agent config against the capability's schema.
+///
+/// An empty object is always valid; capabilities without a schema accept nothing else.
+pub fn validate_capability_config(
+    capability: &dyn Capability,
+    config: &Value,
+) -> Result<(), String> {
+    let is_empty = config.as_object().is_some_and(|o| o.is_empty());
+    let Some(schema) = capability.config_schema() else {
+        if is_empty {
+            return Ok(());
+        }
+        return Err(format!(
+            "Capability '{}' does not accept configuration",
+            capability.id()
+        ));
+    };
+
+    let errors = structured_output::validate(config, &schema);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid configuration for capability '{}': {}",
+            capability.id(),
+            errors.join("; ")
+        ))
+    }
 }
 
 // ============================================================================
@@ -205,6 +267,8 @@ impl CapabilityRegistry {
         registry.register(TestWeatherCapability);
         registry.register(StatelessTodoListCapability);
         registry.register(WebFetchCapability);
+        // Tools are discovered per agent by the worker
+        registry.register(McpCapability::new());
         // Fake demo capabilities
         registry.register(FakeWarehouseCapability);
         registry.register(FakeAwsCapability);
@@ -360,6 +424,18 @@ pub fn collect_capabilities(
     capability_ids: &[String],
     registry: &CapabilityRegistry,
 ) -> CollectedCapabilities {
+    collect_configured_capabilities(capability_ids, &CapabilityConfigs::new(), registry)
+}
+
+/// Collect contributions from capabilities using per-agent configuration.
+///
+/// Capabilities without an entry in `configs` get an empty config object.
+pub fn collect_configured_capabilities(
+    capability_ids: &[String],
+    configs: &CapabilityConfigs,
+    registry: &CapabilityRegistry,
+) -> CollectedCapabilities {
+    let empty_config = Value::Object(Default::default());
     let mut system_prompt_parts: Vec<String> = Vec::new();
     let mut tools: Vec<Box<dyn Tool>> = Vec::new();
     let mut tool_definitions: Vec<ToolDefinition> = Vec::new();
@@ -372,16 +448,17 @@ pub fn collect_capabilities(
                 continue;
             }
 
+            let config = configs.get(cap_id).unwrap_or(&empty_config);
+
             // Collect system prompt addition
-            if let Some(addition) = capability.system_prompt_addition() {
-                system_prompt_parts.push(addition.to_string());
+            if let Some(addition) = capability.configured_system_prompt_addition(config) {
+                system_prompt_parts.push(addition);
             }
 
-            // Collect tools
-            tools.extend(capability.tools());
-
-            // Collect tool definitions
-            tool_definitions.extend(capability.tool_definitions());
+            // Collect tools and their definitions
+            let capability_tools = capability.configured_tools(config);
+            tool_definitions.extend(capability_tools.iter().map(|t| t.to_definition()));
+            tools.extend(capability_tools);
 
             applied_ids.push(cap_id.clone());
         }
@@ -470,6 +547,9 @@ pub fn apply_capabilities(
         max_iterations: base_runtime_agent.max_iterations,
         temperature: base_runtime_agent.temperature,
         max_tokens: base_runtime_agent.max_tokens,
+        response_format: base_runtime_agent.response_format,
+        tool_choice: base_runtime_agent.tool_choice,
+        parallel_tool_calls: base_runtime_agent.parallel_tool_calls,
     };
 
     AppliedCapabilities {
@@ -486,6 +566,7 @@ pub fn apply_capabilities(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     // =========================================================================
     // CapabilityRegistry tests
@@ -504,11 +585,12 @@ mod tests {
         assert!(registry.has(CapabilityId::TEST_WEATHER));
         assert!(registry.has(CapabilityId::STATELESS_TODO_LIST));
         assert!(registry.has(CapabilityId::WEB_FETCH));
+        assert!(registry.has(CapabilityId::MCP));
         assert!(registry.has(CapabilityId::FAKE_WAREHOUSE));
         assert!(registry.has(CapabilityId::FAKE_AWS));
         assert!(registry.has(CapabilityId::FAKE_CRM));
         assert!(registry.has(CapabilityId::FAKE_FINANCIAL));
-        assert_eq!(registry.len(), 13);
+        assert_eq!(registry.len(), 14);
     }
 
     #[test]
@@ -780,4 +862,54 @@ mod tests {
         assert!(applied.tool_registry.has("web_fetch"));
         assert_eq!(applied.tool_registry.len(), 1);
     }
+
+    // =========================================================================
+    // Capability configuration tests
+    // =========================================================================
+
+    #[test]
+    fn test_validate_capability_config() {
+        let registry = CapabilityRegistry::with_builtins();
+        let file_system = registry.get(CapabilityId::FILE_SYSTEM).unwrap();
+        let current_time = registry.get(CapabilityId::CURRENT_TIME).unwrap();
+
+        assert!(
+            validate_capability_config(file_system.as_ref(), &json!({"read_only": true})).is_ok()
+        );
+        assert!(
+            validate_capability_config(file_system.as_ref(), &json!({"read_only": "yes"})).is_err()
+        );
+        assert!(validate_capability_config(file_system.as_ref(), &json!({"unknown": 1})).is_err());
+
+        // Capabilities without a schema only accept an empty config
+        assert!(validate_capability_config(current_time.as_ref(), &json!({})).is_ok());
+        let error =
+            validate_capability_config(current_time.as_ref(), &json!({"tz": "UTC"})).unwrap_err();
+        assert!(error.contains("does not accept configuration"));
+    }
+
+    #[test]
+    fn test_collect_configured_capabilities() {
+        let registry = CapabilityRegistry::with_builtins();
+        let ids = vec![
+            CapabilityId::FILE_SYSTEM.to_string(),
+            CapabilityId::CURRENT_TIME.to_string(),
+        ];
+        let configs = CapabilityConfigs::from([(
+            CapabilityId::FILE_SYSTEM.to_string(),
+            json!({"read_only": true}),
+        )]);
+
+        let collected = collect_configured_capabilities(&ids, &configs, &registry);
+        let names: Vec<&str> = collected.tools.iter().map(|t| t.name()).collect();
+
+        assert!(names.contains(&"read_file"));
+        assert!(!names.contains(&"write_file"));
+        assert!(names.contains(&"get_current_time"));
+        assert_eq!(collected.tools.len(), collected.tool_definitions.len());
+        assert!(collected
+            .system_prompt_prefix()
+            .unwrap()
+            .contains("read-only"));
+    }
 }
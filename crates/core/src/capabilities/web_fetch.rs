@@ -10,6 +10,8 @@
 //! - Timeout for first byte: 1 second (connect + time to first response byte)
 //! - Timeout for body: 30 seconds total, partial content returned if exceeded
 //! - Response includes content size and Last-Modified header when available
+//! - Configuration `{"allowed_domains": [...]}` restricts requests (and redirects)
+//!   to the listed domains and their subdomains
 
 use super::{Capability, CapabilityId, CapabilityStatus};
 use crate::tools::{Tool, ToolExecutionResult};
@@ -28,6 +30,9 @@ const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 /// Timeout for reading the entire response body (30 seconds)
 const BODY_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Maximum number of redirects followed (reqwest's default)
+const MAX_REDIRECTS: usize = 10;
+
 /// WebFetch capability - provides tools to fetch web content
 pub struct WebFetchCapability;
 
@@ -59,7 +64,32 @@ impl Capability for WebFetchCapability {
     // No system_prompt_addition - this capability doesn't need special instructions
 
     fn tools(&self) -> Vec<Box<dyn Tool>> {
-        vec![Box::new(WebFetchTool)]
+        vec![Box::new(WebFetchTool::default())]
+    }
+
+    fn config_schema(&self) -> Option<Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "allowed_domains": {
+                    "type": "array",
+                    "description": "Domains the agent may fetch from (subdomains included)",
+                    "items": {"type": "string", "minLength": 1}
+                }
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn configured_tools(&self, config: &Value) -> Vec<Box<dyn Tool>> {
+        let allowed_domains: Option<Vec<String>> = config
+            .get("allowed_domains")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+        match allowed_domains {
+            Some(domains) => vec![Box::new(WebFetchTool::with_allowed_domains(domains))],
+            None => self.tools(),
+        }
     }
 }
 
@@ -68,7 +98,42 @@ impl Capability for WebFetchCapability {
 // ============================================================================
 
 /// Tool that fetches content from a URL
-pub struct WebFetchTool;
+#[derive(Debug, Clone, Default)]
+pub struct WebFetchTool {
+    /// Domains requests are restricted to (unrestricted when `None`)
+    allowed_domains: Option<Vec<String>>,
+}
+
+impl WebFetchTool {
+    /// Create a tool that only fetches from the given domains and their subdomains
+    pub fn with_allowed_domains(domains: Vec<String>) -> Self {
+        Self {
+            allowed_domains: Some(domains),
+        }
+    }
+
+    /// Whether the URL's host may be fetched
+    fn is_allowed(&self, url: &url::Url) -> bool {
+        match &self.allowed_domains {
+            Some(domains) => url
+                .host_str()
+                .is_some_and(|host| is_domain_allowed(host, domains)),
+            None => true,
+        }
+    }
+}
+
+/// Whether `host` is one of `domains` or a subdomain of one
+fn is_domain_allowed(host: &str, domains: &[String]) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    domains.iter().any(|domain| {
+        let domain = domain
+            .trim_start_matches("*.")
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
 
 /// HTTP methods supported by the web_fetch tool
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -153,6 +218,15 @@ impl Tool for WebFetchTool {
             );
         }
 
+        if self.allowed_domains.is_some() {
+            let allowed = url::Url::parse(url).is_ok_and(|parsed| self.is_allowed(&parsed));
+            if !allowed {
+                return ToolExecutionResult::tool_error(
+                    "URL not allowed: the domain is not in the agent's allowed domains",
+                );
+            }
+        }
+
         // Extract method (defaults to GET)
         let method = arguments
             .get("method")
@@ -201,10 +275,27 @@ impl Tool for WebFetchTool {
         };
         headers.insert(ACCEPT, HeaderValue::from_static(accept_value));
 
+        // Redirects must stay within the allowed domains as well
+        let redirect_policy = if self.allowed_domains.is_some() {
+            let tool = self.clone();
+            reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if tool.is_allowed(attempt.url()) {
+                    attempt.follow()
+                } else {
+                    attempt.error("redirect to a domain that is not allowed")
+                }
+            })
+        } else {
+            reqwest::redirect::Policy::limited(MAX_REDIRECTS)
+        };
+
         // Create HTTP client with connect timeout for first byte
         let client = match reqwest::Client::builder()
             .default_headers(headers)
             .connect_timeout(CONNECT_TIMEOUT)
+            .redirect(redirect_policy)
             // Note: We don't set a global timeout here; we handle body timeout manually
             .build()
         {
@@ -232,6 +323,15 @@ impl Tool for WebFetchTool {
                     );
                 } else if e.is_connect() {
                     return ToolExecutionResult::tool_error("Failed to connect to server");
+                } else if e.is_redirect() {
+                    // The redirect policy's reason is the source of the error
+                    let reason = std::error::Error::source(&e)
+                        .map(|source| source.to_string())
+                        .unwrap_or_else(|| e.to_string());
+                    return ToolExecutionResult::tool_error(format!(
+                        "Redirect not followed: {}",
+                        reason
+                    ));
                 } else {
                     return ToolExecutionResult::tool_error(format!("Request failed: {}", e));
                 }
@@ -490,10 +590,10 @@ fn html_to_markdown(html: &str) -> String {
                     "h4" if !is_closing => result.push_str("\n#### "),
                     "h5" if !is_closing => result.push_str("\n##### "),
                     "h6" if !is_closing => result.push_str("\n###### "),
-                    "p" | "div" | "section" | "article" | "main" | "header" | "footer" => {
-                        if is_closing {
-                            result.push_str("\n\n");
-                        }
+                    "p" | "div" | "section" | "article" | "main" | "header" | "footer"
+                        if is_closing =>
+                    {
+                        result.push_str("\n\n");
                     }
                     "br" => result.push('\n'),
                     "hr" => result.push_str("\n---\n"),
@@ -831,7 +931,7 @@ mod tests {
 
     #[test]
     fn test_web_fetch_tool_parameters() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let schema = tool.parameters_schema();
 
         assert_eq!(schema["type"], "object");
@@ -865,7 +965,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_missing_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool.execute(serde_json::json!({})).await;
 
         if let ToolExecutionResult::ToolError(msg) = result {
@@ -877,7 +977,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_invalid_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({"url": "not-a-valid-url"}))
             .await;
@@ -891,7 +991,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_invalid_method() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({"url": "https://example.com", "method": "POST"}))
             .await;
@@ -918,7 +1018,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri()),
@@ -951,7 +1051,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri()),
@@ -984,7 +1084,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri())
@@ -1016,7 +1116,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/image/png", mock_server.uri())
@@ -1097,7 +1197,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/response-headers", mock_server.uri()),
@@ -1129,7 +1229,7 @@ mod tests {
             .await;
 
         // Normal response should have truncated: false
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri())
@@ -1165,7 +1265,7 @@ mod tests {
     async fn test_web_fetch_timeout_unreachable_host() {
         // Use a non-routable IP address to trigger connection timeout
         // 10.255.255.1 is typically non-routable and will timeout
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": "http://10.255.255.1:12345/test"
@@ -1396,7 +1496,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri())
@@ -1441,7 +1541,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri()),
@@ -1478,7 +1578,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/image/jpeg", mock_server.uri())
@@ -1517,7 +1617,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri()),
@@ -1546,7 +1646,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/html", mock_server.uri()),
@@ -1575,7 +1675,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/json", mock_server.uri())
@@ -1609,7 +1709,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/response-headers", mock_server.uri())
@@ -1686,7 +1786,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/bytes/100", mock_server.uri())
@@ -1719,7 +1819,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/robots.txt", mock_server.uri())
@@ -1750,7 +1850,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/status/404", mock_server.uri())
@@ -1775,7 +1875,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/status/500", mock_server.uri())
@@ -1792,7 +1892,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_dns_failure() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": "https://this-domain-definitely-does-not-exist-12345.com/test"
@@ -1819,9 +1919,64 @@ mod tests {
     // URL validation tests
     // ============================================================================
 
+    #[test]
+    fn test_is_domain_allowed() {
+        let domains = vec!["docs.rs".to_string(), "*.example.com".to_string()];
+
+        assert!(is_domain_allowed("docs.rs", &domains));
+        assert!(is_domain_allowed("DOCS.RS.", &domains));
+        assert!(is_domain_allowed("api.example.com", &domains));
+        assert!(is_domain_allowed("example.com", &domains));
+        assert!(!is_domain_allowed("notdocs.rs", &domains));
+        assert!(!is_domain_allowed("example.com.evil.net", &domains));
+    }
+
+    #[tokio::test]
+    async fn test_web_fetch_allowed_domains() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ok"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/redirect"))
+            .respond_with(ResponseTemplate::new(302).insert_header(
+                "location",
+                format!("http://localhost:{}/ok", mock_server.address().port()).as_str(),
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let capability = WebFetchCapability;
+        let config = serde_json::json!({"allowed_domains": ["127.0.0.1"]});
+        let tools = capability.configured_tools(&config);
+        let tool = &tools[0];
+
+        let result = tool
+            .execute(serde_json::json!({"url": format!("{}/ok", mock_server.uri())}))
+            .await;
+        assert!(matches!(result, ToolExecutionResult::Success(_)));
+
+        let result = tool
+            .execute(serde_json::json!({"url": "https://example.com/"}))
+            .await;
+        assert!(
+            matches!(result, ToolExecutionResult::ToolError(ref m) if m.contains("not allowed"))
+        );
+
+        // Redirects leaving the allowed domains are not followed
+        let result = tool
+            .execute(serde_json::json!({"url": format!("{}/redirect", mock_server.uri())}))
+            .await;
+        assert!(
+            matches!(result, ToolExecutionResult::ToolError(ref m) if m.contains("not allowed"))
+        );
+    }
+
     #[tokio::test]
     async fn test_web_fetch_rejects_ftp_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": "ftp://example.com/file.txt"
@@ -1837,7 +1992,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_web_fetch_rejects_file_url() {
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": "file:///etc/passwd"
@@ -1865,7 +2020,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         // Note: mock_server.uri() returns http:// URL
         let result = tool
             .execute(serde_json::json!({
@@ -1954,7 +2109,7 @@ mod tests {
             .mount(&mock_server)
             .await;
 
-        let tool = WebFetchTool;
+        let tool = WebFetchTool::default();
         let result = tool
             .execute(serde_json::json!({
                 "url": format!("{}/newlines", mock_server.uri())
@@ -40,6 +40,11 @@ pub struct CapabilityInfo {
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     #[cfg_attr(feature = "openapi", schema(value_type = Vec<Object>))]
     pub tool_definitions: Vec<ToolDefinition>,
+    /// JSON schema of the per-agent configuration (set in `Agent.capability_config`).
+    /// Absent when the capability is not configurable.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub config_schema: Option<serde_json::Value>,
 }
 
 impl CapabilityInfo {
@@ -54,6 +59,7 @@ impl CapabilityInfo {
             category: cap.category().map(|s| s.to_string()),
             system_prompt: cap.system_prompt_addition().map(|s| s.to_string()),
             tool_definitions: cap.tool_definitions(),
+            config_schema: cap.config_schema(),
         }
     }
 }
@@ -84,6 +90,7 @@ mod tests {
             category: Some("AI".to_string()),
             system_prompt: Some("You have research capabilities.".to_string()),
             tool_definitions: vec![],
+            config_schema: None,
         };
 
         let json = serde_json::to_string(&cap).unwrap();
@@ -35,6 +35,7 @@ impl CapabilityId {
     pub const TEST_WEATHER: &'static str = "test_weather";
     pub const STATELESS_TODO_LIST: &'static str = "stateless_todo_list";
     pub const WEB_FETCH: &'static str = "web_fetch";
+    pub const MCP: &'static str = "mcp";
     // Fake demo capability ID constants
     pub const FAKE_WAREHOUSE: &'static str = "fake_warehouse";
     pub const FAKE_AWS: &'static str = "fake_aws";
@@ -86,6 +87,11 @@ impl CapabilityId {
         Self::new(Self::WEB_FETCH)
     }
 
+    /// Create the mcp capability ID
+    pub fn mcp() -> Self {
+        Self::new(Self::MCP)
+    }
+
     /// Create the fake_warehouse capability ID
     pub fn fake_warehouse() -> Self {
         Self::new(Self::FAKE_WAREHOUSE)
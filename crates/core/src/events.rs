@@ -42,6 +42,9 @@ pub const SESSION_STARTED: &str = "session.started";
 pub const SESSION_ACTIVATED: &str = "session.activated";
 pub const SESSION_IDLED: &str = "session.idled";
 
+// History events
+pub const EVENTS_SUPERSEDED: &str = "events.superseded";
+
 // ============================================================================
 // Event Context
 // ============================================================================
@@ -295,6 +298,11 @@ pub struct MessageAgentData {
     /// Token usage
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<TokenUsage>,
+
+    /// Parsed response when the turn used a JSON-schema response format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub structured_output: Option<serde_json::Value>,
 }
 
 impl MessageAgentData {
@@ -303,9 +311,15 @@ impl MessageAgentData {
             message,
             metadata: None,
             usage: None,
+            structured_output: None,
         }
     }
 
+    pub fn with_structured_output(mut self, value: serde_json::Value) -> Self {
+        self.structured_output = Some(value);
+        self
+    }
+
     pub fn with_metadata(mut self, metadata: ModelMetadata) -> Self {
         self.metadata = Some(metadata);
         self
@@ -709,6 +723,10 @@ pub struct TurnStartedData {
 
     /// Input message ID that triggered this turn
     pub input_message_id: Uuid,
+
+    /// Agent revision the turn runs with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_revision: Option<i32>,
 }
 
 /// Data for turn.completed event
@@ -780,6 +798,24 @@ pub struct SessionIdledData {
     pub iterations: Option<u32>,
 }
 
+// ============================================================================
+// History Event Data Types
+// ============================================================================
+
+/// Data for events.superseded event (turn regenerated or user message edited)
+///
+/// Events from `from_sequence` up to this one were replaced and should be
+/// dropped by clients that already received them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct EventsSupersededData {
+    /// First superseded event sequence number
+    pub from_sequence: i32,
+
+    /// User message the new turn runs for
+    pub input_message_id: Uuid,
+}
+
 // ============================================================================
 // EventData Enum - Typed event payloads
 // ============================================================================
@@ -808,6 +844,7 @@ pub struct SessionIdledData {
 /// - `session.started` → SessionStartedData
 /// - `session.activated` → SessionActivatedData
 /// - `session.idled` → SessionIdledData
+/// - `events.superseded` → EventsSupersededData
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
@@ -843,6 +880,9 @@ pub enum EventData {
     SessionActivated(SessionActivatedData),
     SessionIdled(SessionIdledData),
 
+    // History events
+    EventsSuperseded(EventsSupersededData),
+
     /// Raw data for backward compatibility with legacy events
     #[cfg_attr(feature = "openapi", schema(value_type = Object))]
     Raw(serde_json::Value),
@@ -871,6 +911,7 @@ impl EventData {
             EventData::SessionStarted(_) => SESSION_STARTED,
             EventData::SessionActivated(_) => SESSION_ACTIVATED,
             EventData::SessionIdled(_) => SESSION_IDLED,
+            EventData::EventsSuperseded(_) => EVENTS_SUPERSEDED,
             EventData::Raw(_) => UNKNOWN,
         }
     }
@@ -978,6 +1019,12 @@ impl From<SessionIdledData> for EventData {
     }
 }
 
+impl From<EventsSupersededData> for EventData {
+    fn from(data: EventsSupersededData) -> Self {
+        EventData::EventsSuperseded(data)
+    }
+}
+
 impl From<serde_json::Value> for EventData {
     fn from(data: serde_json::Value) -> Self {
         EventData::Raw(data)
@@ -1315,4 +1362,17 @@ mod tests {
         let event_data: EventData = data.into();
         assert_eq!(event_data.event_type(), LLM_GENERATION);
     }
+
+    #[test]
+    fn test_events_superseded_data_round_trip() {
+        let data = EventsSupersededData {
+            from_sequence: 5,
+            input_message_id: Uuid::now_v7(),
+        };
+        let json = serde_json::to_value(EventData::from(data)).unwrap();
+
+        // Untagged payloads must not be mistaken for another event type
+        let event_data: EventData = serde_json::from_value(json).unwrap();
+        assert_eq!(event_data.event_type(), EVENTS_SUPERSEDED);
+    }
 }
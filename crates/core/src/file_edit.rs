@@ -0,0 +1,550 @@
+//! Text edits for session files
+//!
+//! Pure functions behind the `edit_file`, `apply_patch` and ranged
+//! `read_file` tools: exact string replacement, unified diff parsing and
+//! application, and line windows. Nothing here touches storage; tools read
+//! the current content, compute the new content and write it back through
+//! `SessionFileStore::apply_changes`.
+//!
+//! Patches are applied leniently: hunk line counts are ignored and a hunk
+//! is matched at its stated position first, then anywhere after the
+//! previous hunk, then ignoring trailing whitespace. Away from the stated
+//! position a hunk must match in exactly one place, so a hunk with too little
+//! context is rejected rather than applied to the wrong lines.
+
+use crate::session_file::FileInfo;
+use thiserror::Error;
+
+/// Errors from editing or patching file content
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EditError {
+    #[error("old_string must not be empty")]
+    EmptyOldString,
+
+    #[error("old_string and new_string are identical")]
+    NoChange,
+
+    #[error("old_string not found in {0}")]
+    NotFound(String),
+
+    #[error("old_string matches {count} times in {path}. Include more surrounding context to make it unique, or set replace_all")]
+    NotUnique { path: String, count: usize },
+
+    #[error("Invalid patch: {0}")]
+    InvalidPatch(String),
+
+    #[error("Patch does not apply to {path}: hunk {hunk} does not match the file content")]
+    HunkMismatch { path: String, hunk: usize },
+
+    #[error("Patch does not apply to {path}: hunk {hunk} matches the file content in {count} places. Include more context lines to make it unique")]
+    AmbiguousHunk {
+        path: String,
+        hunk: usize,
+        count: usize,
+    },
+}
+
+/// Replace `old` with `new` in `content`.
+///
+/// `old` must occur exactly once unless `replace_all` is set. Returns the
+/// new content and the number of replacements.
+pub fn replace_exact(
+    path: &str,
+    content: &str,
+    old: &str,
+    new: &str,
+    replace_all: bool,
+) -> Result<(String, usize), EditError> {
+    if old.is_empty() {
+        return Err(EditError::EmptyOldString);
+    }
+    if old == new {
+        return Err(EditError::NoChange);
+    }
+
+    let count = content.matches(old).count();
+    match count {
+        0 => Err(EditError::NotFound(path.to_string())),
+        1 => Ok((content.replacen(old, new, 1), 1)),
+        _ if replace_all => Ok((content.replace(old, new), count)),
+        _ => Err(EditError::NotUnique {
+            path: path.to_string(),
+            count,
+        }),
+    }
+}
+
+/// A range of lines from a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineWindow {
+    /// The selected lines, with their line endings
+    pub content: String,
+    /// 1-based number of the first line returned
+    pub start_line: usize,
+    /// 1-based number of the last line returned (start_line - 1 if empty)
+    pub end_line: usize,
+    /// Number of lines in the whole file
+    pub total_lines: usize,
+}
+
+impl LineWindow {
+    /// Whether lines after the window were left out
+    pub fn has_more(&self) -> bool {
+        self.end_line < self.total_lines
+    }
+}
+
+/// Select up to `limit` lines starting at 1-based line `offset`
+pub fn line_window(content: &str, offset: Option<usize>, limit: Option<usize>) -> LineWindow {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let total_lines = lines.len();
+    let start = offset.unwrap_or(1).max(1);
+    let skip = (start - 1).min(total_lines);
+    let take = limit.unwrap_or(usize::MAX);
+
+    let selected: Vec<&str> = lines.into_iter().skip(skip).take(take).collect();
+    LineWindow {
+        end_line: skip + selected.len(),
+        content: selected.concat(),
+        start_line: skip + 1,
+        total_lines,
+    }
+}
+
+// ============================================================================
+// Unified diffs
+// ============================================================================
+
+/// Changes to one file from a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    /// Source path (None for new files)
+    pub old_path: Option<String>,
+    /// Destination path (None for deleted files)
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+    /// The patched file ends without a newline
+    new_missing_newline: bool,
+}
+
+/// One `@@` section of a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 1-based line in the original file where the hunk starts
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl FilePatch {
+    /// Path the patch applies to
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("/")
+    }
+
+    pub fn is_create(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    pub fn is_delete(&self) -> bool {
+        self.new_path.is_none()
+    }
+
+    pub fn is_rename(&self) -> bool {
+        matches!((&self.old_path, &self.new_path), (Some(old), Some(new)) if old != new)
+    }
+
+    /// Apply the hunks to `original`, returning the new content
+    pub fn apply(&self, original: &str) -> Result<String, EditError> {
+        let had_newline = original.is_empty() || original.ends_with('\n');
+        let lines: Vec<&str> = original.lines().collect();
+
+        let mut result: Vec<&str> = Vec::with_capacity(lines.len());
+        let mut cursor = 0;
+        let mut offset: isize = 0;
+
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            let old: Vec<&str> = hunk
+                .lines
+                .iter()
+                .filter_map(|line| match line {
+                    HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                    HunkLine::Add(_) => None,
+                })
+                .collect();
+
+            // Unified diffs number pure insertions after the line they follow
+            let expected = if old.is_empty() {
+                hunk.old_start
+            } else {
+                hunk.old_start.saturating_sub(1)
+            };
+            let hint = (expected as isize + offset).max(cursor as isize) as usize;
+
+            let at = match find_block(&lines, &old, hint, cursor) {
+                BlockMatch::At(at) => at,
+                BlockMatch::Missing => {
+                    return Err(EditError::HunkMismatch {
+                        path: self.path().to_string(),
+                        hunk: index + 1,
+                    })
+                }
+                BlockMatch::Ambiguous(count) => {
+                    return Err(EditError::AmbiguousHunk {
+                        path: self.path().to_string(),
+                        hunk: index + 1,
+                        count,
+                    })
+                }
+            };
+            offset = at as isize - expected as isize;
+
+            result.extend_from_slice(&lines[cursor..at]);
+            result.extend(hunk.lines.iter().filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Add(text) => Some(text.as_str()),
+                HunkLine::Remove(_) => None,
+            }));
+            cursor = at + old.len();
+        }
+        result.extend_from_slice(&lines[cursor..]);
+
+        let mut content = result.join("\n");
+        let touches_end = self.hunks.last().is_some_and(|_| cursor == lines.len());
+        let trailing_newline = if self.new_missing_newline {
+            false
+        } else {
+            touches_end || had_newline
+        };
+        if trailing_newline && !content.is_empty() {
+            content.push('\n');
+        }
+        Ok(content)
+    }
+}
+
+/// Where a hunk's original lines are in the file
+enum BlockMatch {
+    At(usize),
+    Missing,
+    /// Matches in this many places away from the stated position
+    Ambiguous(usize),
+}
+
+// Find `block` in `lines` at or after `min`. An exact match at `hint` wins;
+// elsewhere the block must match in exactly one place, first comparing
+// exactly, then with trailing whitespace trimmed.
+fn find_block(lines: &[&str], block: &[&str], hint: usize, min: usize) -> BlockMatch {
+    if block.is_empty() {
+        return BlockMatch::At(hint.min(lines.len()));
+    }
+    if block.len() > lines.len() {
+        return BlockMatch::Missing;
+    }
+    let last = lines.len() - block.len();
+    if min > last {
+        return BlockMatch::Missing;
+    }
+
+    let matches_at = |at: usize, exact: bool| {
+        lines[at..at + block.len()]
+            .iter()
+            .zip(block)
+            .all(|(line, expected)| {
+                if exact {
+                    line == expected
+                } else {
+                    line.trim_end() == expected.trim_end()
+                }
+            })
+    };
+
+    if (min..=last).contains(&hint) && matches_at(hint, true) {
+        return BlockMatch::At(hint);
+    }
+    for exact in [true, false] {
+        let found: Vec<usize> = (min..=last).filter(|&at| matches_at(at, exact)).collect();
+        match found.as_slice() {
+            [] => continue,
+            [at] => return BlockMatch::At(*at),
+            _ => return BlockMatch::Ambiguous(found.len()),
+        }
+    }
+    BlockMatch::Missing
+}
+
+/// Parse a unified diff covering one or more files.
+///
+/// Accepts `git diff` output as well as plain `---`/`+++` headers. Paths
+/// may carry `a/`/`b/` prefixes or the `@vN` suffix used by `diff_file`.
+pub fn parse_patch(text: &str) -> Result<Vec<FilePatch>, EditError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if !(line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ ")))
+        {
+            // Skip `diff --git`, `index` and other preamble lines
+            i += 1;
+            continue;
+        }
+
+        let old_path = parse_header_path(&line[4..]);
+        let new_path = parse_header_path(&lines[i + 1][4..]);
+        if old_path.is_none() && new_path.is_none() {
+            return Err(EditError::InvalidPatch(
+                "both sides of a file header are /dev/null".to_string(),
+            ));
+        }
+        i += 2;
+
+        let mut patch = FilePatch {
+            old_path,
+            new_path,
+            hunks: Vec::new(),
+            new_missing_newline: false,
+        };
+
+        while i < lines.len() && lines[i].starts_with("@@") {
+            let old_start = parse_hunk_header(lines[i])?;
+            i += 1;
+
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+            };
+            while i < lines.len() {
+                let line = lines[i];
+                if line.starts_with("@@")
+                    || line.starts_with("diff ")
+                    || (line.starts_with("--- ")
+                        && lines
+                            .get(i + 1)
+                            .is_some_and(|next| next.starts_with("+++ ")))
+                {
+                    break;
+                }
+                match line.chars().next() {
+                    Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+                    Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+                    Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+                    // "\\ No newline at end of file" after a line the new side keeps
+                    Some('\\') => {
+                        if matches!(
+                            hunk.lines.last(),
+                            Some(HunkLine::Add(_) | HunkLine::Context(_))
+                        ) {
+                            patch.new_missing_newline = true;
+                        }
+                    }
+                    // Editors and models often strip the space from blank context lines
+                    None => hunk.lines.push(HunkLine::Context(String::new())),
+                    Some(_) => {
+                        return Err(EditError::InvalidPatch(format!(
+                            "unexpected line in hunk: {}",
+                            line
+                        )))
+                    }
+                }
+                i += 1;
+            }
+            // A blank line before the next file is separation, not context
+            while matches!(hunk.lines.last(), Some(HunkLine::Context(text)) if text.is_empty())
+                && i < lines.len()
+            {
+                hunk.lines.pop();
+            }
+            patch.hunks.push(hunk);
+        }
+
+        if patch.hunks.is_empty() && !patch.is_delete() && !patch.is_rename() {
+            return Err(EditError::InvalidPatch(format!(
+                "no hunks for {}",
+                patch.path()
+            )));
+        }
+        patches.push(patch);
+    }
+
+    if patches.is_empty() {
+        return Err(EditError::InvalidPatch(
+            "no file headers found (expected '--- ' and '+++ ' lines)".to_string(),
+        ));
+    }
+    Ok(patches)
+}
+
+// Path from a `---`/`+++` header, None for /dev/null
+fn parse_header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    // Version suffix from diff_file headers, e.g. /notes.txt@v3
+    let path = match path.rsplit_once("@v") {
+        Some((base, version)) if version.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => path,
+    };
+    Some(FileInfo::normalize_path(path))
+}
+
+// Old start line from `@@ -l,s +l,s @@`
+fn parse_hunk_header(header: &str) -> Result<usize, EditError> {
+    let invalid = || EditError::InvalidPatch(format!("malformed hunk header: {}", header));
+    let old = header
+        .trim_start_matches('@')
+        .split_whitespace()
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    old.split(',')
+        .next()
+        .and_then(|start| start.parse().ok())
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_exact_requires_unique_match() {
+        let content = "a = 1\nb = 1\n";
+
+        let (updated, count) = replace_exact("/f", content, "a = 1", "a = 2", false).unwrap();
+        assert_eq!(updated, "a = 2\nb = 1\n");
+        assert_eq!(count, 1);
+
+        assert_eq!(
+            replace_exact("/f", content, "= 1", "= 2", false),
+            Err(EditError::NotUnique {
+                path: "/f".to_string(),
+                count: 2
+            })
+        );
+        let (updated, count) = replace_exact("/f", content, "= 1", "= 2", true).unwrap();
+        assert_eq!(updated, "a = 2\nb = 2\n");
+        assert_eq!(count, 2);
+
+        assert_eq!(
+            replace_exact("/f", content, "c", "d", false),
+            Err(EditError::NotFound("/f".to_string()))
+        );
+        assert_eq!(
+            replace_exact("/f", content, "", "d", false),
+            Err(EditError::EmptyOldString)
+        );
+    }
+
+    #[test]
+    fn test_line_window() {
+        let content = "one\ntwo\nthree\nfour\n";
+
+        let window = line_window(content, Some(2), Some(2));
+        assert_eq!(window.content, "two\nthree\n");
+        assert_eq!((window.start_line, window.end_line), (2, 3));
+        assert_eq!(window.total_lines, 4);
+        assert!(window.has_more());
+
+        let window = line_window(content, Some(10), None);
+        assert_eq!(window.content, "");
+        assert!(!window.has_more());
+    }
+
+    #[test]
+    fn test_apply_patch_with_offset_hunks() {
+        let original = "header\none\ntwo\nthree\nfour\nfive\n";
+        let patch = parse_patch(
+            "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n@@ -5,1 +5,2 @@\n five\n+six\n",
+        )
+        .unwrap();
+
+        assert_eq!(patch.len(), 1);
+        assert_eq!(patch[0].path(), "/notes.txt");
+        // Both hunks are one line off and still apply
+        assert_eq!(
+            patch[0].apply(original).unwrap(),
+            "header\none\nTWO\nthree\nfour\nfive\nsix\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_create_delete_and_mismatch() {
+        let patches = parse_patch(
+            "diff --git a/new.txt b/new.txt\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n\\ No newline at end of file\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n",
+        )
+        .unwrap();
+
+        assert_eq!(patches.len(), 2);
+        assert!(patches[0].is_create());
+        assert_eq!(patches[0].apply("").unwrap(), "hello\nworld");
+        assert!(patches[1].is_delete());
+        assert_eq!(patches[1].path(), "/old.txt");
+
+        let patch = parse_patch("--- /a.txt\n+++ /a.txt\n@@ -1 +1 @@\n-x\n+y\n").unwrap();
+        assert_eq!(
+            patch[0].apply("z\n"),
+            Err(EditError::HunkMismatch {
+                path: "/a.txt".to_string(),
+                hunk: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_rejects_ambiguous_hunk() {
+        let original = "fn a() {\n    todo!()\n}\nfn b() {\n    todo!()\n}\n";
+        // Stated position is wrong and the context matches both functions
+        let patch =
+            parse_patch("--- a/lib.rs\n+++ b/lib.rs\n@@ -9,2 +9,2 @@\n-    todo!()\n+    42\n }\n")
+                .unwrap();
+        assert_eq!(
+            patch[0].apply(original),
+            Err(EditError::AmbiguousHunk {
+                path: "/lib.rs".to_string(),
+                hunk: 1,
+                count: 2
+            })
+        );
+
+        // At the stated position the same hunk applies
+        let patch =
+            parse_patch("--- a/lib.rs\n+++ b/lib.rs\n@@ -5,2 +5,2 @@\n-    todo!()\n+    42\n }\n")
+                .unwrap();
+        assert_eq!(
+            patch[0].apply(original).unwrap(),
+            "fn a() {\n    todo!()\n}\nfn b() {\n    42\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_patch_accepts_diff_file_headers() {
+        let diff = crate::session_file::FileDiff::between(
+            "/notes.txt",
+            (1, b"one\ntwo\n"),
+            (2, b"one\nthree\n"),
+        );
+        let patch = parse_patch(&diff.diff).unwrap();
+
+        assert_eq!(patch[0].path(), "/notes.txt");
+        assert!(!patch[0].is_rename());
+        assert_eq!(patch[0].apply("one\ntwo\n").unwrap(), "one\nthree\n");
+    }
+}
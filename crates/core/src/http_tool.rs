@@ -0,0 +1,334 @@
+// User-defined HTTP tools
+//
+// Tools defined at runtime through the API: a name, description and JSON-schema
+// parameters, plus the HTTP endpoint that implements the tool.
+//
+// Design decisions:
+// - Each tool is exposed as its own capability (`custom:<name>`) so it is attached
+//   to agents exactly like a built-in capability
+// - Definitions are plain data; the auth secret is never part of the definition and
+//   is supplied separately (decrypted by the control-plane), like MCP credentials
+// - Arguments are substituted into `{{name}}` placeholders: percent-encoded in the
+//   URL, raw in headers and JSON-encoded in the body template
+// - Without a body template, POST/PUT/PATCH send the arguments as the JSON body
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// Capability ID prefix of user-defined tools (`custom:<tool name>`)
+pub const CUSTOM_CAPABILITY_PREFIX: &str = "custom:";
+
+/// Maximum tool name length (capability IDs are limited to 50 characters)
+const MAX_NAME_LEN: usize = 40;
+
+// ============================================================================
+// Definition
+// ============================================================================
+
+/// Definition of a user-defined HTTP tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct HttpToolDefinition {
+    /// Tool name shown to the model
+    pub name: String,
+    /// What the tool does and when to use it
+    pub description: String,
+    /// JSON schema of the tool arguments
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub parameters: Value,
+    /// HTTP endpoint called when the tool is executed
+    pub endpoint: HttpToolEndpoint,
+}
+
+/// HTTP endpoint that implements a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct HttpToolEndpoint {
+    pub method: HttpToolMethod,
+    /// URL template, e.g. `https://api.example.com/tickets/{{ticket_id}}`
+    pub url: String,
+    /// Header templates
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// JSON body template, e.g. `{"title": {{title}}}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// How the stored secret is sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<HttpToolAuth>,
+}
+
+/// HTTP method of a tool endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpToolMethod {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl HttpToolMethod {
+    /// Whether the arguments are sent as the body when no template is set
+    pub fn has_body(&self) -> bool {
+        matches!(self, Self::Post | Self::Put | Self::Patch)
+    }
+}
+
+/// Authentication scheme using the tool's stored secret
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HttpToolAuth {
+    /// `Authorization: Bearer <secret>`
+    Bearer,
+    /// `<name>: <secret>`
+    Header { name: String },
+    /// `Authorization: Basic base64(<username>:<secret>)`
+    Basic { username: String },
+}
+
+impl HttpToolDefinition {
+    /// Validate the definition (name format, parameters schema and URL)
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_name = !self.name.is_empty()
+            && self.name.len() <= MAX_NAME_LEN
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            && !self.name.contains("__");
+        if !valid_name {
+            return Err(format!(
+                "Tool name '{}' must be 1-{} characters of letters, digits, '_' and '-' without '__'",
+                self.name, MAX_NAME_LEN
+            ));
+        }
+
+        if self.description.trim().is_empty() {
+            return Err(format!("Tool '{}' must have a description", self.name));
+        }
+
+        if self.parameters.get("type").and_then(Value::as_str) != Some("object") {
+            return Err(format!(
+                "Tool '{}' parameters must be a JSON schema with \"type\": \"object\"",
+                self.name
+            ));
+        }
+
+        // Placeholders may appear anywhere in the URL; check the static parts
+        let url = render_template(&self.endpoint.url, &Value::Null, |_| "x".to_string());
+        let parsed = url::Url::parse(&url)
+            .map_err(|e| format!("Tool '{}' has an invalid url: {}", self.name, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("Tool '{}' url must use http or https", self.name));
+        }
+
+        Ok(())
+    }
+
+    /// Capability ID under which the tool is attached to agents
+    pub fn capability_id(&self) -> String {
+        capability_id_for(&self.name)
+    }
+}
+
+/// Capability ID of the tool with the given name
+pub fn capability_id_for(name: &str) -> String {
+    format!("{}{}", CUSTOM_CAPABILITY_PREFIX, name)
+}
+
+/// Tool name referenced by a `custom:<name>` capability ID
+pub fn tool_name_from_capability_id(capability_id: &str) -> Option<&str> {
+    capability_id.strip_prefix(CUSTOM_CAPABILITY_PREFIX)
+}
+
+// ============================================================================
+// Stored tool (API entity)
+// ============================================================================
+
+/// User-defined HTTP tool as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CustomTool {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub parameters: Value,
+    pub endpoint: HttpToolEndpoint,
+    /// Capability ID used to attach the tool to agents
+    pub capability_id: String,
+    /// Whether an auth secret is stored (the value is never returned)
+    pub secret_set: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// Tool definition with its decrypted secret, ready to execute
+#[derive(Clone, PartialEq)]
+pub struct HttpToolConnection {
+    pub definition: HttpToolDefinition,
+    pub secret: Option<String>,
+}
+
+impl HttpToolConnection {
+    pub fn new(definition: HttpToolDefinition, secret: Option<String>) -> Self {
+        Self { definition, secret }
+    }
+}
+
+impl std::fmt::Debug for HttpToolConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpToolConnection")
+            .field("definition", &self.definition)
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// Substitute `{{name}}` placeholders with the encoded argument values.
+///
+/// Names may be dotted paths into nested objects (`{{user.id}}`). Missing
+/// arguments are passed to `encode` as `null`.
+pub fn render_template(
+    template: &str,
+    arguments: &Value,
+    encode: impl Fn(&Value) -> String,
+) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+
+        let path = rest[start + 2..start + 2 + len].trim();
+        let value = path
+            .split('.')
+            .try_fold(arguments, |value, key| value.get(key))
+            .unwrap_or(&Value::Null);
+        output.push_str(&encode(value));
+
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Plain text form of a value (strings without quotes, `null` as empty)
+pub fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Percent-encode a value for use in a URL path segment or query parameter
+pub fn encode_url_value(value: &Value) -> String {
+    value_to_text(value)
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn definition() -> HttpToolDefinition {
+        HttpToolDefinition {
+            name: "create_ticket".to_string(),
+            description: "Create a support ticket".to_string(),
+            parameters: json!({"type": "object", "properties": {"title": {"type": "string"}}}),
+            endpoint: HttpToolEndpoint {
+                method: HttpToolMethod::Post,
+                url: "https://api.example.com/projects/{{project}}/tickets".to_string(),
+                headers: HashMap::new(),
+                body: None,
+                auth: Some(HttpToolAuth::Bearer),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(definition().validate().is_ok());
+
+        let mut invalid = definition();
+        invalid.name = "bad name".to_string();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = definition();
+        invalid.parameters = json!({"type": "string"});
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = definition();
+        invalid.endpoint.url = "ftp://example.com/{{x}}".to_string();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_capability_id() {
+        assert_eq!(definition().capability_id(), "custom:create_ticket");
+        assert_eq!(
+            tool_name_from_capability_id("custom:create_ticket"),
+            Some("create_ticket")
+        );
+        assert_eq!(tool_name_from_capability_id("web_fetch"), None);
+    }
+
+    #[test]
+    fn test_render_template() {
+        let args = json!({"title": "Disk \"full\"", "user": {"id": 7}, "tags": ["a"]});
+
+        assert_eq!(
+            render_template(
+                r#"{"title": {{title}}, "user": {{ user.id }}, "tags": {{tags}}, "x": {{missing}}}"#,
+                &args,
+                |v| v.to_string()
+            ),
+            r#"{"title": "Disk \"full\"", "user": 7, "tags": ["a"], "x": null}"#
+        );
+        assert_eq!(
+            render_template("/search?q={{title}}&u={{user.id}}", &args, encode_url_value),
+            "/search?q=Disk%20%22full%22&u=7"
+        );
+        assert_eq!(
+            render_template("no {{placeholder", &args, value_to_text),
+            "no {{placeholder"
+        );
+    }
+
+    #[test]
+    fn test_method_serialization() {
+        assert_eq!(
+            serde_json::to_value(HttpToolMethod::Patch).unwrap(),
+            json!("PATCH")
+        );
+        assert!(HttpToolMethod::Put.has_body());
+        assert!(!HttpToolMethod::Get.has_body());
+    }
+}
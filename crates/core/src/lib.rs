@@ -40,10 +40,16 @@ pub mod session_file;
 pub mod atoms;
 pub mod capabilities;
 pub mod error;
+pub mod file_edit;
+pub mod http_tool;
 pub mod llm_driver_registry;
+pub mod mcp;
 pub mod message;
+pub mod network_guard;
 pub mod openai_protocol;
+pub mod prompt_template;
 pub mod runtime_agent;
+pub mod structured_output;
 pub mod tools;
 pub mod traits;
 
@@ -64,8 +70,9 @@ pub use message::{
     ReasoningConfig, TextContentPart, ToolCallContentPart, ToolResultContentPart,
 };
 pub use runtime_agent::{RuntimeAgent, RuntimeAgentBuilder};
+pub use structured_output::ResponseFormat;
 pub use traits::{
-    EventEmitter, InputMessage, LlmProviderStore, MessageStore, ModelWithProvider,
+    EventEmitter, InputMessage, LlmProviderStore, LlmRateLimiter, MessageStore, ModelWithProvider,
     NoopEventEmitter, SessionFileStore, SessionStore, ToolContext, ToolExecutor,
 };
 
@@ -77,6 +84,7 @@ pub use llm_driver_registry::{
     BoxedLlmDriver, DriverFactory, DriverRegistry, LlmCallConfig, LlmCallConfigBuilder,
     LlmCompletionMetadata, LlmContentPart, LlmDriver, LlmMessage, LlmMessageContent,
     LlmMessageRole, LlmResponse, LlmResponseStream, LlmStreamEvent, ProviderConfig, ProviderType,
+    RateLimitInfo,
 };
 
 // OpenAI Protocol driver (base implementation for OpenAI-compatible APIs)
@@ -93,9 +101,9 @@ pub use capabilities::{
     apply_capabilities, AddTool, AppliedCapabilities, Capability, CapabilityId, CapabilityRegistry,
     CapabilityRegistryBuilder, CapabilityStatus, CurrentTimeCapability, DeleteFileTool, DivideTool,
     FileSystemCapability, GetCurrentTimeTool, GetForecastTool, GetWeatherTool, GrepFilesTool,
-    ListDirectoryTool, MultiplyTool, NoopCapability, ReadFileTool, ResearchCapability,
-    SandboxCapability, StatFileTool, StatelessTodoListCapability, SubtractTool, TestMathCapability,
-    TestWeatherCapability, WriteFileTool, WriteTodosTool,
+    HttpToolCapability, ListDirectoryTool, McpCapability, MultiplyTool, NoopCapability,
+    ReadFileTool, ResearchCapability, SandboxCapability, StatFileTool, StatelessTodoListCapability,
+    SubtractTool, TestMathCapability, TestWeatherCapability, WriteFileTool, WriteTodosTool,
 };
 
 // Atoms re-exports (stateless atomic operations)
@@ -105,7 +113,7 @@ pub use atoms::{
 };
 
 // Tool types (runtime types defined in this crate)
-pub use tool_types::{BuiltinTool, ToolCall, ToolDefinition, ToolPolicy, ToolResult};
+pub use tool_types::{BuiltinTool, ToolCall, ToolChoice, ToolDefinition, ToolPolicy, ToolResult};
 
 // Note: CapabilityId and CapabilityStatus are re-exported via capabilities module
 
@@ -129,7 +137,10 @@ pub use llm_models::{
     ReasoningEffortConfig, ReasoningEffortValue,
 };
 pub use session::{Session, SessionStatus};
-pub use session_file::{FileInfo, FileStat, GrepMatch, GrepResult, SessionFile};
+pub use session_file::{
+    FileChange, FileDiff, FileInfo, FileStat, FileVersion, GrepMatch, GrepResult, RestoreTarget,
+    SessionFile, SessionSnapshot, SnapshotKind,
+};
 
 // OTel event listener (observation backend)
 pub use observation::OtelEventListener;
@@ -16,12 +16,14 @@
 
 use crate::error::{AgentLoopError, Result};
 use crate::runtime_agent::RuntimeAgent;
-use crate::tool_types::{ToolCall, ToolDefinition};
+use crate::structured_output::ResponseFormat;
+use crate::tool_types::{ToolCall, ToolChoice, ToolDefinition};
 use async_trait::async_trait;
 use futures::Stream;
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 // ============================================================================
 // LlmDriver Trait
@@ -58,6 +60,35 @@ pub struct LlmCompletionMetadata {
     pub finish_reason: Option<String>,
 }
 
+/// Rate limit state reported by a provider in response headers
+///
+/// Every field is optional: providers report different subsets, and a
+/// response may carry only `retry-after` (e.g. a 429).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitInfo {
+    /// Requests allowed per window
+    pub request_limit: Option<u32>,
+    /// Requests remaining in the current window
+    pub requests_remaining: Option<u32>,
+    /// Time until the request window resets
+    pub requests_reset: Option<Duration>,
+    /// Tokens allowed per window
+    pub token_limit: Option<u32>,
+    /// Tokens remaining in the current window
+    pub tokens_remaining: Option<u32>,
+    /// Time until the token window resets
+    pub tokens_reset: Option<Duration>,
+    /// Provider-requested delay before the next request
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitInfo {
+    /// Whether any rate limit header was present
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Trait for LLM drivers
 ///
 /// Implementations handle provider-specific API calls and response parsing.
@@ -70,6 +101,14 @@ pub trait LlmDriver: Send + Sync {
         config: &LlmCallConfig,
     ) -> Result<LlmResponseStream>;
 
+    /// Rate limit headers from the most recent call, if the provider sent any
+    ///
+    /// Set for both successful and rate-limited (429) responses, so callers can
+    /// adapt shared rate limiters to what the provider reports.
+    fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        None
+    }
+
     /// Call the LLM without streaming (convenience method)
     async fn chat_completion(
         &self,
@@ -122,6 +161,10 @@ impl LlmDriver for Box<dyn LlmDriver> {
     ) -> Result<LlmResponse> {
         (**self).chat_completion(messages, config).await
     }
+
+    fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        (**self).last_rate_limit()
+    }
 }
 
 // ============================================================================
@@ -262,6 +305,12 @@ pub struct LlmCallConfig {
     pub tools: Vec<ToolDefinition>,
     /// Reasoning effort level (for models that support it: low, medium, high)
     pub reasoning_effort: Option<String>,
+    /// JSON-schema constrained response format (mapped to provider-native structured output)
+    pub response_format: Option<ResponseFormat>,
+    /// Tool choice (None = provider default, usually auto)
+    pub tool_choice: Option<ToolChoice>,
+    /// Whether the model may emit several tool calls per response (None = provider default)
+    pub parallel_tool_calls: Option<bool>,
 }
 
 impl From<&RuntimeAgent> for LlmCallConfig {
@@ -272,6 +321,9 @@ impl From<&RuntimeAgent> for LlmCallConfig {
             max_tokens: runtime_agent.max_tokens,
             tools: runtime_agent.tools.clone(),
             reasoning_effort: None, // Set by ReasonAtom from user message controls
+            response_format: runtime_agent.response_format.clone(),
+            tool_choice: runtime_agent.tool_choice.clone(),
+            parallel_tool_calls: runtime_agent.parallel_tool_calls,
         }
     }
 }
@@ -344,6 +396,24 @@ impl LlmCallConfigBuilder {
         self
     }
 
+    /// Set response format (overrides the runtime agent's format)
+    pub fn response_format(mut self, format: ResponseFormat) -> Self {
+        self.config.response_format = Some(format);
+        self
+    }
+
+    /// Set tool choice (overrides the runtime agent's tool choice)
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.config.tool_choice = Some(choice);
+        self
+    }
+
+    /// Allow or disallow parallel tool calls
+    pub fn parallel_tool_calls(mut self, parallel: bool) -> Self {
+        self.config.parallel_tool_calls = Some(parallel);
+        self
+    }
+
     /// Build the configuration
     pub fn build(self) -> LlmCallConfig {
         self.config
@@ -559,6 +629,7 @@ impl DriverRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runtime_agent::RuntimeAgentBuilder;
 
     #[test]
     fn test_llm_call_config_builder_from_runtime_agent() {
@@ -598,6 +669,21 @@ mod tests {
         assert_eq!(llm_config.max_tokens, Some(1000));
     }
 
+    #[test]
+    fn test_llm_call_config_builder_tool_settings_override_runtime_agent() {
+        let runtime_agent = RuntimeAgentBuilder::new()
+            .tool_choice(ToolChoice::Auto)
+            .parallel_tool_calls(true)
+            .build();
+        let llm_config = LlmCallConfigBuilder::from(&runtime_agent)
+            .tool_choice(ToolChoice::None)
+            .parallel_tool_calls(false)
+            .build();
+
+        assert_eq!(llm_config.tool_choice, Some(ToolChoice::None));
+        assert_eq!(llm_config.parallel_tool_calls, Some(false));
+    }
+
     #[test]
     fn test_provider_type_parsing() {
         assert_eq!(
@@ -80,6 +80,12 @@ pub struct LlmProvider {
     /// Whether an API key is configured (key is never returned)
     pub api_key_set: bool,
     pub status: LlmProviderStatus,
+    /// Requests per minute allowed across all workers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Tokens (prompt + completion) per minute allowed across all workers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_per_minute: Option<u32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -506,6 +506,9 @@ mod tests {
             max_tokens: None,
             tools: vec![],
             reasoning_effort: None,
+            response_format: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
         }
     }
 
@@ -0,0 +1,1163 @@
+// MCP (Model Context Protocol) client
+//
+// Connects to MCP servers and exposes their tools to the agent loop.
+//
+// Design decisions:
+// - Two transports: stdio (spawned child process, newline-delimited JSON-RPC)
+//   and streamable HTTP (JSON-RPC over POST, JSON or SSE responses)
+// - Only the tools feature of the protocol is used (initialize, tools/list, tools/call)
+// - Server configs are plain data stored on the agent; credentials are never part of
+//   the config and are supplied separately (decrypted by the control-plane)
+// - Credentials become environment variables for stdio servers and HTTP headers
+//   for HTTP servers
+// - Stdio servers run commands on the worker, so a worker only spawns commands
+//   its operator allowed (`MCP_STDIO_COMMANDS`); agent config alone is not enough
+// - The allowed command is resolved with the worker's own PATH and the child gets
+//   a cleared environment: a small base copied from the worker plus the configured
+//   variables and credentials, none of which may change how programs are found
+//   or loaded (PATH, LD_*, NODE_OPTIONS, ...)
+// - HTTP servers may not reach private network addresses unless the operator
+//   allows it (`MCP_ALLOW_PRIVATE_NETWORKS`), using the shared network guard
+// - Clients live for one turn: `McpClientPool` keeps a turn's connections so its
+//   reason and act steps on the same worker reuse them; idle clients are dropped
+//   (stdio children are killed on drop)
+
+use crate::network_guard::{guard_client, literal_private_host, private_target_refused};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// Protocol revision sent during initialization
+pub const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Separator between the server name and the remote tool name in exposed tool names
+pub const MCP_TOOL_SEPARATOR: &str = "__";
+
+/// Timeout for a single JSON-RPC request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum length of an exposed tool name (OpenAI and Anthropic limit)
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// Environment variable listing the stdio commands a worker may spawn
+/// (comma-separated). Stdio servers are refused when it is unset.
+pub const MCP_STDIO_COMMANDS_ENV: &str = "MCP_STDIO_COMMANDS";
+
+/// Environment variable letting HTTP servers use private network addresses
+pub const MCP_ALLOW_PRIVATE_NETWORKS_ENV: &str = "MCP_ALLOW_PRIVATE_NETWORKS";
+
+/// How long a pooled client may sit unused before it is dropped
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Maximum number of redirects followed by HTTP servers
+const MAX_REDIRECTS: usize = 10;
+
+/// Worker environment variables passed on to stdio servers
+const STDIO_BASE_ENV: &[&str] = &["PATH", "HOME", "USER", "LANG", "LC_ALL", "TZ", "TMPDIR"];
+
+/// Variables stdio server configs and credentials may not set: they change
+/// which programs run or what code a program loads
+const DENIED_ENV_NAMES: &[&str] = &[
+    "PATH",
+    "HOME",
+    "SHELL",
+    "ENV",
+    "BASH_ENV",
+    "IFS",
+    "CDPATH",
+    "PS4",
+    "CLASSPATH",
+    "_JAVA_OPTIONS",
+    "JDK_JAVA_OPTIONS",
+    "GCONV_PATH",
+    "GLIBC_TUNABLES",
+    "HOSTALIASES",
+    "LOCPATH",
+    "OPENSSL_CONF",
+    "TMPDIR",
+    "XDG_CONFIG_HOME",
+];
+
+/// Prefixes of denied variables (`LD_PRELOAD`, `NODE_OPTIONS`, `PYTHONPATH`, ...)
+const DENIED_ENV_PREFIXES: &[&str] = &[
+    "LD_",
+    "DYLD_",
+    "NODE_",
+    "NPM_CONFIG_",
+    "PYTHON",
+    "PIP_",
+    "UV_",
+    "PERL",
+    "RUBY",
+    "JAVA_",
+    "GIT_",
+    "BUN_",
+    "DENO_",
+    "BASH_FUNC_",
+];
+
+/// Check a variable name set by a stdio server config or credential
+fn check_env_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("invalid environment variable name '{}'", name));
+    }
+    let upper = name.to_ascii_uppercase();
+    if DENIED_ENV_NAMES.contains(&upper.as_str())
+        || DENIED_ENV_PREFIXES.iter().any(|p| upper.starts_with(p))
+    {
+        return Err(format!("environment variable '{}' may not be set", name));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Configuration
+// ============================================================================
+
+/// MCP server configured on an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct McpServerConfig {
+    /// Server name. Prefixes the server's tools (`<name>__<tool>`).
+    pub name: String,
+    /// How to reach the server
+    pub transport: McpTransportConfig,
+    /// Names of credentials stored for this server. Values are kept in the
+    /// encrypted secrets store and are never returned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credential_names: Vec<String>,
+}
+
+/// Transport used to reach an MCP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum McpTransportConfig {
+    /// Spawn the server as a child process and talk over stdin/stdout.
+    /// Credentials are passed as environment variables.
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+    },
+    /// Streamable HTTP endpoint. Credentials are sent as HTTP headers.
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+impl McpServerConfig {
+    /// Validate the server definition (name format and transport settings)
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_name = !self.name.is_empty()
+            && self.name.len() <= 32
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+            && !self.name.contains(MCP_TOOL_SEPARATOR);
+        if !valid_name {
+            return Err(format!(
+                "MCP server name '{}' must be 1-32 characters of lowercase letters, digits, '_' and '-' without '__'",
+                self.name
+            ));
+        }
+
+        match &self.transport {
+            McpTransportConfig::Stdio { command, env, .. } => {
+                if command.trim().is_empty() {
+                    return Err(format!("MCP server '{}' has an empty command", self.name));
+                }
+                for name in env.keys().chain(&self.credential_names) {
+                    check_env_name(name)
+                        .map_err(|e| format!("MCP server '{}': {}", self.name, e))?;
+                }
+            }
+            McpTransportConfig::Http { url, .. } => {
+                let parsed = url::Url::parse(url)
+                    .map_err(|e| format!("MCP server '{}' has an invalid url: {}", self.name, e))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err(format!(
+                        "MCP server '{}' url must use http or https",
+                        self.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prefix of the tool names exposed for this server
+    pub fn tool_prefix(&self) -> String {
+        format!("{}{}", self.name, MCP_TOOL_SEPARATOR)
+    }
+
+    /// Name under which a remote tool is exposed to the model.
+    ///
+    /// Characters not accepted by LLM providers are replaced with `_` and the
+    /// result is truncated to the provider limit.
+    pub fn exposed_tool_name(&self, remote_name: &str) -> String {
+        let sanitized: String = remote_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let mut name = format!("{}{}", self.tool_prefix(), sanitized);
+        name.truncate(MAX_TOOL_NAME_LEN);
+        name
+    }
+}
+
+/// Server config together with its decrypted credentials, as handed to workers
+#[derive(Clone, PartialEq)]
+pub struct McpServerConnection {
+    pub config: McpServerConfig,
+    pub credentials: HashMap<String, String>,
+}
+
+impl McpServerConnection {
+    pub fn new(config: McpServerConfig, credentials: HashMap<String, String>) -> Self {
+        Self {
+            config,
+            credentials,
+        }
+    }
+}
+
+// Credential values must never end up in logs
+impl std::fmt::Debug for McpServerConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpServerConnection")
+            .field("config", &self.config)
+            .field("credentials", &self.credentials.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+// ============================================================================
+// Errors and protocol types
+// ============================================================================
+
+/// Errors raised while talking to an MCP server
+#[derive(Debug, Error)]
+pub enum McpError {
+    #[error("MCP transport error: {0}")]
+    Transport(String),
+
+    #[error("MCP protocol error: {0}")]
+    Protocol(String),
+
+    #[error("MCP server error {code}: {message}")]
+    Server { code: i64, message: String },
+
+    #[error("MCP request timed out: {0}")]
+    Timeout(String),
+
+    #[error("MCP server not allowed: {0}")]
+    NotAllowed(String),
+}
+
+/// Tool advertised by an MCP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_input_schema")]
+    pub input_schema: Value,
+}
+
+fn default_input_schema() -> Value {
+    json!({"type": "object"})
+}
+
+/// Result of a tools/call request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpCallResult {
+    #[serde(default)]
+    pub content: Vec<Value>,
+    #[serde(default)]
+    pub is_error: bool,
+    #[serde(default)]
+    pub structured_content: Option<Value>,
+}
+
+impl McpCallResult {
+    /// Collapse the result into a single JSON value for the tool result.
+    ///
+    /// Structured content wins; a single text block becomes a string; anything
+    /// else is returned as the raw content array.
+    pub fn into_value(self) -> Value {
+        if let Some(structured) = self.structured_content {
+            return structured;
+        }
+        let texts: Vec<&str> = self
+            .content
+            .iter()
+            .filter(|c| c.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|c| c.get("text").and_then(|t| t.as_str()))
+            .collect();
+        if texts.len() == self.content.len() && !texts.is_empty() {
+            return Value::String(texts.join("\n"));
+        }
+        Value::Array(self.content)
+    }
+}
+
+// ============================================================================
+// Transports
+// ============================================================================
+
+/// A JSON-RPC message channel to an MCP server
+#[async_trait]
+trait Transport: Send + Sync {
+    /// Send a message. Returns the matching response for requests and
+    /// `None` for notifications.
+    async fn send(&self, message: Value) -> Result<Option<Value>, McpError>;
+}
+
+/// Stdio transport: newline-delimited JSON-RPC over a child process
+struct StdioTransport {
+    io: Mutex<(ChildStdin, BufReader<ChildStdout>)>,
+    // Held so the child is killed when the transport is dropped
+    _child: Child,
+}
+
+impl StdioTransport {
+    fn spawn(
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+        credentials: &HashMap<String, String>,
+    ) -> Result<Self, McpError> {
+        for name in env.keys().chain(credentials.keys()) {
+            check_env_name(name).map_err(McpError::NotAllowed)?;
+        }
+        // Resolved before the child environment is built, so configured
+        // variables cannot change which program runs
+        let program = resolve_command(command)?;
+        let base_env = STDIO_BASE_ENV
+            .iter()
+            .filter_map(|name| std::env::var_os(name).map(|value| (*name, value)));
+
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .env_clear()
+            .envs(base_env)
+            .envs(env)
+            .envs(credentials)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| McpError::Transport(format!("failed to spawn '{}': {}", command, e)))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| McpError::Transport("child stdin unavailable".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| McpError::Transport("child stdout unavailable".to_string()))?;
+
+        Ok(Self {
+            io: Mutex::new((stdin, BufReader::new(stdout))),
+            _child: child,
+        })
+    }
+}
+
+/// Absolute path of a command, searched in the worker's PATH unless it
+/// already contains a path
+fn resolve_command(command: &str) -> Result<PathBuf, McpError> {
+    if command.contains('/') {
+        return Ok(PathBuf::from(command));
+    }
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_absolute() && is_executable(candidate))
+        .ok_or_else(|| McpError::Transport(format!("command '{}' not found in PATH", command)))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn send(&self, message: Value) -> Result<Option<Value>, McpError> {
+        let mut io = self.io.lock().await;
+        let (stdin, stdout) = &mut *io;
+
+        let mut line = message.to_string();
+        line.push('\n');
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| McpError::Transport(e.to_string()))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|e| McpError::Transport(e.to_string()))?;
+
+        let Some(id) = message.get("id").cloned() else {
+            return Ok(None);
+        };
+
+        // Skip server notifications and requests until our response arrives
+        loop {
+            let mut buf = String::new();
+            let read = stdout
+                .read_line(&mut buf)
+                .await
+                .map_err(|e| McpError::Transport(e.to_string()))?;
+            if read == 0 {
+                return Err(McpError::Transport("server closed stdout".to_string()));
+            }
+            let Ok(response) = serde_json::from_str::<Value>(buf.trim()) else {
+                continue;
+            };
+            if response.get("id") == Some(&id) && response.get("method").is_none() {
+                return Ok(Some(response));
+            }
+        }
+    }
+}
+
+/// Streamable HTTP transport: JSON-RPC POSTs answered with JSON or an SSE stream
+struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: reqwest::header::HeaderMap,
+    session_id: Mutex<Option<String>>,
+}
+
+impl HttpTransport {
+    fn new(
+        url: &str,
+        headers: &HashMap<String, String>,
+        credentials: &HashMap<String, String>,
+        allow_private_networks: bool,
+    ) -> Result<Self, McpError> {
+        use reqwest::header::{HeaderName, HeaderValue};
+
+        let mut builder = reqwest::Client::builder();
+        if !allow_private_networks {
+            let parsed = url::Url::parse(url)
+                .map_err(|e| McpError::Transport(format!("invalid url: {}", e)))?;
+            if let Some(ip) = literal_private_host(&parsed) {
+                return Err(McpError::NotAllowed(format!(
+                    "private network address {} is not allowed",
+                    ip
+                )));
+            }
+            builder = guard_client(builder, MAX_REDIRECTS);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| McpError::Transport(format!("failed to build HTTP client: {}", e)))?;
+
+        let mut header_map = reqwest::header::HeaderMap::new();
+        for (name, value) in headers.iter().chain(credentials) {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| McpError::Transport(format!("invalid header name: {}", e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| McpError::Transport(format!("invalid header value: {}", e)))?;
+            header_map.insert(name, value);
+        }
+
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            headers: header_map,
+            session_id: Mutex::new(None),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, message: Value) -> Result<Option<Value>, McpError> {
+        use reqwest::header::{ACCEPT, CONTENT_TYPE};
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(&message);
+        if let Some(session_id) = self.session_id.lock().await.as_ref() {
+            request = request.header("mcp-session-id", session_id);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            if private_target_refused(&e) {
+                McpError::NotAllowed(e.to_string())
+            } else {
+                McpError::Transport(e.to_string())
+            }
+        })?;
+
+        if let Some(session_id) = response
+            .headers()
+            .get("mcp-session-id")
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock().await = Some(session_id.to_string());
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(McpError::Transport(format!("HTTP {}", status)));
+        }
+
+        let Some(id) = message.get("id").cloned() else {
+            return Ok(None);
+        };
+
+        let is_sse = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+        if !is_sse {
+            let body: Value = response
+                .json()
+                .await
+                .map_err(|e| McpError::Protocol(format!("invalid JSON response: {}", e)))?;
+            return Ok(Some(body));
+        }
+
+        use eventsource_stream::Eventsource;
+        let mut events = response.bytes_stream().eventsource();
+        while let Some(event) = events.next().await {
+            let event = event.map_err(|e| McpError::Transport(e.to_string()))?;
+            let Ok(data) = serde_json::from_str::<Value>(&event.data) else {
+                continue;
+            };
+            if data.get("id") == Some(&id) && data.get("method").is_none() {
+                return Ok(Some(data));
+            }
+        }
+
+        Err(McpError::Protocol(
+            "event stream ended without a response".to_string(),
+        ))
+    }
+}
+
+// ============================================================================
+// McpClient
+// ============================================================================
+
+/// Initialized connection to an MCP server
+pub struct McpClient {
+    server_name: String,
+    transport: Box<dyn Transport>,
+    next_id: AtomicU64,
+}
+
+impl McpClient {
+    /// Connect to a server and perform the initialize handshake. HTTP servers
+    /// on private network addresses are refused unless `allow_private_networks`.
+    pub async fn connect(
+        config: &McpServerConfig,
+        credentials: &HashMap<String, String>,
+        allow_private_networks: bool,
+    ) -> Result<Self, McpError> {
+        let transport: Box<dyn Transport> = match &config.transport {
+            McpTransportConfig::Stdio { command, args, env } => {
+                Box::new(StdioTransport::spawn(command, args, env, credentials)?)
+            }
+            McpTransportConfig::Http { url, headers } => Box::new(HttpTransport::new(
+                url,
+                headers,
+                credentials,
+                allow_private_networks,
+            )?),
+        };
+
+        let client = Self {
+            server_name: config.name.clone(),
+            transport,
+            next_id: AtomicU64::new(1),
+        };
+
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "everruns",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+            .await?;
+        client
+            .transport
+            .send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await?;
+
+        Ok(client)
+    }
+
+    /// Name of the server this client is connected to
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    /// List all tools advertised by the server (follows pagination)
+    pub async fn list_tools(&self) -> Result<Vec<McpToolInfo>, McpError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+
+            let page: Vec<McpToolInfo> =
+                serde_json::from_value(result.get("tools").cloned().unwrap_or_else(|| json!([])))
+                    .map_err(|e| McpError::Protocol(format!("invalid tools/list result: {}", e)))?;
+            tools.extend(page);
+
+            cursor = result
+                .get("nextCursor")
+                .and_then(|c| c.as_str())
+                .map(String::from);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Call a tool on the server
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<McpCallResult, McpError> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+        serde_json::from_value(result)
+            .map_err(|e| McpError::Protocol(format!("invalid tools/call result: {}", e)))
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, self.transport.send(message))
+            .await
+            .map_err(|_| McpError::Timeout(method.to_string()))??
+            .ok_or_else(|| McpError::Protocol(format!("no response to {}", method)))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(McpError::Server {
+                code: error.get("code").and_then(|c| c.as_i64()).unwrap_or(0),
+                message: error
+                    .get("message")
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("unknown error")
+                    .to_string(),
+            });
+        }
+
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| McpError::Protocol(format!("missing result for {}", method)))
+    }
+}
+
+impl std::fmt::Debug for McpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpClient")
+            .field("server_name", &self.server_name)
+            .finish()
+    }
+}
+
+// ============================================================================
+// McpClientPool
+// ============================================================================
+
+/// Worker-side MCP connections, shared by the steps of a turn
+///
+/// Clients are keyed by a turn scope (the turn's input message id) and server
+/// name. The pool also enforces the operator's stdio allowlist and private
+/// network policy.
+pub struct McpClientPool {
+    stdio_commands: Vec<String>,
+    allow_private_networks: bool,
+    clients: std::sync::Mutex<HashMap<(Uuid, String), PooledClient>>,
+}
+
+struct PooledClient {
+    connection: McpServerConnection,
+    client: Arc<McpClient>,
+    last_used: Instant,
+}
+
+impl McpClientPool {
+    /// Create a pool that may spawn the given stdio commands (exact match)
+    pub fn new(stdio_commands: Vec<String>) -> Self {
+        Self {
+            stdio_commands,
+            allow_private_networks: false,
+            clients: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Let HTTP servers use private network addresses (refused by default)
+    pub fn allow_private_networks(mut self, allow: bool) -> Self {
+        self.allow_private_networks = allow;
+        self
+    }
+
+    /// Check that the worker may connect to the server
+    pub fn check_allowed(&self, config: &McpServerConfig) -> Result<(), McpError> {
+        match &config.transport {
+            McpTransportConfig::Stdio { command, .. }
+                if !self.stdio_commands.iter().any(|c| c == command) =>
+            {
+                Err(McpError::NotAllowed(format!(
+                    "stdio command '{}' of server '{}' is not in {}",
+                    command, config.name, MCP_STDIO_COMMANDS_ENV
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Client for the server within the scope, connecting on first use
+    pub async fn client(
+        &self,
+        scope: Uuid,
+        server: &McpServerConnection,
+    ) -> Result<Arc<McpClient>, McpError> {
+        self.check_allowed(&server.config)?;
+        let key = (scope, server.config.name.clone());
+
+        {
+            let mut clients = self.clients.lock().unwrap();
+            let now = Instant::now();
+            clients.retain(|_, c| now.duration_since(c.last_used) < POOL_IDLE_TIMEOUT);
+            if let Some(pooled) = clients.get_mut(&key) {
+                if pooled.connection == *server {
+                    pooled.last_used = now;
+                    return Ok(pooled.client.clone());
+                }
+            }
+        }
+
+        let client = Arc::new(
+            McpClient::connect(
+                &server.config,
+                &server.credentials,
+                self.allow_private_networks,
+            )
+            .await?,
+        );
+        self.clients.lock().unwrap().insert(
+            key,
+            PooledClient {
+                connection: server.clone(),
+                client: client.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        Ok(client)
+    }
+
+    /// Drop the clients of a finished scope
+    pub fn release(&self, scope: Uuid) {
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|(client_scope, _), _| *client_scope != scope);
+    }
+}
+
+impl Default for McpClientPool {
+    /// Pool that refuses all stdio servers
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl std::fmt::Debug for McpClientPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpClientPool")
+            .field("stdio_commands", &self.stdio_commands)
+            .field("allow_private_networks", &self.allow_private_networks)
+            .finish()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn http_config(url: &str) -> McpServerConfig {
+        McpServerConfig {
+            name: "docs".to_string(),
+            transport: McpTransportConfig::Http {
+                url: url.to_string(),
+                headers: HashMap::new(),
+            },
+            credential_names: vec![],
+        }
+    }
+
+    async fn mount_initialize(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "initialize"})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("mcp-session-id", "session-1")
+                    .set_body_json(json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "result": {"protocolVersion": MCP_PROTOCOL_VERSION, "capabilities": {"tools": {}}}
+                    })),
+            )
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "notifications/initialized"}),
+            ))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn test_server_config_validation() {
+        assert!(http_config("https://mcp.example.com/mcp")
+            .validate()
+            .is_ok());
+        assert!(http_config("ftp://mcp.example.com").validate().is_err());
+
+        let mut config = http_config("https://mcp.example.com/mcp");
+        config.name = "Bad__Name".to_string();
+        assert!(config.validate().is_err());
+
+        let stdio = McpServerConfig {
+            name: "fs".to_string(),
+            transport: McpTransportConfig::Stdio {
+                command: " ".to_string(),
+                args: vec![],
+                env: HashMap::new(),
+            },
+            credential_names: vec![],
+        };
+        assert!(stdio.validate().is_err());
+
+        let mut stdio = McpServerConfig {
+            name: "fs".to_string(),
+            transport: McpTransportConfig::Stdio {
+                command: "npx".to_string(),
+                args: vec![],
+                env: HashMap::from([("API_URL".to_string(), "x".to_string())]),
+            },
+            credential_names: vec!["API_TOKEN".to_string()],
+        };
+        assert!(stdio.validate().is_ok());
+        for name in [
+            "PATH",
+            "LD_PRELOAD",
+            "dyld_insert_libraries",
+            "NODE_OPTIONS",
+            "PYTHONPATH",
+            "A=B",
+        ] {
+            stdio.credential_names = vec![name.to_string()];
+            assert!(stdio.validate().is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_server_config_serialization() {
+        let config: McpServerConfig = serde_json::from_value(json!({
+            "name": "github",
+            "transport": {"type": "stdio", "command": "github-mcp", "args": ["stdio"]},
+            "credential_names": ["GITHUB_TOKEN"]
+        }))
+        .unwrap();
+
+        assert_eq!(config.credential_names, vec!["GITHUB_TOKEN"]);
+        assert!(matches!(
+            config.transport,
+            McpTransportConfig::Stdio { ref command, .. } if command == "github-mcp"
+        ));
+    }
+
+    #[test]
+    fn test_exposed_tool_name() {
+        let config = http_config("https://mcp.example.com/mcp");
+        assert_eq!(config.exposed_tool_name("search"), "docs__search");
+        assert_eq!(config.exposed_tool_name("read.page"), "docs__read_page");
+        assert_eq!(config.exposed_tool_name(&"x".repeat(100)).len(), 64);
+    }
+
+    #[test]
+    fn test_call_result_into_value() {
+        let text = McpCallResult {
+            content: vec![json!({"type": "text", "text": "hello"})],
+            is_error: false,
+            structured_content: None,
+        };
+        assert_eq!(text.into_value(), json!("hello"));
+
+        let structured = McpCallResult {
+            content: vec![json!({"type": "text", "text": "{}"})],
+            is_error: false,
+            structured_content: Some(json!({"count": 2})),
+        };
+        assert_eq!(structured.into_value(), json!({"count": 2}));
+    }
+
+    #[tokio::test]
+    async fn test_http_list_tools() {
+        let server = MockServer::start().await;
+        mount_initialize(&server).await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "tools/list"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "result": {"tools": [{
+                    "name": "search",
+                    "description": "Search the docs",
+                    "inputSchema": {"type": "object", "properties": {"q": {"type": "string"}}}
+                }]}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = McpClient::connect(&http_config(&server.uri()), &HashMap::new(), true)
+            .await
+            .unwrap();
+        let tools = client.list_tools().await.unwrap();
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "search");
+        assert_eq!(tools[0].description.as_deref(), Some("Search the docs"));
+    }
+
+    #[tokio::test]
+    async fn test_http_call_tool_sse_response() {
+        let server = MockServer::start().await;
+        mount_initialize(&server).await;
+        let sse = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"42\"}]}}\n\n";
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "tools/call", "params": {"name": "answer"}}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let client = McpClient::connect(&http_config(&server.uri()), &HashMap::new(), true)
+            .await
+            .unwrap();
+        let result = client.call_tool("answer", json!({})).await.unwrap();
+
+        assert!(!result.is_error);
+        assert_eq!(result.into_value(), json!("42"));
+    }
+
+    /// Minimal stdio server: canned responses, a notification before the
+    /// tools/list response, and the TOKEN credential echoed back by tools/call
+    #[cfg(unix)]
+    const STDIO_SERVER_SCRIPT: &str = r#"while read -r line; do
+  case "$line" in
+    *'"initialize"'*) echo '{"jsonrpc":"2.0","id":1,"result":{}}' ;;
+    *'"tools/list"'*)
+      echo '{"jsonrpc":"2.0","method":"notifications/message","params":{}}'
+      echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"whoami"}]}}' ;;
+    *'"tools/call"'*) echo "{\"jsonrpc\":\"2.0\",\"id\":3,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"$TOKEN\"}]}}" ;;
+  esac
+done"#;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_transport() {
+        let config = McpServerConfig {
+            name: "local".to_string(),
+            transport: McpTransportConfig::Stdio {
+                command: "sh".to_string(),
+                args: vec!["-c".to_string(), STDIO_SERVER_SCRIPT.to_string()],
+                env: HashMap::new(),
+            },
+            credential_names: vec!["TOKEN".to_string()],
+        };
+        let credentials = HashMap::from([("TOKEN".to_string(), "s3cret".to_string())]);
+
+        let client = McpClient::connect(&config, &credentials, false)
+            .await
+            .unwrap();
+        let tools = client.list_tools().await.unwrap();
+        let result = client.call_tool("whoami", json!({})).await.unwrap();
+
+        assert_eq!(tools[0].name, "whoami");
+        assert_eq!(tools[0].input_schema, json!({"type": "object"}));
+        assert_eq!(result.into_value(), json!("s3cret"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdio_environment_is_restricted() {
+        assert!(resolve_command("sh").unwrap().is_absolute());
+
+        for name in ["PATH", "LD_PRELOAD", "NODE_OPTIONS"] {
+            let config = McpServerConfig {
+                name: "local".to_string(),
+                transport: McpTransportConfig::Stdio {
+                    command: "sh".to_string(),
+                    args: vec!["-c".to_string(), STDIO_SERVER_SCRIPT.to_string()],
+                    env: HashMap::from([(name.to_string(), "/tmp/evil".to_string())]),
+                },
+                credential_names: vec![],
+            };
+            let err = McpClient::connect(&config, &HashMap::new(), false)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, McpError::NotAllowed(_)), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_private_targets_refused() {
+        let server = MockServer::start().await;
+        mount_initialize(&server).await;
+
+        let err = McpClient::connect(&http_config(&server.uri()), &HashMap::new(), false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::NotAllowed(_)));
+
+        let url = format!("http://localhost:{}/mcp", server.address().port());
+        let err = McpClient::connect(&http_config(&url), &HashMap::new(), false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::NotAllowed(_)));
+
+        let pool = McpClientPool::default().allow_private_networks(true);
+        let server = McpServerConnection::new(http_config(&server.uri()), HashMap::new());
+        assert!(pool.client(Uuid::now_v7(), &server).await.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_pool_enforces_stdio_allowlist_and_reuses_clients() {
+        let server = McpServerConnection::new(
+            McpServerConfig {
+                name: "local".to_string(),
+                transport: McpTransportConfig::Stdio {
+                    command: "sh".to_string(),
+                    args: vec!["-c".to_string(), STDIO_SERVER_SCRIPT.to_string()],
+                    env: HashMap::new(),
+                },
+                credential_names: vec![],
+            },
+            HashMap::new(),
+        );
+        let turn = Uuid::now_v7();
+
+        let err = McpClientPool::default()
+            .client(turn, &server)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::NotAllowed(_)));
+
+        let pool = McpClientPool::new(vec!["sh".to_string()]);
+        let first = pool.client(turn, &server).await.unwrap();
+        let same_turn = pool.client(turn, &server).await.unwrap();
+        let other_turn = pool.client(Uuid::now_v7(), &server).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &same_turn));
+        assert!(!Arc::ptr_eq(&first, &other_turn));
+
+        pool.release(turn);
+        let after_release = pool.client(turn, &server).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &after_release));
+    }
+
+    #[tokio::test]
+    async fn test_http_server_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": {"code": -32600, "message": "unsupported protocol version"}
+            })))
+            .mount(&server)
+            .await;
+
+        let err = McpClient::connect(&http_config(&server.uri()), &HashMap::new(), true)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, McpError::Server { code: -32600, .. }));
+    }
+}
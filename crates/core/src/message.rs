@@ -10,6 +10,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::structured_output::ResponseFormat;
+use crate::tool_types::ToolChoice;
+
 #[cfg(feature = "openapi")]
 use utoipa::ToSchema;
 
@@ -79,6 +82,21 @@ pub struct Controls {
     /// Reasoning configuration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning: Option<ReasoningConfig>,
+
+    /// JSON-schema constrained response format for this message.
+    /// Overrides the agent's response format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+
+    /// Tool choice for this message (auto, none, required, or a specific tool).
+    /// Overrides the agent's tool choice.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Allow the model to request several tool calls in one response.
+    /// When false, tool calls are also executed sequentially.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
 }
 
 /// A message in the conversation
@@ -0,0 +1,217 @@
+// Private network guard for outbound HTTP
+//
+// Requests whose target comes from users or agents (HTTP tools, MCP servers,
+// webhooks) must not reach internal services or cloud metadata endpoints.
+//
+// Design decisions:
+// - The guard is applied to a `reqwest::ClientBuilder`: a DNS resolver that
+//   fails when a name resolves to any private address, and a redirect policy
+//   that checks every redirect target
+// - IP literals are not resolved, so callers check the URL host with
+//   `literal_private_host` before sending
+// - IPv6 addresses that wrap an IPv4 address (mapped, compatible, NAT64,
+//   6to4) are judged by the wrapped address
+// - Allowing private networks is an operator decision; callers skip the
+//   guard entirely when it is allowed
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+/// Error raised when a request would reach a private network address
+#[derive(Debug, thiserror::Error)]
+#[error("private network address {0} is not allowed")]
+pub struct PrivateTargetError(pub IpAddr);
+
+/// Make the client refuse private targets, following at most `max_redirects`
+pub fn guard_client(
+    builder: reqwest::ClientBuilder,
+    max_redirects: usize,
+) -> reqwest::ClientBuilder {
+    builder
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= max_redirects {
+                return attempt.error("too many redirects");
+            }
+            match literal_private_host(attempt.url()) {
+                Some(ip) => attempt.error(PrivateTargetError(ip)),
+                None => attempt.follow(),
+            }
+        }))
+}
+
+/// Resolver that fails when a name resolves to any private address
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| is_private_address(addr.ip())) {
+                return Err(Box::new(PrivateTargetError(addr.ip())) as _);
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Private address used directly as the URL host (not resolved through DNS)
+pub fn literal_private_host(url: &url::Url) -> Option<IpAddr> {
+    let ip = match url.host()? {
+        url::Host::Ipv4(ip) => IpAddr::V4(ip),
+        url::Host::Ipv6(ip) => IpAddr::V6(ip),
+        url::Host::Domain(_) => return None,
+    };
+    is_private_address(ip).then_some(ip)
+}
+
+/// Whether the request failed because the guard refused its target
+pub fn private_target_refused(error: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if e.is::<PrivateTargetError>() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Whether the address belongs to a private, loopback, link-local or otherwise
+/// non-public range
+pub fn is_private_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // This network (0.0.0.0/8), including the unspecified address
+                || a == 0
+                // Reserved (240.0.0.0/4), including the broadcast address
+                || a >= 240
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (64..128).contains(&b))
+                // Benchmarking (198.18.0.0/15)
+                || (a == 198 && (b & 0xfe) == 18)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = embedded_ipv4(ip) {
+                return is_private_address(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local (fc00::/7), link-local (fe80::/10) and site-local (fec0::/10)
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first & 0xffc0) == 0xfec0
+                // Local-use NAT64 (64:ff9b:1::/48), which may embed any address
+                || ip.segments()[..3] == [0x64, 0xff9b, 1]
+        }
+    }
+}
+
+/// IPv4 address wrapped in an IPv6 address that reaches it
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let s = ip.segments();
+    let v4 = |hi: u16, lo: u16| Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo));
+    match s {
+        // IPv4-mapped (::ffff:0:0/96) and IPv4-compatible (::/96)
+        [0, 0, 0, 0, 0, 0xffff | 0, hi, lo] => Some(v4(hi, lo)),
+        // NAT64 well-known prefix (64:ff9b::/96)
+        [0x64, 0xff9b, 0, 0, 0, 0, hi, lo] => Some(v4(hi, lo)),
+        // 6to4 (2002::/16)
+        [0x2002, hi, lo, ..] => Some(v4(hi, lo)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_is_private_address() {
+        for ip in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "127.0.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "0.1.2.3",
+            "224.0.0.1",
+            "239.255.255.250",
+            "198.18.0.1",
+            "198.19.255.254",
+            "192.0.2.1",
+            "240.0.0.1",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "fec0::1",
+            "ff02::1",
+            "ff0e::1",
+            "::ffff:127.0.0.1",
+            "::ffff:10.0.0.1",
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::7f00:1",
+            "64:ff9b::a00:1",
+            "64:ff9b::a9fe:a9fe",
+            "64:ff9b:1::808:808",
+            "2002:7f00:1::",
+            "2002:a00:1::1",
+            "2002:c0a8:101::1",
+        ] {
+            assert!(is_private_address(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "8.8.8.8",
+            "100.128.0.1",
+            "198.20.0.1",
+            "223.255.255.255",
+            "2606:4700::1111",
+            "::ffff:8.8.8.8",
+            "64:ff9b::808:808",
+            "2002:808:808::1",
+        ] {
+            assert!(!is_private_address(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_guarded_client_refuses_private_targets() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let client = guard_client(reqwest::Client::builder(), 10)
+            .build()
+            .unwrap();
+
+        // Through DNS
+        let url = format!("http://localhost:{}/", server.address().port());
+        let err = client.get(&url).send().await.unwrap_err();
+        assert!(private_target_refused(&err));
+
+        // IP literals are checked by the caller
+        let literal = url::Url::parse("http://[::ffff:127.0.0.1]/").unwrap();
+        assert!(literal_private_host(&literal).is_some());
+        let public = url::Url::parse("http://example.com/").unwrap();
+        assert!(literal_private_host(&public).is_none());
+    }
+}
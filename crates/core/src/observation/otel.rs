@@ -553,6 +553,7 @@ mod tests {
         let started_data = TurnStartedData {
             turn_id,
             input_message_id: Uuid::now_v7(),
+            agent_revision: None,
         };
 
         let start_event = Event::new(
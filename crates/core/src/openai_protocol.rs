@@ -17,13 +17,15 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::error::{AgentLoopError, Result};
 use crate::llm_driver_registry::{
     LlmCallConfig, LlmCompletionMetadata, LlmContentPart, LlmDriver, LlmMessage, LlmMessageContent,
-    LlmMessageRole, LlmResponseStream, LlmStreamEvent,
+    LlmMessageRole, LlmResponseStream, LlmStreamEvent, RateLimitInfo,
 };
-use crate::tool_types::{ToolCall, ToolDefinition};
+use crate::structured_output::ResponseFormat;
+use crate::tool_types::{ToolCall, ToolChoice, ToolDefinition};
 
 const DEFAULT_API_URL: &str = "https://api.openai.com/v1/chat/completions";
 
@@ -51,6 +53,7 @@ pub struct OpenAIProtocolLlmDriver {
     client: Client,
     api_key: String,
     api_url: String,
+    rate_limit: Arc<Mutex<Option<RateLimitInfo>>>,
 }
 
 impl OpenAIProtocolLlmDriver {
@@ -60,6 +63,7 @@ impl OpenAIProtocolLlmDriver {
             client: Client::new(),
             api_key: api_key.into(),
             api_url: DEFAULT_API_URL.to_string(),
+            rate_limit: Arc::default(),
         }
     }
 
@@ -76,6 +80,7 @@ impl OpenAIProtocolLlmDriver {
             client: Client::new(),
             api_key: api_key.into(),
             api_url: api_url.into(),
+            rate_limit: Arc::default(),
         }
     }
 
@@ -179,6 +184,29 @@ impl OpenAIProtocolLlmDriver {
             })
             .collect()
     }
+
+    fn convert_tool_choice(choice: &ToolChoice) -> Value {
+        match choice {
+            ToolChoice::Auto => json!("auto"),
+            ToolChoice::None => json!("none"),
+            ToolChoice::Required => json!("required"),
+            ToolChoice::Tool { name } => json!({
+                "type": "function",
+                "function": { "name": name }
+            }),
+        }
+    }
+
+    fn convert_response_format(format: &ResponseFormat) -> OpenAiResponseFormat {
+        OpenAiResponseFormat {
+            r#type: "json_schema".to_string(),
+            json_schema: OpenAiJsonSchema {
+                name: format.name.clone(),
+                schema: format.schema.clone(),
+                strict: format.strict,
+            },
+        }
+    }
 }
 
 #[async_trait]
@@ -200,6 +228,16 @@ impl LlmDriver for OpenAIProtocolLlmDriver {
             Some(Self::convert_tools(&config.tools))
         };
 
+        // OpenAI rejects tool settings on requests without tools
+        let (tool_choice, parallel_tool_calls) = if tools.is_some() {
+            (
+                config.tool_choice.as_ref().map(Self::convert_tool_choice),
+                config.parallel_tool_calls,
+            )
+        } else {
+            (None, None)
+        };
+
         let request = OpenAiRequest {
             model: config.model.clone(),
             messages: openai_messages,
@@ -207,7 +245,13 @@ impl LlmDriver for OpenAIProtocolLlmDriver {
             max_tokens: config.max_tokens,
             stream: true,
             tools,
+            tool_choice,
+            parallel_tool_calls,
             reasoning_effort: config.reasoning_effort.clone(),
+            response_format: config
+                .response_format
+                .as_ref()
+                .map(Self::convert_response_format),
         };
 
         let response = self
@@ -220,6 +264,9 @@ impl LlmDriver for OpenAIProtocolLlmDriver {
             .await
             .map_err(|e| AgentLoopError::llm(format!("Failed to send request: {}", e)))?;
 
+        let rate_limit = parse_rate_limit_headers(response.headers());
+        *self.rate_limit.lock().unwrap() = (!rate_limit.is_empty()).then_some(rate_limit);
+
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
@@ -360,6 +407,10 @@ impl LlmDriver for OpenAIProtocolLlmDriver {
 
         Ok(converted_stream)
     }
+
+    fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        self.rate_limit.lock().unwrap().clone()
+    }
 }
 
 impl std::fmt::Debug for OpenAIProtocolLlmDriver {
@@ -387,7 +438,26 @@ struct OpenAiRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAiTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAiResponseFormat>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiResponseFormat {
+    r#type: String,
+    json_schema: OpenAiJsonSchema,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiJsonSchema {
+    name: String,
+    schema: Value,
+    strict: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -510,6 +580,67 @@ struct OpenAiStreamFunction {
     arguments: Option<String>,
 }
 
+// ============================================================================
+// Rate limit headers
+// ============================================================================
+
+/// Parse OpenAI `x-ratelimit-*` and `retry-after` headers
+///
+/// Reset values use OpenAI's duration format (`1s`, `6m0s`, `20ms`).
+pub fn parse_rate_limit_headers(headers: &reqwest::header::HeaderMap) -> RateLimitInfo {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let number = |name: &str| header(name).and_then(|v| v.trim().parse::<u32>().ok());
+
+    let retry_after = header("retry-after-ms")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .or_else(|| {
+            header("retry-after")
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .map(Duration::from_secs_f64)
+        });
+
+    RateLimitInfo {
+        request_limit: number("x-ratelimit-limit-requests"),
+        requests_remaining: number("x-ratelimit-remaining-requests"),
+        requests_reset: header("x-ratelimit-reset-requests").and_then(parse_reset_duration),
+        token_limit: number("x-ratelimit-limit-tokens"),
+        tokens_remaining: number("x-ratelimit-remaining-tokens"),
+        tokens_reset: header("x-ratelimit-reset-tokens").and_then(parse_reset_duration),
+        retry_after,
+    }
+}
+
+/// Parse a duration like `1h2m3.5s`, `6m0s` or `20ms`
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = 0.0;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let amount: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += amount * seconds;
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -533,4 +664,76 @@ mod tests {
         assert!(format!("{:?}", driver).contains("OpenAIProtocolLlmDriver"));
         assert_eq!(driver.api_url(), "https://custom.api.com/v1/completions");
     }
+
+    #[test]
+    fn test_convert_tool_choice() {
+        assert_eq!(
+            OpenAIProtocolLlmDriver::convert_tool_choice(&ToolChoice::Required),
+            json!("required")
+        );
+        assert_eq!(
+            OpenAIProtocolLlmDriver::convert_tool_choice(&ToolChoice::Tool {
+                name: "get_weather".to_string()
+            }),
+            json!({"type": "function", "function": {"name": "get_weather"}})
+        );
+    }
+
+    #[test]
+    fn test_convert_response_format_uses_json_schema() {
+        let format = ResponseFormat::new(
+            "answer",
+            json!({"type": "object", "properties": {"value": {"type": "integer"}}}),
+        );
+        let converted =
+            serde_json::to_value(OpenAIProtocolLlmDriver::convert_response_format(&format))
+                .unwrap();
+
+        assert_eq!(converted["type"], "json_schema");
+        assert_eq!(converted["json_schema"]["name"], "answer");
+        assert_eq!(converted["json_schema"]["strict"], true);
+        assert_eq!(
+            converted["json_schema"]["schema"]["properties"]["value"]["type"],
+            "integer"
+        );
+    }
+
+    #[test]
+    fn test_parse_reset_duration() {
+        assert_eq!(parse_reset_duration("1s"), Some(Duration::from_secs(1)));
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(
+            parse_reset_duration("20ms"),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            parse_reset_duration("1h2m3.5s"),
+            Some(Duration::from_secs_f64(3723.5))
+        );
+        assert_eq!(parse_reset_duration(""), None);
+        assert_eq!(parse_reset_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_rate_limit_headers() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-ratelimit-limit-requests", "500".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests", "499".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "120ms".parse().unwrap());
+        headers.insert("x-ratelimit-limit-tokens", "30000".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-tokens", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "1m30s".parse().unwrap());
+        headers.insert("retry-after", "2".parse().unwrap());
+
+        let info = parse_rate_limit_headers(&headers);
+        assert_eq!(info.request_limit, Some(500));
+        assert_eq!(info.requests_remaining, Some(499));
+        assert_eq!(info.requests_reset, Some(Duration::from_millis(120)));
+        assert_eq!(info.token_limit, Some(30000));
+        assert_eq!(info.tokens_remaining, Some(0));
+        assert_eq!(info.tokens_reset, Some(Duration::from_secs(90)));
+        assert_eq!(info.retry_after, Some(Duration::from_secs(2)));
+
+        assert!(parse_rate_limit_headers(&reqwest::header::HeaderMap::new()).is_empty());
+    }
 }
@@ -0,0 +1,272 @@
+// System prompt templates
+//
+// An agent's system prompt may reference values that differ per session:
+//
+//   You are the support agent for {{company}}. Today is {{current_date}}.
+//   {{file "/context/customer.md"}}
+//
+// `{{name}}` is replaced by a session variable or a built-in, and
+// `{{file "/path"}}` by the text content of a session file. Templates are
+// rendered when the runtime agent is built (RuntimeAgentBuilder::with_agent_context),
+// so one agent definition can serve many customers.
+//
+// Decision: Support only variable and file placeholders with a small hand-written
+// parser instead of a full Handlebars/Jinja engine. Rendering never fails:
+// placeholders without a value, and `{{ ... }}` text that is not a valid
+// placeholder (e.g. JSON examples in a prompt), are kept verbatim. Required
+// variables are checked up front when a session is created.
+
+use crate::session::Session;
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+/// Variables provided by the runtime rather than by the session creator.
+/// `user_name` is captured from the authenticated user at session creation
+/// unless the creator sets it explicitly.
+pub const BUILTIN_VARIABLES: &[&str] = &["current_date", "session_id", "user_name"];
+
+/// A parsed system prompt template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Variable { name: String, raw: String },
+    File { path: String, raw: String },
+}
+
+impl PromptTemplate {
+    /// Parse a template. Parsing is infallible; malformed placeholders are text.
+    pub fn parse(source: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let raw = &rest[start..start + 2 + len + 2];
+            let inner = rest[start + 2..start + 2 + len].trim();
+
+            let part = if is_identifier(inner) {
+                Some(Part::Variable {
+                    name: inner.to_string(),
+                    raw: raw.to_string(),
+                })
+            } else {
+                parse_file_path(inner).map(|path| Part::File {
+                    path,
+                    raw: raw.to_string(),
+                })
+            };
+
+            text.push_str(&rest[..start]);
+            match part {
+                Some(part) => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(part);
+                }
+                None => text.push_str(raw),
+            }
+            rest = &rest[start + raw.len()..];
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Self { parts }
+    }
+
+    /// Whether the template has no placeholders
+    pub fn is_static(&self) -> bool {
+        self.parts.iter().all(|p| matches!(p, Part::Text(_)))
+    }
+
+    /// Variable names referenced by the template, in order of first use
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for part in &self.parts {
+            if let Part::Variable { name, .. } = part {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Variables the session creator must provide (everything except built-ins)
+    pub fn required_variables(&self) -> Vec<&str> {
+        self.variables()
+            .into_iter()
+            .filter(|name| !BUILTIN_VARIABLES.contains(name))
+            .collect()
+    }
+
+    /// Session file paths referenced by the template, in order of first use
+    pub fn files(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = Vec::new();
+        for part in &self.parts {
+            if let Part::File { path, .. } = part {
+                if !paths.contains(&path.as_str()) {
+                    paths.push(path);
+                }
+            }
+        }
+        paths
+    }
+
+    /// Render the template, keeping placeholders without a value verbatim
+    pub fn render(&self, context: &TemplateContext) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Variable { name, raw } => {
+                    out.push_str(context.variables.get(name).unwrap_or(raw));
+                }
+                Part::File { path, raw } => {
+                    out.push_str(context.files.get(path).unwrap_or(raw));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Values available when rendering a template
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+    /// Variable values by name (session variables and built-ins)
+    pub variables: BTreeMap<String, String>,
+    /// Text content of session files by absolute path
+    pub files: BTreeMap<String, String>,
+}
+
+impl TemplateContext {
+    /// Create a context with only `current_date` set
+    pub fn new() -> Self {
+        Self::default().variable("current_date", today())
+    }
+
+    /// Create a context from a session's variables plus the built-ins
+    pub fn for_session(session: &Session) -> Self {
+        let mut context = Self::default();
+        context.variables.extend(session.variables.clone());
+        context
+            .variable("current_date", today())
+            .variable("session_id", session.id.to_string())
+    }
+
+    /// Set a variable
+    pub fn variable(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the content of a session file
+    pub fn file(mut self, path: impl Into<String>, content: impl Into<String>) -> Self {
+        self.files.insert(path.into(), content.into());
+        self
+    }
+}
+
+fn today() -> String {
+    Utc::now().format("%Y-%m-%d").to_string()
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse `file "/path"` into the path
+fn parse_file_path(inner: &str) -> Option<String> {
+    let arg = inner.strip_prefix("file")?;
+    if !arg.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let path = arg.trim().strip_prefix('"')?.strip_suffix('"')?;
+    if path.is_empty() || path.contains('"') {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_variables_and_files() {
+        let template = PromptTemplate::parse(
+            "Hello {{ customer }} from {{company}}.\n{{file \"/context/customer.md\"}}",
+        );
+        let context = TemplateContext::default()
+            .variable("customer", "Ada")
+            .variable("company", "Acme")
+            .file("/context/customer.md", "Plan: enterprise");
+
+        assert_eq!(
+            template.render(&context),
+            "Hello Ada from Acme.\nPlan: enterprise"
+        );
+    }
+
+    #[test]
+    fn test_missing_values_are_kept_verbatim() {
+        let template = PromptTemplate::parse("Hi {{name}}, see {{file \"/a.md\"}}");
+        assert_eq!(
+            template.render(&TemplateContext::default()),
+            "Hi {{name}}, see {{file \"/a.md\"}}"
+        );
+    }
+
+    #[test]
+    fn test_non_placeholders_are_text() {
+        let source = "Reply as {{\"answer\": 1}} or {{ two words }} or {{unclosed";
+        let template = PromptTemplate::parse(source);
+        assert!(template.is_static());
+        assert_eq!(template.render(&TemplateContext::new()), source);
+    }
+
+    #[test]
+    fn test_variables_and_files_are_deduplicated() {
+        let template = PromptTemplate::parse(
+            "{{a}} {{user_name}} {{b}} {{a}} {{file \"/x\"}} {{file \"/x\"}} {{current_date}}",
+        );
+        assert_eq!(
+            template.variables(),
+            vec!["a", "user_name", "b", "current_date"]
+        );
+        assert_eq!(template.required_variables(), vec!["a", "b"]);
+        assert_eq!(template.files(), vec!["/x"]);
+    }
+
+    #[test]
+    fn test_for_session_sets_builtins() {
+        let session: Session = serde_json::from_value(serde_json::json!({
+            "id": "01933b5a-0000-7000-8000-000000000001",
+            "agent_id": "01933b5a-0000-7000-8000-000000000002",
+            "status": "idle",
+            "created_at": "2026-01-01T00:00:00Z",
+            "variables": {"company": "Acme", "session_id": "spoofed"}
+        }))
+        .unwrap();
+
+        let context = TemplateContext::for_session(&session);
+        let rendered = PromptTemplate::parse("{{company}} {{session_id}}").render(&context);
+
+        assert_eq!(rendered, "Acme 01933b5a-0000-7000-8000-000000000001");
+        assert!(context.variables.contains_key("current_date"));
+    }
+}
@@ -3,10 +3,13 @@
 // RuntimeAgent is a DB-agnostic configuration struct that can be:
 // - Created directly for standalone usage
 // - Built from an Agent entity via the `with_agent` builder method
+//   (the agent's system prompt is rendered as a template at that point)
 
 use crate::agent::Agent;
-use crate::capabilities::{collect_capabilities, CapabilityRegistry};
-use crate::tool_types::ToolDefinition;
+use crate::capabilities::{collect_configured_capabilities, CapabilityConfigs, CapabilityRegistry};
+use crate::prompt_template::{PromptTemplate, TemplateContext};
+use crate::structured_output::ResponseFormat;
+use crate::tool_types::{ToolChoice, ToolDefinition};
 use serde::{Deserialize, Serialize};
 
 /// Runtime configuration for the agent loop
@@ -33,6 +36,19 @@ pub struct RuntimeAgent {
     /// Maximum tokens to generate per response
     #[serde(default)]
     pub max_tokens: Option<u32>,
+
+    /// JSON-schema constrained format for final responses
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+
+    /// Tool choice sent to the model (None = provider default)
+    #[serde(default)]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Whether the model may request parallel tool calls (None = provider default).
+    /// `Some(false)` also makes tool execution sequential.
+    #[serde(default)]
+    pub parallel_tool_calls: Option<bool>,
 }
 
 fn default_max_iterations() -> usize {
@@ -49,6 +65,9 @@ impl RuntimeAgent {
             max_iterations: default_max_iterations(),
             temperature: None,
             max_tokens: None,
+            response_format: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
         }
     }
 }
@@ -62,6 +81,9 @@ impl Default for RuntimeAgent {
             max_iterations: default_max_iterations(),
             temperature: None,
             max_tokens: None,
+            response_format: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
         }
     }
 }
@@ -85,7 +107,9 @@ impl RuntimeAgentBuilder {
     /// Apply an Agent's configuration to this builder.
     ///
     /// This sets the system prompt from the agent and applies the agent's
-    /// capabilities (tools and system prompt additions).
+    /// capabilities (tools and system prompt additions). The system prompt is
+    /// rendered as a template with only the `current_date` built-in; use
+    /// `with_agent_context` to supply session variables and files.
     ///
     /// # Arguments
     ///
@@ -106,14 +130,40 @@ impl RuntimeAgentBuilder {
     ///     .build();
     /// ```
     pub fn with_agent(self, agent: &Agent, registry: &CapabilityRegistry) -> Self {
+        self.with_agent_context(agent, registry, &TemplateContext::new())
+    }
+
+    /// Apply an Agent's configuration, rendering its system prompt template
+    /// with the given context.
+    ///
+    /// Placeholders without a value in `context` are kept verbatim.
+    pub fn with_agent_context(
+        self,
+        agent: &Agent,
+        registry: &CapabilityRegistry,
+        context: &TemplateContext,
+    ) -> Self {
+        let system_prompt = PromptTemplate::parse(&agent.system_prompt).render(context);
+
         let capability_ids: Vec<String> = agent
             .capabilities
             .iter()
             .map(|cap_id| cap_id.as_str().to_string())
             .collect();
 
-        self.system_prompt(&agent.system_prompt)
-            .with_capabilities(&capability_ids, registry)
+        let builder = self
+            .system_prompt(system_prompt)
+            .with_configured_capabilities(&capability_ids, &agent.capability_config, registry);
+
+        let builder = match &agent.response_format {
+            Some(format) => builder.response_format(format.clone()),
+            None => builder,
+        };
+
+        match agent.parallel_tool_calls {
+            Some(parallel) => builder.parallel_tool_calls(parallel),
+            None => builder,
+        }
     }
 
     /// Apply capabilities to this builder.
@@ -127,11 +177,24 @@ impl RuntimeAgentBuilder {
     /// * `capability_ids` - Ordered list of capability IDs to apply
     /// * `registry` - The capability registry containing implementations
     pub fn with_capabilities(
+        self,
+        capability_ids: &[String],
+        registry: &CapabilityRegistry,
+    ) -> Self {
+        self.with_configured_capabilities(capability_ids, &CapabilityConfigs::new(), registry)
+    }
+
+    /// Apply capabilities with per-agent configuration to this builder.
+    ///
+    /// Same as `with_capabilities`, but each capability builds its tools and
+    /// system prompt addition from its entry in `configs`.
+    pub fn with_configured_capabilities(
         mut self,
         capability_ids: &[String],
+        configs: &CapabilityConfigs,
         registry: &CapabilityRegistry,
     ) -> Self {
-        let collected = collect_capabilities(capability_ids, registry);
+        let collected = collect_configured_capabilities(capability_ids, configs, registry);
 
         // Apply system prompt additions (prepend to existing)
         if let Some(prefix) = collected.system_prompt_prefix() {
@@ -198,6 +261,24 @@ impl RuntimeAgentBuilder {
         self
     }
 
+    /// Set the response format (JSON-schema constrained output)
+    pub fn response_format(mut self, format: ResponseFormat) -> Self {
+        self.runtime_agent.response_format = Some(format);
+        self
+    }
+
+    /// Set the tool choice
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.runtime_agent.tool_choice = Some(choice);
+        self
+    }
+
+    /// Allow or disallow parallel tool calls
+    pub fn parallel_tool_calls(mut self, parallel: bool) -> Self {
+        self.runtime_agent.parallel_tool_calls = Some(parallel);
+        self
+    }
+
     /// Build the runtime agent
     pub fn build(self) -> RuntimeAgent {
         self.runtime_agent
@@ -342,7 +423,12 @@ mod tests {
             description: None,
             system_prompt: "Agent prompt.".to_string(),
             capabilities: vec![CapabilityIdType::from(CapabilityId::CURRENT_TIME)],
+            response_format: None,
+            parallel_tool_calls: None,
+            capability_config: Default::default(),
+            mcp_servers: vec![],
             status: AgentStatus::Active,
+            revision: 1,
             default_model_id: None,
             tags: vec![],
             created_at: chrono::Utc::now(),
@@ -363,6 +449,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_builder_with_agent_context_renders_template() {
+        use uuid::{NoContext, Timestamp, Uuid};
+
+        let registry = CapabilityRegistry::with_builtins();
+        let agent = Agent {
+            id: Uuid::new_v7(Timestamp::now(NoContext)),
+            name: "Support".to_string(),
+            description: None,
+            system_prompt: "Help {{customer}} ({{tier}}).\n{{file \"/context/notes.md\"}}"
+                .to_string(),
+            capabilities: vec![],
+            response_format: None,
+            parallel_tool_calls: None,
+            capability_config: Default::default(),
+            mcp_servers: vec![],
+            status: AgentStatus::Active,
+            revision: 1,
+            default_model_id: None,
+            tags: vec![],
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let context = TemplateContext::default()
+            .variable("customer", "Acme")
+            .file("/context/notes.md", "Prefers email.");
+
+        let runtime_agent = RuntimeAgentBuilder::new()
+            .with_agent_context(&agent, &registry, &context)
+            .build();
+
+        assert_eq!(
+            runtime_agent.system_prompt,
+            "Help Acme ({{tier}}).\nPrefers email."
+        );
+    }
+
     #[test]
     fn test_builder_default() {
         let builder = RuntimeAgentBuilder::default();
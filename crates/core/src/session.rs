@@ -5,6 +5,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 #[cfg(feature = "openapi")]
@@ -78,4 +79,17 @@ pub struct Session {
     /// Timestamp when the session finished (completed or failed).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub finished_at: Option<DateTime<Utc>>,
+    /// Session this one was forked from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<Uuid>,
+    /// Last event of the parent session copied into this fork.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_at_event_id: Option<Uuid>,
+    /// Agent revision pinned at creation.
+    /// When absent, every turn uses the agent's latest revision.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_revision: Option<i32>,
+    /// Values for the agent's system prompt template variables.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
 }
@@ -2,6 +2,10 @@
 //
 // These types represent files and directories stored within a session's
 // virtual filesystem. Each session has its own isolated filesystem.
+//
+// Every content change is kept as a numbered file version, and snapshots
+// capture the whole tree (manually or at turn boundaries) so files can be
+// diffed and restored.
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::{DateTime, Utc};
@@ -45,6 +49,103 @@ impl FileInfo {
             Some(if parent.is_empty() { "/" } else { parent }.to_string())
         }
     }
+
+    /// Normalize a path: ensure it starts with /, no trailing slash, no double slashes
+    pub fn normalize_path(path: &str) -> String {
+        let mut normalized = path.trim().to_string();
+
+        // Ensure starts with /
+        if !normalized.starts_with('/') {
+            normalized = format!("/{}", normalized);
+        }
+
+        // Remove trailing slash (except for root)
+        if normalized.len() > 1 && normalized.ends_with('/') {
+            normalized.pop();
+        }
+
+        // Remove double slashes
+        while normalized.contains("//") {
+            normalized = normalized.replace("//", "/");
+        }
+
+        normalized
+    }
+
+    /// Validate a normalized path
+    pub fn validate_path(path: &str) -> Result<(), &'static str> {
+        if path.is_empty() {
+            return Err("Path cannot be empty");
+        }
+
+        if !path.starts_with('/') {
+            return Err("Path must start with /");
+        }
+
+        // Check for invalid characters
+        if path.contains('\0') {
+            return Err("Path cannot contain null characters");
+        }
+
+        // Check for .. path traversal
+        if path.split('/').any(|segment| segment == "..") {
+            return Err("Path cannot contain '..' segments");
+        }
+
+        Ok(())
+    }
+
+    /// Match an absolute path against a glob pattern.
+    ///
+    /// `*` and `?` match within a path segment, `**` matches any number of
+    /// segments and `{a,b}` matches either alternative.
+    pub fn glob_matches(pattern: &str, path: &str) -> bool {
+        let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        expand_braces(pattern).iter().any(|pattern| {
+            let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+            match_segments(&pattern, &path)
+        })
+    }
+}
+
+// Expand `{a,b}` alternatives into separate patterns
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let Some(close) = pattern[open..].find('}').map(|i| open + i) else {
+        return vec![pattern.to_string()];
+    };
+
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+    pattern[open + 1..close]
+        .split(',')
+        .flat_map(|alt| expand_braces(&format!("{}{}{}", prefix, alt, suffix)))
+        .collect()
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                let segment: Vec<char> = segment.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                match_segment(&segment, &name) && match_segments(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_segment(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_segment(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_segment(rest, &name[1..]),
+    }
 }
 
 /// Complete file with content
@@ -131,6 +232,162 @@ pub struct GrepResult {
     pub matches: Vec<GrepMatch>,
 }
 
+/// A single version of a file's content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct FileVersion {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub path: String,
+    /// Per-path version number, starting at 1
+    pub version: i32,
+    pub size_bytes: i64,
+    /// The file was deleted (or moved away) at this version
+    pub is_deleted: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Unified diff between two versions of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct FileDiff {
+    pub path: String,
+    pub from_version: i32,
+    pub to_version: i32,
+    /// Unified diff text (empty when the versions are identical)
+    pub diff: String,
+    /// Either side is binary; `diff` is empty
+    #[serde(default)]
+    pub is_binary: bool,
+}
+
+impl FileDiff {
+    /// Diff two versions' raw content
+    pub fn between(path: &str, from: (i32, &[u8]), to: (i32, &[u8])) -> Self {
+        let (from_version, old) = from;
+        let (to_version, new) = to;
+        let texts = match (std::str::from_utf8(old), std::str::from_utf8(new)) {
+            (Ok(old), Ok(new))
+                if SessionFile::is_text_content(old.as_bytes())
+                    && SessionFile::is_text_content(new.as_bytes()) =>
+            {
+                Some((old, new))
+            }
+            _ => None,
+        };
+
+        let diff = match texts {
+            Some((old, new)) => similar::TextDiff::from_lines(old, new)
+                .unified_diff()
+                .context_radius(3)
+                .header(
+                    &format!("{}@v{}", path, from_version),
+                    &format!("{}@v{}", path, to_version),
+                )
+                .to_string(),
+            None => String::new(),
+        };
+
+        Self {
+            path: path.to_string(),
+            from_version,
+            to_version,
+            diff,
+            is_binary: texts.is_none(),
+        }
+    }
+}
+
+/// How a snapshot was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotKind {
+    /// Taken on request (API, CLI or tool)
+    Manual,
+    /// Taken automatically when a turn started
+    TurnStart,
+    /// Taken automatically when a turn completed or failed
+    TurnEnd,
+}
+
+impl SnapshotKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::TurnStart => "turn_start",
+            Self::TurnEnd => "turn_end",
+        }
+    }
+}
+
+impl From<&str> for SnapshotKind {
+    fn from(s: &str) -> Self {
+        match s {
+            "turn_start" => Self::TurnStart,
+            "turn_end" => Self::TurnEnd,
+            _ => Self::Manual,
+        }
+    }
+}
+
+impl std::fmt::Display for SnapshotKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Named point-in-time copy of a session's file tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SessionSnapshot {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub name: String,
+    pub kind: SnapshotKind,
+    /// Turn this snapshot was taken for (automatic snapshots only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_id: Option<Uuid>,
+    /// 1-based turn number within the session (automatic snapshots only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_number: Option<i32>,
+    /// Number of files and directories captured
+    pub file_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What to restore a file or the whole tree to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreTarget {
+    /// A file version number (single files only)
+    Version(i32),
+    /// A snapshot by ID
+    Snapshot(Uuid),
+    /// The snapshot taken at the end of a turn (or its start if it never ended)
+    Turn(Uuid),
+}
+
+/// One change in an atomic multi-file update
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    /// Create or replace a file's content
+    Write {
+        path: String,
+        content: String,
+        encoding: String,
+    },
+    /// Delete a file
+    Delete { path: String },
+}
+
+impl FileChange {
+    pub fn path(&self) -> &str {
+        match self {
+            FileChange::Write { path, .. } | FileChange::Delete { path } => path,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,6 +411,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize_and_validate_path() {
+        assert_eq!(FileInfo::normalize_path("foo//bar/"), "/foo/bar");
+        assert_eq!(FileInfo::normalize_path(""), "/");
+        assert!(FileInfo::validate_path("/foo/bar.txt").is_ok());
+        assert!(FileInfo::validate_path("/foo/../etc").is_err());
+        assert!(FileInfo::validate_path("/foo\0").is_err());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(FileInfo::glob_matches("/*.rs", "/main.rs"));
+        assert!(!FileInfo::glob_matches("/*.rs", "/src/main.rs"));
+        assert!(FileInfo::glob_matches("/**/*.rs", "/main.rs"));
+        assert!(FileInfo::glob_matches("/**/*.rs", "/src/a/lib.rs"));
+        assert!(FileInfo::glob_matches("/src/**", "/src/a/lib.rs"));
+        assert!(FileInfo::glob_matches(
+            "/data/file?.{csv,json}",
+            "/data/file1.json"
+        ));
+        assert!(!FileInfo::glob_matches(
+            "/data/file?.{csv,json}",
+            "/data/file10.csv"
+        ));
+    }
+
     #[test]
     fn test_is_text_content() {
         assert!(SessionFile::is_text_content(b"hello world"));
@@ -231,4 +514,40 @@ mod tests {
         assert!(json.contains("\"line_number\":1"));
         assert!(json.contains("\"line\":\"hello world\""));
     }
+
+    #[test]
+    fn test_file_diff_text() {
+        let diff = FileDiff::between("/notes.txt", (1, b"a\nb\nc\n"), (2, b"a\nB\nc\n"));
+        assert!(!diff.is_binary);
+        assert!(diff.diff.contains("--- /notes.txt@v1"));
+        assert!(diff.diff.contains("+++ /notes.txt@v2"));
+        assert!(diff.diff.contains("-b\n"));
+        assert!(diff.diff.contains("+B\n"));
+    }
+
+    #[test]
+    fn test_file_diff_identical_and_binary() {
+        let same = FileDiff::between("/a.txt", (1, b"same\n"), (2, b"same\n"));
+        assert!(same.diff.is_empty());
+        assert!(!same.is_binary);
+
+        let binary = FileDiff::between("/a.bin", (1, b"\x00\x01"), (2, b"\x00\x02"));
+        assert!(binary.is_binary);
+        assert!(binary.diff.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_kind_roundtrip() {
+        for kind in [
+            SnapshotKind::Manual,
+            SnapshotKind::TurnStart,
+            SnapshotKind::TurnEnd,
+        ] {
+            assert_eq!(SnapshotKind::from(kind.as_str()), kind);
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+    }
 }
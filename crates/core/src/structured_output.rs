@@ -0,0 +1,402 @@
+// Structured output (JSON-schema constrained responses)
+//
+// ResponseFormat describes the JSON shape an agent's final answer must follow.
+// It can be configured on the Agent (default for every turn) or per message via
+// Controls (overrides the agent). Drivers translate it to provider-native
+// mechanisms (OpenAI response_format, Anthropic forced tool use); ReasonAtom
+// validates the final text against the schema and asks the model to repair it
+// when validation fails.
+//
+// Decision: Ship a small built-in validator covering the JSON Schema subset
+// that providers accept for structured outputs (type, properties, required,
+// items, enum, const, additionalProperties, anyOf, min/max bounds) instead of
+// pulling in a full JSON Schema engine.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+/// Maximum number of repair attempts after the first response fails validation
+pub const MAX_STRUCTURED_OUTPUT_REPAIR_ATTEMPTS: usize = 2;
+
+/// JSON-schema constrained response format
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ResponseFormat {
+    /// Name of the output schema (letters, digits, underscores and dashes).
+    #[cfg_attr(feature = "openapi", schema(example = "weather_report"))]
+    pub name: String,
+
+    /// JSON Schema the response must conform to. The root must be an object schema.
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub schema: Value,
+
+    /// Ask the provider to enforce the schema strictly (when supported).
+    #[serde(default = "default_strict")]
+    pub strict: bool,
+}
+
+fn default_strict() -> bool {
+    true
+}
+
+impl ResponseFormat {
+    /// Create a new response format with strict enforcement
+    pub fn new(name: impl Into<String>, schema: Value) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+            strict: true,
+        }
+    }
+
+    /// Validate the response format definition itself
+    pub fn validate_definition(&self) -> std::result::Result<(), String> {
+        if self.name.is_empty() || self.name.len() > 64 {
+            return Err("response_format.name must be 1-64 characters".to_string());
+        }
+        if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(
+                "response_format.name may only contain letters, digits, '_' and '-'".to_string(),
+            );
+        }
+        let Some(schema) = self.schema.as_object() else {
+            return Err("response_format.schema must be a JSON object".to_string());
+        };
+        if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+            return Err("response_format.schema root must have \"type\": \"object\"".to_string());
+        }
+        Ok(())
+    }
+
+    /// Parse model output and validate it against the schema.
+    ///
+    /// Returns the parsed value, or the list of validation errors.
+    pub fn parse_output(&self, text: &str) -> std::result::Result<Value, Vec<String>> {
+        let json_text = strip_code_fence(text);
+        let value: Value = serde_json::from_str(json_text)
+            .map_err(|e| vec![format!("response is not valid JSON: {}", e)])?;
+
+        let errors = validate(&value, &self.schema);
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Build the user-facing repair instruction sent after a failed validation
+    pub fn repair_prompt(&self, errors: &[String]) -> String {
+        format!(
+            "Your previous response did not match the required JSON schema \"{}\":\n- {}\n\n\
+             Respond again with only a JSON value that conforms to the schema. \
+             Do not include any prose or code fences.",
+            self.name,
+            errors.join("\n- ")
+        )
+    }
+}
+
+/// Remove a surrounding ```json fenced block, if present
+fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return trimmed;
+    };
+    // Drop the optional language tag on the opening line
+    match body.find('\n') {
+        Some(pos) => body[pos + 1..].trim(),
+        None => body.trim(),
+    }
+}
+
+// ============================================================================
+// Schema validation
+// ============================================================================
+
+/// Validate a JSON value against a schema, returning all errors found
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, "$", &mut errors);
+    errors
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` / `{}` accept anything; `false` rejects everything
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{}: no value is allowed here", path));
+        }
+        return;
+    };
+
+    if let Some(variants) = schema.get("anyOf").and_then(|v| v.as_array()) {
+        let matches_any = variants.iter().any(|variant| {
+            let mut variant_errors = Vec::new();
+            validate_at(value, variant, path, &mut variant_errors);
+            variant_errors.is_empty()
+        });
+        if !matches_any {
+            errors.push(format!("{}: does not match any allowed schema", path));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            errors.push(format!("{}: must equal {}", path, expected));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|v| v.as_array()) {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{}: must be one of {}",
+                path,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+
+    if let Some(type_spec) = schema.get("type") {
+        let types: Vec<&str> = match type_spec {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                types.join(" or "),
+                type_name(value)
+            ));
+            // Nested checks are meaningless once the type is wrong
+            return;
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(required) = schema.get("required").and_then(|v| v.as_array()) {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !map.contains_key(key) {
+                        errors.push(format!("{}: missing required property \"{}\"", path, key));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(|v| v.as_object());
+            for (key, field_value) in map {
+                let field_path = format!("{}.{}", path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(field_schema) => {
+                        validate_at(field_value, field_schema, &field_path, errors)
+                    }
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{}: unexpected property", field_path))
+                        }
+                        Some(extra_schema @ Value::Object(_)) => {
+                            validate_at(field_value, extra_schema, &field_path, errors)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64()) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{}: expected at least {} items", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64()) {
+                if (items.len() as u64) > max {
+                    errors.push(format!("{}: expected at most {} items", path, max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (idx, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{}[{}]", path, idx), errors);
+                }
+            }
+        }
+        Value::Number(n) => {
+            if let (Some(min), Some(x)) =
+                (schema.get("minimum").and_then(|v| v.as_f64()), n.as_f64())
+            {
+                if x < min {
+                    errors.push(format!("{}: must be >= {}", path, min));
+                }
+            }
+            if let (Some(max), Some(x)) =
+                (schema.get("maximum").and_then(|v| v.as_f64()), n.as_f64())
+            {
+                if x > max {
+                    errors.push(format!("{}: must be <= {}", path, max));
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|v| v.as_u64()) {
+                if len < min {
+                    errors.push(format!("{}: must be at least {} characters", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|v| v.as_u64()) {
+                if len > max {
+                    errors.push(format!("{}: must be at most {} characters", path, max));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches_type(value: &Value, type_name: &str) -> bool {
+    match type_name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        // Unknown type keywords are not enforced
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn weather_format() -> ResponseFormat {
+        ResponseFormat::new(
+            "weather_report",
+            json!({
+                "type": "object",
+                "properties": {
+                    "city": {"type": "string"},
+                    "temperature": {"type": "number"},
+                    "conditions": {"type": "string", "enum": ["sunny", "cloudy", "rain"]},
+                    "alerts": {"type": "array", "items": {"type": "string"}}
+                },
+                "required": ["city", "temperature"],
+                "additionalProperties": false
+            }),
+        )
+    }
+
+    #[test]
+    fn test_parse_output_valid() {
+        let format = weather_format();
+        let value = format
+            .parse_output(r#"{"city": "Paris", "temperature": 21.5, "conditions": "sunny"}"#)
+            .unwrap();
+        assert_eq!(value["city"], "Paris");
+    }
+
+    #[test]
+    fn test_parse_output_strips_code_fence() {
+        let format = weather_format();
+        let text = "```json\n{\"city\": \"Oslo\", \"temperature\": -3}\n```";
+        assert!(format.parse_output(text).is_ok());
+    }
+
+    #[test]
+    fn test_parse_output_invalid_json() {
+        let format = weather_format();
+        let errors = format.parse_output("It is sunny in Paris").unwrap_err();
+        assert!(errors[0].contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_parse_output_reports_schema_errors() {
+        let format = weather_format();
+        let errors = format
+            .parse_output(
+                r#"{"temperature": "warm", "conditions": "snow", "alerts": [1], "wind": 3}"#,
+            )
+            .unwrap_err();
+
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("missing required property \"city\"")));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("$.temperature: expected number")));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("$.conditions: must be one of")));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("$.alerts[0]: expected string")));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("$.wind: unexpected property")));
+    }
+
+    #[test]
+    fn test_validate_nullable_and_any_of() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "note": {"type": ["string", "null"]},
+                "value": {"anyOf": [{"type": "integer"}, {"type": "string", "maxLength": 3}]}
+            }
+        });
+        assert!(validate(&json!({"note": null, "value": 5}), &schema).is_empty());
+        assert!(validate(&json!({"note": "x", "value": "abc"}), &schema).is_empty());
+        assert_eq!(validate(&json!({"value": "abcd"}), &schema).len(), 1);
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        assert!(weather_format().validate_definition().is_ok());
+
+        let bad_name = ResponseFormat::new("has space", json!({"type": "object"}));
+        assert!(bad_name.validate_definition().is_err());
+
+        let bad_root = ResponseFormat::new("list", json!({"type": "array"}));
+        assert!(bad_root.validate_definition().is_err());
+    }
+
+    #[test]
+    fn test_response_format_serde_defaults_strict() {
+        let format: ResponseFormat =
+            serde_json::from_value(json!({"name": "x", "schema": {"type": "object"}})).unwrap();
+        assert!(format.strict);
+    }
+
+    #[test]
+    fn test_repair_prompt_lists_errors() {
+        let prompt = weather_format().repair_prompt(&["$.city: missing".to_string()]);
+        assert!(prompt.contains("weather_report"));
+        assert!(prompt.contains("- $.city: missing"));
+    }
+}
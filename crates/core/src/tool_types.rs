@@ -47,6 +47,24 @@ pub struct BuiltinTool {
     pub policy: ToolPolicy,
 }
 
+/// Controls whether and which tools the model may call
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// Model decides whether to call tools (provider default)
+    Auto,
+    /// Model must not call tools
+    None,
+    /// Model must call at least one tool
+    Required,
+    /// Model must call the named tool
+    Tool {
+        /// Name of the tool to call
+        name: String,
+    },
+}
+
 /// Tool call from LLM response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
@@ -111,6 +129,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tool_choice_serialization() {
+        assert_eq!(
+            serde_json::to_value(ToolChoice::Required).unwrap(),
+            serde_json::json!({"type": "required"})
+        );
+
+        let choice: ToolChoice =
+            serde_json::from_str(r#"{"type": "tool", "name": "get_weather"}"#).unwrap();
+        assert_eq!(
+            choice,
+            ToolChoice::Tool {
+                name: "get_weather".to_string()
+            }
+        );
+    }
+
     #[test]
     fn test_tool_call_serialization() {
         let tool_call = ToolCall {
@@ -385,7 +385,7 @@ impl ToolRegistry {
             .tool(DeleteFileTool)
             .tool(StatFileTool)
             // WebFetch capability tools
-            .tool(WebFetchTool)
+            .tool(WebFetchTool::default())
             .build()
     }
 
@@ -6,8 +6,12 @@
 // - Channel-based implementations for streaming
 
 use crate::agent::Agent;
+use crate::llm_driver_registry::RateLimitInfo;
 use crate::llm_models::LlmProviderType;
-use crate::session_file::{FileInfo, FileStat, GrepMatch, SessionFile};
+use crate::session_file::{
+    FileChange, FileDiff, FileInfo, FileStat, FileVersion, GrepMatch, RestoreTarget, SessionFile,
+    SessionSnapshot,
+};
 use crate::tool_types::{ToolCall, ToolDefinition, ToolResult};
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -130,6 +134,16 @@ pub trait MessageStore: Send + Sync {
 pub trait AgentStore: Send + Sync {
     /// Get an agent by ID
     async fn get_agent(&self, agent_id: Uuid) -> Result<Option<Agent>>;
+
+    /// Get an agent as it was at a specific revision
+    ///
+    /// The default implementation only knows the current revision.
+    async fn get_agent_revision(&self, agent_id: Uuid, revision: i32) -> Result<Option<Agent>> {
+        Ok(self
+            .get_agent(agent_id)
+            .await?
+            .filter(|agent| agent.revision == revision))
+    }
 }
 
 // ============================================================================
@@ -164,6 +178,8 @@ pub struct ModelWithProvider {
     pub api_key: Option<String>,
     /// Optional base URL override
     pub base_url: Option<String>,
+    /// Provider ID, used to share rate limits across workers (if known)
+    pub provider_id: Option<Uuid>,
 }
 
 /// Trait for retrieving LLM provider and model configurations
@@ -189,6 +205,27 @@ pub trait LlmProviderStore: Send + Sync {
     async fn get_default_model(&self) -> Result<Option<ModelWithProvider>>;
 }
 
+// ============================================================================
+// LlmRateLimiter - Shared rate limits for LLM providers
+// ============================================================================
+
+/// Rate limiter shared by everything calling the same LLM provider
+///
+/// Workers acquire capacity before each LLM call and report the rate limit
+/// headers the provider returned, so limits are enforced across workers
+/// instead of each worker discovering them through 429s.
+#[async_trait]
+pub trait LlmRateLimiter: Send + Sync {
+    /// Wait until the provider has capacity for one request of about
+    /// `estimated_tokens` tokens
+    ///
+    /// Returns an error if capacity does not become available in time.
+    async fn acquire(&self, provider_id: Uuid, estimated_tokens: u32) -> Result<()>;
+
+    /// Report rate limit state returned by the provider
+    async fn observe(&self, provider_id: Uuid, rate_limit: &RateLimitInfo) -> Result<()>;
+}
+
 // ============================================================================
 // ToolExecutor - For executing tool calls
 // ============================================================================
@@ -337,6 +374,53 @@ pub trait SessionFileStore: Send + Sync {
 
     /// Create a directory
     async fn create_directory(&self, session_id: Uuid, path: &str) -> Result<FileInfo>;
+
+    /// List files and directories whose absolute path matches a glob pattern
+    async fn glob_files(&self, session_id: Uuid, pattern: &str) -> Result<Vec<FileInfo>>;
+
+    /// Apply writes and deletes across several files atomically: either all
+    /// changes are applied or none. Returns the written files.
+    async fn apply_changes(
+        &self,
+        session_id: Uuid,
+        changes: Vec<FileChange>,
+    ) -> Result<Vec<FileInfo>>;
+
+    /// List a file's versions, newest first
+    async fn list_file_versions(&self, session_id: Uuid, path: &str) -> Result<Vec<FileVersion>>;
+
+    /// Diff two versions of a file.
+    ///
+    /// `to` defaults to the latest version and `from` to the one before `to`.
+    async fn diff_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        from: Option<i32>,
+        to: Option<i32>,
+    ) -> Result<FileDiff>;
+
+    /// Restore a single file to a version, snapshot or turn
+    async fn restore_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        target: RestoreTarget,
+    ) -> Result<SessionFile>;
+
+    /// List snapshots, newest first
+    async fn list_snapshots(&self, session_id: Uuid) -> Result<Vec<SessionSnapshot>>;
+
+    /// Snapshot the current file tree under `name`
+    async fn create_snapshot(&self, session_id: Uuid, name: &str) -> Result<SessionSnapshot>;
+
+    /// Restore the whole file tree to a snapshot or turn, returning the
+    /// snapshot that was applied
+    async fn restore_snapshot(
+        &self,
+        session_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<SessionSnapshot>;
 }
 
 // ============================================================================
@@ -16,8 +16,12 @@ use everruns_core::memory::{
 };
 use everruns_core::session::{Session, SessionStatus};
 use everruns_core::traits::{MessageStore, ModelWithProvider, NoopEventEmitter};
-use everruns_core::{Message, ToolCall};
+use everruns_core::{
+    LlmCallConfig, LlmCompletionMetadata, LlmDriver, LlmMessage, LlmResponseStream, LlmStreamEvent,
+    Message, ToolCall, ToolChoice,
+};
 use serde_json::json;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 /// Create a basic test setup with in-memory stores
@@ -44,7 +48,12 @@ async fn setup_test_environment() -> (
         capabilities: vec![],
         default_model_id: None,
         tags: vec![],
+        response_format: None,
+        parallel_tool_calls: None,
+        capability_config: Default::default(),
+        mcp_servers: vec![],
         status: AgentStatus::Active,
+        revision: 1,
         created_at: chrono::Utc::now(),
         updated_at: chrono::Utc::now(),
     };
@@ -62,6 +71,10 @@ async fn setup_test_environment() -> (
         created_at: chrono::Utc::now(),
         started_at: None,
         finished_at: None,
+        parent_session_id: None,
+        forked_at_event_id: None,
+        agent_revision: None,
+        variables: Default::default(),
     };
     session_store.add_session(session).await;
 
@@ -71,6 +84,7 @@ async fn setup_test_environment() -> (
         provider_type: LlmProviderType::LlmSim,
         api_key: Some("fake-api-key".to_string()), // Required by registry but unused by LlmSim
         base_url: None,
+        provider_id: None,
     };
     provider_store.set_default_model(model).await;
 
@@ -282,6 +296,10 @@ async fn test_reason_atom_with_different_configs() {
         created_at: chrono::Utc::now(),
         started_at: None,
         finished_at: None,
+        parent_session_id: None,
+        forked_at_event_id: None,
+        agent_revision: None,
+        variables: Default::default(),
     };
     session_store.add_session(session2).await;
     message_store
@@ -538,6 +556,240 @@ async fn test_reason_atom_handles_llm_error() {
     );
 }
 
+fn structured_controls() -> everruns_core::Controls {
+    everruns_core::Controls {
+        response_format: Some(everruns_core::ResponseFormat::new(
+            "capital",
+            json!({
+                "type": "object",
+                "properties": {"city": {"type": "string"}},
+                "required": ["city"],
+                "additionalProperties": false
+            }),
+        )),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_reason_atom_structured_output_is_stored_on_event() {
+    use everruns_core::memory::InMemoryEventEmitter;
+
+    let (agent_store, session_store, message_store, provider_store, agent_id, session_id) =
+        setup_test_environment().await;
+
+    let mut user_message = Message::user("What is the capital of France?");
+    user_message.controls = Some(structured_controls());
+    message_store.seed(session_id, vec![user_message]).await;
+
+    let driver_registry =
+        create_custom_driver_registry(LlmSimConfig::fixed(r#"{"city": "Paris"}"#));
+    let event_emitter = InMemoryEventEmitter::new();
+
+    let atom = ReasonAtom::new(
+        agent_store,
+        session_store,
+        message_store.clone(),
+        provider_store,
+        CapabilityRegistry::new(),
+        driver_registry,
+        event_emitter.clone(),
+    );
+
+    let result = atom
+        .execute(ReasonInput {
+            context: create_context(session_id),
+            agent_id,
+        })
+        .await
+        .unwrap();
+    assert!(result.success);
+
+    let events = event_emitter.events().await;
+    let message_agent = events
+        .iter()
+        .find(|e| e.event_type == "message.agent")
+        .expect("message.agent event");
+    let data = serde_json::to_value(&message_agent.data).unwrap();
+    assert_eq!(data["structured_output"], json!({"city": "Paris"}));
+}
+
+#[tokio::test]
+async fn test_reason_atom_structured_output_repairs_invalid_response() {
+    let (agent_store, session_store, message_store, provider_store, agent_id, session_id) =
+        setup_test_environment().await;
+
+    let mut user_message = Message::user("What is the capital of France?");
+    user_message.controls = Some(structured_controls());
+    message_store.seed(session_id, vec![user_message]).await;
+
+    // First answer is prose, the repaired answer conforms to the schema
+    let driver_registry = create_custom_driver_registry(LlmSimConfig::sequence(vec![
+        "The capital is Paris.".to_string(),
+        r#"{"city": "Paris"}"#.to_string(),
+    ]));
+
+    let atom = ReasonAtom::new(
+        agent_store,
+        session_store,
+        message_store.clone(),
+        provider_store,
+        CapabilityRegistry::new(),
+        driver_registry,
+        NoopEventEmitter,
+    );
+
+    let result = atom
+        .execute(ReasonInput {
+            context: create_context(session_id),
+            agent_id,
+        })
+        .await
+        .unwrap();
+
+    assert!(result.success);
+    assert_eq!(result.text, r#"{"city": "Paris"}"#);
+
+    // Only the final, valid answer is persisted
+    let messages = message_store.load(session_id).await.unwrap();
+    assert_eq!(messages.len(), 2);
+}
+
+#[tokio::test]
+async fn test_reason_atom_structured_output_fails_after_repair_attempts() {
+    let (agent_store, session_store, message_store, provider_store, agent_id, session_id) =
+        setup_test_environment().await;
+
+    let mut user_message = Message::user("What is the capital of France?");
+    user_message.controls = Some(structured_controls());
+    message_store.seed(session_id, vec![user_message]).await;
+
+    let driver_registry = create_custom_driver_registry(LlmSimConfig::fixed("Paris, obviously."));
+
+    let atom = ReasonAtom::new(
+        agent_store,
+        session_store,
+        message_store,
+        provider_store,
+        CapabilityRegistry::new(),
+        driver_registry,
+        NoopEventEmitter,
+    );
+
+    let result = atom
+        .execute(ReasonInput {
+            context: create_context(session_id),
+            agent_id,
+        })
+        .await
+        .unwrap();
+
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("did not match schema"));
+}
+
+/// Driver that calls a tool whenever the tool choice requires one, and
+/// records the tool choice of every call
+struct ToolChoiceDriver {
+    choices: Arc<Mutex<Vec<Option<ToolChoice>>>>,
+}
+
+#[async_trait::async_trait]
+impl LlmDriver for ToolChoiceDriver {
+    async fn chat_completion_stream(
+        &self,
+        _messages: Vec<LlmMessage>,
+        config: &LlmCallConfig,
+    ) -> everruns_core::Result<LlmResponseStream> {
+        self.choices
+            .lock()
+            .unwrap()
+            .push(config.tool_choice.clone());
+        let event = match config.tool_choice {
+            Some(ToolChoice::Required) => LlmStreamEvent::ToolCalls(vec![ToolCall {
+                id: format!("call_{}", Uuid::now_v7()),
+                name: "get_weather".to_string(),
+                arguments: json!({"city": "Tokyo"}),
+            }]),
+            _ => LlmStreamEvent::TextDelta("It's sunny in Tokyo.".to_string()),
+        };
+        Ok(Box::pin(futures::stream::iter(vec![
+            Ok(event),
+            Ok(LlmStreamEvent::Done(LlmCompletionMetadata::default())),
+        ])))
+    }
+}
+
+#[tokio::test]
+async fn test_reason_atom_required_tool_choice_ends_after_tool_result() {
+    let (agent_store, session_store, message_store, provider_store, agent_id, session_id) =
+        setup_test_environment().await;
+
+    let mut user_message = Message::user("What's the weather in Tokyo?");
+    user_message.controls = Some(everruns_core::Controls {
+        tool_choice: Some(ToolChoice::Required),
+        ..Default::default()
+    });
+    message_store.seed(session_id, vec![user_message]).await;
+
+    let choices = Arc::new(Mutex::new(Vec::new()));
+    let mut driver_registry = DriverRegistry::new();
+    let recorded = choices.clone();
+    driver_registry.register(ProviderType::LlmSim, move |_api_key, _base_url| {
+        Box::new(ToolChoiceDriver {
+            choices: recorded.clone(),
+        })
+    });
+
+    let atom = ReasonAtom::new(
+        agent_store,
+        session_store,
+        message_store.clone(),
+        provider_store,
+        CapabilityRegistry::new(),
+        driver_registry,
+        NoopEventEmitter,
+    );
+
+    // First step is forced to call a tool
+    let result = atom
+        .execute(ReasonInput {
+            context: create_context(session_id),
+            agent_id,
+        })
+        .await
+        .unwrap();
+    assert!(result.has_tool_calls);
+
+    let messages = message_store.load(session_id).await.unwrap();
+    let call_id = messages
+        .last()
+        .and_then(|m| m.tool_calls().first().map(|call| call.id.clone()))
+        .expect("tool call");
+    message_store
+        .store(
+            session_id,
+            Message::tool_result(&call_id, Some(json!({"condition": "sunny"})), None),
+        )
+        .await
+        .unwrap();
+
+    // With the tool result in, the model may answer and the turn ends
+    let result = atom
+        .execute(ReasonInput {
+            context: create_context(session_id),
+            agent_id,
+        })
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert!(!result.has_tool_calls);
+    assert_eq!(
+        *choices.lock().unwrap(),
+        vec![Some(ToolChoice::Required), Some(ToolChoice::Auto)]
+    );
+}
+
 #[tokio::test]
 async fn test_driver_registry_integration() {
     // Verify that register_driver works with the standard DriverRegistry flow
@@ -566,6 +818,9 @@ async fn test_driver_registry_integration() {
         max_tokens: None,
         tools: vec![],
         reasoning_effort: None,
+        response_format: None,
+        tool_choice: None,
+        parallel_tool_calls: None,
     };
 
     let response = driver
@@ -0,0 +1,81 @@
+//! Time source abstraction
+//!
+//! Components that make time-based decisions (timeouts, task timestamps in
+//! the in-memory store) read the time through [`Clock`], so tests can control
+//! it with [`ManualClock`].
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall clock (`Utc::now()`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to (for tests)
+///
+/// # Example
+///
+/// ```
+/// use everruns_durable::{Clock, ManualClock};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!((clock.now() - start).num_seconds(), 60);
+/// ```
+#[derive(Debug)]
+pub struct ManualClock {
+    now: RwLock<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    /// Create a clock starting at the current wall clock time
+    pub fn new() -> Self {
+        Self::starting_at(Utc::now())
+    }
+
+    /// Create a clock starting at the given time
+    pub fn starting_at(now: DateTime<Utc>) -> Self {
+        Self {
+            now: RwLock::new(now),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.write();
+        *now += chrono::Duration::from_std(duration).unwrap_or_default();
+    }
+
+    /// Set the clock to the given time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.write() = now;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.read()
+    }
+}
@@ -15,9 +15,10 @@ use crate::activity::ActivityError;
 use crate::persistence::{
     StoreError, TaskDefinition, TraceContext, WorkflowEventStore, WorkflowStatus,
 };
-use crate::workflow::{WorkflowAction, WorkflowEvent, WorkflowSignal};
+use crate::workflow::{VersionState, WorkflowAction, WorkflowEvent, WorkflowSignal};
 
-use super::registry::{AnyWorkflow, RegistryError, WorkflowRegistry};
+use super::registry::{RegistryError, WorkflowRegistry};
+use super::replay::{self, NonDeterminismError};
 
 /// Configuration for the workflow executor
 #[derive(Debug, Clone)]
@@ -57,10 +58,18 @@ pub enum ExecutorError {
     #[error("workflow not found: {0}")]
     WorkflowNotFound(Uuid),
 
-    /// Replay error (non-determinism detected)
+    /// Replay error (malformed history)
     #[error("replay error: {0}")]
     ReplayError(String),
 
+    /// Workflow rejected a query
+    #[error("query failed: {0}")]
+    QueryFailed(crate::workflow::WorkflowError),
+
+    /// Workflow code diverged from its recorded history
+    #[error(transparent)]
+    NonDeterminism(#[from] NonDeterminismError),
+
     /// Too many events
     #[error("workflow {0} has too many events ({1} > {2})")]
     TooManyEvents(Uuid, usize, usize),
@@ -147,6 +156,11 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         &self.store
     }
 
+    /// Get the registered workflow types
+    pub fn registry(&self) -> &WorkflowRegistry {
+        &self.registry
+    }
+
     /// Start a new workflow
     ///
     /// Creates the workflow instance, persists the start event, and
@@ -172,19 +186,29 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
             )
             .await?;
 
-        // Append WorkflowStarted event
-        let start_event = WorkflowEvent::WorkflowStarted {
+        // Create workflow instance and process on_start
+        let mut workflow = W::new(input);
+        let mut versions = VersionState::default();
+        let actions = versions.run(false, || workflow.on_start());
+        if let Some(reason) = versions.take_error() {
+            return Err(NonDeterminismError {
+                sequence: 0,
+                reason,
+            }
+            .into());
+        }
+
+        // Append WorkflowStarted event and the versions on_start resolved
+        let mut start_events = vec![WorkflowEvent::WorkflowStarted {
             input: input_json.clone(),
-        };
+        }];
+        start_events.extend(replay::marker_events(versions.take_markers()));
 
-        self.store
-            .append_events(workflow_id, 0, vec![start_event])
+        let sequence = self
+            .store
+            .append_events(workflow_id, 0, start_events)
             .await?;
 
-        // Create workflow instance and process on_start
-        let mut workflow = W::new(input);
-        let actions = workflow.on_start();
-
         // Check if workflow completes immediately
         let completes_immediately = actions.iter().any(|a| {
             matches!(
@@ -194,7 +218,13 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         });
 
         // Process initial actions
-        self.process_actions(workflow_id, 1, actions).await?;
+        let continued = self.process_actions(workflow_id, sequence, actions).await?;
+
+        // A run that continued as new is already running as its next run
+        if continued {
+            self.process_workflow(workflow_id).await?;
+            return Ok(workflow_id);
+        }
 
         // Only update status to Running if workflow didn't complete immediately
         if !completes_immediately {
@@ -209,12 +239,41 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
     /// Process a workflow after external events (activity completions, signals, etc.)
     ///
     /// This replays the workflow from its event history and processes any
-    /// new actions that result from recent events.
+    /// new actions that result from recent events. Fails with
+    /// [`ExecutorError::NonDeterminism`] when the registered workflow code
+    /// returns actions that differ from the recorded history.
+    ///
+    /// When the workflow continues as new, the next run is processed too and
+    /// the result covers every run processed.
     #[instrument(skip(self))]
     pub async fn process_workflow(
         &self,
         workflow_id: Uuid,
     ) -> Result<ProcessResult, ExecutorError> {
+        let mut total = ProcessResult {
+            completed: false,
+            events_written: 0,
+            tasks_enqueued: 0,
+            signals_processed: 0,
+        };
+
+        loop {
+            let (result, continued) = self.process_run(workflow_id).await?;
+            total.completed = result.completed;
+            total.events_written += result.events_written;
+            total.tasks_enqueued += result.tasks_enqueued;
+            total.signals_processed += result.signals_processed;
+
+            if !continued {
+                return Ok(total);
+            }
+            debug!(%workflow_id, "processing continued run");
+        }
+    }
+
+    /// Process the current run of a workflow, also returning whether it
+    /// continued as new
+    async fn process_run(&self, workflow_id: Uuid) -> Result<(ProcessResult, bool), ExecutorError> {
         // Get workflow info including type and status
         let workflow_info = self.store.get_workflow_info(workflow_id).await?;
 
@@ -223,12 +282,15 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
             WorkflowStatus::Completed | WorkflowStatus::Failed | WorkflowStatus::Cancelled
         ) {
             debug!(%workflow_id, status = ?workflow_info.status, "workflow already in terminal state");
-            return Ok(ProcessResult {
-                completed: true,
-                events_written: 0,
-                tasks_enqueued: 0,
-                signals_processed: 0,
-            });
+            return Ok((
+                ProcessResult {
+                    completed: true,
+                    events_written: 0,
+                    tasks_enqueued: 0,
+                    signals_processed: 0,
+                },
+                false,
+            ));
         }
 
         // Load all events
@@ -264,37 +326,84 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         let mut events_written = 0;
         let mut tasks_enqueued = 0;
 
-        // Replay all events to rebuild state
-        for (_seq, event) in &events {
-            self.replay_event(&mut *workflow, event)?;
-        }
+        // Replay all events to rebuild state, checking recorded commands
+        let mut versions = VersionState::default();
+        let outcome =
+            replay::replay(&mut *workflow, &events, &mut versions).inspect_err(|e| {
+                error!(%workflow_id, workflow_type = %workflow_info.workflow_type, error = %e, "workflow replay diverged from recorded history");
+            })?;
 
         debug!(%workflow_id, current_sequence, "replayed events");
 
-        // Check for pending signals
+        // Carry out actions returned for events not yet processed
+        if !outcome.version_markers.is_empty() {
+            events_written += outcome.version_markers.len();
+            current_sequence = self
+                .store
+                .append_events(workflow_id, current_sequence, outcome.version_markers)
+                .await?;
+        }
+
+        let applied = self
+            .process_actions_internal(workflow_id, current_sequence, outcome.pending_actions)
+            .await?;
+        current_sequence = applied.sequence;
+        events_written += applied.events_written;
+        tasks_enqueued += applied.tasks_enqueued;
+
+        if applied.continued {
+            return Ok((
+                ProcessResult {
+                    completed: false,
+                    events_written,
+                    tasks_enqueued,
+                    signals_processed: 0,
+                },
+                true,
+            ));
+        }
+
+        // Check for pending signals; those after a continue-as-new are left
+        // for the next run
         let signals = self.store.get_pending_signals(workflow_id).await?;
-        let signals_processed = signals.len();
+        let mut signals_processed = 0;
+        let mut continued = false;
 
         for signal in &signals {
-            let actions = workflow.on_signal(signal);
-            let signal_event = WorkflowEvent::SignalReceived {
+            let actions = versions.run(false, || workflow.on_signal(signal));
+            if let Some(reason) = versions.take_error() {
+                return Err(NonDeterminismError {
+                    sequence: current_sequence,
+                    reason,
+                }
+                .into());
+            }
+
+            // Append signal event and the versions the handler resolved
+            let mut signal_events = vec![WorkflowEvent::SignalReceived {
                 signal: signal.clone(),
-            };
+            }];
+            signal_events.extend(replay::marker_events(versions.take_markers()));
+            events_written += signal_events.len();
 
-            // Append signal event
             current_sequence = self
                 .store
-                .append_events(workflow_id, current_sequence, vec![signal_event])
+                .append_events(workflow_id, current_sequence, signal_events)
                 .await?;
-            events_written += 1;
 
             // Process resulting actions
-            let (new_seq, written, enqueued) = self
+            let applied = self
                 .process_actions_internal(workflow_id, current_sequence, actions)
                 .await?;
-            current_sequence = new_seq;
-            events_written += written;
-            tasks_enqueued += enqueued;
+            current_sequence = applied.sequence;
+            events_written += applied.events_written;
+            tasks_enqueued += applied.tasks_enqueued;
+            signals_processed += 1;
+
+            if applied.continued {
+                continued = true;
+                break;
+            }
         }
 
         // Mark signals as processed
@@ -304,6 +413,18 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
                 .await?;
         }
 
+        if continued {
+            return Ok((
+                ProcessResult {
+                    completed: false,
+                    events_written,
+                    tasks_enqueued,
+                    signals_processed,
+                },
+                true,
+            ));
+        }
+
         // Check if workflow is now complete
         let completed = workflow.is_completed();
         if completed {
@@ -323,12 +444,15 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
             }
         }
 
-        Ok(ProcessResult {
-            completed,
-            events_written,
-            tasks_enqueued,
-            signals_processed,
-        })
+        Ok((
+            ProcessResult {
+                completed,
+                events_written,
+                tasks_enqueued,
+                signals_processed,
+            },
+            false,
+        ))
     }
 
     /// Send a signal to a workflow
@@ -355,6 +479,96 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         Ok(())
     }
 
+    /// Send a signal to a workflow, starting it first if it does not exist
+    ///
+    /// Creating the workflow and queueing the signal are atomic, so this is
+    /// safe to call concurrently for the same id, e.g. to feed messages to a
+    /// long-lived workflow keyed by a session id. A new workflow runs
+    /// `on_start` before it receives the signal.
+    #[instrument(skip(self, input, signal, trace_context), fields(workflow_type = W::TYPE))]
+    pub async fn signal_with_start<W: crate::workflow::Workflow>(
+        &self,
+        workflow_id: Uuid,
+        input: W::Input,
+        signal: WorkflowSignal,
+        trace_context: Option<TraceContext>,
+    ) -> Result<ProcessResult, ExecutorError> {
+        let input_json = serde_json::to_value(&input)?;
+        self.signal_with_start_json(workflow_id, W::TYPE, input_json, signal, trace_context)
+            .await
+            .map(|(_, result)| result)
+    }
+
+    /// Like [`Self::signal_with_start`] for a workflow type named at runtime
+    ///
+    /// The type must be registered with this executor. Also returns whether
+    /// this call created the workflow.
+    #[instrument(skip(self, input, signal, trace_context))]
+    pub async fn signal_with_start_json(
+        &self,
+        workflow_id: Uuid,
+        workflow_type: &str,
+        input: serde_json::Value,
+        signal: WorkflowSignal,
+        trace_context: Option<TraceContext>,
+    ) -> Result<(bool, ProcessResult), ExecutorError> {
+        if !self.registry.contains(workflow_type) {
+            return Err(RegistryError::UnknownWorkflowType(workflow_type.to_string()).into());
+        }
+
+        let created = self
+            .store
+            .signal_with_start(
+                workflow_id,
+                workflow_type,
+                input,
+                signal,
+                trace_context.as_ref(),
+            )
+            .await
+            .map_err(|e| match e {
+                StoreError::WorkflowClosed(id) => ExecutorError::WorkflowCompleted(id),
+                e => ExecutorError::Store(e),
+            })?;
+
+        if created {
+            info!(%workflow_id, "started workflow with signal");
+        }
+
+        let result = self.process_workflow(workflow_id).await?;
+
+        if created && !result.completed {
+            self.store
+                .update_workflow_status(workflow_id, WorkflowStatus::Running, None, None)
+                .await?;
+        }
+
+        Ok((created, result))
+    }
+
+    /// Query a workflow's state by replaying its history
+    ///
+    /// Read-only: nothing is written, and actions returned during replay
+    /// are discarded. See [`crate::Workflow::query`].
+    #[instrument(skip(self, args))]
+    pub async fn query_workflow(
+        &self,
+        workflow_id: Uuid,
+        query_type: &str,
+        args: serde_json::Value,
+    ) -> Result<serde_json::Value, ExecutorError> {
+        let workflow_info = self.store.get_workflow_info(workflow_id).await?;
+        let events = self.store.load_events(workflow_id).await?;
+
+        replay::query_history(
+            &self.registry,
+            &workflow_info.workflow_type,
+            &events,
+            query_type,
+            args,
+        )
+    }
+
     /// Handle activity completion
     ///
     /// Called by the worker pool when an activity completes successfully.
@@ -369,6 +583,16 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         let events = self.store.load_events(workflow_id).await?;
         let current_sequence = events.len() as i32;
 
+        if !activity_pending(&events, activity_id) {
+            debug!(%workflow_id, %activity_id, "dropping result of activity that is not pending");
+            return Ok(ProcessResult {
+                completed: false,
+                events_written: 0,
+                tasks_enqueued: 0,
+                signals_processed: 0,
+            });
+        }
+
         // Append completion event
         let completion_event = WorkflowEvent::ActivityCompleted {
             activity_id: activity_id.to_string(),
@@ -398,6 +622,16 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         let events = self.store.load_events(workflow_id).await?;
         let current_sequence = events.len() as i32;
 
+        if !activity_pending(&events, activity_id) {
+            debug!(%workflow_id, %activity_id, "dropping failure of activity that is not pending");
+            return Ok(ProcessResult {
+                completed: false,
+                events_written: 0,
+                tasks_enqueued: 0,
+                signals_processed: 0,
+            });
+        }
+
         // Append failure event
         let failure_event = WorkflowEvent::ActivityFailed {
             activity_id: activity_id.to_string(),
@@ -423,6 +657,9 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
     }
 
     /// Handle timer fired
+    ///
+    /// Does nothing when the timer is not pending, e.g. because another
+    /// caller fired it first.
     #[instrument(skip(self))]
     pub async fn on_timer_fired(
         &self,
@@ -433,6 +670,16 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         let events = self.store.load_events(workflow_id).await?;
         let current_sequence = events.len() as i32;
 
+        if !timer_pending(&events, timer_id) {
+            debug!(%workflow_id, %timer_id, "timer is not pending");
+            return Ok(ProcessResult {
+                completed: false,
+                events_written: 0,
+                tasks_enqueued: 0,
+                signals_processed: 0,
+            });
+        }
+
         // Append timer fired event
         let timer_event = WorkflowEvent::TimerFired {
             timer_id: timer_id.to_string(),
@@ -443,82 +690,59 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
             .await?;
 
         // Process the workflow
-        self.process_workflow(workflow_id).await
+        let mut result = self.process_workflow(workflow_id).await?;
+        result.events_written += 1;
+        Ok(result)
     }
 
-    // =========================================================================
-    // Internal Methods
-    // =========================================================================
-
-    /// Replay a single event on a workflow
-    fn replay_event(
-        &self,
-        workflow: &mut dyn AnyWorkflow,
-        event: &WorkflowEvent,
-    ) -> Result<(), ExecutorError> {
-        match event {
-            WorkflowEvent::WorkflowStarted { .. } => {
-                // on_start is called during workflow creation, not replay
-                let _actions = workflow.on_start();
-            }
-
-            WorkflowEvent::ActivityCompleted {
-                activity_id,
-                result,
-            } => {
-                let _actions = workflow.on_activity_completed(activity_id, result.clone());
-            }
-
-            WorkflowEvent::ActivityFailed {
-                activity_id,
-                error,
-                will_retry,
-            } => {
-                // Only notify workflow of final failure (when won't retry)
-                if !will_retry {
-                    let _actions = workflow.on_activity_failed(activity_id, error);
+    /// Fire up to `limit` timers that are due, returning how many fired
+    ///
+    /// Meant to be called periodically. A timer that fails to fire, e.g.
+    /// because its workflow changed concurrently, stays due and is retried
+    /// on the next call.
+    pub async fn fire_due_timers(&self, limit: usize) -> Result<usize, ExecutorError> {
+        let due = self
+            .store
+            .list_due_timers(chrono::Utc::now(), limit)
+            .await?;
+        let mut fired = 0;
+
+        for timer in due {
+            match self
+                .on_timer_fired(timer.workflow_id, &timer.timer_id)
+                .await
+            {
+                Ok(result) if result.events_written > 0 => fired += 1,
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(
+                        workflow_id = %timer.workflow_id,
+                        timer_id = %timer.timer_id,
+                        error = %e,
+                        "failed to fire timer"
+                    );
                 }
             }
-
-            WorkflowEvent::TimerFired { timer_id } => {
-                let _actions = workflow.on_timer_fired(timer_id);
-            }
-
-            WorkflowEvent::SignalReceived { signal } => {
-                let _actions = workflow.on_signal(signal);
-            }
-
-            // Events that don't affect workflow state during replay
-            WorkflowEvent::WorkflowCompleted { .. }
-            | WorkflowEvent::WorkflowFailed { .. }
-            | WorkflowEvent::WorkflowCancelled { .. }
-            | WorkflowEvent::ActivityScheduled { .. }
-            | WorkflowEvent::ActivityStarted { .. }
-            | WorkflowEvent::ActivityTimedOut { .. }
-            | WorkflowEvent::ActivityCancelled { .. }
-            | WorkflowEvent::TimerStarted { .. }
-            | WorkflowEvent::TimerCancelled { .. }
-            | WorkflowEvent::ChildWorkflowStarted { .. }
-            | WorkflowEvent::ChildWorkflowCompleted { .. }
-            | WorkflowEvent::ChildWorkflowFailed { .. } => {
-                // These events are informational during replay
-            }
         }
 
-        Ok(())
+        Ok(fired)
     }
 
-    /// Process actions from workflow, returning the new sequence number
+    // =========================================================================
+    // Internal Methods
+    // =========================================================================
+
+    /// Process actions from workflow, returning whether the run continued as new
     async fn process_actions(
         &self,
         workflow_id: Uuid,
         sequence: i32,
         actions: Vec<WorkflowAction>,
-    ) -> Result<(), ExecutorError> {
-        let (_new_seq, _written, _enqueued) = self
+    ) -> Result<bool, ExecutorError> {
+        let applied = self
             .process_actions_internal(workflow_id, sequence, actions)
             .await?;
-        Ok(())
+        Ok(applied.continued)
     }
 
     /// Internal action processing that returns detailed results
@@ -527,11 +751,13 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
         workflow_id: Uuid,
         mut sequence: i32,
         actions: Vec<WorkflowAction>,
-    ) -> Result<(i32, usize, usize), ExecutorError> {
+    ) -> Result<AppliedActions, ExecutorError> {
         let mut events_written = 0;
         let mut tasks_enqueued = 0;
+        let mut continued = false;
+        let mut actions = actions.into_iter();
 
-        for action in actions {
+        for action in actions.by_ref() {
             match action {
                 WorkflowAction::ScheduleActivity {
                     activity_id,
@@ -659,7 +885,7 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
                     debug!(%workflow_id, %activity_id, "cancelling activity");
 
                     let event = WorkflowEvent::ActivityCancelled {
-                        activity_id,
+                        activity_id: activity_id.clone(),
                         reason: "cancelled by workflow".to_string(),
                     };
 
@@ -668,6 +894,29 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
                         .append_events(workflow_id, sequence, vec![event])
                         .await?;
                     events_written += 1;
+
+                    // Workers see the cancellation through their heartbeats
+                    self.store
+                        .cancel_activity(workflow_id, &activity_id)
+                        .await?;
+                }
+
+                WorkflowAction::ContinueAsNew { input } => {
+                    let run_id = self
+                        .store
+                        .continue_as_new(workflow_id, sequence, input)
+                        .await
+                        .map_err(|e| match e {
+                            StoreError::WorkflowClosed(id) => ExecutorError::WorkflowCompleted(id),
+                            e => ExecutorError::Store(e),
+                        })?;
+                    info!(%workflow_id, %run_id, "continued workflow as new");
+
+                    // WorkflowContinuedAsNew, then WorkflowStarted of the next run
+                    sequence = 1;
+                    events_written += 2;
+                    continued = true;
+                    break;
                 }
 
                 WorkflowAction::None => {
@@ -676,10 +925,81 @@ impl<S: WorkflowEventStore> WorkflowExecutor<S> {
             }
         }
 
-        Ok((sequence, events_written, tasks_enqueued))
+        if continued && actions.len() > 0 {
+            warn!(%workflow_id, ignored = actions.len(), "ignoring actions after continue-as-new");
+        }
+
+        Ok(AppliedActions {
+            sequence,
+            events_written,
+            tasks_enqueued,
+            continued,
+        })
     }
 }
 
+/// Outcome of carrying out a batch of workflow actions
+struct AppliedActions {
+    /// Next expected sequence of the workflow history
+    sequence: i32,
+    events_written: usize,
+    tasks_enqueued: usize,
+    /// Whether the run continued as new
+    continued: bool,
+}
+
+/// Whether the current run scheduled an activity that has not finished yet
+///
+/// Results of activities from an earlier run, or of activities that already
+/// completed, failed for good or were cancelled, are dropped.
+fn activity_pending(events: &[(i32, WorkflowEvent)], activity_id: &str) -> bool {
+    events
+        .iter()
+        .rev()
+        .find_map(|(_, event)| match event {
+            WorkflowEvent::ActivityScheduled {
+                activity_id: id, ..
+            }
+            | WorkflowEvent::ActivityStarted {
+                activity_id: id, ..
+            }
+            | WorkflowEvent::ActivityTimedOut {
+                activity_id: id, ..
+            } if id == activity_id => Some(true),
+            WorkflowEvent::ActivityFailed {
+                activity_id: id,
+                will_retry,
+                ..
+            } if id == activity_id => Some(*will_retry),
+            WorkflowEvent::ActivityCompleted {
+                activity_id: id, ..
+            }
+            | WorkflowEvent::ActivityCancelled {
+                activity_id: id, ..
+            } if id == activity_id => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// Whether the latest start of a timer has not fired or been cancelled yet
+fn timer_pending(events: &[(i32, WorkflowEvent)], timer_id: &str) -> bool {
+    events
+        .iter()
+        .rev()
+        .find_map(|(_, event)| match event {
+            WorkflowEvent::TimerStarted { timer_id: id, .. } if id == timer_id => Some(true),
+            WorkflowEvent::TimerFired { timer_id: id }
+            | WorkflowEvent::TimerCancelled { timer_id: id }
+                if id == timer_id =>
+            {
+                Some(false)
+            }
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -786,6 +1106,17 @@ mod tests {
         fn error(&self) -> Option<crate::WorkflowError> {
             self.error_message.as_ref().map(crate::WorkflowError::new)
         }
+
+        fn query(
+            &self,
+            query_type: &str,
+            _args: serde_json::Value,
+        ) -> Result<serde_json::Value, crate::WorkflowError> {
+            match query_type {
+                "current" => Ok(serde_json::json!(self.current)),
+                _ => Err(crate::WorkflowError::new("unknown query")),
+            }
+        }
     }
 
     #[tokio::test]
@@ -902,6 +1233,43 @@ mod tests {
         assert_eq!(status, WorkflowStatus::Completed);
     }
 
+    #[tokio::test]
+    async fn test_activity_completion_schedules_next_activity() {
+        let store = InMemoryWorkflowEventStore::new();
+        let mut executor = WorkflowExecutor::new(store);
+        executor.register::<CounterWorkflow>();
+
+        let input = CounterInput {
+            start: 0,
+            target: 2,
+        };
+        let workflow_id = executor
+            .start_workflow::<CounterWorkflow>(input, None)
+            .await
+            .expect("should start workflow");
+
+        let result = executor
+            .on_activity_completed(
+                workflow_id,
+                "increment-0",
+                serde_json::json!({ "value": 1 }),
+            )
+            .await
+            .expect("should complete activity");
+
+        assert_eq!(result.tasks_enqueued, 1);
+        let events = executor.store().load_events(workflow_id).await.unwrap();
+        assert!(matches!(
+            &events.last().unwrap().1,
+            WorkflowEvent::ActivityScheduled { activity_id, .. } if activity_id == "increment-1"
+        ));
+
+        // Replaying the recorded history schedules nothing new
+        let result = executor.process_workflow(workflow_id).await.unwrap();
+        assert_eq!(result.events_written, 0);
+        assert_eq!(result.tasks_enqueued, 0);
+    }
+
     #[tokio::test]
     async fn test_activity_failure() {
         let store = InMemoryWorkflowEventStore::new();
@@ -967,6 +1335,95 @@ mod tests {
         assert_eq!(result.signals_processed, 1);
     }
 
+    #[tokio::test]
+    async fn test_signal_with_start() {
+        let store = InMemoryWorkflowEventStore::new();
+        let mut executor = WorkflowExecutor::new(store);
+        executor.register::<CounterWorkflow>();
+
+        let workflow_id = Uuid::now_v7();
+        let input = CounterInput {
+            start: 0,
+            target: 10,
+        };
+
+        // First call starts the workflow, then delivers the signal
+        let result = executor
+            .signal_with_start::<CounterWorkflow>(
+                workflow_id,
+                input.clone(),
+                WorkflowSignal::new("message", serde_json::json!({ "n": 1 })),
+                None,
+            )
+            .await
+            .expect("should start workflow");
+
+        assert_eq!(result.signals_processed, 1);
+        assert_eq!(result.tasks_enqueued, 1);
+        let status = executor.store().get_workflow_status(workflow_id).await;
+        assert_eq!(status.unwrap(), WorkflowStatus::Running);
+
+        // Later calls only signal the running workflow
+        let result = executor
+            .signal_with_start::<CounterWorkflow>(
+                workflow_id,
+                input,
+                WorkflowSignal::new("message", serde_json::json!({ "n": 2 })),
+                None,
+            )
+            .await
+            .expect("should signal workflow");
+
+        assert_eq!(result.signals_processed, 1);
+        assert_eq!(result.tasks_enqueued, 0);
+        let events = executor.store().load_events(workflow_id).await.unwrap();
+        let signals = events
+            .iter()
+            .filter(|(_, e)| matches!(e, WorkflowEvent::SignalReceived { .. }))
+            .count();
+        assert_eq!(signals, 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_workflow() {
+        let store = InMemoryWorkflowEventStore::new();
+        let mut executor = WorkflowExecutor::new(store);
+        executor.register::<CounterWorkflow>();
+
+        let input = CounterInput {
+            start: 0,
+            target: 3,
+        };
+        let workflow_id = executor
+            .start_workflow::<CounterWorkflow>(input, None)
+            .await
+            .expect("should start workflow");
+        executor
+            .on_activity_completed(
+                workflow_id,
+                "increment-0",
+                serde_json::json!({ "value": 1 }),
+            )
+            .await
+            .unwrap();
+
+        let events_before = executor.store().load_events(workflow_id).await.unwrap();
+        let current = executor
+            .query_workflow(workflow_id, "current", serde_json::Value::Null)
+            .await
+            .expect("should answer query");
+        assert_eq!(current, serde_json::json!(1));
+
+        let result = executor
+            .query_workflow(workflow_id, "unknown", serde_json::Value::Null)
+            .await;
+        assert!(matches!(result, Err(ExecutorError::QueryFailed(_))));
+
+        // Queries are read-only
+        let events_after = executor.store().load_events(workflow_id).await.unwrap();
+        assert_eq!(events_before, events_after);
+    }
+
     #[tokio::test]
     async fn test_cannot_signal_completed_workflow() {
         let store = InMemoryWorkflowEventStore::new();
@@ -1035,4 +1492,300 @@ mod tests {
         let result = executor.process_workflow(workflow_id).await.unwrap();
         assert!(result.completed);
     }
+
+    struct SleepWorkflow {
+        sleep_ms: u64,
+        woke: bool,
+    }
+
+    impl crate::workflow::Workflow for SleepWorkflow {
+        const TYPE: &'static str = "sleep_workflow";
+        type Input = u64;
+        type Output = bool;
+
+        fn new(input: Self::Input) -> Self {
+            Self {
+                sleep_ms: input,
+                woke: false,
+            }
+        }
+
+        fn on_start(&mut self) -> Vec<WorkflowAction> {
+            vec![WorkflowAction::timer(
+                "sleep",
+                std::time::Duration::from_millis(self.sleep_ms),
+            )]
+        }
+
+        fn on_activity_completed(
+            &mut self,
+            _activity_id: &str,
+            _result: serde_json::Value,
+        ) -> Vec<WorkflowAction> {
+            vec![]
+        }
+
+        fn on_activity_failed(
+            &mut self,
+            _activity_id: &str,
+            _error: &ActivityError,
+        ) -> Vec<WorkflowAction> {
+            vec![]
+        }
+
+        fn on_timer_fired(&mut self, _timer_id: &str) -> Vec<WorkflowAction> {
+            self.woke = true;
+            vec![WorkflowAction::complete(serde_json::json!(true))]
+        }
+
+        fn is_completed(&self) -> bool {
+            self.woke
+        }
+
+        fn result(&self) -> Option<Self::Output> {
+            self.woke.then_some(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fire_due_timers() {
+        let store = InMemoryWorkflowEventStore::new();
+        let mut executor = WorkflowExecutor::new(store);
+        executor.register::<SleepWorkflow>();
+
+        let due_id = executor
+            .start_workflow::<SleepWorkflow>(0, None)
+            .await
+            .unwrap();
+        let sleeping_id = executor
+            .start_workflow::<SleepWorkflow>(3_600_000, None)
+            .await
+            .unwrap();
+
+        assert_eq!(executor.fire_due_timers(10).await.unwrap(), 1);
+        assert_eq!(
+            executor.store().get_workflow_status(due_id).await.unwrap(),
+            WorkflowStatus::Completed
+        );
+        assert_eq!(
+            executor
+                .store()
+                .get_workflow_status(sleeping_id)
+                .await
+                .unwrap(),
+            WorkflowStatus::Running
+        );
+
+        // A timer fires once
+        assert_eq!(executor.fire_due_timers(10).await.unwrap(), 0);
+        let result = executor.on_timer_fired(due_id, "sleep").await.unwrap();
+        assert_eq!(result.events_written, 0);
+    }
+
+    /// Counts "add" signals, continuing as new every two of them, and runs a
+    /// "work" activity per "work" signal
+    struct TallyWorkflow {
+        count: u32,
+        added: u32,
+        done: bool,
+    }
+
+    impl crate::workflow::Workflow for TallyWorkflow {
+        const TYPE: &'static str = "tally_workflow";
+        type Input = u32;
+        type Output = u32;
+
+        fn new(input: Self::Input) -> Self {
+            Self {
+                count: input,
+                added: 0,
+                done: false,
+            }
+        }
+
+        fn on_start(&mut self) -> Vec<WorkflowAction> {
+            vec![]
+        }
+
+        fn on_activity_completed(
+            &mut self,
+            _activity_id: &str,
+            _result: serde_json::Value,
+        ) -> Vec<WorkflowAction> {
+            vec![]
+        }
+
+        fn on_activity_failed(
+            &mut self,
+            _activity_id: &str,
+            _error: &ActivityError,
+        ) -> Vec<WorkflowAction> {
+            vec![]
+        }
+
+        fn on_signal(&mut self, signal: &WorkflowSignal) -> Vec<WorkflowAction> {
+            if signal.signal_type == "done" {
+                self.done = true;
+                return vec![WorkflowAction::complete(serde_json::json!(self.count))];
+            }
+            if signal.signal_type == "work" {
+                return vec![WorkflowAction::schedule_activity(
+                    format!("work-{}", self.added),
+                    "work",
+                    serde_json::json!({}),
+                )];
+            }
+            self.count += 1;
+            self.added += 1;
+            if self.added == 2 {
+                vec![WorkflowAction::continue_as_new(serde_json::json!(
+                    self.count
+                ))]
+            } else {
+                vec![]
+            }
+        }
+
+        fn is_completed(&self) -> bool {
+            self.done
+        }
+
+        fn result(&self) -> Option<Self::Output> {
+            self.done.then_some(self.count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_continue_as_new() {
+        let store = InMemoryWorkflowEventStore::new();
+        let mut executor = WorkflowExecutor::new(store);
+        executor.register::<TallyWorkflow>();
+
+        let workflow_id = executor
+            .start_workflow::<TallyWorkflow>(0, None)
+            .await
+            .unwrap();
+        for signal_type in ["add", "add", "add", "done"] {
+            executor
+                .send_signal(
+                    workflow_id,
+                    WorkflowSignal::new(signal_type, serde_json::json!({})),
+                )
+                .await
+                .unwrap();
+        }
+
+        // The first run continues after two signals; the second run gets the rest
+        let result = executor.process_workflow(workflow_id).await.unwrap();
+        assert!(result.completed);
+        assert_eq!(result.signals_processed, 4);
+
+        let info = executor
+            .store()
+            .get_workflow_info(workflow_id)
+            .await
+            .unwrap();
+        assert_eq!(info.status, WorkflowStatus::Completed);
+        assert_eq!(info.input, serde_json::json!(2));
+        assert_eq!(info.result, Some(serde_json::json!(3)));
+        assert_eq!(info.run_number, 2);
+
+        let first_run = info.previous_run_id.expect("should link the first run");
+        let first = executor.store().get_workflow_info(first_run).await.unwrap();
+        assert_eq!(first.status, WorkflowStatus::Completed);
+        assert_eq!(first.run_number, 1);
+        assert_eq!(first.previous_run_id, None);
+        assert_eq!(first.continued_as, Some(workflow_id));
+
+        // The first run keeps its own history
+        let events = executor.store().load_events(first_run).await.unwrap();
+        assert!(matches!(
+            events.last(),
+            Some((_, WorkflowEvent::WorkflowContinuedAsNew { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_continue_as_new_cancels_running_activities() {
+        let store = InMemoryWorkflowEventStore::new();
+        let mut executor = WorkflowExecutor::new(store);
+        executor.register::<TallyWorkflow>();
+
+        let workflow_id = executor
+            .start_workflow::<TallyWorkflow>(0, None)
+            .await
+            .unwrap();
+        executor
+            .send_signal(
+                workflow_id,
+                WorkflowSignal::new("work", serde_json::json!({})),
+            )
+            .await
+            .unwrap();
+        executor.process_workflow(workflow_id).await.unwrap();
+        let running = executor
+            .store()
+            .claim_task("worker-1", &["work".to_string()], 1)
+            .await
+            .unwrap();
+        assert_eq!(running.len(), 1);
+
+        // "work-1" is still pending when the run continues as new
+        for signal_type in ["add", "work", "add"] {
+            executor
+                .send_signal(
+                    workflow_id,
+                    WorkflowSignal::new(signal_type, serde_json::json!({})),
+                )
+                .await
+                .unwrap();
+        }
+        executor.process_workflow(workflow_id).await.unwrap();
+        let info = executor
+            .store()
+            .get_workflow_info(workflow_id)
+            .await
+            .unwrap();
+        assert_eq!(info.run_number, 2);
+
+        // Both tasks of the first run are cancelled
+        let heartbeat = executor
+            .store()
+            .heartbeat_task(running[0].id, "worker-1", None)
+            .await
+            .unwrap();
+        assert!(heartbeat.should_cancel);
+        assert!(executor
+            .store()
+            .claim_task("worker-1", &["work".to_string()], 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // A late result of the first run does not reach the second
+        let result = executor
+            .on_activity_completed(workflow_id, "work-0", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result.events_written, 0);
+        let result = executor
+            .on_activity_failed(
+                workflow_id,
+                "work-0",
+                ActivityError::non_retryable("late"),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.events_written, 0);
+        assert_eq!(
+            executor
+                .store()
+                .load_events(workflow_id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
 }
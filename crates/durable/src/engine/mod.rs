@@ -1,10 +1,14 @@
 //! Workflow execution engine
 //!
 //! The engine module provides the `WorkflowExecutor` which drives workflow
-//! state machines through event replay and action processing.
+//! state machines through event replay and action processing, and
+//! [`replay_history`] for checking workflow code changes against recorded
+//! histories.
 
 mod executor;
 mod registry;
+mod replay;
 
 pub use executor::{ExecutorConfig, ExecutorError, WorkflowExecutor};
 pub use registry::{WorkflowFactory, WorkflowRegistry};
+pub use replay::{query_history, replay_history, NonDeterminismError, ReplayOutcome};
@@ -46,6 +46,9 @@ pub trait AnyWorkflow: Send + Sync {
 
     /// Get the error (if failed)
     fn error(&self) -> Option<WorkflowError>;
+
+    /// Answer a read-only query about the workflow state
+    fn query(&self, query_type: &str, args: Value) -> Result<Value, WorkflowError>;
 }
 
 /// Wrapper to implement AnyWorkflow for any Workflow
@@ -95,6 +98,10 @@ impl<W: Workflow> AnyWorkflow for WorkflowWrapper<W> {
     fn error(&self) -> Option<WorkflowError> {
         self.inner.error()
     }
+
+    fn query(&self, query_type: &str, args: Value) -> Result<Value, WorkflowError> {
+        self.inner.query(query_type, args)
+    }
 }
 
 /// Factory function type for creating workflows from JSON input
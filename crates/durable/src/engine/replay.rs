@@ -0,0 +1,598 @@
+//! Deterministic replay of recorded workflow history
+//!
+//! Replay re-runs the workflow handler for every recorded trigger event
+//! (`WorkflowStarted`, `ActivityCompleted`, a final `ActivityFailed`,
+//! `TimerFired`, `SignalReceived`) and checks the actions it returns against
+//! the command events recorded after it (`ActivityScheduled`, `TimerStarted`,
+//! `ActivityCancelled`, `ChildWorkflowStarted`, `WorkflowCompleted`,
+//! `WorkflowFailed`, `WorkflowContinuedAsNew`). A workflow whose code no
+//! longer produces the recorded commands fails with a [`NonDeterminismError`]
+//! instead of silently diverging from its history.
+//!
+//! Actions returned for trigger events at the end of the history, with no
+//! commands recorded yet, are handed back to the executor to carry out.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::workflow::{VersionState, Workflow, WorkflowAction, WorkflowEvent};
+
+use super::executor::ExecutorError;
+use super::registry::{AnyWorkflow, WorkflowRegistry};
+
+/// Workflow code returned actions that differ from its recorded history
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("non-deterministic workflow at event {sequence}: {reason}")]
+pub struct NonDeterminismError {
+    /// Sequence number of the event where replay diverged
+    pub sequence: i32,
+
+    /// What diverged
+    pub reason: String,
+}
+
+/// Result of replaying a workflow history
+#[derive(Debug, Default)]
+pub struct ReplayOutcome {
+    /// Actions returned by handlers whose commands are not recorded yet
+    pub pending_actions: Vec<WorkflowAction>,
+
+    /// Version markers to record before the pending actions
+    pub version_markers: Vec<WorkflowEvent>,
+}
+
+/// Replay a recorded history against the current code of workflow `W`
+///
+/// Use in tests to check that changed workflow code still replays histories
+/// recorded by earlier versions, e.g. histories exported from production:
+///
+/// ```ignore
+/// let history: Vec<WorkflowEvent> = serde_json::from_str(include_str!("order_history.json"))?;
+/// replay_history::<OrderWorkflow>(&history)?;
+/// ```
+pub fn replay_history<W: Workflow>(
+    events: &[WorkflowEvent],
+) -> Result<ReplayOutcome, ExecutorError> {
+    let mut registry = WorkflowRegistry::new();
+    registry.register::<W>();
+
+    let events: Vec<(i32, WorkflowEvent)> = events
+        .iter()
+        .enumerate()
+        .map(|(sequence, event)| (sequence as i32, event.clone()))
+        .collect();
+
+    let mut workflow = create_from_history(&registry, W::TYPE, &events)?;
+    Ok(replay(
+        &mut *workflow,
+        &events,
+        &mut VersionState::default(),
+    )?)
+}
+
+/// Answer a query by replaying a workflow's recorded history
+///
+/// The workflow sees every recorded event, including events the executor
+/// has not processed yet. Actions returned during replay are discarded.
+pub fn query_history(
+    registry: &WorkflowRegistry,
+    workflow_type: &str,
+    events: &[(i32, WorkflowEvent)],
+    query_type: &str,
+    args: serde_json::Value,
+) -> Result<serde_json::Value, ExecutorError> {
+    let mut workflow = create_from_history(registry, workflow_type, events)?;
+    replay(&mut *workflow, events, &mut VersionState::default())?;
+
+    workflow
+        .query(query_type, args)
+        .map_err(ExecutorError::QueryFailed)
+}
+
+/// Create a workflow from the input recorded in its `WorkflowStarted` event
+fn create_from_history(
+    registry: &WorkflowRegistry,
+    workflow_type: &str,
+    events: &[(i32, WorkflowEvent)],
+) -> Result<Box<dyn AnyWorkflow>, ExecutorError> {
+    let Some((_, WorkflowEvent::WorkflowStarted { input })) = events.first() else {
+        return Err(ExecutorError::ReplayError(
+            "first event must be WorkflowStarted".to_string(),
+        ));
+    };
+
+    Ok(registry.create(workflow_type, input.clone())?)
+}
+
+/// Replay `events` on a freshly created workflow
+///
+/// `versions` keeps the resolved versions for handlers run after replay.
+pub(crate) fn replay(
+    workflow: &mut dyn AnyWorkflow,
+    events: &[(i32, WorkflowEvent)],
+    versions: &mut VersionState,
+) -> Result<ReplayOutcome, NonDeterminismError> {
+    // Handlers for trigger events before the last recorded command already
+    // ran when the history was written
+    let last_recorded = events.iter().rposition(|(_, event)| {
+        event.is_command() || matches!(event, WorkflowEvent::VersionMarker { .. })
+    });
+
+    let mut pending = VecDeque::new();
+    let mut after_commands = false;
+
+    for (index, (sequence, event)) in events.iter().enumerate() {
+        if let Some(recorded) = Command::from_event(event) {
+            after_commands = true;
+            let action = pending.pop_front().ok_or_else(|| NonDeterminismError {
+                sequence: *sequence,
+                reason: format!(
+                    "history recorded {} but the workflow returned nothing",
+                    recorded
+                ),
+            })?;
+            let returned = Command::from_action(&action);
+            if returned.as_ref() != Some(&recorded) {
+                return Err(NonDeterminismError {
+                    sequence: *sequence,
+                    reason: format!(
+                        "history recorded {} but the workflow returned {}",
+                        recorded,
+                        returned.map_or_else(|| "nothing".to_string(), |c| c.to_string())
+                    ),
+                });
+            }
+            continue;
+        }
+
+        if matches!(event, WorkflowEvent::VersionMarker { .. }) {
+            after_commands = true;
+            continue;
+        }
+
+        if !is_trigger(event) {
+            continue;
+        }
+
+        check_recorded(after_commands, &pending, *sequence)?;
+        after_commands = false;
+
+        // Markers recorded for this handler precede its commands
+        for (_, later) in events[index + 1..]
+            .iter()
+            .take_while(|(_, later)| !later.is_command())
+        {
+            if let WorkflowEvent::VersionMarker { change_id, version } = later {
+                versions.record(change_id, *version);
+            }
+        }
+
+        let replaying = last_recorded.is_some_and(|last| index < last);
+        let actions = versions.run(replaying, || dispatch(workflow, event));
+        if let Some(reason) = versions.take_error() {
+            return Err(NonDeterminismError {
+                sequence: *sequence,
+                reason,
+            });
+        }
+
+        pending.extend(
+            actions
+                .into_iter()
+                .filter(|action| !matches!(action, WorkflowAction::None)),
+        );
+    }
+
+    check_recorded(
+        after_commands,
+        &pending,
+        events.last().map_or(0, |(sequence, _)| sequence + 1),
+    )?;
+
+    Ok(ReplayOutcome {
+        pending_actions: pending.into(),
+        version_markers: marker_events(versions.take_markers()),
+    })
+}
+
+/// Version marker events for markers taken from a [`VersionState`]
+pub(crate) fn marker_events(markers: Vec<(String, u32)>) -> Vec<WorkflowEvent> {
+    markers
+        .into_iter()
+        .map(|(change_id, version)| WorkflowEvent::VersionMarker { change_id, version })
+        .collect()
+}
+
+/// Fail if handlers returned actions the finished step did not record
+fn check_recorded(
+    after_commands: bool,
+    pending: &VecDeque<WorkflowAction>,
+    sequence: i32,
+) -> Result<(), NonDeterminismError> {
+    match pending.front().and_then(Command::from_action) {
+        Some(command) if after_commands => Err(NonDeterminismError {
+            sequence,
+            reason: format!(
+                "the workflow returned {} which history did not record",
+                command
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Check if the workflow handles this event
+fn is_trigger(event: &WorkflowEvent) -> bool {
+    match event {
+        WorkflowEvent::WorkflowStarted { .. }
+        | WorkflowEvent::ActivityCompleted { .. }
+        | WorkflowEvent::TimerFired { .. }
+        | WorkflowEvent::SignalReceived { .. } => true,
+        // Only the final failure reaches the workflow
+        WorkflowEvent::ActivityFailed { will_retry, .. } => !will_retry,
+        _ => false,
+    }
+}
+
+/// Run the handler for a trigger event
+fn dispatch(workflow: &mut dyn AnyWorkflow, event: &WorkflowEvent) -> Vec<WorkflowAction> {
+    match event {
+        WorkflowEvent::WorkflowStarted { .. } => workflow.on_start(),
+        WorkflowEvent::ActivityCompleted {
+            activity_id,
+            result,
+        } => workflow.on_activity_completed(activity_id, result.clone()),
+        WorkflowEvent::ActivityFailed {
+            activity_id, error, ..
+        } => workflow.on_activity_failed(activity_id, error),
+        WorkflowEvent::TimerFired { timer_id } => workflow.on_timer_fired(timer_id),
+        WorkflowEvent::SignalReceived { signal } => workflow.on_signal(signal),
+        _ => vec![],
+    }
+}
+
+/// The parts of an action that must match its recorded event
+///
+/// Activity inputs and options may change between versions; ids and types
+/// may not, since later events refer to them.
+#[derive(Debug, PartialEq, Eq)]
+enum Command<'a> {
+    ScheduleActivity {
+        activity_id: &'a str,
+        activity_type: &'a str,
+    },
+    StartTimer {
+        timer_id: &'a str,
+    },
+    CancelActivity {
+        activity_id: &'a str,
+    },
+    StartChildWorkflow {
+        workflow_type: &'a str,
+    },
+    CompleteWorkflow,
+    FailWorkflow,
+    ContinueAsNew,
+}
+
+impl<'a> Command<'a> {
+    fn from_action(action: &'a WorkflowAction) -> Option<Self> {
+        match action {
+            WorkflowAction::ScheduleActivity {
+                activity_id,
+                activity_type,
+                ..
+            } => Some(Self::ScheduleActivity {
+                activity_id,
+                activity_type,
+            }),
+            WorkflowAction::StartTimer { timer_id, .. } => Some(Self::StartTimer { timer_id }),
+            WorkflowAction::CancelActivity { activity_id } => {
+                Some(Self::CancelActivity { activity_id })
+            }
+            WorkflowAction::ScheduleChildWorkflow { workflow_type, .. } => {
+                Some(Self::StartChildWorkflow { workflow_type })
+            }
+            WorkflowAction::CompleteWorkflow { .. } => Some(Self::CompleteWorkflow),
+            WorkflowAction::FailWorkflow { .. } => Some(Self::FailWorkflow),
+            WorkflowAction::ContinueAsNew { .. } => Some(Self::ContinueAsNew),
+            WorkflowAction::None => None,
+        }
+    }
+
+    fn from_event(event: &'a WorkflowEvent) -> Option<Self> {
+        match event {
+            WorkflowEvent::ActivityScheduled {
+                activity_id,
+                activity_type,
+                ..
+            } => Some(Self::ScheduleActivity {
+                activity_id,
+                activity_type,
+            }),
+            WorkflowEvent::TimerStarted { timer_id, .. } => Some(Self::StartTimer { timer_id }),
+            WorkflowEvent::ActivityCancelled { activity_id, .. } => {
+                Some(Self::CancelActivity { activity_id })
+            }
+            WorkflowEvent::ChildWorkflowStarted { workflow_type, .. } => {
+                Some(Self::StartChildWorkflow { workflow_type })
+            }
+            WorkflowEvent::WorkflowCompleted { .. } => Some(Self::CompleteWorkflow),
+            WorkflowEvent::WorkflowFailed { .. } => Some(Self::FailWorkflow),
+            WorkflowEvent::WorkflowContinuedAsNew { .. } => Some(Self::ContinueAsNew),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ScheduleActivity {
+                activity_id,
+                activity_type,
+            } => write!(f, "activity '{}' ({})", activity_id, activity_type),
+            Self::StartTimer { timer_id } => write!(f, "timer '{}'", timer_id),
+            Self::CancelActivity { activity_id } => {
+                write!(f, "cancellation of activity '{}'", activity_id)
+            }
+            Self::StartChildWorkflow { workflow_type } => {
+                write!(f, "child workflow ({})", workflow_type)
+            }
+            Self::CompleteWorkflow => write!(f, "workflow completion"),
+            Self::FailWorkflow => write!(f, "workflow failure"),
+            Self::ContinueAsNew => write!(f, "continue-as-new"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::ActivityError;
+    use crate::workflow::{ActivityOptions, DEFAULT_VERSION};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+
+    /// Schedules `steps` activities one after another, then completes
+    ///
+    /// With `ADD_TIMER`, waits on a timer before completing. With `PATCHED`,
+    /// guards the timer behind a version check.
+    struct StepsWorkflow<const ADD_TIMER: bool, const PATCHED: bool> {
+        steps: u64,
+        done: u64,
+        completed: bool,
+    }
+
+    impl<const ADD_TIMER: bool, const PATCHED: bool> Workflow for StepsWorkflow<ADD_TIMER, PATCHED> {
+        const TYPE: &'static str = "steps_workflow";
+        type Input = Value;
+        type Output = Value;
+
+        fn new(input: Self::Input) -> Self {
+            Self {
+                steps: input["steps"].as_u64().unwrap_or(1),
+                done: 0,
+                completed: false,
+            }
+        }
+
+        fn on_start(&mut self) -> Vec<WorkflowAction> {
+            vec![WorkflowAction::schedule_activity(
+                "step-0",
+                "step",
+                json!({}),
+            )]
+        }
+
+        fn on_activity_completed(
+            &mut self,
+            _activity_id: &str,
+            _result: Value,
+        ) -> Vec<WorkflowAction> {
+            self.done += 1;
+            if self.done < self.steps {
+                return vec![WorkflowAction::schedule_activity(
+                    format!("step-{}", self.done),
+                    "step",
+                    json!({}),
+                )];
+            }
+
+            let wait = ADD_TIMER && (!PATCHED || self.patched("wait-before-complete"));
+            if wait {
+                vec![WorkflowAction::timer("wait", Duration::from_secs(1))]
+            } else {
+                self.completed = true;
+                vec![WorkflowAction::complete(json!({ "done": self.done }))]
+            }
+        }
+
+        fn on_activity_failed(
+            &mut self,
+            _activity_id: &str,
+            error: &ActivityError,
+        ) -> Vec<WorkflowAction> {
+            vec![WorkflowAction::fail(crate::WorkflowError::new(
+                &error.message,
+            ))]
+        }
+
+        fn on_timer_fired(&mut self, _timer_id: &str) -> Vec<WorkflowAction> {
+            self.completed = true;
+            vec![WorkflowAction::complete(json!({ "done": self.done }))]
+        }
+
+        fn is_completed(&self) -> bool {
+            self.completed
+        }
+
+        fn result(&self) -> Option<Self::Output> {
+            self.completed.then(|| json!({ "done": self.done }))
+        }
+
+        fn query(&self, query_type: &str, args: Value) -> Result<Value, crate::WorkflowError> {
+            match query_type {
+                "progress" => Ok(json!({ "done": self.done, "steps": self.steps })),
+                _ => Err(crate::WorkflowError::new(format!(
+                    "unknown query: {}",
+                    args
+                ))),
+            }
+        }
+    }
+
+    type Original = StepsWorkflow<false, false>;
+    type Unguarded = StepsWorkflow<true, false>;
+    type Patched = StepsWorkflow<true, true>;
+
+    fn scheduled(step: u64) -> WorkflowEvent {
+        WorkflowEvent::ActivityScheduled {
+            activity_id: format!("step-{}", step),
+            activity_type: "step".to_string(),
+            input: json!({}),
+            options: ActivityOptions::default(),
+        }
+    }
+
+    fn completed(step: u64) -> WorkflowEvent {
+        WorkflowEvent::ActivityCompleted {
+            activity_id: format!("step-{}", step),
+            result: json!({}),
+        }
+    }
+
+    /// History of a finished two-step run of the original code
+    fn finished_history() -> Vec<WorkflowEvent> {
+        vec![
+            WorkflowEvent::WorkflowStarted {
+                input: json!({ "steps": 2 }),
+            },
+            scheduled(0),
+            completed(0),
+            scheduled(1),
+            completed(1),
+            WorkflowEvent::WorkflowCompleted {
+                result: json!({ "done": 2 }),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_replay_matches_recorded_history() {
+        let outcome = replay_history::<Original>(&finished_history()).unwrap();
+        assert!(outcome.pending_actions.is_empty());
+        assert!(outcome.version_markers.is_empty());
+    }
+
+    #[test]
+    fn test_replay_returns_actions_for_new_events() {
+        let mut history = finished_history();
+        history.truncate(3);
+
+        let outcome = replay_history::<Original>(&history).unwrap();
+        assert_eq!(outcome.pending_actions.len(), 1);
+        assert!(matches!(
+            &outcome.pending_actions[0],
+            WorkflowAction::ScheduleActivity { activity_id, .. } if activity_id == "step-1"
+        ));
+    }
+
+    #[test]
+    fn test_replay_detects_changed_actions() {
+        let error = match replay_history::<Unguarded>(&finished_history()) {
+            Err(ExecutorError::NonDeterminism(error)) => error,
+            other => panic!("expected non-determinism, got {:?}", other),
+        };
+
+        assert_eq!(error.sequence, 5);
+        assert!(error.reason.contains("workflow completion"));
+        assert!(error.reason.contains("timer 'wait'"));
+    }
+
+    #[test]
+    fn test_replay_detects_missing_actions() {
+        let mut history = finished_history();
+        history.truncate(2);
+        history.push(WorkflowEvent::TimerStarted {
+            timer_id: "wait".to_string(),
+            duration_ms: 1000,
+        });
+
+        let error = replay_history::<Original>(&history).unwrap_err();
+        assert!(error.to_string().contains("returned nothing"));
+    }
+
+    #[test]
+    fn test_patched_workflow_replays_old_and_new_histories() {
+        // Histories from before the change keep completing directly
+        let outcome = replay_history::<Patched>(&finished_history()).unwrap();
+        assert!(outcome.pending_actions.is_empty());
+
+        // New executions record a marker and wait on the timer
+        let mut history = finished_history();
+        history.truncate(5);
+        let outcome = replay_history::<Patched>(&history).unwrap();
+        assert_eq!(
+            outcome.version_markers,
+            vec![WorkflowEvent::VersionMarker {
+                change_id: "wait-before-complete".to_string(),
+                version: 1,
+            }]
+        );
+        assert!(matches!(
+            outcome.pending_actions.as_slice(),
+            [WorkflowAction::StartTimer { .. }]
+        ));
+
+        // and replay the recorded marker
+        history.extend(outcome.version_markers);
+        history.push(WorkflowEvent::TimerStarted {
+            timer_id: "wait".to_string(),
+            duration_ms: 1000,
+        });
+        history.push(WorkflowEvent::TimerFired {
+            timer_id: "wait".to_string(),
+        });
+        let outcome = replay_history::<Patched>(&history).unwrap();
+        assert!(matches!(
+            outcome.pending_actions.as_slice(),
+            [WorkflowAction::CompleteWorkflow { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_query_history() {
+        let mut registry = WorkflowRegistry::new();
+        registry.register::<Original>();
+        let events: Vec<(i32, WorkflowEvent)> = finished_history()
+            .into_iter()
+            .take(3)
+            .enumerate()
+            .map(|(sequence, event)| (sequence as i32, event))
+            .collect();
+
+        let progress =
+            query_history(&registry, Original::TYPE, &events, "progress", Value::Null).unwrap();
+        assert_eq!(progress, json!({ "done": 1, "steps": 2 }));
+
+        let error =
+            query_history(&registry, Original::TYPE, &events, "missing", Value::Null).unwrap_err();
+        assert!(matches!(error, ExecutorError::QueryFailed(_)));
+    }
+
+    #[test]
+    fn test_unsupported_version_fails_replay() {
+        let mut history = finished_history();
+        history.insert(
+            5,
+            WorkflowEvent::VersionMarker {
+                change_id: "wait-before-complete".to_string(),
+                version: DEFAULT_VERSION + 2,
+            },
+        );
+
+        let error = replay_history::<Patched>(&history).unwrap_err();
+        assert!(error.to_string().contains("wait-before-complete"));
+    }
+}
@@ -64,6 +64,7 @@
 //! ```
 
 pub mod activity;
+pub mod clock;
 pub mod engine;
 pub mod persistence;
 pub mod reliability;
@@ -89,14 +90,23 @@ pub mod prelude {
 
 // Re-export key types at crate root
 pub use activity::{Activity, ActivityContext, ActivityError};
-pub use engine::{ExecutorConfig, ExecutorError, WorkflowExecutor, WorkflowRegistry};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use engine::{
+    query_history, replay_history, ExecutorConfig, ExecutorError, NonDeterminismError,
+    ReplayOutcome, WorkflowExecutor, WorkflowRegistry,
+};
 pub use persistence::{
-    ClaimedTask, HeartbeatResponse, InMemoryWorkflowEventStore, PostgresWorkflowEventStore,
-    StoreError, TaskDefinition, TaskFailureOutcome, TraceContext, WorkflowEventStore, WorkflowInfo,
-    WorkflowStatus,
+    ClaimedTask, DueTimer, ExpiredWorkflow, FairnessConfig, HeartbeatResponse,
+    InMemoryWorkflowEventStore, MonthlyPartition, PostgresWorkflowEventStore, StoreError,
+    TaskDefinition, TaskFailureOutcome, TraceContext, WorkflowEventStore, WorkflowInfo,
+    WorkflowStatus, PARTITIONED_TABLES,
+};
+pub use reliability::{
+    CircuitBreakerConfig, RateLimitConfig, RateLimitDecision, RateLimitObservation, RetryPolicy,
+    TimedOutTask, TimeoutManager,
 };
-pub use reliability::{CircuitBreakerConfig, RetryPolicy};
 pub use worker::{WorkerPool, WorkerPoolConfig, WorkerPoolError};
 pub use workflow::{
-    ActivityOptions, Workflow, WorkflowAction, WorkflowError, WorkflowEvent, WorkflowSignal,
+    get_version, ActivityOptions, Workflow, WorkflowAction, WorkflowError, WorkflowEvent,
+    WorkflowSignal, DEFAULT_TASK_QUEUE, DEFAULT_VERSION, UNKNOWN_QUERY,
 };
@@ -0,0 +1,277 @@
+//! Fair scheduling of task claims
+//!
+//! Tasks are claimed by priority, then in visibility order. Tasks may carry a
+//! fairness key (e.g. `session:<id>` or `agent:<id>`, see
+//! [`ActivityOptions::fairness_key`](crate::ActivityOptions::fairness_key)).
+//! Among tasks of the same priority, claims take turns between keys, so one
+//! session fanning out many tool calls cannot starve the others.
+//! [`FairnessConfig`] additionally caps the number of claimed (running) tasks
+//! per key and per activity type.
+//!
+//! A claim does not rank every pending task: it reads at most
+//! [`scan_limit`] tasks from the head of each (task queue, activity type),
+//! skipping keys and activity types that are already at their limit, and
+//! selects among those.
+//!
+//! Both stores apply the same rules; [`select_fair`] is the reference
+//! implementation, used by the in-memory store and mirrored in SQL by the
+//! PostgreSQL store.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+/// Concurrency limits applied when claiming tasks
+///
+/// # Example
+///
+/// ```
+/// use everruns_durable::persistence::FairnessConfig;
+///
+/// // At most 4 running tasks per session, 20 concurrent LLM calls overall
+/// let config = FairnessConfig::new()
+///     .with_max_concurrent_per_key(4)
+///     .with_max_concurrent_for_activity("reason", 20);
+/// assert!(config.has_limits());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FairnessConfig {
+    /// Maximum claimed tasks per fairness key (tasks without a key are not
+    /// limited)
+    pub max_concurrent_per_key: Option<u32>,
+    /// Maximum claimed tasks per activity type, across all workers
+    pub max_concurrent_per_activity_type: HashMap<String, u32>,
+}
+
+impl FairnessConfig {
+    /// No limits: fair ordering only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit claimed tasks per fairness key
+    pub fn with_max_concurrent_per_key(mut self, max: u32) -> Self {
+        self.max_concurrent_per_key = Some(max);
+        self
+    }
+
+    /// Limit claimed tasks of an activity type
+    pub fn with_max_concurrent_for_activity(
+        mut self,
+        activity_type: impl Into<String>,
+        max: u32,
+    ) -> Self {
+        self.max_concurrent_per_activity_type
+            .insert(activity_type.into(), max);
+        self
+    }
+
+    /// Whether any concurrency limit is configured
+    pub fn has_limits(&self) -> bool {
+        self.max_concurrent_per_key.is_some() || !self.max_concurrent_per_activity_type.is_empty()
+    }
+}
+
+/// A claimable task
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Candidate<'a> {
+    pub id: Uuid,
+    pub priority: i32,
+    pub fairness_key: Option<&'a str>,
+    pub activity_type: &'a str,
+}
+
+/// Claimed (running) task counts
+#[derive(Debug, Default)]
+pub(crate) struct RunningCounts<'a> {
+    pub by_key: HashMap<&'a str, u32>,
+    pub by_activity_type: HashMap<&'a str, u32>,
+}
+
+impl RunningCounts<'_> {
+    /// Whether tasks with this key or activity type cannot be claimed now
+    pub fn at_limit(
+        &self,
+        fairness_key: Option<&str>,
+        activity_type: &str,
+        config: &FairnessConfig,
+    ) -> bool {
+        let key_full = match (fairness_key, config.max_concurrent_per_key) {
+            (Some(key), Some(max)) => self.by_key.get(key).copied().unwrap_or(0) >= max,
+            _ => false,
+        };
+        let type_full = config
+            .max_concurrent_per_activity_type
+            .get(activity_type)
+            .is_some_and(|&max| {
+                self.by_activity_type
+                    .get(activity_type)
+                    .copied()
+                    .unwrap_or(0)
+                    >= max
+            });
+        key_full || type_full
+    }
+}
+
+/// Pending tasks a claim of `max_tasks` reads per (task queue, activity type)
+pub(crate) fn scan_limit(max_tasks: usize) -> usize {
+    const SCAN_PER_TASK: usize = 10;
+    const MIN_SCAN: usize = 100;
+    max_tasks.saturating_mul(SCAN_PER_TASK).max(MIN_SCAN)
+}
+
+/// Pick up to `max_tasks` candidates to claim
+///
+/// `candidates` must be in claim order (priority descending, then visible
+/// time). Each candidate is ranked within its key; a candidate is eligible if
+/// its key's running tasks plus its rank stay within the key limit, and then
+/// likewise for its activity type among the remaining candidates. Eligible
+/// candidates are taken by priority, then rank within their key (round-robin
+/// between keys), then original order.
+pub(crate) fn select_fair(
+    candidates: &[Candidate<'_>],
+    running: &RunningCounts<'_>,
+    config: &FairnessConfig,
+    max_tasks: usize,
+) -> Vec<Uuid> {
+    // Rank within key; tasks without a key share one bucket
+    let mut key_ranks: HashMap<Option<&str>, u32> = HashMap::new();
+    let mut ranked: Vec<(usize, u32)> = Vec::with_capacity(candidates.len());
+    for (i, c) in candidates.iter().enumerate() {
+        let rank = key_ranks.entry(c.fairness_key).or_default();
+        *rank += 1;
+        let within_key_limit = match (c.fairness_key, config.max_concurrent_per_key) {
+            (Some(key), Some(max)) => running.by_key.get(key).copied().unwrap_or(0) + *rank <= max,
+            _ => true,
+        };
+        if within_key_limit {
+            ranked.push((i, *rank));
+        }
+    }
+
+    // Rank within activity type among the tasks within their key limit
+    let mut type_ranks: HashMap<&str, u32> = HashMap::new();
+    ranked.retain(|&(i, _)| {
+        let activity_type = candidates[i].activity_type;
+        let rank = type_ranks.entry(activity_type).or_default();
+        *rank += 1;
+        match config.max_concurrent_per_activity_type.get(activity_type) {
+            Some(&max) => {
+                running
+                    .by_activity_type
+                    .get(activity_type)
+                    .copied()
+                    .unwrap_or(0)
+                    + *rank
+                    <= max
+            }
+            None => true,
+        }
+    });
+
+    ranked.sort_by_key(|&(i, key_rank)| (std::cmp::Reverse(candidates[i].priority), key_rank, i));
+    ranked
+        .into_iter()
+        .take(max_tasks)
+        .map(|(i, _)| candidates[i].id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates<'a>(tasks: &[(&'a str, &'a str)]) -> Vec<Candidate<'a>> {
+        tasks
+            .iter()
+            .map(|&(key, activity_type)| Candidate {
+                id: Uuid::now_v7(),
+                priority: 0,
+                fairness_key: (!key.is_empty()).then_some(key),
+                activity_type,
+            })
+            .collect()
+    }
+
+    fn keys<'a>(candidates: &[Candidate<'a>], ids: &[Uuid]) -> Vec<&'a str> {
+        ids.iter()
+            .map(|id| {
+                let c = candidates.iter().find(|c| c.id == *id).unwrap();
+                c.fairness_key.unwrap_or("")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_robin_between_keys() {
+        let tasks = candidates(&[
+            ("a", "act"),
+            ("a", "act"),
+            ("a", "act"),
+            ("b", "act"),
+            ("c", "act"),
+            ("b", "act"),
+        ]);
+
+        let ids = select_fair(&tasks, &RunningCounts::default(), &FairnessConfig::new(), 4);
+        assert_eq!(keys(&tasks, &ids), vec!["a", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_priority_before_fairness() {
+        let mut tasks = candidates(&[("a", "act"), ("a", "act"), ("b", "act")]);
+        tasks[1].priority = 10;
+        tasks.sort_by_key(|c| std::cmp::Reverse(c.priority));
+
+        let ids = select_fair(&tasks, &RunningCounts::default(), &FairnessConfig::new(), 3);
+        assert_eq!(ids[0], tasks[0].id);
+        assert_eq!(keys(&tasks, &ids), vec!["a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_key_limit_counts_running_tasks() {
+        let tasks = candidates(&[("a", "act"), ("a", "act"), ("b", "act"), ("", "act")]);
+        let mut running = RunningCounts::default();
+        running.by_key.insert("a", 1);
+        let config = FairnessConfig::new().with_max_concurrent_per_key(2);
+
+        let ids = select_fair(&tasks, &running, &config, 10);
+        // One more "a" task fits; tasks without a key are not limited
+        assert_eq!(keys(&tasks, &ids), vec!["a", "b", ""]);
+    }
+
+    #[test]
+    fn test_activity_type_limit() {
+        let tasks = candidates(&[
+            ("a", "reason"),
+            ("b", "reason"),
+            ("c", "reason"),
+            ("a", "act"),
+        ]);
+        let mut running = RunningCounts::default();
+        running.by_activity_type.insert("reason", 1);
+        let config = FairnessConfig::new().with_max_concurrent_for_activity("reason", 3);
+
+        let ids = select_fair(&tasks, &running, &config, 10);
+        assert_eq!(ids.len(), 3);
+        assert!(!ids.contains(&tasks[2].id));
+        assert!(ids.contains(&tasks[3].id));
+    }
+
+    #[test]
+    fn test_at_limit() {
+        let mut running = RunningCounts::default();
+        running.by_key.insert("a", 2);
+        running.by_activity_type.insert("reason", 3);
+        let config = FairnessConfig::new()
+            .with_max_concurrent_per_key(2)
+            .with_max_concurrent_for_activity("reason", 3);
+
+        assert!(running.at_limit(Some("a"), "act", &config));
+        assert!(running.at_limit(Some("b"), "reason", &config));
+        assert!(!running.at_limit(Some("b"), "act", &config));
+        assert!(!running.at_limit(None, "act", &config));
+        assert!(!running.at_limit(Some("a"), "act", &FairnessConfig::new()));
+    }
+}
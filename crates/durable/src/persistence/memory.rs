@@ -2,14 +2,21 @@
 
 use std::collections::HashMap;
 use std::sync::atomic::AtomicI32;
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use uuid::Uuid;
 
+use super::fairness::{scan_limit, select_fair, Candidate, FairnessConfig, RunningCounts};
 use super::store::*;
+use crate::clock::{Clock, SystemClock};
+use crate::reliability::{
+    RateLimitConfig, RateLimitDecision, RateLimitObservation, TaskTimingInfo, TimeoutConfig,
+    TokenBucketState,
+};
 use crate::workflow::{WorkflowError, WorkflowEvent, WorkflowSignal};
 
 /// Internal workflow state
@@ -20,8 +27,14 @@ struct WorkflowState {
     input: serde_json::Value,
     result: Option<serde_json::Value>,
     error: Option<WorkflowError>,
-    events: Vec<WorkflowEvent>,
+    /// Events with the time they were recorded
+    events: Vec<(WorkflowEvent, DateTime<Utc>)>,
     signals: Vec<WorkflowSignal>,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    run_number: i32,
+    previous_run_id: Option<Uuid>,
+    continued_as: Option<Uuid>,
 }
 
 /// Internal task state
@@ -32,6 +45,36 @@ struct TaskState {
     claimed_by: Option<String>,
     last_error: Option<String>,
     error_history: Vec<String>,
+    visible_at: DateTime<Utc>,
+    claimed_at: Option<DateTime<Utc>>,
+    heartbeat_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl TaskState {
+    fn pending(definition: TaskDefinition, now: DateTime<Utc>) -> Self {
+        Self {
+            definition,
+            status: TaskStatus::Pending,
+            attempt: 0,
+            claimed_by: None,
+            last_error: None,
+            error_history: vec![],
+            visible_at: now,
+            claimed_at: None,
+            heartbeat_at: None,
+            created_at: now,
+        }
+    }
+
+    /// Put the task back in the queue, visible after `delay`
+    fn requeue(&mut self, now: DateTime<Utc>, delay: Duration) {
+        self.status = TaskStatus::Pending;
+        self.claimed_by = None;
+        self.claimed_at = None;
+        self.heartbeat_at = None;
+        self.visible_at = now + chrono::Duration::from_std(delay).unwrap_or_default();
+    }
 }
 
 /// Circuit breaker state in memory
@@ -45,7 +88,9 @@ struct CircuitBreakerMemState {
 /// In-memory implementation of WorkflowEventStore
 ///
 /// This is primarily for testing. It stores all data in memory and
-/// provides the same semantics as the PostgreSQL implementation.
+/// provides the same semantics as the PostgreSQL implementation, except that
+/// claiming ignores task visibility (retry delays). Task timestamps come from
+/// a [`Clock`], so timeouts can be tested with a [`ManualClock`](crate::ManualClock).
 ///
 /// # Example
 ///
@@ -59,22 +104,39 @@ pub struct InMemoryWorkflowEventStore {
     tasks: RwLock<HashMap<Uuid, TaskState>>,
     dlq: RwLock<HashMap<Uuid, DlqEntry>>,
     circuit_breakers: RwLock<HashMap<String, CircuitBreakerMemState>>,
+    rate_limiters: RwLock<HashMap<String, TokenBucketState>>,
     #[allow(dead_code)] // Reserved for future global sequence counter
     sequence_counter: AtomicI32,
+    clock: Arc<dyn Clock>,
+    fairness: FairnessConfig,
 }
 
 impl InMemoryWorkflowEventStore {
     /// Create a new in-memory store
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// Create a new in-memory store that reads the time from `clock`
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
         Self {
             workflows: RwLock::new(HashMap::new()),
             tasks: RwLock::new(HashMap::new()),
             dlq: RwLock::new(HashMap::new()),
             circuit_breakers: RwLock::new(HashMap::new()),
+            rate_limiters: RwLock::new(HashMap::new()),
             sequence_counter: AtomicI32::new(0),
+            clock,
+            fairness: FairnessConfig::default(),
         }
     }
 
+    /// Apply concurrency limits when claiming tasks
+    pub fn with_fairness(mut self, fairness: FairnessConfig) -> Self {
+        self.fairness = fairness;
+        self
+    }
+
     /// Get the number of workflows
     pub fn workflow_count(&self) -> usize {
         self.workflows.read().len()
@@ -128,6 +190,11 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
                 error: None,
                 events: vec![],
                 signals: vec![],
+                created_at: self.clock.now(),
+                completed_at: None,
+                run_number: 1,
+                previous_run_id: None,
+                continued_as: None,
             },
         );
         Ok(())
@@ -154,6 +221,9 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
             input: workflow.input.clone(),
             result: workflow.result.clone(),
             error: workflow.error.clone(),
+            run_number: workflow.run_number,
+            previous_run_id: workflow.previous_run_id,
+            continued_as: workflow.continued_as,
         })
     }
 
@@ -176,7 +246,10 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
             });
         }
 
-        workflow.events.extend(events);
+        let now = self.clock.now();
+        workflow
+            .events
+            .extend(events.into_iter().map(|event| (event, now)));
         Ok(workflow.events.len() as i32)
     }
 
@@ -193,7 +266,7 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
             .events
             .iter()
             .enumerate()
-            .map(|(i, e)| (i as i32, e.clone()))
+            .map(|(i, (e, _))| (i as i32, e.clone()))
             .collect())
     }
 
@@ -212,58 +285,186 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         workflow.status = status;
         workflow.result = result;
         workflow.error = error;
+        if status.is_terminal() {
+            workflow.completed_at.get_or_insert(self.clock.now());
+        }
         Ok(())
     }
 
+    async fn continue_as_new(
+        &self,
+        workflow_id: Uuid,
+        expected_sequence: i32,
+        input: serde_json::Value,
+    ) -> Result<Uuid, StoreError> {
+        let mut workflows = self.workflows.write();
+        let workflow = workflows
+            .get_mut(&workflow_id)
+            .ok_or(StoreError::WorkflowNotFound(workflow_id))?;
+
+        if workflow.status.is_terminal() {
+            return Err(StoreError::WorkflowClosed(workflow_id));
+        }
+        let current_sequence = workflow.events.len() as i32;
+        if current_sequence != expected_sequence {
+            return Err(StoreError::ConcurrencyConflict {
+                expected: expected_sequence,
+                actual: current_sequence,
+            });
+        }
+
+        let now = self.clock.now();
+        let run_id = Uuid::now_v7();
+        let mut events = std::mem::replace(
+            &mut workflow.events,
+            vec![(
+                WorkflowEvent::WorkflowStarted {
+                    input: input.clone(),
+                },
+                now,
+            )],
+        );
+        events.push((
+            WorkflowEvent::WorkflowContinuedAsNew {
+                input: input.clone(),
+            },
+            now,
+        ));
+
+        let closed_run = WorkflowState {
+            workflow_type: workflow.workflow_type.clone(),
+            status: WorkflowStatus::Completed,
+            input: std::mem::replace(&mut workflow.input, input),
+            result: None,
+            error: None,
+            events,
+            signals: vec![],
+            created_at: workflow.created_at,
+            completed_at: Some(now),
+            run_number: workflow.run_number,
+            previous_run_id: workflow.previous_run_id,
+            continued_as: Some(workflow_id),
+        };
+
+        workflow.status = WorkflowStatus::Running;
+        workflow.result = None;
+        workflow.error = None;
+        workflow.run_number += 1;
+        workflow.previous_run_id = Some(run_id);
+
+        workflows.insert(run_id, closed_run);
+
+        // Cancel the finished run's outstanding tasks so their results do
+        // not reach the next run
+        for task in self.tasks.write().values_mut() {
+            if task.definition.workflow_id == workflow_id
+                && matches!(task.status, TaskStatus::Pending | TaskStatus::Claimed)
+            {
+                task.status = TaskStatus::Cancelled;
+            }
+        }
+        Ok(run_id)
+    }
+
     async fn enqueue_task(&self, task: TaskDefinition) -> Result<Uuid, StoreError> {
         let task_id = Uuid::now_v7();
         let mut tasks = self.tasks.write();
-        tasks.insert(
-            task_id,
-            TaskState {
-                definition: task,
-                status: TaskStatus::Pending,
-                attempt: 0,
-                claimed_by: None,
-                last_error: None,
-                error_history: vec![],
-            },
-        );
+        tasks.insert(task_id, TaskState::pending(task, self.clock.now()));
         Ok(task_id)
     }
 
-    async fn claim_task(
+    async fn claim_task_from_queues(
         &self,
         worker_id: &str,
+        task_queues: &[String],
         activity_types: &[String],
         max_tasks: usize,
     ) -> Result<Vec<ClaimedTask>, StoreError> {
         let mut tasks = self.tasks.write();
-        let mut claimed = vec![];
+        let now = self.clock.now();
 
-        for (task_id, task) in tasks.iter_mut() {
-            if claimed.len() >= max_tasks {
-                break;
+        let selected = {
+            let mut running = RunningCounts::default();
+            for task in tasks.values().filter(|t| t.status == TaskStatus::Claimed) {
+                if let Some(key) = task.definition.options.fairness_key.as_deref() {
+                    *running.by_key.entry(key).or_default() += 1;
+                }
+                *running
+                    .by_activity_type
+                    .entry(task.definition.activity_type.as_str())
+                    .or_default() += 1;
             }
 
-            if task.status == TaskStatus::Pending
-                && activity_types.contains(&task.definition.activity_type)
-            {
-                task.status = TaskStatus::Claimed;
-                task.claimed_by = Some(worker_id.to_string());
-                task.attempt += 1;
-
-                claimed.push(ClaimedTask {
-                    id: *task_id,
-                    workflow_id: task.definition.workflow_id,
-                    activity_id: task.definition.activity_id.clone(),
-                    activity_type: task.definition.activity_type.clone(),
-                    input: task.definition.input.clone(),
-                    options: task.definition.options.clone(),
-                    attempt: task.attempt,
-                    max_attempts: task.definition.options.retry_policy.max_attempts,
-                });
-            }
+            let mut pending: Vec<(&Uuid, &TaskState)> = tasks
+                .iter()
+                .filter(|(_, task)| {
+                    task.status == TaskStatus::Pending
+                        && task.visible_at <= now
+                        && task_queues.contains(&task.definition.options.task_queue)
+                        && activity_types.contains(&task.definition.activity_type)
+                        && !running.at_limit(
+                            task.definition.options.fairness_key.as_deref(),
+                            &task.definition.activity_type,
+                            &self.fairness,
+                        )
+                })
+                .collect();
+            pending.sort_by_key(|(id, task)| {
+                (
+                    std::cmp::Reverse(task.definition.options.priority),
+                    task.visible_at,
+                    **id,
+                )
+            });
+
+            // Read only the head of each (task queue, activity type)
+            let limit = scan_limit(max_tasks);
+            let mut scanned: HashMap<(&str, &str), usize> = HashMap::new();
+            pending.retain(|(_, task)| {
+                let count = scanned
+                    .entry((
+                        task.definition.options.task_queue.as_str(),
+                        task.definition.activity_type.as_str(),
+                    ))
+                    .or_default();
+                *count += 1;
+                *count <= limit
+            });
+
+            let candidates: Vec<Candidate<'_>> = pending
+                .iter()
+                .map(|(id, task)| Candidate {
+                    id: **id,
+                    priority: task.definition.options.priority,
+                    fairness_key: task.definition.options.fairness_key.as_deref(),
+                    activity_type: &task.definition.activity_type,
+                })
+                .collect();
+
+            select_fair(&candidates, &running, &self.fairness, max_tasks)
+        };
+
+        let mut claimed = Vec::with_capacity(selected.len());
+        for task_id in selected {
+            let Some(task) = tasks.get_mut(&task_id) else {
+                continue;
+            };
+            task.status = TaskStatus::Claimed;
+            task.claimed_by = Some(worker_id.to_string());
+            task.claimed_at = Some(now);
+            task.heartbeat_at = Some(now);
+            task.attempt += 1;
+
+            claimed.push(ClaimedTask {
+                id: task_id,
+                workflow_id: task.definition.workflow_id,
+                activity_id: task.definition.activity_id.clone(),
+                activity_type: task.definition.activity_type.clone(),
+                input: task.definition.input.clone(),
+                options: task.definition.options.clone(),
+                attempt: task.attempt,
+                max_attempts: task.definition.options.retry_policy.max_attempts,
+            });
         }
 
         Ok(claimed)
@@ -275,14 +476,17 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         _worker_id: &str,
         _details: Option<serde_json::Value>,
     ) -> Result<HeartbeatResponse, StoreError> {
-        let tasks = self.tasks.read();
-        if !tasks.contains_key(&task_id) {
-            return Err(StoreError::TaskNotFound(task_id));
+        let mut tasks = self.tasks.write();
+        let task = tasks
+            .get_mut(&task_id)
+            .ok_or(StoreError::TaskNotFound(task_id))?;
+        if task.status == TaskStatus::Claimed {
+            task.heartbeat_at = Some(self.clock.now());
         }
 
         Ok(HeartbeatResponse {
             accepted: true,
-            should_cancel: false,
+            should_cancel: task.status == TaskStatus::Cancelled,
         })
     }
 
@@ -296,10 +500,53 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
             .get_mut(&task_id)
             .ok_or(StoreError::TaskNotFound(task_id))?;
 
-        task.status = TaskStatus::Completed;
+        if task.status != TaskStatus::Cancelled {
+            task.status = TaskStatus::Completed;
+        }
         Ok(())
     }
 
+    async fn complete_and_enqueue(
+        &self,
+        task_id: Uuid,
+        _result: serde_json::Value,
+        next: Option<TaskDefinition>,
+    ) -> Result<bool, StoreError> {
+        let mut tasks = self.tasks.write();
+        let task = tasks
+            .get_mut(&task_id)
+            .ok_or(StoreError::TaskNotFound(task_id))?;
+
+        if task.status == TaskStatus::Cancelled {
+            return Ok(false);
+        }
+        task.status = TaskStatus::Completed;
+        if let Some(next) = next {
+            tasks.insert(Uuid::now_v7(), TaskState::pending(next, self.clock.now()));
+        }
+        Ok(true)
+    }
+
+    async fn cancel_activity(
+        &self,
+        workflow_id: Uuid,
+        activity_id: &str,
+    ) -> Result<u64, StoreError> {
+        let chained = format!("{}/", activity_id);
+        let mut cancelled = 0;
+        for task in self.tasks.write().values_mut() {
+            if task.definition.workflow_id == workflow_id
+                && (task.definition.activity_id == activity_id
+                    || task.definition.activity_id.starts_with(&chained))
+                && matches!(task.status, TaskStatus::Pending | TaskStatus::Claimed)
+            {
+                task.status = TaskStatus::Cancelled;
+                cancelled += 1;
+            }
+        }
+        Ok(cancelled)
+    }
+
     async fn fail_task(
         &self,
         task_id: Uuid,
@@ -313,17 +560,20 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         task.error_history.push(error.to_string());
         task.last_error = Some(error.to_string());
 
+        // A cancelled task is never retried
+        if task.status == TaskStatus::Cancelled {
+            return Ok(TaskFailureOutcome::ExhaustedRetries);
+        }
+
         let max_attempts = task.definition.options.retry_policy.max_attempts;
         if task.attempt < max_attempts {
             // Requeue for retry
-            task.status = TaskStatus::Pending;
-            task.claimed_by = None;
-
             let delay = task
                 .definition
                 .options
                 .retry_policy
                 .delay_for_attempt(task.attempt + 1);
+            task.requeue(self.clock.now(), delay);
 
             Ok(TaskFailureOutcome::WillRetry {
                 next_attempt: task.attempt + 1,
@@ -344,6 +594,75 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         Ok(vec![])
     }
 
+    async fn list_timeout_candidates(
+        &self,
+        _now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TaskTimingInfo>, StoreError> {
+        // Return every active task; the caller checks the timeouts
+        let tasks = self.tasks.read();
+        Ok(tasks
+            .iter()
+            .filter(|(_, t)| matches!(t.status, TaskStatus::Pending | TaskStatus::Claimed))
+            .take(limit)
+            .map(|(task_id, t)| TaskTimingInfo {
+                task_id: *task_id,
+                workflow_id: t.definition.workflow_id,
+                activity_id: t.definition.activity_id.clone(),
+                attempt: t.attempt,
+                scheduled_at: t.visible_at,
+                started_at: t.claimed_at,
+                last_heartbeat_at: t.heartbeat_at,
+                timeout_config: TimeoutConfig::from(&t.definition.options),
+            })
+            .collect())
+    }
+
+    async fn fail_timed_out_task(
+        &self,
+        timing: &TaskTimingInfo,
+        error: &str,
+    ) -> Result<Option<TaskFailureOutcome>, StoreError> {
+        let expected_status = if timing.started_at.is_some() {
+            TaskStatus::Claimed
+        } else {
+            TaskStatus::Pending
+        };
+
+        let mut tasks = self.tasks.write();
+        let Some(task) = tasks
+            .get_mut(&timing.task_id)
+            .filter(|t| t.status == expected_status && t.attempt == timing.attempt)
+        else {
+            return Ok(None);
+        };
+
+        task.error_history.push(error.to_string());
+        task.last_error = Some(error.to_string());
+        if expected_status == TaskStatus::Pending {
+            // A task that never started still used up the attempt it was waiting for
+            task.attempt += 1;
+        }
+
+        let max_attempts = task.definition.options.retry_policy.max_attempts;
+        if task.attempt < max_attempts {
+            let delay = task
+                .definition
+                .options
+                .retry_policy
+                .delay_for_attempt(task.attempt + 1);
+            task.requeue(self.clock.now(), delay);
+
+            Ok(Some(TaskFailureOutcome::WillRetry {
+                next_attempt: task.attempt + 1,
+                delay,
+            }))
+        } else {
+            task.status = TaskStatus::Dead;
+            Ok(Some(TaskFailureOutcome::MovedToDlq))
+        }
+    }
+
     async fn send_signal(
         &self,
         workflow_id: Uuid,
@@ -358,6 +677,53 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         Ok(())
     }
 
+    async fn signal_with_start(
+        &self,
+        workflow_id: Uuid,
+        workflow_type: &str,
+        input: serde_json::Value,
+        signal: WorkflowSignal,
+        _trace_context: Option<&TraceContext>,
+    ) -> Result<bool, StoreError> {
+        let mut workflows = self.workflows.write();
+        if let Some(workflow) = workflows.get_mut(&workflow_id) {
+            if workflow.workflow_type != workflow_type {
+                return Err(StoreError::WorkflowTypeMismatch {
+                    workflow_id,
+                    expected: workflow_type.to_string(),
+                    actual: workflow.workflow_type.clone(),
+                });
+            }
+            if matches!(
+                workflow.status,
+                WorkflowStatus::Completed | WorkflowStatus::Failed | WorkflowStatus::Cancelled
+            ) {
+                return Err(StoreError::WorkflowClosed(workflow_id));
+            }
+            workflow.signals.push(signal);
+            return Ok(false);
+        }
+
+        workflows.insert(
+            workflow_id,
+            WorkflowState {
+                workflow_type: workflow_type.to_string(),
+                status: WorkflowStatus::Pending,
+                input: input.clone(),
+                result: None,
+                error: None,
+                events: vec![(WorkflowEvent::WorkflowStarted { input }, self.clock.now())],
+                signals: vec![signal],
+                created_at: self.clock.now(),
+                completed_at: None,
+                run_number: 1,
+                previous_run_id: None,
+                continued_as: None,
+            },
+        );
+        Ok(true)
+    }
+
     async fn get_pending_signals(
         &self,
         workflow_id: Uuid,
@@ -402,6 +768,7 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
             workflow_id: task.definition.workflow_id,
             activity_id: task.definition.activity_id.clone(),
             activity_type: task.definition.activity_type.clone(),
+            task_queue: task.definition.options.task_queue.clone(),
             input: task.definition.input.clone(),
             attempts: task.attempt,
             last_error: task.last_error.clone().unwrap_or_default(),
@@ -427,24 +794,20 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         let mut tasks = self.tasks.write();
 
         // We need to recreate options - use defaults for simplicity in test
-        let options = crate::workflow::ActivityOptions::default();
+        let options = crate::workflow::ActivityOptions::default().with_task_queue(entry.task_queue);
 
         tasks.insert(
             task_id,
-            TaskState {
-                definition: TaskDefinition {
+            TaskState::pending(
+                TaskDefinition {
                     workflow_id: entry.workflow_id,
                     activity_id: entry.activity_id,
                     activity_type: entry.activity_type,
                     input: entry.input,
                     options,
                 },
-                status: TaskStatus::Pending,
-                attempt: 0,
-                claimed_by: None,
-                last_error: None,
-                error_history: vec![],
-            },
+                self.clock.now(),
+            ),
         );
 
         Ok(task_id)
@@ -474,7 +837,7 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
             .cloned()
             .collect();
 
-        entries.sort_by(|a, b| b.dead_at.cmp(&a.dead_at));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.dead_at));
 
         let start = pagination.offset as usize;
         let end = (pagination.offset + pagination.limit) as usize;
@@ -563,6 +926,170 @@ impl WorkflowEventStore for InMemoryWorkflowEventStore {
         }
         Ok(())
     }
+
+    async fn acquire_rate_limit(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+        tokens: u32,
+    ) -> Result<RateLimitDecision, StoreError> {
+        let now = self.clock.now();
+        let mut limiters = self.rate_limiters.write();
+        let state = limiters
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucketState::new(now));
+        Ok(state.try_acquire(config, 1, tokens, now))
+    }
+
+    async fn observe_rate_limit(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+        observation: &RateLimitObservation,
+    ) -> Result<(), StoreError> {
+        let now = self.clock.now();
+        let mut limiters = self.rate_limiters.write();
+        limiters
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucketState::new(now))
+            .observe(config, observation, now);
+        Ok(())
+    }
+
+    async fn list_expired_workflows(
+        &self,
+        finished_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ExpiredWorkflow>, StoreError> {
+        let workflows = self.workflows.read();
+        let mut expired: Vec<ExpiredWorkflow> = workflows
+            .iter()
+            .filter_map(|(id, w)| {
+                let completed_at = w.completed_at.filter(|at| *at < finished_before)?;
+                w.status.is_terminal().then(|| ExpiredWorkflow {
+                    id: *id,
+                    workflow_type: w.workflow_type.clone(),
+                    status: w.status,
+                    created_at: w.created_at,
+                    completed_at,
+                })
+            })
+            .collect();
+        expired.sort_by_key(|w| (w.completed_at, w.id));
+        expired.truncate(limit);
+        Ok(expired)
+    }
+
+    async fn count_expired_workflows(
+        &self,
+        finished_before: DateTime<Utc>,
+    ) -> Result<u64, StoreError> {
+        let workflows = self.workflows.read();
+        Ok(workflows
+            .values()
+            .filter(|w| {
+                w.status.is_terminal() && w.completed_at.is_some_and(|at| at < finished_before)
+            })
+            .count() as u64)
+    }
+
+    async fn delete_workflows(&self, workflow_ids: &[Uuid]) -> Result<u64, StoreError> {
+        let mut workflows = self.workflows.write();
+        let deleted: Vec<Uuid> = workflow_ids
+            .iter()
+            .filter(|id| workflows.get(id).is_some_and(|w| w.status.is_terminal()))
+            .copied()
+            .collect();
+        for id in &deleted {
+            workflows.remove(id);
+        }
+        drop(workflows);
+
+        self.tasks
+            .write()
+            .retain(|_, task| !deleted.contains(&task.definition.workflow_id));
+        self.dlq
+            .write()
+            .retain(|_, entry| !deleted.contains(&entry.workflow_id));
+        Ok(deleted.len() as u64)
+    }
+
+    async fn count_expired_tasks(&self, created_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        let tasks = self.tasks.read();
+        Ok(tasks
+            .values()
+            .filter(|task| is_expired_task(task, created_before))
+            .count() as u64)
+    }
+
+    async fn delete_expired_tasks(
+        &self,
+        created_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, StoreError> {
+        let mut tasks = self.tasks.write();
+        let expired: Vec<Uuid> = tasks
+            .iter()
+            .filter(|(_, task)| is_expired_task(task, created_before))
+            .map(|(id, _)| *id)
+            .take(limit)
+            .collect();
+        for id in &expired {
+            tasks.remove(id);
+        }
+        Ok(expired.len() as u64)
+    }
+
+    async fn list_due_timers(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<DueTimer>, StoreError> {
+        let workflows = self.workflows.read();
+        let mut due: Vec<DueTimer> = workflows
+            .iter()
+            .filter(|(_, w)| matches!(w.status, WorkflowStatus::Pending | WorkflowStatus::Running))
+            .flat_map(|(workflow_id, w)| {
+                // Latest start per timer id, dropped once fired or cancelled
+                let mut started: HashMap<&str, DateTime<Utc>> = HashMap::new();
+                for (event, recorded_at) in &w.events {
+                    match event {
+                        WorkflowEvent::TimerStarted {
+                            timer_id,
+                            duration_ms,
+                        } => {
+                            let due_at =
+                                *recorded_at + chrono::Duration::milliseconds(*duration_ms as i64);
+                            started.insert(timer_id, due_at);
+                        }
+                        WorkflowEvent::TimerFired { timer_id }
+                        | WorkflowEvent::TimerCancelled { timer_id } => {
+                            started.remove(timer_id.as_str());
+                        }
+                        _ => {}
+                    }
+                }
+                started
+                    .into_iter()
+                    .filter(|(_, due_at)| *due_at <= now)
+                    .map(|(timer_id, due_at)| DueTimer {
+                        workflow_id: *workflow_id,
+                        timer_id: timer_id.to_string(),
+                        due_at,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        due.sort_by_key(|t| (t.due_at, t.workflow_id));
+        due.truncate(limit);
+        Ok(due)
+    }
+}
+
+/// Whether a task has finished and was created before the retention cutoff
+fn is_expired_task(task: &TaskState, created_before: DateTime<Utc>) -> bool {
+    !matches!(task.status, TaskStatus::Pending | TaskStatus::Claimed)
+        && task.created_at < created_before
 }
 
 #[cfg(test)]
@@ -633,6 +1160,226 @@ mod tests {
         assert_eq!(events.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_claim_task_from_queues() {
+        let store = InMemoryWorkflowEventStore::new();
+        let workflow_id = Uuid::now_v7();
+        store
+            .create_workflow(workflow_id, "test", serde_json::json!({}), None)
+            .await
+            .unwrap();
+
+        for task_queue in ["default", "gpu"] {
+            store
+                .enqueue_task(TaskDefinition {
+                    workflow_id,
+                    activity_id: task_queue.to_string(),
+                    activity_type: "test_activity".to_string(),
+                    input: serde_json::json!({}),
+                    options: ActivityOptions::default().with_task_queue(task_queue),
+                })
+                .await
+                .unwrap();
+        }
+
+        let activity_types = ["test_activity".to_string()];
+        let claimed = store
+            .claim_task_from_queues("gpu-worker", &["gpu".to_string()], &activity_types, 10)
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].activity_id, "gpu");
+
+        // claim_task serves the default queue
+        let claimed = store
+            .claim_task("worker", &activity_types, 10)
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].activity_id, "default");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_is_shared_per_key() {
+        let clock = Arc::new(crate::ManualClock::new());
+        let store = InMemoryWorkflowEventStore::with_clock(clock.clone());
+        let config = RateLimitConfig::new().with_requests_per_minute(2);
+
+        for _ in 0..2 {
+            let decision = store
+                .acquire_rate_limit("llm_provider:a", &config, 100)
+                .await
+                .unwrap();
+            assert_eq!(decision, RateLimitDecision::Acquired);
+        }
+        let decision = store
+            .acquire_rate_limit("llm_provider:a", &config, 100)
+            .await
+            .unwrap();
+        assert_eq!(decision, RateLimitDecision::Wait(Duration::from_secs(30)));
+
+        // Other keys have their own buckets
+        let decision = store
+            .acquire_rate_limit("llm_provider:b", &config, 100)
+            .await
+            .unwrap();
+        assert_eq!(decision, RateLimitDecision::Acquired);
+
+        // A 429 blocks the key past the refill
+        clock.advance(Duration::from_secs(30));
+        let observation = RateLimitObservation {
+            retry_after: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        store
+            .observe_rate_limit("llm_provider:a", &config, &observation)
+            .await
+            .unwrap();
+        let decision = store
+            .acquire_rate_limit("llm_provider:a", &config, 100)
+            .await
+            .unwrap();
+        assert_eq!(decision, RateLimitDecision::Wait(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn test_retention_deletes_finished_workflows_and_tasks() {
+        let clock = Arc::new(crate::ManualClock::new());
+        let store = InMemoryWorkflowEventStore::with_clock(clock.clone());
+        let finished = Uuid::now_v7();
+        let running = Uuid::now_v7();
+
+        let mut task_ids = vec![];
+        for workflow_id in [finished, running] {
+            store
+                .create_workflow(workflow_id, "test", serde_json::json!({}), None)
+                .await
+                .unwrap();
+            let task_id = store
+                .enqueue_task(TaskDefinition {
+                    workflow_id,
+                    activity_id: "step-1".to_string(),
+                    activity_type: "test_activity".to_string(),
+                    input: serde_json::json!({}),
+                    options: ActivityOptions::default(),
+                })
+                .await
+                .unwrap();
+            task_ids.push(task_id);
+        }
+        store
+            .complete_task(task_ids[1], serde_json::json!({}))
+            .await
+            .unwrap();
+        store
+            .update_workflow_status(finished, WorkflowStatus::Completed, None, None)
+            .await
+            .unwrap();
+
+        // Nothing has expired yet
+        let cutoff = clock.now();
+        assert_eq!(store.count_expired_workflows(cutoff).await.unwrap(), 0);
+        assert_eq!(store.count_expired_tasks(cutoff).await.unwrap(), 0);
+
+        clock.advance(Duration::from_secs(60));
+        let cutoff = clock.now();
+        let expired = store.list_expired_workflows(cutoff, 10).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, finished);
+        assert_eq!(expired[0].status, WorkflowStatus::Completed);
+
+        // Only terminal workflows are deleted, together with their tasks
+        let deleted = store.delete_workflows(&[finished, running]).await.unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(store.workflow_count(), 1);
+
+        assert_eq!(store.pending_task_count(), 0);
+
+        // Finished tasks of running workflows expire on their own
+        assert_eq!(store.count_expired_tasks(cutoff).await.unwrap(), 1);
+        let deleted = store.delete_expired_tasks(cutoff, 10).await.unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(store.count_expired_tasks(cutoff).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_claim_is_fair_and_limited_per_key() {
+        let store = InMemoryWorkflowEventStore::new()
+            .with_fairness(FairnessConfig::new().with_max_concurrent_per_key(2));
+        let workflow_id = Uuid::now_v7();
+        store
+            .create_workflow(workflow_id, "test", serde_json::json!({}), None)
+            .await
+            .unwrap();
+
+        // A busy session enqueues first
+        for (i, key) in ["a", "a", "a", "b"].into_iter().enumerate() {
+            store
+                .enqueue_task(TaskDefinition {
+                    workflow_id,
+                    activity_id: format!("{}-{}", key, i),
+                    activity_type: "test_activity".to_string(),
+                    input: serde_json::json!({}),
+                    options: ActivityOptions::default().with_fairness_key(key),
+                })
+                .await
+                .unwrap();
+        }
+
+        let activity_types = ["test_activity".to_string()];
+        let claimed = store.claim_task("w1", &activity_types, 2).await.unwrap();
+        let ids: Vec<_> = claimed.iter().map(|t| t.activity_id.as_str()).collect();
+        assert_eq!(ids, vec!["a-0", "b-3"]);
+
+        // "a" may run one more task, then it is at its limit
+        let claimed = store.claim_task("w2", &activity_types, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].activity_id, "a-1");
+        assert!(store
+            .claim_task("w3", &activity_types, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_claim_skips_keys_at_limit_beyond_scan_window() {
+        let store = InMemoryWorkflowEventStore::new()
+            .with_fairness(FairnessConfig::new().with_max_concurrent_per_key(1));
+        let workflow_id = Uuid::now_v7();
+        store
+            .create_workflow(workflow_id, "test", serde_json::json!({}), None)
+            .await
+            .unwrap();
+        let enqueue = |activity_id: String, key: &'static str| TaskDefinition {
+            workflow_id,
+            activity_id,
+            activity_type: "test_activity".to_string(),
+            input: serde_json::json!({}),
+            options: ActivityOptions::default().with_fairness_key(key),
+        };
+
+        // More tasks of the busy key than one claim reads
+        for i in 0..scan_limit(1) + 10 {
+            store
+                .enqueue_task(enqueue(format!("a-{}", i), "a"))
+                .await
+                .unwrap();
+        }
+        let activity_types = ["test_activity".to_string()];
+        let claimed = store.claim_task("w1", &activity_types, 1).await.unwrap();
+        assert_eq!(claimed[0].activity_id, "a-0");
+
+        // "a" is at its limit, so its queued tasks do not hide "b"
+        store
+            .enqueue_task(enqueue("b-0".to_string(), "b"))
+            .await
+            .unwrap();
+        let claimed = store.claim_task("w2", &activity_types, 1).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].activity_id, "b-0");
+    }
+
     #[tokio::test]
     async fn test_concurrency_conflict() {
         let store = InMemoryWorkflowEventStore::new();
@@ -660,6 +1407,71 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_continue_as_new() {
+        let store = InMemoryWorkflowEventStore::new();
+        let workflow_id = Uuid::now_v7();
+
+        store
+            .create_workflow(workflow_id, "test", serde_json::json!(1), None)
+            .await
+            .unwrap();
+        store
+            .append_events(
+                workflow_id,
+                0,
+                vec![WorkflowEvent::WorkflowStarted {
+                    input: serde_json::json!(1),
+                }],
+            )
+            .await
+            .unwrap();
+
+        let result = store
+            .continue_as_new(workflow_id, 0, serde_json::json!(2))
+            .await;
+        assert!(matches!(
+            result,
+            Err(StoreError::ConcurrencyConflict { .. })
+        ));
+
+        let run_id = store
+            .continue_as_new(workflow_id, 1, serde_json::json!(2))
+            .await
+            .unwrap();
+
+        let info = store.get_workflow_info(workflow_id).await.unwrap();
+        assert_eq!(info.status, WorkflowStatus::Running);
+        assert_eq!(info.input, serde_json::json!(2));
+        assert_eq!(info.run_number, 2);
+        assert_eq!(info.previous_run_id, Some(run_id));
+        assert_eq!(
+            store.load_events(workflow_id).await.unwrap(),
+            vec![(
+                0,
+                WorkflowEvent::WorkflowStarted {
+                    input: serde_json::json!(2)
+                }
+            )]
+        );
+
+        let closed = store.get_workflow_info(run_id).await.unwrap();
+        assert_eq!(closed.status, WorkflowStatus::Completed);
+        assert_eq!(closed.input, serde_json::json!(1));
+        assert_eq!(closed.run_number, 1);
+        assert_eq!(closed.continued_as, Some(workflow_id));
+        let events = store.load_events(run_id).await.unwrap();
+        assert_eq!(
+            events.last(),
+            Some(&(
+                1,
+                WorkflowEvent::WorkflowContinuedAsNew {
+                    input: serde_json::json!(2)
+                }
+            ))
+        );
+    }
+
     #[tokio::test]
     async fn test_task_lifecycle() {
         let store = InMemoryWorkflowEventStore::new();
@@ -702,6 +1514,67 @@ mod tests {
         assert_eq!(store.pending_task_count(), 0);
     }
 
+    #[tokio::test]
+    async fn test_cancel_activity_stops_chained_steps() {
+        let store = InMemoryWorkflowEventStore::new();
+        let workflow_id = Uuid::now_v7();
+        let task = |activity_id: &str| TaskDefinition {
+            workflow_id,
+            activity_id: activity_id.to_string(),
+            activity_type: "test_activity".to_string(),
+            input: serde_json::json!({}),
+            options: ActivityOptions::default(),
+        };
+
+        store
+            .create_workflow(workflow_id, "test", serde_json::json!({}), None)
+            .await
+            .unwrap();
+        store.enqueue_task(task("turn-1")).await.unwrap();
+        store.enqueue_task(task("turn-10")).await.unwrap();
+
+        let claimed = store
+            .claim_task("worker-1", &["test_activity".to_string()], 1)
+            .await
+            .unwrap();
+        assert!(store
+            .complete_and_enqueue(
+                claimed[0].id,
+                serde_json::json!({}),
+                Some(task("turn-1/step-1"))
+            )
+            .await
+            .unwrap());
+
+        // The chained step is cancelled with its activity; other activities
+        // sharing the prefix are not
+        assert_eq!(
+            store.cancel_activity(workflow_id, "turn-1").await.unwrap(),
+            1
+        );
+        let claimed = store
+            .claim_task("worker-1", &["test_activity".to_string()], 10)
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].activity_id, "turn-10");
+
+        // A cancelled step does not chain the next one
+        assert_eq!(
+            store.cancel_activity(workflow_id, "turn-10").await.unwrap(),
+            1
+        );
+        assert!(!store
+            .complete_and_enqueue(
+                claimed[0].id,
+                serde_json::json!({}),
+                Some(task("turn-10/step-1"))
+            )
+            .await
+            .unwrap());
+        assert_eq!(store.pending_task_count(), 0);
+    }
+
     #[tokio::test]
     async fn test_task_retry() {
         let store = InMemoryWorkflowEventStore::new();
@@ -765,4 +1638,72 @@ mod tests {
         let signals = store.get_pending_signals(workflow_id).await.unwrap();
         assert_eq!(signals.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_signal_with_start() {
+        let store = InMemoryWorkflowEventStore::new();
+        let workflow_id = Uuid::now_v7();
+        let input = serde_json::json!({ "session": 1 });
+
+        let created = store
+            .signal_with_start(
+                workflow_id,
+                "test",
+                input.clone(),
+                WorkflowSignal::custom("message", serde_json::json!({ "n": 1 })),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(created);
+
+        let created = store
+            .signal_with_start(
+                workflow_id,
+                "test",
+                input.clone(),
+                WorkflowSignal::custom("message", serde_json::json!({ "n": 2 })),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(!created);
+
+        let events = store.load_events(workflow_id).await.unwrap();
+        assert_eq!(events, vec![(0, WorkflowEvent::WorkflowStarted { input })]);
+        assert_eq!(
+            store.get_pending_signals(workflow_id).await.unwrap().len(),
+            2
+        );
+
+        store
+            .update_workflow_status(workflow_id, WorkflowStatus::Completed, None, None)
+            .await
+            .unwrap();
+        let result = store
+            .signal_with_start(
+                workflow_id,
+                "test",
+                serde_json::json!({}),
+                WorkflowSignal::custom("message", serde_json::json!({})),
+                None,
+            )
+            .await;
+        assert!(matches!(result, Err(StoreError::WorkflowClosed(_))));
+
+        let result = store
+            .signal_with_start(
+                workflow_id,
+                "other",
+                serde_json::json!({}),
+                WorkflowSignal::custom("message", serde_json::json!({})),
+                None,
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(StoreError::WorkflowTypeMismatch { expected, actual, .. })
+                if expected == "other" && actual == "test"
+        ));
+    }
 }
@@ -4,15 +4,21 @@
 //! - [`WorkflowEventStore`] trait for workflow and event persistence
 //! - [`InMemoryWorkflowEventStore`] for testing
 //! - [`PostgresWorkflowEventStore`] for production
+//! - [`FairnessConfig`] for fair scheduling of task claims
+//! - [`MonthlyPartition`] for maintaining the partitioned history tables
 
+mod fairness;
 mod memory;
+mod partitions;
 mod postgres;
 mod store;
 
+pub use fairness::FairnessConfig;
 pub use memory::InMemoryWorkflowEventStore;
+pub use partitions::{MonthlyPartition, PARTITIONED_TABLES};
 pub use postgres::PostgresWorkflowEventStore;
 pub use store::{
-    CircuitBreakerState, ClaimedTask, DlqEntry, DlqFilter, HeartbeatResponse, Pagination,
-    StoreError, TaskDefinition, TaskFailureOutcome, TaskStatus, TraceContext, WorkerFilter,
-    WorkerInfo, WorkflowEventStore, WorkflowInfo, WorkflowStatus,
+    CircuitBreakerState, ClaimedTask, DlqEntry, DlqFilter, DueTimer, ExpiredWorkflow,
+    HeartbeatResponse, Pagination, StoreError, TaskDefinition, TaskFailureOutcome, TaskStatus,
+    TraceContext, WorkerFilter, WorkerInfo, WorkflowEventStore, WorkflowInfo, WorkflowStatus,
 };
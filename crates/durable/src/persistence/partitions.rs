@@ -0,0 +1,115 @@
+//! Monthly partitions of the durable history tables
+//!
+//! `durable_workflow_events` and `durable_task_queue` are range-partitioned
+//! by month on `created_at`, with partitions named `<table>_pYYYY_MM` and
+//! bounded on UTC month starts. Rows outside every monthly partition land in
+//! the `<table>_default` partition.
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+
+/// Tables partitioned by month on `created_at`
+pub const PARTITIONED_TABLES: &[&str] = &["durable_workflow_events", "durable_task_queue"];
+
+/// A monthly partition of a durable table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonthlyPartition {
+    pub table: String,
+    pub name: String,
+    /// First instant covered by the partition
+    pub starts_at: DateTime<Utc>,
+    /// First instant after the partition
+    pub ends_at: DateTime<Utc>,
+}
+
+impl MonthlyPartition {
+    /// The partition of `table` that holds rows created at `at`
+    pub fn containing(table: &str, at: DateTime<Utc>) -> Self {
+        Self::for_month(table, at.year(), at.month())
+    }
+
+    /// Parse a partition name of `table`, e.g. `durable_task_queue_p2026_10`
+    pub fn parse(table: &str, name: &str) -> Option<Self> {
+        let suffix = name.strip_prefix(table)?.strip_prefix("_p")?;
+        let (year, month) = suffix.split_once('_')?;
+        if year.len() != 4 || month.len() != 2 {
+            return None;
+        }
+        let year: i32 = year.parse().ok()?;
+        let month: u32 = month.parse().ok()?;
+        (1..=12)
+            .contains(&month)
+            .then(|| Self::for_month(table, year, month))
+    }
+
+    /// The partition for the following month
+    pub fn next(&self) -> Self {
+        Self::containing(&self.table, self.ends_at)
+    }
+
+    fn for_month(table: &str, year: i32, month: u32) -> Self {
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+        Self {
+            table: table.to_string(),
+            name: format!("{}_p{:04}_{:02}", table, year, month),
+            starts_at: month_start(year, month),
+            ends_at: month_start(next_year, next_month),
+        }
+    }
+}
+
+fn month_start(year: i32, month: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .expect("first of month is a valid UTC time")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_containing() {
+        let at = Utc.with_ymd_and_hms(2026, 12, 31, 23, 59, 59).unwrap();
+        let partition = MonthlyPartition::containing("durable_task_queue", at);
+        assert_eq!(partition.name, "durable_task_queue_p2026_12");
+        assert_eq!(
+            partition.starts_at,
+            Utc.with_ymd_and_hms(2026, 12, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            partition.ends_at,
+            Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(partition.next().name, "durable_task_queue_p2027_01");
+    }
+
+    #[test]
+    fn test_partition_parse() {
+        let partition = MonthlyPartition::parse(
+            "durable_workflow_events",
+            "durable_workflow_events_p2026_03",
+        )
+        .unwrap();
+        assert_eq!(
+            partition.starts_at,
+            Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap()
+        );
+
+        for name in [
+            "durable_workflow_events_default",
+            "durable_workflow_events_p2026_13",
+            "durable_workflow_events_p26_03",
+            "durable_task_queue_p2026_03",
+        ] {
+            assert_eq!(
+                MonthlyPartition::parse("durable_workflow_events", name),
+                None,
+                "{name}"
+            );
+        }
+    }
+}
@@ -3,6 +3,7 @@
 //! Production-ready persistence using PostgreSQL with:
 //! - Optimistic concurrency control via sequence numbers
 //! - Efficient task claiming with SKIP LOCKED
+//! - Fair scheduling and concurrency limits on claims (see [`FairnessConfig`])
 //! - Event sourcing for workflow replay
 
 use std::time::Duration;
@@ -13,8 +14,13 @@ use sqlx::{PgPool, Row};
 use tracing::{debug, error, instrument};
 use uuid::Uuid;
 
+use super::fairness::{scan_limit, FairnessConfig};
+use super::partitions::MonthlyPartition;
 use super::store::*;
-use crate::reliability::{CircuitBreakerConfig, CircuitState};
+use crate::reliability::{
+    CircuitBreakerConfig, CircuitState, RateLimitConfig, RateLimitDecision, RateLimitObservation,
+    TaskTimingInfo, TimeoutConfig, TokenBucketState,
+};
 use crate::workflow::{ActivityOptions, WorkflowError, WorkflowEvent, WorkflowSignal};
 
 /// PostgreSQL implementation of WorkflowEventStore
@@ -34,18 +40,275 @@ use crate::workflow::{ActivityOptions, WorkflowError, WorkflowEvent, WorkflowSig
 #[derive(Clone)]
 pub struct PostgresWorkflowEventStore {
     pool: PgPool,
+    fairness: FairnessConfig,
 }
 
+/// Advisory lock serializing claims while concurrency limits are configured,
+/// so concurrent claimers cannot both take the last free slot
+///
+/// Limits are checked against the claimed rows other transactions have
+/// committed; without the lock two claims running side by side would both
+/// see the same free slots. The lock is global rather than per key because a
+/// claim does not know which keys it will take before it runs. It is held
+/// for the single claim statement, whose cost is bounded by [`scan_limit`]
+/// rather than by the queue length: with 200k pending tasks a claim takes
+/// about 6 ms on a local PostgreSQL 15 (over 500 ms when every pending task
+/// was ranked), which caps limited claims at roughly 150 per second. Without
+/// limits no lock is taken and claims only contend on the rows they lock.
+const CLAIM_LIMITS_LOCK_ID: i64 = 0x0064_7572_6162_6c65;
+
 impl PostgresWorkflowEventStore {
     /// Create a new PostgreSQL store with the given connection pool
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            fairness: FairnessConfig::default(),
+        }
+    }
+
+    /// Apply concurrency limits when claiming tasks
+    pub fn with_fairness(mut self, fairness: FairnessConfig) -> Self {
+        self.fairness = fairness;
+        self
     }
 
     /// Get a reference to the connection pool
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
+
+    /// Insert a pending task
+    ///
+    /// The partitioned queue's key is (id, created_at), so task ids are
+    /// always generated here rather than taken from callers.
+    async fn insert_task<'e>(
+        executor: impl sqlx::PgExecutor<'e>,
+        task: &TaskDefinition,
+    ) -> Result<Uuid, StoreError> {
+        let task_id = Uuid::now_v7();
+        let options_json = serde_json::to_value(&task.options)
+            .map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO durable_task_queue (
+                id, workflow_id, activity_id, activity_type, input, options,
+                max_attempts, priority,
+                schedule_to_start_timeout_ms, start_to_close_timeout_ms, heartbeat_timeout_ms,
+                task_queue, fairness_key
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(task_id)
+        .bind(task.workflow_id)
+        .bind(&task.activity_id)
+        .bind(&task.activity_type)
+        .bind(&task.input)
+        .bind(&options_json)
+        .bind(task.options.retry_policy.max_attempts as i32)
+        .bind(task.options.priority)
+        .bind(
+            task.options
+                .schedule_to_start_timeout
+                .map(|d| d.as_millis() as i64),
+        )
+        .bind(task.options.start_to_close_timeout.as_millis() as i64)
+        .bind(task.options.heartbeat_timeout.map(|d| d.as_millis() as i64))
+        .bind(&task.options.task_queue)
+        .bind(&task.options.fairness_key)
+        .execute(executor)
+        .await
+        .map_err(|e| {
+            error!("Failed to enqueue task: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        debug!(
+            %task_id,
+            activity_type = %task.activity_type,
+            task_queue = %task.options.task_queue,
+            "enqueued task"
+        );
+        Ok(task_id)
+    }
+
+    /// Apply `update` to a rate limiter's bucket state under a row lock
+    async fn update_rate_limiter<T: Send>(
+        &self,
+        key: &str,
+        update: impl FnOnce(&mut TokenBucketState, DateTime<Utc>) -> T + Send,
+    ) -> Result<T, StoreError> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            error!("Failed to begin transaction: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        // Create the bucket on first use; concurrent creators are fine
+        sqlx::query(
+            r#"
+            INSERT INTO durable_rate_limiter_state (key, updated_at)
+            VALUES ($1, $2)
+            ON CONFLICT (key) DO NOTHING
+            "#,
+        )
+        .bind(key)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT available_requests, available_tokens,
+                   learned_requests_per_minute, learned_tokens_per_minute,
+                   blocked_until, updated_at
+            FROM durable_rate_limiter_state
+            WHERE key = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to load rate limiter: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        // NULL means no limit is known, i.e. an unlimited (full) bucket
+        let mut state = TokenBucketState {
+            available_requests: row
+                .get::<Option<f64>, _>("available_requests")
+                .unwrap_or(f64::INFINITY),
+            available_tokens: row
+                .get::<Option<f64>, _>("available_tokens")
+                .unwrap_or(f64::INFINITY),
+            learned_requests_per_minute: row
+                .get::<Option<i32>, _>("learned_requests_per_minute")
+                .map(|v| v as u32),
+            learned_tokens_per_minute: row
+                .get::<Option<i32>, _>("learned_tokens_per_minute")
+                .map(|v| v as u32),
+            blocked_until: row.get("blocked_until"),
+            updated_at: row.get("updated_at"),
+        };
+        let result = update(&mut state, now);
+
+        sqlx::query(
+            r#"
+            UPDATE durable_rate_limiter_state
+            SET available_requests = $2,
+                available_tokens = $3,
+                learned_requests_per_minute = $4,
+                learned_tokens_per_minute = $5,
+                blocked_until = $6,
+                updated_at = $7
+            WHERE key = $1
+            "#,
+        )
+        .bind(key)
+        .bind(Some(state.available_requests).filter(|v| v.is_finite()))
+        .bind(Some(state.available_tokens).filter(|v| v.is_finite()))
+        .bind(state.learned_requests_per_minute.map(|v| v as i32))
+        .bind(state.learned_tokens_per_minute.map(|v| v as i32))
+        .bind(state.blocked_until)
+        .bind(state.updated_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to update rate limiter: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        Ok(result)
+    }
+
+    // =========================================================================
+    // Partition Maintenance
+    // =========================================================================
+
+    /// Monthly partitions of a partitioned table, oldest first
+    pub async fn list_partitions(&self, table: &str) -> Result<Vec<MonthlyPartition>, StoreError> {
+        let names: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT child.relname::TEXT
+            FROM pg_inherits
+            JOIN pg_class parent ON parent.oid = pg_inherits.inhparent
+            JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+            WHERE parent.relname = $1
+            "#,
+        )
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to list partitions: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        let mut partitions: Vec<MonthlyPartition> = names
+            .iter()
+            .filter_map(|name| MonthlyPartition::parse(table, name))
+            .collect();
+        partitions.sort_by_key(|p| p.starts_at);
+        Ok(partitions)
+    }
+
+    /// Create a monthly partition if it does not exist yet
+    pub async fn create_partition(&self, partition: &MonthlyPartition) -> Result<(), StoreError> {
+        sqlx::query("SELECT durable_ensure_monthly_partition($1, $2)")
+            .bind(&partition.table)
+            .bind(partition.starts_at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(partition = %partition.name, "Failed to create partition: {}", e);
+                StoreError::Database(e.to_string())
+            })?;
+
+        debug!(partition = %partition.name, "created partition");
+        Ok(())
+    }
+
+    /// Whether a partition holds no rows
+    pub async fn is_partition_empty(
+        &self,
+        partition: &MonthlyPartition,
+    ) -> Result<bool, StoreError> {
+        // Partition names come from MonthlyPartition, so they are safe to inline
+        let exists: bool = sqlx::query_scalar(&format!(
+            r#"SELECT EXISTS (SELECT 1 FROM "{}")"#,
+            partition.name
+        ))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(!exists)
+    }
+
+    /// Drop a partition
+    ///
+    /// Dropping briefly locks the parent table; callers only drop partitions
+    /// that are already empty. (Detaching concurrently is not possible while
+    /// the table has a default partition.)
+    pub async fn drop_partition(&self, partition: &MonthlyPartition) -> Result<(), StoreError> {
+        sqlx::query(&format!(r#"DROP TABLE "{}""#, partition.name))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(partition = %partition.name, "Failed to drop partition: {}", e);
+                StoreError::Database(e.to_string())
+            })?;
+
+        debug!(partition = %partition.name, "dropped partition");
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -108,7 +371,8 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
     async fn get_workflow_info(&self, workflow_id: Uuid) -> Result<WorkflowInfo, StoreError> {
         let row = sqlx::query(
             r#"
-            SELECT id, workflow_type, status, input, result, error
+            SELECT id, workflow_type, status, input, result, error,
+                   run_number, previous_run_id, continued_as
             FROM durable_workflow_instances
             WHERE id = $1
             "#,
@@ -132,6 +396,9 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
             input: row.get("input"),
             result: row.get("result"),
             error: error_json.and_then(|v| serde_json::from_value(v).ok()),
+            run_number: row.get("run_number"),
+            previous_run_id: row.get("previous_run_id"),
+            continued_as: row.get("continued_as"),
         })
     }
 
@@ -148,7 +415,9 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
             .await
             .map_err(|e| StoreError::Database(e.to_string()))?;
 
-        // Lock the workflow instance row to prevent concurrent modifications
+        // Lock the workflow instance row to prevent concurrent modifications.
+        // The partitioned events table has no unique (workflow_id,
+        // sequence_num) constraint, so every event insert must hold this lock.
         sqlx::query(
             r#"
             SELECT id FROM durable_workflow_instances
@@ -300,73 +569,285 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         Ok(())
     }
 
-    #[instrument(skip(self, task))]
-    async fn enqueue_task(&self, task: TaskDefinition) -> Result<Uuid, StoreError> {
-        let task_id = Uuid::now_v7();
-        let options_json = serde_json::to_value(&task.options)
+    #[instrument(skip(self, input))]
+    async fn continue_as_new(
+        &self,
+        workflow_id: Uuid,
+        expected_sequence: i32,
+        input: serde_json::Value,
+    ) -> Result<Uuid, StoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT status FROM durable_workflow_instances
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(workflow_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?
+        .ok_or(StoreError::WorkflowNotFound(workflow_id))?;
+
+        let status: String = row.get("status");
+        if parse_workflow_status(&status)?.is_terminal() {
+            return Err(StoreError::WorkflowClosed(workflow_id));
+        }
+
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(MAX(sequence_num) + 1, 0) as next_seq
+            FROM durable_workflow_events
+            WHERE workflow_id = $1
+            "#,
+        )
+        .bind(workflow_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let current_sequence: i32 = row.get::<i32, _>("next_seq");
+        if current_sequence != expected_sequence {
+            return Err(StoreError::ConcurrencyConflict {
+                expected: expected_sequence,
+                actual: current_sequence,
+            });
+        }
+
+        let continued = WorkflowEvent::WorkflowContinuedAsNew {
+            input: input.clone(),
+        };
+        let event_data = serde_json::to_value(&continued)
             .map_err(|e| StoreError::Serialization(e.to_string()))?;
+        sqlx::query(
+            r#"
+            INSERT INTO durable_workflow_events (workflow_id, sequence_num, event_type, event_data)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(current_sequence)
+        .bind(event_type_name(&continued))
+        .bind(&event_data)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
 
+        // Close the finished run under a fresh id and move its history there
+        let run_id = Uuid::now_v7();
         sqlx::query(
             r#"
-            INSERT INTO durable_task_queue (
-                id, workflow_id, activity_id, activity_type, input, options,
-                max_attempts, priority,
-                schedule_to_start_timeout_ms, start_to_close_timeout_ms, heartbeat_timeout_ms
+            INSERT INTO durable_workflow_instances (
+                id, workflow_type, status, input, created_at, started_at, completed_at,
+                partition_key, trace_id, span_id, run_number, previous_run_id, continued_as
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            SELECT $2, workflow_type, 'completed', input, created_at, started_at, NOW(),
+                   partition_key, trace_id, span_id, run_number, previous_run_id, id
+            FROM durable_workflow_instances
+            WHERE id = $1
             "#,
         )
-        .bind(task_id)
-        .bind(task.workflow_id)
-        .bind(&task.activity_id)
-        .bind(&task.activity_type)
-        .bind(&task.input)
-        .bind(&options_json)
-        .bind(task.options.retry_policy.max_attempts as i32)
-        .bind(task.options.priority)
-        .bind(task.options.schedule_to_start_timeout.as_millis() as i64)
-        .bind(task.options.start_to_close_timeout.as_millis() as i64)
-        .bind(task.options.heartbeat_timeout.map(|d| d.as_millis() as i64))
-        .execute(&self.pool)
+        .bind(workflow_id)
+        .bind(run_id)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| {
-            error!("Failed to enqueue task: {}", e);
-            StoreError::Database(e.to_string())
-        })?;
+        .map_err(|e| StoreError::Database(e.to_string()))?;
 
-        debug!(%task_id, activity_type = %task.activity_type, "enqueued task");
-        Ok(task_id)
+        sqlx::query(
+            r#"
+            UPDATE durable_workflow_events
+            SET workflow_id = $2
+            WHERE workflow_id = $1
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(run_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        // Cancel the finished run's outstanding tasks so their results do
+        // not reach the next run
+        sqlx::query(
+            r#"
+            UPDATE durable_task_queue
+            SET status = 'cancelled'
+            WHERE workflow_id = $1 AND status IN ('pending', 'claimed')
+            "#,
+        )
+        .bind(workflow_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        // Restart the workflow id as the next run
+        sqlx::query(
+            r#"
+            UPDATE durable_workflow_instances
+            SET status = 'running',
+                input = $2,
+                result = NULL,
+                error = NULL,
+                started_at = NOW(),
+                completed_at = NULL,
+                run_number = run_number + 1,
+                previous_run_id = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(&input)
+        .bind(run_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let started = WorkflowEvent::WorkflowStarted { input };
+        let event_data =
+            serde_json::to_value(&started).map_err(|e| StoreError::Serialization(e.to_string()))?;
+        sqlx::query(
+            r#"
+            INSERT INTO durable_workflow_events (workflow_id, sequence_num, event_type, event_data)
+            VALUES ($1, 0, $2, $3)
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(event_type_name(&started))
+        .bind(&event_data)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        debug!(%workflow_id, %run_id, "continued workflow as new");
+        Ok(run_id)
+    }
+
+    #[instrument(skip(self, task))]
+    async fn enqueue_task(&self, task: TaskDefinition) -> Result<Uuid, StoreError> {
+        Self::insert_task(&self.pool, &task).await
     }
 
-    #[instrument(skip(self, activity_types))]
-    async fn claim_task(
+    #[instrument(skip(self, task_queues, activity_types))]
+    async fn claim_task_from_queues(
         &self,
         worker_id: &str,
+        task_queues: &[String],
         activity_types: &[String],
         max_tasks: usize,
     ) -> Result<Vec<ClaimedTask>, StoreError> {
-        if activity_types.is_empty() {
+        if task_queues.is_empty() || activity_types.is_empty() {
             return Ok(vec![]);
         }
 
-        // Use SKIP LOCKED for efficient concurrent claiming
-        // This query:
-        // 1. Finds pending tasks matching activity types
-        // 2. Orders by priority (desc) then visibility time
-        // 3. Limits to max_tasks
-        // 4. Uses SKIP LOCKED to avoid contention
-        // 5. Updates status and claiming info in one atomic operation
+        let activity_limits = serde_json::to_value(&self.fairness.max_concurrent_per_activity_type)
+            .map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| {
+            error!("Failed to begin transaction: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        if self.fairness.has_limits() {
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(CLAIM_LIMITS_LOCK_ID)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| StoreError::Database(e.to_string()))?;
+        }
+
+        // Same rules as `fairness::select_fair`. This query:
+        // 1. Reads up to $7 pending tasks from the head of each of the worker's
+        //    (task queue, activity type) pairs along the pending index, skipping
+        //    keys and activity types already at their limit
+        // 2. Ranks them within their fairness key and drops tasks over the
+        //    per-key limit ($5), counting claimed tasks
+        // 3. Ranks the rest within their activity type and drops tasks over the
+        //    per-activity-type limits ($6)
+        // 4. Orders by priority (desc), rank within key (round-robin between
+        //    keys), then visibility time, and locks up to max_tasks of them
+        // 5. Uses SKIP LOCKED, so concurrent claims take disjoint tasks
+        // 6. Updates status and claiming info in one atomic operation
         let rows = sqlx::query(
             r#"
-            WITH claimable AS (
-                SELECT id
+            WITH running_keys AS (
+                SELECT fairness_key, COUNT(*) AS running
                 FROM durable_task_queue
-                WHERE status = 'pending'
-                  AND activity_type = ANY($1)
-                  AND visible_at <= NOW()
-                ORDER BY priority DESC, visible_at
+                WHERE status = 'claimed' AND fairness_key IS NOT NULL
+                GROUP BY fairness_key
+            ),
+            running_types AS (
+                SELECT activity_type, COUNT(*) AS running
+                FROM durable_task_queue
+                WHERE status = 'claimed'
+                GROUP BY activity_type
+            ),
+            full_keys AS (
+                SELECT fairness_key FROM running_keys WHERE running >= $5
+            ),
+            open_pairs AS (
+                SELECT q.task_queue, a.activity_type
+                FROM UNNEST($4::TEXT[]) AS q(task_queue)
+                CROSS JOIN UNNEST($1::TEXT[]) AS a(activity_type)
+                LEFT JOIN running_types rt ON rt.activity_type = a.activity_type
+                WHERE NOT ($6::JSONB ? a.activity_type)
+                   OR COALESCE(rt.running, 0) < ($6::JSONB ->> a.activity_type)::INT
+            ),
+            candidates AS (
+                SELECT t.id, t.priority, t.visible_at, t.activity_type, t.fairness_key,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY t.fairness_key ORDER BY t.priority DESC, t.visible_at, t.id
+                       ) AS key_rank
+                FROM open_pairs p
+                CROSS JOIN LATERAL (
+                    SELECT id, priority, visible_at, activity_type, fairness_key
+                    FROM durable_task_queue
+                    WHERE status = 'pending'
+                      AND task_queue = p.task_queue
+                      AND activity_type = p.activity_type
+                      AND visible_at <= NOW()
+                      AND (fairness_key IS NULL
+                           OR fairness_key NOT IN (SELECT fairness_key FROM full_keys))
+                    ORDER BY priority DESC, visible_at
+                    LIMIT $7
+                ) t
+            ),
+            within_key_limit AS (
+                SELECT c.*,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY c.activity_type ORDER BY c.priority DESC, c.visible_at, c.id
+                       ) AS type_rank
+                FROM candidates c
+                LEFT JOIN running_keys rk ON rk.fairness_key = c.fairness_key
+                WHERE $5::INT IS NULL
+                   OR c.fairness_key IS NULL
+                   OR COALESCE(rk.running, 0) + c.key_rank <= $5
+            ),
+            eligible AS (
+                SELECT w.id, w.priority, w.key_rank, w.visible_at
+                FROM within_key_limit w
+                LEFT JOIN running_types rt ON rt.activity_type = w.activity_type
+                WHERE NOT ($6::JSONB ? w.activity_type)
+                   OR COALESCE(rt.running, 0) + w.type_rank
+                      <= ($6::JSONB ->> w.activity_type)::INT
+            ),
+            claimable AS (
+                SELECT t.id
+                FROM durable_task_queue t
+                JOIN eligible e ON e.id = t.id
+                WHERE t.status = 'pending'
+                ORDER BY e.priority DESC, e.key_rank, e.visible_at, e.id
                 LIMIT $2
-                FOR UPDATE SKIP LOCKED
+                FOR UPDATE OF t SKIP LOCKED
             )
             UPDATE durable_task_queue t
             SET status = 'claimed',
@@ -383,13 +864,21 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         .bind(activity_types)
         .bind(max_tasks as i32)
         .bind(worker_id)
-        .fetch_all(&self.pool)
+        .bind(task_queues)
+        .bind(self.fairness.max_concurrent_per_key.map(|max| max as i32))
+        .bind(&activity_limits)
+        .bind(scan_limit(max_tasks) as i64)
+        .fetch_all(&mut *tx)
         .await
         .map_err(|e| {
             error!("Failed to claim tasks: {}", e);
             StoreError::Database(e.to_string())
         })?;
 
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
         let mut claimed = Vec::with_capacity(rows.len());
         for row in rows {
             let options_json: serde_json::Value = row.get("options");
@@ -465,7 +954,7 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
             r#"
             UPDATE durable_task_queue
             SET status = 'completed'
-            WHERE id = $1
+            WHERE id = $1 AND status <> 'cancelled'
             "#,
         )
         .bind(task_id)
@@ -480,39 +969,344 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         Ok(())
     }
 
+    #[instrument(skip(self, _result, next))]
+    async fn complete_and_enqueue(
+        &self,
+        task_id: Uuid,
+        _result: serde_json::Value,
+        next: Option<TaskDefinition>,
+    ) -> Result<bool, StoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        // Share-lock the workflow: `cancel_activity` locks it exclusively, so
+        // it either cancelled this task already or sees the next one
+        sqlx::query(
+            r#"
+            SELECT w.id
+            FROM durable_task_queue t
+            JOIN durable_workflow_instances w ON w.id = t.workflow_id
+            WHERE t.id = $1
+            FOR SHARE OF w
+            "#,
+        )
+        .bind(task_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?
+        .ok_or(StoreError::TaskNotFound(task_id))?;
+
+        let completed = sqlx::query(
+            r#"
+            UPDATE durable_task_queue
+            SET status = 'completed'
+            WHERE id = $1 AND status <> 'cancelled'
+            "#,
+        )
+        .bind(task_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to complete task: {}", e);
+            StoreError::Database(e.to_string())
+        })?
+        .rows_affected()
+            > 0;
+
+        if completed {
+            if let Some(next) = &next {
+                Self::insert_task(&mut *tx, next).await?;
+            }
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        debug!(%task_id, completed, "completed task");
+        Ok(completed)
+    }
+
+    #[instrument(skip(self))]
+    async fn cancel_activity(
+        &self,
+        workflow_id: Uuid,
+        activity_id: &str,
+    ) -> Result<u64, StoreError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        // Waits for `complete_and_enqueue` calls in flight, so the steps they
+        // chain are cancelled too
+        sqlx::query(
+            r#"
+            SELECT id FROM durable_workflow_instances
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(workflow_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?
+        .ok_or(StoreError::WorkflowNotFound(workflow_id))?;
+
+        let cancelled = sqlx::query(
+            r#"
+            UPDATE durable_task_queue
+            SET status = 'cancelled'
+            WHERE workflow_id = $1
+              AND (activity_id = $2 OR starts_with(activity_id, $2 || '/'))
+              AND status IN ('pending', 'claimed')
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(activity_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?
+        .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        debug!(%workflow_id, %activity_id, cancelled, "cancelled activity tasks");
+        Ok(cancelled)
+    }
+
     #[instrument(skip(self))]
     async fn fail_task(
         &self,
-        task_id: Uuid,
+        task_id: Uuid,
+        error: &str,
+    ) -> Result<TaskFailureOutcome, StoreError> {
+        // Get current task state
+        let row = sqlx::query(
+            r#"
+            SELECT status, attempt, max_attempts, options
+            FROM durable_task_queue
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(task_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?
+        .ok_or(StoreError::TaskNotFound(task_id))?;
+
+        // A cancelled task is never retried
+        let status: String = row.get("status");
+        if status == "cancelled" {
+            debug!(%task_id, "ignoring failure of cancelled task");
+            return Ok(TaskFailureOutcome::ExhaustedRetries);
+        }
+
+        let attempt: i32 = row.get("attempt");
+        let max_attempts: i32 = row.get("max_attempts");
+        let options_json: serde_json::Value = row.get("options");
+        let options: ActivityOptions = serde_json::from_value(options_json)
+            .map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+        if attempt < max_attempts {
+            // Calculate retry delay
+            let delay = options.retry_policy.delay_for_attempt((attempt + 1) as u32);
+            let visible_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+
+            // Requeue for retry
+            sqlx::query(
+                r#"
+                UPDATE durable_task_queue
+                SET status = 'pending',
+                    claimed_by = NULL,
+                    claimed_at = NULL,
+                    heartbeat_at = NULL,
+                    last_error = $2,
+                    visible_at = $3
+                WHERE id = $1
+                "#,
+            )
+            .bind(task_id)
+            .bind(error)
+            .bind(visible_at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+            debug!(%task_id, next_attempt = attempt + 1, "task will retry");
+            Ok(TaskFailureOutcome::WillRetry {
+                next_attempt: (attempt + 1) as u32,
+                delay,
+            })
+        } else {
+            // Move to DLQ
+            sqlx::query(
+                r#"
+                UPDATE durable_task_queue
+                SET status = 'dead',
+                    last_error = $2
+                WHERE id = $1
+                "#,
+            )
+            .bind(task_id)
+            .bind(error)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+            debug!(%task_id, "task moved to DLQ");
+            Ok(TaskFailureOutcome::MovedToDlq)
+        }
+    }
+
+    #[instrument(skip(self))]
+    async fn reclaim_stale_tasks(
+        &self,
+        stale_threshold: Duration,
+    ) -> Result<Vec<Uuid>, StoreError> {
+        let threshold =
+            Utc::now() - chrono::Duration::from_std(stale_threshold).unwrap_or_default();
+
+        // Find and reclaim stale tasks
+        let rows = sqlx::query(
+            r#"
+            UPDATE durable_task_queue
+            SET status = 'pending',
+                claimed_by = NULL,
+                claimed_at = NULL,
+                visible_at = NOW()
+            WHERE status = 'claimed'
+              AND heartbeat_at < $1
+            RETURNING id
+            "#,
+        )
+        .bind(threshold)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to reclaim stale tasks: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        let reclaimed: Vec<Uuid> = rows.iter().map(|r| r.get("id")).collect();
+
+        if !reclaimed.is_empty() {
+            debug!(count = reclaimed.len(), "reclaimed stale tasks");
+        }
+
+        Ok(reclaimed)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_timeout_candidates(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TaskTimingInfo>, StoreError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, workflow_id, activity_id, attempt, options,
+                   visible_at, claimed_at, heartbeat_at
+            FROM durable_task_queue
+            WHERE (status = 'pending'
+                   AND visible_at + schedule_to_start_timeout_ms * INTERVAL '1 millisecond' < $1)
+               OR (status = 'claimed'
+                   AND (claimed_at + start_to_close_timeout_ms * INTERVAL '1 millisecond' < $1
+                        OR COALESCE(heartbeat_at, claimed_at)
+                           + heartbeat_timeout_ms * INTERVAL '1 millisecond' < $1))
+            ORDER BY visible_at
+            LIMIT $2
+            "#,
+        )
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to list timeout candidates: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        rows.into_iter()
+            .map(|row| {
+                let options_json: serde_json::Value = row.get("options");
+                let options: ActivityOptions = serde_json::from_value(options_json)
+                    .map_err(|e| StoreError::Serialization(e.to_string()))?;
+                Ok(TaskTimingInfo {
+                    task_id: row.get("id"),
+                    workflow_id: row.get("workflow_id"),
+                    activity_id: row.get("activity_id"),
+                    attempt: row.get::<i32, _>("attempt") as u32,
+                    scheduled_at: row.get("visible_at"),
+                    started_at: row.get("claimed_at"),
+                    last_heartbeat_at: row.get("heartbeat_at"),
+                    timeout_config: TimeoutConfig::from(&options),
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self, timing), fields(task_id = %timing.task_id))]
+    async fn fail_timed_out_task(
+        &self,
+        timing: &TaskTimingInfo,
         error: &str,
-    ) -> Result<TaskFailureOutcome, StoreError> {
-        // Get current task state
-        let row = sqlx::query(
+    ) -> Result<Option<TaskFailureOutcome>, StoreError> {
+        let expected_status = if timing.started_at.is_some() {
+            "claimed"
+        } else {
+            "pending"
+        };
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        // Lock the task and make sure it was not completed, retried or
+        // reclaimed since the timeout was detected
+        let Some(row) = sqlx::query(
             r#"
-            SELECT attempt, max_attempts, options
+            SELECT max_attempts, options
             FROM durable_task_queue
-            WHERE id = $1
+            WHERE id = $1 AND status = $2 AND attempt = $3
             FOR UPDATE
             "#,
         )
-        .bind(task_id)
-        .fetch_optional(&self.pool)
+        .bind(timing.task_id)
+        .bind(expected_status)
+        .bind(timing.attempt as i32)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|e| StoreError::Database(e.to_string()))?
-        .ok_or(StoreError::TaskNotFound(task_id))?;
+        else {
+            return Ok(None);
+        };
 
-        let attempt: i32 = row.get("attempt");
-        let max_attempts: i32 = row.get("max_attempts");
+        let max_attempts = row.get::<i32, _>("max_attempts") as u32;
         let options_json: serde_json::Value = row.get("options");
         let options: ActivityOptions = serde_json::from_value(options_json)
             .map_err(|e| StoreError::Serialization(e.to_string()))?;
 
-        if attempt < max_attempts {
-            // Calculate retry delay
-            let delay = options.retry_policy.delay_for_attempt((attempt + 1) as u32);
+        // A task that never started still used up the attempt it was waiting for
+        let attempt = if timing.started_at.is_some() {
+            timing.attempt
+        } else {
+            timing.attempt + 1
+        };
+
+        let outcome = if attempt < max_attempts {
+            let delay = options.retry_policy.delay_for_attempt(attempt + 1);
             let visible_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
 
-            // Requeue for retry
             sqlx::query(
                 r#"
                 UPDATE durable_task_queue
@@ -520,87 +1314,172 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
                     claimed_by = NULL,
                     claimed_at = NULL,
                     heartbeat_at = NULL,
-                    last_error = $2,
-                    visible_at = $3
+                    attempt = $2,
+                    last_error = $3,
+                    visible_at = $4
                 WHERE id = $1
                 "#,
             )
-            .bind(task_id)
+            .bind(timing.task_id)
+            .bind(attempt as i32)
             .bind(error)
             .bind(visible_at)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
             .map_err(|e| StoreError::Database(e.to_string()))?;
 
-            debug!(%task_id, next_attempt = attempt + 1, "task will retry");
-            Ok(TaskFailureOutcome::WillRetry {
-                next_attempt: (attempt + 1) as u32,
+            TaskFailureOutcome::WillRetry {
+                next_attempt: attempt + 1,
                 delay,
-            })
+            }
         } else {
-            // Move to DLQ
             sqlx::query(
                 r#"
                 UPDATE durable_task_queue
                 SET status = 'dead',
-                    last_error = $2
+                    attempt = $2,
+                    last_error = $3
                 WHERE id = $1
                 "#,
             )
-            .bind(task_id)
+            .bind(timing.task_id)
+            .bind(attempt as i32)
             .bind(error)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
             .map_err(|e| StoreError::Database(e.to_string()))?;
 
-            debug!(%task_id, "task moved to DLQ");
-            Ok(TaskFailureOutcome::MovedToDlq)
-        }
+            TaskFailureOutcome::MovedToDlq
+        };
+
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        debug!(task_id = %timing.task_id, ?outcome, "failed timed out task");
+        Ok(Some(outcome))
     }
 
-    #[instrument(skip(self))]
-    async fn reclaim_stale_tasks(
+    #[instrument(skip(self, signal))]
+    async fn send_signal(
         &self,
-        stale_threshold: Duration,
-    ) -> Result<Vec<Uuid>, StoreError> {
-        let threshold =
-            Utc::now() - chrono::Duration::from_std(stale_threshold).unwrap_or_default();
-
-        // Find and reclaim stale tasks
-        let rows = sqlx::query(
+        workflow_id: Uuid,
+        signal: WorkflowSignal,
+    ) -> Result<(), StoreError> {
+        sqlx::query(
             r#"
-            UPDATE durable_task_queue
-            SET status = 'pending',
-                claimed_by = NULL,
-                claimed_at = NULL
-            WHERE status = 'claimed'
-              AND heartbeat_at < $1
-            RETURNING id
+            INSERT INTO durable_signals (workflow_id, signal_type, payload, sent_at)
+            VALUES ($1, $2, $3, $4)
             "#,
         )
-        .bind(threshold)
-        .fetch_all(&self.pool)
+        .bind(workflow_id)
+        .bind(&signal.signal_type)
+        .bind(&signal.payload)
+        .bind(signal.sent_at)
+        .execute(&self.pool)
         .await
         .map_err(|e| {
-            error!("Failed to reclaim stale tasks: {}", e);
+            error!("Failed to send signal: {}", e);
             StoreError::Database(e.to_string())
         })?;
 
-        let reclaimed: Vec<Uuid> = rows.iter().map(|r| r.get("id")).collect();
-
-        if !reclaimed.is_empty() {
-            debug!(count = reclaimed.len(), "reclaimed stale tasks");
-        }
-
-        Ok(reclaimed)
+        debug!(%workflow_id, signal_type = %signal.signal_type, "sent signal");
+        Ok(())
     }
 
-    #[instrument(skip(self, signal))]
-    async fn send_signal(
+    #[instrument(skip(self, input, signal, trace_context))]
+    async fn signal_with_start(
         &self,
         workflow_id: Uuid,
+        workflow_type: &str,
+        input: serde_json::Value,
         signal: WorkflowSignal,
-    ) -> Result<(), StoreError> {
+        trace_context: Option<&TraceContext>,
+    ) -> Result<bool, StoreError> {
+        let (trace_id, span_id) = trace_context
+            .map(|tc| (Some(tc.trace_id.clone()), Some(tc.span_id.clone())))
+            .unwrap_or((None, None));
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        let created = sqlx::query(
+            r#"
+            INSERT INTO durable_workflow_instances (id, workflow_type, status, input, trace_id, span_id)
+            VALUES ($1, $2, 'pending', $3, $4, $5)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(workflow_type)
+        .bind(&input)
+        .bind(&trace_id)
+        .bind(&span_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("Failed to create workflow: {}", e);
+            StoreError::Database(e.to_string())
+        })?
+        .rows_affected()
+            == 1;
+
+        if created {
+            // The new instance row stays invisible to other appenders until
+            // commit, so the first event cannot race with them
+            let event = WorkflowEvent::WorkflowStarted { input };
+            let event_data = serde_json::to_value(&event)
+                .map_err(|e| StoreError::Serialization(e.to_string()))?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO durable_workflow_events (workflow_id, sequence_num, event_type, event_data)
+                VALUES ($1, 0, $2, $3)
+                "#,
+            )
+            .bind(workflow_id)
+            .bind(event_type_name(&event))
+            .bind(&event_data)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+        } else {
+            // Lock the existing workflow so it cannot finish before the
+            // signal is queued
+            let row = sqlx::query(
+                r#"
+                SELECT workflow_type, status FROM durable_workflow_instances
+                WHERE id = $1
+                FOR UPDATE
+                "#,
+            )
+            .bind(workflow_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?
+            .ok_or(StoreError::WorkflowNotFound(workflow_id))?;
+
+            let existing_type: String = row.get("workflow_type");
+            if existing_type != workflow_type {
+                return Err(StoreError::WorkflowTypeMismatch {
+                    workflow_id,
+                    expected: workflow_type.to_string(),
+                    actual: existing_type,
+                });
+            }
+
+            let status: String = row.get("status");
+            if matches!(
+                parse_workflow_status(&status)?,
+                WorkflowStatus::Completed | WorkflowStatus::Failed | WorkflowStatus::Cancelled
+            ) {
+                return Err(StoreError::WorkflowClosed(workflow_id));
+            }
+        }
+
         sqlx::query(
             r#"
             INSERT INTO durable_signals (workflow_id, signal_type, payload, sent_at)
@@ -611,15 +1490,19 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         .bind(&signal.signal_type)
         .bind(&signal.payload)
         .bind(signal.sent_at)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await
         .map_err(|e| {
             error!("Failed to send signal: {}", e);
             StoreError::Database(e.to_string())
         })?;
 
-        debug!(%workflow_id, signal_type = %signal.signal_type, "sent signal");
-        Ok(())
+        tx.commit()
+            .await
+            .map_err(|e| StoreError::Database(e.to_string()))?;
+
+        debug!(%workflow_id, %workflow_type, created, signal_type = %signal.signal_type, "signal with start");
+        Ok(created)
     }
 
     #[instrument(skip(self))]
@@ -692,9 +1575,10 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
             r#"
             INSERT INTO durable_workers (
                 id, worker_group, activity_types, max_concurrency, current_load,
-                status, started_at, last_heartbeat_at, accepting_tasks, hostname, metadata
+                status, started_at, last_heartbeat_at, accepting_tasks, backpressure_reason,
+                hostname, metadata
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             ON CONFLICT (id) DO UPDATE SET
                 worker_group = EXCLUDED.worker_group,
                 activity_types = EXCLUDED.activity_types,
@@ -702,7 +1586,8 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
                 current_load = EXCLUDED.current_load,
                 status = EXCLUDED.status,
                 last_heartbeat_at = EXCLUDED.last_heartbeat_at,
-                accepting_tasks = EXCLUDED.accepting_tasks
+                accepting_tasks = EXCLUDED.accepting_tasks,
+                backpressure_reason = EXCLUDED.backpressure_reason
             "#,
         )
         .bind(&worker.id)
@@ -714,6 +1599,7 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         .bind(worker.started_at)
         .bind(worker.last_heartbeat_at)
         .bind(worker.accepting_tasks)
+        .bind(&worker.backpressure_reason)
         .bind::<Option<String>>(None) // hostname
         .bind::<Option<serde_json::Value>>(None) // metadata
         .execute(&self.pool)
@@ -733,19 +1619,22 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         worker_id: &str,
         current_load: usize,
         accepting_tasks: bool,
+        backpressure_reason: Option<&str>,
     ) -> Result<(), StoreError> {
         sqlx::query(
             r#"
             UPDATE durable_workers
             SET last_heartbeat_at = NOW(),
                 current_load = $2,
-                accepting_tasks = $3
+                accepting_tasks = $3,
+                backpressure_reason = $4
             WHERE id = $1
             "#,
         )
         .bind(worker_id)
         .bind(current_load as i32)
         .bind(accepting_tasks)
+        .bind(backpressure_reason)
         .execute(&self.pool)
         .await
         .map_err(|e| {
@@ -761,7 +1650,7 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         let mut query = String::from(
             r#"
             SELECT id, worker_group, activity_types, max_concurrency, current_load,
-                   status, started_at, last_heartbeat_at, accepting_tasks
+                   status, started_at, last_heartbeat_at, accepting_tasks, backpressure_reason
             FROM durable_workers
             WHERE 1=1
             "#,
@@ -794,6 +1683,7 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
                 current_load: row.get::<i32, _>("current_load") as u32,
                 status: row.get("status"),
                 accepting_tasks: row.get("accepting_tasks"),
+                backpressure_reason: row.get("backpressure_reason"),
                 started_at: row.get("started_at"),
                 last_heartbeat_at: row.get("last_heartbeat_at"),
             })
@@ -836,10 +1726,10 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         sqlx::query(
             r#"
             INSERT INTO durable_dead_letter_queue (
-                original_task_id, workflow_id, activity_id, activity_type,
+                original_task_id, workflow_id, activity_id, activity_type, task_queue,
                 input, attempts, last_error, error_history
             )
-            SELECT id, workflow_id, activity_id, activity_type,
+            SELECT id, workflow_id, activity_id, activity_type, task_queue,
                    input, attempt, COALESCE(last_error, 'unknown'), $2
             FROM durable_task_queue
             WHERE id = $1
@@ -868,17 +1758,18 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         let result = sqlx::query(
             r#"
             WITH dlq_entry AS (
-                SELECT workflow_id, activity_id, activity_type, input
+                SELECT workflow_id, activity_id, activity_type, task_queue, input
                 FROM durable_dead_letter_queue
                 WHERE id = $1
             )
             INSERT INTO durable_task_queue (
                 id, workflow_id, activity_id, activity_type, input, options,
                 max_attempts, priority,
-                schedule_to_start_timeout_ms, start_to_close_timeout_ms
+                schedule_to_start_timeout_ms, start_to_close_timeout_ms, task_queue
             )
             SELECT $2, workflow_id, activity_id, activity_type, input,
-                   $3, 3, 0, 60000, 300000
+                   jsonb_set($3, '{task_queue}', to_jsonb(task_queue)),
+                   3, 0, NULL, 300000, task_queue
             FROM dlq_entry
             RETURNING id
             "#,
@@ -923,7 +1814,7 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
     ) -> Result<Vec<DlqEntry>, StoreError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, original_task_id, workflow_id, activity_id, activity_type,
+            SELECT id, original_task_id, workflow_id, activity_id, activity_type, task_queue,
                    input, attempts, last_error, error_history, dead_at
             FROM durable_dead_letter_queue
             WHERE ($1::uuid IS NULL OR workflow_id = $1)
@@ -957,6 +1848,7 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
                     workflow_id: row.get("workflow_id"),
                     activity_id: row.get("activity_id"),
                     activity_type: row.get("activity_type"),
+                    task_queue: row.get("task_queue"),
                     input: row.get("input"),
                     attempts: row.get::<i32, _>("attempts") as u32,
                     last_error: row.get("last_error"),
@@ -1101,6 +1993,210 @@ impl WorkflowEventStore for PostgresWorkflowEventStore {
         Ok(())
     }
 
+    #[instrument(skip(self))]
+    async fn acquire_rate_limit(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+        tokens: u32,
+    ) -> Result<RateLimitDecision, StoreError> {
+        self.update_rate_limiter(key, |state, now| state.try_acquire(config, 1, tokens, now))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    async fn observe_rate_limit(
+        &self,
+        key: &str,
+        config: &RateLimitConfig,
+        observation: &RateLimitObservation,
+    ) -> Result<(), StoreError> {
+        self.update_rate_limiter(key, |state, now| state.observe(config, observation, now))
+            .await?;
+        debug!(key, ?observation, "observed rate limit");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn list_expired_workflows(
+        &self,
+        finished_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ExpiredWorkflow>, StoreError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, workflow_type, status, created_at, completed_at
+            FROM durable_workflow_instances
+            WHERE status IN ('completed', 'failed', 'cancelled')
+              AND completed_at < $1
+            ORDER BY completed_at, id
+            LIMIT $2
+            "#,
+        )
+        .bind(finished_before)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to list expired workflows: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        rows.into_iter()
+            .map(|row| {
+                let status: String = row.get("status");
+                Ok(ExpiredWorkflow {
+                    id: row.get("id"),
+                    workflow_type: row.get("workflow_type"),
+                    status: parse_workflow_status(&status)?,
+                    created_at: row.get("created_at"),
+                    completed_at: row.get("completed_at"),
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn count_expired_workflows(
+        &self,
+        finished_before: DateTime<Utc>,
+    ) -> Result<u64, StoreError> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM durable_workflow_instances
+            WHERE status IN ('completed', 'failed', 'cancelled')
+              AND completed_at < $1
+            "#,
+        )
+        .bind(finished_before)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(count as u64)
+    }
+
+    #[instrument(skip(self, workflow_ids))]
+    async fn delete_workflows(&self, workflow_ids: &[Uuid]) -> Result<u64, StoreError> {
+        // Events, tasks, signals and DLQ entries are removed by ON DELETE CASCADE
+        let result = sqlx::query(
+            r#"
+            DELETE FROM durable_workflow_instances
+            WHERE id = ANY($1)
+              AND status IN ('completed', 'failed', 'cancelled')
+            "#,
+        )
+        .bind(workflow_ids)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete workflows: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        debug!(count = result.rows_affected(), "deleted workflows");
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(skip(self))]
+    async fn count_expired_tasks(&self, created_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM durable_task_queue
+            WHERE status IN ('completed', 'failed', 'dead', 'cancelled')
+              AND created_at < $1
+            "#,
+        )
+        .bind(created_before)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StoreError::Database(e.to_string()))?;
+        Ok(count as u64)
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_expired_tasks(
+        &self,
+        created_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<u64, StoreError> {
+        // Matching on created_at too lets Postgres prune partitions
+        let result = sqlx::query(
+            r#"
+            DELETE FROM durable_task_queue
+            WHERE (id, created_at) IN (
+                SELECT id, created_at
+                FROM durable_task_queue
+                WHERE status IN ('completed', 'failed', 'dead', 'cancelled')
+                  AND created_at < $1
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(created_before)
+        .bind(limit as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to delete expired tasks: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        debug!(count = result.rows_affected(), "deleted expired tasks");
+        Ok(result.rows_affected())
+    }
+
+    #[instrument(skip(self))]
+    async fn list_due_timers(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<DueTimer>, StoreError> {
+        // A later start, fire or cancel for the same id supersedes a start
+        let rows = sqlx::query(
+            r#"
+            SELECT s.workflow_id,
+                   s.event_data->>'timer_id' AS timer_id,
+                   s.created_at + (s.event_data->>'duration_ms')::BIGINT
+                       * INTERVAL '1 millisecond' AS due_at
+            FROM durable_workflow_events s
+            JOIN durable_workflow_instances w ON w.id = s.workflow_id
+            WHERE s.event_type = 'timer_started'
+              AND w.status IN ('pending', 'running')
+              AND s.created_at + (s.event_data->>'duration_ms')::BIGINT
+                      * INTERVAL '1 millisecond' <= $1
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM durable_workflow_events l
+                  WHERE l.workflow_id = s.workflow_id
+                    AND l.sequence_num > s.sequence_num
+                    AND l.event_type IN ('timer_started', 'timer_fired', 'timer_cancelled')
+                    AND l.event_data->>'timer_id' = s.event_data->>'timer_id'
+              )
+            ORDER BY due_at, s.workflow_id
+            LIMIT $2
+            "#,
+        )
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to list due timers: {}", e);
+            StoreError::Database(e.to_string())
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DueTimer {
+                workflow_id: row.get("workflow_id"),
+                timer_id: row.get("timer_id"),
+                due_at: row.get("due_at"),
+            })
+            .collect())
+    }
+
     #[instrument(skip(self))]
     async fn count_active_workflows(&self) -> Result<i64, StoreError> {
         let row = sqlx::query(
@@ -1155,6 +2251,7 @@ fn event_type_name(event: &WorkflowEvent) -> &'static str {
         WorkflowEvent::WorkflowCompleted { .. } => "workflow_completed",
         WorkflowEvent::WorkflowFailed { .. } => "workflow_failed",
         WorkflowEvent::WorkflowCancelled { .. } => "workflow_cancelled",
+        WorkflowEvent::WorkflowContinuedAsNew { .. } => "workflow_continued_as_new",
         WorkflowEvent::ActivityScheduled { .. } => "activity_scheduled",
         WorkflowEvent::ActivityStarted { .. } => "activity_started",
         WorkflowEvent::ActivityCompleted { .. } => "activity_completed",
@@ -1168,6 +2265,7 @@ fn event_type_name(event: &WorkflowEvent) -> &'static str {
         WorkflowEvent::ChildWorkflowStarted { .. } => "child_workflow_started",
         WorkflowEvent::ChildWorkflowCompleted { .. } => "child_workflow_completed",
         WorkflowEvent::ChildWorkflowFailed { .. } => "child_workflow_failed",
+        WorkflowEvent::VersionMarker { .. } => "version_marker",
     }
 }
 
@@ -7,7 +7,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::workflow::{ActivityOptions, WorkflowEvent, WorkflowSignal};
+use crate::reliability::TaskTimingInfo;
+use crate::workflow::{ActivityOptions, WorkflowEvent, WorkflowSignal, DEFAULT_TASK_QUEUE};
 
 /// Error type for store operations
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +21,18 @@ pub enum StoreError {
     #[error("task not found: {0}")]
     TaskNotFound(Uuid),
 
+    /// Workflow already reached a terminal state
+    #[error("workflow {0} is already closed")]
+    WorkflowClosed(Uuid),
+
+    /// Workflow exists with a different type than requested
+    #[error("workflow {workflow_id} has type {actual}, not {expected}")]
+    WorkflowTypeMismatch {
+        workflow_id: Uuid,
+        expected: String,
+        actual: String,
+    },
+
     /// Concurrency conflict (optimistic locking failed)
     #[error("concurrency conflict: expected sequence {expected}, got {actual}")]
     ConcurrencyConflict { expected: i32, actual: i32 },
@@ -156,6 +169,8 @@ pub struct WorkerInfo {
     pub current_load: u32,
     pub status: String,
     pub accepting_tasks: bool,
+    /// Why the worker stopped accepting tasks (if it did)
+    pub backpressure_reason: Option<String>,
     pub started_at: DateTime<Utc>,
     pub last_heartbeat_at: DateTime<Utc>,
 }
@@ -191,6 +206,8 @@ pub struct DlqEntry {
     pub workflow_id: Uuid,
     pub activity_id: String,
     pub activity_type: String,
+    /// Task queue the task was routed to (and is requeued to)
+    pub task_queue: String,
     pub input: serde_json::Value,
     pub attempts: u32,
     pub last_error: String,
@@ -215,6 +232,30 @@ pub struct WorkflowInfo {
     pub input: serde_json::Value,
     pub result: Option<serde_json::Value>,
     pub error: Option<crate::workflow::WorkflowError>,
+    /// Position of this run in its continue-as-new chain, starting at 1
+    pub run_number: i32,
+    /// Closed run this run continued from
+    pub previous_run_id: Option<Uuid>,
+    /// Workflow this run continued as, for runs closed by continue-as-new
+    pub continued_as: Option<Uuid>,
+}
+
+/// A finished workflow past its retention period
+#[derive(Debug, Clone)]
+pub struct ExpiredWorkflow {
+    pub id: Uuid,
+    pub workflow_type: String,
+    pub status: WorkflowStatus,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// A started timer whose duration has elapsed without it firing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueTimer {
+    pub workflow_id: Uuid,
+    pub timer_id: String,
+    pub due_at: DateTime<Utc>,
 }
 
 /// Store for workflow events and task queue
@@ -265,6 +306,20 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
         error: Option<crate::workflow::WorkflowError>,
     ) -> Result<(), StoreError>;
 
+    /// Close the current run of a workflow and start a new run under its id
+    ///
+    /// Atomically appends `WorkflowContinuedAsNew` at `expected_sequence`,
+    /// moves the run and its history to a new completed workflow with a
+    /// fresh id, and restarts `workflow_id` as the next run: `running`, with
+    /// `input` and a `WorkflowStarted` event. Signals stay with
+    /// `workflow_id`. Returns the id of the closed run.
+    async fn continue_as_new(
+        &self,
+        workflow_id: Uuid,
+        expected_sequence: i32,
+        input: serde_json::Value,
+    ) -> Result<Uuid, StoreError>;
+
     // =========================================================================
     // Task Queue Operations
     // =========================================================================
@@ -272,12 +327,29 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
     /// Enqueue an activity task
     async fn enqueue_task(&self, task: TaskDefinition) -> Result<Uuid, StoreError>;
 
-    /// Claim tasks for execution
+    /// Claim tasks from the default task queue for execution
+    async fn claim_task(
+        &self,
+        worker_id: &str,
+        activity_types: &[String],
+        max_tasks: usize,
+    ) -> Result<Vec<ClaimedTask>, StoreError> {
+        self.claim_task_from_queues(
+            worker_id,
+            &[DEFAULT_TASK_QUEUE.to_string()],
+            activity_types,
+            max_tasks,
+        )
+        .await
+    }
+
+    /// Claim tasks from the given task queues for execution
     ///
     /// Uses SELECT FOR UPDATE SKIP LOCKED for efficient concurrent claiming.
-    async fn claim_task(
+    async fn claim_task_from_queues(
         &self,
         worker_id: &str,
+        task_queues: &[String],
         activity_types: &[String],
         max_tasks: usize,
     ) -> Result<Vec<ClaimedTask>, StoreError>;
@@ -297,6 +369,29 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
         result: serde_json::Value,
     ) -> Result<(), StoreError>;
 
+    /// Complete a task and enqueue the step chained after it
+    ///
+    /// Returns `false`, enqueuing nothing, if the task was cancelled. The check
+    /// and the enqueue are atomic with respect to
+    /// [`cancel_activity`](Self::cancel_activity), so a chain never outlives
+    /// the cancellation of its activity.
+    async fn complete_and_enqueue(
+        &self,
+        task_id: Uuid,
+        result: serde_json::Value,
+        next: Option<TaskDefinition>,
+    ) -> Result<bool, StoreError>;
+
+    /// Cancel the pending and claimed tasks of an activity
+    ///
+    /// Includes the steps chained under it: tasks whose activity id is
+    /// `<activity_id>/<step>`. Returns how many tasks were cancelled.
+    async fn cancel_activity(
+        &self,
+        workflow_id: Uuid,
+        activity_id: &str,
+    ) -> Result<u64, StoreError>;
+
     /// Fail a task (may requeue or send to DLQ)
     async fn fail_task(&self, task_id: Uuid, error: &str)
         -> Result<TaskFailureOutcome, StoreError>;
@@ -305,6 +400,28 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
     async fn reclaim_stale_tasks(&self, stale_threshold: Duration)
         -> Result<Vec<Uuid>, StoreError>;
 
+    /// List pending and claimed tasks that may have exceeded a timeout at `now`
+    ///
+    /// Implementations may return tasks that have not timed out; callers
+    /// check each task (see [`TimeoutManager`](crate::reliability::TimeoutManager)).
+    async fn list_timeout_candidates(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TaskTimingInfo>, StoreError>;
+
+    /// Fail a task that timed out, applying its retry policy
+    ///
+    /// Unlike [`fail_task`](Self::fail_task) this only acts if the task is
+    /// still in the state `timing` was read in (same status and attempt), so a
+    /// task completed or reclaimed meanwhile is left alone (`None`). A timeout
+    /// of a pending task (schedule-to-start) consumes an attempt.
+    async fn fail_timed_out_task(
+        &self,
+        timing: &TaskTimingInfo,
+        error: &str,
+    ) -> Result<Option<TaskFailureOutcome>, StoreError>;
+
     // =========================================================================
     // Signal Operations
     // =========================================================================
@@ -316,6 +433,23 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
         signal: WorkflowSignal,
     ) -> Result<(), StoreError>;
 
+    /// Send a signal to a workflow, creating it first if it does not exist
+    ///
+    /// A created workflow starts `pending` with its `WorkflowStarted` event.
+    /// Creating the workflow and queueing the signal happen atomically, so
+    /// concurrent calls for the same id create it once and deliver every
+    /// signal. Returns whether the workflow was created,
+    /// [`StoreError::WorkflowClosed`] if it already finished, or
+    /// [`StoreError::WorkflowTypeMismatch`] if it has another type.
+    async fn signal_with_start(
+        &self,
+        workflow_id: Uuid,
+        workflow_type: &str,
+        input: serde_json::Value,
+        signal: WorkflowSignal,
+        trace_context: Option<&TraceContext>,
+    ) -> Result<bool, StoreError>;
+
     /// Get pending signals for a workflow
     async fn get_pending_signals(
         &self,
@@ -338,12 +472,13 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
         Ok(())
     }
 
-    /// Update worker heartbeat and load
+    /// Update worker heartbeat, load and backpressure state
     async fn worker_heartbeat(
         &self,
         _worker_id: &str,
         _current_load: usize,
         _accepting_tasks: bool,
+        _backpressure_reason: Option<&str>,
     ) -> Result<(), StoreError> {
         Ok(())
     }
@@ -415,6 +550,93 @@ pub trait WorkflowEventStore: Send + Sync + 'static {
         Ok(())
     }
 
+    // =========================================================================
+    // Rate Limiter Operations (optional, default no-op)
+    // =========================================================================
+    // Token buckets shared by all workers, keyed like circuit breakers (e.g.
+    // `llm_provider:<id>`). See reliability::rate_limiter for the bucket rules.
+
+    /// Take one request and `tokens` tokens from a rate limiter's buckets,
+    /// or report how long to wait
+    async fn acquire_rate_limit(
+        &self,
+        _key: &str,
+        _config: &crate::reliability::RateLimitConfig,
+        _tokens: u32,
+    ) -> Result<crate::reliability::RateLimitDecision, StoreError> {
+        Ok(crate::reliability::RateLimitDecision::Acquired)
+    }
+
+    /// Adapt a rate limiter to state reported by the provider
+    async fn observe_rate_limit(
+        &self,
+        _key: &str,
+        _config: &crate::reliability::RateLimitConfig,
+        _observation: &crate::reliability::RateLimitObservation,
+    ) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    // =========================================================================
+    // Retention Operations (optional, default no-op)
+    // =========================================================================
+    // Finished workflows and tasks are removed after their retention period.
+    // Deleting a workflow removes its events, tasks, signals and DLQ entries.
+
+    /// List terminal workflows that finished before `finished_before`, oldest first
+    async fn list_expired_workflows(
+        &self,
+        _finished_before: DateTime<Utc>,
+        _limit: usize,
+    ) -> Result<Vec<ExpiredWorkflow>, StoreError> {
+        Ok(vec![])
+    }
+
+    /// Count terminal workflows that finished before `finished_before`
+    async fn count_expired_workflows(
+        &self,
+        _finished_before: DateTime<Utc>,
+    ) -> Result<u64, StoreError> {
+        Ok(0)
+    }
+
+    /// Delete terminal workflows and everything recorded for them.
+    /// Workflows that are not terminal are left alone.
+    async fn delete_workflows(&self, _workflow_ids: &[Uuid]) -> Result<u64, StoreError> {
+        Ok(0)
+    }
+
+    /// Count finished tasks (completed, failed, dead or cancelled) created
+    /// before `created_before`
+    async fn count_expired_tasks(&self, _created_before: DateTime<Utc>) -> Result<u64, StoreError> {
+        Ok(0)
+    }
+
+    /// Delete up to `limit` finished tasks created before `created_before`
+    async fn delete_expired_tasks(
+        &self,
+        _created_before: DateTime<Utc>,
+        _limit: usize,
+    ) -> Result<u64, StoreError> {
+        Ok(0)
+    }
+
+    // =========================================================================
+    // Timer Operations (optional, default no-op)
+    // =========================================================================
+
+    /// List timers of active workflows that are due at `now`, earliest first
+    ///
+    /// A timer is due once its `TimerStarted` duration has elapsed and no
+    /// later `TimerFired` or `TimerCancelled` event exists for its id.
+    async fn list_due_timers(
+        &self,
+        _now: DateTime<Utc>,
+        _limit: usize,
+    ) -> Result<Vec<DueTimer>, StoreError> {
+        Ok(vec![])
+    }
+
     // =========================================================================
     // Utility Operations (optional, default no-op)
     // =========================================================================
@@ -4,13 +4,15 @@
 //! - [`RetryPolicy`] - Configurable retry with exponential backoff (ACTIVE)
 //! - [`CircuitBreakerConfig`] - Circuit breaker configuration
 //! - [`DistributedCircuitBreaker`] - Distributed circuit breaker using PostgreSQL (FUTURE)
-//! - [`TimeoutManager`] - Activity timeout handling (ACTIVE)
+//! - [`TimeoutManager`] - Activity timeout enforcement (ACTIVE, swept by the control plane)
+//! - [`TokenBucketState`] - Distributed token-bucket rate limiting (ACTIVE, used for LLM providers)
 //!
 //! Note: `DistributedCircuitBreaker` is fully implemented but not yet integrated.
 //! See the module documentation for planned integration points.
 
 mod circuit_breaker;
 mod distributed_circuit_breaker;
+mod rate_limiter;
 mod retry;
 mod timeout;
 
@@ -18,5 +20,8 @@ pub use circuit_breaker::{CircuitBreakerConfig, CircuitState};
 pub use distributed_circuit_breaker::{
     CircuitBreakerError, CircuitBreakerPermit, DistributedCircuitBreaker,
 };
+pub use rate_limiter::{
+    RateLimitConfig, RateLimitDecision, RateLimitObservation, TokenBucketState,
+};
 pub use retry::RetryPolicy;
-pub use timeout::{TimeoutConfig, TimeoutError, TimeoutManager};
+pub use timeout::{TaskTimingInfo, TimedOutTask, TimeoutConfig, TimeoutError, TimeoutManager};
@@ -0,0 +1,385 @@
+//! Distributed token-bucket rate limiting
+//!
+//! Rate limiters are keyed (e.g. `llm_provider:<id>`) and shared by all
+//! workers through the store, like circuit breakers. Each key has two
+//! buckets, one for requests and one for tokens, refilled continuously at
+//! their per-minute limit. A limit is the lower of the configured limit and
+//! the limit learned from provider responses.
+//!
+//! Providers also report remaining capacity and reset times. Observations
+//! lower the buckets to what the provider reports and, once a bucket is
+//! exhausted or the provider asks callers to back off, block the key until
+//! the reset.
+//!
+//! [`TokenBucketState`] holds the pure bucket logic; stores load the state,
+//! apply [`TokenBucketState::try_acquire`] or [`TokenBucketState::observe`],
+//! and persist it atomically.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Configured limits for a rate limiter key
+///
+/// Unset limits are only enforced once learned from provider responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Maximum requests per minute
+    pub requests_per_minute: Option<u32>,
+    /// Maximum tokens (prompt + completion) per minute
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimitConfig {
+    /// Create a config with no configured limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the requests-per-minute limit
+    pub fn with_requests_per_minute(mut self, limit: u32) -> Self {
+        self.requests_per_minute = Some(limit);
+        self
+    }
+
+    /// Set the tokens-per-minute limit
+    pub fn with_tokens_per_minute(mut self, limit: u32) -> Self {
+        self.tokens_per_minute = Some(limit);
+        self
+    }
+}
+
+/// Rate limit state reported by a provider (usually from response headers)
+///
+/// Limits are treated as per-minute limits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitObservation {
+    /// Requests allowed per minute
+    pub request_limit: Option<u32>,
+    /// Requests remaining in the current window
+    pub requests_remaining: Option<u32>,
+    /// Time until the request window resets
+    pub requests_reset: Option<Duration>,
+    /// Tokens allowed per minute
+    pub token_limit: Option<u32>,
+    /// Tokens remaining in the current window
+    pub tokens_remaining: Option<u32>,
+    /// Time until the token window resets
+    pub tokens_reset: Option<Duration>,
+    /// Provider-requested delay before the next request
+    pub retry_after: Option<Duration>,
+}
+
+/// Outcome of trying to acquire capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    /// Capacity was taken from the buckets
+    Acquired,
+    /// Not enough capacity; retry after this long
+    Wait(Duration),
+}
+
+/// Token bucket state for one rate limiter key
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBucketState {
+    /// Requests available now (infinite while no limit is known)
+    pub available_requests: f64,
+    /// Tokens available now (infinite while no limit is known)
+    pub available_tokens: f64,
+    /// Requests-per-minute limit reported by the provider
+    pub learned_requests_per_minute: Option<u32>,
+    /// Tokens-per-minute limit reported by the provider
+    pub learned_tokens_per_minute: Option<u32>,
+    /// No capacity is handed out before this time
+    pub blocked_until: Option<DateTime<Utc>>,
+    /// When the buckets were last refilled
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TokenBucketState {
+    /// New state with full buckets
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            available_requests: f64::INFINITY,
+            available_tokens: f64::INFINITY,
+            learned_requests_per_minute: None,
+            learned_tokens_per_minute: None,
+            blocked_until: None,
+            updated_at: now,
+        }
+    }
+
+    /// Try to take one request's worth of capacity
+    ///
+    /// `requests` and `tokens` are the capacity needed. A token estimate larger
+    /// than the whole bucket waits for a full bucket instead of never fitting.
+    pub fn try_acquire(
+        &mut self,
+        config: &RateLimitConfig,
+        requests: u32,
+        tokens: u32,
+        now: DateTime<Utc>,
+    ) -> RateLimitDecision {
+        self.refill(config, now);
+
+        if let Some(until) = self.blocked_until {
+            if until > now {
+                return RateLimitDecision::Wait((until - now).to_std().unwrap_or_default());
+            }
+            self.blocked_until = None;
+        }
+
+        let requests_capacity = self.requests_capacity(config);
+        let tokens_capacity = self.tokens_capacity(config);
+        let requests = needed(requests, requests_capacity);
+        let tokens = needed(tokens, tokens_capacity);
+        let wait = wait_for(self.available_requests, requests, requests_capacity).max(wait_for(
+            self.available_tokens,
+            tokens,
+            tokens_capacity,
+        ));
+        if !wait.is_zero() {
+            return RateLimitDecision::Wait(wait);
+        }
+
+        self.available_requests -= requests;
+        self.available_tokens -= tokens;
+        RateLimitDecision::Acquired
+    }
+
+    /// Adapt the buckets to state reported by the provider
+    pub fn observe(
+        &mut self,
+        config: &RateLimitConfig,
+        observation: &RateLimitObservation,
+        now: DateTime<Utc>,
+    ) {
+        self.refill(config, now);
+
+        if let Some(limit) = observation.request_limit.filter(|l| *l > 0) {
+            self.learned_requests_per_minute = Some(limit);
+        }
+        if let Some(limit) = observation.token_limit.filter(|l| *l > 0) {
+            self.learned_tokens_per_minute = Some(limit);
+        }
+        // Apply the (possibly lower) learned limits
+        self.refill(config, now);
+
+        if let Some(remaining) = observation.requests_remaining {
+            self.available_requests = self.available_requests.min(remaining as f64);
+        }
+        if let Some(remaining) = observation.tokens_remaining {
+            self.available_tokens = self.available_tokens.min(remaining as f64);
+        }
+
+        let exhausted_until = |remaining: Option<u32>, reset: Option<Duration>| {
+            reset.filter(|_| remaining == Some(0))
+        };
+        let block = [
+            exhausted_until(observation.requests_remaining, observation.requests_reset),
+            exhausted_until(observation.tokens_remaining, observation.tokens_reset),
+            observation.retry_after,
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        let until = block
+            .and_then(|block| chrono::Duration::from_std(block).ok())
+            .and_then(|block| now.checked_add_signed(block));
+        if let Some(until) = until {
+            self.blocked_until = Some(self.blocked_until.map_or(until, |b| b.max(until)));
+        }
+    }
+
+    /// Effective requests-per-minute limit
+    pub fn requests_capacity(&self, config: &RateLimitConfig) -> Option<u32> {
+        min_limit(config.requests_per_minute, self.learned_requests_per_minute)
+    }
+
+    /// Effective tokens-per-minute limit
+    pub fn tokens_capacity(&self, config: &RateLimitConfig) -> Option<u32> {
+        min_limit(config.tokens_per_minute, self.learned_tokens_per_minute)
+    }
+
+    fn refill(&mut self, config: &RateLimitConfig, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).to_std().unwrap_or_default();
+        let elapsed = elapsed.as_secs_f64();
+        let requests_capacity = self.requests_capacity(config);
+        let tokens_capacity = self.tokens_capacity(config);
+        refill(&mut self.available_requests, requests_capacity, elapsed);
+        refill(&mut self.available_tokens, tokens_capacity, elapsed);
+        self.updated_at = self.updated_at.max(now);
+    }
+}
+
+fn min_limit(configured: Option<u32>, learned: Option<u32>) -> Option<u32> {
+    match (configured, learned) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Add `elapsed` seconds of refill, capped at the bucket capacity
+fn refill(available: &mut f64, capacity: Option<u32>, elapsed: f64) {
+    match capacity {
+        Some(capacity) => {
+            let capacity = capacity as f64;
+            *available = (*available + elapsed * capacity / 60.0).min(capacity);
+        }
+        None => *available = f64::INFINITY,
+    }
+}
+
+fn needed(amount: u32, capacity: Option<u32>) -> f64 {
+    match capacity {
+        Some(capacity) => amount.min(capacity) as f64,
+        None => 0.0,
+    }
+}
+
+/// Time until `needed` is available at the bucket's refill rate
+fn wait_for(available: f64, needed: f64, capacity: Option<u32>) -> Duration {
+    match capacity {
+        Some(capacity) if available < needed => {
+            Duration::from_secs_f64((needed - available) * 60.0 / capacity as f64)
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_unlimited_without_limits() {
+        let mut state = TokenBucketState::new(at(0));
+        let config = RateLimitConfig::new();
+        for _ in 0..1000 {
+            assert_eq!(
+                state.try_acquire(&config, 1, 100_000, at(0)),
+                RateLimitDecision::Acquired
+            );
+        }
+    }
+
+    #[test]
+    fn test_requests_per_minute() {
+        let mut state = TokenBucketState::new(at(0));
+        let config = RateLimitConfig::new().with_requests_per_minute(60);
+        for _ in 0..60 {
+            assert_eq!(
+                state.try_acquire(&config, 1, 0, at(0)),
+                RateLimitDecision::Acquired
+            );
+        }
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(0)),
+            RateLimitDecision::Wait(Duration::from_secs(1))
+        );
+
+        // Refills at one request per second
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(1)),
+            RateLimitDecision::Acquired
+        );
+    }
+
+    #[test]
+    fn test_tokens_per_minute() {
+        let mut state = TokenBucketState::new(at(0));
+        let config = RateLimitConfig::new().with_tokens_per_minute(6000);
+        assert_eq!(
+            state.try_acquire(&config, 1, 4000, at(0)),
+            RateLimitDecision::Acquired
+        );
+        // 2000 left, 1000 more needed at 100 tokens/second
+        assert_eq!(
+            state.try_acquire(&config, 1, 3000, at(0)),
+            RateLimitDecision::Wait(Duration::from_secs(10))
+        );
+        // Oversized requests wait for a full bucket
+        assert_eq!(
+            state.try_acquire(&config, 1, 100_000, at(0)),
+            RateLimitDecision::Wait(Duration::from_secs(40))
+        );
+    }
+
+    #[test]
+    fn test_observation_learns_limits_and_remaining() {
+        let mut state = TokenBucketState::new(at(0));
+        let config = RateLimitConfig::new().with_requests_per_minute(1000);
+        state.observe(
+            &config,
+            &RateLimitObservation {
+                request_limit: Some(120),
+                requests_remaining: Some(1),
+                ..Default::default()
+            },
+            at(0),
+        );
+
+        assert_eq!(state.requests_capacity(&config), Some(120));
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(0)),
+            RateLimitDecision::Acquired
+        );
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(0)),
+            RateLimitDecision::Wait(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn test_exhausted_bucket_blocks_until_reset() {
+        let mut state = TokenBucketState::new(at(0));
+        let config = RateLimitConfig::new();
+        state.observe(
+            &config,
+            &RateLimitObservation {
+                token_limit: Some(60_000),
+                tokens_remaining: Some(0),
+                tokens_reset: Some(Duration::from_secs(30)),
+                ..Default::default()
+            },
+            at(0),
+        );
+
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(10)),
+            RateLimitDecision::Wait(Duration::from_secs(20))
+        );
+        assert_eq!(
+            state.try_acquire(&config, 1, 1000, at(30)),
+            RateLimitDecision::Acquired
+        );
+    }
+
+    #[test]
+    fn test_retry_after_blocks() {
+        let mut state = TokenBucketState::new(at(0));
+        let config = RateLimitConfig::new();
+        state.observe(
+            &config,
+            &RateLimitObservation {
+                retry_after: Some(Duration::from_secs(5)),
+                ..Default::default()
+            },
+            at(0),
+        );
+
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(0)),
+            RateLimitDecision::Wait(Duration::from_secs(5))
+        );
+        assert_eq!(
+            state.try_acquire(&config, 1, 0, at(5)),
+            RateLimitDecision::Acquired
+        );
+    }
+}
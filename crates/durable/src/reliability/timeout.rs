@@ -8,9 +8,16 @@ use std::time::Duration;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
-use crate::persistence::{StoreError, WorkflowEventStore};
+use crate::activity::ActivityError;
+use crate::clock::{Clock, SystemClock};
+use crate::engine::{ExecutorError, WorkflowExecutor};
+use crate::persistence::{StoreError, TaskFailureOutcome, WorkflowEventStore, WorkflowStatus};
+use crate::workflow::{ActivityOptions, WorkflowError, WorkflowEvent};
+
+pub use crate::workflow::TimeoutType;
 
 /// Timeout-related errors
 #[derive(Debug, Error)]
@@ -30,14 +37,18 @@ pub enum TimeoutError {
     /// Store error
     #[error("store error: {0}")]
     Store(#[from] StoreError),
+
+    /// Processing the workflow of an exhausted activity failed
+    #[error("executor error: {0}")]
+    Executor(#[from] ExecutorError),
 }
 
 /// Timeout configuration for activities
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TimeoutConfig {
-    /// Maximum time from scheduling to start of execution
-    #[serde(with = "duration_millis")]
-    pub schedule_to_start: Duration,
+    /// Maximum time from scheduling to start of execution (None = not enforced)
+    #[serde(default, with = "option_duration_millis")]
+    pub schedule_to_start: Option<Duration>,
 
     /// Maximum time from start to completion
     #[serde(with = "duration_millis")]
@@ -51,9 +62,9 @@ pub struct TimeoutConfig {
 impl Default for TimeoutConfig {
     fn default() -> Self {
         Self {
-            schedule_to_start: Duration::from_secs(60), // 1 minute to start
-            start_to_close: Duration::from_secs(300),   // 5 minutes to complete
-            heartbeat: None,                            // No heartbeat by default
+            schedule_to_start: None,                  // Pending tasks wait for a worker
+            start_to_close: Duration::from_secs(300), // 5 minutes to complete
+            heartbeat: None,                          // No heartbeat by default
         }
     }
 }
@@ -66,7 +77,7 @@ impl TimeoutConfig {
 
     /// Set schedule-to-start timeout
     pub fn with_schedule_to_start(mut self, timeout: Duration) -> Self {
-        self.schedule_to_start = timeout;
+        self.schedule_to_start = Some(timeout);
         self
     }
 
@@ -89,14 +100,30 @@ impl TimeoutConfig {
     }
 }
 
+impl From<&ActivityOptions> for TimeoutConfig {
+    fn from(options: &ActivityOptions) -> Self {
+        Self {
+            schedule_to_start: options.schedule_to_start_timeout,
+            start_to_close: options.start_to_close_timeout,
+            heartbeat: options.heartbeat_timeout,
+        }
+    }
+}
+
 /// Information about a task's timing
 #[derive(Debug, Clone)]
 pub struct TaskTimingInfo {
     /// Task ID
     pub task_id: Uuid,
-    /// When the task was scheduled
+    /// Workflow the task belongs to
+    pub workflow_id: Uuid,
+    /// Activity the task executes
+    pub activity_id: String,
+    /// Attempts made so far (claims)
+    pub attempt: u32,
+    /// When the task became claimable (enqueue time, or end of the retry delay)
     pub scheduled_at: DateTime<Utc>,
-    /// When the task started (was claimed)
+    /// When the task started (was claimed); `None` while pending
     pub started_at: Option<DateTime<Utc>>,
     /// Last heartbeat time
     pub last_heartbeat_at: Option<DateTime<Utc>>,
@@ -106,54 +133,82 @@ pub struct TaskTimingInfo {
 
 /// Manages activity timeouts
 ///
-/// The TimeoutManager checks tasks for timeout violations and can
-/// trigger appropriate actions (mark as failed, requeue, etc.).
+/// The TimeoutManager finds tasks that exceeded a timeout and fails them:
+/// the task is retried according to its retry policy or, once attempts are
+/// exhausted, moved to the DLQ. Every timeout is recorded as an
+/// `ActivityTimedOut` workflow event. An exhausted activity is also recorded
+/// as a final `ActivityFailed`, and the workflow is processed by the executor
+/// so its own failure handling decides what happens next. Workflow types the
+/// executor does not know are marked failed: the worker that drives them
+/// never sees the task again.
+///
+/// Schedule-to-start timeouts only apply to activities that set one; pending
+/// tasks otherwise wait for a worker however long fairness caps,
+/// backpressure or an outage hold them back.
 ///
 /// # Example
 ///
 /// ```ignore
 /// use everruns_durable::reliability::TimeoutManager;
 ///
-/// let manager = TimeoutManager::new(store);
+/// let manager = TimeoutManager::new(executor);
 ///
-/// // Check for timed out tasks
-/// let timed_out = manager.find_timed_out_tasks().await?;
-///
-/// for task in timed_out {
-///     manager.handle_timeout(task.task_id, task.timeout_type).await?;
-/// }
+/// // Run periodically (e.g. from a background task)
+/// let timed_out = manager.sweep(100).await?;
 /// ```
-pub struct TimeoutManager {
-    store: Arc<dyn WorkflowEventStore>,
+pub struct TimeoutManager<S: WorkflowEventStore> {
+    executor: WorkflowExecutor<S>,
+    clock: Arc<dyn Clock>,
 }
 
-/// Type of timeout that occurred
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TimeoutType {
-    /// Task waited too long to be claimed
-    ScheduleToStart,
-    /// Task took too long to complete
-    StartToClose,
-    /// No heartbeat received in time
-    Heartbeat,
-}
-
-/// A task that has timed out
+/// A task that has timed out and was failed by [`TimeoutManager::sweep`]
 #[derive(Debug, Clone)]
-#[allow(dead_code)] // Part of public API, will be used in timeout scanning loop
 pub struct TimedOutTask {
     /// Task ID
     pub task_id: Uuid,
+    /// Workflow the task belongs to
+    pub workflow_id: Uuid,
+    /// Activity the task executes
+    pub activity_id: String,
     /// Type of timeout
     pub timeout_type: TimeoutType,
     /// How long the timeout was exceeded by
     pub exceeded_by: Duration,
+    /// Whether the task will be retried or went to the DLQ
+    pub outcome: TaskFailureOutcome,
 }
 
-impl TimeoutManager {
-    /// Create a new timeout manager
-    pub fn new(store: Arc<dyn WorkflowEventStore>) -> Self {
-        Self { store }
+impl<S: WorkflowEventStore> TimeoutManager<S> {
+    /// Create a new timeout manager using the system clock
+    pub fn new(executor: WorkflowExecutor<S>) -> Self {
+        Self {
+            executor,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Executor that processes workflows after a final timeout
+    pub fn executor(&self) -> &WorkflowExecutor<S> {
+        &self.executor
+    }
+
+    fn store(&self) -> &S {
+        self.executor.store()
+    }
+
+    /// Use a different clock (for tests)
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Time elapsed since `since` (zero if in the future)
+    fn elapsed_since(&self, since: DateTime<Utc>) -> Duration {
+        self.clock
+            .now()
+            .signed_duration_since(since)
+            .to_std()
+            .unwrap_or(Duration::ZERO)
     }
 
     /// Check if a task has exceeded its schedule-to-start timeout
@@ -163,21 +218,17 @@ impl TimeoutManager {
         started_at: Option<DateTime<Utc>>,
         config: &TimeoutConfig,
     ) -> Option<TimeoutError> {
+        let limit = config.schedule_to_start?;
+
         // Only check if task hasn't started yet
         if started_at.is_some() {
             return None;
         }
 
-        let elapsed = Utc::now()
-            .signed_duration_since(scheduled_at)
-            .to_std()
-            .unwrap_or(Duration::ZERO);
+        let elapsed = self.elapsed_since(scheduled_at);
 
-        if elapsed > config.schedule_to_start {
-            Some(TimeoutError::ScheduleToStartTimeout {
-                elapsed,
-                limit: config.schedule_to_start,
-            })
+        if elapsed > limit {
+            Some(TimeoutError::ScheduleToStartTimeout { elapsed, limit })
         } else {
             None
         }
@@ -191,10 +242,7 @@ impl TimeoutManager {
     ) -> Option<TimeoutError> {
         let started = started_at?;
 
-        let elapsed = Utc::now()
-            .signed_duration_since(started)
-            .to_std()
-            .unwrap_or(Duration::ZERO);
+        let elapsed = self.elapsed_since(started);
 
         if elapsed > config.start_to_close {
             Some(TimeoutError::StartToCloseTimeout {
@@ -221,10 +269,7 @@ impl TimeoutManager {
         // Use last heartbeat time, or started_at if no heartbeat yet
         let last_beat = last_heartbeat_at.or(started_at)?;
 
-        let elapsed = Utc::now()
-            .signed_duration_since(last_beat)
-            .to_std()
-            .unwrap_or(Duration::ZERO);
+        let elapsed = self.elapsed_since(last_beat);
 
         if elapsed > heartbeat_timeout {
             Some(TimeoutError::HeartbeatTimeout {
@@ -267,30 +312,166 @@ impl TimeoutManager {
         None
     }
 
+    /// Find timed out tasks and fail them (up to `limit` tasks per call)
+    pub async fn sweep(&self, limit: usize) -> Result<Vec<TimedOutTask>, TimeoutError> {
+        let candidates = self
+            .store()
+            .list_timeout_candidates(self.clock.now(), limit)
+            .await?;
+
+        let mut timed_out = vec![];
+        for timing in candidates {
+            let Some((timeout_type, error)) = self.check_task_timeout(&timing) else {
+                continue;
+            };
+            let exceeded_by = error.exceeded_by();
+            if let Some(outcome) = self.handle_timeout(&timing, timeout_type, &error).await? {
+                timed_out.push(TimedOutTask {
+                    task_id: timing.task_id,
+                    workflow_id: timing.workflow_id,
+                    activity_id: timing.activity_id.clone(),
+                    timeout_type,
+                    exceeded_by,
+                    outcome,
+                });
+            }
+        }
+
+        Ok(timed_out)
+    }
+
     /// Handle a timed out task by failing it
+    ///
+    /// Returns `None` if the task changed state since `timing` was read.
     pub async fn handle_timeout(
         &self,
-        task_id: Uuid,
+        timing: &TaskTimingInfo,
         timeout_type: TimeoutType,
-    ) -> Result<(), TimeoutError> {
-        let error_message = match timeout_type {
-            TimeoutType::ScheduleToStart => "Task timed out waiting to start",
-            TimeoutType::StartToClose => "Task execution timed out",
-            TimeoutType::Heartbeat => "Task heartbeat timed out",
+        error: &TimeoutError,
+    ) -> Result<Option<TaskFailureOutcome>, TimeoutError> {
+        let error_message = error.to_string();
+        let Some(outcome) = self
+            .store()
+            .fail_timed_out_task(timing, &error_message)
+            .await?
+        else {
+            return Ok(None);
         };
 
-        self.store.fail_task(task_id, error_message).await?;
+        let timed_out = WorkflowEvent::ActivityTimedOut {
+            activity_id: timing.activity_id.clone(),
+            timeout_type,
+        };
+        match &outcome {
+            TaskFailureOutcome::WillRetry { next_attempt, .. } => {
+                // The timeout is informational while the activity retries
+                if let Err(e) = self
+                    .record_events(timing.workflow_id, vec![timed_out])
+                    .await
+                {
+                    warn!(
+                        task_id = %timing.task_id,
+                        workflow_id = %timing.workflow_id,
+                        error = %e,
+                        "Failed to record activity timeout event"
+                    );
+                }
+                debug!(
+                    task_id = %timing.task_id,
+                    ?timeout_type,
+                    next_attempt,
+                    "Timed out task will retry"
+                );
+            }
+            TaskFailureOutcome::MovedToDlq | TaskFailureOutcome::ExhaustedRetries => {
+                self.store()
+                    .move_to_dlq(timing.task_id, vec![error_message.clone()])
+                    .await?;
+                let failed = WorkflowEvent::ActivityFailed {
+                    activity_id: timing.activity_id.clone(),
+                    error: ActivityError::non_retryable(error_message.clone())
+                        .with_type("activity_timed_out"),
+                    will_retry: false,
+                };
+                self.record_events(timing.workflow_id, vec![timed_out, failed])
+                    .await?;
+                self.process_workflow(timing.workflow_id, &error_message)
+                    .await?;
+                debug!(
+                    task_id = %timing.task_id,
+                    ?timeout_type,
+                    "Timed out task moved to DLQ"
+                );
+            }
+        }
+
+        Ok(Some(outcome))
+    }
+
+    /// Let the executor react to the recorded failure if it runs the workflow
+    /// type; otherwise fail the workflow with the activity
+    async fn process_workflow(&self, workflow_id: Uuid, error: &str) -> Result<(), TimeoutError> {
+        let info = self.store().get_workflow_info(workflow_id).await?;
+        if self.executor.registry().contains(&info.workflow_type) {
+            self.executor.process_workflow(workflow_id).await?;
+        } else if !info.status.is_terminal() {
+            self.store()
+                .update_workflow_status(
+                    workflow_id,
+                    WorkflowStatus::Failed,
+                    None,
+                    Some(WorkflowError::new(error).with_code("activity_timed_out")),
+                )
+                .await?;
+        }
         Ok(())
     }
 
+    /// Append events to a workflow, retrying on concurrent appends
+    async fn record_events(
+        &self,
+        workflow_id: Uuid,
+        events: Vec<WorkflowEvent>,
+    ) -> Result<(), StoreError> {
+        const MAX_APPEND_ATTEMPTS: usize = 3;
+
+        let mut attempt = 1;
+        loop {
+            let sequence = self
+                .store()
+                .load_events(workflow_id)
+                .await?
+                .last()
+                .map(|(sequence, _)| sequence + 1)
+                .unwrap_or(0);
+            match self
+                .store()
+                .append_events(workflow_id, sequence, events.clone())
+                .await
+            {
+                Err(StoreError::ConcurrencyConflict { .. }) if attempt < MAX_APPEND_ATTEMPTS => {
+                    attempt += 1;
+                }
+                result => return result.map(|_| ()),
+            }
+        }
+    }
+
     /// Calculate remaining time for a timeout
     pub fn remaining_time(&self, started_at: DateTime<Utc>, timeout: Duration) -> Option<Duration> {
-        let elapsed = Utc::now()
-            .signed_duration_since(started_at)
-            .to_std()
-            .unwrap_or(Duration::ZERO);
+        timeout.checked_sub(self.elapsed_since(started_at))
+    }
+}
 
-        timeout.checked_sub(elapsed)
+impl TimeoutError {
+    /// How long the limit was exceeded by (zero for store and executor errors)
+    pub fn exceeded_by(&self) -> Duration {
+        match self {
+            Self::ScheduleToStartTimeout { elapsed, limit }
+            | Self::StartToCloseTimeout { elapsed, limit }
+            | Self::HeartbeatTimeout { elapsed, limit } => elapsed.saturating_sub(*limit),
+            Self::Store(_) | Self::Executor(_) => Duration::ZERO,
+        }
     }
 }
 
@@ -342,18 +523,79 @@ mod option_duration_millis {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::persistence::InMemoryWorkflowEventStore;
+    use crate::clock::ManualClock;
+    use crate::persistence::{InMemoryWorkflowEventStore, TaskDefinition};
+    use crate::reliability::RetryPolicy;
+    use crate::workflow::{Workflow, WorkflowAction};
     use chrono::Duration as ChronoDuration;
 
-    fn create_test_manager() -> TimeoutManager {
-        let store = Arc::new(InMemoryWorkflowEventStore::new());
-        TimeoutManager::new(store)
+    fn create_test_manager() -> TimeoutManager<InMemoryWorkflowEventStore> {
+        TimeoutManager::new(WorkflowExecutor::new(InMemoryWorkflowEventStore::new()))
+    }
+
+    /// Runs one activity with the given options and fails when it fails
+    struct StepWorkflow {
+        options: ActivityOptions,
+        error: Option<WorkflowError>,
+    }
+
+    impl Workflow for StepWorkflow {
+        const TYPE: &'static str = "step_workflow";
+        type Input = ActivityOptions;
+        type Output = serde_json::Value;
+
+        fn new(options: Self::Input) -> Self {
+            Self {
+                options,
+                error: None,
+            }
+        }
+
+        fn on_start(&mut self) -> Vec<WorkflowAction> {
+            vec![WorkflowAction::ScheduleActivity {
+                activity_id: "step-1".to_string(),
+                activity_type: "test_activity".to_string(),
+                input: serde_json::json!({}),
+                options: self.options.clone(),
+            }]
+        }
+
+        fn on_activity_completed(
+            &mut self,
+            _activity_id: &str,
+            _result: serde_json::Value,
+        ) -> Vec<WorkflowAction> {
+            vec![WorkflowAction::complete(serde_json::json!({}))]
+        }
+
+        fn on_activity_failed(
+            &mut self,
+            _activity_id: &str,
+            error: &ActivityError,
+        ) -> Vec<WorkflowAction> {
+            let error = WorkflowError::new(&error.message)
+                .with_code(error.error_type.clone().unwrap_or_default());
+            self.error = Some(error.clone());
+            vec![WorkflowAction::fail(error)]
+        }
+
+        fn is_completed(&self) -> bool {
+            self.error.is_some()
+        }
+
+        fn result(&self) -> Option<Self::Output> {
+            None
+        }
+
+        fn error(&self) -> Option<WorkflowError> {
+            self.error.clone()
+        }
     }
 
     #[test]
     fn test_timeout_config_defaults() {
         let config = TimeoutConfig::default();
-        assert_eq!(config.schedule_to_start, Duration::from_secs(60));
+        assert!(config.schedule_to_start.is_none());
         assert_eq!(config.start_to_close, Duration::from_secs(300));
         assert!(config.heartbeat.is_none());
     }
@@ -365,7 +607,7 @@ mod tests {
             .with_start_to_close(Duration::from_secs(600))
             .with_heartbeat(Duration::from_secs(10));
 
-        assert_eq!(config.schedule_to_start, Duration::from_secs(30));
+        assert_eq!(config.schedule_to_start, Some(Duration::from_secs(30)));
         assert_eq!(config.start_to_close, Duration::from_secs(600));
         assert_eq!(config.heartbeat, Some(Duration::from_secs(10)));
     }
@@ -374,7 +616,7 @@ mod tests {
     fn test_schedule_to_start_not_started() {
         let manager = create_test_manager();
         let scheduled_at = Utc::now() - ChronoDuration::seconds(120);
-        let config = TimeoutConfig::default(); // 60s schedule-to-start
+        let config = TimeoutConfig::default().with_schedule_to_start(Duration::from_secs(60));
 
         let result = manager.check_schedule_to_start(scheduled_at, None, &config);
         assert!(result.is_some());
@@ -384,12 +626,21 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_schedule_to_start_not_enforced_by_default() {
+        let manager = create_test_manager();
+        let scheduled_at = Utc::now() - ChronoDuration::days(1);
+
+        let result = manager.check_schedule_to_start(scheduled_at, None, &TimeoutConfig::default());
+        assert!(result.is_none());
+    }
+
     #[test]
     fn test_schedule_to_start_already_started() {
         let manager = create_test_manager();
         let scheduled_at = Utc::now() - ChronoDuration::seconds(120);
         let started_at = Some(Utc::now() - ChronoDuration::seconds(60));
-        let config = TimeoutConfig::default();
+        let config = TimeoutConfig::default().with_schedule_to_start(Duration::from_secs(60));
 
         let result = manager.check_schedule_to_start(scheduled_at, started_at, &config);
         assert!(result.is_none());
@@ -475,4 +726,273 @@ mod tests {
         let parsed: TimeoutConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(config, parsed);
     }
+
+    struct SweepFixture {
+        clock: Arc<ManualClock>,
+        manager: TimeoutManager<InMemoryWorkflowEventStore>,
+        workflow_id: Uuid,
+    }
+
+    impl SweepFixture {
+        fn store(&self) -> &InMemoryWorkflowEventStore {
+            self.manager.store()
+        }
+    }
+
+    async fn sweep_fixture(options: ActivityOptions) -> (SweepFixture, Uuid) {
+        let clock = Arc::new(ManualClock::new());
+        let mut executor =
+            WorkflowExecutor::new(InMemoryWorkflowEventStore::with_clock(clock.clone()));
+        executor.register::<StepWorkflow>();
+        let workflow_id = executor
+            .start_workflow::<StepWorkflow>(options, None)
+            .await
+            .unwrap();
+        let manager = TimeoutManager::new(executor).with_clock(clock.clone());
+        let task_id = manager
+            .store()
+            .list_timeout_candidates(clock.now(), 1)
+            .await
+            .unwrap()[0]
+            .task_id;
+        let fixture = SweepFixture {
+            clock,
+            manager,
+            workflow_id,
+        };
+        (fixture, task_id)
+    }
+
+    async fn claim(store: &InMemoryWorkflowEventStore) {
+        let claimed = store
+            .claim_task("worker-1", &["test_activity".to_string()], 1)
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+    }
+
+    async fn timeout_events(fixture: &SweepFixture) -> Vec<TimeoutType> {
+        fixture
+            .store()
+            .load_events(fixture.workflow_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|(_, event)| match event {
+                WorkflowEvent::ActivityTimedOut { timeout_type, .. } => Some(timeout_type),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sweep_schedule_to_start_retries() {
+        let options = ActivityOptions::default()
+            .with_schedule_to_start_timeout(Duration::from_secs(30))
+            .with_retry(RetryPolicy::fixed(Duration::from_secs(10), 3));
+        let (fixture, task_id) = sweep_fixture(options).await;
+
+        fixture.clock.advance(Duration::from_secs(20));
+        assert!(fixture.manager.sweep(10).await.unwrap().is_empty());
+
+        fixture.clock.advance(Duration::from_secs(15));
+        let timed_out = fixture.manager.sweep(10).await.unwrap();
+        assert_eq!(timed_out.len(), 1);
+        assert_eq!(timed_out[0].task_id, task_id);
+        assert_eq!(timed_out[0].timeout_type, TimeoutType::ScheduleToStart);
+        assert_eq!(timed_out[0].exceeded_by, Duration::from_secs(5));
+        assert!(matches!(
+            timed_out[0].outcome,
+            TaskFailureOutcome::WillRetry {
+                next_attempt: 2,
+                ..
+            }
+        ));
+        assert_eq!(
+            timeout_events(&fixture).await,
+            [TimeoutType::ScheduleToStart]
+        );
+
+        // The retry delay restarts the schedule-to-start window
+        fixture.clock.advance(Duration::from_secs(30));
+        assert!(fixture.manager.sweep(10).await.unwrap().is_empty());
+        assert_eq!(fixture.store().pending_task_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_start_to_close_retries() {
+        let options = ActivityOptions::default()
+            .with_start_to_close_timeout(Duration::from_secs(60))
+            .with_retry(RetryPolicy::fixed(Duration::from_secs(1), 3));
+        let (fixture, _) = sweep_fixture(options).await;
+        claim(fixture.store()).await;
+
+        fixture.clock.advance(Duration::from_secs(61));
+        let timed_out = fixture.manager.sweep(10).await.unwrap();
+        assert_eq!(timed_out.len(), 1);
+        assert_eq!(timed_out[0].timeout_type, TimeoutType::StartToClose);
+        assert!(matches!(
+            timed_out[0].outcome,
+            TaskFailureOutcome::WillRetry {
+                next_attempt: 2,
+                ..
+            }
+        ));
+        assert_eq!(fixture.store().pending_task_count(), 1);
+        assert_eq!(timeout_events(&fixture).await, [TimeoutType::StartToClose]);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_heartbeat_timeout() {
+        let options = ActivityOptions::default().with_heartbeat(Duration::from_secs(10));
+        let (fixture, task_id) = sweep_fixture(options).await;
+        claim(fixture.store()).await;
+
+        // Heartbeats keep the task alive
+        fixture.clock.advance(Duration::from_secs(8));
+        fixture
+            .store()
+            .heartbeat_task(task_id, "worker-1", None)
+            .await
+            .unwrap();
+        fixture.clock.advance(Duration::from_secs(8));
+        assert!(fixture.manager.sweep(10).await.unwrap().is_empty());
+
+        fixture.clock.advance(Duration::from_secs(5));
+        let timed_out = fixture.manager.sweep(10).await.unwrap();
+        assert_eq!(timed_out.len(), 1);
+        assert_eq!(timed_out[0].timeout_type, TimeoutType::Heartbeat);
+        assert_eq!(timeout_events(&fixture).await, [TimeoutType::Heartbeat]);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_exhausted_moves_to_dlq() {
+        let options = ActivityOptions::default()
+            .with_start_to_close_timeout(Duration::from_secs(60))
+            .with_retry(RetryPolicy::no_retry());
+        let (fixture, _) = sweep_fixture(options).await;
+        claim(fixture.store()).await;
+
+        fixture.clock.advance(Duration::from_secs(61));
+        let timed_out = fixture.manager.sweep(10).await.unwrap();
+        assert_eq!(timed_out.len(), 1);
+        assert!(matches!(
+            timed_out[0].outcome,
+            TaskFailureOutcome::MovedToDlq
+        ));
+        assert_eq!(fixture.store().dlq_count(), 1);
+        assert_eq!(fixture.store().pending_task_count(), 0);
+
+        // The workflow failed through its own activity failure handling
+        let events = fixture
+            .store()
+            .load_events(fixture.workflow_id)
+            .await
+            .unwrap();
+        assert!(events.iter().any(|(_, event)| matches!(
+            event,
+            WorkflowEvent::ActivityFailed {
+                will_retry: false,
+                ..
+            }
+        )));
+        let info = fixture
+            .store()
+            .get_workflow_info(fixture.workflow_id)
+            .await
+            .unwrap();
+        assert_eq!(info.status, WorkflowStatus::Failed);
+        assert_eq!(
+            info.error.and_then(|e| e.code).as_deref(),
+            Some("activity_timed_out")
+        );
+
+        // Dead tasks are not swept again
+        fixture.clock.advance(Duration::from_secs(600));
+        assert!(fixture.manager.sweep(10).await.unwrap().is_empty());
+        assert_eq!(timeout_events(&fixture).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_fails_unregistered_workflow() {
+        let clock = Arc::new(ManualClock::new());
+        let store = InMemoryWorkflowEventStore::with_clock(clock.clone());
+        let workflow_id = Uuid::now_v7();
+        store
+            .create_workflow(workflow_id, "driven_elsewhere", serde_json::json!({}), None)
+            .await
+            .unwrap();
+        let options = ActivityOptions::default()
+            .with_start_to_close_timeout(Duration::from_secs(60))
+            .with_retry(RetryPolicy::no_retry());
+        store
+            .enqueue_task(TaskDefinition {
+                workflow_id,
+                activity_id: "step".to_string(),
+                activity_type: "test_activity".to_string(),
+                input: serde_json::json!({}),
+                options,
+            })
+            .await
+            .unwrap();
+        claim(&store).await;
+        let manager = TimeoutManager::new(WorkflowExecutor::new(store)).with_clock(clock.clone());
+
+        clock.advance(Duration::from_secs(61));
+        let timed_out = manager.sweep(10).await.unwrap();
+        assert_eq!(timed_out.len(), 1);
+        assert_eq!(timed_out[0].activity_id, "step");
+
+        let info = manager
+            .store()
+            .get_workflow_info(workflow_id)
+            .await
+            .unwrap();
+        assert_eq!(info.status, WorkflowStatus::Failed);
+        assert_eq!(
+            info.error.and_then(|e| e.code).as_deref(),
+            Some("activity_timed_out")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sweep_leaves_pending_tasks_without_schedule_to_start() {
+        let (fixture, _) = sweep_fixture(ActivityOptions::default()).await;
+
+        // E.g. held back by a fairness cap or with no worker running
+        fixture.clock.advance(Duration::from_secs(24 * 60 * 60));
+        assert!(fixture.manager.sweep(10).await.unwrap().is_empty());
+        assert_eq!(fixture.store().pending_task_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_handle_timeout_ignores_completed_task() {
+        let options =
+            ActivityOptions::default().with_start_to_close_timeout(Duration::from_secs(60));
+        let (fixture, task_id) = sweep_fixture(options).await;
+        claim(fixture.store()).await;
+
+        fixture.clock.advance(Duration::from_secs(61));
+        let candidates = fixture
+            .store()
+            .list_timeout_candidates(fixture.clock.now(), 10)
+            .await
+            .unwrap();
+        let (timeout_type, error) = fixture.manager.check_task_timeout(&candidates[0]).unwrap();
+
+        // The task completes between the check and the timeout handling
+        fixture
+            .store()
+            .complete_task(task_id, serde_json::json!({}))
+            .await
+            .unwrap();
+
+        let outcome = fixture
+            .manager
+            .handle_timeout(&candidates[0], timeout_type, &error)
+            .await
+            .unwrap();
+        assert!(outcome.is_none());
+        assert!(timeout_events(&fixture).await.is_empty());
+    }
 }
@@ -6,10 +6,16 @@ use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use serde::{Deserialize, Serialize};
 
+use super::resources::ResourceUsage;
+
 /// Backpressure configuration
 ///
 /// Controls when workers start rejecting new tasks based on load.
 ///
+/// The memory and CPU thresholds play the role of the high watermark: the
+/// worker stops claiming tasks when a measurement reaches its threshold and
+/// resumes once it drops to `threshold * low_watermark / high_watermark`.
+///
 /// # Example
 ///
 /// ```
@@ -32,7 +38,7 @@ pub struct BackpressureConfig {
     /// Memory pressure threshold in bytes (optional)
     pub memory_threshold: Option<usize>,
 
-    /// CPU pressure threshold as percentage (optional)
+    /// CPU pressure threshold as a ratio of the available CPUs (optional)
     pub cpu_threshold: Option<f64>,
 }
 
@@ -77,6 +83,20 @@ impl BackpressureConfig {
         self
     }
 
+    /// Whether memory or CPU usage needs to be sampled
+    pub fn has_resource_thresholds(&self) -> bool {
+        self.memory_threshold.is_some() || self.cpu_threshold.is_some()
+    }
+
+    /// Ratio of a resource threshold at which task acceptance resumes
+    fn resume_ratio(&self) -> f64 {
+        if self.high_watermark > 0.0 {
+            self.low_watermark / self.high_watermark
+        } else {
+            0.0
+        }
+    }
+
     /// Validate the configuration
     pub fn validate(&self) -> Result<(), BackpressureError> {
         if self.low_watermark >= self.high_watermark {
@@ -99,13 +119,16 @@ pub enum BackpressureError {
 /// Backpressure state for a worker
 ///
 /// Tracks current load and determines when to accept or reject new tasks.
-/// Uses atomic operations for thread-safe access without locks.
+/// Uses atomic operations for thread-safe access without locks. Memory and
+/// CPU usage are only considered once recorded with
+/// [`record_resources`](Self::record_resources).
 pub struct BackpressureState {
     config: BackpressureConfig,
     current_load: AtomicUsize,
     max_concurrency: usize,
     accepting_tasks: AtomicBool,
     backpressure_reason: std::sync::RwLock<Option<String>>,
+    resources: std::sync::RwLock<ResourceUsage>,
 }
 
 impl BackpressureState {
@@ -117,6 +140,7 @@ impl BackpressureState {
             max_concurrency,
             accepting_tasks: AtomicBool::new(true),
             backpressure_reason: std::sync::RwLock::new(None),
+            resources: std::sync::RwLock::new(ResourceUsage::default()),
         }
     }
 
@@ -125,23 +149,18 @@ impl BackpressureState {
     /// Implements hysteresis using high/low watermarks to prevent oscillation.
     pub fn should_accept(&self) -> bool {
         let currently_accepting = self.accepting_tasks.load(Ordering::Relaxed);
-        let load = self.current_load.load(Ordering::Relaxed);
-        let load_ratio = load as f64 / self.max_concurrency.max(1) as f64;
 
         if currently_accepting {
             // If accepting, check if we should stop (high watermark)
-            if load_ratio >= self.config.high_watermark {
+            if let Some(reason) = self.overload_reason() {
                 self.accepting_tasks.store(false, Ordering::Relaxed);
-                *self.backpressure_reason.write().unwrap() = Some(format!(
-                    "load ratio {:.1}% exceeds high watermark",
-                    load_ratio * 100.0
-                ));
+                *self.backpressure_reason.write().unwrap() = Some(reason);
                 return false;
             }
             true
         } else {
             // If not accepting, check if we should resume (low watermark)
-            if load_ratio <= self.config.low_watermark {
+            if self.below_low_watermarks() {
                 self.accepting_tasks.store(true, Ordering::Relaxed);
                 *self.backpressure_reason.write().unwrap() = None;
                 return true;
@@ -150,6 +169,69 @@ impl BackpressureState {
         }
     }
 
+    /// Why the worker is overloaded, if load, memory or CPU reached its high mark
+    fn overload_reason(&self) -> Option<String> {
+        let load_ratio = self.load_ratio();
+        if load_ratio >= self.config.high_watermark {
+            return Some(format!(
+                "load ratio {:.1}% exceeds high watermark",
+                load_ratio * 100.0
+            ));
+        }
+
+        let resources = *self.resources.read().unwrap();
+        if let (Some(threshold), Some(memory)) =
+            (self.config.memory_threshold, resources.memory_bytes())
+        {
+            if memory >= threshold as u64 {
+                return Some(format!(
+                    "memory usage {} MiB exceeds threshold of {} MiB",
+                    memory / (1024 * 1024),
+                    threshold / (1024 * 1024)
+                ));
+            }
+        }
+        if let (Some(threshold), Some(cpu)) = (self.config.cpu_threshold, resources.cpu_ratio) {
+            if cpu >= threshold {
+                return Some(format!(
+                    "CPU usage {:.1}% exceeds threshold of {:.1}%",
+                    cpu * 100.0,
+                    threshold * 100.0
+                ));
+            }
+        }
+        None
+    }
+
+    /// Whether load, memory and CPU are all back at or below their low marks
+    fn below_low_watermarks(&self) -> bool {
+        if self.load_ratio() > self.config.low_watermark {
+            return false;
+        }
+
+        let resume_ratio = self.config.resume_ratio();
+        let resources = *self.resources.read().unwrap();
+        let memory_ok = match (self.config.memory_threshold, resources.memory_bytes()) {
+            (Some(threshold), Some(memory)) => memory as f64 <= threshold as f64 * resume_ratio,
+            _ => true,
+        };
+        let cpu_ok = match (self.config.cpu_threshold, resources.cpu_ratio) {
+            (Some(threshold), Some(cpu)) => cpu <= threshold * resume_ratio,
+            _ => true,
+        };
+        memory_ok && cpu_ok
+    }
+
+    /// Record the latest memory and CPU measurements
+    pub fn record_resources(&self, usage: ResourceUsage) {
+        *self.resources.write().unwrap() = usage;
+    }
+
+    /// Get the latest recorded memory and CPU measurements
+    pub fn resource_usage(&self) -> ResourceUsage {
+        *self.resources.read().unwrap()
+    }
+
     /// Get the current load
     pub fn current_load(&self) -> usize {
         self.current_load.load(Ordering::Relaxed)
@@ -199,7 +281,7 @@ impl BackpressureState {
 
     /// Resume accepting tasks (if below low watermark)
     pub fn resume(&self) {
-        if self.load_ratio() <= self.config.low_watermark {
+        if self.below_low_watermarks() {
             self.accepting_tasks.store(true, Ordering::Relaxed);
             *self.backpressure_reason.write().unwrap() = None;
         }
@@ -346,4 +428,93 @@ mod tests {
         assert_eq!(state.available_slots(), 7);
         assert_eq!(state.current_load(), 3);
     }
+
+    fn usage(memory_mib: Option<u64>, cpu_ratio: Option<f64>) -> ResourceUsage {
+        ResourceUsage {
+            process_memory_bytes: memory_mib.map(|mib| mib * 1024 * 1024),
+            cgroup_memory_bytes: None,
+            cpu_ratio,
+        }
+    }
+
+    #[test]
+    fn test_memory_threshold_hysteresis() {
+        // Resume at 1000 MiB * 0.5 / 0.8 = 625 MiB
+        let config = BackpressureConfig::new()
+            .with_high_watermark(0.8)
+            .with_low_watermark(0.5)
+            .with_memory_threshold(1000 * 1024 * 1024);
+        let state = BackpressureState::new(config, 10);
+
+        state.record_resources(usage(Some(900), None));
+        assert!(state.should_accept());
+
+        state.record_resources(usage(Some(1000), None));
+        assert!(!state.should_accept());
+        assert_eq!(
+            state.backpressure_reason(),
+            Some("memory usage 1000 MiB exceeds threshold of 1000 MiB".to_string())
+        );
+
+        // Between the marks: still paused
+        state.record_resources(usage(Some(700), None));
+        assert!(!state.should_accept());
+
+        state.record_resources(usage(Some(600), None));
+        assert!(state.should_accept());
+        assert!(state.backpressure_reason().is_none());
+    }
+
+    #[test]
+    fn test_cpu_threshold_hysteresis() {
+        // Resume at 0.8 * 0.7 / 0.9 = ~0.62
+        let config = BackpressureConfig::default().with_cpu_threshold(0.8);
+        let state = BackpressureState::new(config, 10);
+
+        state.record_resources(usage(None, Some(0.85)));
+        assert!(!state.should_accept());
+        assert!(state
+            .backpressure_reason()
+            .unwrap()
+            .starts_with("CPU usage"));
+
+        state.record_resources(usage(None, Some(0.7)));
+        assert!(!state.should_accept());
+
+        state.record_resources(usage(None, Some(0.6)));
+        assert!(state.should_accept());
+    }
+
+    #[test]
+    fn test_resources_ignored_without_thresholds() {
+        let state = BackpressureState::new(BackpressureConfig::default(), 10);
+        state.record_resources(usage(Some(u64::MAX / (2 * 1024 * 1024)), Some(1.0)));
+        assert!(state.should_accept());
+    }
+
+    #[test]
+    fn test_resume_requires_all_resources_below_low_watermark() {
+        let config = BackpressureConfig::new()
+            .with_high_watermark(0.8)
+            .with_low_watermark(0.5)
+            .with_memory_threshold(1000 * 1024 * 1024);
+        let state = BackpressureState::new(config, 10);
+
+        for _ in 0..8 {
+            state.task_started();
+        }
+        state.record_resources(usage(Some(700), None));
+        assert!(!state.should_accept());
+
+        // Load is back down, but memory is still above its low mark
+        for _ in 0..8 {
+            state.task_completed();
+        }
+        assert!(!state.should_accept());
+        state.resume();
+        assert!(!state.is_accepting());
+
+        state.record_resources(usage(Some(600), None));
+        assert!(state.should_accept());
+    }
 }
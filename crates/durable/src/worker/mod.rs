@@ -3,6 +3,7 @@
 //! This module provides:
 //! - [`WorkerPool`] - Main worker pool with concurrent task execution
 //! - [`BackpressureConfig`] - Load-aware task acceptance configuration
+//! - [`ResourceMonitor`] - Process and cgroup memory/CPU sampling for backpressure
 //! - [`PollerConfig`] - Task polling with exponential backoff
 //!
 //! # Architecture
@@ -18,7 +19,7 @@
 //! │         ▼                                                    │
 //! │  ┌─────────────────────────────────────────────────────┐    │
 //! │  │              BackpressureState                       │    │
-//! │  │  (high/low watermarks, load tracking)               │    │
+//! │  │  (high/low watermarks, load, memory and CPU)        │    │
 //! │  └─────────────────────────────────────────────────────┘    │
 //! │         │                                                    │
 //! │         ▼                                                    │
@@ -57,7 +58,9 @@
 mod backpressure;
 mod poller;
 mod pool;
+mod resources;
 
 pub use backpressure::{BackpressureConfig, BackpressureError, BackpressureState};
 pub use poller::{AdaptivePoller, PollerConfig, PollerError, TaskPoller};
 pub use pool::{WorkerPool, WorkerPoolConfig, WorkerPoolError, WorkerPoolStatus};
+pub use resources::{ResourceMonitor, ResourceUsage};
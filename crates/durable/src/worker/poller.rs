@@ -10,6 +10,7 @@ use tokio::sync::watch;
 use tracing::{debug, instrument, trace};
 
 use crate::persistence::{ClaimedTask, StoreError, WorkflowEventStore};
+use crate::workflow::DEFAULT_TASK_QUEUE;
 
 /// Polling configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -78,6 +79,7 @@ impl PollerConfig {
 pub struct TaskPoller {
     store: Arc<dyn WorkflowEventStore>,
     worker_id: String,
+    task_queues: Vec<String>,
     activity_types: Vec<String>,
     config: PollerConfig,
     current_interval: Duration,
@@ -85,7 +87,7 @@ pub struct TaskPoller {
 }
 
 impl TaskPoller {
-    /// Create a new task poller
+    /// Create a new task poller for the default task queue
     pub fn new(
         store: Arc<dyn WorkflowEventStore>,
         worker_id: String,
@@ -96,6 +98,7 @@ impl TaskPoller {
         Self {
             store,
             worker_id,
+            task_queues: vec![DEFAULT_TASK_QUEUE.to_string()],
             activity_types,
             config: config.clone(),
             current_interval: config.min_interval,
@@ -103,6 +106,12 @@ impl TaskPoller {
         }
     }
 
+    /// Poll the given task queues instead of the default one
+    pub fn with_task_queues(mut self, task_queues: Vec<String>) -> Self {
+        self.task_queues = task_queues;
+        self
+    }
+
     /// Poll for available tasks
     ///
     /// Returns claimed tasks and updates internal backoff state.
@@ -118,7 +127,12 @@ impl TaskPoller {
 
         let tasks = self
             .store
-            .claim_task(&self.worker_id, &self.activity_types, batch_size)
+            .claim_task_from_queues(
+                &self.worker_id,
+                &self.task_queues,
+                &self.activity_types,
+                batch_size,
+            )
             .await
             .map_err(PollerError::Store)?;
 
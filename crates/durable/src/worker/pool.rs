@@ -15,7 +15,9 @@ use uuid::Uuid;
 
 use super::backpressure::{BackpressureConfig, BackpressureState};
 use super::poller::{PollerConfig, PollerError, TaskPoller};
+use super::resources::ResourceMonitor;
 use crate::persistence::{ClaimedTask, StoreError, WorkerInfo, WorkflowEventStore};
+use crate::workflow::DEFAULT_TASK_QUEUE;
 
 /// Worker pool configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +31,9 @@ pub struct WorkerPoolConfig {
     /// Activity types this worker handles
     pub activity_types: Vec<String>,
 
+    /// Task queues this worker claims from
+    pub task_queues: Vec<String>,
+
     /// Maximum concurrent task executions
     pub max_concurrency: usize,
 
@@ -53,6 +58,11 @@ pub struct WorkerPoolConfig {
     /// Graceful shutdown timeout
     #[serde(with = "duration_millis")]
     pub shutdown_timeout: Duration,
+
+    /// Memory/CPU sampling interval (only used when the backpressure config
+    /// has a memory or CPU threshold)
+    #[serde(with = "duration_millis")]
+    pub resource_sample_interval: Duration,
 }
 
 impl Default for WorkerPoolConfig {
@@ -61,6 +71,7 @@ impl Default for WorkerPoolConfig {
             worker_id: format!("worker-{}", Uuid::now_v7()),
             worker_group: "default".to_string(),
             activity_types: vec![],
+            task_queues: vec![DEFAULT_TASK_QUEUE.to_string()],
             max_concurrency: 10,
             backpressure: BackpressureConfig::default(),
             poller: PollerConfig::default(),
@@ -68,6 +79,7 @@ impl Default for WorkerPoolConfig {
             stale_reclaim_interval: Duration::from_secs(30),
             stale_threshold: Duration::from_secs(60),
             shutdown_timeout: Duration::from_secs(30),
+            resource_sample_interval: Duration::from_secs(2),
         }
     }
 }
@@ -93,6 +105,12 @@ impl WorkerPoolConfig {
         self
     }
 
+    /// Set the task queues to claim from (replaces the default queue)
+    pub fn with_task_queues(mut self, task_queues: Vec<String>) -> Self {
+        self.task_queues = task_queues;
+        self
+    }
+
     /// Set maximum concurrency
     pub fn with_max_concurrency(mut self, max: usize) -> Self {
         self.max_concurrency = max.max(1);
@@ -122,6 +140,12 @@ impl WorkerPoolConfig {
         self.shutdown_timeout = timeout;
         self
     }
+
+    /// Set memory/CPU sampling interval
+    pub fn with_resource_sample_interval(mut self, interval: Duration) -> Self {
+        self.resource_sample_interval = interval;
+        self
+    }
 }
 
 /// Worker pool status
@@ -217,6 +241,7 @@ pub struct WorkerPool {
     poll_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
     heartbeat_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
     reclaim_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+    resource_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
 }
 
 impl WorkerPool {
@@ -240,6 +265,7 @@ impl WorkerPool {
             poll_handle: std::sync::Mutex::new(None),
             heartbeat_handle: std::sync::Mutex::new(None),
             reclaim_handle: std::sync::Mutex::new(None),
+            resource_handle: std::sync::Mutex::new(None),
         }
     }
 
@@ -269,6 +295,7 @@ impl WorkerPool {
         info!(
             worker_id = %self.config.worker_id,
             activity_types = ?self.config.activity_types,
+            task_queues = ?self.config.task_queues,
             max_concurrency = self.config.max_concurrency,
             "Starting worker pool"
         );
@@ -280,6 +307,9 @@ impl WorkerPool {
         *self.status.write().unwrap() = WorkerPoolStatus::Running;
 
         // Start background tasks
+        if self.config.backpressure.has_resource_thresholds() {
+            self.start_resource_loop();
+        }
         self.start_poll_loop();
         self.start_heartbeat_loop();
         self.start_reclaim_loop();
@@ -349,6 +379,11 @@ impl WorkerPool {
         &self.config.worker_id
     }
 
+    /// Get the reason the pool stopped accepting tasks (if it did)
+    pub fn backpressure_reason(&self) -> Option<String> {
+        self.backpressure.backpressure_reason()
+    }
+
     /// Check if accepting tasks
     pub fn is_accepting(&self) -> bool {
         self.backpressure.is_accepting()
@@ -365,6 +400,7 @@ impl WorkerPool {
             current_load: 0,
             status: "active".to_string(),
             accepting_tasks: true,
+            backpressure_reason: None,
             started_at: Utc::now(),
             last_heartbeat_at: Utc::now(),
         };
@@ -395,7 +431,8 @@ impl WorkerPool {
                 config.activity_types.clone(),
                 config.poller.clone(),
                 shutdown_rx.clone(),
-            );
+            )
+            .with_task_queues(config.task_queues.clone());
 
             loop {
                 // Check for shutdown
@@ -511,8 +548,12 @@ impl WorkerPool {
                     _ = ticker.tick() => {
                         let load = backpressure.current_load();
                         let accepting = backpressure.is_accepting();
+                        let reason = backpressure.backpressure_reason();
 
-                        if let Err(e) = store.worker_heartbeat(&worker_id, load, accepting).await {
+                        if let Err(e) = store
+                            .worker_heartbeat(&worker_id, load, accepting, reason.as_deref())
+                            .await
+                        {
                             error!("Heartbeat failed: {}", e);
                         }
                     }
@@ -529,6 +570,34 @@ impl WorkerPool {
         *self.heartbeat_handle.lock().unwrap() = Some(handle);
     }
 
+    /// Start the memory/CPU sampling loop
+    fn start_resource_loop(&self) {
+        let interval = self.config.resource_sample_interval;
+        let backpressure = Arc::clone(&self.backpressure);
+        let mut shutdown_rx = self.shutdown_rx.clone();
+
+        let handle = tokio::spawn(async move {
+            let monitor = ResourceMonitor::new();
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        backpressure.record_resources(monitor.sample());
+                    }
+                    _ = shutdown_rx.changed() => {
+                        debug!("Resource loop: shutdown requested");
+                        break;
+                    }
+                }
+            }
+
+            debug!("Resource loop exited");
+        });
+
+        *self.resource_handle.lock().unwrap() = Some(handle);
+    }
+
     /// Start the stale task reclamation loop
     fn start_reclaim_loop(&self) {
         let store = Arc::clone(&self.store);
@@ -597,6 +666,7 @@ mod tests {
         let config = WorkerPoolConfig::default();
         assert!(!config.worker_id.is_empty());
         assert_eq!(config.worker_group, "default");
+        assert_eq!(config.task_queues, vec![DEFAULT_TASK_QUEUE]);
         assert_eq!(config.max_concurrency, 10);
         assert_eq!(config.heartbeat_interval, Duration::from_secs(5));
     }
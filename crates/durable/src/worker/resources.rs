@@ -0,0 +1,259 @@
+//! Resource sampling for backpressure
+//!
+//! Reads memory and CPU usage of the worker process and of its cgroup
+//! (cgroup v2 on Linux). On other platforms, or when the files are not
+//! readable, the measurements are `None` and only the in-flight task ratio
+//! drives backpressure.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Clock ticks per second used by `/proc/<pid>/stat` (USER_HZ, 100 on Linux)
+const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+/// A resource usage sample
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    /// Resident memory of the worker process in bytes
+    pub process_memory_bytes: Option<u64>,
+    /// Memory charged to the worker's cgroup in bytes
+    pub cgroup_memory_bytes: Option<u64>,
+    /// CPU usage since the previous sample, as a ratio of the CPUs available
+    /// to the worker (cgroup quota, or all cores)
+    pub cpu_ratio: Option<f64>,
+}
+
+impl ResourceUsage {
+    /// Memory usage checked against the threshold: the larger of the process
+    /// and cgroup measurements
+    pub fn memory_bytes(&self) -> Option<u64> {
+        self.process_memory_bytes.max(self.cgroup_memory_bytes)
+    }
+}
+
+/// Samples process and cgroup resource usage
+///
+/// CPU usage is a rate, so it is computed between two calls to
+/// [`sample`](Self::sample); the first sample has no CPU measurement.
+///
+/// # Example
+///
+/// ```
+/// use everruns_durable::worker::ResourceMonitor;
+///
+/// let monitor = ResourceMonitor::new();
+/// let usage = monitor.sample();
+/// println!("memory: {:?} bytes", usage.memory_bytes());
+/// ```
+pub struct ResourceMonitor {
+    proc_dir: PathBuf,
+    cgroup_dir: Option<PathBuf>,
+    last_cpu: Mutex<Option<(Instant, Duration)>>,
+}
+
+impl ResourceMonitor {
+    /// Monitor the current process and its cgroup
+    pub fn new() -> Self {
+        let proc_dir = PathBuf::from("/proc/self");
+        let cgroup_dir = cgroup_v2_dir(Path::new("/sys/fs/cgroup"), &proc_dir);
+        Self::with_paths(proc_dir, cgroup_dir)
+    }
+
+    /// Monitor using explicit `/proc/<pid>` and cgroup directories
+    pub fn with_paths(proc_dir: impl Into<PathBuf>, cgroup_dir: Option<PathBuf>) -> Self {
+        Self {
+            proc_dir: proc_dir.into(),
+            cgroup_dir,
+            last_cpu: Mutex::new(None),
+        }
+    }
+
+    /// Take a sample
+    pub fn sample(&self) -> ResourceUsage {
+        ResourceUsage {
+            process_memory_bytes: self.process_memory(),
+            cgroup_memory_bytes: self
+                .cgroup_dir
+                .as_ref()
+                .and_then(|dir| read_u64(&dir.join("memory.current"))),
+            cpu_ratio: self.cpu_ratio(),
+        }
+    }
+
+    /// VmRSS from `/proc/<pid>/status`
+    fn process_memory(&self) -> Option<u64> {
+        let status = fs::read_to_string(self.proc_dir.join("status")).ok()?;
+        let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb * 1024)
+    }
+
+    /// CPU time used so far: the cgroup's when available, else the process's
+    fn cpu_time(&self) -> Option<Duration> {
+        if let Some(dir) = &self.cgroup_dir {
+            let stat = fs::read_to_string(dir.join("cpu.stat")).ok();
+            let usage_usec = stat.as_deref().and_then(|stat| {
+                stat.lines()
+                    .find_map(|l| l.strip_prefix("usage_usec "))
+                    .and_then(|v| v.trim().parse().ok())
+            });
+            if let Some(usec) = usage_usec {
+                return Some(Duration::from_micros(usec));
+            }
+        }
+
+        // utime and stime are fields 14 and 15; the command name (field 2)
+        // may contain spaces, so split after its closing parenthesis
+        let stat = fs::read_to_string(self.proc_dir.join("stat")).ok()?;
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        Some(Duration::from_millis(
+            (utime + stime) * 1000 / CLOCK_TICKS_PER_SEC,
+        ))
+    }
+
+    /// Number of CPUs available: the cgroup quota (`cpu.max`) or all cores
+    fn available_cpus(&self) -> f64 {
+        let quota = self.cgroup_dir.as_ref().and_then(|dir| {
+            let max = fs::read_to_string(dir.join("cpu.max")).ok()?;
+            let mut parts = max.split_whitespace();
+            let quota: f64 = parts.next()?.parse().ok()?; // "max" = unlimited
+            let period: f64 = parts.next()?.parse().ok()?;
+            (period > 0.0).then(|| quota / period)
+        });
+        quota.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get() as f64)
+                .unwrap_or(1.0)
+        })
+    }
+
+    fn cpu_ratio(&self) -> Option<f64> {
+        let now = Instant::now();
+        let cpu_time = self.cpu_time()?;
+        let previous = self.last_cpu.lock().replace((now, cpu_time));
+
+        let (last_at, last_cpu_time) = previous?;
+        let wall = now.duration_since(last_at).as_secs_f64();
+        if wall <= 0.0 {
+            return None;
+        }
+        let used = cpu_time.saturating_sub(last_cpu_time).as_secs_f64();
+        Some((used / wall / self.available_cpus()).clamp(0.0, 1.0))
+    }
+}
+
+impl Default for ResourceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The process's cgroup v2 directory (from the `0::<path>` entry of
+/// `/proc/<pid>/cgroup`), if cgroup v2 is mounted
+fn cgroup_v2_dir(cgroup_root: &Path, proc_dir: &Path) -> Option<PathBuf> {
+    if !cgroup_root.join("cgroup.controllers").exists() {
+        return None;
+    }
+    let membership = fs::read_to_string(proc_dir.join("cgroup")).unwrap_or_default();
+    let dir = membership
+        .lines()
+        .find_map(|l| l.strip_prefix("0::"))
+        .map(|path| cgroup_root.join(path.trim().trim_start_matches('/')))
+        .filter(|dir| dir.is_dir());
+    // Inside a container the cgroup namespace root is the container's cgroup
+    Some(dir.unwrap_or_else(|| cgroup_root.to_path_buf()))
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("everruns-resources-{}", Uuid::now_v7()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn write(&self, name: &str, content: &str) {
+            fs::write(self.0.join(name), content).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_sample_reads_process_and_cgroup_memory() {
+        let proc_dir = TempDir::new();
+        proc_dir.write("status", "Name:\tworker\nVmRSS:\t  2048 kB\nThreads:\t4\n");
+        let cgroup_dir = TempDir::new();
+        cgroup_dir.write("memory.current", "8388608\n");
+
+        let monitor = ResourceMonitor::with_paths(&proc_dir.0, Some(cgroup_dir.0.clone()));
+        let usage = monitor.sample();
+
+        assert_eq!(usage.process_memory_bytes, Some(2048 * 1024));
+        assert_eq!(usage.cgroup_memory_bytes, Some(8 * 1024 * 1024));
+        assert_eq!(usage.memory_bytes(), Some(8 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_cpu_ratio_uses_cgroup_quota() {
+        let proc_dir = TempDir::new();
+        let cgroup_dir = TempDir::new();
+        cgroup_dir.write("cpu.max", "200000 100000\n"); // 2 CPUs
+        cgroup_dir.write("cpu.stat", "usage_usec 1000000\nuser_usec 800000\n");
+
+        let monitor = ResourceMonitor::with_paths(&proc_dir.0, Some(cgroup_dir.0.clone()));
+        assert_eq!(monitor.available_cpus(), 2.0);
+
+        // The first sample has nothing to compare against
+        assert!(monitor.sample().cpu_ratio.is_none());
+
+        // Pretend the previous sample was taken a second ago
+        *monitor.last_cpu.lock() = Some((
+            Instant::now() - Duration::from_secs(1),
+            Duration::from_millis(1000),
+        ));
+        cgroup_dir.write("cpu.stat", "usage_usec 2000000\n");
+        let ratio = monitor.sample().cpu_ratio.unwrap();
+        assert!((0.45..=0.5).contains(&ratio), "ratio {}", ratio);
+    }
+
+    #[test]
+    fn test_process_cpu_time_from_stat() {
+        let proc_dir = TempDir::new();
+        // Command names may contain spaces and parentheses
+        proc_dir.write(
+            "stat",
+            "1234 (my (worker) bin) S 1 1234 1234 0 -1 4194560 100 0 0 0 250 50 0 0 20 0 4 0",
+        );
+
+        let monitor = ResourceMonitor::with_paths(&proc_dir.0, None);
+        assert_eq!(monitor.cpu_time(), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_missing_files() {
+        let proc_dir = TempDir::new();
+        let monitor = ResourceMonitor::with_paths(&proc_dir.0, None);
+
+        assert_eq!(monitor.sample(), ResourceUsage::default());
+    }
+}
@@ -13,6 +13,7 @@ use crate::reliability::{CircuitBreakerConfig, RetryPolicy};
 /// Each action is persisted as a [`WorkflowEvent`](super::WorkflowEvent) before execution.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)] // ScheduleActivity is the common action; boxing its options buys nothing
 pub enum WorkflowAction {
     /// Schedule an activity for execution
     ScheduleActivity {
@@ -51,6 +52,17 @@ pub enum WorkflowAction {
         error: WorkflowError,
     },
 
+    /// Complete this run and start a new run of the same workflow id
+    ///
+    /// Keeps the history of long-running workflows bounded: the new run
+    /// starts from `input` with an empty history. Signals not yet processed
+    /// are delivered to the new run. Actions returned after this one are
+    /// ignored.
+    ContinueAsNew {
+        /// Input of the new run
+        input: serde_json::Value,
+    },
+
     /// Schedule a child workflow
     ScheduleChildWorkflow {
         /// Unique identifier for the child workflow
@@ -98,6 +110,11 @@ impl WorkflowAction {
         Self::FailWorkflow { error }
     }
 
+    /// Create a continue-as-new action
+    pub fn continue_as_new(input: serde_json::Value) -> Self {
+        Self::ContinueAsNew { input }
+    }
+
     /// Create a timer action
     pub fn timer(timer_id: impl Into<String>, duration: Duration) -> Self {
         Self::StartTimer {
@@ -107,15 +124,24 @@ impl WorkflowAction {
     }
 }
 
+/// Task queue used when an activity does not name one
+pub const DEFAULT_TASK_QUEUE: &str = "default";
+
+fn default_task_queue() -> String {
+    DEFAULT_TASK_QUEUE.to_string()
+}
+
 /// Options for activity execution
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ActivityOptions {
     /// Retry policy for this activity
     pub retry_policy: RetryPolicy,
 
-    /// Maximum time to wait for activity to be claimed by a worker
-    #[serde(with = "duration_serde")]
-    pub schedule_to_start_timeout: Duration,
+    /// Maximum time to wait for activity to be claimed by a worker. Not
+    /// enforced unless set: tasks held back by fairness caps, backpressure or
+    /// a worker outage stay pending instead of failing
+    #[serde(default, with = "option_duration_serde")]
+    pub schedule_to_start_timeout: Option<Duration>,
 
     /// Maximum time for activity execution (from start to completion)
     #[serde(with = "duration_serde")]
@@ -131,17 +157,30 @@ pub struct ActivityOptions {
 
     /// Priority (higher values = higher priority, claimed first)
     pub priority: i32,
+
+    /// Task queue the activity is routed to; only workers serving this queue
+    /// claim it
+    #[serde(default = "default_task_queue")]
+    pub task_queue: String,
+
+    /// Key the activity is scheduled fairly by (e.g. `session:<id>`): claims
+    /// alternate between keys and can be capped per key (see
+    /// [`FairnessConfig`](crate::persistence::FairnessConfig))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fairness_key: Option<String>,
 }
 
 impl Default for ActivityOptions {
     fn default() -> Self {
         Self {
             retry_policy: RetryPolicy::default(),
-            schedule_to_start_timeout: Duration::from_secs(60),
+            schedule_to_start_timeout: None,
             start_to_close_timeout: Duration::from_secs(300),
             heartbeat_timeout: None,
             circuit_breaker: None,
             priority: 0,
+            task_queue: default_task_queue(),
+            fairness_key: None,
         }
     }
 }
@@ -153,9 +192,9 @@ impl ActivityOptions {
         self
     }
 
-    /// Set the schedule-to-start timeout
+    /// Fail the activity when no worker claims it within `timeout`
     pub fn with_schedule_to_start_timeout(mut self, timeout: Duration) -> Self {
-        self.schedule_to_start_timeout = timeout;
+        self.schedule_to_start_timeout = Some(timeout);
         self
     }
 
@@ -176,6 +215,18 @@ impl ActivityOptions {
         self.priority = priority;
         self
     }
+
+    /// Route the activity to a task queue
+    pub fn with_task_queue(mut self, task_queue: impl Into<String>) -> Self {
+        self.task_queue = task_queue.into();
+        self
+    }
+
+    /// Set the fairness key
+    pub fn with_fairness_key(mut self, key: impl Into<String>) -> Self {
+        self.fairness_key = Some(key.into());
+        self
+    }
 }
 
 /// Serde support for Duration (as milliseconds)
@@ -5,6 +5,9 @@ use serde::{de::DeserializeOwned, Serialize};
 use super::{WorkflowAction, WorkflowSignal};
 use crate::activity::ActivityError;
 
+/// Error code for queries a workflow does not answer
+pub const UNKNOWN_QUERY: &str = "UNKNOWN_QUERY";
+
 /// Error type for workflow failures
 #[derive(Debug, Clone, Serialize, serde::Deserialize, PartialEq, Eq)]
 pub struct WorkflowError {
@@ -59,11 +62,15 @@ impl std::error::Error for WorkflowError {}
 /// - How to handle activity completions (`on_activity_completed`, `on_activity_failed`)
 /// - How to handle timers (`on_timer_fired`)
 /// - How to handle external signals (`on_signal`)
+/// - How to answer read-only queries about its state (`query`)
 ///
 /// # Determinism
 ///
 /// Workflows must be deterministic - given the same sequence of events, they must
 /// produce the same sequence of actions. This enables replay-based recovery.
+/// Replay fails with a non-determinism error when handlers return actions
+/// that differ from the recorded history; guard changes to returned actions
+/// with [`Workflow::get_version`] or [`Workflow::patched`].
 ///
 /// # Example
 ///
@@ -159,6 +166,43 @@ pub trait Workflow: Send + Sync + 'static {
     fn error(&self) -> Option<WorkflowError> {
         None
     }
+
+    /// Answer a read-only query about the workflow state
+    ///
+    /// Queries are served by replaying the recorded history, so they see the
+    /// state after the last recorded event and must not change it. The
+    /// default rejects every query type.
+    fn query(
+        &self,
+        query_type: &str,
+        args: serde_json::Value,
+    ) -> Result<serde_json::Value, WorkflowError> {
+        let _ = args;
+        Err(
+            WorkflowError::new(format!("unknown query type: {}", query_type))
+                .with_code(UNKNOWN_QUERY),
+        )
+    }
+
+    /// Resolve which version of a code change this workflow runs
+    ///
+    /// New executions get `max_supported`, recorded as a version marker.
+    /// Replays get the recorded version, or [`DEFAULT_VERSION`] for history
+    /// recorded before the change. A version outside
+    /// `min_supported..=max_supported` fails replay as non-deterministic.
+    /// Call only from handlers.
+    ///
+    /// [`DEFAULT_VERSION`]: super::DEFAULT_VERSION
+    fn get_version(&self, change_id: &str, min_supported: u32, max_supported: u32) -> u32 {
+        super::version::get_version(change_id, min_supported, max_supported)
+    }
+
+    /// Check whether a single-step change applies to this workflow
+    ///
+    /// Shorthand for `get_version(change_id, DEFAULT_VERSION, 1) == 1`.
+    fn patched(&self, change_id: &str) -> bool {
+        self.get_version(change_id, super::DEFAULT_VERSION, 1) == 1
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +59,12 @@ pub enum WorkflowEvent {
         reason: String,
     },
 
+    /// Run closed to continue as a new run of the same workflow
+    WorkflowContinuedAsNew {
+        /// Input of the new run
+        input: serde_json::Value,
+    },
+
     // =========================================================================
     // Activity Lifecycle Events
     // =========================================================================
@@ -190,6 +196,21 @@ pub enum WorkflowEvent {
         /// Error from the child workflow
         error: WorkflowError,
     },
+
+    // =========================================================================
+    // Versioning Events
+    // =========================================================================
+    /// A handler resolved the version of a code change (see `Workflow::get_version`)
+    ///
+    /// Recorded after the event whose handler first asked for the change, so
+    /// replays return the same version after the workflow code has changed.
+    VersionMarker {
+        /// Identifier of the code change
+        change_id: String,
+
+        /// Version the handler used
+        version: u32,
+    },
 }
 
 impl WorkflowEvent {
@@ -206,6 +227,22 @@ impl WorkflowEvent {
         }
     }
 
+    /// Check if this event records an action returned by a workflow handler
+    ///
+    /// Replay compares these against the actions the current code returns.
+    pub fn is_command(&self) -> bool {
+        matches!(
+            self,
+            Self::WorkflowCompleted { .. }
+                | Self::WorkflowFailed { .. }
+                | Self::WorkflowContinuedAsNew { .. }
+                | Self::ActivityScheduled { .. }
+                | Self::ActivityCancelled { .. }
+                | Self::TimerStarted { .. }
+                | Self::ChildWorkflowStarted { .. }
+        )
+    }
+
     /// Check if this is a terminal workflow event
     pub fn is_terminal(&self) -> bool {
         matches!(
@@ -213,6 +250,7 @@ impl WorkflowEvent {
             Self::WorkflowCompleted { .. }
                 | Self::WorkflowFailed { .. }
                 | Self::WorkflowCancelled { .. }
+                | Self::WorkflowContinuedAsNew { .. }
         )
     }
 }
@@ -274,6 +312,11 @@ mod tests {
         .is_terminal());
 
         assert!(!WorkflowEvent::WorkflowStarted { input: json!({}) }.is_terminal());
+        assert!(!WorkflowEvent::VersionMarker {
+            change_id: "x".to_string(),
+            version: 1
+        }
+        .is_terminal());
         assert!(!WorkflowEvent::ActivityCompleted {
             activity_id: "x".to_string(),
             result: json!({})
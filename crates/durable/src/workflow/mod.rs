@@ -5,13 +5,17 @@
 //! - [`WorkflowAction`] enum for workflow commands
 //! - [`WorkflowEvent`] enum for persisted events
 //! - [`WorkflowSignal`] for external communication
+//! - [`get_version`] for versioning workflow code changes
 
 mod action;
 mod definition;
 mod event;
 mod signal;
+mod version;
 
-pub use action::{ActivityOptions, WorkflowAction};
-pub use definition::{Workflow, WorkflowError};
+pub use action::{ActivityOptions, WorkflowAction, DEFAULT_TASK_QUEUE};
+pub use definition::{Workflow, WorkflowError, UNKNOWN_QUERY};
 pub use event::{TimeoutType, WorkflowEvent};
 pub use signal::{signal_types, WorkflowSignal};
+pub(crate) use version::VersionState;
+pub use version::{get_version, DEFAULT_VERSION};
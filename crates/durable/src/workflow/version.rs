@@ -0,0 +1,167 @@
+//! Workflow versioning
+//!
+//! Workflow code can change while instances are in flight. Replaying an
+//! older history through changed handlers must return the actions that were
+//! recorded, so changes to returned actions are guarded by a version check:
+//!
+//! ```ignore
+//! fn on_activity_completed(&mut self, _id: &str, result: Value) -> Vec<WorkflowAction> {
+//!     if self.patched("notify-on-complete") {
+//!         vec![WorkflowAction::schedule_activity("notify", "notify", result)]
+//!     } else {
+//!         vec![WorkflowAction::complete(result)]
+//!     }
+//! }
+//! ```
+//!
+//! The first handler to ask for a change records a `VersionMarker` event with
+//! the newest supported version, and replays return the recorded version.
+//! Handlers replayed from history recorded before the change existed get
+//! [`DEFAULT_VERSION`]. Once no in-flight workflow can still be at an old
+//! version, raise `min_supported` and delete the old branch.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Version of a change in histories recorded before the change existed
+pub const DEFAULT_VERSION: u32 = 0;
+
+thread_local! {
+    static CURRENT: RefCell<Option<VersionState>> = const { RefCell::new(None) };
+}
+
+/// Resolve the version of a code change for the running handler
+///
+/// Returns `max_supported` when called outside of the executor, e.g. when a
+/// test calls handlers directly. See [`crate::Workflow::get_version`].
+pub fn get_version(change_id: &str, min_supported: u32, max_supported: u32) -> u32 {
+    CURRENT.with(|current| match current.borrow_mut().as_mut() {
+        Some(state) => state.resolve(change_id, min_supported, max_supported),
+        None => max_supported,
+    })
+}
+
+/// Versions resolved while driving one workflow instance
+#[derive(Debug, Default)]
+pub(crate) struct VersionState {
+    /// Resolved versions by change id
+    versions: HashMap<String, u32>,
+    /// Whether the running handler is replayed from recorded history
+    replaying: bool,
+    /// Markers the running handlers need recorded
+    new_markers: Vec<(String, u32)>,
+    /// First version the code no longer supports
+    error: Option<String>,
+}
+
+impl VersionState {
+    /// Apply a `VersionMarker` found in history
+    pub(crate) fn record(&mut self, change_id: &str, version: u32) {
+        self.versions.insert(change_id.to_string(), version);
+    }
+
+    /// Run a handler with this state visible to [`get_version`]
+    pub(crate) fn run<R>(&mut self, replaying: bool, handler: impl FnOnce() -> R) -> R {
+        struct Restore<'a>(&'a mut VersionState);
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                if let Some(state) = CURRENT.with(|current| current.borrow_mut().take()) {
+                    *self.0 = state;
+                }
+            }
+        }
+
+        self.replaying = replaying;
+        CURRENT.with(|current| *current.borrow_mut() = Some(std::mem::take(self)));
+        let _restore = Restore(self);
+        handler()
+    }
+
+    /// Take the markers to record for handlers that ran since the last call
+    pub(crate) fn take_markers(&mut self) -> Vec<(String, u32)> {
+        std::mem::take(&mut self.new_markers)
+    }
+
+    /// Take the first unsupported version a handler asked for
+    pub(crate) fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn resolve(&mut self, change_id: &str, min_supported: u32, max_supported: u32) -> u32 {
+        let version = match self.versions.get(change_id) {
+            Some(version) => *version,
+            None => {
+                // A replayed handler that never recorded the change ran
+                // before it existed; remember that for later handlers too
+                let version = if self.replaying {
+                    DEFAULT_VERSION
+                } else {
+                    self.new_markers
+                        .push((change_id.to_string(), max_supported));
+                    max_supported
+                };
+                self.versions.insert(change_id.to_string(), version);
+                version
+            }
+        };
+
+        if !(min_supported..=max_supported).contains(&version) && self.error.is_none() {
+            self.error = Some(format!(
+                "change '{}' is at version {} but the workflow supports versions {}..={}",
+                change_id, version, min_supported, max_supported
+            ));
+        }
+
+        version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_version_outside_executor() {
+        assert_eq!(get_version("change", DEFAULT_VERSION, 2), 2);
+    }
+
+    #[test]
+    fn test_new_handler_records_max_version() {
+        let mut state = VersionState::default();
+        let version = state.run(false, || get_version("change", DEFAULT_VERSION, 2));
+
+        assert_eq!(version, 2);
+        assert_eq!(state.take_markers(), vec![("change".to_string(), 2)]);
+        assert_eq!(state.run(false, || get_version("change", 0, 2)), 2);
+        assert!(state.take_markers().is_empty());
+    }
+
+    #[test]
+    fn test_replayed_handler_uses_recorded_or_default_version() {
+        let mut state = VersionState::default();
+        state.record("recorded", 1);
+
+        let versions = state.run(true, || {
+            (
+                get_version("recorded", DEFAULT_VERSION, 2),
+                get_version("unrecorded", DEFAULT_VERSION, 2),
+            )
+        });
+        assert_eq!(versions, (1, DEFAULT_VERSION));
+        assert!(state.take_markers().is_empty());
+
+        // Later handlers keep the version the replayed handler used
+        assert_eq!(state.run(false, || get_version("unrecorded", 0, 2)), 0);
+        assert!(state.take_error().is_none());
+    }
+
+    #[test]
+    fn test_unsupported_version_is_an_error() {
+        let mut state = VersionState::default();
+        state.record("change", 1);
+
+        assert_eq!(state.run(true, || get_version("change", 2, 3)), 1);
+        assert!(state.take_error().unwrap().contains("change"));
+    }
+}
@@ -14,10 +14,13 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use everruns_durable::persistence::{
-    DlqFilter, Pagination, PostgresWorkflowEventStore, StoreError, TaskDefinition,
-    TaskFailureOutcome, TraceContext, WorkerFilter, WorkerInfo, WorkflowEventStore, WorkflowStatus,
+    DlqFilter, FairnessConfig, MonthlyPartition, Pagination, PostgresWorkflowEventStore,
+    StoreError, TaskDefinition, TaskFailureOutcome, TraceContext, WorkerFilter, WorkerInfo,
+    WorkflowEventStore, WorkflowStatus,
+};
+use everruns_durable::reliability::{
+    RateLimitConfig, RateLimitDecision, RateLimitObservation, RetryPolicy,
 };
-use everruns_durable::reliability::RetryPolicy;
 use everruns_durable::workflow::{ActivityOptions, WorkflowError, WorkflowEvent, WorkflowSignal};
 
 /// Get test database URL from environment or use default
@@ -377,6 +380,249 @@ async fn test_task_claim_by_activity_type() {
     cleanup_workflow(&store, workflow_id).await;
 }
 
+#[tokio::test]
+async fn test_task_claim_by_task_queue() {
+    let store = create_test_store().await;
+    let workflow_id = Uuid::now_v7();
+
+    store
+        .create_workflow(workflow_id, "task_queue_test", json!({}), None)
+        .await
+        .unwrap();
+
+    store
+        .enqueue_task(TaskDefinition {
+            workflow_id,
+            activity_id: "sandboxed".to_string(),
+            activity_type: "act".to_string(),
+            input: json!({}),
+            options: ActivityOptions::default().with_task_queue("sandbox"),
+        })
+        .await
+        .unwrap();
+
+    // Workers of the default queue don't see it
+    let default_tasks = store
+        .claim_task("default-worker", &["act".to_string()], 10)
+        .await
+        .unwrap();
+    assert!(default_tasks.is_empty());
+
+    let sandbox_tasks = store
+        .claim_task_from_queues(
+            "sandbox-worker",
+            &["sandbox".to_string()],
+            &["act".to_string()],
+            10,
+        )
+        .await
+        .unwrap();
+    assert_eq!(sandbox_tasks.len(), 1);
+    assert_eq!(sandbox_tasks[0].options.task_queue, "sandbox");
+
+    cleanup_workflow(&store, workflow_id).await;
+}
+
+#[tokio::test]
+async fn test_task_claim_fairness() {
+    // Unique activity type and keys so other tests' tasks don't count
+    let activity_type = format!("fair_{}", Uuid::now_v7().simple());
+    let busy = format!("session:{}", Uuid::now_v7());
+    let quiet = format!("session:{}", Uuid::now_v7());
+    let store = create_test_store().await.with_fairness(
+        FairnessConfig::new()
+            .with_max_concurrent_per_key(2)
+            .with_max_concurrent_for_activity(activity_type.clone(), 3),
+    );
+    let workflow_id = Uuid::now_v7();
+
+    store
+        .create_workflow(workflow_id, "fairness_test", json!({}), None)
+        .await
+        .unwrap();
+
+    for (i, key) in [&busy, &busy, &busy, &quiet, &quiet]
+        .into_iter()
+        .enumerate()
+    {
+        store
+            .enqueue_task(TaskDefinition {
+                workflow_id,
+                activity_id: format!("task-{}", i),
+                activity_type: activity_type.clone(),
+                input: json!({}),
+                options: ActivityOptions::default().with_fairness_key(key.clone()),
+            })
+            .await
+            .unwrap();
+    }
+
+    let activity_types = vec![activity_type.clone()];
+
+    // Claims alternate between the sessions
+    let first = store
+        .claim_task("worker-1", &activity_types, 2)
+        .await
+        .unwrap();
+    let keys: Vec<_> = first
+        .iter()
+        .map(|t| t.options.fairness_key.clone().unwrap())
+        .collect();
+    assert_eq!(keys, vec![busy.clone(), quiet.clone()]);
+
+    // Only one more task fits the activity type limit
+    let second = store
+        .claim_task("worker-2", &activity_types, 10)
+        .await
+        .unwrap();
+    assert_eq!(second.len(), 1);
+    assert!(store
+        .claim_task("worker-3", &activity_types, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    cleanup_workflow(&store, workflow_id).await;
+}
+
+#[tokio::test]
+async fn test_task_claim_skips_keys_at_limit() {
+    let activity_type = format!("fair_{}", Uuid::now_v7().simple());
+    let busy = format!("session:{}", Uuid::now_v7());
+    let quiet = format!("session:{}", Uuid::now_v7());
+    let store = create_test_store()
+        .await
+        .with_fairness(FairnessConfig::new().with_max_concurrent_per_key(1));
+    let workflow_id = Uuid::now_v7();
+
+    store
+        .create_workflow(workflow_id, "fairness_test", json!({}), None)
+        .await
+        .unwrap();
+    let task = |i: usize, key: &str| TaskDefinition {
+        workflow_id,
+        activity_id: format!("task-{}", i),
+        activity_type: activity_type.clone(),
+        input: json!({}),
+        options: ActivityOptions::default().with_fairness_key(key.to_string()),
+    };
+
+    // More queued tasks of the busy session than one claim reads
+    for i in 0..150 {
+        store.enqueue_task(task(i, &busy)).await.unwrap();
+    }
+    let activity_types = vec![activity_type.clone()];
+    let first = store
+        .claim_task("worker-1", &activity_types, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        first[0].options.fairness_key.as_deref(),
+        Some(busy.as_str())
+    );
+
+    // The busy session is at its limit, so the quiet one is claimed next
+    store.enqueue_task(task(150, &quiet)).await.unwrap();
+    let second = store
+        .claim_task("worker-2", &activity_types, 1)
+        .await
+        .unwrap();
+    assert_eq!(second.len(), 1);
+    assert_eq!(
+        second[0].options.fairness_key.as_deref(),
+        Some(quiet.as_str())
+    );
+
+    cleanup_workflow(&store, workflow_id).await;
+}
+
+#[tokio::test]
+async fn test_rate_limiter_shared_state() {
+    let store = create_test_store().await;
+    let key = format!("llm_provider:{}", Uuid::now_v7());
+    let config = RateLimitConfig::new().with_requests_per_minute(2);
+
+    for _ in 0..2 {
+        let decision = store.acquire_rate_limit(&key, &config, 100).await.unwrap();
+        assert_eq!(decision, RateLimitDecision::Acquired);
+    }
+    match store.acquire_rate_limit(&key, &config, 100).await.unwrap() {
+        RateLimitDecision::Wait(wait) => assert!(wait > Duration::from_secs(25)),
+        RateLimitDecision::Acquired => panic!("bucket should be empty"),
+    }
+
+    // Provider-reported token limits apply to the same bucket
+    let observation = RateLimitObservation {
+        token_limit: Some(1000),
+        tokens_remaining: Some(0),
+        tokens_reset: Some(Duration::from_secs(60)),
+        ..Default::default()
+    };
+    store
+        .observe_rate_limit(&key, &RateLimitConfig::new(), &observation)
+        .await
+        .unwrap();
+    let other = RateLimitConfig::new();
+    match store.acquire_rate_limit(&key, &other, 100).await.unwrap() {
+        RateLimitDecision::Wait(wait) => assert!(wait > Duration::from_secs(55)),
+        RateLimitDecision::Acquired => panic!("provider reported no tokens left"),
+    }
+
+    sqlx::query("DELETE FROM durable_rate_limiter_state WHERE key = $1")
+        .bind(&key)
+        .execute(store.pool())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_retention_deletes_finished_workflows() {
+    let store = create_test_store().await;
+    let workflow_id = Uuid::now_v7();
+
+    store
+        .create_workflow(workflow_id, "retention_test", json!({}), None)
+        .await
+        .unwrap();
+    store
+        .append_events(
+            workflow_id,
+            0,
+            vec![WorkflowEvent::WorkflowStarted { input: json!({}) }],
+        )
+        .await
+        .unwrap();
+    store
+        .update_workflow_status(workflow_id, WorkflowStatus::Completed, None, None)
+        .await
+        .unwrap();
+
+    let cutoff = Utc::now() + chrono::Duration::seconds(1);
+    let expired = store.list_expired_workflows(cutoff, 1000).await.unwrap();
+    assert!(expired.iter().any(|w| w.id == workflow_id));
+
+    let deleted = store.delete_workflows(&[workflow_id]).await.unwrap();
+    assert_eq!(deleted, 1);
+    assert!(store.load_events(workflow_id).await.unwrap().is_empty());
+    assert!(matches!(
+        store.get_workflow_status(workflow_id).await,
+        Err(StoreError::WorkflowNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_partitions_are_created_ahead() {
+    let store = create_test_store().await;
+    let next_month = MonthlyPartition::containing("durable_task_queue", Utc::now()).next();
+
+    store.create_partition(&next_month).await.unwrap();
+    // Creating an existing partition is a no-op
+    store.create_partition(&next_month).await.unwrap();
+
+    let partitions = store.list_partitions("durable_task_queue").await.unwrap();
+    assert!(partitions.contains(&next_month));
+}
+
 #[tokio::test]
 async fn test_task_complete() {
     let store = create_test_store().await;
@@ -419,6 +665,63 @@ async fn test_task_complete() {
     cleanup_workflow(&store, workflow_id).await;
 }
 
+#[tokio::test]
+async fn test_cancel_activity_stops_chained_steps() {
+    let store = create_test_store().await;
+    let workflow_id = Uuid::now_v7();
+    let task = |activity_id: &str| TaskDefinition {
+        workflow_id,
+        activity_id: activity_id.to_string(),
+        activity_type: "chain".to_string(),
+        input: json!({}),
+        options: ActivityOptions::default(),
+    };
+
+    store
+        .create_workflow(workflow_id, "chain_test", json!({}), None)
+        .await
+        .unwrap();
+    store.enqueue_task(task("turn-1")).await.unwrap();
+
+    let claimed = store
+        .claim_task("worker", &["chain".to_string()], 1)
+        .await
+        .unwrap();
+    assert!(store
+        .complete_and_enqueue(claimed[0].id, json!({}), Some(task("turn-1/step-1")))
+        .await
+        .unwrap());
+
+    // The chained step is claimed, then its activity is cancelled
+    let claimed = store
+        .claim_task("worker", &["chain".to_string()], 1)
+        .await
+        .unwrap();
+    assert_eq!(claimed[0].activity_id, "turn-1/step-1");
+    assert_eq!(
+        store.cancel_activity(workflow_id, "turn-1").await.unwrap(),
+        1
+    );
+    let heartbeat = store
+        .heartbeat_task(claimed[0].id, "worker", None)
+        .await
+        .unwrap();
+    assert!(heartbeat.should_cancel);
+
+    // Its completion does not chain the next step
+    assert!(!store
+        .complete_and_enqueue(claimed[0].id, json!({}), Some(task("turn-1/step-2")))
+        .await
+        .unwrap());
+    let claimed = store
+        .claim_task("worker", &["chain".to_string()], 10)
+        .await
+        .unwrap();
+    assert!(claimed.is_empty());
+
+    cleanup_workflow(&store, workflow_id).await;
+}
+
 #[tokio::test]
 async fn test_task_failure_with_retry() {
     let store = create_test_store().await;
@@ -685,6 +988,175 @@ async fn test_signals() {
     cleanup_workflow(&store, workflow_id).await;
 }
 
+#[tokio::test]
+async fn test_signal_with_start() {
+    let store = create_test_store().await;
+    let workflow_id = Uuid::now_v7();
+
+    // Concurrent calls create the workflow once and deliver every signal
+    let results = futures::future::join_all((0..4).map(|n| {
+        store.signal_with_start(
+            workflow_id,
+            "signal_with_start_test",
+            json!({"session": "s-1"}),
+            WorkflowSignal::custom("message", json!({"n": n})),
+            None,
+        )
+    }))
+    .await;
+    let created = results.into_iter().filter(|r| *r.as_ref().unwrap()).count();
+    assert_eq!(created, 1);
+
+    let events = store.load_events(workflow_id).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0].1, WorkflowEvent::WorkflowStarted { .. }));
+    assert_eq!(
+        store.get_pending_signals(workflow_id).await.unwrap().len(),
+        4
+    );
+
+    store
+        .update_workflow_status(workflow_id, WorkflowStatus::Completed, None, None)
+        .await
+        .unwrap();
+    let result = store
+        .signal_with_start(
+            workflow_id,
+            "signal_with_start_test",
+            json!({}),
+            WorkflowSignal::custom("message", json!({})),
+            None,
+        )
+        .await;
+    assert!(matches!(result, Err(StoreError::WorkflowClosed(_))));
+
+    let result = store
+        .signal_with_start(
+            workflow_id,
+            "other_type",
+            json!({}),
+            WorkflowSignal::custom("message", json!({})),
+            None,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(StoreError::WorkflowTypeMismatch { actual, .. }) if actual == "signal_with_start_test"
+    ));
+
+    cleanup_workflow(&store, workflow_id).await;
+}
+
+#[tokio::test]
+async fn test_continue_as_new() {
+    let store = create_test_store().await;
+    let workflow_id = Uuid::now_v7();
+
+    store
+        .create_workflow(workflow_id, "continue_as_new_test", json!({"run": 1}), None)
+        .await
+        .unwrap();
+    store
+        .append_events(
+            workflow_id,
+            0,
+            vec![WorkflowEvent::WorkflowStarted {
+                input: json!({"run": 1}),
+            }],
+        )
+        .await
+        .unwrap();
+    store
+        .send_signal(workflow_id, WorkflowSignal::custom("message", json!({})))
+        .await
+        .unwrap();
+
+    // One running and one pending task of the first run
+    for n in 0..2 {
+        store
+            .enqueue_task(TaskDefinition {
+                workflow_id,
+                activity_id: format!("step-{}", n),
+                activity_type: "continue_as_new_task".to_string(),
+                input: json!({}),
+                options: ActivityOptions::default(),
+            })
+            .await
+            .unwrap();
+    }
+    let running = store
+        .claim_task("worker-1", &["continue_as_new_task".to_string()], 1)
+        .await
+        .unwrap();
+    assert_eq!(running.len(), 1);
+
+    let result = store
+        .continue_as_new(workflow_id, 0, json!({"run": 2}))
+        .await;
+    assert!(matches!(
+        result,
+        Err(StoreError::ConcurrencyConflict { .. })
+    ));
+
+    let run_id = store
+        .continue_as_new(workflow_id, 1, json!({"run": 2}))
+        .await
+        .unwrap();
+
+    let info = store.get_workflow_info(workflow_id).await.unwrap();
+    assert_eq!(info.status, WorkflowStatus::Running);
+    assert_eq!(info.input, json!({"run": 2}));
+    assert_eq!(info.run_number, 2);
+    assert_eq!(info.previous_run_id, Some(run_id));
+    let events = store.load_events(workflow_id).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0].1, WorkflowEvent::WorkflowStarted { .. }));
+
+    // Signals stay with the workflow id
+    assert_eq!(
+        store.get_pending_signals(workflow_id).await.unwrap().len(),
+        1
+    );
+
+    let closed = store.get_workflow_info(run_id).await.unwrap();
+    assert_eq!(closed.status, WorkflowStatus::Completed);
+    assert_eq!(closed.input, json!({"run": 1}));
+    assert_eq!(closed.run_number, 1);
+    assert_eq!(closed.continued_as, Some(workflow_id));
+    let events = store.load_events(run_id).await.unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[1].1,
+        WorkflowEvent::WorkflowContinuedAsNew { .. }
+    ));
+
+    // The first run's tasks are cancelled and stay cancelled
+    let statuses: Vec<String> =
+        sqlx::query_scalar("SELECT status FROM durable_task_queue WHERE workflow_id = $1")
+            .bind(workflow_id)
+            .fetch_all(store.pool())
+            .await
+            .unwrap();
+    assert_eq!(statuses, vec!["cancelled", "cancelled"]);
+    let heartbeat = store
+        .heartbeat_task(running[0].id, "worker-1", None)
+        .await
+        .unwrap();
+    assert!(heartbeat.should_cancel);
+    let outcome = store.fail_task(running[0].id, "late").await.unwrap();
+    assert!(matches!(outcome, TaskFailureOutcome::ExhaustedRetries));
+    store.complete_task(running[0].id, json!({})).await.unwrap();
+    let status: String = sqlx::query_scalar("SELECT status FROM durable_task_queue WHERE id = $1")
+        .bind(running[0].id)
+        .fetch_one(store.pool())
+        .await
+        .unwrap();
+    assert_eq!(status, "cancelled");
+
+    cleanup_workflow(&store, run_id).await;
+    cleanup_workflow(&store, workflow_id).await;
+}
+
 // ============================================
 // Worker Registry Tests
 // ============================================
@@ -704,6 +1176,7 @@ async fn test_worker_registration() {
             current_load: 0,
             status: "active".to_string(),
             accepting_tasks: true,
+            backpressure_reason: None,
             started_at: Utc::now(),
             last_heartbeat_at: Utc::now(),
         })
@@ -711,7 +1184,15 @@ async fn test_worker_registration() {
         .unwrap();
 
     // Update heartbeat
-    store.worker_heartbeat(&worker_id, 5, true).await.unwrap();
+    store
+        .worker_heartbeat(
+            &worker_id,
+            5,
+            false,
+            Some("memory usage 900 MiB exceeds threshold of 800 MiB"),
+        )
+        .await
+        .unwrap();
 
     // List workers
     let workers = store.list_workers(WorkerFilter::default()).await.unwrap();
@@ -719,6 +1200,8 @@ async fn test_worker_registration() {
     assert!(our_worker.is_some());
     let w = our_worker.unwrap();
     assert_eq!(w.current_load, 5);
+    assert!(!w.accepting_tasks);
+    assert!(w.backpressure_reason.is_some());
 
     // Deregister
     store.deregister_worker(&worker_id).await.unwrap();
@@ -883,3 +1366,82 @@ async fn test_concurrent_task_claiming() {
 
     cleanup_workflow(&store, workflow_id).await;
 }
+
+#[tokio::test]
+async fn test_concurrent_appends() {
+    let store = create_test_store().await;
+    let workflow_id = Uuid::now_v7();
+
+    store
+        .create_workflow(workflow_id, "concurrent_append_test", json!({}), None)
+        .await
+        .unwrap();
+    store
+        .append_events(
+            workflow_id,
+            0,
+            vec![WorkflowEvent::WorkflowStarted { input: json!({}) }],
+        )
+        .await
+        .unwrap();
+
+    // Appenders racing for the same sequence: exactly one wins
+    let results = futures::future::join_all((0..8).map(|n| {
+        store.append_events(
+            workflow_id,
+            1,
+            vec![WorkflowEvent::ActivityScheduled {
+                activity_id: format!("race-{}", n),
+                activity_type: "concurrent_append".to_string(),
+                input: json!({}),
+                options: ActivityOptions::default(),
+            }],
+        )
+    }))
+    .await;
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+    assert!(results.iter().all(|r| matches!(
+        r,
+        Ok(2)
+            | Err(StoreError::ConcurrencyConflict {
+                expected: 1,
+                actual: 2
+            })
+    )));
+
+    // Appenders retrying on conflict each land on their own sequence
+    let results = futures::future::join_all((0..8).map(|n| {
+        let store = store.clone();
+        async move {
+            loop {
+                let expected = store.load_events(workflow_id).await?.len() as i32;
+                let event = WorkflowEvent::ActivityScheduled {
+                    activity_id: format!("retry-{}", n),
+                    activity_type: "concurrent_append".to_string(),
+                    input: json!({}),
+                    options: ActivityOptions::default(),
+                };
+                match store
+                    .append_events(workflow_id, expected, vec![event])
+                    .await
+                {
+                    Err(StoreError::ConcurrencyConflict { .. }) => continue,
+                    result => return result,
+                }
+            }
+        }
+    }))
+    .await;
+    assert!(results.iter().all(|r| r.is_ok()));
+
+    let sequences: Vec<i32> = sqlx::query_scalar(
+        "SELECT sequence_num FROM durable_workflow_events WHERE workflow_id = $1 ORDER BY sequence_num",
+    )
+    .bind(workflow_id)
+    .fetch_all(store.pool())
+    .await
+    .unwrap();
+    assert_eq!(sequences, (0..10).collect::<Vec<_>>());
+
+    cleanup_workflow(&store, workflow_id).await;
+}
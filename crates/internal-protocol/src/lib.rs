@@ -286,6 +286,7 @@ fn serialize_event_data(data: &everruns_core::EventData) -> serde_json::Value {
         EventData::SessionStarted(d) => serde_json::to_value(d).unwrap_or_default(),
         EventData::SessionActivated(d) => serde_json::to_value(d).unwrap_or_default(),
         EventData::SessionIdled(d) => serde_json::to_value(d).unwrap_or_default(),
+        EventData::EventsSuperseded(d) => serde_json::to_value(d).unwrap_or_default(),
         EventData::Raw(v) => v.clone(),
     }
 }
@@ -307,7 +308,11 @@ pub fn proto_agent_to_schema(value: proto::Agent) -> Result<everruns_core::Agent
         "default_model_id": value.default_model_id.as_ref().map(|u| &u.value),
         "tags": tags,
         "capabilities": value.capability_ids,
+        "capability_config": value.capability_config.as_ref().map(proto_struct_to_json).unwrap_or_else(|| serde_json::json!({})),
+        "response_format": value.response_format.as_ref().map(proto_struct_to_json),
+        "parallel_tool_calls": value.parallel_tool_calls,
         "status": value.status,
+        "revision": value.revision,
         "created_at": value.created_at.as_ref().map(|t| proto_timestamp_to_datetime(t).to_rfc3339()),
         "updated_at": value.updated_at.as_ref().map(|t| proto_timestamp_to_datetime(t).to_rfc3339()),
     });
@@ -324,13 +329,76 @@ pub fn schema_agent_to_proto(value: &everruns_core::Agent) -> proto::Agent {
         default_model_id: value.default_model_id.map(uuid_to_proto_uuid),
         temperature: None,
         max_tokens: None,
+        response_format: value
+            .response_format
+            .as_ref()
+            .and_then(|f| serde_json::to_value(f).ok())
+            .map(|v| json_to_proto_struct(&v)),
+        parallel_tool_calls: value.parallel_tool_calls,
+        capability_config: Some(&value.capability_config)
+            .filter(|config| !config.is_empty())
+            .and_then(|config| serde_json::to_value(config).ok())
+            .map(|v| json_to_proto_struct(&v)),
         status: value.status.to_string(),
+        revision: value.revision,
         created_at: Some(datetime_to_proto_timestamp(value.created_at)),
         updated_at: Some(datetime_to_proto_timestamp(value.updated_at)),
         capability_ids: value.capabilities.iter().map(|c| c.to_string()).collect(),
     }
 }
 
+/// Convert an MCP server connection (config + decrypted credentials) to proto
+pub fn mcp_connection_to_proto(
+    value: &everruns_core::mcp::McpServerConnection,
+) -> Result<proto::McpServerConnection, ConversionError> {
+    let config = serde_json::to_value(&value.config)?;
+    Ok(proto::McpServerConnection {
+        config: Some(json_to_proto_struct(&config)),
+        credentials: value.credentials.clone().into_iter().collect(),
+    })
+}
+
+/// Convert proto MCP server connection to the core type
+pub fn proto_mcp_connection_to_schema(
+    value: proto::McpServerConnection,
+) -> Result<everruns_core::mcp::McpServerConnection, ConversionError> {
+    let config = value
+        .config
+        .as_ref()
+        .ok_or(ConversionError::MissingField("config"))?;
+    let config = serde_json::from_value(proto_struct_to_json(config))?;
+    Ok(everruns_core::mcp::McpServerConnection::new(
+        config,
+        value.credentials.into_iter().collect(),
+    ))
+}
+
+/// Convert an HTTP tool connection (definition + decrypted secret) to proto
+pub fn http_tool_connection_to_proto(
+    value: &everruns_core::http_tool::HttpToolConnection,
+) -> Result<proto::HttpToolConnection, ConversionError> {
+    let definition = serde_json::to_value(&value.definition)?;
+    Ok(proto::HttpToolConnection {
+        definition: Some(json_to_proto_struct(&definition)),
+        secret: value.secret.clone(),
+    })
+}
+
+/// Convert proto HTTP tool connection to the core type
+pub fn proto_http_tool_connection_to_schema(
+    value: proto::HttpToolConnection,
+) -> Result<everruns_core::http_tool::HttpToolConnection, ConversionError> {
+    let definition = value
+        .definition
+        .as_ref()
+        .ok_or(ConversionError::MissingField("definition"))?;
+    let definition = serde_json::from_value(proto_struct_to_json(definition))?;
+    Ok(everruns_core::http_tool::HttpToolConnection::new(
+        definition,
+        value.secret,
+    ))
+}
+
 /// Convert proto Session to schemas Session using JSON
 pub fn proto_session_to_schema(
     value: proto::Session,
@@ -345,6 +413,8 @@ pub fn proto_session_to_schema(
         "tags": tags,
         "model_id": value.default_model_id.as_ref().map(|u| &u.value),
         "status": value.status,
+        "agent_revision": value.agent_revision,
+        "variables": value.variables,
         "created_at": value.created_at.as_ref().map(|t| proto_timestamp_to_datetime(t).to_rfc3339()),
         "started_at": started_at,
         "finished_at": finished_at,
@@ -362,6 +432,8 @@ pub fn schema_session_to_proto(value: &everruns_core::Session) -> proto::Session
         created_at: Some(datetime_to_proto_timestamp(value.created_at)),
         updated_at: Some(datetime_to_proto_timestamp(value.created_at)), // Use created_at as fallback
         default_model_id: value.model_id.map(uuid_to_proto_uuid),
+        agent_revision: value.agent_revision,
+        variables: value.variables.clone().into_iter().collect(),
     }
 }
 
@@ -786,6 +858,173 @@ pub fn schema_grep_match_to_proto(value: &everruns_core::GrepMatch) -> proto::Gr
     }
 }
 
+/// Convert proto FileVersion to schemas FileVersion
+pub fn proto_file_version_to_schema(
+    value: proto::FileVersion,
+) -> Result<everruns_core::FileVersion, ConversionError> {
+    let id = value
+        .id
+        .as_ref()
+        .ok_or(ConversionError::MissingField("id"))?;
+    let id = proto_uuid_to_uuid(id)?;
+    let session_id = value
+        .session_id
+        .as_ref()
+        .ok_or(ConversionError::MissingField("session_id"))?;
+    let session_id = proto_uuid_to_uuid(session_id)?;
+    let created_at = value
+        .created_at
+        .as_ref()
+        .map(proto_timestamp_to_datetime)
+        .ok_or(ConversionError::MissingField("created_at"))?;
+
+    Ok(everruns_core::FileVersion {
+        id,
+        session_id,
+        path: value.path,
+        version: value.version,
+        size_bytes: value.size_bytes,
+        is_deleted: value.is_deleted,
+        created_at,
+    })
+}
+
+/// Convert schemas FileVersion to proto FileVersion
+pub fn schema_file_version_to_proto(value: &everruns_core::FileVersion) -> proto::FileVersion {
+    proto::FileVersion {
+        id: Some(uuid_to_proto_uuid(value.id)),
+        session_id: Some(uuid_to_proto_uuid(value.session_id)),
+        path: value.path.clone(),
+        version: value.version,
+        size_bytes: value.size_bytes,
+        is_deleted: value.is_deleted,
+        created_at: Some(datetime_to_proto_timestamp(value.created_at)),
+    }
+}
+
+/// Convert proto SessionSnapshot to schemas SessionSnapshot
+pub fn proto_session_snapshot_to_schema(
+    value: proto::SessionSnapshot,
+) -> Result<everruns_core::SessionSnapshot, ConversionError> {
+    let id = value
+        .id
+        .as_ref()
+        .ok_or(ConversionError::MissingField("id"))?;
+    let id = proto_uuid_to_uuid(id)?;
+    let session_id = value
+        .session_id
+        .as_ref()
+        .ok_or(ConversionError::MissingField("session_id"))?;
+    let session_id = proto_uuid_to_uuid(session_id)?;
+    let turn_id = value.turn_id.as_ref().map(proto_uuid_to_uuid).transpose()?;
+    let created_at = value
+        .created_at
+        .as_ref()
+        .map(proto_timestamp_to_datetime)
+        .ok_or(ConversionError::MissingField("created_at"))?;
+
+    Ok(everruns_core::SessionSnapshot {
+        id,
+        session_id,
+        name: value.name,
+        kind: everruns_core::SnapshotKind::from(value.kind.as_str()),
+        turn_id,
+        turn_number: value.turn_number,
+        file_count: value.file_count,
+        created_at,
+    })
+}
+
+/// Convert schemas SessionSnapshot to proto SessionSnapshot
+pub fn schema_session_snapshot_to_proto(
+    value: &everruns_core::SessionSnapshot,
+) -> proto::SessionSnapshot {
+    proto::SessionSnapshot {
+        id: Some(uuid_to_proto_uuid(value.id)),
+        session_id: Some(uuid_to_proto_uuid(value.session_id)),
+        name: value.name.clone(),
+        kind: value.kind.as_str().to_string(),
+        turn_id: value.turn_id.map(uuid_to_proto_uuid),
+        turn_number: value.turn_number,
+        file_count: value.file_count,
+        created_at: Some(datetime_to_proto_timestamp(value.created_at)),
+    }
+}
+
+/// Convert proto RestoreTarget to schemas RestoreTarget
+///
+/// Exactly one of version, snapshot_id or turn_id must be set.
+pub fn proto_restore_target_to_schema(
+    value: proto::RestoreTarget,
+) -> Result<everruns_core::RestoreTarget, ConversionError> {
+    match (value.version, value.snapshot_id, value.turn_id) {
+        (Some(version), None, None) => Ok(everruns_core::RestoreTarget::Version(version)),
+        (None, Some(snapshot_id), None) => Ok(everruns_core::RestoreTarget::Snapshot(
+            proto_uuid_to_uuid(&snapshot_id)?,
+        )),
+        (None, None, Some(turn_id)) => Ok(everruns_core::RestoreTarget::Turn(proto_uuid_to_uuid(
+            &turn_id,
+        )?)),
+        _ => Err(ConversionError::MissingField(
+            "target (exactly one of version, snapshot_id, turn_id)",
+        )),
+    }
+}
+
+/// Convert schemas RestoreTarget to proto RestoreTarget
+pub fn schema_restore_target_to_proto(
+    value: &everruns_core::RestoreTarget,
+) -> proto::RestoreTarget {
+    match value {
+        everruns_core::RestoreTarget::Version(version) => proto::RestoreTarget {
+            version: Some(*version),
+            ..Default::default()
+        },
+        everruns_core::RestoreTarget::Snapshot(id) => proto::RestoreTarget {
+            snapshot_id: Some(uuid_to_proto_uuid(*id)),
+            ..Default::default()
+        },
+        everruns_core::RestoreTarget::Turn(id) => proto::RestoreTarget {
+            turn_id: Some(uuid_to_proto_uuid(*id)),
+            ..Default::default()
+        },
+    }
+}
+
+/// Convert proto FileChange to schemas FileChange
+pub fn proto_file_change_to_schema(value: proto::FileChange) -> everruns_core::FileChange {
+    if value.delete {
+        everruns_core::FileChange::Delete { path: value.path }
+    } else {
+        everruns_core::FileChange::Write {
+            path: value.path,
+            content: value.content,
+            encoding: value.encoding,
+        }
+    }
+}
+
+/// Convert schemas FileChange to proto FileChange
+pub fn schema_file_change_to_proto(value: &everruns_core::FileChange) -> proto::FileChange {
+    match value {
+        everruns_core::FileChange::Write {
+            path,
+            content,
+            encoding,
+        } => proto::FileChange {
+            path: path.clone(),
+            content: content.clone(),
+            encoding: encoding.clone(),
+            delete: false,
+        },
+        everruns_core::FileChange::Delete { path } => proto::FileChange {
+            path: path.clone(),
+            delete: true,
+            ..Default::default()
+        },
+    }
+}
+
 // ============================================================================
 // Helper functions
 // ============================================================================
@@ -938,7 +1177,16 @@ mod tests {
                 CapabilityId::new("tools:read_file"),
                 CapabilityId::new("tools:write_file"),
             ],
+            response_format: None,
+            parallel_tool_calls: None,
+            capability_config: [(
+                "tools:read_file".to_string(),
+                serde_json::json!({"max_bytes": 1024}),
+            )]
+            .into(),
+            mcp_servers: vec![],
             status: everruns_core::AgentStatus::Active,
+            revision: 1,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -966,6 +1214,7 @@ mod tests {
         assert!(schema_agent
             .capabilities
             .contains(&CapabilityId::new("tools:write_file")));
+        assert_eq!(schema_agent.capability_config, agent.capability_config);
     }
 
     #[test]
@@ -982,7 +1231,12 @@ mod tests {
             default_model_id: None,
             tags: vec![],
             capabilities: vec![],
+            response_format: None,
+            parallel_tool_calls: None,
+            capability_config: Default::default(),
+            mcp_servers: vec![],
             status: everruns_core::AgentStatus::Active,
+            revision: 1,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -999,4 +1253,120 @@ mod tests {
         // Verify capabilities remain empty
         assert!(schema_agent.capabilities.is_empty());
     }
+
+    #[test]
+    fn test_mcp_connection_roundtrip() {
+        use everruns_core::mcp::{McpServerConfig, McpServerConnection, McpTransportConfig};
+        use std::collections::HashMap;
+
+        let connection = McpServerConnection::new(
+            McpServerConfig {
+                name: "github".to_string(),
+                transport: McpTransportConfig::Stdio {
+                    command: "github-mcp".to_string(),
+                    args: vec!["stdio".to_string()],
+                    env: HashMap::new(),
+                },
+                credential_names: vec!["GITHUB_TOKEN".to_string()],
+            },
+            HashMap::from([("GITHUB_TOKEN".to_string(), "ghp_secret".to_string())]),
+        );
+
+        let proto_connection = mcp_connection_to_proto(&connection).unwrap();
+        let parsed = proto_mcp_connection_to_schema(proto_connection).unwrap();
+
+        assert_eq!(parsed, connection);
+    }
+
+    #[test]
+    fn test_http_tool_connection_roundtrip() {
+        use everruns_core::http_tool::{
+            HttpToolAuth, HttpToolConnection, HttpToolDefinition, HttpToolEndpoint, HttpToolMethod,
+        };
+
+        let connection = HttpToolConnection::new(
+            HttpToolDefinition {
+                name: "create_ticket".to_string(),
+                description: "Create a ticket".to_string(),
+                parameters: serde_json::json!({"type": "object", "properties": {}}),
+                endpoint: HttpToolEndpoint {
+                    method: HttpToolMethod::Post,
+                    url: "https://example.com/tickets".to_string(),
+                    headers: Default::default(),
+                    body: Some(r#"{"title": {{title}}}"#.to_string()),
+                    auth: Some(HttpToolAuth::Bearer),
+                },
+            },
+            Some("secret".to_string()),
+        );
+
+        let proto_connection = http_tool_connection_to_proto(&connection).unwrap();
+        let parsed = proto_http_tool_connection_to_schema(proto_connection).unwrap();
+
+        assert_eq!(parsed, connection);
+    }
+
+    #[test]
+    fn test_session_snapshot_roundtrip() {
+        use uuid::Uuid;
+
+        let snapshot = everruns_core::SessionSnapshot {
+            id: Uuid::now_v7(),
+            session_id: Uuid::now_v7(),
+            name: "turn-3-end".to_string(),
+            kind: everruns_core::SnapshotKind::TurnEnd,
+            turn_id: Some(Uuid::now_v7()),
+            turn_number: Some(3),
+            file_count: 7,
+            created_at: Utc::now(),
+        };
+
+        let proto_snapshot = schema_session_snapshot_to_proto(&snapshot);
+        let parsed = proto_session_snapshot_to_schema(proto_snapshot).unwrap();
+
+        assert_eq!(parsed.id, snapshot.id);
+        assert_eq!(parsed.kind, everruns_core::SnapshotKind::TurnEnd);
+        assert_eq!(parsed.turn_id, snapshot.turn_id);
+        assert_eq!(parsed.turn_number, Some(3));
+        assert_eq!(parsed.file_count, 7);
+    }
+
+    #[test]
+    fn test_restore_target_requires_exactly_one_field() {
+        use uuid::Uuid;
+
+        let target = everruns_core::RestoreTarget::Version(4);
+        let parsed = proto_restore_target_to_schema(schema_restore_target_to_proto(&target));
+        assert!(matches!(
+            parsed,
+            Ok(everruns_core::RestoreTarget::Version(4))
+        ));
+
+        assert!(proto_restore_target_to_schema(proto::RestoreTarget::default()).is_err());
+        assert!(proto_restore_target_to_schema(proto::RestoreTarget {
+            version: Some(1),
+            turn_id: Some(uuid_to_proto_uuid(Uuid::now_v7())),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_file_change_roundtrip() {
+        let changes = vec![
+            everruns_core::FileChange::Write {
+                path: "/src/main.rs".to_string(),
+                content: "fn main() {}".to_string(),
+                encoding: "text".to_string(),
+            },
+            everruns_core::FileChange::Delete {
+                path: "/old.rs".to_string(),
+            },
+        ];
+
+        for change in changes {
+            let parsed = proto_file_change_to_schema(schema_file_change_to_proto(&change));
+            assert_eq!(parsed, change);
+        }
+    }
 }
@@ -8,7 +8,7 @@ use async_trait::async_trait;
 use everruns_core::error::Result;
 use everruns_core::llm_driver_registry::{
     BoxedLlmDriver, DriverRegistry, LlmCallConfig, LlmDriver, LlmMessage, LlmResponseStream,
-    ProviderType,
+    ProviderType, RateLimitInfo,
 };
 use everruns_core::OpenAIProtocolLlmDriver;
 
@@ -72,6 +72,10 @@ impl LlmDriver for OpenAILlmDriver {
         // Future: Add OpenAI-specific preprocessing here
         self.inner.chat_completion_stream(messages, config).await
     }
+
+    fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        self.inner.last_rate_limit()
+    }
 }
 
 impl std::fmt::Debug for OpenAILlmDriver {
@@ -14,14 +14,20 @@
 
 use anyhow::{Context, Result};
 use everruns_core::atoms::{ActAtom, Atom, InputAtom, ReasonAtom};
-use everruns_core::capabilities::CapabilityRegistry;
-use everruns_core::ToolRegistry;
+use everruns_core::capabilities::{
+    Capability, CapabilityRegistry, HttpTool, HttpToolCapability, McpCapability,
+};
+use everruns_core::mcp::{McpClientPool, MCP_TOOL_SEPARATOR};
+use everruns_core::traits::{AgentStore, SessionStore};
+use everruns_core::{Agent, ToolRegistry};
+use std::collections::HashSet;
 use std::sync::Arc;
+use uuid::Uuid;
 
 use crate::adapters::create_driver_registry;
 use crate::grpc_adapters::{
-    GrpcAgentStore, GrpcClient, GrpcEventEmitter, GrpcLlmProviderStore, GrpcMessageStore,
-    GrpcSessionFileStore, GrpcSessionStore,
+    GrpcAgentStore, GrpcClient, GrpcEventEmitter, GrpcLlmProviderStore, GrpcLlmRateLimiter,
+    GrpcMessageStore, GrpcSessionFileStore, GrpcSessionStore,
 };
 
 // Re-export atom types for activity callers
@@ -29,6 +35,15 @@ pub use everruns_core::atoms::{
     ActInput, ActResult, InputAtomInput, InputAtomResult, ReasonInput, ReasonResult, ToolCallResult,
 };
 
+/// Worker-level access settings for tools that reach other systems
+#[derive(Debug, Default)]
+pub struct ToolAccess {
+    /// MCP connections shared by the steps of a turn
+    pub mcp_clients: McpClientPool,
+    /// Whether user-defined HTTP tools may call private network addresses
+    pub http_private_networks: bool,
+}
+
 // ============================================================================
 // Activity Implementations
 // ============================================================================
@@ -78,13 +93,21 @@ pub async fn input_activity(
         tracing::warn!(error = %e, "Failed to emit session.activated event");
     }
 
-    // Emit turn.started event
+    // Emit turn.started event with the agent revision the turn runs with
+    let agent_revision = match turn_agent_revision(&grpc_client, input.context.session_id).await {
+        Ok(revision) => revision,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to resolve agent revision");
+            None
+        }
+    };
     let turn_started_event = EventRequest::new(
         input.context.session_id,
         EventContext::turn(input.context.turn_id, input.context.input_message_id),
         TurnStartedData {
             turn_id: input.context.turn_id,
             input_message_id: input.context.input_message_id,
+            agent_revision,
         },
     );
     if let Err(e) = event_emitter.emit(turn_started_event).await {
@@ -111,8 +134,14 @@ pub async fn input_activity(
 /// 7. Returns the result with tool calls (if any)
 /// 8. If turn completes (no tool calls), emits turn.completed, sets session status to "idle" and emits session.idled
 ///
+/// MCP connections are taken from `tools.mcp_clients` and released when the turn completes.
+///
 /// Note: API key decryption is handled by the control-plane gRPC service.
-pub async fn reason_activity(grpc_client: GrpcClient, input: ReasonInput) -> Result<ReasonResult> {
+pub async fn reason_activity(
+    grpc_client: GrpcClient,
+    tools: &ToolAccess,
+    input: ReasonInput,
+) -> Result<ReasonResult> {
     use everruns_core::events::{
         EventContext, EventRequest, SessionIdledData, TurnCompletedData, TurnFailedData,
     };
@@ -134,7 +163,40 @@ pub async fn reason_activity(grpc_client: GrpcClient, input: ReasonInput) -> Res
     let session_store = GrpcSessionStore::new(grpc_client.clone());
     let message_store = GrpcMessageStore::new(grpc_client.clone());
     let provider_store = GrpcLlmProviderStore::new(grpc_client.clone());
-    let capability_registry = CapabilityRegistry::with_builtins();
+    let mut capability_registry = CapabilityRegistry::with_builtins();
+    let revision = pinned_agent_revision(&grpc_client, session_id).await?;
+    // Replace the empty MCP capability with the agent's discovered servers
+    let mcp_servers = grpc_client
+        .get_agent_mcp_servers(input.agent_id, revision)
+        .await?;
+    if !mcp_servers.is_empty() {
+        capability_registry.register(
+            McpCapability::discover(&mcp_servers, &tools.mcp_clients, input_message_id).await,
+        );
+    }
+    // User-defined HTTP tools are registered as `custom:<name>` capabilities;
+    // a tool shadowing a registered tool name is skipped
+    let registered: HashSet<String> = capability_registry
+        .list()
+        .iter()
+        .flat_map(|capability| capability.tools())
+        .map(|tool| tool.name().to_string())
+        .collect();
+    for connection in grpc_client
+        .get_agent_http_tools(input.agent_id, revision)
+        .await?
+    {
+        if registered.contains(&connection.definition.name) {
+            tracing::warn!(
+                tool = %connection.definition.name,
+                "Skipping HTTP tool whose name collides with a registered tool"
+            );
+            continue;
+        }
+        capability_registry.register(
+            HttpToolCapability::new(connection).allow_private_networks(tools.http_private_networks),
+        );
+    }
     let driver_registry = create_driver_registry();
     let event_emitter = GrpcEventEmitter::new(grpc_client.clone());
 
@@ -146,7 +208,9 @@ pub async fn reason_activity(grpc_client: GrpcClient, input: ReasonInput) -> Res
         capability_registry,
         driver_registry,
         event_emitter,
-    );
+    )
+    .with_file_store(Arc::new(GrpcSessionFileStore::new(grpc_client.clone())))
+    .with_rate_limiter(Arc::new(GrpcLlmRateLimiter::new(grpc_client.clone())));
 
     let result = atom
         .execute(input)
@@ -156,6 +220,8 @@ pub async fn reason_activity(grpc_client: GrpcClient, input: ReasonInput) -> Res
     // If turn is complete (no tool calls, or failure), set session to idle
     let turn_complete = !result.has_tool_calls || !result.success;
     if turn_complete {
+        tools.mcp_clients.release(input_message_id);
+
         // Set session status to "idle"
         if let Err(e) = grpc_client.set_session_status(session_id, "idle").await {
             tracing::warn!(error = %e, "Failed to set session status to idle");
@@ -220,7 +286,11 @@ pub async fn reason_activity(grpc_client: GrpcClient, input: ReasonInput) -> Res
 /// 4. Stores tool result messages
 /// 5. Emits act.completed event
 /// 6. Returns comprehensive results for all tools
-pub async fn act_activity(grpc_client: GrpcClient, input: ActInput) -> Result<ActResult> {
+pub async fn act_activity(
+    grpc_client: GrpcClient,
+    tools: &ToolAccess,
+    input: ActInput,
+) -> Result<ActResult> {
     tracing::info!(
         session_id = %input.context.session_id,
         turn_id = %input.context.turn_id,
@@ -228,7 +298,73 @@ pub async fn act_activity(grpc_client: GrpcClient, input: ActInput) -> Result<Ac
         "Executing act_activity"
     );
 
-    let tool_executor = ToolRegistry::with_defaults();
+    let mut tool_executor = ToolRegistry::with_defaults();
+
+    // Configured capabilities build their own tools (e.g. a read-only file system):
+    // replace the default implementations so calls honor the agent's config
+    let revision = pinned_agent_revision(&grpc_client, input.context.session_id).await?;
+    let agent = get_agent(&grpc_client, input.agent_id, revision).await?;
+    if let Some(agent) = agent.filter(|a| !a.capability_config.is_empty()) {
+        let registry = CapabilityRegistry::with_builtins();
+        for (capability_id, config) in &agent.capability_config {
+            let Some(capability) = registry.get(capability_id) else {
+                continue;
+            };
+            for tool in capability.tools() {
+                tool_executor.unregister(tool.name());
+            }
+            for tool in capability.configured_tools(config) {
+                tool_executor.register_boxed(tool);
+            }
+        }
+    }
+
+    // MCP tools are not compiled in: connect to the servers the calls refer to
+    if input
+        .tool_calls
+        .iter()
+        .any(|call| call.name.contains(MCP_TOOL_SEPARATOR))
+    {
+        let servers: Vec<_> = grpc_client
+            .get_agent_mcp_servers(input.agent_id, revision)
+            .await?
+            .into_iter()
+            .filter(|server| {
+                let prefix = server.config.tool_prefix();
+                input
+                    .tool_calls
+                    .iter()
+                    .any(|call| call.name.starts_with(&prefix))
+            })
+            .collect();
+        let capability =
+            McpCapability::discover(&servers, &tools.mcp_clients, input.context.input_message_id)
+                .await;
+        for tool in capability.tools() {
+            tool_executor.register_boxed(tool);
+        }
+    }
+
+    // User-defined HTTP tools are not compiled in either
+    if input
+        .tool_calls
+        .iter()
+        .any(|call| !tool_executor.has(&call.name))
+    {
+        for connection in grpc_client
+            .get_agent_http_tools(input.agent_id, revision)
+            .await?
+        {
+            // Never replace a registered tool with a user-defined one
+            let name = connection.definition.name.clone();
+            if !tool_executor.has(&name) && input.tool_calls.iter().any(|call| call.name == name) {
+                tool_executor.register(
+                    HttpTool::new(connection).allow_private_networks(tools.http_private_networks),
+                );
+            }
+        }
+    }
+
     let event_emitter = GrpcEventEmitter::new(grpc_client.clone());
     let file_store = Arc::new(GrpcSessionFileStore::new(grpc_client));
 
@@ -239,6 +375,47 @@ pub async fn act_activity(grpc_client: GrpcClient, input: ActInput) -> Result<Ac
         .context("ActAtom execution failed")
 }
 
+// ============================================================================
+// Agent revision helpers
+// ============================================================================
+
+/// Agent revision pinned by the session (None follows the latest revision)
+async fn pinned_agent_revision(grpc_client: &GrpcClient, session_id: Uuid) -> Result<Option<i32>> {
+    let session = GrpcSessionStore::new(grpc_client.clone())
+        .get_session(session_id)
+        .await?;
+    Ok(session.and_then(|s| s.agent_revision))
+}
+
+/// Agent revision a turn of the session runs with: the pinned one, else the latest
+async fn turn_agent_revision(grpc_client: &GrpcClient, session_id: Uuid) -> Result<Option<i32>> {
+    let Some(session) = GrpcSessionStore::new(grpc_client.clone())
+        .get_session(session_id)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if session.agent_revision.is_some() {
+        return Ok(session.agent_revision);
+    }
+    let agent = get_agent(grpc_client, session.agent_id, None).await?;
+    Ok(agent.map(|a| a.revision))
+}
+
+/// Load the agent at a revision (latest when None)
+async fn get_agent(
+    grpc_client: &GrpcClient,
+    agent_id: Uuid,
+    revision: Option<i32>,
+) -> Result<Option<Agent>> {
+    let store = GrpcAgentStore::new(grpc_client.clone());
+    let agent = match revision {
+        Some(revision) => store.get_agent_revision(agent_id, revision).await?,
+        None => store.get_agent(agent_id).await?,
+    };
+    Ok(agent)
+}
+
 // ============================================================================
 // Activity Type Constants
 // ============================================================================
@@ -315,6 +492,7 @@ mod tests {
                 parameters: json!({}),
                 policy: ToolPolicy::Auto,
             })],
+            sequential: false,
         };
 
         let json = serde_json::to_string(&input).unwrap();
@@ -332,6 +510,7 @@ mod tests {
             has_tool_calls: false,
             tool_definitions: vec![],
             max_iterations: 10,
+            sequential_tool_calls: false,
             error: None,
         };
 
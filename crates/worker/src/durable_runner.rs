@@ -3,6 +3,7 @@
 // Decision: AgentRunner interface for clean abstraction
 // Decision: Workers communicate with control-plane via gRPC (no direct DB access)
 // Decision: Control-plane uses direct database access (PostgresWorkflowEventStore)
+// Decision: With session workflows enabled, messages signal the session workflow instead
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -14,12 +15,21 @@ use uuid::Uuid;
 
 use crate::grpc_durable_store::{GrpcDurableStore, WorkflowStatus};
 use crate::runner::AgentRunner;
-use everruns_durable::{PostgresWorkflowEventStore, WorkflowEventStore};
+use crate::session_workflow::{
+    message_signal, session_queries, session_signals, session_workflow_executor,
+    session_workflow_id, SessionState, SessionWorkflow, SessionWorkflowInput,
+};
+use everruns_durable::{
+    PostgresWorkflowEventStore, WorkflowEventStore, WorkflowExecutor, WorkflowSignal,
+};
 
 // =============================================================================
 // TurnWorkflow Input/Output
 // =============================================================================
 
+/// Workflow type of the per-message turn workflow
+pub const TURN_WORKFLOW_TYPE: &str = "turn_workflow";
+
 /// Input for the turn workflow
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DurableTurnInput {
@@ -238,6 +248,8 @@ fn local_to_durable_status(s: WorkflowStatus) -> everruns_durable::WorkflowStatu
 /// - Control-plane uses direct database access
 pub struct DurableRunner {
     store: Arc<Mutex<dyn DurableStoreBackend>>,
+    /// Drives session workflows when they are enabled (direct DB mode only)
+    session_executor: Option<WorkflowExecutor<PostgresWorkflowEventStore>>,
 }
 
 impl DurableRunner {
@@ -255,6 +267,7 @@ impl DurableRunner {
 
         Ok(Self {
             store: Arc::new(Mutex::new(store)),
+            session_executor: None,
         })
     }
 
@@ -267,9 +280,33 @@ impl DurableRunner {
 
         Self {
             store: Arc::new(Mutex::new(store)),
+            session_executor: None,
         }
     }
 
+    /// Run each session as a long-lived session workflow
+    pub fn with_session_workflows(mut self, pool: sqlx::PgPool) -> Self {
+        info!("Session workflows enabled");
+        self.session_executor = Some(session_workflow_executor(pool));
+        self
+    }
+
+    /// Running turn of a session workflow, if any
+    async fn session_turn(
+        executor: &WorkflowExecutor<PostgresWorkflowEventStore>,
+        session_id: Uuid,
+    ) -> Result<Option<Uuid>> {
+        let state = executor
+            .query_workflow(
+                session_workflow_id(session_id),
+                session_queries::STATE,
+                serde_json::Value::Null,
+            )
+            .await?;
+        let state: SessionState = serde_json::from_value(state)?;
+        Ok(state.current_turn)
+    }
+
     /// Create from GRPC_ADDRESS environment variable (defaults to 127.0.0.1:9001)
     /// Used by workers
     pub async fn from_env() -> Result<Self> {
@@ -289,6 +326,28 @@ impl AgentRunner for DurableRunner {
         agent_id: Uuid,
         input_message_id: Uuid,
     ) -> Result<()> {
+        if let Some(executor) = &self.session_executor {
+            let workflow_id = session_workflow_id(session_id);
+            info!(
+                session_id = %session_id,
+                workflow_id = %workflow_id,
+                input_message_id = %input_message_id,
+                "Signalling session workflow with message"
+            );
+
+            executor
+                .signal_with_start::<SessionWorkflow>(
+                    workflow_id,
+                    SessionWorkflowInput::new(session_id, agent_id),
+                    message_signal(input_message_id),
+                    None,
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to signal session workflow: {}", e))?;
+
+            return Ok(());
+        }
+
         info!(
             session_id = %session_id,
             agent_id = %agent_id,
@@ -334,7 +393,7 @@ impl AgentRunner for DurableRunner {
 
         // Create new workflow
         store
-            .create_workflow(workflow_id, "turn_workflow", input_json.clone())
+            .create_workflow(workflow_id, TURN_WORKFLOW_TYPE, input_json.clone())
             .await
             .map_err(|e| anyhow::anyhow!("Failed to create workflow: {}", e))?;
 
@@ -361,6 +420,23 @@ impl AgentRunner for DurableRunner {
     async fn cancel_run(&self, session_id: Uuid) -> Result<()> {
         info!(session_id = %session_id, "Cancelling durable workflow");
 
+        if let Some(executor) = &self.session_executor {
+            // Stop the running turn; the session keeps accepting messages
+            let workflow_id = session_workflow_id(session_id);
+            executor
+                .send_signal(
+                    workflow_id,
+                    WorkflowSignal::new(session_signals::STOP_TURN, serde_json::json!({})),
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to stop session turn: {}", e))?;
+            executor
+                .process_workflow(workflow_id)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to stop session turn: {}", e))?;
+            return Ok(());
+        }
+
         let workflow_id = session_id;
         let mut store = self.store.lock().await;
 
@@ -385,6 +461,10 @@ impl AgentRunner for DurableRunner {
     }
 
     async fn is_running(&self, session_id: Uuid) -> bool {
+        if let Some(executor) = &self.session_executor {
+            return matches!(Self::session_turn(executor, session_id).await, Ok(Some(_)));
+        }
+
         let workflow_id = session_id;
         let mut store = self.store.lock().await;
 
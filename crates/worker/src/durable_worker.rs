@@ -1,9 +1,14 @@
 // Durable execution engine worker
 // Decision: Polls task queue via gRPC instead of direct database access
 // Decision: Uses gRPC adapters for control-plane communication
+// Decision: Turns of a session workflow report completion with a signal instead of
+//           completing the workflow
+// Decision: Turn steps are chained under the turn's first activity and enqueued as the
+//           previous step completes, so a stopped turn cancels them and chains no more
 
 use anyhow::Result;
 use everruns_core::atoms::AtomContext;
+use everruns_core::mcp::{McpClientPool, MCP_ALLOW_PRIVATE_NETWORKS_ENV, MCP_STDIO_COMMANDS_ENV};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{watch, Mutex};
@@ -12,11 +17,14 @@ use uuid::Uuid;
 
 use crate::activities::{
     act_activity, input_activity, reason_activity, ActInput, InputAtomInput, ReasonInput,
-    ReasonResult,
+    ReasonResult, ToolAccess,
 };
 use crate::durable_runner::DurableTurnInput;
 use crate::grpc_adapters::GrpcClient;
 use crate::grpc_durable_store::{ClaimedTask, GrpcDurableStore, WorkflowStatus};
+use crate::session_workflow::{
+    session_signals, session_workflow_id, SessionWorkflowInput, SESSION_WORKFLOW_TYPE,
+};
 
 // =============================================================================
 // Configuration
@@ -29,6 +37,8 @@ pub struct DurableWorkerConfig {
     pub worker_id: String,
     /// Activity types this worker handles
     pub activity_types: Vec<String>,
+    /// Task queues this worker claims from
+    pub task_queues: Vec<String>,
     /// Maximum concurrent tasks
     pub max_concurrent_tasks: usize,
     /// Poll interval when no tasks available
@@ -37,6 +47,12 @@ pub struct DurableWorkerConfig {
     pub heartbeat_interval: Duration,
     /// gRPC address for control-plane communication
     pub grpc_address: String,
+    /// Commands this worker may spawn for stdio MCP servers (none by default)
+    pub mcp_stdio_commands: Vec<String>,
+    /// Whether HTTP MCP servers may use private network addresses
+    pub mcp_private_networks: bool,
+    /// Whether user-defined HTTP tools may call private network addresses
+    pub http_tools_private_networks: bool,
 }
 
 impl Default for DurableWorkerConfig {
@@ -48,10 +64,14 @@ impl Default for DurableWorkerConfig {
                 "reason".to_string(),
                 "act".to_string(),
             ],
+            task_queues: vec![everruns_durable::DEFAULT_TASK_QUEUE.to_string()],
             max_concurrent_tasks: 10,
             poll_interval: Duration::from_secs(1),
             heartbeat_interval: Duration::from_secs(10),
             grpc_address: "127.0.0.1:9001".to_string(),
+            mcp_stdio_commands: Vec::new(),
+            mcp_private_networks: false,
+            http_tools_private_networks: false,
         }
     }
 }
@@ -70,12 +90,43 @@ impl DurableWorkerConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(10);
 
-        Self {
+        let mut config = Self {
             worker_id,
             grpc_address,
             max_concurrent_tasks: max_concurrent,
             ..Default::default()
+        };
+
+        // Comma-separated, e.g. "sandbox,default"
+        if let Ok(queues) = std::env::var("WORKER_TASK_QUEUES") {
+            let queues: Vec<String> = queues
+                .split(',')
+                .map(|q| q.trim().to_string())
+                .filter(|q| !q.is_empty())
+                .collect();
+            if !queues.is_empty() {
+                config.task_queues = queues;
+            }
         }
+
+        // Comma-separated, e.g. "npx,uvx"
+        if let Ok(commands) = std::env::var(MCP_STDIO_COMMANDS_ENV) {
+            config.mcp_stdio_commands = commands
+                .split(',')
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect();
+        }
+
+        config.mcp_private_networks = std::env::var(MCP_ALLOW_PRIVATE_NETWORKS_ENV)
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        config.http_tools_private_networks = std::env::var("HTTP_TOOLS_ALLOW_PRIVATE_NETWORKS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+
+        config
     }
 }
 
@@ -88,6 +139,8 @@ pub struct DurableWorker {
     config: DurableWorkerConfig,
     store: Arc<Mutex<GrpcDurableStore>>,
     grpc_address: String,
+    /// MCP connections and network access of tools
+    tools: ToolAccess,
     shutdown_tx: watch::Sender<bool>,
     shutdown_rx: watch::Receiver<bool>,
 }
@@ -98,12 +151,18 @@ impl DurableWorker {
         info!(
             worker_id = %config.worker_id,
             grpc_address = %config.grpc_address,
+            task_queues = ?config.task_queues,
             max_concurrent = config.max_concurrent_tasks,
             "Initializing durable worker (gRPC mode)"
         );
 
         let store = GrpcDurableStore::connect(&config.grpc_address).await?;
         let grpc_address = config.grpc_address.clone();
+        let tools = ToolAccess {
+            mcp_clients: McpClientPool::new(config.mcp_stdio_commands.clone())
+                .allow_private_networks(config.mcp_private_networks),
+            http_private_networks: config.http_tools_private_networks,
+        };
 
         let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
@@ -113,6 +172,7 @@ impl DurableWorker {
             config,
             store: Arc::new(Mutex::new(store)),
             grpc_address,
+            tools,
             shutdown_tx,
             shutdown_rx,
         })
@@ -177,6 +237,7 @@ impl DurableWorker {
             store
                 .claim_tasks(
                     &self.config.worker_id,
+                    &self.config.task_queues,
                     &self.config.activity_types,
                     self.config.max_concurrent_tasks,
                 )
@@ -205,8 +266,26 @@ impl DurableWorker {
                 );
 
                 // Report failure to store
-                let mut store = self.store.lock().await;
-                let _ = store.fail_task(task.id, &e.to_string()).await;
+                let will_retry = {
+                    let mut store = self.store.lock().await;
+                    store.fail_task(task.id, &e.to_string()).await
+                };
+
+                // A session turn that gave up must still free the session
+                if let (Ok(false), Ok(Some(turn_input))) = (will_retry, turn_input(task)) {
+                    if is_session_turn(task.workflow_id, &turn_input) {
+                        if let Err(e) = self
+                            .complete_session_turn(task.workflow_id, &turn_input)
+                            .await
+                        {
+                            error!(
+                                workflow_id = %task.workflow_id,
+                                error = %e,
+                                "Failed to report session turn failure"
+                            );
+                        }
+                    }
+                }
             }
         }
 
@@ -261,75 +340,72 @@ impl DurableWorker {
         });
 
         // Execute based on activity type - different activities have different input formats
-        let (result, turn_input_opt) = match task.activity_type.as_str() {
-            "process_input" | "reason" => {
-                // These activities use DurableTurnInput
-                let turn_input: DurableTurnInput = serde_json::from_value(task.input.clone())
-                    .map_err(|e| anyhow::anyhow!("Failed to parse task input: {}", e))?;
-                let res = match task.activity_type.as_str() {
-                    "process_input" => self.execute_input_activity(grpc_client, &turn_input).await,
-                    "reason" => self.execute_reason_activity(grpc_client, &turn_input).await,
-                    _ => unreachable!(),
-                };
-                (res, Some(turn_input))
+        let turn_input_opt = turn_input(task)?;
+        let result = match (task.activity_type.as_str(), &turn_input_opt) {
+            ("process_input", Some(turn_input)) => {
+                self.execute_input_activity(grpc_client, turn_input).await
             }
-            "act" => {
-                // Act activity uses ActInput directly - parse it to extract session context
-                let act_input: ActInput = serde_json::from_value(task.input.clone())
-                    .map_err(|e| anyhow::anyhow!("Failed to parse ActInput: {}", e))?;
-                // Create DurableTurnInput from ActInput context for scheduling next activity
-                let turn_input = DurableTurnInput {
-                    session_id: act_input.context.session_id,
-                    agent_id: act_input.agent_id, // Pass through agent_id for follow-up reason
-                    input_message_id: act_input.context.input_message_id,
-                };
-                let res = self.execute_act_activity(grpc_client, &task.input).await;
-                (res, Some(turn_input))
+            ("reason", Some(turn_input)) => {
+                self.execute_reason_activity(grpc_client, turn_input).await
             }
-            _ => (
-                Err(anyhow::anyhow!(
-                    "Unknown activity type: {}",
-                    task.activity_type
-                )),
-                None,
-            ),
+            ("act", Some(_)) => self.execute_act_activity(grpc_client, &task.input).await,
+            _ => Err(anyhow::anyhow!(
+                "Unknown activity type: {}",
+                task.activity_type
+            )),
         };
 
         // Stop heartbeat loop
         let _ = cancel_tx.send(());
         let _ = heartbeat_handle.await;
 
-        match result {
-            Ok(output) => {
-                // Complete the task
-                {
-                    let mut store = self.store.lock().await;
+        let output = result?;
+        let next = match &turn_input_opt {
+            Some(turn_input) => Some(next_step(task, turn_input, &output)?),
+            None => None,
+        };
+
+        // The next step is chained atomically, so a stopped turn enqueues nothing
+        let completed = {
+            let mut store = self.store.lock().await;
+            match &next {
+                Some(NextStep::Enqueue {
+                    activity_id,
+                    activity_type,
+                    input,
+                }) => {
                     store
-                        .complete_task(task.id, output.clone())
+                        .complete_and_enqueue(
+                            task.id,
+                            output,
+                            task.workflow_id,
+                            activity_id.clone(),
+                            activity_type.to_string(),
+                            input.clone(),
+                        )
                         .await
-                        .map_err(|e| anyhow::anyhow!("Failed to complete task: {}", e))?;
                 }
+                _ => store.complete_task(task.id, output).await,
+            }
+            .map_err(|e| anyhow::anyhow!("Failed to complete task: {}", e))?
+        };
+        if !completed {
+            info!(
+                task_id = %task.id,
+                workflow_id = %task.workflow_id,
+                "Task was cancelled, not scheduling next activity"
+            );
+            return Ok(());
+        }
 
-                info!(
-                    task_id = %task.id,
-                    activity_type = %task.activity_type,
-                    "Task completed successfully"
-                );
+        info!(
+            task_id = %task.id,
+            activity_type = %task.activity_type,
+            "Task completed successfully"
+        );
 
-                // Check if workflow is complete and schedule next activity
-                if let Some(turn_input) = turn_input_opt {
-                    self.schedule_next_activity(
-                        task.workflow_id,
-                        &task.activity_type,
-                        &turn_input,
-                        &output,
-                    )
-                    .await?;
-                }
-            }
-            Err(e) => {
-                return Err(e);
-            }
+        if let (Some(NextStep::EndTurn), Some(turn_input)) = (&next, &turn_input_opt) {
+            self.end_turn(task.workflow_id, turn_input).await?;
         }
 
         Ok(())
@@ -387,7 +463,7 @@ impl DurableWorker {
         };
 
         // Use the existing reason_activity function with gRPC adapters
-        let result = reason_activity(grpc_client, reason_input).await?;
+        let result = reason_activity(grpc_client, &self.tools, reason_input).await?;
 
         Ok(serde_json::to_value(&result)?)
     }
@@ -409,109 +485,151 @@ impl DurableWorker {
         );
 
         // Use the existing act_activity function with gRPC adapters
-        let result = act_activity(grpc_client, act_input).await?;
+        let result = act_activity(grpc_client, &self.tools, act_input).await?;
 
         Ok(serde_json::to_value(&result)?)
     }
 
-    /// Schedule the next activity based on current activity completion
-    async fn schedule_next_activity(
+    /// End a turn whose last step completed
+    async fn end_turn(&self, workflow_id: Uuid, input: &DurableTurnInput) -> Result<()> {
+        if is_session_turn(workflow_id, input) {
+            // The session lives on
+            self.complete_session_turn(workflow_id, input).await?;
+            info!(workflow_id = %workflow_id, "Session turn completed");
+        } else {
+            let mut store = self.store.lock().await;
+            store
+                .update_workflow_status(workflow_id, WorkflowStatus::Completed, None, None)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to update workflow status: {}", e))?;
+            info!(workflow_id = %workflow_id, "Workflow completed");
+        }
+        Ok(())
+    }
+
+    /// Tell the session workflow its turn finished
+    async fn complete_session_turn(
         &self,
         workflow_id: Uuid,
-        completed_activity: &str,
         input: &DurableTurnInput,
-        output: &serde_json::Value,
     ) -> Result<()> {
-        let input_json = serde_json::to_value(input)?;
+        let session_input = SessionWorkflowInput::new(input.session_id, input.agent_id);
         let mut store = self.store.lock().await;
+        store
+            .signal_with_start(
+                workflow_id,
+                SESSION_WORKFLOW_TYPE,
+                serde_json::to_value(session_input)?,
+                session_signals::TURN_COMPLETED,
+                serde_json::json!({ "input_message_id": input.input_message_id }),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to signal session turn completion: {}", e))?;
+        Ok(())
+    }
+}
 
-        match completed_activity {
-            "process_input" => {
-                // After input processing, schedule reason activity
-                store
-                    .enqueue_task(
-                        workflow_id,
-                        format!("reason_{}", Uuid::now_v7()),
-                        "reason".to_string(),
-                        input_json,
-                    )
-                    .await
-                    .map_err(|e| anyhow::anyhow!("Failed to enqueue reason task: {}", e))?;
-
-                debug!(workflow_id = %workflow_id, "Scheduled reason activity");
-            }
-            "reason" => {
-                // After reasoning, check if there are tool calls
-                let reason_result: ReasonResult = serde_json::from_value(output.clone())
-                    .map_err(|e| anyhow::anyhow!("Failed to parse ReasonResult: {}", e))?;
-
-                if reason_result.has_tool_calls && reason_result.success {
-                    // Schedule act activity to execute the tool calls
-                    let tool_count = reason_result.tool_calls.len();
-                    let act_input = ActInput {
-                        context: AtomContext {
-                            session_id: input.session_id,
-                            turn_id: Uuid::now_v7(),
-                            input_message_id: input.input_message_id,
-                            exec_id: Uuid::now_v7(),
-                        },
-                        agent_id: input.agent_id, // Pass through for follow-up reason activity
-                        tool_calls: reason_result.tool_calls,
-                        tool_definitions: reason_result.tool_definitions,
-                    };
-                    let act_input_json = serde_json::to_value(&act_input)?;
-
-                    store
-                        .enqueue_task(
-                            workflow_id,
-                            format!("act_{}", Uuid::now_v7()),
-                            "act".to_string(),
-                            act_input_json,
-                        )
-                        .await
-                        .map_err(|e| anyhow::anyhow!("Failed to enqueue act task: {}", e))?;
-
-                    debug!(
-                        workflow_id = %workflow_id,
-                        tool_count = tool_count,
-                        "Scheduled act activity for tool execution"
-                    );
-                } else {
-                    // No tool calls or failure - workflow complete
-                    store
-                        .update_workflow_status(workflow_id, WorkflowStatus::Completed, None, None)
-                        .await
-                        .map_err(|e| anyhow::anyhow!("Failed to update workflow status: {}", e))?;
+/// What follows a completed turn step
+enum NextStep {
+    /// Enqueue the step chained after it
+    Enqueue {
+        activity_id: String,
+        activity_type: &'static str,
+        input: serde_json::Value,
+    },
+    /// The turn is over
+    EndTurn,
+}
 
-                    info!(workflow_id = %workflow_id, "Workflow completed");
-                }
-            }
-            "act" => {
-                // After action, schedule another reason activity (continue the loop)
-                store
-                    .enqueue_task(
-                        workflow_id,
-                        format!("reason_{}", Uuid::now_v7()),
-                        "reason".to_string(),
-                        input_json,
-                    )
-                    .await
-                    .map_err(|e| anyhow::anyhow!("Failed to enqueue reason task: {}", e))?;
-
-                debug!(workflow_id = %workflow_id, "Scheduled reason activity after act");
-            }
-            _ => {
-                warn!(
-                    activity = completed_activity,
-                    "Unknown activity type completed"
-                );
+/// Step that follows a completed turn activity
+fn next_step(
+    task: &ClaimedTask,
+    input: &DurableTurnInput,
+    output: &serde_json::Value,
+) -> Result<NextStep> {
+    match task.activity_type.as_str() {
+        // After input processing or tool execution, reason (again)
+        "process_input" | "act" => Ok(NextStep::Enqueue {
+            activity_id: chained_activity_id(&task.activity_id, "reason"),
+            activity_type: "reason",
+            input: serde_json::to_value(input)?,
+        }),
+        "reason" => {
+            let reason_result: ReasonResult = serde_json::from_value(output.clone())
+                .map_err(|e| anyhow::anyhow!("Failed to parse ReasonResult: {}", e))?;
+
+            // No tool calls or failure - the turn is over
+            if !(reason_result.has_tool_calls && reason_result.success) {
+                return Ok(NextStep::EndTurn);
             }
+
+            // Execute the tool calls
+            let act_input = ActInput {
+                context: AtomContext {
+                    session_id: input.session_id,
+                    turn_id: Uuid::now_v7(),
+                    input_message_id: input.input_message_id,
+                    exec_id: Uuid::now_v7(),
+                },
+                agent_id: input.agent_id, // Pass through for follow-up reason activity
+                tool_calls: reason_result.tool_calls,
+                tool_definitions: reason_result.tool_definitions,
+                sequential: reason_result.sequential_tool_calls,
+            };
+            Ok(NextStep::Enqueue {
+                activity_id: chained_activity_id(&task.activity_id, "act"),
+                activity_type: "act",
+                input: serde_json::to_value(&act_input)?,
+            })
         }
+        other => Err(anyhow::anyhow!(
+            "Unknown activity type completed: {}",
+            other
+        )),
+    }
+}
 
-        Ok(())
+/// Activity id of a turn step
+///
+/// Steps are chained under the turn's first activity (`<activity>/<step>`),
+/// so cancelling the turn's activity cancels them too.
+fn chained_activity_id(activity_id: &str, step: &str) -> String {
+    let root = activity_id
+        .split_once('/')
+        .map_or(activity_id, |(root, _)| root);
+    format!("{}/{}-{}", root, step, Uuid::now_v7())
+}
+
+/// Turn context of a task, for activities that are part of a turn
+fn turn_input(task: &ClaimedTask) -> Result<Option<DurableTurnInput>> {
+    match task.activity_type.as_str() {
+        // These activities use DurableTurnInput
+        "process_input" | "reason" => serde_json::from_value(task.input.clone())
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("Failed to parse task input: {}", e)),
+        "act" => {
+            // Act activity uses ActInput directly - parse it to extract session context
+            let act_input: ActInput = serde_json::from_value(task.input.clone())
+                .map_err(|e| anyhow::anyhow!("Failed to parse ActInput: {}", e))?;
+            // Create DurableTurnInput from ActInput context for scheduling next activity
+            Ok(Some(DurableTurnInput {
+                session_id: act_input.context.session_id,
+                agent_id: act_input.agent_id, // Pass through agent_id for follow-up reason
+                input_message_id: act_input.context.input_message_id,
+            }))
+        }
+        _ => Ok(None),
     }
 }
 
+/// Whether a turn runs under its session's session workflow
+///
+/// Turn workflows use the session id as workflow id; session workflows
+/// derive theirs from it.
+fn is_session_turn(workflow_id: Uuid, input: &DurableTurnInput) -> bool {
+    workflow_id == session_workflow_id(input.session_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -523,4 +641,28 @@ mod tests {
         assert_eq!(config.max_concurrent_tasks, 10);
         assert_eq!(config.grpc_address, "127.0.0.1:9001");
     }
+
+    #[test]
+    fn test_chained_activity_id() {
+        let step = chained_activity_id("turn-1", "reason");
+        assert!(step.starts_with("turn-1/reason-"));
+        // Later steps chain under the same first activity
+        assert!(chained_activity_id(&step, "act").starts_with("turn-1/act-"));
+    }
+
+    #[test]
+    fn test_is_session_turn() {
+        let input = DurableTurnInput {
+            session_id: Uuid::now_v7(),
+            agent_id: Uuid::now_v7(),
+            input_message_id: Uuid::now_v7(),
+        };
+
+        assert!(is_session_turn(
+            session_workflow_id(input.session_id),
+            &input
+        ));
+        // Turn workflows run under the session id itself
+        assert!(!is_session_turn(input.session_id, &input));
+    }
 }
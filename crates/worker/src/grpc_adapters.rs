@@ -9,18 +9,26 @@
 use async_trait::async_trait;
 use everruns_core::error::{AgentLoopError, Result};
 use everruns_core::events::{Event, EventRequest};
-use everruns_core::session_file::{FileInfo, FileStat, GrepMatch, SessionFile};
+use everruns_core::http_tool::HttpToolConnection;
+use everruns_core::mcp::McpServerConnection;
+use everruns_core::session_file::{
+    FileChange, FileDiff, FileInfo, FileStat, FileVersion, GrepMatch, RestoreTarget, SessionFile,
+    SessionSnapshot,
+};
 use everruns_core::traits::{
-    AgentStore, EventEmitter, InputMessage, LlmProviderStore, MessageStore, ModelWithProvider,
-    SessionFileStore, SessionStore,
+    AgentStore, EventEmitter, InputMessage, LlmProviderStore, LlmRateLimiter, MessageStore,
+    ModelWithProvider, SessionFileStore, SessionStore,
 };
-use everruns_core::{Agent, Message, Session};
+use everruns_core::{Agent, Message, RateLimitInfo, Session};
 use everruns_internal_protocol::proto;
 use everruns_internal_protocol::{
-    json_to_proto_list, json_to_proto_struct, proto_list_to_json, proto_struct_to_json,
-    WorkerServiceClient,
+    json_to_proto_list, json_to_proto_struct, proto_file_version_to_schema,
+    proto_http_tool_connection_to_schema, proto_list_to_json, proto_mcp_connection_to_schema,
+    proto_session_snapshot_to_schema, proto_struct_to_json, schema_file_change_to_proto,
+    schema_restore_target_to_proto, WorkerServiceClient,
 };
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tonic::transport::Channel;
 use uuid::Uuid;
@@ -75,6 +83,64 @@ impl GrpcClient {
 
         proto_session_to_session(proto_session)
     }
+
+    /// Get the agent's MCP servers with decrypted credentials
+    /// (at a pinned revision, if given)
+    pub async fn get_agent_mcp_servers(
+        &self,
+        agent_id: Uuid,
+        revision: Option<i32>,
+    ) -> Result<Vec<McpServerConnection>> {
+        let request = proto::GetAgentMcpServersRequest {
+            agent_id: Some(uuid_to_proto(agent_id)),
+            revision,
+        };
+
+        let mut client = self.inner.lock().await;
+        let response = client
+            .get_agent_mcp_servers(request)
+            .await
+            .map_err(|e| grpc_error(format!("Failed to get MCP servers: {}", e)))?;
+
+        response
+            .into_inner()
+            .servers
+            .into_iter()
+            .map(|s| {
+                proto_mcp_connection_to_schema(s)
+                    .map_err(|e| grpc_error(format!("Invalid MCP server: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Get the user-defined HTTP tools attached to the agent with decrypted secrets
+    /// (at a pinned revision, if given)
+    pub async fn get_agent_http_tools(
+        &self,
+        agent_id: Uuid,
+        revision: Option<i32>,
+    ) -> Result<Vec<HttpToolConnection>> {
+        let request = proto::GetAgentHttpToolsRequest {
+            agent_id: Some(uuid_to_proto(agent_id)),
+            revision,
+        };
+
+        let mut client = self.inner.lock().await;
+        let response = client
+            .get_agent_http_tools(request)
+            .await
+            .map_err(|e| grpc_error(format!("Failed to get HTTP tools: {}", e)))?;
+
+        response
+            .into_inner()
+            .tools
+            .into_iter()
+            .map(|t| {
+                proto_http_tool_connection_to_schema(t)
+                    .map_err(|e| grpc_error(format!("Invalid HTTP tool: {}", e)))
+            })
+            .collect()
+    }
 }
 
 // ============================================================================
@@ -256,15 +322,14 @@ impl GrpcAgentStore {
     pub fn new(client: GrpcClient) -> Self {
         Self { client }
     }
-}
 
-#[async_trait]
-impl AgentStore for GrpcAgentStore {
-    async fn get_agent(&self, agent_id: Uuid) -> Result<Option<Agent>> {
+    /// Fetch the agent at a revision (latest when None)
+    async fn fetch_agent(&self, agent_id: Uuid, revision: Option<i32>) -> Result<Option<Agent>> {
         let mut client = self.client.inner.lock().await;
 
         let request = proto::GetAgentRequest {
             agent_id: Some(uuid_to_proto(agent_id)),
+            revision,
         };
 
         let response = client
@@ -282,6 +347,17 @@ impl AgentStore for GrpcAgentStore {
     }
 }
 
+#[async_trait]
+impl AgentStore for GrpcAgentStore {
+    async fn get_agent(&self, agent_id: Uuid) -> Result<Option<Agent>> {
+        self.fetch_agent(agent_id, None).await
+    }
+
+    async fn get_agent_revision(&self, agent_id: Uuid, revision: i32) -> Result<Option<Agent>> {
+        self.fetch_agent(agent_id, Some(revision)).await
+    }
+}
+
 fn proto_agent_to_agent(proto_agent: proto::Agent) -> Result<Agent> {
     let id = proto_uuid_to_uuid(proto_agent.id.as_ref())?;
     let default_model_id = proto_agent
@@ -308,6 +384,20 @@ fn proto_agent_to_agent(proto_agent: proto::Agent) -> Result<Agent> {
         _ => everruns_core::AgentStatus::Active,
     };
 
+    let response_format = proto_agent
+        .response_format
+        .as_ref()
+        .map(|s| serde_json::from_value(proto_struct_to_json(s)))
+        .transpose()
+        .map_err(|e| grpc_error(format!("Failed to parse agent response_format: {}", e)))?;
+    let capability_config = proto_agent
+        .capability_config
+        .as_ref()
+        .map(|s| serde_json::from_value(proto_struct_to_json(s)))
+        .transpose()
+        .map_err(|e| grpc_error(format!("Failed to parse agent capability_config: {}", e)))?
+        .unwrap_or_default();
+
     Ok(Agent {
         id,
         name: proto_agent.name,
@@ -324,7 +414,12 @@ fn proto_agent_to_agent(proto_agent: proto::Agent) -> Result<Agent> {
             .into_iter()
             .filter_map(|s| s.parse().ok())
             .collect(),
+        capability_config,
+        response_format,
+        parallel_tool_calls: proto_agent.parallel_tool_calls,
+        mcp_servers: vec![],
         status,
+        revision: proto_agent.revision,
         created_at,
         updated_at,
     })
@@ -408,6 +503,10 @@ fn proto_session_to_session(proto_session: proto::Session) -> Result<Session> {
         created_at,
         started_at: None,
         finished_at: None,
+        parent_session_id: None,
+        forked_at_event_id: None,
+        agent_revision: proto_session.agent_revision,
+        variables: proto_session.variables.into_iter().collect(),
     })
 }
 
@@ -469,6 +568,98 @@ impl LlmProviderStore for GrpcLlmProviderStore {
     }
 }
 
+// ============================================================================
+// LlmRateLimiter implementation
+// ============================================================================
+
+/// Default longest wait for provider capacity before failing the LLM call
+const DEFAULT_RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(120);
+
+/// gRPC-backed LLM rate limiter
+///
+/// Token buckets live in the control plane, shared by all workers. Acquiring
+/// polls until capacity is available, sleeping for the wait the control
+/// plane reports.
+pub struct GrpcLlmRateLimiter {
+    client: GrpcClient,
+    max_wait: Duration,
+}
+
+impl GrpcLlmRateLimiter {
+    pub fn new(client: GrpcClient) -> Self {
+        Self {
+            client,
+            max_wait: DEFAULT_RATE_LIMIT_MAX_WAIT,
+        }
+    }
+
+    /// Fail LLM calls that cannot get capacity within `max_wait`
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+}
+
+#[async_trait]
+impl LlmRateLimiter for GrpcLlmRateLimiter {
+    async fn acquire(&self, provider_id: Uuid, estimated_tokens: u32) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let request = proto::AcquireLlmRateLimitRequest {
+                provider_id: Some(uuid_to_proto(provider_id)),
+                estimated_tokens,
+            };
+            let response = {
+                let mut client = self.client.inner.lock().await;
+                client
+                    .acquire_llm_rate_limit(request)
+                    .await
+                    .map_err(|e| grpc_error(format!("gRPC acquire_llm_rate_limit failed: {}", e)))?
+                    .into_inner()
+            };
+            if response.acquired {
+                return Ok(());
+            }
+
+            let waited = started.elapsed();
+            let retry_after = Duration::from_millis(response.retry_after_ms);
+            if waited + retry_after > self.max_wait {
+                return Err(AgentLoopError::llm(format!(
+                    "LLM provider {} is rate limited: no capacity within {:?} (retry after {:?})",
+                    provider_id, self.max_wait, retry_after
+                )));
+            }
+            tracing::debug!(
+                provider_id = %provider_id,
+                retry_after_ms = response.retry_after_ms,
+                "Waiting for LLM provider rate limit"
+            );
+            tokio::time::sleep(retry_after.max(Duration::from_millis(10))).await;
+        }
+    }
+
+    async fn observe(&self, provider_id: Uuid, rate_limit: &RateLimitInfo) -> Result<()> {
+        let millis = |d: Option<Duration>| d.map(|d| d.as_millis() as u64);
+        let request = proto::ReportLlmRateLimitRequest {
+            provider_id: Some(uuid_to_proto(provider_id)),
+            request_limit: rate_limit.request_limit,
+            requests_remaining: rate_limit.requests_remaining,
+            requests_reset_ms: millis(rate_limit.requests_reset),
+            token_limit: rate_limit.token_limit,
+            tokens_remaining: rate_limit.tokens_remaining,
+            tokens_reset_ms: millis(rate_limit.tokens_reset),
+            retry_after_ms: millis(rate_limit.retry_after),
+        };
+
+        let mut client = self.client.inner.lock().await;
+        client
+            .report_llm_rate_limit(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC report_llm_rate_limit failed: {}", e)))?;
+        Ok(())
+    }
+}
+
 fn proto_model_with_provider_to_model(
     proto: proto::ModelWithProvider,
 ) -> Result<ModelWithProvider> {
@@ -490,6 +681,10 @@ fn proto_model_with_provider_to_model(
         provider_type,
         api_key: proto.api_key.filter(|s| !s.is_empty()),
         base_url: proto.base_url.filter(|s| !s.is_empty()),
+        provider_id: proto
+            .provider_id
+            .as_ref()
+            .and_then(|id| Uuid::parse_str(&id.value).ok()),
     })
 }
 
@@ -672,6 +867,205 @@ impl SessionFileStore for GrpcSessionFileStore {
 
         proto_file_info_to_file_info(proto_info)
     }
+
+    async fn glob_files(&self, session_id: Uuid, pattern: &str) -> Result<Vec<FileInfo>> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionGlobFilesRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            pattern: pattern.to_string(),
+        };
+
+        let response = client
+            .session_glob_files(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_glob_files failed: {}", e)))?;
+
+        response
+            .into_inner()
+            .files
+            .into_iter()
+            .map(proto_file_info_to_file_info)
+            .collect()
+    }
+
+    async fn apply_changes(
+        &self,
+        session_id: Uuid,
+        changes: Vec<FileChange>,
+    ) -> Result<Vec<FileInfo>> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionApplyFileChangesRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            changes: changes.iter().map(schema_file_change_to_proto).collect(),
+        };
+
+        let response = client
+            .session_apply_file_changes(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_apply_file_changes failed: {}", e)))?;
+
+        response
+            .into_inner()
+            .files
+            .into_iter()
+            .map(proto_file_info_to_file_info)
+            .collect()
+    }
+
+    async fn list_file_versions(&self, session_id: Uuid, path: &str) -> Result<Vec<FileVersion>> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionListFileVersionsRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            path: path.to_string(),
+        };
+
+        let response = client
+            .session_list_file_versions(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_list_file_versions failed: {}", e)))?;
+
+        response
+            .into_inner()
+            .versions
+            .into_iter()
+            .map(|v| {
+                proto_file_version_to_schema(v)
+                    .map_err(|e| grpc_error(format!("Invalid file version: {}", e)))
+            })
+            .collect()
+    }
+
+    async fn diff_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        from_version: Option<i32>,
+        to_version: Option<i32>,
+    ) -> Result<FileDiff> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionDiffFileRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            path: path.to_string(),
+            from_version,
+            to_version,
+        };
+
+        let response = client
+            .session_diff_file(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_diff_file failed: {}", e)))?
+            .into_inner();
+
+        Ok(FileDiff {
+            path: response.path,
+            from_version: response.from_version,
+            to_version: response.to_version,
+            diff: response.diff,
+            is_binary: response.is_binary,
+        })
+    }
+
+    async fn restore_file(
+        &self,
+        session_id: Uuid,
+        path: &str,
+        target: RestoreTarget,
+    ) -> Result<SessionFile> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionRestoreFileRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            path: path.to_string(),
+            target: Some(schema_restore_target_to_proto(&target)),
+        };
+
+        let response = client
+            .session_restore_file(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_restore_file failed: {}", e)))?;
+
+        let proto_file = response
+            .into_inner()
+            .file
+            .ok_or_else(|| grpc_error("No file in response"))?;
+
+        proto_session_file_to_file(proto_file)
+    }
+
+    async fn list_snapshots(&self, session_id: Uuid) -> Result<Vec<SessionSnapshot>> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionListSnapshotsRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+        };
+
+        let response = client
+            .session_list_snapshots(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_list_snapshots failed: {}", e)))?;
+
+        response
+            .into_inner()
+            .snapshots
+            .into_iter()
+            .map(proto_snapshot_to_snapshot)
+            .collect()
+    }
+
+    async fn create_snapshot(&self, session_id: Uuid, name: &str) -> Result<SessionSnapshot> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionCreateSnapshotRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            name: name.to_string(),
+        };
+
+        let response = client
+            .session_create_snapshot(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_create_snapshot failed: {}", e)))?;
+
+        let proto_snapshot = response
+            .into_inner()
+            .snapshot
+            .ok_or_else(|| grpc_error("No snapshot in response"))?;
+
+        proto_snapshot_to_snapshot(proto_snapshot)
+    }
+
+    async fn restore_snapshot(
+        &self,
+        session_id: Uuid,
+        target: RestoreTarget,
+    ) -> Result<SessionSnapshot> {
+        let mut client = self.client.inner.lock().await;
+
+        let request = proto::SessionRestoreSnapshotRequest {
+            session_id: Some(uuid_to_proto(session_id)),
+            target: Some(schema_restore_target_to_proto(&target)),
+        };
+
+        let response = client
+            .session_restore_snapshot(request)
+            .await
+            .map_err(|e| grpc_error(format!("gRPC session_restore_snapshot failed: {}", e)))?;
+
+        let proto_snapshot = response
+            .into_inner()
+            .snapshot
+            .ok_or_else(|| grpc_error("No snapshot in response"))?;
+
+        proto_snapshot_to_snapshot(proto_snapshot)
+    }
+}
+
+fn proto_snapshot_to_snapshot(proto: proto::SessionSnapshot) -> Result<SessionSnapshot> {
+    proto_session_snapshot_to_schema(proto)
+        .map_err(|e| grpc_error(format!("Invalid snapshot: {}", e)))
 }
 
 fn proto_session_file_to_file(proto: proto::SessionFile) -> Result<SessionFile> {
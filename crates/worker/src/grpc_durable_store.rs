@@ -7,9 +7,13 @@ use everruns_internal_protocol::proto::{
     self, ClaimDurableTasksRequest, CompleteDurableTaskRequest, CountActiveDurableWorkflowsRequest,
     CreateDurableWorkflowRequest, DurableActivityOptions, DurableTaskDefinition,
     EnqueueDurableTaskRequest, FailDurableTaskRequest, GetDurableWorkflowStatusRequest,
-    HeartbeatDurableTaskRequest, UpdateDurableWorkflowStatusRequest,
+    HeartbeatDurableTaskRequest, QueryDurableWorkflowRequest,
+    SignalWithStartDurableWorkflowRequest, UpdateDurableWorkflowStatusRequest,
+};
+use everruns_internal_protocol::{
+    json_to_proto_struct, json_to_proto_value, proto_value_to_json, uuid_to_proto_uuid,
+    WorkerServiceClient,
 };
-use everruns_internal_protocol::{json_to_proto_struct, uuid_to_proto_uuid, WorkerServiceClient};
 use tonic::transport::Channel;
 use uuid::Uuid;
 
@@ -100,14 +104,7 @@ impl GrpcDurableStore {
         activity_type: String,
         input: serde_json::Value,
     ) -> Result<Uuid> {
-        let task = DurableTaskDefinition {
-            workflow_id: Some(uuid_to_proto_uuid(workflow_id)),
-            activity_id,
-            activity_type,
-            input: Some(json_to_proto_struct(&input)),
-            options: Some(DurableActivityOptions::default()),
-        };
-
+        let task = task_definition(workflow_id, activity_id, activity_type, input);
         let request = EnqueueDurableTaskRequest { task: Some(task) };
 
         let response = self.client.enqueue_durable_task(request).await?;
@@ -119,10 +116,11 @@ impl GrpcDurableStore {
         parse_proto_uuid(&task_id)
     }
 
-    /// Claim tasks for execution
+    /// Claim tasks from the given task queues for execution
     pub async fn claim_tasks(
         &mut self,
         worker_id: &str,
+        task_queues: &[String],
         activity_types: &[String],
         max_tasks: usize,
     ) -> Result<Vec<ClaimedTask>> {
@@ -130,6 +128,7 @@ impl GrpcDurableStore {
             worker_id: worker_id.to_string(),
             activity_types: activity_types.to_vec(),
             max_tasks: max_tasks as i32,
+            task_queues: task_queues.to_vec(),
         };
 
         let response = self.client.claim_durable_tasks(request).await?;
@@ -169,14 +168,46 @@ impl GrpcDurableStore {
     }
 
     /// Complete a task
-    pub async fn complete_task(&mut self, task_id: Uuid, output: serde_json::Value) -> Result<()> {
+    ///
+    /// Returns `false` if the task was cancelled.
+    pub async fn complete_task(
+        &mut self,
+        task_id: Uuid,
+        output: serde_json::Value,
+    ) -> Result<bool> {
+        self.complete(task_id, output, None).await
+    }
+
+    /// Complete a task and enqueue the step chained after it
+    ///
+    /// Returns `false`, enqueuing nothing, if the task was cancelled.
+    pub async fn complete_and_enqueue(
+        &mut self,
+        task_id: Uuid,
+        output: serde_json::Value,
+        workflow_id: Uuid,
+        activity_id: String,
+        activity_type: String,
+        input: serde_json::Value,
+    ) -> Result<bool> {
+        let next = task_definition(workflow_id, activity_id, activity_type, input);
+        self.complete(task_id, output, Some(next)).await
+    }
+
+    async fn complete(
+        &mut self,
+        task_id: Uuid,
+        output: serde_json::Value,
+        next: Option<DurableTaskDefinition>,
+    ) -> Result<bool> {
         let request = CompleteDurableTaskRequest {
             task_id: Some(uuid_to_proto_uuid(task_id)),
             output: Some(json_to_proto_struct(&output)),
+            next,
         };
 
-        self.client.complete_durable_task(request).await?;
-        Ok(())
+        let response = self.client.complete_durable_task(request).await?;
+        Ok(response.into_inner().completed)
     }
 
     /// Fail a task
@@ -218,6 +249,53 @@ impl GrpcDurableStore {
         let response = self.client.count_active_durable_workflows(request).await?;
         Ok(response.into_inner().count as usize)
     }
+
+    /// Signal a workflow, creating it first if it does not exist
+    ///
+    /// Returns whether this call created the workflow.
+    pub async fn signal_with_start(
+        &mut self,
+        workflow_id: Uuid,
+        workflow_type: &str,
+        input: serde_json::Value,
+        signal_type: &str,
+        payload: serde_json::Value,
+    ) -> Result<bool> {
+        let request = SignalWithStartDurableWorkflowRequest {
+            workflow_id: Some(uuid_to_proto_uuid(workflow_id)),
+            workflow_type: workflow_type.to_string(),
+            input: Some(json_to_proto_struct(&input)),
+            signal_type: signal_type.to_string(),
+            payload: Some(json_to_proto_value(&payload)),
+        };
+
+        let response = self
+            .client
+            .signal_with_start_durable_workflow(request)
+            .await?;
+        Ok(response.into_inner().created)
+    }
+
+    /// Query a workflow's state by replaying its history
+    pub async fn query_workflow(
+        &mut self,
+        workflow_id: Uuid,
+        query_type: &str,
+        args: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let request = QueryDurableWorkflowRequest {
+            workflow_id: Some(uuid_to_proto_uuid(workflow_id)),
+            query_type: query_type.to_string(),
+            args: Some(json_to_proto_value(&args)),
+        };
+
+        let response = self.client.query_durable_workflow(request).await?;
+        Ok(response
+            .into_inner()
+            .result
+            .map(|v| proto_value_to_json(&v))
+            .unwrap_or(serde_json::Value::Null))
+    }
 }
 
 // ============================================================================
@@ -263,6 +341,21 @@ impl WorkflowStatus {
 // Helper functions
 // ============================================================================
 
+fn task_definition(
+    workflow_id: Uuid,
+    activity_id: String,
+    activity_type: String,
+    input: serde_json::Value,
+) -> DurableTaskDefinition {
+    DurableTaskDefinition {
+        workflow_id: Some(uuid_to_proto_uuid(workflow_id)),
+        activity_id,
+        activity_type,
+        input: Some(json_to_proto_struct(&input)),
+        options: Some(DurableActivityOptions::default()),
+    }
+}
+
 fn parse_proto_uuid(proto_uuid: &proto::Uuid) -> Result<Uuid> {
     Uuid::parse_str(&proto_uuid.value).map_err(|e| anyhow::anyhow!("Invalid UUID: {}", e))
 }
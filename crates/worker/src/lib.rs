@@ -5,10 +5,12 @@ pub mod durable_worker;
 pub mod grpc_adapters;
 pub mod grpc_durable_store;
 pub mod runner;
+pub mod session_workflow;
 
 // Re-export main types
 pub use durable_runner::{
     DirectDurableStore, DurableRunner, DurableStoreBackend, DurableTurnInput, DurableTurnOutput,
+    TURN_WORKFLOW_TYPE,
 };
 pub use durable_worker::{DurableWorker, DurableWorkerConfig};
 pub use grpc_durable_store::{
@@ -16,6 +18,11 @@ pub use grpc_durable_store::{
     WorkflowStatus as GrpcWorkflowStatus,
 };
 pub use runner::{create_runner, AgentRunner};
+pub use session_workflow::{
+    session_workflow_executor, session_workflow_id, session_workflows_enabled, turn_message_id,
+    BusyPolicy, ScheduledWakeup, SessionState, SessionWorkflow, SessionWorkflowInput,
+    SessionWorkflowOutput, SESSION_WORKFLOW_TYPE, TURNS_PER_RUN,
+};
 
 // Re-export LLM driver factory helpers
 pub use adapters::{create_driver_registry, create_llm_driver};
@@ -23,7 +30,7 @@ pub use adapters::{create_driver_registry, create_llm_driver};
 // Re-export gRPC adapters for worker communication with control plane
 pub use grpc_adapters::{
     load_turn_context, GrpcAgentStore, GrpcClient, GrpcEventEmitter, GrpcLlmProviderStore,
-    GrpcMessageStore, GrpcSessionFileStore, GrpcSessionStore, TurnContext,
+    GrpcLlmRateLimiter, GrpcMessageStore, GrpcSessionFileStore, GrpcSessionStore, TurnContext,
 };
 
 // Re-export OpenAI driver from the openai crate
@@ -19,6 +19,7 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::durable_runner::DurableRunner;
+use crate::session_workflow::session_workflows_enabled;
 
 // =============================================================================
 // AgentRunner Trait
@@ -59,10 +60,14 @@ pub trait AgentRunner: Send + Sync {
 ///
 /// This is used by the control-plane API to start workflows.
 /// Pass a database pool for direct access (control-plane) or None for gRPC (workers).
+/// With a pool, sessions run as session workflows when `SESSION_WORKFLOWS_ENABLED` is set.
 pub async fn create_runner(db_pool: Option<sqlx::PgPool>) -> Result<Arc<dyn AgentRunner>> {
     if let Some(pool) = db_pool {
         tracing::info!("Creating Durable execution engine runner (direct DB mode)");
-        let runner = DurableRunner::new_with_pool(pool);
+        let mut runner = DurableRunner::new_with_pool(pool.clone());
+        if session_workflows_enabled() {
+            runner = runner.with_session_workflows(pool);
+        }
         Ok(Arc::new(runner))
     } else {
         tracing::info!("Creating Durable execution engine runner (gRPC mode)");
@@ -0,0 +1,797 @@
+// Session workflow: one long-lived durable workflow per session
+// Decision: Opt-in via SESSION_WORKFLOWS_ENABLED; without it every message starts a turn workflow
+// Decision: Messages, turn completions and wakeups arrive as signals; turns run as activity chains
+// Decision: Workflow id is derived from the session id so callers need no lookup
+// Decision: The worker reports turn completion with a signal, since task completions
+//           do not reach the workflow history
+// Decision: A turn activity or chained step the timeout sweeper fails for good ends the
+//           turn like a completion; its failure is recorded in the history
+// Decision: Every TURNS_PER_RUN turns the workflow continues as new between turns to bound
+//           its history; queued messages and pending wakeups carry over in the input
+
+use chrono::{DateTime, Utc};
+use everruns_durable::{
+    workflow::signal_types, ActivityError, PostgresWorkflowEventStore, Workflow, WorkflowAction,
+    WorkflowError, WorkflowExecutor, WorkflowSignal, UNKNOWN_QUERY,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::durable_runner::DurableTurnInput;
+
+/// Workflow type of the session workflow
+pub const SESSION_WORKFLOW_TYPE: &str = "session_workflow";
+
+/// Turns a session workflow run completes before continuing as new
+pub const TURNS_PER_RUN: u64 = 100;
+
+/// Namespace for deriving session workflow ids from session ids
+const SESSION_WORKFLOW_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c_2a7e_93d4_4b8a_a5e0_7c3f_1d92_b640);
+
+/// Signals understood by the session workflow
+pub mod session_signals {
+    /// A user message is ready for a turn: `{"input_message_id": uuid}`
+    pub const MESSAGE: &str = "session.message";
+
+    /// A turn finished, successfully or not: `{"input_message_id": uuid}`
+    pub const TURN_COMPLETED: &str = "session.turn_completed";
+
+    /// Run a turn for a message later:
+    /// `{"wakeup_id": string, "input_message_id": uuid, "delay_ms": u64}`
+    pub const SCHEDULE_WAKEUP: &str = "session.schedule_wakeup";
+
+    /// Cancel the running turn and drop queued messages; the session stays open
+    pub const STOP_TURN: &str = "session.stop_turn";
+
+    /// Stop accepting messages and complete once queued turns are done
+    pub const CLOSE: &str = "session.close";
+}
+
+/// Queries answered by the session workflow
+pub mod session_queries {
+    /// Current [`super::SessionState`]
+    pub const STATE: &str = "state";
+}
+
+/// Whether sessions run as long-lived session workflows
+pub fn session_workflows_enabled() -> bool {
+    std::env::var("SESSION_WORKFLOWS_ENABLED")
+        .map(|v| matches!(v.to_lowercase().as_str(), "true" | "1" | "on"))
+        .unwrap_or(false)
+}
+
+/// Durable workflow id of a session's workflow
+pub fn session_workflow_id(session_id: Uuid) -> Uuid {
+    Uuid::new_v5(&SESSION_WORKFLOW_NAMESPACE, session_id.as_bytes())
+}
+
+/// Executor with the session workflow registered, for control-plane use
+pub fn session_workflow_executor(
+    pool: sqlx::PgPool,
+) -> WorkflowExecutor<PostgresWorkflowEventStore> {
+    let mut executor = WorkflowExecutor::new(PostgresWorkflowEventStore::new(pool));
+    executor.register::<SessionWorkflow>();
+    executor
+}
+
+/// What to do with a message that arrives while a turn is running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusyPolicy {
+    /// Run it after the running turn and any earlier queued messages
+    #[default]
+    Queue,
+    /// Cancel the running turn and run it right away
+    Interrupt,
+}
+
+/// Input for the session workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionWorkflowInput {
+    pub session_id: Uuid,
+    pub agent_id: Uuid,
+    #[serde(default)]
+    pub busy_policy: BusyPolicy,
+    /// Messages to run turns for on start, oldest first
+    #[serde(default)]
+    pub queued: Vec<Uuid>,
+    /// Turns completed by earlier runs
+    #[serde(default)]
+    pub turns_completed: u64,
+    /// Wakeups carried over from the previous run, by wakeup id
+    #[serde(default)]
+    pub wakeups: BTreeMap<String, ScheduledWakeup>,
+    /// When the previous run continued as new; carried wakeups are timed from here
+    #[serde(default)]
+    pub continued_at: Option<DateTime<Utc>>,
+}
+
+impl SessionWorkflowInput {
+    pub fn new(session_id: Uuid, agent_id: Uuid) -> Self {
+        Self {
+            session_id,
+            agent_id,
+            busy_policy: BusyPolicy::default(),
+            queued: Vec::new(),
+            turns_completed: 0,
+            wakeups: BTreeMap::new(),
+            continued_at: None,
+        }
+    }
+}
+
+/// A message waiting for a wakeup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledWakeup {
+    pub input_message_id: Uuid,
+    pub due_at: DateTime<Utc>,
+}
+
+/// Output of a closed session workflow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionWorkflowOutput {
+    pub session_id: Uuid,
+    pub turns_completed: u64,
+}
+
+/// Session workflow state returned by the `state` query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionState {
+    pub session_id: Uuid,
+    /// Message of the running turn
+    pub current_turn: Option<Uuid>,
+    /// Messages waiting for a turn, oldest first
+    pub queued: Vec<Uuid>,
+    /// Messages waiting for a wakeup, by wakeup id
+    pub wakeups: BTreeMap<String, ScheduledWakeup>,
+    pub turns_completed: u64,
+    pub closing: bool,
+}
+
+#[derive(Deserialize)]
+struct MessagePayload {
+    input_message_id: Uuid,
+}
+
+#[derive(Deserialize)]
+struct WakeupPayload {
+    wakeup_id: String,
+    input_message_id: Uuid,
+    delay_ms: u64,
+}
+
+/// Long-lived workflow that runs one turn at a time for a session
+pub struct SessionWorkflow {
+    session_id: Uuid,
+    agent_id: Uuid,
+    busy_policy: BusyPolicy,
+    current_turn: Option<Uuid>,
+    queued: VecDeque<Uuid>,
+    wakeups: BTreeMap<String, ScheduledWakeup>,
+    turns_completed: u64,
+    /// Turns completed by this run
+    run_turns: u64,
+    continued_at: Option<DateTime<Utc>>,
+    closing: bool,
+    completed: bool,
+}
+
+impl SessionWorkflow {
+    /// Run a turn for a message now or later, depending on the busy policy
+    fn accept(&mut self, input_message_id: Uuid) -> Vec<WorkflowAction> {
+        if self.closing {
+            warn!(session_id = %self.session_id, %input_message_id, "session closing, dropping message");
+            return vec![];
+        }
+
+        match (self.current_turn, self.busy_policy) {
+            (None, _) => vec![self.start_turn(input_message_id)],
+            (Some(_), BusyPolicy::Queue) => {
+                self.queued.push_back(input_message_id);
+                vec![]
+            }
+            (Some(_), BusyPolicy::Interrupt) => {
+                let mut actions: Vec<WorkflowAction> = self.stop_turn().into_iter().collect();
+                actions.push(self.start_turn(input_message_id));
+                actions
+            }
+        }
+    }
+
+    fn start_turn(&mut self, input_message_id: Uuid) -> WorkflowAction {
+        self.current_turn = Some(input_message_id);
+        let input = DurableTurnInput {
+            session_id: self.session_id,
+            agent_id: self.agent_id,
+            input_message_id,
+        };
+        WorkflowAction::schedule_activity(
+            turn_activity_id(input_message_id),
+            "process_input",
+            serde_json::to_value(input).unwrap_or_default(),
+        )
+    }
+
+    /// Cancel the running turn, if any, with the steps chained under it
+    fn stop_turn(&mut self) -> Option<WorkflowAction> {
+        self.current_turn
+            .take()
+            .map(|running| WorkflowAction::CancelActivity {
+                activity_id: turn_activity_id(running),
+            })
+    }
+
+    /// Count the running turn as finished
+    fn finish_turn(&mut self) {
+        self.current_turn = None;
+        self.turns_completed += 1;
+        self.run_turns += 1;
+    }
+
+    /// Start the next queued turn, or complete if closing and idle
+    fn next_turn(&mut self) -> Vec<WorkflowAction> {
+        if let Some(input_message_id) = self.queued.pop_front() {
+            return vec![self.start_turn(input_message_id)];
+        }
+        if self.closing {
+            return vec![self.complete()];
+        }
+        vec![]
+    }
+
+    /// Start a fresh run with the queued messages and pending wakeups
+    fn continue_as_new(&self, now: DateTime<Utc>) -> WorkflowAction {
+        let input = SessionWorkflowInput {
+            session_id: self.session_id,
+            agent_id: self.agent_id,
+            busy_policy: self.busy_policy,
+            queued: self.queued.iter().copied().collect(),
+            turns_completed: self.turns_completed,
+            wakeups: self.wakeups.clone(),
+            continued_at: Some(now),
+        };
+        WorkflowAction::continue_as_new(serde_json::to_value(input).unwrap_or_default())
+    }
+
+    fn complete(&mut self) -> WorkflowAction {
+        self.completed = true;
+        WorkflowAction::complete(serde_json::to_value(self.output()).unwrap_or_default())
+    }
+
+    fn output(&self) -> SessionWorkflowOutput {
+        SessionWorkflowOutput {
+            session_id: self.session_id,
+            turns_completed: self.turns_completed,
+        }
+    }
+
+    fn state(&self) -> SessionState {
+        SessionState {
+            session_id: self.session_id,
+            current_turn: self.current_turn,
+            queued: self.queued.iter().copied().collect(),
+            wakeups: self.wakeups.clone(),
+            turns_completed: self.turns_completed,
+            closing: self.closing,
+        }
+    }
+}
+
+impl Workflow for SessionWorkflow {
+    const TYPE: &'static str = SESSION_WORKFLOW_TYPE;
+    type Input = SessionWorkflowInput;
+    type Output = SessionWorkflowOutput;
+
+    fn new(input: Self::Input) -> Self {
+        Self {
+            session_id: input.session_id,
+            agent_id: input.agent_id,
+            busy_policy: input.busy_policy,
+            current_turn: None,
+            queued: input.queued.into(),
+            wakeups: input.wakeups,
+            turns_completed: input.turns_completed,
+            run_turns: 0,
+            continued_at: input.continued_at,
+            closing: false,
+            completed: false,
+        }
+    }
+
+    fn on_start(&mut self) -> Vec<WorkflowAction> {
+        // Restart the timers of carried wakeups; overdue ones fire right away
+        let mut actions: Vec<WorkflowAction> = self
+            .wakeups
+            .iter()
+            .map(|(wakeup_id, wakeup)| {
+                let delay = self
+                    .continued_at
+                    .and_then(|continued_at| (wakeup.due_at - continued_at).to_std().ok())
+                    .unwrap_or_default();
+                WorkflowAction::timer(wakeup_timer_id(wakeup_id), delay)
+            })
+            .collect();
+        actions.extend(self.next_turn());
+        actions
+    }
+
+    // Turn activities report back through TURN_COMPLETED signals
+    fn on_activity_completed(
+        &mut self,
+        _activity_id: &str,
+        _result: serde_json::Value,
+    ) -> Vec<WorkflowAction> {
+        vec![]
+    }
+
+    // Only the timeout sweeper records failures; the worker reports the rest
+    // through TURN_COMPLETED
+    fn on_activity_failed(
+        &mut self,
+        activity_id: &str,
+        _error: &ActivityError,
+    ) -> Vec<WorkflowAction> {
+        match self.current_turn {
+            Some(running) if turn_message_id(activity_id) == Some(running) => {
+                self.finish_turn();
+                self.next_turn()
+            }
+            _ => vec![],
+        }
+    }
+
+    fn on_timer_fired(&mut self, timer_id: &str) -> Vec<WorkflowAction> {
+        let wakeup_id = timer_id.strip_prefix("wakeup-").unwrap_or(timer_id);
+        match self.wakeups.remove(wakeup_id) {
+            Some(wakeup) => self.accept(wakeup.input_message_id),
+            None => vec![],
+        }
+    }
+
+    fn on_signal(&mut self, signal: &WorkflowSignal) -> Vec<WorkflowAction> {
+        match signal.signal_type.as_str() {
+            session_signals::MESSAGE => match parse_payload::<MessagePayload>(signal) {
+                Some(payload) => self.accept(payload.input_message_id),
+                None => vec![],
+            },
+            session_signals::TURN_COMPLETED => match parse_payload::<MessagePayload>(signal) {
+                // Completions of interrupted turns are stale
+                Some(payload) if self.current_turn == Some(payload.input_message_id) => {
+                    self.finish_turn();
+                    if self.run_turns >= TURNS_PER_RUN && !self.closing {
+                        return vec![self.continue_as_new(signal.sent_at)];
+                    }
+                    self.next_turn()
+                }
+                _ => vec![],
+            },
+            session_signals::SCHEDULE_WAKEUP => match parse_payload::<WakeupPayload>(signal) {
+                Some(payload) if !self.closing => {
+                    let timer_id = wakeup_timer_id(&payload.wakeup_id);
+                    let delay = Duration::from_millis(payload.delay_ms);
+                    let wakeup = ScheduledWakeup {
+                        input_message_id: payload.input_message_id,
+                        due_at: signal.sent_at
+                            + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX),
+                    };
+                    self.wakeups.insert(payload.wakeup_id, wakeup);
+                    vec![WorkflowAction::timer(timer_id, delay)]
+                }
+                _ => vec![],
+            },
+            session_signals::STOP_TURN => {
+                self.queued.clear();
+                self.stop_turn().into_iter().collect()
+            }
+            session_signals::CLOSE => {
+                self.closing = true;
+                self.wakeups.clear();
+                if self.current_turn.is_none() {
+                    self.next_turn()
+                } else {
+                    vec![]
+                }
+            }
+            signal_types::CANCEL => {
+                self.closing = true;
+                self.queued.clear();
+                self.wakeups.clear();
+                let mut actions: Vec<WorkflowAction> = self.stop_turn().into_iter().collect();
+                actions.push(self.complete());
+                actions
+            }
+            _ => vec![],
+        }
+    }
+
+    fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    fn result(&self) -> Option<Self::Output> {
+        self.completed.then(|| self.output())
+    }
+
+    fn query(
+        &self,
+        query_type: &str,
+        _args: serde_json::Value,
+    ) -> Result<serde_json::Value, WorkflowError> {
+        match query_type {
+            session_queries::STATE => Ok(serde_json::to_value(self.state()).unwrap_or_default()),
+            _ => Err(
+                WorkflowError::new(format!("unknown query type: {}", query_type))
+                    .with_code(UNKNOWN_QUERY),
+            ),
+        }
+    }
+}
+
+/// Timer id of a wakeup
+fn wakeup_timer_id(wakeup_id: &str) -> String {
+    format!("wakeup-{}", wakeup_id)
+}
+
+/// Activity id of the turn for a message
+fn turn_activity_id(input_message_id: Uuid) -> String {
+    format!("turn-{}", input_message_id)
+}
+
+/// Message of the turn an activity or one of its chained steps runs
+pub fn turn_message_id(activity_id: &str) -> Option<Uuid> {
+    let root = activity_id
+        .split_once('/')
+        .map_or(activity_id, |(root, _)| root);
+    root.strip_prefix("turn-")?.parse().ok()
+}
+
+fn parse_payload<T: serde::de::DeserializeOwned>(signal: &WorkflowSignal) -> Option<T> {
+    serde_json::from_value(signal.payload.clone())
+        .inspect_err(|e| {
+            warn!(signal_type = %signal.signal_type, error = %e, "ignoring malformed session signal");
+        })
+        .ok()
+}
+
+/// Signal that a message is ready for a turn
+pub fn message_signal(input_message_id: Uuid) -> WorkflowSignal {
+    WorkflowSignal::new(
+        session_signals::MESSAGE,
+        serde_json::json!({ "input_message_id": input_message_id }),
+    )
+}
+
+/// Signal that the turn for a message finished
+pub fn turn_completed_signal(input_message_id: Uuid) -> WorkflowSignal {
+    WorkflowSignal::new(
+        session_signals::TURN_COMPLETED,
+        serde_json::json!({ "input_message_id": input_message_id }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use everruns_durable::{
+        ActivityOptions, InMemoryWorkflowEventStore, ManualClock, TaskDefinition,
+        TaskFailureOutcome, TimeoutManager, WorkflowEvent, WorkflowEventStore, WorkflowExecutor,
+    };
+    use std::sync::Arc;
+
+    fn executor() -> WorkflowExecutor<InMemoryWorkflowEventStore> {
+        let mut executor = WorkflowExecutor::new(InMemoryWorkflowEventStore::new());
+        executor.register::<SessionWorkflow>();
+        executor
+    }
+
+    async fn state(
+        executor: &WorkflowExecutor<InMemoryWorkflowEventStore>,
+        workflow_id: Uuid,
+    ) -> SessionState {
+        let state = executor
+            .query_workflow(workflow_id, session_queries::STATE, serde_json::Value::Null)
+            .await
+            .unwrap();
+        serde_json::from_value(state).unwrap()
+    }
+
+    #[test]
+    fn test_session_workflow_id_is_stable() {
+        let session_id = Uuid::now_v7();
+        assert_eq!(
+            session_workflow_id(session_id),
+            session_workflow_id(session_id)
+        );
+        assert_ne!(session_workflow_id(session_id), session_id);
+    }
+
+    #[tokio::test]
+    async fn test_messages_queue_while_turn_runs() {
+        let executor = executor();
+        let input = SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7());
+        let workflow_id = session_workflow_id(input.session_id);
+        let (first, second) = (Uuid::now_v7(), Uuid::now_v7());
+
+        let result = executor
+            .signal_with_start::<SessionWorkflow>(
+                workflow_id,
+                input.clone(),
+                message_signal(first),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.tasks_enqueued, 1);
+
+        let result = executor
+            .signal_with_start::<SessionWorkflow>(
+                workflow_id,
+                input.clone(),
+                message_signal(second),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.tasks_enqueued, 0);
+
+        let session = state(&executor, workflow_id).await;
+        assert_eq!(session.current_turn, Some(first));
+        assert_eq!(session.queued, vec![second]);
+
+        // Finishing the first turn starts the queued one
+        executor
+            .send_signal(workflow_id, turn_completed_signal(first))
+            .await
+            .unwrap();
+        let result = executor.process_workflow(workflow_id).await.unwrap();
+        assert_eq!(result.tasks_enqueued, 1);
+
+        let session = state(&executor, workflow_id).await;
+        assert_eq!(session.current_turn, Some(second));
+        assert!(session.queued.is_empty());
+        assert_eq!(session.turns_completed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_policy_replaces_running_turn() {
+        let executor = executor();
+        let input = SessionWorkflowInput {
+            busy_policy: BusyPolicy::Interrupt,
+            ..SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7())
+        };
+        let workflow_id = session_workflow_id(input.session_id);
+        let (first, second) = (Uuid::now_v7(), Uuid::now_v7());
+
+        for message in [first, second] {
+            executor
+                .signal_with_start::<SessionWorkflow>(
+                    workflow_id,
+                    input.clone(),
+                    message_signal(message),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let session = state(&executor, workflow_id).await;
+        assert_eq!(session.current_turn, Some(second));
+        assert!(session.queued.is_empty());
+
+        // The interrupted turn finishing late changes nothing
+        executor
+            .send_signal(workflow_id, turn_completed_signal(first))
+            .await
+            .unwrap();
+        executor.process_workflow(workflow_id).await.unwrap();
+        assert_eq!(
+            state(&executor, workflow_id).await.current_turn,
+            Some(second)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wakeup_fires_turn() {
+        let executor = executor();
+        let input = SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7());
+        let workflow_id = session_workflow_id(input.session_id);
+        let message = Uuid::now_v7();
+
+        let wakeup = WorkflowSignal::new(
+            session_signals::SCHEDULE_WAKEUP,
+            serde_json::json!({ "wakeup_id": "check-in", "input_message_id": message, "delay_ms": 0 }),
+        );
+        executor
+            .signal_with_start::<SessionWorkflow>(workflow_id, input, wakeup, None)
+            .await
+            .unwrap();
+        assert_eq!(state(&executor, workflow_id).await.wakeups.len(), 1);
+
+        assert_eq!(executor.fire_due_timers(10).await.unwrap(), 1);
+
+        let session = state(&executor, workflow_id).await;
+        assert_eq!(session.current_turn, Some(message));
+        assert!(session.wakeups.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_continues_as_new_after_turn_limit() {
+        let executor = executor();
+        let input = SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7());
+        let workflow_id = session_workflow_id(input.session_id);
+        let mut current = Uuid::now_v7();
+
+        executor
+            .signal_with_start::<SessionWorkflow>(workflow_id, input, message_signal(current), None)
+            .await
+            .unwrap();
+        let wakeup = WorkflowSignal::new(
+            session_signals::SCHEDULE_WAKEUP,
+            serde_json::json!({ "wakeup_id": "check-in", "input_message_id": Uuid::now_v7(), "delay_ms": 3_600_000 }),
+        );
+        executor.send_signal(workflow_id, wakeup).await.unwrap();
+        executor.process_workflow(workflow_id).await.unwrap();
+        let wakeups = state(&executor, workflow_id).await.wakeups;
+
+        for _ in 0..TURNS_PER_RUN {
+            let next = Uuid::now_v7();
+            executor
+                .send_signal(workflow_id, message_signal(next))
+                .await
+                .unwrap();
+            executor
+                .send_signal(workflow_id, turn_completed_signal(current))
+                .await
+                .unwrap();
+            executor.process_workflow(workflow_id).await.unwrap();
+            current = next;
+        }
+
+        let info = executor
+            .store()
+            .get_workflow_info(workflow_id)
+            .await
+            .unwrap();
+        assert_eq!(info.run_number, 2);
+
+        // The new run picks up the queued message and the pending wakeup
+        let session = state(&executor, workflow_id).await;
+        assert_eq!(session.current_turn, Some(current));
+        assert_eq!(session.turns_completed, TURNS_PER_RUN);
+        assert_eq!(session.wakeups, wakeups);
+        assert!(executor
+            .store()
+            .load_events(workflow_id)
+            .await
+            .unwrap()
+            .iter()
+            .any(|(_, event)| matches!(
+                event,
+                WorkflowEvent::TimerStarted { timer_id, .. } if timer_id == "wakeup-check-in"
+            )));
+    }
+
+    #[tokio::test]
+    async fn test_close_completes_after_running_turn() {
+        let executor = executor();
+        let input = SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7());
+        let workflow_id = session_workflow_id(input.session_id);
+        let message = Uuid::now_v7();
+
+        executor
+            .signal_with_start::<SessionWorkflow>(workflow_id, input, message_signal(message), None)
+            .await
+            .unwrap();
+        executor
+            .send_signal(
+                workflow_id,
+                WorkflowSignal::new(session_signals::CLOSE, serde_json::json!({})),
+            )
+            .await
+            .unwrap();
+        let result = executor.process_workflow(workflow_id).await.unwrap();
+        assert!(!result.completed);
+
+        executor
+            .send_signal(workflow_id, turn_completed_signal(message))
+            .await
+            .unwrap();
+        let result = executor.process_workflow(workflow_id).await.unwrap();
+        assert!(result.completed);
+    }
+
+    #[tokio::test]
+    async fn test_stop_turn_cancels_chained_steps() {
+        let executor = executor();
+        let input = SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7());
+        let workflow_id = session_workflow_id(input.session_id);
+        let message = Uuid::now_v7();
+
+        executor
+            .signal_with_start::<SessionWorkflow>(workflow_id, input, message_signal(message), None)
+            .await
+            .unwrap();
+
+        // The worker completes the turn's first activity and chains a step
+        let store = executor.store();
+        let claimed = store
+            .claim_task("worker-1", &["process_input".to_string()], 1)
+            .await
+            .unwrap();
+        let step = format!("{}/reason-1", claimed[0].activity_id);
+        assert_eq!(turn_message_id(&step), Some(message));
+        let next = TaskDefinition {
+            workflow_id,
+            activity_id: step,
+            activity_type: "reason".to_string(),
+            input: claimed[0].input.clone(),
+            options: ActivityOptions::default(),
+        };
+        assert!(store
+            .complete_and_enqueue(claimed[0].id, serde_json::json!({}), Some(next))
+            .await
+            .unwrap());
+
+        executor
+            .send_signal(
+                workflow_id,
+                WorkflowSignal::new(session_signals::STOP_TURN, serde_json::json!({})),
+            )
+            .await
+            .unwrap();
+        executor.process_workflow(workflow_id).await.unwrap();
+
+        assert_eq!(state(&executor, workflow_id).await.current_turn, None);
+        assert_eq!(store.pending_task_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_turn_starts_queued_turn() {
+        let clock = Arc::new(ManualClock::new());
+        let mut executor =
+            WorkflowExecutor::new(InMemoryWorkflowEventStore::with_clock(clock.clone()));
+        executor.register::<SessionWorkflow>();
+        let input = SessionWorkflowInput::new(Uuid::now_v7(), Uuid::now_v7());
+        let workflow_id = session_workflow_id(input.session_id);
+        let (first, second) = (Uuid::now_v7(), Uuid::now_v7());
+
+        for message in [first, second] {
+            executor
+                .signal_with_start::<SessionWorkflow>(
+                    workflow_id,
+                    input.clone(),
+                    message_signal(message),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        let manager = TimeoutManager::new(executor).with_clock(clock.clone());
+        let store = manager.executor().store();
+
+        // A worker claims the turn and never finishes it, attempt after attempt
+        loop {
+            let claimed = store
+                .claim_task("worker-1", &["process_input".to_string()], 1)
+                .await
+                .unwrap();
+            assert_eq!(claimed[0].activity_id, turn_activity_id(first));
+            clock.advance(Duration::from_secs(3600));
+            let timed_out = manager.sweep(10).await.unwrap();
+            assert_eq!(timed_out.len(), 1);
+            if !matches!(timed_out[0].outcome, TaskFailureOutcome::WillRetry { .. }) {
+                break;
+            }
+            // Past the retry backoff
+            clock.advance(Duration::from_secs(60));
+        }
+
+        let session = state(manager.executor(), workflow_id).await;
+        assert_eq!(session.current_turn, Some(second));
+        assert!(session.queued.is_empty());
+        assert_eq!(session.turns_completed, 1);
+    }
+}